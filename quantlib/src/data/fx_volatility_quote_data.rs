@@ -0,0 +1,105 @@
+use crate::currency::FxCode;
+use crate::definitions::Real;
+use crate::enums::{FxAtmType, FxDeltaType, FxStrangleType};
+use ndarray::Array1;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// FX volatility quotes on the expiry dates in the market convention:
+/// the at-the-money volatility, the 25-delta risk reversal (call minus put)
+/// and the 25-delta butterfly in strangle_type, the market strangle by default.
+/// The deltas and the at-the-money strike are in delta_type and atm_type.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FxVolatilityQuoteData {
+    expiry_dates: Vec<OffsetDateTime>,
+    atm: Array1<Real>,
+    risk_reversal_25: Array1<Real>,
+    butterfly_25: Array1<Real>,
+    delta_type: FxDeltaType,
+    atm_type: FxAtmType,
+    #[serde(default)]
+    strangle_type: FxStrangleType,
+    market_datetime: Option<OffsetDateTime>,
+    fx_code: FxCode,
+    name: String,
+    code: String,
+}
+
+impl FxVolatilityQuoteData {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        expiry_dates: Vec<OffsetDateTime>,
+        atm: Array1<Real>,
+        risk_reversal_25: Array1<Real>,
+        butterfly_25: Array1<Real>,
+        delta_type: FxDeltaType,
+        atm_type: FxAtmType,
+        market_datetime: Option<OffsetDateTime>,
+        fx_code: FxCode,
+        name: String,
+        code: String,
+    ) -> FxVolatilityQuoteData {
+        FxVolatilityQuoteData {
+            expiry_dates,
+            atm,
+            risk_reversal_25,
+            butterfly_25,
+            delta_type,
+            atm_type,
+            strangle_type: FxStrangleType::default(),
+            market_datetime,
+            fx_code,
+            name,
+            code,
+        }
+    }
+
+    pub fn with_strangle_type(mut self, strangle_type: FxStrangleType) -> FxVolatilityQuoteData {
+        self.strangle_type = strangle_type;
+        self
+    }
+
+    pub fn get_expiry_dates(&self) -> &Vec<OffsetDateTime> {
+        &self.expiry_dates
+    }
+
+    pub fn get_atm(&self) -> &Array1<Real> {
+        &self.atm
+    }
+
+    pub fn get_risk_reversal_25(&self) -> &Array1<Real> {
+        &self.risk_reversal_25
+    }
+
+    pub fn get_butterfly_25(&self) -> &Array1<Real> {
+        &self.butterfly_25
+    }
+
+    pub fn get_delta_type(&self) -> FxDeltaType {
+        self.delta_type
+    }
+
+    pub fn get_atm_type(&self) -> FxAtmType {
+        self.atm_type
+    }
+
+    pub fn get_strangle_type(&self) -> FxStrangleType {
+        self.strangle_type
+    }
+
+    pub fn get_market_datetime(&self) -> Option<OffsetDateTime> {
+        self.market_datetime
+    }
+
+    pub fn get_fx_code(&self) -> &FxCode {
+        &self.fx_code
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_code(&self) -> &str {
+        &self.code
+    }
+}
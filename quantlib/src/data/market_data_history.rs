@@ -0,0 +1,289 @@
+use crate::currency::FxCode;
+use crate::data::{
+    fx_volatility_quote_data::FxVolatilityQuoteData, rate_volatility_data::RateVolatilityData,
+    surface_data::SurfaceData, value_data::ValueData, vector_data::VectorData,
+};
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use time::OffsetDateTime;
+
+/// Time series of the market data on the (increasing) dates for the historical scenarios.
+/// Each series is keyed as in the data of EngineGenerator and has a value on every date,
+/// e.g., fx_data[USDKRW][i] is the USD/KRW rate on dates[i].
+/// The curves are compared on their times, so the tenors of the series may change over the dates.
+/// The fx volatility quotes and the rate volatility cubes are optional (see with_* methods),
+/// and they keep their shapes over the dates.
+#[derive(Debug, Clone, Default)]
+pub struct MarketDataHistory {
+    dates: Vec<OffsetDateTime>,
+    fx_data: HashMap<FxCode, Vec<ValueData>>,
+    stock_data: HashMap<String, Vec<ValueData>>,
+    curve_data: HashMap<String, Vec<VectorData>>,
+    equity_constant_volatility_data: HashMap<String, Vec<ValueData>>,
+    equity_volatility_surface_data: HashMap<String, Vec<SurfaceData>>,
+    fx_constant_volatility_data: HashMap<FxCode, Vec<ValueData>>,
+    fx_volatility_quote_data: HashMap<FxCode, Vec<FxVolatilityQuoteData>>,
+    rate_volatility_data: HashMap<String, Vec<RateVolatilityData>>,
+}
+
+fn check_length(kind: &str, key: String, len: usize, dates: usize) -> Result<()> {
+    if len != dates {
+        return Err(anyhow!(
+            "({}:{}) the {} history of {} has {} values, but there are {} dates",
+            file!(),
+            line!(),
+            kind,
+            key,
+            len,
+            dates
+        ));
+    }
+    Ok(())
+}
+
+impl MarketDataHistory {
+    pub fn new(
+        dates: Vec<OffsetDateTime>,
+        fx_data: HashMap<FxCode, Vec<ValueData>>,
+        stock_data: HashMap<String, Vec<ValueData>>,
+        curve_data: HashMap<String, Vec<VectorData>>,
+        equity_constant_volatility_data: HashMap<String, Vec<ValueData>>,
+        equity_volatility_surface_data: HashMap<String, Vec<SurfaceData>>,
+        fx_constant_volatility_data: HashMap<FxCode, Vec<ValueData>>,
+    ) -> Result<MarketDataHistory> {
+        if dates.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(anyhow!(
+                "({}:{}) the dates of the market data history are not increasing: {:?}",
+                file!(),
+                line!(),
+                dates
+            ));
+        }
+
+        let n = dates.len();
+        let check = |kind: &str, key: String, len: usize| check_length(kind, key, len, n);
+        for (key, series) in &fx_data {
+            check("fx", key.to_string(), series.len())?;
+        }
+        for (key, series) in &stock_data {
+            check("stock", key.clone(), series.len())?;
+        }
+        for (key, series) in &curve_data {
+            check("curve", key.clone(), series.len())?;
+        }
+        for (key, series) in &equity_constant_volatility_data {
+            check("equity volatility", key.clone(), series.len())?;
+        }
+        for (key, series) in &equity_volatility_surface_data {
+            check("equity volatility surface", key.clone(), series.len())?;
+            if series
+                .windows(2)
+                .any(|w| w[0].get_value().dim() != w[1].get_value().dim())
+            {
+                return Err(anyhow!(
+                    "({}:{}) the equity volatility surface history of {} changes its shape",
+                    file!(),
+                    line!(),
+                    key
+                ));
+            }
+        }
+        for (key, series) in &fx_constant_volatility_data {
+            check("fx volatility", key.to_string(), series.len())?;
+        }
+
+        Ok(MarketDataHistory {
+            dates,
+            fx_data,
+            stock_data,
+            curve_data,
+            equity_constant_volatility_data,
+            equity_volatility_surface_data,
+            fx_constant_volatility_data,
+            fx_volatility_quote_data: HashMap::new(),
+            rate_volatility_data: HashMap::new(),
+        })
+    }
+
+    /// history of the fx volatility quotes on the same expiries over the dates
+    pub fn with_fx_volatility_quote_data(
+        mut self,
+        fx_volatility_quote_data: HashMap<FxCode, Vec<FxVolatilityQuoteData>>,
+    ) -> Result<MarketDataHistory> {
+        for (key, series) in &fx_volatility_quote_data {
+            check_length(
+                "fx volatility quote",
+                key.to_string(),
+                series.len(),
+                self.dates.len(),
+            )?;
+            if series.windows(2).any(|w| {
+                w[0].get_atm().len() != w[1].get_atm().len()
+                    || w[0].get_risk_reversal_25().len() != w[1].get_risk_reversal_25().len()
+                    || w[0].get_butterfly_25().len() != w[1].get_butterfly_25().len()
+            }) {
+                return Err(anyhow!(
+                    "({}:{}) the fx volatility quote history of {} changes its expiries",
+                    file!(),
+                    line!(),
+                    key
+                ));
+            }
+        }
+        self.fx_volatility_quote_data = fx_volatility_quote_data;
+        Ok(self)
+    }
+
+    /// history of the rate volatility cubes on the same grid over the dates
+    pub fn with_rate_volatility_data(
+        mut self,
+        rate_volatility_data: HashMap<String, Vec<RateVolatilityData>>,
+    ) -> Result<MarketDataHistory> {
+        for (key, series) in &rate_volatility_data {
+            check_length(
+                "rate volatility",
+                key.clone(),
+                series.len(),
+                self.dates.len(),
+            )?;
+            if series
+                .windows(2)
+                .any(|w| w[0].get_value().dim() != w[1].get_value().dim())
+            {
+                return Err(anyhow!(
+                    "({}:{}) the rate volatility history of {} changes its shape",
+                    file!(),
+                    line!(),
+                    key
+                ));
+            }
+        }
+        self.rate_volatility_data = rate_volatility_data;
+        Ok(self)
+    }
+
+    pub fn get_dates(&self) -> &Vec<OffsetDateTime> {
+        &self.dates
+    }
+
+    pub fn get_fx_data(&self) -> &HashMap<FxCode, Vec<ValueData>> {
+        &self.fx_data
+    }
+
+    pub fn get_stock_data(&self) -> &HashMap<String, Vec<ValueData>> {
+        &self.stock_data
+    }
+
+    pub fn get_curve_data(&self) -> &HashMap<String, Vec<VectorData>> {
+        &self.curve_data
+    }
+
+    pub fn get_equity_constant_volatility_data(&self) -> &HashMap<String, Vec<ValueData>> {
+        &self.equity_constant_volatility_data
+    }
+
+    pub fn get_equity_volatility_surface_data(&self) -> &HashMap<String, Vec<SurfaceData>> {
+        &self.equity_volatility_surface_data
+    }
+
+    pub fn get_fx_constant_volatility_data(&self) -> &HashMap<FxCode, Vec<ValueData>> {
+        &self.fx_constant_volatility_data
+    }
+
+    pub fn get_fx_volatility_quote_data(&self) -> &HashMap<FxCode, Vec<FxVolatilityQuoteData>> {
+        &self.fx_volatility_quote_data
+    }
+
+    pub fn get_rate_volatility_data(&self) -> &HashMap<String, Vec<RateVolatilityData>> {
+        &self.rate_volatility_data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::enums::RateVolatilityType;
+    use ndarray::{array, Array3};
+    use time::macros::datetime;
+
+    #[test]
+    fn test_market_data_history_validation() -> Result<()> {
+        let dates = vec![
+            datetime!(2024-01-02 16:00:00 +09:00),
+            datetime!(2024-01-03 16:00:00 +09:00),
+        ];
+        let price = |v| {
+            ValueData::new(
+                v,
+                None,
+                Currency::KRW,
+                "005930".to_string(),
+                "005930".to_string(),
+            )
+        };
+        let mut stock_data = HashMap::new();
+        stock_data.insert(
+            "005930".to_string(),
+            vec![price(70_000.0)?, price(71_000.0)?],
+        );
+        let history = MarketDataHistory::new(
+            dates.clone(),
+            HashMap::new(),
+            stock_data.clone(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+        )?;
+        assert_eq!(history.get_stock_data()["005930"][1].get_value(), 71_000.0);
+
+        // the rate volatility cube changes its shape
+        let cube = |n: usize| {
+            RateVolatilityData::new(
+                Array3::from_elem((n, 1, 1), 0.01),
+                vec![dates[0]; n],
+                array![1.0],
+                array![0.03],
+                RateVolatilityType::Normal,
+                0.0,
+                None,
+                Currency::KRW,
+                "CD91".to_string(),
+                "CD91".to_string(),
+            )
+        };
+        let mut rate_volatility_data = HashMap::new();
+        rate_volatility_data.insert("CD91".to_string(), vec![cube(1), cube(2)]);
+        assert!(history
+            .clone()
+            .with_rate_volatility_data(rate_volatility_data)
+            .is_err());
+
+        // a missing value
+        stock_data.insert("000660".to_string(), vec![price(130_000.0)?]);
+        assert!(MarketDataHistory::new(
+            dates.clone(),
+            HashMap::new(),
+            stock_data,
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+        )
+        .is_err());
+
+        // the dates in reverse order
+        assert!(MarketDataHistory::new(
+            dates.into_iter().rev().collect(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+        )
+        .is_err());
+        Ok(())
+    }
+}
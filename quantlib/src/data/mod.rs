@@ -1,4 +1,8 @@
+pub mod fx_volatility_quote_data;
+pub mod market_data_history;
+pub mod rate_volatility_data;
 pub mod surface_data;
+pub mod svi_parameter_data;
 pub mod value_data;
 pub mod vector_data;
 //pub mod observable;
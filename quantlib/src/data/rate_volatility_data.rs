@@ -0,0 +1,92 @@
+use crate::currency::Currency;
+use crate::definitions::Real;
+use crate::enums::RateVolatilityType;
+use ndarray::{Array1, Array3};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Rate volatility cube on expiry dates x underlying tenors (in years) x absolute strikes.
+/// The tenor of a caplet is the tenor of its rate index, and that of a swaption is the swap length.
+/// shift is used only for RateVolatilityType::ShiftedLognormal
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RateVolatilityData {
+    value: Array3<Real>,
+    expiry_dates: Vec<OffsetDateTime>,
+    tenors: Array1<Real>,
+    strikes: Array1<Real>,
+    volatility_type: RateVolatilityType,
+    shift: Real,
+    market_datetime: Option<OffsetDateTime>,
+    currency: Currency,
+    name: String,
+    code: String,
+}
+
+impl RateVolatilityData {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        value: Array3<Real>,
+        expiry_dates: Vec<OffsetDateTime>,
+        tenors: Array1<Real>,
+        strikes: Array1<Real>,
+        volatility_type: RateVolatilityType,
+        shift: Real,
+        market_datetime: Option<OffsetDateTime>,
+        currency: Currency,
+        name: String,
+        code: String,
+    ) -> RateVolatilityData {
+        RateVolatilityData {
+            value,
+            expiry_dates,
+            tenors,
+            strikes,
+            volatility_type,
+            shift,
+            market_datetime,
+            currency,
+            name,
+            code,
+        }
+    }
+
+    pub fn get_value(&self) -> &Array3<Real> {
+        &self.value
+    }
+
+    pub fn get_expiry_dates(&self) -> &Vec<OffsetDateTime> {
+        &self.expiry_dates
+    }
+
+    pub fn get_tenors(&self) -> &Array1<Real> {
+        &self.tenors
+    }
+
+    pub fn get_strikes(&self) -> &Array1<Real> {
+        &self.strikes
+    }
+
+    pub fn get_volatility_type(&self) -> RateVolatilityType {
+        self.volatility_type
+    }
+
+    pub fn get_shift(&self) -> Real {
+        self.shift
+    }
+
+    pub fn get_market_datetime(&self) -> Option<OffsetDateTime> {
+        self.market_datetime
+    }
+
+    pub fn get_currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_code(&self) -> &str {
+        &self.code
+    }
+}
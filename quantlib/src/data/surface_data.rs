@@ -60,6 +60,10 @@ impl SurfaceData {
         &self.strikes
     }
 
+    pub fn get_currency(&self) -> &Currency {
+        &self.currency
+    }
+
     pub fn get_name(&self) -> &str {
         &self.name
     }
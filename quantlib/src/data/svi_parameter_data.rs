@@ -0,0 +1,67 @@
+use crate::currency::Currency;
+use crate::definitions::Real;
+use crate::parameters::volatilities::svi_volatility_surface::SviParameters;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// SVI or SSVI parameters marked on the expiries, where spot is the underlying price at the marking
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SviParameterData {
+    spot: Real,
+    parameters: SviParameters,
+    dates: Vec<OffsetDateTime>,
+    market_datetime: Option<OffsetDateTime>,
+    currency: Currency,
+    name: String,
+    code: String,
+}
+
+impl SviParameterData {
+    pub fn new(
+        spot: Real,
+        parameters: SviParameters,
+        dates: Vec<OffsetDateTime>,
+        market_datetime: Option<OffsetDateTime>,
+        currency: Currency,
+        name: String,
+        code: String,
+    ) -> SviParameterData {
+        SviParameterData {
+            spot,
+            parameters,
+            dates,
+            market_datetime,
+            currency,
+            name,
+            code,
+        }
+    }
+
+    pub fn get_spot(&self) -> Real {
+        self.spot
+    }
+
+    pub fn get_parameters(&self) -> &SviParameters {
+        &self.parameters
+    }
+
+    pub fn get_dates(&self) -> &Vec<OffsetDateTime> {
+        &self.dates
+    }
+
+    pub fn get_market_datetime(&self) -> Option<OffsetDateTime> {
+        self.market_datetime
+    }
+
+    pub fn get_currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_code(&self) -> &str {
+        &self.code
+    }
+}
@@ -64,6 +64,10 @@ impl ValueData {
         &self.name
     }
 
+    pub fn get_code(&self) -> &String {
+        &self.code
+    }
+
     pub fn get_currency(&self) -> &Currency {
         &self.currency
     }
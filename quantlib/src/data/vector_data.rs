@@ -130,6 +130,18 @@ impl VectorData {
     pub fn get_dates_clone(&self) -> Option<Vec<OffsetDateTime>> {
         self.dates.clone()
     }
+
+    pub fn get_market_datetime(&self) -> Option<OffsetDateTime> {
+        self.market_datetime
+    }
+
+    pub fn get_currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    pub fn get_code_clone(&self) -> String {
+        self.code.clone()
+    }
 }
 
 #[cfg(test)]
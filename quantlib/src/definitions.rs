@@ -27,6 +27,7 @@ pub const NEW_YORK_OFFSET: (i8, i8, i8) = (-5, 0, 0);
 pub const DELTA_PNL_UNIT: Real = 0.01;
 pub const GAMMA_PNL_UNIT: Real = 0.01;
 pub const VEGA_PNL_UNIT: Real = 0.01;
+pub const NORMAL_VEGA_PNL_UNIT: Real = 0.0001;
 pub const RHO_PNL_UNIT: Real = 0.0001;
 pub const DIV_PNL_UNIT: Real = 0.0001;
 pub const THETA_PNL_UNIT: Real = 1.0;
@@ -66,6 +66,119 @@ pub enum OptionType {
     Put,
 }
 
+/// Knock-in options become vanilla options when the barrier is hit,
+/// and knock-out options expire when the barrier is hit.
+/// Up barriers are above the spot, and down barriers are below the spot.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Copy)]
+pub enum BarrierType {
+    UpAndIn,
+    UpAndOut,
+    DownAndIn,
+    DownAndOut,
+}
+
+/// The average of the fixings of an Asian option
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Copy)]
+pub enum AveragingType {
+    Arithmetic,
+    Geometric,
+}
+
+/// Fixed-strike lookbacks pay the maximum (call) or the minimum (put) against the strike,
+/// and floating-strike lookbacks pay the terminal price against the minimum (call) or the maximum (put).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Copy)]
+pub enum LookbackType {
+    FixedStrike,
+    FloatingStrike,
+}
+
+/// Caps pay the excess of the fixings over the strike, and floors pay the shortfall.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Copy)]
+pub enum CapFloorType {
+    Cap,
+    Floor,
+}
+
+/// Payer swaptions exercise into paying the fixed rate, and receiver swaptions into receiving it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Copy)]
+pub enum SwaptionType {
+    Payer,
+    Receiver,
+}
+
+/// Protection buyers pay the premium and receive the loss given default, and sellers the other way around.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Copy)]
+pub enum ProtectionSide {
+    Buyer,
+    Seller,
+}
+
+/// Deliverable forwards exchange both currencies at the maturity,
+/// and non-deliverable forwards (NDF) pay the difference to the fixing in the settlement currency.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Copy)]
+pub enum FxSettlementType {
+    Deliverable,
+    NonDeliverable,
+}
+
+/// Delta convention of FX volatility quotes.
+/// Spot: the delta is the change of the value in the base currency on the spot, i.e., DF_base N(d1).
+/// Forward: the delta on the forward, i.e., N(d1).
+/// The premium-adjusted deltas subtract the premium paid in the base currency,
+/// i.e., DF_base K / F N(d2) and K / F N(d2), which is the market convention
+/// when the premium is paid in the base currency, e.g., USD/JPY and USD/KRW with the premium in USD.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Copy)]
+pub enum FxDeltaType {
+    Spot,
+    Forward,
+    SpotPremiumAdjusted,
+    ForwardPremiumAdjusted,
+}
+
+/// At-the-money convention of FX volatility quotes.
+/// AtmForward: the strike is the forward.
+/// DeltaNeutralStraddle: the strike at which the call and the put deltas sum to zero.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Copy, Default)]
+pub enum FxAtmType {
+    AtmForward,
+    #[default]
+    DeltaNeutralStraddle,
+}
+
+/// Convention of the 25-delta butterfly quote.
+/// MarketStrangle: the broker strangle, priced on the single volatility ATM + BF
+/// at its own 25-delta call and put strikes.
+/// SmileStrangle: the average of the 25-delta call and put volatilities on the smile minus the ATM.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Copy, Default)]
+pub enum FxStrangleType {
+    #[default]
+    MarketStrangle,
+    SmileStrangle,
+}
+
+/// Quotation of rate volatilities.
+/// ShiftedLognormal: Black volatilities on the rate plus the shift.
+/// Normal: Bachelier volatilities in absolute rate.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Copy)]
+pub enum RateVolatilityType {
+    ShiftedLognormal,
+    Normal,
+}
+
+/// Compounding of a bond yield to maturity.
+/// Krx: compounded at the coupon frequency from the next coupon date
+/// and simple until the next coupon date (금융투자회사의 영업 및 업무에 관한 규정 별표 14).
+/// CouponFrequency: compounded at the coupon frequency on the daycounter of the bond.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Copy, Default)]
+pub enum YieldCompounding {
+    #[default]
+    Krx,
+    Simple,
+    Continuous,
+    Annual,
+    CouponFrequency,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Copy)]
 pub enum OptionExerciseType {
     European,
@@ -90,6 +203,57 @@ pub enum StickynessType {
     StickyToStrike,
 }
 
+/// Interpolation scheme of ZeroCurve.
+/// Linear: zero rates are linearly interpolated on the fixed tenor grid (the default).
+/// LogLinearDiscount: log discount factors are linear between the input nodes,
+/// i.e., piecewise flat forwards, and the zero rate is flat after the last node.
+/// MonotoneConvex: Hagan-West monotone convex on the discrete forwards.
+/// The forwards are continuous and positive if the discrete forwards are positive.
+/// NaturalCubicRate: natural cubic spline on the zero rates, which gives smooth forwards.
+/// FlatForward: piecewise flat forwards as LogLinearDiscount,
+/// but the last forward is extended after the last node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Copy, Default)]
+pub enum ZeroCurveInterpolationType {
+    #[default]
+    Linear,
+    LogLinearDiscount,
+    MonotoneConvex,
+    NaturalCubicRate,
+    FlatForward,
+}
+
+/// Equity volatility surface built from the market implied volatility surface.
+/// LocalVolatility: the quotes are interpolated into LocalVolatilitySurface (the default).
+/// RawSvi: raw SVI is calibrated on each expiry into SviVolatilitySurface.
+/// Ssvi: SSVI is calibrated on all the expiries into SviVolatilitySurface.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Copy, Default)]
+pub enum VolatilitySurfaceType {
+    #[default]
+    LocalVolatility,
+    RawSvi,
+    Ssvi,
+}
+
+/// P&L of the historical scenarios in HistoricalVarEngine.
+/// FullRevaluation: the instruments are repriced on the shocked market data.
+/// DeltaGamma: the P&L is approximated by the delta, gamma, vega and rho on the base market data.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Copy, Default)]
+pub enum VarMethod {
+    #[default]
+    FullRevaluation,
+    DeltaGamma,
+}
+
+/// Method of the P&L explain between two evaluation dates.
+/// RiskBased: the P&L is attributed by the Greeks of the start date on the market moves.
+/// StepByStep: the instruments are repriced while the market data is moved to the end date one group at a time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Copy, Default)]
+pub enum PnlExplainMethod {
+    #[default]
+    RiskBased,
+    StepByStep,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Copy)]
 pub enum VanillaOptionCalculationMethod {
     MonteCarlo = 0,
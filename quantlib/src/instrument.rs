@@ -1,19 +1,29 @@
 use crate::currency::{Currency, FxCode};
 use crate::definitions::Real;
 use crate::enums::{
-    AccountingLevel, CreditRating, IssuerType, OptionDailySettlementType, OptionType, RankType,
+    AccountingLevel, CreditRating, IssuerType, OptionDailySettlementType, OptionExerciseType,
+    OptionType, RankType,
 };
 
 use crate::instruments::schedule::Schedule;
 use crate::instruments::{
+    asian_option::AsianOption,
+    autocallable::Autocallable,
+    barrier_option::BarrierOption,
     bond::Bond,
     bond_futures::BondFutures,
+    cap_floor::CapFloor,
     cash::Cash,
+    credit_default_swap::CreditDefaultSwap,
     futures::Futures,
+    fx_forward::FxForward,
     fx_futures::FxFutures,
+    fx_option::FxOption,
     ktbf::KTBF,
+    lookback_option::LookbackOption,
     plain_swap::{PlainSwap, PlainSwapType},
     stock::Stock,
+    swaption::Swaption,
     vanilla_option::VanillaOption,
 };
 
@@ -66,10 +76,25 @@ pub trait InstrumentTrait {
     fn get_all_fxcodes_for_pricing(&self) -> Vec<FxCode> {
         vec![]
     }
+    // fx rates which are the underlying of the instrument, e.g., FX forwards,
+    // so that the fx delta is calculated on them
+    fn get_underlying_fxcodes(&self) -> Vec<&FxCode> {
+        vec![]
+    }
 
     fn get_underlying_codes_requiring_volatility(&self) -> Vec<&String> {
         vec![]
     }
+
+    /// fx rates whose volatility is needed, e.g., for FX options
+    fn get_fxcodes_requiring_volatility(&self) -> Vec<&FxCode> {
+        vec![]
+    }
+
+    /// rate indices whose RateVolatility is needed, e.g., for caps and swaptions
+    fn get_rate_index_codes_requiring_volatility(&self) -> Vec<&String> {
+        vec![]
+    }
     // only for bonds, so None must be allowed
     fn get_credit_rating(&self) -> Result<&CreditRating> {
         Err(anyhow!(
@@ -215,6 +240,19 @@ pub trait InstrumentTrait {
         ))
     }
 
+    fn get_option_exercise_type(&self) -> Result<OptionExerciseType> {
+        Err(anyhow!(
+            "not supported instrument type on get_option_exercise_type"
+        ))
+    }
+
+    /// exercise dates of Bermudan options
+    fn get_exercise_dates(&self) -> Result<&Vec<OffsetDateTime>> {
+        Err(anyhow!(
+            "not supported instrument type on get_exercise_dates"
+        ))
+    }
+
     fn get_fxfutres_und_fxcode(&self) -> Result<&FxCode> {
         Err(anyhow!("not supported instrument type on get_fx_code"))
     }
@@ -240,8 +278,17 @@ pub enum Instrument {
     BondFutures(BondFutures),
     KTBF(KTBF),
     PlainSwap(PlainSwap),
+    CreditDefaultSwap(CreditDefaultSwap),
+    CapFloor(CapFloor),
+    Swaption(Swaption),
     FxFutures(FxFutures),
+    FxForward(FxForward),
+    FxOption(FxOption),
     VanillaOption(VanillaOption),
+    BarrierOption(BarrierOption),
+    AsianOption(AsianOption),
+    LookbackOption(LookbackOption),
+    Autocallable(Autocallable),
     Stock(Stock),
     Cash(Cash),
 }
@@ -347,7 +394,7 @@ impl Instruments {
             }
 
             match instrument.get_type_name() {
-                "Futures" | "FxFutures" => {
+                "Futures" | "FxFutures" | "FxForward" | "FxOption" => {
                     let currency = instrument.get_underlying_currency().with_context(|| {
                         anyhow!(
                             "({}:{}) get_underlying_currency failed for {} ({})",
@@ -381,6 +428,28 @@ impl Instruments {
         Ok(currencies)
     }
 
+    pub fn get_all_underlying_fxcodes(&self) -> Vec<&FxCode> {
+        let mut fxcodes = Vec::<&FxCode>::new();
+        for instrument in self.instruments.iter() {
+            for code in instrument.get_underlying_fxcodes() {
+                if !fxcodes.contains(&code) {
+                    fxcodes.push(code);
+                }
+            }
+        }
+        fxcodes
+    }
+
+    pub fn instruments_with_underlying_fxcode(&self, fxcode: &FxCode) -> Vec<Rc<Instrument>> {
+        let mut res = Vec::<Rc<Instrument>>::new();
+        for instrument in self.instruments.iter() {
+            if instrument.get_underlying_fxcodes().contains(&fxcode) {
+                res.push(instrument.clone());
+            }
+        }
+        res
+    }
+
     pub fn instruments_with_underlying(
         &self,
         und_code: &String,
@@ -398,6 +467,35 @@ impl Instruments {
         res
     }
 
+    pub fn instruments_with_rate_volatility(
+        &self,
+        rate_index_code: &String,
+    ) -> Vec<Rc<Instrument>> {
+        let mut res = Vec::<Rc<Instrument>>::new();
+        for instrument in self.instruments.iter() {
+            if instrument
+                .get_rate_index_codes_requiring_volatility()
+                .contains(&rate_index_code)
+            {
+                res.push(instrument.clone());
+            }
+        }
+        res
+    }
+
+    pub fn instruments_with_fx_volatility(&self, fxcode: &FxCode) -> Vec<Rc<Instrument>> {
+        let mut res = Vec::<Rc<Instrument>>::new();
+        for instrument in self.instruments.iter() {
+            if instrument
+                .get_fxcodes_requiring_volatility()
+                .contains(&fxcode)
+            {
+                res.push(instrument.clone());
+            }
+        }
+        res
+    }
+
     pub fn instruments_with_currency(&self, currency: &Currency) -> Vec<Rc<Instrument>> {
         let mut res = Vec::<Rc<Instrument>>::new();
         for instrument in self.instruments.iter() {
@@ -458,6 +556,17 @@ impl Instruments {
             if match_parameter.get_floating_crs_curve_name(instrument)? == curve_name {
                 res.push(instrument.clone());
             }
+            // 6) risk-free curve under the credit spread curve discounting the instrument
+            if match_parameter.get_credit_spread_base_curve_name(
+                match_parameter.get_discount_curve_name(instrument)?,
+            )? == Some(curve_name)
+            {
+                res.push(instrument.clone());
+            }
+            // 7) par spread curve of the reference entity of CDS
+            if match_parameter.get_cds_spread_curve_name(instrument)? == curve_name {
+                res.push(instrument.clone());
+            }
         }
         Ok(res)
     }
@@ -471,6 +580,14 @@ impl Instruments {
         let dummy = String::from("Dummy");
         for instrument in self.instruments.iter() {
             let discount_curve_name = match_parameter.get_discount_curve_name(instrument)?;
+            // the risk-free curve comes before the credit spread curve layered on it
+            if let Some(base_curve_name) =
+                match_parameter.get_credit_spread_base_curve_name(discount_curve_name)?
+            {
+                if !res.contains(&base_curve_name) {
+                    res.push(base_curve_name);
+                }
+            }
             if !res.contains(&discount_curve_name) && discount_curve_name != &dummy {
                 res.push(discount_curve_name);
             }
@@ -493,6 +610,18 @@ impl Instruments {
             if !res.contains(&floating_crs_curve_name) && floating_crs_curve_name != &dummy {
                 res.push(floating_crs_curve_name);
             }
+            let cds_spread_curve_name = match_parameter.get_cds_spread_curve_name(instrument)?;
+            if !res.contains(&cds_spread_curve_name) && cds_spread_curve_name != &dummy {
+                res.push(cds_spread_curve_name);
+            }
+            // the repo curves of bond futures and KTBF are given by their tags, not by the underlying codes
+            if let Instrument::BondFutures(_) | Instrument::KTBF(_) = instrument.as_ref() {
+                for name in match_parameter.get_borrowing_curve_names(instrument)? {
+                    if !res.contains(&name) {
+                        res.push(name);
+                    }
+                }
+            }
         }
         Ok(res)
     }
@@ -705,6 +834,30 @@ impl Instruments {
         }
     }
 
+    pub fn get_all_rate_index_codes_requiring_volatility(&self) -> Vec<String> {
+        let mut res = Vec::<String>::new();
+        for instrument in self.instruments.iter() {
+            for code in instrument.get_rate_index_codes_requiring_volatility() {
+                if !res.contains(code) {
+                    res.push(code.clone());
+                }
+            }
+        }
+        res
+    }
+
+    pub fn get_all_fxcodes_requiring_volatility(&self) -> Vec<FxCode> {
+        let mut res = Vec::<FxCode>::new();
+        for instrument in self.instruments.iter() {
+            for code in instrument.get_fxcodes_requiring_volatility() {
+                if !res.contains(code) {
+                    res.push(*code);
+                }
+            }
+        }
+        res
+    }
+
     pub fn get_all_unerlying_codes_requiring_volatility(
         &self,
         instruments: Option<&Vec<Rc<Instrument>>>,
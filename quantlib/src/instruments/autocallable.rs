@@ -0,0 +1,314 @@
+use crate::currency::{Currency, FxCode};
+use crate::definitions::Real;
+use crate::instrument::InstrumentTrait;
+use crate::instruments::schedule::{build_schedule, Schedule};
+use crate::time::{
+    conventions::{BusinessDayConvention, PaymentFrequency},
+    jointcalendar::JointCalendar,
+};
+//
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Step-down autocallable (Korean ELS) on the worst performer of the underlyings.
+/// The performance of an underlying is its price divided by its initial price,
+/// and all the barriers are given in the ratio to the initial prices.
+///
+/// On the i-th observation date (calc_end_date of the i-th schedule), the note is redeemed
+/// with 1 + autocall_coupons[i] (in the ratio to the unit notional) paid on the payment date
+/// if the worst performance is at or above autocall_barriers[i].
+/// If the note is not redeemed until the maturity, it pays 1 + dummy_coupon
+/// unless the worst performance has ever been below knock_in_barrier (observed on the daily closes),
+/// and the worst performance otherwise. Without knock_in_barrier, it pays the worst performance.
+///
+/// Optionally, coupons[i] is paid on the i-th payment date if the worst performance is at or above
+/// coupon_barriers[i]. With coupon_memory, the coupons missed before are paid together.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Autocallable {
+    underlying_codes: Vec<String>,
+    initial_prices: Vec<Real>,
+    schedule: Schedule,
+    autocall_barriers: Vec<Real>,
+    autocall_coupons: Vec<Real>,
+    knock_in_barrier: Option<Real>,
+    dummy_coupon: Real,
+    #[serde(default)]
+    coupon_barriers: Vec<Real>,
+    #[serde(default)]
+    coupons: Vec<Real>,
+    #[serde(default)]
+    coupon_memory: bool,
+    //
+    unit_notional: Real,
+    issue_date: OffsetDateTime,
+    maturity: OffsetDateTime,
+    calendar: JointCalendar,
+    underlying_currency: Currency,
+    currency: Currency,
+    quanto_fx_code: Option<FxCode>,
+    name: String,
+    code: String,
+}
+
+impl Autocallable {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        underlying_codes: Vec<String>,
+        initial_prices: Vec<Real>,
+        schedule: Schedule,
+        autocall_barriers: Vec<Real>,
+        autocall_coupons: Vec<Real>,
+        knock_in_barrier: Option<Real>,
+        dummy_coupon: Real,
+        unit_notional: Real,
+        issue_date: OffsetDateTime,
+        calendar: JointCalendar,
+        underlying_currency: Currency,
+        currency: Currency,
+        name: String,
+        code: String,
+    ) -> Result<Autocallable> {
+        if underlying_codes.is_empty() || underlying_codes.len() != initial_prices.len() {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has {} underlyings but {} initial prices",
+                file!(),
+                line!(),
+                name,
+                code,
+                underlying_codes.len(),
+                initial_prices.len()
+            ));
+        }
+        if schedule.is_empty()
+            || schedule.len() != autocall_barriers.len()
+            || schedule.len() != autocall_coupons.len()
+        {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has {} observations, {} autocall barriers and {} autocall coupons",
+                file!(),
+                line!(),
+                name,
+                code,
+                schedule.len(),
+                autocall_barriers.len(),
+                autocall_coupons.len()
+            ));
+        }
+        let maturity = *schedule[schedule.len() - 1].get_payment_date();
+        let quanto_fx_code = if currency != underlying_currency {
+            Some(FxCode::new(underlying_currency, currency))
+        } else {
+            None
+        };
+
+        Ok(Autocallable {
+            underlying_codes,
+            initial_prices,
+            schedule,
+            autocall_barriers,
+            autocall_coupons,
+            knock_in_barrier,
+            dummy_coupon,
+            coupon_barriers: vec![],
+            coupons: vec![],
+            coupon_memory: false,
+            unit_notional,
+            issue_date,
+            maturity,
+            calendar,
+            underlying_currency,
+            currency,
+            quanto_fx_code,
+            name,
+            code,
+        })
+    }
+
+    /// The observation schedule is generated forward from the issue date by build_schedule,
+    /// e.g., semiannual observations on the SouthKorea calendar with the payment two days after.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_from_conventions(
+        underlying_codes: Vec<String>,
+        initial_prices: Vec<Real>,
+        autocall_barriers: Vec<Real>,
+        autocall_coupons: Vec<Real>,
+        knock_in_barrier: Option<Real>,
+        dummy_coupon: Real,
+        unit_notional: Real,
+        issue_date: OffsetDateTime,
+        maturity: OffsetDateTime,
+        calendar: JointCalendar,
+        busi_convention: BusinessDayConvention,
+        observation_frequency: PaymentFrequency,
+        payment_gap_days: i64,
+        underlying_currency: Currency,
+        currency: Currency,
+        name: String,
+        code: String,
+    ) -> Result<Autocallable> {
+        let schedule = build_schedule(
+            true,
+            &issue_date,
+            &maturity,
+            &calendar,
+            &busi_convention,
+            &observation_frequency,
+            0,
+            payment_gap_days,
+        )
+        .with_context(|| {
+            anyhow!(
+                "({}:{}) failed to build the observation schedule of {} ({})",
+                file!(),
+                line!(),
+                &name,
+                &code
+            )
+        })?;
+
+        Autocallable::new(
+            underlying_codes,
+            initial_prices,
+            schedule,
+            autocall_barriers,
+            autocall_coupons,
+            knock_in_barrier,
+            dummy_coupon,
+            unit_notional,
+            issue_date,
+            calendar,
+            underlying_currency,
+            currency,
+            name,
+            code,
+        )
+    }
+
+    /// coupons paid on the observations where the worst performance is at or above coupon_barriers
+    pub fn with_coupons(
+        mut self,
+        coupon_barriers: Vec<Real>,
+        coupons: Vec<Real>,
+        coupon_memory: bool,
+    ) -> Result<Autocallable> {
+        if coupon_barriers.len() != self.schedule.len() || coupons.len() != self.schedule.len() {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has {} observations, {} coupon barriers and {} coupons",
+                file!(),
+                line!(),
+                self.name,
+                self.code,
+                self.schedule.len(),
+                coupon_barriers.len(),
+                coupons.len()
+            ));
+        }
+        self.coupon_barriers = coupon_barriers;
+        self.coupons = coupons;
+        self.coupon_memory = coupon_memory;
+        Ok(self)
+    }
+
+    pub fn get_initial_prices(&self) -> &Vec<Real> {
+        &self.initial_prices
+    }
+
+    pub fn get_autocall_barriers(&self) -> &Vec<Real> {
+        &self.autocall_barriers
+    }
+
+    pub fn get_autocall_coupons(&self) -> &Vec<Real> {
+        &self.autocall_coupons
+    }
+
+    pub fn get_knock_in_barrier(&self) -> Option<Real> {
+        self.knock_in_barrier
+    }
+
+    pub fn get_dummy_coupon(&self) -> Real {
+        self.dummy_coupon
+    }
+
+    /// empty if the note has no coupon other than the autocall coupons
+    pub fn get_coupon_barriers(&self) -> &Vec<Real> {
+        &self.coupon_barriers
+    }
+
+    pub fn get_coupons(&self) -> &Vec<Real> {
+        &self.coupons
+    }
+
+    pub fn get_coupon_memory(&self) -> bool {
+        self.coupon_memory
+    }
+
+    /// the payment at the maturity if the note has not been redeemed early
+    pub fn get_maturity_redemption(&self, worst_performance: Real, knocked_in: bool) -> Real {
+        match self.knock_in_barrier {
+            Some(_) if !knocked_in => 1.0 + self.dummy_coupon,
+            _ => worst_performance,
+        }
+    }
+}
+
+impl InstrumentTrait for Autocallable {
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    fn get_currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    fn get_unit_notional(&self) -> Real {
+        self.unit_notional
+    }
+
+    fn get_type_name(&self) -> &'static str {
+        "Autocallable"
+    }
+
+    fn get_maturity(&self) -> Option<&OffsetDateTime> {
+        Some(&self.maturity)
+    }
+
+    fn get_issue_date(&self) -> Result<&OffsetDateTime> {
+        Ok(&self.issue_date)
+    }
+
+    fn get_underlying_codes(&self) -> Vec<&String> {
+        self.underlying_codes.iter().collect()
+    }
+
+    fn get_underlying_currency(&self) -> Result<&Currency> {
+        Ok(&self.underlying_currency)
+    }
+
+    fn get_quanto_fxcode_und_pair(&self) -> Vec<(&String, &FxCode)> {
+        match &self.quanto_fx_code {
+            Some(fx_code) => self
+                .underlying_codes
+                .iter()
+                .map(|code| (code, fx_code))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    fn get_underlying_codes_requiring_volatility(&self) -> Vec<&String> {
+        self.underlying_codes.iter().collect()
+    }
+
+    fn get_schedule(&self) -> Result<&Schedule> {
+        Ok(&self.schedule)
+    }
+
+    fn get_calendar(&self) -> Result<&JointCalendar> {
+        Ok(&self.calendar)
+    }
+}
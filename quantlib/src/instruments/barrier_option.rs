@@ -0,0 +1,171 @@
+use crate::currency::{Currency, FxCode};
+use crate::definitions::Real;
+use crate::enums::{BarrierType, OptionDailySettlementType, OptionExerciseType, OptionType};
+use crate::instrument::InstrumentTrait;
+//
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// European barrier option on a single underlying.
+/// The rebate of knock-out options is paid when the barrier is hit,
+/// and the rebate of knock-in options is paid at maturity if the barrier has never been hit.
+/// The barrier is monitored continuously unless monitoring dates are given.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BarrierOption {
+    strike: Real,
+    barrier: Real,
+    barrier_type: BarrierType,
+    rebate: Real,
+    #[serde(default)]
+    monitoring_dates: Vec<OffsetDateTime>,
+    unit_notional: Real,
+    issue_date: OffsetDateTime,
+    last_trade_date: OffsetDateTime,
+    maturity: OffsetDateTime,
+    settlement_date: OffsetDateTime,
+    underlying_codes: Vec<String>,
+    underlying_currency: Currency,
+    currency: Currency,
+    quanto_fx_code: Option<FxCode>,
+    option_type: OptionType,
+    daily_settlement_type: OptionDailySettlementType,
+    name: String,
+    code: String,
+}
+
+impl BarrierOption {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        strike: Real,
+        barrier: Real,
+        barrier_type: BarrierType,
+        rebate: Real,
+        unit_notional: Real,
+        issue_date: OffsetDateTime,
+        last_trade_date: OffsetDateTime,
+        maturity: OffsetDateTime,
+        settlement_date: OffsetDateTime,
+        underlying_codes: Vec<String>,
+        underlying_currency: Currency,
+        currency: Currency,
+        option_type: OptionType,
+        option_daily_settlement_type: OptionDailySettlementType,
+        name: String,
+        code: String,
+    ) -> BarrierOption {
+        let quanto_fx_code = if currency != underlying_currency {
+            Some(FxCode::new(underlying_currency, currency))
+        } else {
+            None
+        };
+
+        BarrierOption {
+            strike,
+            barrier,
+            barrier_type,
+            rebate,
+            monitoring_dates: vec![],
+            unit_notional,
+            issue_date,
+            last_trade_date,
+            maturity,
+            settlement_date,
+            underlying_codes,
+            underlying_currency,
+            currency,
+            quanto_fx_code,
+            option_type,
+            daily_settlement_type: option_daily_settlement_type,
+            name,
+            code,
+        }
+    }
+
+    /// discrete monitoring on the given dates, e.g., the daily closes
+    pub fn with_monitoring_dates(mut self, monitoring_dates: Vec<OffsetDateTime>) -> BarrierOption {
+        self.monitoring_dates = monitoring_dates;
+        self
+    }
+
+    pub fn get_barrier(&self) -> Real {
+        self.barrier
+    }
+
+    pub fn get_barrier_type(&self) -> BarrierType {
+        self.barrier_type
+    }
+
+    pub fn get_rebate(&self) -> Real {
+        self.rebate
+    }
+
+    /// empty for the continuous monitoring
+    pub fn get_monitoring_dates(&self) -> &Vec<OffsetDateTime> {
+        &self.monitoring_dates
+    }
+}
+
+impl InstrumentTrait for BarrierOption {
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_type_name(&self) -> &'static str {
+        match self.option_type {
+            OptionType::Call => "BarrierCall",
+            OptionType::Put => "BarrierPut",
+        }
+    }
+
+    fn get_unit_notional(&self) -> Real {
+        self.unit_notional
+    }
+
+    fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    fn get_currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    fn get_underlying_currency(&self) -> Result<&Currency> {
+        Ok(&self.underlying_currency)
+    }
+
+    fn get_maturity(&self) -> Option<&OffsetDateTime> {
+        Some(&self.maturity)
+    }
+
+    fn get_underlying_codes(&self) -> Vec<&String> {
+        vec![&self.underlying_codes[0]]
+    }
+
+    fn get_option_type(&self) -> Result<OptionType> {
+        Ok(self.option_type)
+    }
+
+    fn get_option_daily_settlement_type(&self) -> Result<OptionDailySettlementType> {
+        Ok(self.daily_settlement_type)
+    }
+
+    fn get_option_exercise_type(&self) -> Result<OptionExerciseType> {
+        Ok(OptionExerciseType::European)
+    }
+
+    fn get_strike(&self) -> Result<Real> {
+        Ok(self.strike)
+    }
+
+    fn get_quanto_fxcode_und_pair(&self) -> Vec<(&String, &FxCode)> {
+        match &self.quanto_fx_code {
+            Some(fx_code) => vec![(&self.underlying_codes[0], fx_code)],
+            None => vec![],
+        }
+    }
+
+    fn get_underlying_codes_requiring_volatility(&self) -> Vec<&String> {
+        vec![&self.underlying_codes[0]]
+    }
+}
@@ -16,7 +16,7 @@ use serde::{Deserialize, Serialize};
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 use time::OffsetDateTime;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Bond {
     issuer_type: IssuerType,
     credit_rating: CreditRating,
@@ -232,6 +232,14 @@ impl Bond {
     pub fn set_pricing_date(&mut self, pricing_date: OffsetDateTime) {
         self.pricing_date = Some(pricing_date);
     }
+
+    pub fn get_daycounter(&self) -> &DayCountConvention {
+        &self.daycounter
+    }
+
+    pub fn get_fixed_coupon_rate(&self) -> Option<Real> {
+        self.fixed_coupon_rate
+    }
 }
 
 impl InstrumentTrait for Bond {
@@ -271,6 +279,10 @@ impl InstrumentTrait for Bond {
         Ok(&self.issuer_type)
     }
 
+    fn get_rank_type(&self) -> Result<&RankType> {
+        Ok(&self.rank)
+    }
+
     fn get_issuer_name(&self) -> Result<&String> {
         Ok(&self.issuer_name)
     }
@@ -1,19 +1,185 @@
 use crate::currency::Currency;
 use crate::definitions::Real;
 use crate::instrument::InstrumentTrait;
+use crate::instruments::bond::Bond;
+//
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
-use time::OffsetDateTime;
+use time::{Date, OffsetDateTime};
 
+/// Futures on a deliverable basket of bonds such as US Treasury futures.
+/// The short delivers any of underlying_bonds at delivery_date against
+/// the futures price times the conversion factor of the bond plus its accrued interest.
+/// * notional_coupon_rate: coupon rate of the notional bond (6% for US Treasury futures)
+/// * maturity_rounding_months: the time to maturity of the deliverables from the first day of the delivery month
+///   is rounded down to this number of months in the conversion factors (3 for bond futures and 1 for note futures)
+/// * borrowing_curve_tag: tag of the repo curve financing the deliverables
+///
+/// The price is per unit face value as for the bonds, so unit_notional is the face value of a contract.
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct BondFutures {
     currency: Currency,
     unit_notional: Real,
     issue_date: OffsetDateTime,
     maturity: OffsetDateTime,
+    delivery_date: OffsetDateTime,
+    notional_coupon_rate: Real,
+    maturity_rounding_months: i32,
+    underlying_bonds: Vec<Bond>,
+    conversion_factors: Vec<Real>,
+    borrowing_curve_tag: String,
     name: String,
     code: String,
 }
 
+impl BondFutures {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        currency: Currency,
+        unit_notional: Real,
+        issue_date: OffsetDateTime,
+        maturity: OffsetDateTime,
+        delivery_date: OffsetDateTime,
+        notional_coupon_rate: Real,
+        maturity_rounding_months: i32,
+        underlying_bonds: Vec<Bond>,
+        borrowing_curve_tag: String,
+        name: String,
+        code: String,
+    ) -> Result<BondFutures> {
+        if underlying_bonds.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) bond futures {} ({}) has no deliverable bond",
+                file!(),
+                line!(),
+                name,
+                code
+            ));
+        }
+        if maturity_rounding_months <= 0 || notional_coupon_rate <= 0.0 {
+            return Err(anyhow!(
+                "({}:{}) bond futures {} ({}) has maturity_rounding_months = {} and notional_coupon_rate = {}, \
+                which should be positive",
+                file!(),
+                line!(),
+                name,
+                code,
+                maturity_rounding_months,
+                notional_coupon_rate
+            ));
+        }
+
+        let first_delivery_date = delivery_date.date().replace_day(1)?;
+        let conversion_factors = underlying_bonds
+            .iter()
+            .map(|bond| {
+                BondFutures::conversion_factor(
+                    bond,
+                    first_delivery_date,
+                    notional_coupon_rate,
+                    maturity_rounding_months,
+                )
+            })
+            .collect::<Result<Vec<Real>>>()?;
+
+        Ok(BondFutures {
+            currency,
+            unit_notional,
+            issue_date,
+            maturity,
+            delivery_date,
+            notional_coupon_rate,
+            maturity_rounding_months,
+            underlying_bonds,
+            conversion_factors,
+            borrowing_curve_tag,
+            name,
+            code,
+        })
+    }
+
+    /// Price per unit face value of the bond yielding the notional coupon rate
+    /// on the first day of the delivery month, less the accrued interest, rounded to 4 decimals.
+    /// The time to maturity is rounded down to maturity_rounding_months,
+    /// so that it is the CBOT conversion factor for semi-annual bonds with 6% notional coupon.
+    pub fn conversion_factor(
+        bond: &Bond,
+        first_delivery_date: Date,
+        notional_coupon_rate: Real,
+        maturity_rounding_months: i32,
+    ) -> Result<Real> {
+        let coupon_rate = bond.get_fixed_coupon_rate().ok_or_else(|| {
+            anyhow!(
+                "({}:{}) {} ({}) is not a fixed coupon bond, so it can not be delivered",
+                file!(),
+                line!(),
+                bond.get_name(),
+                bond.get_code()
+            )
+        })?;
+        let frequency = bond.get_coupon_frequency()?.as_real();
+        if frequency <= 0.0 || 12.0 % frequency != 0.0 {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has coupon frequency {} which is not supported in conversion factor",
+                file!(),
+                line!(),
+                bond.get_name(),
+                bond.get_code(),
+                frequency
+            ));
+        }
+        let maturity = bond.get_maturity().unwrap().date();
+        let mut months = (maturity.year() - first_delivery_date.year()) * 12
+            + (maturity.month() as i32 - first_delivery_date.month() as i32);
+        if maturity.day() < first_delivery_date.day() {
+            months -= 1;
+        }
+        let months = months - months.rem_euclid(maturity_rounding_months);
+        if months < 0 {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) matures before the delivery month {}",
+                file!(),
+                line!(),
+                bond.get_name(),
+                bond.get_code(),
+                first_delivery_date
+            ));
+        }
+
+        // the full periods after the next coupon and the months to the next coupon
+        let period_months = (12.0 / frequency) as i32;
+        let periods = months / period_months;
+        let months_to_coupon = months % period_months;
+
+        let coupon = coupon_rate / frequency;
+        let growth = 1.0 + notional_coupon_rate / frequency;
+        let discount = growth.powi(-periods);
+        let value_at_coupon =
+            coupon + discount + coupon_rate / notional_coupon_rate * (1.0 - discount);
+        let time_to_coupon = months_to_coupon as Real / period_months as Real;
+        let accrued = coupon * (1.0 - time_to_coupon);
+        let res = value_at_coupon * growth.powf(-time_to_coupon) - accrued;
+        Ok((res * 10_000.0).round() / 10_000.0)
+    }
+
+    pub fn get_delivery_date(&self) -> &OffsetDateTime {
+        &self.delivery_date
+    }
+
+    pub fn get_notional_coupon_rate(&self) -> Real {
+        self.notional_coupon_rate
+    }
+
+    pub fn get_maturity_rounding_months(&self) -> i32 {
+        self.maturity_rounding_months
+    }
+
+    /// conversion factors in the order of the underlying bonds
+    pub fn get_conversion_factors(&self) -> &Vec<Real> {
+        &self.conversion_factors
+    }
+}
+
 impl InstrumentTrait for BondFutures {
     fn get_type_name(&self) -> &'static str {
         "BondFutures"
@@ -35,7 +201,19 @@ impl InstrumentTrait for BondFutures {
         self.unit_notional
     }
 
+    fn get_issue_date(&self) -> Result<&OffsetDateTime> {
+        Ok(&self.issue_date)
+    }
+
     fn get_maturity(&self) -> Option<&OffsetDateTime> {
         Some(&self.maturity)
     }
+
+    fn get_underlying_bonds(&self) -> Result<&Vec<Bond>> {
+        Ok(&self.underlying_bonds)
+    }
+
+    fn get_bond_futures_borrowing_curve_tags(&self) -> Vec<&String> {
+        vec![&self.borrowing_curve_tag]
+    }
 }
@@ -0,0 +1,225 @@
+use crate::currency::Currency;
+use crate::definitions::Real;
+use crate::enums::CapFloorType;
+use crate::instrument::InstrumentTrait;
+use crate::instruments::schedule::{self, Schedule};
+use crate::parameters::rate_index::RateIndex;
+use crate::time::conventions::{BusinessDayConvention, DayCountConvention, PaymentFrequency};
+use crate::time::jointcalendar::JointCalendar;
+//
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Strip of caplets (or floorlets) on the fixings of a RateIndex on each period of the schedule.
+/// The caplet of a period pays max(L - K, 0) * frac (the floorlet max(K - L, 0) * frac)
+/// at the payment date where L is fixed at the fixing date on the tenor of the rate index.
+/// The fixings before the evaluation date are taken from the past data of the rate index.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapFloor {
+    cap_floor_type: CapFloorType,
+    strike: Real,
+    floating_legs: Schedule,
+    rate_index: RateIndex,
+    calendar: JointCalendar,
+    daycounter: DayCountConvention,
+    fixing_gap_days: i64,
+    unit_notional: Real,
+    //
+    issue_date: OffsetDateTime,
+    effective_date: OffsetDateTime,
+    maturity: OffsetDateTime,
+    //
+    currency: Currency,
+    name: String,
+    code: String,
+}
+
+impl CapFloor {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        cap_floor_type: CapFloorType,
+        strike: Real,
+        floating_legs: Schedule,
+        rate_index: RateIndex,
+        calendar: JointCalendar,
+        daycounter: DayCountConvention,
+        fixing_gap_days: i64,
+        unit_notional: Real,
+        issue_date: OffsetDateTime,
+        effective_date: OffsetDateTime,
+        maturity: OffsetDateTime,
+        currency: Currency,
+        name: String,
+        code: String,
+    ) -> Result<CapFloor> {
+        if floating_legs.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has an empty schedule",
+                file!(),
+                line!(),
+                name,
+                code
+            ));
+        }
+        if rate_index.get_currency() != &currency {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) is in {:?} but the rate index {} is in {:?}",
+                file!(),
+                line!(),
+                name,
+                code,
+                currency,
+                rate_index.get_code(),
+                rate_index.get_currency()
+            ));
+        }
+
+        Ok(CapFloor {
+            cap_floor_type,
+            strike,
+            floating_legs,
+            rate_index,
+            calendar,
+            daycounter,
+            fixing_gap_days,
+            unit_notional,
+            issue_date,
+            effective_date,
+            maturity,
+            currency,
+            name,
+            code,
+        })
+    }
+
+    /// construct the caplets on the schedule generated by the conventions
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_from_conventions(
+        cap_floor_type: CapFloorType,
+        strike: Real,
+        rate_index: RateIndex,
+        unit_notional: Real,
+        issue_date: OffsetDateTime,
+        effective_date: OffsetDateTime,
+        maturity: OffsetDateTime,
+        //
+        forward_generation: bool,
+        daycounter: DayCountConvention,
+        busi_convention: BusinessDayConvention,
+        frequency: PaymentFrequency,
+        fixing_gap_days: i64,
+        payment_gap_days: i64,
+        //
+        calendar: JointCalendar,
+        currency: Currency,
+        name: String,
+        code: String,
+    ) -> Result<CapFloor> {
+        let floating_legs = schedule::build_schedule(
+            forward_generation,
+            &effective_date,
+            &maturity,
+            &calendar,
+            &busi_convention,
+            &frequency,
+            fixing_gap_days,
+            payment_gap_days,
+        )
+        .with_context(|| {
+            anyhow!(
+                "({}:{}) Failed to build the schedule of {} ({})",
+                file!(),
+                line!(),
+                &name,
+                &code
+            )
+        })?;
+
+        CapFloor::new(
+            cap_floor_type,
+            strike,
+            floating_legs,
+            rate_index,
+            calendar,
+            daycounter,
+            fixing_gap_days,
+            unit_notional,
+            issue_date,
+            effective_date,
+            maturity,
+            currency,
+            name,
+            code,
+        )
+    }
+
+    pub fn get_cap_floor_type(&self) -> CapFloorType {
+        self.cap_floor_type
+    }
+
+    pub fn get_daycounter(&self) -> &DayCountConvention {
+        &self.daycounter
+    }
+
+    pub fn get_fixing_gap_days(&self) -> i64 {
+        self.fixing_gap_days
+    }
+
+    pub fn get_effective_date(&self) -> &OffsetDateTime {
+        &self.effective_date
+    }
+}
+
+impl InstrumentTrait for CapFloor {
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    fn get_currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    fn get_unit_notional(&self) -> Real {
+        self.unit_notional
+    }
+
+    fn get_type_name(&self) -> &'static str {
+        match self.cap_floor_type {
+            CapFloorType::Cap => "Cap",
+            CapFloorType::Floor => "Floor",
+        }
+    }
+
+    fn get_maturity(&self) -> Option<&OffsetDateTime> {
+        Some(&self.maturity)
+    }
+
+    fn get_issue_date(&self) -> Result<&OffsetDateTime> {
+        Ok(&self.issue_date)
+    }
+
+    fn get_rate_index(&self) -> Result<Option<&RateIndex>> {
+        Ok(Some(&self.rate_index))
+    }
+
+    fn get_rate_index_codes_requiring_volatility(&self) -> Vec<&String> {
+        vec![self.rate_index.get_code()]
+    }
+
+    fn get_schedule(&self) -> Result<&Schedule> {
+        Ok(&self.floating_legs)
+    }
+
+    fn get_calendar(&self) -> Result<&JointCalendar> {
+        Ok(&self.calendar)
+    }
+
+    fn get_strike(&self) -> Result<Real> {
+        Ok(self.strike)
+    }
+}
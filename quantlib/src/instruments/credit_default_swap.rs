@@ -0,0 +1,230 @@
+use crate::currency::Currency;
+use crate::definitions::Real;
+use crate::enums::ProtectionSide;
+use crate::instrument::InstrumentTrait;
+use crate::instruments::schedule::{self, Schedule};
+use crate::time::conventions::{BusinessDayConvention, DayCountConvention};
+use crate::time::jointcalendar::JointCalendar;
+//
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Single-name credit default swap on reference_entity.
+/// The protection buyer pays coupon_rate * frac on each period of the premium schedule
+/// (and the accrued premium on default), and receives (1 - recovery_rate) of the notional on default until the maturity.
+/// The premium schedule rolls on the CDS standard dates (20th of Mar, Jun, Sep, Dec).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreditDefaultSwap {
+    side: ProtectionSide,
+    coupon_rate: Real,
+    recovery_rate: Real,
+    reference_entity: String,
+    premium_legs: Schedule,
+    calendar: JointCalendar,
+    daycounter: DayCountConvention,
+    unit_notional: Real,
+    //
+    issue_date: OffsetDateTime,
+    effective_date: OffsetDateTime,
+    maturity: OffsetDateTime,
+    //
+    currency: Currency,
+    name: String,
+    code: String,
+}
+
+impl CreditDefaultSwap {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        side: ProtectionSide,
+        coupon_rate: Real,
+        recovery_rate: Real,
+        reference_entity: String,
+        premium_legs: Schedule,
+        calendar: JointCalendar,
+        daycounter: DayCountConvention,
+        unit_notional: Real,
+        issue_date: OffsetDateTime,
+        effective_date: OffsetDateTime,
+        maturity: OffsetDateTime,
+        currency: Currency,
+        name: String,
+        code: String,
+    ) -> Result<CreditDefaultSwap> {
+        if premium_legs.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has an empty schedule",
+                file!(),
+                line!(),
+                name,
+                code
+            ));
+        }
+        if !(0.0..1.0).contains(&recovery_rate) {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has recovery rate {} which is not in [0, 1)",
+                file!(),
+                line!(),
+                name,
+                code,
+                recovery_rate
+            ));
+        }
+
+        Ok(CreditDefaultSwap {
+            side,
+            coupon_rate,
+            recovery_rate,
+            reference_entity,
+            premium_legs,
+            calendar,
+            daycounter,
+            unit_notional,
+            issue_date,
+            effective_date,
+            maturity,
+            currency,
+            name,
+            code,
+        })
+    }
+
+    /// construct the CDS on the standard conventions:
+    /// quarterly premiums on the CDS standard dates adjusted by Following with Actual360,
+    /// where the first period starts on the last standard date on or before the issue (trade) date
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_from_conventions(
+        side: ProtectionSide,
+        coupon_rate: Real,
+        recovery_rate: Real,
+        reference_entity: String,
+        unit_notional: Real,
+        issue_date: OffsetDateTime,
+        maturity: OffsetDateTime,
+        calendar: JointCalendar,
+        currency: Currency,
+        name: String,
+        code: String,
+    ) -> Result<CreditDefaultSwap> {
+        let premium_legs = schedule::build_cds_schedule(
+            &issue_date,
+            &maturity,
+            &calendar,
+            &BusinessDayConvention::Following,
+        )
+        .with_context(|| {
+            anyhow!(
+                "({}:{}) Failed to build the schedule of {} ({})",
+                file!(),
+                line!(),
+                &name,
+                &code
+            )
+        })?;
+        let effective_date = *premium_legs[0].get_calc_start_date();
+
+        CreditDefaultSwap::new(
+            side,
+            coupon_rate,
+            recovery_rate,
+            reference_entity,
+            premium_legs,
+            calendar,
+            DayCountConvention::Actual360,
+            unit_notional,
+            issue_date,
+            effective_date,
+            maturity,
+            currency,
+            name,
+            code,
+        )
+    }
+
+    pub fn get_side(&self) -> ProtectionSide {
+        self.side
+    }
+
+    pub fn get_coupon_rate(&self) -> Real {
+        self.coupon_rate
+    }
+
+    pub fn get_recovery_rate(&self) -> Real {
+        self.recovery_rate
+    }
+
+    pub fn get_reference_entity(&self) -> &String {
+        &self.reference_entity
+    }
+
+    pub fn get_daycounter(&self) -> &DayCountConvention {
+        &self.daycounter
+    }
+
+    pub fn get_effective_date(&self) -> &OffsetDateTime {
+        &self.effective_date
+    }
+
+    /// the same CDS with the other coupon and recovery rates, e.g., for the pillars of the bootstrapping
+    pub fn with_coupon_and_recovery(
+        &self,
+        coupon_rate: Real,
+        recovery_rate: Real,
+    ) -> Result<CreditDefaultSwap> {
+        CreditDefaultSwap::new(
+            self.side,
+            coupon_rate,
+            recovery_rate,
+            self.reference_entity.clone(),
+            self.premium_legs.clone(),
+            self.calendar.clone(),
+            self.daycounter,
+            self.unit_notional,
+            self.issue_date,
+            self.effective_date,
+            self.maturity,
+            self.currency,
+            self.name.clone(),
+            self.code.clone(),
+        )
+    }
+}
+
+impl InstrumentTrait for CreditDefaultSwap {
+    fn get_type_name(&self) -> &'static str {
+        "CreditDefaultSwap"
+    }
+
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    fn get_currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    fn get_unit_notional(&self) -> Real {
+        self.unit_notional
+    }
+
+    fn get_issue_date(&self) -> Result<&OffsetDateTime> {
+        Ok(&self.issue_date)
+    }
+
+    fn get_maturity(&self) -> Option<&OffsetDateTime> {
+        Some(&self.maturity)
+    }
+
+    fn get_calendar(&self) -> Result<&JointCalendar> {
+        Ok(&self.calendar)
+    }
+
+    fn get_schedule(&self) -> Result<&Schedule> {
+        Ok(&self.premium_legs)
+    }
+}
@@ -0,0 +1,193 @@
+use crate::currency::{Currency, FxCode};
+use crate::definitions::Real;
+use crate::enums::FxSettlementType;
+use crate::instrument::InstrumentTrait;
+//
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Outright FX forward on fx_code = (base, quote), e.g., USD/KRW.
+/// The holder buys unit_notional of the base currency at forward_rate (quote per base) on the maturity.
+/// Deliverable forwards exchange both currencies on the maturity.
+/// Non-deliverable forwards pay unit_notional * (fixing - forward_rate) in the quote currency,
+/// or divided by the fixing in the base currency, on the maturity,
+/// where the fixing is the close of fixing_source on the fixing date.
+/// The value is in the quote currency.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FxForward {
+    fx_code: FxCode,
+    forward_rate: Real,
+    settlement_type: FxSettlementType,
+    settlement_currency: Currency,
+    fixing_date: OffsetDateTime,
+    fixing_source: Option<String>,
+    issue_date: OffsetDateTime,
+    maturity: OffsetDateTime,
+    unit_notional: Real,
+    currency: Currency,
+    name: String,
+    code: String,
+}
+
+impl FxForward {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        fx_code: FxCode,
+        forward_rate: Real,
+        settlement_type: FxSettlementType,
+        settlement_currency: Currency,
+        fixing_date: OffsetDateTime,
+        fixing_source: Option<String>,
+        issue_date: OffsetDateTime,
+        maturity: OffsetDateTime,
+        unit_notional: Real,
+        name: String,
+        code: String,
+    ) -> Result<FxForward> {
+        let base_currency = *fx_code.get_currency1();
+        let quote_currency = *fx_code.get_currency2();
+        if settlement_currency != base_currency && settlement_currency != quote_currency {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) settles in {:?} which is not in {}",
+                file!(),
+                line!(),
+                name,
+                code,
+                settlement_currency,
+                fx_code
+            ));
+        }
+        if fixing_date > maturity {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has the fixing date {} after the maturity {}",
+                file!(),
+                line!(),
+                name,
+                code,
+                fixing_date,
+                maturity
+            ));
+        }
+        if settlement_type == FxSettlementType::NonDeliverable && fixing_source.is_none() {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) is non-deliverable but has no fixing source",
+                file!(),
+                line!(),
+                name,
+                code
+            ));
+        }
+
+        Ok(FxForward {
+            fx_code,
+            forward_rate,
+            settlement_type,
+            settlement_currency,
+            fixing_date,
+            fixing_source,
+            issue_date,
+            maturity,
+            unit_notional,
+            currency: quote_currency,
+            name,
+            code,
+        })
+    }
+
+    /// deliverable forward which exchanges the currencies on the maturity
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_deliverable(
+        fx_code: FxCode,
+        forward_rate: Real,
+        issue_date: OffsetDateTime,
+        maturity: OffsetDateTime,
+        unit_notional: Real,
+        name: String,
+        code: String,
+    ) -> Result<FxForward> {
+        FxForward::new(
+            fx_code,
+            forward_rate,
+            FxSettlementType::Deliverable,
+            *fx_code.get_currency2(),
+            maturity,
+            None,
+            issue_date,
+            maturity,
+            unit_notional,
+            name,
+            code,
+        )
+    }
+
+    pub fn get_fx_code(&self) -> &FxCode {
+        &self.fx_code
+    }
+
+    pub fn get_forward_rate(&self) -> Real {
+        self.forward_rate
+    }
+
+    pub fn get_settlement_type(&self) -> FxSettlementType {
+        self.settlement_type
+    }
+
+    pub fn get_settlement_currency(&self) -> &Currency {
+        &self.settlement_currency
+    }
+
+    pub fn get_fixing_date(&self) -> &OffsetDateTime {
+        &self.fixing_date
+    }
+
+    pub fn get_fixing_source(&self) -> Option<&String> {
+        self.fixing_source.as_ref()
+    }
+}
+
+impl InstrumentTrait for FxForward {
+    fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    fn get_type_name(&self) -> &'static str {
+        "FxForward"
+    }
+
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    fn get_underlying_currency(&self) -> Result<&Currency> {
+        Ok(self.fx_code.get_currency1())
+    }
+
+    fn get_issue_date(&self) -> Result<&OffsetDateTime> {
+        Ok(&self.issue_date)
+    }
+
+    fn get_maturity(&self) -> Option<&OffsetDateTime> {
+        Some(&self.maturity)
+    }
+
+    fn get_unit_notional(&self) -> Real {
+        self.unit_notional
+    }
+
+    fn get_strike(&self) -> Result<Real> {
+        Ok(self.forward_rate)
+    }
+
+    fn get_all_fxcodes_for_pricing(&self) -> Vec<FxCode> {
+        vec![self.fx_code]
+    }
+
+    fn get_underlying_fxcodes(&self) -> Vec<&FxCode> {
+        vec![&self.fx_code]
+    }
+}
@@ -0,0 +1,113 @@
+use crate::currency::{Currency, FxCode};
+use crate::definitions::Real;
+use crate::enums::{OptionExerciseType, OptionType};
+use crate::instrument::InstrumentTrait;
+//
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// European option on fx_code = (base, quote), e.g., USD/KRW.
+/// The call pays unit_notional * max(fx - strike, 0) in the quote currency on the maturity,
+/// i.e., the right to buy unit_notional of the base currency at the strike (quote per base).
+/// The value is in the quote currency.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FxOption {
+    fx_code: FxCode,
+    strike: Real,
+    option_type: OptionType,
+    issue_date: OffsetDateTime,
+    maturity: OffsetDateTime,
+    unit_notional: Real,
+    currency: Currency,
+    name: String,
+    code: String,
+}
+
+impl FxOption {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        fx_code: FxCode,
+        strike: Real,
+        option_type: OptionType,
+        issue_date: OffsetDateTime,
+        maturity: OffsetDateTime,
+        unit_notional: Real,
+        name: String,
+        code: String,
+    ) -> FxOption {
+        FxOption {
+            fx_code,
+            strike,
+            option_type,
+            issue_date,
+            maturity,
+            unit_notional,
+            currency: *fx_code.get_currency2(),
+            name,
+            code,
+        }
+    }
+
+    pub fn get_fx_code(&self) -> &FxCode {
+        &self.fx_code
+    }
+}
+
+impl InstrumentTrait for FxOption {
+    fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    fn get_type_name(&self) -> &'static str {
+        "FxOption"
+    }
+
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    fn get_underlying_currency(&self) -> Result<&Currency> {
+        Ok(self.fx_code.get_currency1())
+    }
+
+    fn get_issue_date(&self) -> Result<&OffsetDateTime> {
+        Ok(&self.issue_date)
+    }
+
+    fn get_maturity(&self) -> Option<&OffsetDateTime> {
+        Some(&self.maturity)
+    }
+
+    fn get_unit_notional(&self) -> Real {
+        self.unit_notional
+    }
+
+    fn get_strike(&self) -> Result<Real> {
+        Ok(self.strike)
+    }
+
+    fn get_option_type(&self) -> Result<OptionType> {
+        Ok(self.option_type)
+    }
+
+    fn get_option_exercise_type(&self) -> Result<OptionExerciseType> {
+        Ok(OptionExerciseType::European)
+    }
+
+    fn get_all_fxcodes_for_pricing(&self) -> Vec<FxCode> {
+        vec![self.fx_code]
+    }
+
+    fn get_underlying_fxcodes(&self) -> Vec<&FxCode> {
+        vec![&self.fx_code]
+    }
+
+    fn get_fxcodes_requiring_volatility(&self) -> Vec<&FxCode> {
+        vec![&self.fx_code]
+    }
+}
@@ -45,6 +45,43 @@ impl KtbfVirtualBond {
         res += 1.0 / (1.0 + effective_yield).powi(coupon_payment_number);
         res * self.unit_notional
     }
+
+    /// derivative of npv with respect to bond_yield
+    pub fn npv_derivative(&self, bond_yield: Real) -> Real {
+        let coupon_payment_number = self.year * self.frequency as Integer;
+        let calc_freq = self.frequency.as_real();
+        let effective_yield = bond_yield / calc_freq;
+        let effective_coupon = self.coupon_rate / calc_freq;
+        let mut res = 0.0;
+        for i in 1..=coupon_payment_number {
+            res -= i as Real * effective_coupon / (1.0 + effective_yield).powi(i + 1);
+        }
+        res -=
+            coupon_payment_number as Real / (1.0 + effective_yield).powi(coupon_payment_number + 1);
+        res * self.unit_notional / calc_freq
+    }
+
+    /// the yield at which npv is the given price, found by Newton's method
+    pub fn implied_yield(&self, price: Real, init_guess: Option<Real>) -> Result<Real> {
+        let mut bond_yield = init_guess.unwrap_or(self.coupon_rate);
+        for _ in 0..100 {
+            let step = (self.npv(bond_yield) - price) / self.npv_derivative(bond_yield);
+            bond_yield -= step;
+            if step.abs() < 1.0e-6 {
+                return Ok(bond_yield);
+            }
+        }
+        Err(anyhow!(
+            "({}:{}) failed to find the yield of the KTBF virtual bond at price {}",
+            file!(),
+            line!(),
+            price
+        ))
+    }
+
+    pub fn get_year(&self) -> Integer {
+        self.year
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -117,6 +154,10 @@ impl KTBF {
     pub fn get_underlying_bonds(&self) -> &Vec<Bond> {
         &self.underlying_bonds
     }
+
+    pub fn get_virtual_bond(&self) -> &KtbfVirtualBond {
+        &self.virtual_bond
+    }
 }
 
 impl InstrumentTrait for KTBF {
@@ -0,0 +1,166 @@
+use crate::currency::{Currency, FxCode};
+use crate::definitions::Real;
+use crate::enums::{LookbackType, OptionDailySettlementType, OptionExerciseType, OptionType};
+use crate::instrument::InstrumentTrait;
+//
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// European lookback option on a single underlying.
+/// The extremes are observed on the daily closes from the issue date to the maturity,
+/// and the closes before the evaluation date are taken from the past close prices of the underlying.
+/// The strike is given only for the fixed-strike lookbacks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LookbackOption {
+    lookback_type: LookbackType,
+    strike: Option<Real>,
+    unit_notional: Real,
+    issue_date: OffsetDateTime,
+    last_trade_date: OffsetDateTime,
+    maturity: OffsetDateTime,
+    settlement_date: OffsetDateTime,
+    underlying_codes: Vec<String>,
+    underlying_currency: Currency,
+    currency: Currency,
+    quanto_fx_code: Option<FxCode>,
+    option_type: OptionType,
+    daily_settlement_type: OptionDailySettlementType,
+    name: String,
+    code: String,
+}
+
+impl LookbackOption {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        lookback_type: LookbackType,
+        strike: Option<Real>,
+        unit_notional: Real,
+        issue_date: OffsetDateTime,
+        last_trade_date: OffsetDateTime,
+        maturity: OffsetDateTime,
+        settlement_date: OffsetDateTime,
+        underlying_codes: Vec<String>,
+        underlying_currency: Currency,
+        currency: Currency,
+        option_type: OptionType,
+        option_daily_settlement_type: OptionDailySettlementType,
+        name: String,
+        code: String,
+    ) -> Result<LookbackOption> {
+        if (lookback_type == LookbackType::FixedStrike) != strike.is_some() {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) is {:?} lookback but the strike is {:?}",
+                file!(),
+                line!(),
+                name,
+                code,
+                lookback_type,
+                strike
+            ));
+        }
+        let quanto_fx_code = if currency != underlying_currency {
+            Some(FxCode::new(underlying_currency, currency))
+        } else {
+            None
+        };
+
+        Ok(LookbackOption {
+            lookback_type,
+            strike,
+            unit_notional,
+            issue_date,
+            last_trade_date,
+            maturity,
+            settlement_date,
+            underlying_codes,
+            underlying_currency,
+            currency,
+            quanto_fx_code,
+            option_type,
+            daily_settlement_type: option_daily_settlement_type,
+            name,
+            code,
+        })
+    }
+
+    pub fn get_lookback_type(&self) -> LookbackType {
+        self.lookback_type
+    }
+}
+
+impl InstrumentTrait for LookbackOption {
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_type_name(&self) -> &'static str {
+        match self.option_type {
+            OptionType::Call => "LookbackCall",
+            OptionType::Put => "LookbackPut",
+        }
+    }
+
+    fn get_unit_notional(&self) -> Real {
+        self.unit_notional
+    }
+
+    fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    fn get_currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    fn get_underlying_currency(&self) -> Result<&Currency> {
+        Ok(&self.underlying_currency)
+    }
+
+    fn get_issue_date(&self) -> Result<&OffsetDateTime> {
+        Ok(&self.issue_date)
+    }
+
+    fn get_maturity(&self) -> Option<&OffsetDateTime> {
+        Some(&self.maturity)
+    }
+
+    fn get_underlying_codes(&self) -> Vec<&String> {
+        vec![&self.underlying_codes[0]]
+    }
+
+    fn get_option_type(&self) -> Result<OptionType> {
+        Ok(self.option_type)
+    }
+
+    fn get_option_daily_settlement_type(&self) -> Result<OptionDailySettlementType> {
+        Ok(self.daily_settlement_type)
+    }
+
+    fn get_option_exercise_type(&self) -> Result<OptionExerciseType> {
+        Ok(OptionExerciseType::European)
+    }
+
+    fn get_strike(&self) -> Result<Real> {
+        self.strike.ok_or_else(|| {
+            anyhow!(
+                "({}:{}) {} ({}) is a floating-strike lookback which has no strike",
+                file!(),
+                line!(),
+                self.name,
+                self.code
+            )
+        })
+    }
+
+    fn get_quanto_fxcode_und_pair(&self) -> Vec<(&String, &FxCode)> {
+        match &self.quanto_fx_code {
+            Some(fx_code) => vec![(&self.underlying_codes[0], fx_code)],
+            None => vec![],
+        }
+    }
+
+    fn get_underlying_codes_requiring_volatility(&self) -> Vec<&String> {
+        vec![&self.underlying_codes[0]]
+    }
+}
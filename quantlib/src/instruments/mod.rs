@@ -1,11 +1,20 @@
+pub mod asian_option;
+pub mod autocallable;
+pub mod barrier_option;
 pub mod bond;
 pub mod bond_futures;
+pub mod cap_floor;
 pub mod cash;
+pub mod credit_default_swap;
 pub mod futures;
+pub mod fx_forward;
 pub mod fx_futures;
+pub mod fx_option;
 pub mod instrument_info;
 pub mod ktbf;
+pub mod lookback_option;
 pub mod plain_swap;
 pub mod schedule;
 pub mod stock;
+pub mod swaption;
 pub mod vanilla_option;
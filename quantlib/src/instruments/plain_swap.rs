@@ -445,6 +445,10 @@ impl PlainSwap {
             code,
         })
     }
+
+    pub fn get_fixed_rate(&self) -> Option<Real> {
+        self.fixed_rate
+    }
 }
 
 impl InstrumentTrait for PlainSwap {
@@ -230,6 +230,103 @@ pub fn build_schedule(
     Ok(schedule)
 }
 
+/// CDS standard dates (IMM dates of CDS) are the 20th of March, June, September and December.
+/// This is the first standard date strictly after date.
+pub fn next_cds_date(date: &OffsetDateTime) -> OffsetDateTime {
+    let mut res = previous_cds_date(date);
+    while res <= *date {
+        res = add_period(&res, "3M");
+    }
+    res
+}
+
+/// the last CDS standard date on or before date
+pub fn previous_cds_date(date: &OffsetDateTime) -> OffsetDateTime {
+    let month = date.month() as u8;
+    let mut cds_month = month - (month % 3);
+    let mut year = date.year();
+    if cds_month == month && date.day() < 20 {
+        cds_month -= 3;
+    }
+    if cds_month == 0 {
+        cds_month = 12;
+        year -= 1;
+    }
+    // the 20th exists in every month, so the replacement does not fail
+    date.replace_date(
+        time::Date::from_calendar_date(year, time::Month::try_from(cds_month).unwrap(), 20)
+            .unwrap(),
+    )
+}
+
+/// Maturity of the standard CDS traded at trade_date with tenor (e.g., "5Y").
+/// The maturities roll semiannually on 20 March and 20 September (ISDA 2015):
+/// the trades from 20 March to 19 September mature the tenor after 20 June,
+/// and the trades from 20 September to 19 March the tenor after 20 December,
+/// e.g., 5Y traded on 2024-03-13 matures on 2028-12-20.
+pub fn cds_standard_maturity(trade_date: &OffsetDateTime, tenor: &str) -> OffsetDateTime {
+    let on_or_after_20th = |month: time::Month| {
+        trade_date.month() as u8 > month as u8
+            || (trade_date.month() == month && trade_date.day() >= 20)
+    };
+    let (year, month) = if on_or_after_20th(time::Month::September) {
+        (trade_date.year(), time::Month::December)
+    } else if on_or_after_20th(time::Month::March) {
+        (trade_date.year(), time::Month::June)
+    } else {
+        (trade_date.year() - 1, time::Month::December)
+    };
+    let roll_date =
+        trade_date.replace_date(time::Date::from_calendar_date(year, month, 20).unwrap());
+    add_period(&roll_date, tenor)
+}
+
+/// make a quarterly premium schedule of a CDS on the CDS standard dates.
+/// The first calc_start_date is the last standard date on or before the effective date,
+/// and the last calc_end_date is the maturity (unadjusted).
+/// The other dates are adjusted by the BusinessDayConvention,
+/// and the payment dates are the adjusted calc_end_dates.
+pub fn build_cds_schedule(
+    effective_date: &OffsetDateTime,
+    maturity: &OffsetDateTime,
+    calendar: &JointCalendar,
+    conv: &BusinessDayConvention,
+) -> Result<Schedule> {
+    let mut raw_dates = vec![previous_cds_date(effective_date)];
+    while raw_dates.last().unwrap() < maturity {
+        let next = next_cds_date(raw_dates.last().unwrap());
+        raw_dates.push(next.min(*maturity));
+    }
+    if raw_dates.len() < 2 {
+        return Err(anyhow!(
+            "({}:{}) maturity {:?} is not after the effective date {:?} in build_cds_schedule",
+            file!(),
+            line!(),
+            maturity,
+            effective_date
+        ));
+    }
+
+    let mut base_schedule_vec: Vec<BaseSchedule> = vec![];
+    let last = raw_dates.len() - 2;
+    for i in 0..=last {
+        let calc_start_date = calendar.adjust(&raw_dates[i], conv)?;
+        let calc_end_date = match i == last {
+            true => raw_dates[i + 1],
+            false => calendar.adjust(&raw_dates[i + 1], conv)?,
+        };
+        let payment_date = calendar.adjust(&raw_dates[i + 1], conv)?;
+        base_schedule_vec.push(BaseSchedule::new(
+            calc_start_date,
+            calc_start_date,
+            calc_end_date,
+            payment_date,
+            None,
+        ));
+    }
+    Ok(Schedule::new(base_schedule_vec))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -425,4 +522,48 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_cds_standard_maturity() {
+        for (trade_date, tenor, maturity) in [
+            (
+                datetime!(2024-03-13 16:30:00 +09:00),
+                "5Y",
+                date!(2028 - 12 - 20),
+            ),
+            (
+                datetime!(2024-03-20 16:30:00 +09:00),
+                "5Y",
+                date!(2029 - 06 - 20),
+            ),
+            (
+                datetime!(2024-09-19 16:30:00 +09:00),
+                "5Y",
+                date!(2029 - 06 - 20),
+            ),
+            (
+                datetime!(2024-09-20 16:30:00 +09:00),
+                "5Y",
+                date!(2029 - 12 - 20),
+            ),
+            (
+                datetime!(2024-12-31 16:30:00 +09:00),
+                "1Y",
+                date!(2025 - 12 - 20),
+            ),
+            (
+                datetime!(2024-03-13 16:30:00 +09:00),
+                "6M",
+                date!(2024 - 06 - 20),
+            ),
+        ] {
+            assert_eq!(
+                cds_standard_maturity(&trade_date, tenor).date(),
+                maturity,
+                "{} traded on {}",
+                tenor,
+                trade_date
+            );
+        }
+    }
 }
@@ -0,0 +1,297 @@
+use crate::currency::Currency;
+use crate::definitions::Real;
+use crate::enums::SwaptionType;
+use crate::instrument::InstrumentTrait;
+use crate::instruments::schedule::{self, Schedule};
+use crate::parameters::rate_index::RateIndex;
+use crate::time::conventions::{BusinessDayConvention, DayCountConvention, PaymentFrequency};
+use crate::time::jointcalendar::JointCalendar;
+//
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// European swaption physically settled into the IRS starting at swap_effective_date,
+/// whose fixed leg pays (Payer) or receives (Receiver) the fixed rate
+/// against the floating leg on the RateIndex.
+/// The instrument matures at the expiry, after which the swap is booked as a PlainSwap.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Swaption {
+    swaption_type: SwaptionType,
+    fixed_rate: Real,
+    expiry: OffsetDateTime,
+    fixed_legs: Schedule,
+    floating_legs: Schedule,
+    rate_index: RateIndex,
+    floating_compound_tenor: Option<String>,
+    calendar: JointCalendar,
+    fixed_daycounter: DayCountConvention,
+    floating_daycounter: DayCountConvention,
+    fixing_gap_days: i64,
+    unit_notional: Real,
+    //
+    issue_date: OffsetDateTime,
+    swap_effective_date: OffsetDateTime,
+    swap_maturity: OffsetDateTime,
+    //
+    currency: Currency,
+    name: String,
+    code: String,
+}
+
+impl Swaption {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        swaption_type: SwaptionType,
+        fixed_rate: Real,
+        expiry: OffsetDateTime,
+        fixed_legs: Schedule,
+        floating_legs: Schedule,
+        rate_index: RateIndex,
+        floating_compound_tenor: Option<String>,
+        calendar: JointCalendar,
+        fixed_daycounter: DayCountConvention,
+        floating_daycounter: DayCountConvention,
+        fixing_gap_days: i64,
+        unit_notional: Real,
+        issue_date: OffsetDateTime,
+        swap_effective_date: OffsetDateTime,
+        swap_maturity: OffsetDateTime,
+        currency: Currency,
+        name: String,
+        code: String,
+    ) -> Result<Swaption> {
+        if fixed_legs.is_empty() || floating_legs.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has an empty schedule on the underlying swap",
+                file!(),
+                line!(),
+                name,
+                code
+            ));
+        }
+        if expiry.date() > swap_effective_date.date() {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) expires at {} after the effective date of the swap {}",
+                file!(),
+                line!(),
+                name,
+                code,
+                expiry,
+                swap_effective_date
+            ));
+        }
+        if rate_index.get_currency() != &currency {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) is in {:?} but the rate index {} is in {:?}",
+                file!(),
+                line!(),
+                name,
+                code,
+                currency,
+                rate_index.get_code(),
+                rate_index.get_currency()
+            ));
+        }
+
+        Ok(Swaption {
+            swaption_type,
+            fixed_rate,
+            expiry,
+            fixed_legs,
+            floating_legs,
+            rate_index,
+            floating_compound_tenor,
+            calendar,
+            fixed_daycounter,
+            floating_daycounter,
+            fixing_gap_days,
+            unit_notional,
+            issue_date,
+            swap_effective_date,
+            swap_maturity,
+            currency,
+            name,
+            code,
+        })
+    }
+
+    /// construct the underlying swap legs by the conventions as in PlainSwap::new_from_conventions
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_from_conventions(
+        swaption_type: SwaptionType,
+        fixed_rate: Real,
+        expiry: OffsetDateTime,
+        rate_index: RateIndex,
+        floating_compound_tenor: Option<String>,
+        unit_notional: Real,
+        issue_date: OffsetDateTime,
+        swap_effective_date: OffsetDateTime,
+        swap_maturity: OffsetDateTime,
+        //
+        forward_generation: bool,
+        fixed_daycounter: DayCountConvention,
+        floating_daycounter: DayCountConvention,
+        fixed_busi_convention: BusinessDayConvention,
+        floating_busi_convention: BusinessDayConvention,
+        fixed_frequency: PaymentFrequency,
+        floating_frequency: PaymentFrequency,
+        fixing_gap_days: i64,
+        payment_gap_days: i64,
+        //
+        calendar: JointCalendar,
+        currency: Currency,
+        name: String,
+        code: String,
+    ) -> Result<Swaption> {
+        let fixed_legs = schedule::build_schedule(
+            forward_generation,
+            &swap_effective_date,
+            &swap_maturity,
+            &calendar,
+            &fixed_busi_convention,
+            &fixed_frequency,
+            fixing_gap_days,
+            payment_gap_days,
+        )
+        .with_context(|| {
+            anyhow!(
+                "({}:{}) Failed to build fixed legs in swaption: {}({})",
+                file!(),
+                line!(),
+                &name,
+                &code
+            )
+        })?;
+
+        let floating_legs = schedule::build_schedule(
+            forward_generation,
+            &swap_effective_date,
+            &swap_maturity,
+            &calendar,
+            &floating_busi_convention,
+            &floating_frequency,
+            fixing_gap_days,
+            payment_gap_days,
+        )
+        .with_context(|| {
+            anyhow!(
+                "({}:{}) Failed to build floating legs in swaption: {}({})",
+                file!(),
+                line!(),
+                &name,
+                &code
+            )
+        })?;
+
+        Swaption::new(
+            swaption_type,
+            fixed_rate,
+            expiry,
+            fixed_legs,
+            floating_legs,
+            rate_index,
+            floating_compound_tenor,
+            calendar,
+            fixed_daycounter,
+            floating_daycounter,
+            fixing_gap_days,
+            unit_notional,
+            issue_date,
+            swap_effective_date,
+            swap_maturity,
+            currency,
+            name,
+            code,
+        )
+    }
+
+    pub fn get_swaption_type(&self) -> SwaptionType {
+        self.swaption_type
+    }
+
+    pub fn get_fixed_rate(&self) -> Real {
+        self.fixed_rate
+    }
+
+    pub fn get_fixed_legs(&self) -> &Schedule {
+        &self.fixed_legs
+    }
+
+    pub fn get_floating_legs(&self) -> &Schedule {
+        &self.floating_legs
+    }
+
+    pub fn get_floating_compound_tenor(&self) -> Option<&String> {
+        self.floating_compound_tenor.as_ref()
+    }
+
+    pub fn get_fixed_daycounter(&self) -> &DayCountConvention {
+        &self.fixed_daycounter
+    }
+
+    pub fn get_floating_daycounter(&self) -> &DayCountConvention {
+        &self.floating_daycounter
+    }
+
+    pub fn get_fixing_gap_days(&self) -> i64 {
+        self.fixing_gap_days
+    }
+
+    pub fn get_swap_effective_date(&self) -> &OffsetDateTime {
+        &self.swap_effective_date
+    }
+
+    pub fn get_swap_maturity(&self) -> &OffsetDateTime {
+        &self.swap_maturity
+    }
+}
+
+impl InstrumentTrait for Swaption {
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    fn get_currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    fn get_unit_notional(&self) -> Real {
+        self.unit_notional
+    }
+
+    fn get_type_name(&self) -> &'static str {
+        match self.swaption_type {
+            SwaptionType::Payer => "PayerSwaption",
+            SwaptionType::Receiver => "ReceiverSwaption",
+        }
+    }
+
+    fn get_maturity(&self) -> Option<&OffsetDateTime> {
+        Some(&self.expiry)
+    }
+
+    fn get_issue_date(&self) -> Result<&OffsetDateTime> {
+        Ok(&self.issue_date)
+    }
+
+    fn get_rate_index(&self) -> Result<Option<&RateIndex>> {
+        Ok(Some(&self.rate_index))
+    }
+
+    fn get_rate_index_codes_requiring_volatility(&self) -> Vec<&String> {
+        vec![self.rate_index.get_code()]
+    }
+
+    fn get_calendar(&self) -> Result<&JointCalendar> {
+        Ok(&self.calendar)
+    }
+
+    fn get_strike(&self) -> Result<Real> {
+        Ok(self.fixed_rate)
+    }
+}
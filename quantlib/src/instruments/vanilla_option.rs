@@ -21,6 +21,8 @@ pub struct VanillaOption {
     quanto_fx_code: Option<FxCode>,
     option_type: OptionType,
     exercise_type: OptionExerciseType,
+    #[serde(default)]
+    exercise_dates: Vec<OffsetDateTime>,
     daily_settlement_type: OptionDailySettlementType,
     name: String,
     code: String,
@@ -40,6 +42,7 @@ impl Default for VanillaOption {
             currency: Currency::KRW,
             quanto_fx_code: None,
             exercise_type: OptionExerciseType::European,
+            exercise_dates: vec![],
             option_type: OptionType::Call,
             daily_settlement_type: OptionDailySettlementType::NotSettled,
             name: String::from(""),
@@ -85,12 +88,19 @@ impl VanillaOption {
             currency,
             option_type,
             exercise_type,
+            exercise_dates: vec![],
             daily_settlement_type: option_daily_settlement_type,
             name,
             code,
         }
     }
 
+    /// exercise dates for Bermudan options. The maturity is always an exercise date.
+    pub fn with_exercise_dates(mut self, exercise_dates: Vec<OffsetDateTime>) -> VanillaOption {
+        self.exercise_dates = exercise_dates;
+        self
+    }
+
     pub fn get_strike(&self) -> Real {
         self.strike
     }
@@ -139,6 +149,14 @@ impl InstrumentTrait for VanillaOption {
         Ok(self.daily_settlement_type)
     }
 
+    fn get_option_exercise_type(&self) -> Result<OptionExerciseType> {
+        Ok(self.exercise_type)
+    }
+
+    fn get_exercise_dates(&self) -> Result<&Vec<OffsetDateTime>> {
+        Ok(&self.exercise_dates)
+    }
+
     fn get_strike(&self) -> Result<Real> {
         Ok(self.strike)
     }
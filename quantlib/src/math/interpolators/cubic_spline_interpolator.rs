@@ -0,0 +1,479 @@
+use crate::definitions::Real;
+use crate::math::interpolator::{ExtraPolationType, InterpolatorReal1D};
+use crate::math::tridiagonal_solver::solve_tridiagonal;
+use crate::utils::find_index_ndarray::binary_search_index_ndarray;
+use anyhow::{anyhow, Result};
+use ndarray::Array1;
+
+/// Piecewise cubic Hermite polynomial given by the values and the first derivatives at the nodes.
+/// All the cubic interpolators in this module differ only in the node derivatives.
+/// Outside of the domain, Flat extrapolation keeps the end value (zero derivatives),
+/// Linear extrapolation follows the tangent at the end node, and None returns an error.
+#[derive(Debug, Clone)]
+struct HermiteCubic {
+    domain: Array1<Real>,
+    value: Array1<Real>,
+    slopes: Array1<Real>,
+    extrapolation_type: ExtraPolationType,
+}
+
+impl HermiteCubic {
+    fn check_input(domain: &Array1<Real>, value: &Array1<Real>) -> Result<()> {
+        if domain.len() != value.len() {
+            return Err(anyhow!(
+                "({}:{}) domain and value must have the same length\ndomain: {:?}\nvalue: {:?}",
+                file!(),
+                line!(),
+                domain,
+                value
+            ));
+        }
+        if domain.len() < 2 {
+            return Err(anyhow!(
+                "({}:{}) cubic interpolation needs at least two points: {:?}",
+                file!(),
+                line!(),
+                domain
+            ));
+        }
+        if domain.windows(2).into_iter().any(|w| w[1] <= w[0]) {
+            return Err(anyhow!(
+                "({}:{}) domain must be strictly increasing: \n{:?}",
+                file!(),
+                line!(),
+                domain
+            ));
+        }
+        Ok(())
+    }
+
+    /// slopes of the chords between the nodes
+    fn secants(domain: &Array1<Real>, value: &Array1<Real>) -> Vec<Real> {
+        (0..domain.len() - 1)
+            .map(|i| (value[i + 1] - value[i]) / (domain[i + 1] - domain[i]))
+            .collect()
+    }
+
+    /// returns (value, first derivative, second derivative)
+    fn evaluate(&self, x: Real) -> Result<(Real, Real, Real)> {
+        let n = self.domain.len();
+        let (x0, x1) = (self.domain[0], self.domain[n - 1]);
+        if x < x0 || x > x1 {
+            let (end, i) = if x < x0 { (x0, 0) } else { (x1, n - 1) };
+            return match self.extrapolation_type {
+                ExtraPolationType::Flat => Ok((self.value[i], 0.0, 0.0)),
+                ExtraPolationType::Linear => Ok((
+                    self.value[i] + self.slopes[i] * (x - end),
+                    self.slopes[i],
+                    0.0,
+                )),
+                ExtraPolationType::None => Err(anyhow!(
+                    "({}:{}) x (= {}) is out of range [{}, {}]",
+                    file!(),
+                    line!(),
+                    x,
+                    x0,
+                    x1
+                )),
+            };
+        }
+
+        let i = binary_search_index_ndarray(&self.domain, x).min(n - 2);
+        let h = self.domain[i + 1] - self.domain[i];
+        let t = (x - self.domain[i]) / h;
+        let (y0, y1) = (self.value[i], self.value[i + 1]);
+        let (d0, d1) = (self.slopes[i] * h, self.slopes[i + 1] * h);
+        let (t2, t3) = (t * t, t * t * t);
+
+        let value = (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+            + (t3 - 2.0 * t2 + t) * d0
+            + (-2.0 * t3 + 3.0 * t2) * y1
+            + (t3 - t2) * d1;
+        let first = ((6.0 * t2 - 6.0 * t) * (y0 - y1)
+            + (3.0 * t2 - 4.0 * t + 1.0) * d0
+            + (3.0 * t2 - 2.0 * t) * d1)
+            / h;
+        let second =
+            ((12.0 * t - 6.0) * (y0 - y1) + (6.0 * t - 4.0) * d0 + (6.0 * t - 2.0) * d1) / (h * h);
+        Ok((value, first, second))
+    }
+}
+
+/// Cubic spline which is twice continuously differentiable.
+/// The natural spline has zero second derivatives at both ends,
+/// and the clamped spline has the given first derivatives at both ends.
+#[derive(Debug, Clone)]
+pub struct CubicSplineInterpolator1D {
+    hermite: HermiteCubic,
+}
+
+impl CubicSplineInterpolator1D {
+    /// natural cubic spline
+    pub fn new(
+        domain: Array1<Real>,
+        value: Array1<Real>,
+        extrapolation_type: ExtraPolationType,
+    ) -> Result<CubicSplineInterpolator1D> {
+        CubicSplineInterpolator1D::build(domain, value, None, extrapolation_type)
+    }
+
+    /// cubic spline whose first derivatives at both ends are left_slope and right_slope
+    pub fn new_clamped(
+        domain: Array1<Real>,
+        value: Array1<Real>,
+        left_slope: Real,
+        right_slope: Real,
+        extrapolation_type: ExtraPolationType,
+    ) -> Result<CubicSplineInterpolator1D> {
+        CubicSplineInterpolator1D::build(
+            domain,
+            value,
+            Some((left_slope, right_slope)),
+            extrapolation_type,
+        )
+    }
+
+    fn build(
+        domain: Array1<Real>,
+        value: Array1<Real>,
+        clamped_slopes: Option<(Real, Real)>,
+        extrapolation_type: ExtraPolationType,
+    ) -> Result<CubicSplineInterpolator1D> {
+        HermiteCubic::check_input(&domain, &value)?;
+        let n = domain.len();
+        let h: Vec<Real> = (0..n - 1).map(|i| domain[i + 1] - domain[i]).collect();
+        let secants = HermiteCubic::secants(&domain, &value);
+
+        // solve the second derivatives M from
+        // h[i-1] M[i-1] + 2 (h[i-1] + h[i]) M[i] + h[i] M[i+1] = 6 (secants[i] - secants[i-1])
+        let mut lower = vec![0.0; n];
+        let mut diag = vec![1.0; n];
+        let mut upper = vec![0.0; n];
+        let mut rhs = vec![0.0; n];
+        for i in 1..n - 1 {
+            lower[i] = h[i - 1];
+            diag[i] = 2.0 * (h[i - 1] + h[i]);
+            upper[i] = h[i];
+            rhs[i] = 6.0 * (secants[i] - secants[i - 1]);
+        }
+        if let Some((left_slope, right_slope)) = clamped_slopes {
+            diag[0] = 2.0 * h[0];
+            upper[0] = h[0];
+            rhs[0] = 6.0 * (secants[0] - left_slope);
+            lower[n - 1] = h[n - 2];
+            diag[n - 1] = 2.0 * h[n - 2];
+            rhs[n - 1] = 6.0 * (right_slope - secants[n - 2]);
+        }
+        let m = solve_tridiagonal(&lower, &diag, &upper, &rhs)?;
+
+        let mut slopes = Array1::zeros(n);
+        for i in 0..n - 1 {
+            slopes[i] = secants[i] - h[i] * (2.0 * m[i] + m[i + 1]) / 6.0;
+        }
+        slopes[n - 1] = secants[n - 2] + h[n - 2] * (m[n - 2] + 2.0 * m[n - 1]) / 6.0;
+
+        Ok(CubicSplineInterpolator1D {
+            hermite: HermiteCubic {
+                domain,
+                value,
+                slopes,
+                extrapolation_type,
+            },
+        })
+    }
+
+    pub fn derivative(&self, x: Real) -> Result<Real> {
+        Ok(self.hermite.evaluate(x)?.1)
+    }
+
+    pub fn second_derivative(&self, x: Real) -> Result<Real> {
+        Ok(self.hermite.evaluate(x)?.2)
+    }
+}
+
+impl InterpolatorReal1D for CubicSplineInterpolator1D {
+    fn interpolate(&self, x: Real) -> Result<Real> {
+        Ok(self.hermite.evaluate(x)?.0)
+    }
+
+    fn vectorized_interpolate_for_sorted_ndarray(&self, x: &Array1<Real>) -> Result<Array1<Real>> {
+        x.iter().map(|&xi| self.interpolate(xi)).collect()
+    }
+}
+
+/// Fritsch and Carlson (1980) monotone piecewise cubic interpolation.
+/// The interpolant is monotone on each interval where the data is monotone,
+/// so it does not overshoot, e.g., a discount factor or a total variance in time.
+/// It is only once continuously differentiable.
+#[derive(Debug, Clone)]
+pub struct FritschCarlsonInterpolator1D {
+    hermite: HermiteCubic,
+}
+
+impl FritschCarlsonInterpolator1D {
+    pub fn new(
+        domain: Array1<Real>,
+        value: Array1<Real>,
+        extrapolation_type: ExtraPolationType,
+    ) -> Result<FritschCarlsonInterpolator1D> {
+        HermiteCubic::check_input(&domain, &value)?;
+        let n = domain.len();
+        let secants = HermiteCubic::secants(&domain, &value);
+
+        let mut slopes = Array1::zeros(n);
+        slopes[0] = secants[0];
+        slopes[n - 1] = secants[n - 2];
+        for i in 1..n - 1 {
+            slopes[i] = match secants[i - 1] * secants[i] <= 0.0 {
+                true => 0.0,
+                false => 0.5 * (secants[i - 1] + secants[i]),
+            };
+        }
+        // restrict the slopes to the monotonicity region alpha^2 + beta^2 <= 9
+        for i in 0..n - 1 {
+            if secants[i] == 0.0 {
+                slopes[i] = 0.0;
+                slopes[i + 1] = 0.0;
+                continue;
+            }
+            let alpha = slopes[i] / secants[i];
+            let beta = slopes[i + 1] / secants[i];
+            let norm = alpha * alpha + beta * beta;
+            if norm > 9.0 {
+                let tau = 3.0 / norm.sqrt();
+                slopes[i] = tau * alpha * secants[i];
+                slopes[i + 1] = tau * beta * secants[i];
+            }
+        }
+
+        Ok(FritschCarlsonInterpolator1D {
+            hermite: HermiteCubic {
+                domain,
+                value,
+                slopes,
+                extrapolation_type,
+            },
+        })
+    }
+
+    pub fn derivative(&self, x: Real) -> Result<Real> {
+        Ok(self.hermite.evaluate(x)?.1)
+    }
+
+    pub fn second_derivative(&self, x: Real) -> Result<Real> {
+        Ok(self.hermite.evaluate(x)?.2)
+    }
+}
+
+impl InterpolatorReal1D for FritschCarlsonInterpolator1D {
+    fn interpolate(&self, x: Real) -> Result<Real> {
+        Ok(self.hermite.evaluate(x)?.0)
+    }
+
+    fn vectorized_interpolate_for_sorted_ndarray(&self, x: &Array1<Real>) -> Result<Array1<Real>> {
+        x.iter().map(|&xi| self.interpolate(xi)).collect()
+    }
+}
+
+/// Akima (1970) interpolation.
+/// The node slopes are weighted by the differences of the neighboring secants,
+/// so that an outlier does not make wiggles far from it as in the cubic spline.
+/// The two missing secants at each end are extrapolated linearly as in the original paper.
+/// It is only once continuously differentiable.
+#[derive(Debug, Clone)]
+pub struct AkimaInterpolator1D {
+    hermite: HermiteCubic,
+}
+
+impl AkimaInterpolator1D {
+    pub fn new(
+        domain: Array1<Real>,
+        value: Array1<Real>,
+        extrapolation_type: ExtraPolationType,
+    ) -> Result<AkimaInterpolator1D> {
+        HermiteCubic::check_input(&domain, &value)?;
+        let n = domain.len();
+        let secants = HermiteCubic::secants(&domain, &value);
+
+        // m[k + 2] is the k-th secant
+        let mut m = vec![0.0; n + 3];
+        m[2..n + 1].copy_from_slice(&secants);
+        m[1] = 2.0 * m[2] - m[3.min(n)];
+        m[0] = 2.0 * m[1] - m[2];
+        m[n + 1] = 2.0 * m[n] - m[(n - 1).max(2)];
+        m[n + 2] = 2.0 * m[n + 1] - m[n];
+
+        let mut slopes = Array1::zeros(n);
+        for i in 0..n {
+            let w1 = (m[i + 3] - m[i + 2]).abs();
+            let w2 = (m[i + 1] - m[i]).abs();
+            slopes[i] = match w1 + w2 > 0.0 {
+                true => (w1 * m[i + 1] + w2 * m[i + 2]) / (w1 + w2),
+                false => 0.5 * (m[i + 1] + m[i + 2]),
+            };
+        }
+
+        Ok(AkimaInterpolator1D {
+            hermite: HermiteCubic {
+                domain,
+                value,
+                slopes,
+                extrapolation_type,
+            },
+        })
+    }
+
+    pub fn derivative(&self, x: Real) -> Result<Real> {
+        Ok(self.hermite.evaluate(x)?.1)
+    }
+
+    pub fn second_derivative(&self, x: Real) -> Result<Real> {
+        Ok(self.hermite.evaluate(x)?.2)
+    }
+}
+
+impl InterpolatorReal1D for AkimaInterpolator1D {
+    fn interpolate(&self, x: Real) -> Result<Real> {
+        Ok(self.hermite.evaluate(x)?.0)
+    }
+
+    fn vectorized_interpolate_for_sorted_ndarray(&self, x: &Array1<Real>) -> Result<Array1<Real>> {
+        x.iter().map(|&xi| self.interpolate(xi)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    /// value, first and second derivatives at x
+    type Evaluation<'a> = Box<dyn Fn(Real) -> Result<(Real, Real, Real)> + 'a>;
+
+    #[test]
+    fn test_cubic_interpolators() -> Result<()> {
+        let domain = array![0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+        let value = array![0.0, 1.0, 0.0, 1.0, 1.0, 3.0];
+        let natural =
+            CubicSplineInterpolator1D::new(domain.clone(), value.clone(), ExtraPolationType::Flat)?;
+        let clamped = CubicSplineInterpolator1D::new_clamped(
+            domain.clone(),
+            value.clone(),
+            1.0,
+            -1.0,
+            ExtraPolationType::Linear,
+        )?;
+        let fritsch_carlson = FritschCarlsonInterpolator1D::new(
+            domain.clone(),
+            value.clone(),
+            ExtraPolationType::Flat,
+        )?;
+        let akima =
+            AkimaInterpolator1D::new(domain.clone(), value.clone(), ExtraPolationType::None)?;
+        let interpolators: Vec<&dyn InterpolatorReal1D> =
+            vec![&natural, &clamped, &fritsch_carlson, &akima];
+
+        // all the interpolators go through the nodes
+        for interpolator in interpolators.iter() {
+            let res = interpolator.vectorized_interpolate_for_sorted_ndarray(&domain)?;
+            for (y, expected) in res.iter().zip(value.iter()) {
+                assert!((y - expected).abs() < 1.0e-6);
+            }
+        }
+
+        // boundary conditions of the splines
+        assert!(natural.second_derivative(0.0)?.abs() < 1.0e-5);
+        assert!(natural.second_derivative(5.0)?.abs() < 1.0e-5);
+        assert!((clamped.derivative(0.0)? - 1.0).abs() < 1.0e-5);
+        assert!((clamped.derivative(5.0)? + 1.0).abs() < 1.0e-5);
+
+        // extrapolation
+        assert!((natural.interpolate(-1.0)? - 0.0).abs() < 1.0e-6);
+        assert!((clamped.interpolate(6.0)? - 2.0).abs() < 1.0e-5);
+        assert!(akima.interpolate(6.0).is_err());
+
+        // the derivatives agree with the finite differences,
+        // and the second derivative of the spline is continuous at the knots
+        let evaluations: Vec<(&str, Evaluation)> = vec![
+            (
+                "natural",
+                Box::new(|x| {
+                    Ok((
+                        natural.interpolate(x)?,
+                        natural.derivative(x)?,
+                        natural.second_derivative(x)?,
+                    ))
+                }),
+            ),
+            (
+                "fritsch_carlson",
+                Box::new(|x| {
+                    Ok((
+                        fritsch_carlson.interpolate(x)?,
+                        fritsch_carlson.derivative(x)?,
+                        fritsch_carlson.second_derivative(x)?,
+                    ))
+                }),
+            ),
+            (
+                "akima",
+                Box::new(|x| {
+                    Ok((
+                        akima.interpolate(x)?,
+                        akima.derivative(x)?,
+                        akima.second_derivative(x)?,
+                    ))
+                }),
+            ),
+        ];
+        let eps = 1.0e-2;
+        for (name, evaluate) in evaluations.iter() {
+            for x in [0.5, 1.3, 2.7, 3.5, 4.2] {
+                let (mid, first, second) = evaluate(x)?;
+                let (up, _, _) = evaluate(x + eps)?;
+                let (down, _, _) = evaluate(x - eps)?;
+                let fd_first = (up - down) / (2.0 * eps);
+                let fd_second = (up - 2.0 * mid + down) / (eps * eps);
+                assert!(
+                    (first - fd_first).abs() < 1.0e-3,
+                    "{} at {}: {} vs {}",
+                    name,
+                    x,
+                    first,
+                    fd_first
+                );
+                assert!(
+                    (second - fd_second).abs() < 5.0e-2,
+                    "{} at {}: {} vs {}",
+                    name,
+                    x,
+                    second,
+                    fd_second
+                );
+            }
+        }
+        for x in [1.0, 2.0, 3.0, 4.0] {
+            let left = natural.second_derivative(x - 1.0e-4)?;
+            let right = natural.second_derivative(x + 1.0e-4)?;
+            assert!((left - right).abs() < 1.0e-2, "x = {}", x);
+        }
+
+        // Fritsch-Carlson does not overshoot on the flat and monotone pieces
+        let grid = Array1::linspace(0.0, 5.0, 201);
+        let res = fritsch_carlson.vectorized_interpolate_for_sorted_ndarray(&grid)?;
+        for (x, y) in grid.iter().zip(res.iter()) {
+            if (3.0..=4.0).contains(x) {
+                assert!((y - 1.0).abs() < 1.0e-6, "x = {}, y = {}", x, y);
+            }
+            if (4.0..=5.0).contains(x) {
+                assert!(*y >= 1.0 - 1.0e-6 && *y <= 3.0 + 1.0e-6);
+            }
+        }
+        let res = natural.vectorized_interpolate_for_sorted_ndarray(&grid)?;
+        assert!(res
+            .iter()
+            .zip(grid.iter())
+            .any(|(y, x)| (3.0..=4.0).contains(x) && (y - 1.0).abs() > 1.0e-2));
+        Ok(())
+    }
+}
@@ -1,7 +1,9 @@
 pub mod interpolator;
 pub mod interpolators {
     pub mod bilinear_interpolator;
+    pub mod cubic_spline_interpolator;
     pub mod linear_interpolator;
     pub mod stepwise_interpolatior;
 }
 pub mod cholescky_factorization;
+pub mod tridiagonal_solver;
@@ -0,0 +1,83 @@
+use crate::definitions::Real;
+use anyhow::{anyhow, Result};
+use ndarray::Array1;
+
+/// Solve the tridiagonal system by Thomas algorithm
+/// lower[i] * x[i-1] + diag[i] * x[i] + upper[i] * x[i+1] = rhs[i]
+/// lower[0] and upper[n-1] are ignored.
+/// The algorithm is stable when the matrix is diagonally dominant,
+/// which is the case for the implicit and Crank-Nicolson schemes with a moderate time step.
+pub fn solve_tridiagonal(
+    lower: &[Real],
+    diag: &[Real],
+    upper: &[Real],
+    rhs: &[Real],
+) -> Result<Array1<Real>> {
+    let n = diag.len();
+    if lower.len() != n || upper.len() != n || rhs.len() != n {
+        return Err(anyhow!(
+            "({}:{}) size mismatch: lower = {}, diag = {}, upper = {}, rhs = {}",
+            file!(),
+            line!(),
+            lower.len(),
+            n,
+            upper.len(),
+            rhs.len()
+        ));
+    }
+    if n == 0 {
+        return Ok(Array1::zeros(0));
+    }
+
+    let mut c_prime = vec![0.0; n];
+    let mut d_prime = vec![0.0; n];
+    let mut denom = diag[0];
+    if denom == 0.0 {
+        return Err(anyhow!("({}:{}) zero pivot at row 0", file!(), line!()));
+    }
+    c_prime[0] = upper[0] / denom;
+    d_prime[0] = rhs[0] / denom;
+    for i in 1..n {
+        denom = diag[i] - lower[i] * c_prime[i - 1];
+        if denom == 0.0 {
+            return Err(anyhow!("({}:{}) zero pivot at row {}", file!(), line!(), i));
+        }
+        c_prime[i] = upper[i] / denom;
+        d_prime[i] = (rhs[i] - lower[i] * d_prime[i - 1]) / denom;
+    }
+
+    let mut x = Array1::zeros(n);
+    x[n - 1] = d_prime[n - 1];
+    for i in (0..n - 1).rev() {
+        x[i] = d_prime[i] - c_prime[i] * x[i + 1];
+    }
+    Ok(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve_tridiagonal() -> Result<()> {
+        // [ 2 -1  0  0] [1]   [ 0]
+        // [-1  2 -1  0] [2] = [ 0]
+        // [ 0 -1  2 -1] [3]   [ 0]
+        // [ 0  0 -1  2] [4]   [ 5]
+        let lower = vec![0.0, -1.0, -1.0, -1.0];
+        let diag = vec![2.0, 2.0, 2.0, 2.0];
+        let upper = vec![-1.0, -1.0, -1.0, 0.0];
+        let rhs = vec![0.0, 0.0, 0.0, 5.0];
+        let x = solve_tridiagonal(&lower, &diag, &upper, &rhs)?;
+        for (i, v) in x.iter().enumerate() {
+            assert!(
+                (v - (i as Real + 1.0)).abs() < 1.0e-5,
+                "x[{}] = {}, expected = {}",
+                i,
+                v,
+                i + 1
+            );
+        }
+        Ok(())
+    }
+}
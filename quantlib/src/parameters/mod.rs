@@ -3,6 +3,9 @@ pub mod market_price;
 pub mod past_price;
 pub mod quanto;
 pub mod rate_index;
+pub mod rate_volatility;
+pub mod survival_curve;
 pub mod volatilities;
 pub mod volatility;
 pub mod zero_curve;
+pub mod zero_curve_bootstrapper;
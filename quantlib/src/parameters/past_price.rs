@@ -100,6 +100,17 @@ impl DailyClosePrice {
         }
     }
 
+    /// the minimum and the maximum of the closes on the dates in [from, to), None if there is no close
+    pub fn get_min_max(&self, from: &Date, to: &Date) -> Option<(Real, Real)> {
+        self.value
+            .iter()
+            .filter(|(date, _)| *date >= from && *date < to)
+            .fold(None, |acc, (_, value)| match acc {
+                None => Some((*value, *value)),
+                Some((min, max)) => Some((min.min(*value), max.max(*value))),
+            })
+    }
+
     pub fn get_close_time(&self) -> &Time {
         &self.close_time
     }
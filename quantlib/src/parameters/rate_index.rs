@@ -10,12 +10,60 @@ use crate::time::jointcalendar::JointCalendar;
 use crate::util::min_offsetdatetime;
 use crate::utils::string_arithmetic::add_period;
 //
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::{cell::RefCell, rc::Rc};
 use time::{Duration, OffsetDateTime};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Conventions of an overnight index (KOFR, SOFR, etc) compounded in arrears over each accrual period
+/// * lookback_days: the rate of each business day is observed lookback_days business days before
+/// * lockout_days: the rates of the last lockout_days business days in the period are fixed
+///   at the rate observed for the business day just before the lockout
+/// * observation_shift: if true, the daily weights are also taken on the observation period
+///   shifted back by lookback_days, otherwise on the accrual period
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct OvernightConvention {
+    lookback_days: i64,
+    lockout_days: i64,
+    observation_shift: bool,
+}
+
+impl OvernightConvention {
+    pub fn new(
+        lookback_days: i64,
+        lockout_days: i64,
+        observation_shift: bool,
+    ) -> Result<OvernightConvention> {
+        if lookback_days < 0 || lockout_days < 0 {
+            return Err(anyhow!(
+                "({}:{}) lookback_days = {} and lockout_days = {} should be non-negative",
+                file!(),
+                line!(),
+                lookback_days,
+                lockout_days
+            ));
+        }
+        Ok(OvernightConvention {
+            lookback_days,
+            lockout_days,
+            observation_shift,
+        })
+    }
+
+    pub fn get_lookback_days(&self) -> i64 {
+        self.lookback_days
+    }
+
+    pub fn get_lockout_days(&self) -> i64 {
+        self.lockout_days
+    }
+
+    pub fn get_observation_shift(&self) -> bool {
+        self.observation_shift
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 /// * Tenor is forward curve calculation period\n
 /// * Compounding_tenor (Option<String>) is the period of compounding\n
 /// At each the end date of the previous compounding_tenor,
@@ -24,11 +72,16 @@ use time::{Duration, OffsetDateTime};
 /// that's the case of KODEX CD ETF (A459580)
 /// Theoretically, the compound tenor can be greater than the tenor,
 /// but I have not seen such a case in the market, so I chose not to allow such case (return error)
+///
+/// An overnight index given with_overnight_convention is compounded daily in arrears on the
+/// business days of the calendar regardless of the compounding_tenor.
 pub struct RateIndex {
     curve_tenor: String,
     currency: Currency,
     name: String, // USD LIBOR 3M, EURIBOR 6M, CD91, etc
     code: String,
+    #[serde(default)]
+    overnight_convention: Option<OvernightConvention>,
 }
 
 impl RateIndex {
@@ -43,9 +96,23 @@ impl RateIndex {
             currency,
             code,
             name,
+            overnight_convention: None,
         })
     }
 
+    pub fn with_overnight_convention(mut self, convention: OvernightConvention) -> RateIndex {
+        self.overnight_convention = Some(convention);
+        self
+    }
+
+    pub fn get_overnight_convention(&self) -> Option<&OvernightConvention> {
+        self.overnight_convention.as_ref()
+    }
+
+    pub fn is_overnight(&self) -> bool {
+        self.overnight_convention.is_some()
+    }
+
     pub fn get_code(&self) -> &String {
         &self.code
     }
@@ -86,6 +153,18 @@ impl RateIndex {
         daycounter: &DayCountConvention,
         fixing_days: i64,
     ) -> Result<Real> {
+        if let Some(convention) = self.overnight_convention.as_ref() {
+            return self.get_compounded_in_arrears_amount(
+                base_schedule,
+                spread,
+                forward_curve,
+                close_data,
+                pricing_date,
+                calendar,
+                daycounter,
+                convention,
+            );
+        }
         let spread = spread.unwrap_or(0.0);
         match compound_tenor.as_ref() {
             None => {
@@ -214,6 +293,127 @@ impl RateIndex {
             }
         }
     }
+
+    /// (compounded rate + spread) * accrual fraction of an overnight index over the accrual period,
+    /// where the rates observed before the evaluation date are taken from close_data
+    /// and the others are the overnight forwards on forward_curve.
+    /// The spread is not compounded.
+    #[allow(clippy::too_many_arguments)]
+    fn get_compounded_in_arrears_amount(
+        &self,
+        base_schedule: &BaseSchedule,
+        spread: Option<Real>,
+        forward_curve: Rc<RefCell<ZeroCurve>>,
+        close_data: Rc<DailyClosePrice>,
+        pricing_date: &OffsetDateTime,
+        calendar: &JointCalendar,
+        daycounter: &DayCountConvention,
+        convention: &OvernightConvention,
+    ) -> Result<Real> {
+        let calc_start_date = *base_schedule.get_calc_start_date();
+        let calc_end_date = *base_schedule.get_calc_end_date();
+        let accrual_frac = calendar.year_fraction(&calc_start_date, &calc_end_date, daycounter)?;
+
+        let next_business_day = |date: &OffsetDateTime| -> Result<OffsetDateTime> {
+            calendar.adjust(
+                &(*date + Duration::days(1)),
+                &BusinessDayConvention::Following,
+            )
+        };
+        let lookback = |date: &OffsetDateTime| -> Result<OffsetDateTime> {
+            let mut res = calendar.adjust(date, &BusinessDayConvention::Preceding)?;
+            for _ in 0..convention.get_lookback_days() {
+                res = calendar.adjust(
+                    &(res - Duration::days(1)),
+                    &BusinessDayConvention::Preceding,
+                )?;
+            }
+            Ok(res)
+        };
+
+        // the accrual dates are the business days in [calc_start_date, calc_end_date]
+        let mut accrual_dates = vec![calc_start_date];
+        let mut date = calc_start_date;
+        while date < calc_end_date {
+            date = min_offsetdatetime(&next_business_day(&date)?, &calc_end_date);
+            accrual_dates.push(date);
+        }
+        let days = accrual_dates.len() - 1;
+        if days == 0 {
+            return Ok(0.0);
+        }
+        let observation_dates = accrual_dates
+            .iter()
+            .map(lookback)
+            .collect::<Result<Vec<OffsetDateTime>>>()?;
+        let last_observed = days.saturating_sub(convention.get_lockout_days() as usize + 1);
+
+        // the daily factors are compounded in f64 since each of them differs from one by about 1.0e-4,
+        // and the future rates are quoted on the daycounter from the ratios of discount factors
+        // so that they telescope on the curve
+        let mut compounded_value: f64 = 1.0;
+        let mut missing_fixings: Vec<time::Date> = vec![];
+        for i in 0..days {
+            let observation_date = observation_dates[i.min(last_observed)];
+            let rate: f64 = match close_data.get(&observation_date.date()) {
+                Some(rate) if observation_date.date() <= pricing_date.date() => *rate as f64,
+                _ if observation_date.date() < pricing_date.date() => {
+                    missing_fixings.push(observation_date.date());
+                    let spot_end_date = next_business_day(pricing_date)?;
+                    forward_curve.borrow().get_forward_rate_between_dates(
+                        pricing_date,
+                        &spot_end_date,
+                        Compounding::Simple,
+                    )? as f64
+                }
+                _ => {
+                    let observation_end_date = next_business_day(&observation_date)?;
+                    let curve = forward_curve.borrow();
+                    let growth = curve.get_discount_factor_at_date(&observation_date)? as f64
+                        / curve.get_discount_factor_at_date(&observation_end_date)? as f64;
+                    let tau = calendar.year_fraction(
+                        &observation_date,
+                        &observation_end_date,
+                        daycounter,
+                    )? as f64;
+                    (growth - 1.0) / tau
+                }
+            };
+            let weight = match convention.get_observation_shift() {
+                true => calendar.year_fraction(
+                    &observation_dates[i],
+                    &observation_dates[i + 1],
+                    daycounter,
+                )?,
+                false => {
+                    calendar.year_fraction(&accrual_dates[i], &accrual_dates[i + 1], daycounter)?
+                }
+            };
+            compounded_value *= 1.0 + rate * weight as f64;
+        }
+        if !missing_fixings.is_empty() {
+            println!(
+                "Warning! ({}:{}) {} has no fixings on {:?} before the evaluation date = {:?}, \
+                thus the overnight rate at the evaluation date is used",
+                file!(),
+                line!(),
+                self.code,
+                missing_fixings,
+                pricing_date.date()
+            );
+        }
+
+        let observation_frac = match convention.get_observation_shift() {
+            true => calendar.year_fraction(
+                &observation_dates[0],
+                &observation_dates[days],
+                daycounter,
+            )?,
+            false => accrual_frac,
+        };
+        let compounded_rate = ((compounded_value - 1.0) / observation_frac as f64) as Real;
+        Ok((compounded_rate + spread.unwrap_or(0.0)) * accrual_frac)
+    }
 }
 
 #[cfg(test)]
@@ -223,12 +423,14 @@ mod tests {
     use crate::data::vector_data::VectorData;
     use crate::definitions::{DEFAULT_CLOSING_TIME, NEW_YORK_OFFSET};
     use crate::evaluation_date::EvaluationDate;
-    use crate::instruments::schedule::BaseSchedule;
+    use crate::instrument::InstrumentTrait;
+    use crate::instruments::{plain_swap::PlainSwap, schedule::BaseSchedule};
     use crate::parameters::zero_curve::ZeroCurve;
     use crate::time::conventions::{BusinessDayConvention, DayCountConvention, PaymentFrequency};
     use crate::time::{
         calendar::Calendar,
         calendar_trait::CalendarTrait,
+        calendars::southkorea::{SouthKorea, SouthKoreaType},
         calendars::unitedstates::{UnitedStates, UnitedStatesType},
         jointcalendar::JointCalendar,
     };
@@ -338,4 +540,185 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_overnight_compounding_in_arrears() -> Result<()> {
+        let dt = datetime!(2024-03-15 16:30:00 +09:00);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(dt)));
+        let calendar = JointCalendar::new(vec![Calendar::SouthKorea(SouthKorea::new(
+            SouthKoreaType::Settlement,
+        ))])?;
+        let daycounter = DayCountConvention::Actual365Fixed;
+        let curve_data = VectorData::new(
+            array![0.035, 0.035],
+            None,
+            Some(array![0.5, 5.0]),
+            Some(dt),
+            Currency::KRW,
+            "KRWOIS".to_string(),
+            "KRWOIS".to_string(),
+        )?;
+        let zero_curve = Rc::new(RefCell::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &curve_data,
+            "KRWOIS".to_string(),
+            "KRWOIS".to_string(),
+        )?));
+
+        let mut history_map = HashMap::new();
+        history_map.insert(date!(2024 - 02 - 28), 0.030);
+        history_map.insert(date!(2024 - 02 - 29), 0.031);
+        history_map.insert(date!(2024 - 03 - 04), 0.032);
+        history_map.insert(date!(2024 - 03 - 05), 0.033);
+        history_map.insert(date!(2024 - 03 - 06), 0.034);
+        history_map.insert(date!(2024 - 03 - 07), 0.035);
+        history_map.insert(date!(2024 - 03 - 08), 0.036);
+        let close_data = Rc::new(DailyClosePrice::new(
+            history_map,
+            DEFAULT_CLOSING_TIME,
+            UtcOffset::from_hms(9, 0, 0).unwrap(),
+            Calendar::SouthKorea(SouthKorea::new(SouthKoreaType::Settlement)),
+            "KOFR".to_string(),
+            "KOFR".to_string(),
+        ));
+
+        // 2024-03-01 is a holiday, so the accrual days from 2024-03-04 are observed
+        // two business days before on 02-28, 02-29, 03-04, 03-05 and 03-06 (over the weekend)
+        let base_schedule = BaseSchedule::new(
+            datetime!(2024-03-04 16:30:00 +09:00),
+            datetime!(2024-03-04 16:30:00 +09:00),
+            datetime!(2024-03-11 16:30:00 +09:00),
+            datetime!(2024-03-11 16:30:00 +09:00),
+            None,
+        );
+        let amount = |convention: OvernightConvention| -> Result<Real> {
+            RateIndex::new(
+                "1D".to_string(),
+                Currency::KRW,
+                "KOFR".to_string(),
+                "KOFR".to_string(),
+            )?
+            .with_overnight_convention(convention)
+            .get_coupon_amount(
+                &base_schedule,
+                None,
+                zero_curve.clone(),
+                close_data.clone(),
+                &dt,
+                None,
+                &calendar,
+                &daycounter,
+                0,
+            )
+        };
+        let compound = |rates_and_days: &[(Real, Real)]| -> Real {
+            (rates_and_days.iter().fold(1.0, |acc: f64, (rate, days)| {
+                acc * (1.0 + *rate as f64 * *days as f64 / 365.0)
+            }) - 1.0) as Real
+        };
+
+        let lookback = amount(OvernightConvention::new(2, 0, false)?)?;
+        let expected = compound(&[
+            (0.030, 1.0),
+            (0.031, 1.0),
+            (0.032, 1.0),
+            (0.033, 1.0),
+            (0.034, 3.0),
+        ]);
+        assert!(
+            (lookback - expected).abs() < 1.0e-7,
+            "lookback = {}, expected = {}",
+            lookback,
+            expected
+        );
+
+        // the last day is locked out at the rate of the day before
+        let lockout = amount(OvernightConvention::new(2, 1, false)?)?;
+        let expected = compound(&[
+            (0.030, 1.0),
+            (0.031, 1.0),
+            (0.032, 1.0),
+            (0.033, 1.0),
+            (0.033, 3.0),
+        ]);
+        assert!(
+            (lockout - expected).abs() < 1.0e-7,
+            "lockout = {}, expected = {}",
+            lockout,
+            expected
+        );
+
+        // the weights are on the observation period from 02-28 to 03-07 (8 days)
+        let shift = amount(OvernightConvention::new(2, 0, true)?)?;
+        let expected = compound(&[
+            (0.030, 1.0),
+            (0.031, 4.0),
+            (0.032, 1.0),
+            (0.033, 1.0),
+            (0.034, 1.0),
+        ]) / 8.0
+            * 7.0;
+        assert!(
+            (shift - expected).abs() < 1.0e-7,
+            "observation shift = {}, expected = {}",
+            shift,
+            expected
+        );
+
+        // the floating leg of an OIS on the same curve is worth the discount factor difference
+        let kofr = RateIndex::new(
+            "1D".to_string(),
+            Currency::KRW,
+            "KOFR".to_string(),
+            "KOFR".to_string(),
+        )?
+        .with_overnight_convention(OvernightConvention::new(0, 0, false)?);
+        let effective_date = datetime!(2024-03-18 16:30:00 +09:00);
+        let maturity = datetime!(2025-03-18 16:30:00 +09:00);
+        let ois = PlainSwap::new_from_conventions(
+            Currency::KRW,
+            Currency::KRW,
+            None,
+            None,
+            None,
+            None,
+            1.0,
+            dt,
+            effective_date,
+            maturity,
+            Some(0.035),
+            Some(kofr),
+            None,
+            true,
+            daycounter,
+            daycounter,
+            BusinessDayConvention::ModifiedFollowing,
+            BusinessDayConvention::ModifiedFollowing,
+            PaymentFrequency::Quarterly,
+            PaymentFrequency::Quarterly,
+            0,
+            0,
+            calendar.clone(),
+            "KOFR OIS".to_string(),
+            "KOFR OIS".to_string(),
+        )?;
+        let floating_leg = ois
+            .get_floating_cashflows(&dt, Some(zero_curve.clone()), Some(close_data.clone()))?
+            .iter()
+            .map(|(date, amount)| {
+                Ok(amount * zero_curve.borrow().get_discount_factor_at_date(date)?)
+            })
+            .sum::<Result<Real>>()?;
+        let expected = zero_curve
+            .borrow()
+            .get_discount_factor_at_date(&effective_date)?
+            - zero_curve.borrow().get_discount_factor_at_date(&maturity)?;
+        assert!(
+            (floating_leg - expected).abs() < 1.0e-5,
+            "floating leg = {}, expected = {}",
+            floating_leg,
+            expected
+        );
+        Ok(())
+    }
 }
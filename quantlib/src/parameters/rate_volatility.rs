@@ -0,0 +1,264 @@
+use crate::data::rate_volatility_data::RateVolatilityData;
+use crate::definitions::{Real, Time};
+use crate::enums::RateVolatilityType;
+use crate::evaluation_date::EvaluationDate;
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+use crate::utils::find_index_ndarray::binary_search_index_ndarray;
+//
+use anyhow::{anyhow, Result};
+use ndarray::{Array1, Array3};
+use std::{cell::RefCell, rc::Rc};
+use time::OffsetDateTime;
+
+/// lower node index, upper node index and the weight on the upper node (flat outside the nodes)
+fn bracket(nodes: &Array1<Real>, x: Real) -> (usize, usize, Real) {
+    let n = nodes.len();
+    if n == 1 || x <= nodes[0] {
+        return (0, 0, 0.0);
+    }
+    if x >= nodes[n - 1] {
+        return (n - 1, n - 1, 0.0);
+    }
+    let i = binary_search_index_ndarray(nodes, x).min(n - 2);
+    (i, i + 1, (x - nodes[i]) / (nodes[i + 1] - nodes[i]))
+}
+
+/// Volatility cube of a rate index on expiry x tenor x strike for caps, floors and swaptions.
+/// The quotes are linearly interpolated on the time to expiry, the tenor and the absolute strike,
+/// and flat outside the nodes.
+///
+/// The normal vega is computed by bump_normal_volatility which shifts the Bachelier volatility.
+/// For ShiftedLognormal quotes the bump is mapped to the Black volatility by
+/// sigma_N ~ sigma_B * (F + shift) at the forward of each option.
+#[derive(Debug, Clone)]
+pub struct RateVolatility {
+    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    value: Array3<Real>,
+    expiry_dates: Vec<OffsetDateTime>,
+    tenors: Array1<Real>,
+    strikes: Array1<Real>,
+    volatility_type: RateVolatilityType,
+    shift: Real,
+    normal_bump: Real,
+    time_calculator: NullCalendar,
+    name: String,
+    code: String,
+}
+
+impl RateVolatility {
+    pub fn new(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        data: &RateVolatilityData,
+        name: String,
+        code: String,
+    ) -> Result<RateVolatility> {
+        let value = data.get_value().clone();
+        let expiry_dates = data.get_expiry_dates().clone();
+        let tenors = data.get_tenors().clone();
+        let strikes = data.get_strikes().clone();
+        let shape = (expiry_dates.len(), tenors.len(), strikes.len());
+        if value.dim() != shape {
+            return Err(anyhow!(
+                "({}:{}) {} ({}): the volatility cube has the shape {:?}, \
+                but (expiry_dates, tenors, strikes) has the lengths {:?}",
+                file!(),
+                line!(),
+                name,
+                code,
+                value.dim(),
+                shape
+            ));
+        }
+        if value.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) {} ({}): empty volatility cube",
+                file!(),
+                line!(),
+                name,
+                code
+            ));
+        }
+        let is_increasing = |x: &Array1<Real>| x.iter().zip(x.iter().skip(1)).all(|(a, b)| a < b);
+        if !expiry_dates.windows(2).all(|w| w[0] < w[1])
+            || !is_increasing(&tenors)
+            || !is_increasing(&strikes)
+        {
+            return Err(anyhow!(
+                "({}:{}) {} ({}): expiry_dates, tenors and strikes must be strictly increasing",
+                file!(),
+                line!(),
+                name,
+                code
+            ));
+        }
+        if value.iter().any(|v| *v < 0.0) {
+            return Err(anyhow!(
+                "({}:{}) {} ({}): negative volatility in the cube",
+                file!(),
+                line!(),
+                name,
+                code
+            ));
+        }
+        let shift = match data.get_volatility_type() {
+            RateVolatilityType::ShiftedLognormal => {
+                if strikes[0] + data.get_shift() <= 0.0 {
+                    return Err(anyhow!(
+                        "({}:{}) {} ({}): the lowest strike {} is not above -shift = {}",
+                        file!(),
+                        line!(),
+                        name,
+                        code,
+                        strikes[0],
+                        -data.get_shift()
+                    ));
+                }
+                data.get_shift()
+            }
+            RateVolatilityType::Normal => 0.0,
+        };
+
+        Ok(RateVolatility {
+            evaluation_date,
+            value,
+            expiry_dates,
+            tenors,
+            strikes,
+            volatility_type: data.get_volatility_type(),
+            shift,
+            normal_bump: 0.0,
+            time_calculator: NullCalendar::new(),
+            name,
+            code,
+        })
+    }
+
+    pub fn get_volatility_type(&self) -> RateVolatilityType {
+        self.volatility_type
+    }
+
+    pub fn get_shift(&self) -> Real {
+        self.shift
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    pub fn get_normal_bump(&self) -> Real {
+        self.normal_bump
+    }
+
+    /// quoted volatility without the normal bump
+    pub fn get_value(&self, expiry: &OffsetDateTime, tenor: Time, strike: Real) -> Real {
+        let eval_date = self.evaluation_date.borrow();
+        let expiry_times: Array1<Time> = self
+            .expiry_dates
+            .iter()
+            .map(|d| {
+                self.time_calculator
+                    .get_time_difference(eval_date.get_date(), d)
+            })
+            .collect();
+        let t = self
+            .time_calculator
+            .get_time_difference(eval_date.get_date(), expiry);
+
+        let (e0, e1, we) = bracket(&expiry_times, t);
+        let (n0, n1, wn) = bracket(&self.tenors, tenor);
+        let (k0, k1, wk) = bracket(&self.strikes, strike);
+        let mut res = 0.0;
+        for (e, a) in [(e0, 1.0 - we), (e1, we)] {
+            for (n, b) in [(n0, 1.0 - wn), (n1, wn)] {
+                for (k, c) in [(k0, 1.0 - wk), (k1, wk)] {
+                    res += a * b * c * self.value[[e, n, k]];
+                }
+            }
+        }
+        res
+    }
+
+    /// volatility in the quotation of the cube including the normal bump.
+    /// The forward is used only to map the normal bump on ShiftedLognormal quotes
+    pub fn get_volatility(
+        &self,
+        expiry: &OffsetDateTime,
+        tenor: Time,
+        strike: Real,
+        forward: Real,
+    ) -> Real {
+        let quote = self.get_value(expiry, tenor, strike);
+        match self.volatility_type {
+            RateVolatilityType::Normal => quote + self.normal_bump,
+            RateVolatilityType::ShiftedLognormal => {
+                quote + self.normal_bump / (forward + self.shift).max(1.0e-6)
+            }
+        }
+    }
+
+    /// parallel bump of the Bachelier volatility, which is accumulated on the previous bumps
+    pub fn bump_normal_volatility(&mut self, bump: Real) {
+        self.normal_bump += bump;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use ndarray::{array, Array3};
+    use time::macros::datetime;
+
+    #[test]
+    fn test_rate_volatility_interpolation() -> Result<()> {
+        let dt = datetime!(2024-01-02 16:30:00 +09:00);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(dt)));
+        let expiry_dates = vec![
+            datetime!(2025-01-02 16:30:00 +09:00),
+            datetime!(2026-01-02 16:30:00 +09:00),
+        ];
+        let tenors = array![0.25, 5.0];
+        let strikes = array![0.02, 0.04];
+        // value[[e, n, k]] = 0.006 + 0.001 * e + 0.002 * n + 0.004 * k
+        let value = Array3::from_shape_fn((2, 2, 2), |(e, n, k)| {
+            0.006 + 0.001 * e as Real + 0.002 * n as Real + 0.004 * k as Real
+        });
+        let data = RateVolatilityData::new(
+            value,
+            expiry_dates.clone(),
+            tenors,
+            strikes,
+            RateVolatilityType::Normal,
+            0.0,
+            None,
+            Currency::KRW,
+            "CD91 Vol".to_string(),
+            "CD91".to_string(),
+        );
+        let mut vol = RateVolatility::new(
+            evaluation_date,
+            &data,
+            "CD91 Vol".to_string(),
+            "CD91".to_string(),
+        )?;
+
+        // a node and the mid points
+        let node = vol.get_value(&expiry_dates[1], 5.0, 0.02);
+        assert!((node - 0.009).abs() < 1.0e-7, "node: {}", node);
+        let mid_expiry = datetime!(2025-07-03 16:30:00 +09:00);
+        let mid = vol.get_value(&mid_expiry, 2.625, 0.03);
+        assert!((mid - 0.0095).abs() < 1.0e-5, "mid: {}", mid);
+        // flat extrapolation
+        let far = vol.get_value(&datetime!(2030-01-02 16:30:00 +09:00), 30.0, 0.10);
+        assert!((far - 0.013).abs() < 1.0e-7, "far: {}", far);
+
+        vol.bump_normal_volatility(0.0001);
+        let bumped = vol.get_volatility(&expiry_dates[1], 5.0, 0.02, 0.03);
+        assert!((bumped - 0.0091).abs() < 1.0e-7, "bumped: {}", bumped);
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+use crate::definitions::{Real, Time};
+use crate::evaluation_date::EvaluationDate;
+use crate::instruments::credit_default_swap::CreditDefaultSwap;
+use crate::parameters::zero_curve::ZeroCurve;
+use crate::pricing_engines::credit_default_swap_pricer::cds_legs;
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use anyhow::{anyhow, Context, Result};
+use std::{cell::RefCell, rc::Rc};
+use time::OffsetDateTime;
+
+const BOOTSTRAP_MAX_ITERATION: usize = 60;
+const BOOTSTRAP_MAX_HAZARD_RATE: Real = 10.0;
+
+/// Survival probability curve of piecewise flat hazard rates.
+/// hazard_rates[i] is the hazard rate on (pillar_times[i-1], pillar_times[i]]
+/// where the first one starts from the evaluation date and the last one is extended after the last pillar.
+#[derive(Clone, Debug)]
+pub struct SurvivalCurve {
+    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    pillar_times: Vec<Time>,
+    hazard_rates: Vec<Real>,
+    time_calculator: NullCalendar,
+    name: String,
+    code: String,
+}
+
+impl SurvivalCurve {
+    pub fn new(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        pillar_times: Vec<Time>,
+        hazard_rates: Vec<Real>,
+        name: String,
+        code: String,
+    ) -> Result<SurvivalCurve> {
+        if pillar_times.is_empty() || pillar_times.len() != hazard_rates.len() {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has pillar_times = {:?} and hazard_rates = {:?}",
+                file!(),
+                line!(),
+                name,
+                code,
+                pillar_times,
+                hazard_rates
+            ));
+        }
+        if pillar_times.windows(2).any(|w| w[0] >= w[1]) || pillar_times[0] <= 0.0 {
+            return Err(anyhow!(
+                "({}:{}) pillar_times of {} ({}) must be positive and increasing: {:?}",
+                file!(),
+                line!(),
+                name,
+                code,
+                pillar_times
+            ));
+        }
+        Ok(SurvivalCurve {
+            evaluation_date,
+            pillar_times,
+            hazard_rates,
+            time_calculator: NullCalendar::default(),
+            name,
+            code,
+        })
+    }
+
+    /// Bootstrap the hazard rates so that the CDS of each quote is priced at par,
+    /// i.e., the par spread of the CDS is the quote.
+    /// quotes are the pairs of CDS and its par spread, where the CDS gives the conventions of the pillar
+    /// (its coupon rate is ignored), and recovery_rate is used for all pillars.
+    pub fn bootstrap(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        discount_curve: &ZeroCurve,
+        quotes: &[(CreditDefaultSwap, Real)],
+        recovery_rate: Real,
+        name: String,
+        code: String,
+    ) -> Result<SurvivalCurve> {
+        let eval_date = evaluation_date.borrow().get_date_clone();
+        let time_calculator = NullCalendar::default();
+        let mut quotes = quotes
+            .iter()
+            .map(|(cds, spread)| {
+                let maturity = *crate::instrument::InstrumentTrait::get_maturity(cds).unwrap();
+                (
+                    time_calculator.get_time_difference(&eval_date, &maturity),
+                    cds,
+                    *spread,
+                )
+            })
+            .collect::<Vec<_>>();
+        quotes.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let mut pillar_times = vec![];
+        let mut hazard_rates = vec![];
+        for (t, cds, spread) in quotes.iter() {
+            let cds = cds.with_coupon_and_recovery(*spread, recovery_rate)?;
+            pillar_times.push(*t);
+            hazard_rates.push(0.0);
+            let residual = |hazard_rates: &Vec<Real>| -> Result<Real> {
+                let curve = SurvivalCurve::new(
+                    evaluation_date.clone(),
+                    pillar_times.clone(),
+                    hazard_rates.clone(),
+                    name.clone(),
+                    code.clone(),
+                )?;
+                let legs = cds_legs(&eval_date, &cds, discount_curve, &curve)?;
+                Ok(legs.get_par_spread() - spread)
+            };
+            // the par spread is increasing in the hazard rate
+            let (mut low, mut high) = (0.0, BOOTSTRAP_MAX_HAZARD_RATE);
+            let last = hazard_rates.len() - 1;
+            hazard_rates[last] = high;
+            if residual(&hazard_rates)? < 0.0 {
+                return Err(anyhow!(
+                    "({}:{}) the par spread {} at {} in {} ({}) is too large to be bootstrapped",
+                    file!(),
+                    line!(),
+                    spread,
+                    t,
+                    name,
+                    code
+                ));
+            }
+            for _ in 0..BOOTSTRAP_MAX_ITERATION {
+                hazard_rates[last] = 0.5 * (low + high);
+                match residual(&hazard_rates).with_context(|| {
+                    anyhow!(
+                        "({}:{}) failed to bootstrap {} ({}) at {}",
+                        file!(),
+                        line!(),
+                        name,
+                        code,
+                        t
+                    )
+                })? < 0.0
+                {
+                    true => low = hazard_rates[last],
+                    false => high = hazard_rates[last],
+                }
+                if high - low < 1.0e-7 {
+                    break;
+                }
+            }
+            hazard_rates[last] = 0.5 * (low + high);
+        }
+        SurvivalCurve::new(evaluation_date, pillar_times, hazard_rates, name, code)
+    }
+
+    /// integrated hazard rate from the evaluation date to time
+    fn integrated_hazard_rate(&self, time: Time) -> Real {
+        let mut res = 0.0;
+        let mut previous = 0.0;
+        for (pillar, hazard_rate) in self.pillar_times.iter().zip(self.hazard_rates.iter()) {
+            if time <= *pillar {
+                return res + hazard_rate * (time - previous);
+            }
+            res += hazard_rate * (pillar - previous);
+            previous = *pillar;
+        }
+        res + self.hazard_rates.last().unwrap() * (time - previous)
+    }
+
+    pub fn get_survival_probability(&self, time: Time) -> Real {
+        match time <= 0.0 {
+            true => 1.0,
+            false => (-self.integrated_hazard_rate(time)).exp(),
+        }
+    }
+
+    pub fn get_survival_probability_at_date(&self, date: &OffsetDateTime) -> Real {
+        let t = self
+            .time_calculator
+            .get_time_difference(&self.evaluation_date.borrow().get_date_clone(), date);
+        self.get_survival_probability(t)
+    }
+
+    pub fn get_pillar_times(&self) -> &Vec<Time> {
+        &self.pillar_times
+    }
+
+    pub fn get_hazard_rates(&self) -> &Vec<Real> {
+        &self.hazard_rates
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_code(&self) -> &String {
+        &self.code
+    }
+}
@@ -0,0 +1,687 @@
+use crate::data::fx_volatility_quote_data::FxVolatilityQuoteData;
+use crate::definitions::{Real, Time};
+use crate::enums::{FxAtmType, FxDeltaType, FxStrangleType};
+use crate::evaluation_date::EvaluationDate;
+use crate::parameters::{volatility::VolatilityTrait, zero_curve::ZeroCurve};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use statrs::distribution::{Continuous, ContinuousCDF, Normal};
+use std::{cell::RefCell, rc::Rc};
+use time::OffsetDateTime;
+
+/// the delta of the wing quotes
+const FX_WING_DELTA: f64 = 0.25;
+const FX_STRIKE_MAX_ITERATION: usize = 100;
+const FX_STRIKE_TOLERANCE: f64 = 1.0e-12;
+/// the search range of the log forward moneyness in the total deviation
+const FX_STRIKE_SEARCH_DEVIATIONS: f64 = 10.0;
+
+/// bisection of a function which changes its sign on [lower, upper]
+fn bisection<F: Fn(f64) -> f64>(f: F, mut lower: f64, mut upper: f64) -> f64 {
+    let f_lower = f(lower);
+    for _ in 0..FX_STRIKE_MAX_ITERATION {
+        let mid = 0.5 * (lower + upper);
+        if (f(mid) > 0.0) == (f_lower > 0.0) {
+            lower = mid;
+        } else {
+            upper = mid;
+        }
+        if upper - lower < FX_STRIKE_TOLERANCE {
+            break;
+        }
+    }
+    0.5 * (lower + upper)
+}
+
+/// Black value of the option on the unit forward at the log forward moneyness x and the total deviation s
+fn undiscounted_black(x: f64, s: f64, is_call: bool) -> f64 {
+    let normal = Normal::new(0.0, 1.0).unwrap();
+    let d1 = -x / s + 0.5 * s;
+    let d2 = d1 - s;
+    match is_call {
+        true => normal.cdf(d1) - x.exp() * normal.cdf(d2),
+        false => x.exp() * normal.cdf(-d2) - normal.cdf(-d1),
+    }
+}
+
+/// Log forward moneyness ln(K / F) of the option whose absolute delta is delta in delta_type,
+/// where s is the total deviation and df is the discount factor of the base currency.
+/// The premium-adjusted call delta DF K / F N(d2) is not monotone in the strike,
+/// and the strike is taken above the maximum of the delta as in the market convention.
+fn log_moneyness_from_delta(
+    delta: f64,
+    is_call: bool,
+    s: f64,
+    df: f64,
+    delta_type: FxDeltaType,
+) -> Result<f64> {
+    let normal = Normal::new(0.0, 1.0).unwrap();
+    let df = match delta_type {
+        FxDeltaType::Spot | FxDeltaType::SpotPremiumAdjusted => df,
+        FxDeltaType::Forward | FxDeltaType::ForwardPremiumAdjusted => 1.0,
+    };
+    let target = delta / df;
+    if target <= 0.0 || target >= 1.0 {
+        return Err(anyhow!(
+            "({}:{}) delta {} is out of range on the discount factor {}",
+            file!(),
+            line!(),
+            delta,
+            df
+        ));
+    }
+    match (delta_type, is_call) {
+        // N(d1) = target where d1 = -x / s + s / 2
+        (FxDeltaType::Spot | FxDeltaType::Forward, true) => {
+            Ok(0.5 * s * s - s * normal.inverse_cdf(target))
+        }
+        // N(-d1) = target
+        (FxDeltaType::Spot | FxDeltaType::Forward, false) => {
+            Ok(0.5 * s * s + s * normal.inverse_cdf(target))
+        }
+        // e^x N(d2) = target where d2 = -x / s - s / 2, decreasing above the maximum of the delta
+        (FxDeltaType::SpotPremiumAdjusted | FxDeltaType::ForwardPremiumAdjusted, true) => {
+            let d2 = |x: f64| -x / s - 0.5 * s;
+            let slope = |x: f64| normal.cdf(d2(x)) - normal.pdf(d2(x)) / s;
+            let range = FX_STRIKE_SEARCH_DEVIATIONS * s;
+            let maximum = bisection(slope, -0.5 * s * s - range, 0.5 * s * s);
+            let premium_adjusted = |x: f64| x.exp() * normal.cdf(d2(x)) - target;
+            if premium_adjusted(maximum) < 0.0 {
+                return Err(anyhow!(
+                    "({}:{}) premium-adjusted call delta {} is above the maximum {} (total deviation = {})",
+                    file!(),
+                    line!(),
+                    delta,
+                    (premium_adjusted(maximum) + target) * df,
+                    s
+                ));
+            }
+            Ok(bisection(premium_adjusted, maximum, 0.5 * s * s + range))
+        }
+        // e^x N(-d2) = target, which is increasing in x
+        (FxDeltaType::SpotPremiumAdjusted | FxDeltaType::ForwardPremiumAdjusted, false) => {
+            let premium_adjusted = |x: f64| x.exp() * normal.cdf(x / s + 0.5 * s) - target;
+            let range = FX_STRIKE_SEARCH_DEVIATIONS * s;
+            Ok(bisection(
+                premium_adjusted,
+                -0.5 * s * s - range,
+                0.5 * s * s + range,
+            ))
+        }
+    }
+}
+
+/// Smile of an expiry on the 25-delta put, the at-the-money and the 25-delta call
+/// in the log forward moneyness ln(K / F) and the implied volatility.
+/// The volatility is quadratic in the log forward moneyness between the wings and flat outside.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FxSmile {
+    log_moneyness: [Real; 3],
+    volatilities: [Real; 3],
+}
+
+impl FxSmile {
+    pub fn get_log_moneyness(&self) -> &[Real; 3] {
+        &self.log_moneyness
+    }
+
+    pub fn get_volatilities(&self) -> &[Real; 3] {
+        &self.volatilities
+    }
+
+    fn value(&self, x: f64) -> f64 {
+        let k = self.log_moneyness.map(|y| y as f64);
+        let v = self.volatilities.map(|y| y as f64);
+        if x <= k[0] {
+            return v[0];
+        }
+        if x >= k[2] {
+            return v[2];
+        }
+        v[0] * (x - k[1]) * (x - k[2]) / ((k[0] - k[1]) * (k[0] - k[2]))
+            + v[1] * (x - k[0]) * (x - k[2]) / ((k[1] - k[0]) * (k[1] - k[2]))
+            + v[2] * (x - k[0]) * (x - k[1]) / ((k[2] - k[0]) * (k[2] - k[1]))
+    }
+}
+
+/// FX implied volatility surface from the at-the-money, 25-delta risk reversal and 25-delta butterfly quotes.
+///
+/// On each expiry, the 25-delta call and put volatilities are ATM + SS + RR / 2 and ATM + SS - RR / 2,
+/// and their strikes are solved in the delta convention of the quotes on the discount curve of the base currency.
+/// The smile strangle SS is the butterfly quote in FxStrangleType::SmileStrangle. For the market strangle,
+/// SS is calibrated so that the smile prices the strangle at the 25-delta strikes on the volatility ATM + BF. The at-the-money strike is the forward
+/// or the delta-neutral straddle, which is F exp(s^2 / 2) and F exp(-s^2 / 2) if premium-adjusted.
+///
+/// The smiles are in the log forward moneyness, so that they move with the forward (sticky delta).
+/// Between the expiries, the total variance is linearly interpolated in time at the same log forward moneyness,
+/// and before the first expiry and after the last expiry, the implied volatility is flat in time.
+#[derive(Debug, Clone)]
+pub struct FxVolatilitySurface {
+    expiry_dates: Vec<OffsetDateTime>,
+    expiry_times: Vec<Time>,
+    smiles: Vec<FxSmile>,
+    delta_type: FxDeltaType,
+    atm_type: FxAtmType,
+    strangle_type: FxStrangleType,
+    //
+    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    base_currency_curve: Rc<RefCell<ZeroCurve>>,
+    //
+    name: String,
+    code: String,
+}
+
+impl FxVolatilitySurface {
+    pub fn new(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        base_currency_curve: Rc<RefCell<ZeroCurve>>,
+        data: &FxVolatilityQuoteData,
+        name: String,
+        code: String,
+    ) -> Result<FxVolatilitySurface> {
+        let mut surface = FxVolatilitySurface {
+            expiry_dates: Vec::new(),
+            expiry_times: Vec::new(),
+            smiles: Vec::new(),
+            delta_type: data.get_delta_type(),
+            atm_type: data.get_atm_type(),
+            strangle_type: data.get_strangle_type(),
+            evaluation_date,
+            base_currency_curve,
+            name,
+            code,
+        };
+        surface.set_smiles(data).with_context(|| {
+            anyhow!(
+                "({}:{}) failed to build the smiles of {} ({}) from {}",
+                file!(),
+                line!(),
+                surface.name,
+                surface.code,
+                data.get_name()
+            )
+        })?;
+        Ok(surface)
+    }
+
+    fn set_smiles(&mut self, data: &FxVolatilityQuoteData) -> Result<()> {
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let expiry_dates = data.get_expiry_dates();
+        let (atm, risk_reversal, butterfly) = (
+            data.get_atm(),
+            data.get_risk_reversal_25(),
+            data.get_butterfly_25(),
+        );
+        if expiry_dates.is_empty()
+            || expiry_dates.windows(2).any(|w| w[0] >= w[1])
+            || expiry_dates[0] <= eval_date
+        {
+            return Err(anyhow!(
+                "({}:{}) expiry dates must be increasing and after the evaluation date {}: {:?}",
+                file!(),
+                line!(),
+                eval_date,
+                expiry_dates
+            ));
+        }
+        if atm.len() != expiry_dates.len()
+            || risk_reversal.len() != expiry_dates.len()
+            || butterfly.len() != expiry_dates.len()
+        {
+            return Err(anyhow!(
+                "({}:{}) {} expiries but {} ATM, {} RR and {} BF quotes",
+                file!(),
+                line!(),
+                expiry_dates.len(),
+                atm.len(),
+                risk_reversal.len(),
+                butterfly.len()
+            ));
+        }
+
+        let time_calculator = NullCalendar::new();
+        let mut expiry_times = Vec::new();
+        let mut smiles = Vec::new();
+        for (i, date) in expiry_dates.iter().enumerate() {
+            let t = time_calculator.get_time_difference(&eval_date, date);
+            let df = self
+                .base_currency_curve
+                .borrow()
+                .get_discount_factor_at_date(date)? as f64;
+            let sqrt_t = (t as f64).sqrt();
+            let (atm_vol, rr, bf) = (atm[i] as f64, risk_reversal[i] as f64, butterfly[i] as f64);
+            let smile = match self.strangle_type {
+                FxStrangleType::SmileStrangle => self.smile(sqrt_t, df, atm_vol, rr, bf),
+                FxStrangleType::MarketStrangle => self
+                    .smile_strangle(sqrt_t, df, atm_vol, rr, bf)
+                    .and_then(|ss| self.smile(sqrt_t, df, atm_vol, rr, ss)),
+            }
+            .with_context(|| {
+                anyhow!(
+                    "({}:{}) failed to build the smile on {}",
+                    file!(),
+                    line!(),
+                    date
+                )
+            })?;
+            expiry_times.push(t);
+            smiles.push(smile);
+        }
+        self.expiry_dates = expiry_dates.clone();
+        self.expiry_times = expiry_times;
+        self.smiles = smiles;
+        Ok(())
+    }
+
+    /// smile on the ATM, 25-delta risk reversal and smile strangle volatilities
+    fn smile(
+        &self,
+        sqrt_t: f64,
+        df: f64,
+        atm_vol: f64,
+        risk_reversal: f64,
+        smile_strangle: f64,
+    ) -> Result<FxSmile> {
+        let call_vol = atm_vol + smile_strangle + 0.5 * risk_reversal;
+        let put_vol = atm_vol + smile_strangle - 0.5 * risk_reversal;
+        if atm_vol <= 0.0 || call_vol <= 0.0 || put_vol <= 0.0 {
+            return Err(anyhow!(
+                "({}:{}) non-positive volatility: ATM = {}, 25C = {}, 25P = {}",
+                file!(),
+                line!(),
+                atm_vol,
+                call_vol,
+                put_vol
+            ));
+        }
+
+        let s = atm_vol * sqrt_t;
+        let atm_moneyness = match (self.atm_type, self.delta_type) {
+            (FxAtmType::AtmForward, _) => 0.0,
+            (FxAtmType::DeltaNeutralStraddle, FxDeltaType::Spot | FxDeltaType::Forward) => {
+                0.5 * s * s
+            }
+            (FxAtmType::DeltaNeutralStraddle, _) => -0.5 * s * s,
+        };
+        let put_moneyness =
+            log_moneyness_from_delta(FX_WING_DELTA, false, put_vol * sqrt_t, df, self.delta_type)?;
+        let call_moneyness =
+            log_moneyness_from_delta(FX_WING_DELTA, true, call_vol * sqrt_t, df, self.delta_type)?;
+        if !(put_moneyness < atm_moneyness && atm_moneyness < call_moneyness) {
+            return Err(anyhow!(
+                "({}:{}) the strikes of 25P, ATM and 25C are not increasing: {}, {}, {}",
+                file!(),
+                line!(),
+                put_moneyness,
+                atm_moneyness,
+                call_moneyness
+            ));
+        }
+        Ok(FxSmile {
+            log_moneyness: [
+                put_moneyness as Real,
+                atm_moneyness as Real,
+                call_moneyness as Real,
+            ],
+            volatilities: [put_vol as Real, atm_vol as Real, call_vol as Real],
+        })
+    }
+
+    /// Smile strangle whose smile prices the market strangle, that is, the 25-delta call and put
+    /// on the single volatility ATM + BF at their strikes in the delta convention on that volatility.
+    /// The strangle value is increasing in the smile strangle, which is solved by bisection
+    /// while the wings of the smile stay positive.
+    fn smile_strangle(
+        &self,
+        sqrt_t: f64,
+        df: f64,
+        atm_vol: f64,
+        risk_reversal: f64,
+        market_strangle: f64,
+    ) -> Result<f64> {
+        let market_vol = atm_vol + market_strangle;
+        if market_vol <= 0.0 {
+            return Err(anyhow!(
+                "({}:{}) non-positive market strangle volatility {}",
+                file!(),
+                line!(),
+                market_vol
+            ));
+        }
+        let s = market_vol * sqrt_t;
+        let call_moneyness = log_moneyness_from_delta(FX_WING_DELTA, true, s, df, self.delta_type)?;
+        let put_moneyness = log_moneyness_from_delta(FX_WING_DELTA, false, s, df, self.delta_type)?;
+        let target = undiscounted_black(call_moneyness, s, true)
+            + undiscounted_black(put_moneyness, s, false);
+        let difference = |smile_strangle: f64| -> Result<f64> {
+            let smile = self.smile(sqrt_t, df, atm_vol, risk_reversal, smile_strangle)?;
+            let value =
+                |x: f64, is_call: bool| undiscounted_black(x, smile.value(x) * sqrt_t, is_call);
+            Ok(value(call_moneyness, true) + value(put_moneyness, false) - target)
+        };
+
+        // the put and the call wings are halved from the market strangle volatility at the lower bound
+        let mut lower = market_strangle - 0.5 * (market_vol - 0.5 * risk_reversal.abs());
+        let mut upper = market_strangle + atm_vol;
+        let (f_lower, f_upper) = (difference(lower)?, difference(upper)?);
+        if f_lower > 0.0 || f_upper < 0.0 {
+            return Err(anyhow!(
+                "({}:{}) the market strangle {} is not bracketed in the smile strangle [{}, {}]",
+                file!(),
+                line!(),
+                market_strangle,
+                lower,
+                upper
+            ));
+        }
+        for _ in 0..FX_STRIKE_MAX_ITERATION {
+            let mid = 0.5 * (lower + upper);
+            if difference(mid)? > 0.0 {
+                upper = mid;
+            } else {
+                lower = mid;
+            }
+            if upper - lower < FX_STRIKE_TOLERANCE {
+                break;
+            }
+        }
+        Ok(0.5 * (lower + upper))
+    }
+
+    fn total_variance_at(&self, t: Time, x: f64) -> f64 {
+        let times = &self.expiry_times;
+        let n = times.len();
+        let t = t as f64;
+        let slice = |i: usize| self.smiles[i].value(x).powi(2) * times[i] as f64;
+        if t <= times[0] as f64 {
+            return slice(0) * t / times[0] as f64;
+        }
+        if t >= times[n - 1] as f64 {
+            return slice(n - 1) * t / times[n - 1] as f64;
+        }
+        let i = times.partition_point(|s| (*s as f64) <= t) - 1;
+        let (t0, t1) = (times[i] as f64, times[i + 1] as f64);
+        slice(i) + (t - t0) / (t1 - t0) * (slice(i + 1) - slice(i))
+    }
+
+    pub fn get_expiry_dates(&self) -> &Vec<OffsetDateTime> {
+        &self.expiry_dates
+    }
+
+    pub fn get_expiry_times(&self) -> &Vec<Time> {
+        &self.expiry_times
+    }
+
+    pub fn get_smiles(&self) -> &Vec<FxSmile> {
+        &self.smiles
+    }
+
+    pub fn get_delta_type(&self) -> FxDeltaType {
+        self.delta_type
+    }
+
+    pub fn get_atm_type(&self) -> FxAtmType {
+        self.atm_type
+    }
+
+    pub fn get_strangle_type(&self) -> FxStrangleType {
+        self.strangle_type
+    }
+}
+
+impl VolatilityTrait for FxVolatilitySurface {
+    fn get_value(&self, t: Time, forward_moneyness: Real) -> Real {
+        let x = (forward_moneyness as f64).ln();
+        let t = t.max(1.0e-6);
+        (self.total_variance_at(t, x).max(0.0) / t as f64).sqrt() as Real
+    }
+
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    fn total_variance(&self, t: Time, forward_moneyness: Real) -> Result<Real> {
+        if forward_moneyness <= 0.0 {
+            return Err(anyhow!(
+                "({}:{}) non-positive forward moneyness {} in {} ({})",
+                file!(),
+                line!(),
+                forward_moneyness,
+                self.name,
+                self.code
+            ));
+        }
+        if t <= 0.0 {
+            return Ok(0.0);
+        }
+        let x = (forward_moneyness as f64).ln();
+        Ok(self.total_variance_at(t, x).max(0.0) as Real)
+    }
+
+    fn total_deviation(&self, t: Time, forward_moneyness: Real) -> Result<Real> {
+        Ok(self.total_variance(t, forward_moneyness)?.sqrt())
+    }
+
+    /// add bump to the volatilities of the smiles on the expiries in time1 < t <= time2.
+    /// Since the smiles are quoted in delta, the moneyness range is on the forward moneyness of the quotes,
+    /// and their strikes are kept.
+    fn bump_volatility(
+        &mut self,
+        time1: Option<Time>,
+        time2: Option<Time>,
+        left_spot_moneyness: Option<Real>,
+        right_spot_moneyness: Option<Real>,
+        bump: Real,
+    ) -> Result<()> {
+        let time1 = time1.unwrap_or(Time::MIN + 10.0);
+        let time2 = time2.unwrap_or(Time::MAX - 10.0);
+        let left = left_spot_moneyness.unwrap_or(Real::MIN + 10.0);
+        let right = right_spot_moneyness.unwrap_or(Real::MAX - 10.0);
+        let eps = 1.0e-4;
+        for (t, smile) in self.expiry_times.iter().zip(self.smiles.iter_mut()) {
+            if !(time1 + eps < *t && *t <= time2 + eps) {
+                continue;
+            }
+            for (x, vol) in smile
+                .log_moneyness
+                .iter()
+                .zip(smile.volatilities.iter_mut())
+            {
+                let moneyness = x.exp();
+                if left + eps < moneyness && moneyness <= right + eps {
+                    *vol += bump;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::{Currency, FxCode};
+    use crate::data::vector_data::VectorData;
+    use ndarray::array;
+    use time::macros::datetime;
+
+    /// the absolute delta of the option at the log forward moneyness x in delta_type
+    fn delta(x: f64, vol: f64, t: f64, df: f64, is_call: bool, delta_type: FxDeltaType) -> f64 {
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        let s = vol * t.sqrt();
+        let d1 = -x / s + 0.5 * s;
+        let d2 = d1 - s;
+        let (n1, n2) = match is_call {
+            true => (normal.cdf(d1), normal.cdf(d2)),
+            false => (normal.cdf(-d1), normal.cdf(-d2)),
+        };
+        match delta_type {
+            FxDeltaType::Spot => df * n1,
+            FxDeltaType::Forward => n1,
+            FxDeltaType::SpotPremiumAdjusted => df * x.exp() * n2,
+            FxDeltaType::ForwardPremiumAdjusted => x.exp() * n2,
+        }
+    }
+
+    #[test]
+    fn test_fx_volatility_surface() -> Result<()> {
+        let dt = datetime!(2024-01-02 16:00:00 +09:00);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(dt)));
+        let curve_data = VectorData::new(
+            array![0.05, 0.05],
+            None,
+            Some(array![0.5, 5.0]),
+            None,
+            Currency::USD,
+            "USDOIS".to_string(),
+            "USDOIS".to_string(),
+        )?;
+        let usd_curve = Rc::new(RefCell::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &curve_data,
+            "USDOIS".to_string(),
+            "USDOIS".to_string(),
+        )?));
+        let expiry_dates = vec![
+            datetime!(2024-04-02 16:00:00 +09:00),
+            datetime!(2025-01-02 16:00:00 +09:00),
+        ];
+        let quotes = |delta_type: FxDeltaType, atm_type: FxAtmType| {
+            FxVolatilityQuoteData::new(
+                expiry_dates.clone(),
+                array![0.08, 0.09],
+                array![0.01, 0.015],
+                array![0.003, 0.004],
+                delta_type,
+                atm_type,
+                Some(dt),
+                FxCode::new(Currency::USD, Currency::KRW),
+                "USDKRW vol".to_string(),
+                "USDKRW vol".to_string(),
+            )
+        };
+
+        for delta_type in [
+            FxDeltaType::Spot,
+            FxDeltaType::Forward,
+            FxDeltaType::SpotPremiumAdjusted,
+            FxDeltaType::ForwardPremiumAdjusted,
+        ] {
+            let data = quotes(delta_type, FxAtmType::DeltaNeutralStraddle);
+            let surface = FxVolatilitySurface::new(
+                evaluation_date.clone(),
+                usd_curve.clone(),
+                &data,
+                "USDKRW".to_string(),
+                "USDKRW".to_string(),
+            )?;
+            for (i, smile) in surface.get_smiles().iter().enumerate() {
+                let t = surface.get_expiry_times()[i];
+                let df = usd_curve
+                    .borrow()
+                    .get_discount_factor_at_date(&expiry_dates[i])? as f64;
+                let x = smile.get_log_moneyness().map(|y| y as f64);
+                let v = smile.get_volatilities().map(|y| y as f64);
+                // the wings have the quoted delta
+                let put = delta(x[0], v[0], t as f64, df, false, delta_type);
+                let call = delta(x[2], v[2], t as f64, df, true, delta_type);
+                assert!(
+                    (put - 0.25).abs() < 1.0e-5,
+                    "{:?} put delta {}",
+                    delta_type,
+                    put
+                );
+                assert!(
+                    (call - 0.25).abs() < 1.0e-5,
+                    "{:?} call delta {}",
+                    delta_type,
+                    call
+                );
+                // the at-the-money straddle is delta neutral
+                let atm_call = delta(x[1], v[1], t as f64, df, true, delta_type);
+                let atm_put = delta(x[1], v[1], t as f64, df, false, delta_type);
+                assert!((atm_call - atm_put).abs() < 1.0e-5, "{:?}", delta_type);
+                // the smile reproduces the quotes and the risk reversal skews it to the calls
+                for j in 0..3 {
+                    let vol = surface.get_value(t, x[j].exp() as Real);
+                    assert!((vol as f64 - v[j]).abs() < 1.0e-5, "{} vs {}", vol, v[j]);
+                }
+                assert!(v[2] - v[0] > 0.0);
+
+                // the smile prices the market strangle at its strikes on ATM + BF
+                let (atm_vol, bf) = (data.get_atm()[i] as f64, data.get_butterfly_25()[i] as f64);
+                let s = (atm_vol + bf) * (t as f64).sqrt();
+                let call_x = log_moneyness_from_delta(0.25, true, s, df, delta_type)?;
+                let put_x = log_moneyness_from_delta(0.25, false, s, df, delta_type)?;
+                let market =
+                    undiscounted_black(call_x, s, true) + undiscounted_black(put_x, s, false);
+                let on_smile = |x: f64, is_call: bool| {
+                    let deviation = surface.total_deviation(t, x.exp() as Real).unwrap() as f64;
+                    undiscounted_black(x, deviation, is_call)
+                };
+                let smile_value = on_smile(call_x, true) + on_smile(put_x, false);
+                assert!(
+                    (smile_value - market).abs() < 1.0e-6,
+                    "{:?} smile strangle {} vs market strangle {}",
+                    delta_type,
+                    smile_value,
+                    market
+                );
+                // the smile strangle is above the market strangle on the skewed smile
+                assert!(0.5 * (v[0] + v[2]) - v[1] > bf);
+            }
+
+            // the butterfly is the smile strangle
+            let data = quotes(delta_type, FxAtmType::DeltaNeutralStraddle)
+                .with_strangle_type(FxStrangleType::SmileStrangle);
+            let surface = FxVolatilitySurface::new(
+                evaluation_date.clone(),
+                usd_curve.clone(),
+                &data,
+                "USDKRW".to_string(),
+                "USDKRW".to_string(),
+            )?;
+            for (i, smile) in surface.get_smiles().iter().enumerate() {
+                let v = smile.get_volatilities();
+                let (atm_vol, rr, bf) = (
+                    data.get_atm()[i],
+                    data.get_risk_reversal_25()[i],
+                    data.get_butterfly_25()[i],
+                );
+                assert!((v[0] - (atm_vol + bf - 0.5 * rr)).abs() < 1.0e-6);
+                assert!((v[2] - (atm_vol + bf + 0.5 * rr)).abs() < 1.0e-6);
+            }
+        }
+
+        // the at-the-money forward is on the forward
+        let data = quotes(FxDeltaType::Spot, FxAtmType::AtmForward);
+        let mut surface = FxVolatilitySurface::new(
+            evaluation_date.clone(),
+            usd_curve.clone(),
+            &data,
+            "USDKRW".to_string(),
+            "USDKRW".to_string(),
+        )?;
+        let t1 = surface.get_expiry_times()[1];
+        assert!((surface.get_value(t1, 1.0) - 0.09).abs() < 1.0e-6);
+        // total variance is linear in time between the expiries and the volatility is flat after the last one
+        let t0 = surface.get_expiry_times()[0];
+        let mid = 0.5 * (t0 + t1);
+        let expected = 0.5 * (0.08 * 0.08 * t0 + 0.09 * 0.09 * t1);
+        assert!((surface.total_variance(mid, 1.0)? - expected).abs() < 1.0e-6);
+        assert!((surface.get_value(2.0 * t1, 1.0) - 0.09).abs() < 1.0e-6);
+
+        surface.bump_volatility(None, None, None, None, 0.01)?;
+        assert!((surface.get_value(t1, 1.0) - 0.10).abs() < 1.0e-6);
+        surface.bump_volatility(Some(t0), None, None, None, -0.01)?;
+        assert!((surface.get_value(t1, 1.0) - 0.09).abs() < 1.0e-6);
+        assert!((surface.get_value(t0, 1.0) - 0.09).abs() < 1.0e-6);
+        Ok(())
+    }
+}
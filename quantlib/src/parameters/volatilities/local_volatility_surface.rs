@@ -7,17 +7,56 @@ use crate::math::interpolators::bilinear_interpolator::BilinearInterpolator;
 use crate::math::interpolators::linear_interpolator::LinearInterpolator1D;
 use crate::parameters::market_price::MarketPrice;
 use crate::parameters::{
-    volatilities::volatiltiy_interpolator::VolatilityInterplator, volatility::VolatilityTrait,
+    volatilities::volatiltiy_interpolator::{VolatilityInterplator, VolatilityInterplatorTrait},
+    volatility::VolatilityTrait,
     zero_curve::ZeroCurve,
 };
 use crate::time::calendar_trait::CalendarTrait;
 use crate::time::calendars::nullcalendar::NullCalendar;
 use crate::utils::string_arithmetic::add_period;
 use anyhow::{anyhow, Context, Result};
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::{OnceCell, RefCell},
+    rc::Rc,
+};
 //
 use ndarray::{Array1, Array2};
 use time::OffsetDateTime;
+
+const CALIBRATION_REUSE_TOLERANCE: Real = 1.0e-6;
+
+/// local volatility calibrated lazily on the first call of get_local_volatility,
+/// and the forward moneyness knots and the implied volatility it is calibrated to
+#[derive(Clone, Debug, Default)]
+struct LocalVolatilityCalibration {
+    forward_moneyness: Vec<Array1<Real>>,
+    implied_volatility: Array2<Real>,
+    local_volatility: OnceCell<BilinearInterpolator>,
+}
+
+impl LocalVolatilityCalibration {
+    fn is_calibrated_to(
+        &self,
+        forward_moneyness: &[Array1<Real>],
+        implied_volatility: &Array2<Real>,
+    ) -> bool {
+        // a bump and its reversal do not give back the same floats exactly
+        let close = |a: &Real, b: &Real| (a - b).abs() < CALIBRATION_REUSE_TOLERANCE;
+        self.forward_moneyness.len() == forward_moneyness.len()
+            && self
+                .forward_moneyness
+                .iter()
+                .zip(forward_moneyness)
+                .all(|(a, b)| a.len() == b.len() && a.iter().zip(b).all(|(x, y)| close(x, y)))
+            && self.implied_volatility.shape() == implied_volatility.shape()
+            && self
+                .implied_volatility
+                .iter()
+                .zip(implied_volatility)
+                .all(|(x, y)| close(x, y))
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct LocalVolatilitySurface {
     interpolated_imvol: Array2<Real>,
@@ -34,10 +73,10 @@ pub struct LocalVolatilitySurface {
     borrowing_curve: Rc<RefCell<ZeroCurve>>,
     //
     stickyness_type: StickynessType,
-    #[allow(dead_code)]
     lv_interpolator: VolatilityInterplator,
-    #[allow(dead_code)]
-    local_volatility: BilinearInterpolator,
+    /// the calibration of the current surface, and the previous one which is reused when a bump is put back
+    calibration: LocalVolatilityCalibration,
+    previous_calibration: LocalVolatilityCalibration,
     //
     name: String,
     code: String,
@@ -71,7 +110,8 @@ impl LocalVolatilitySurface {
             //
             stickyness_type,
             lv_interpolator,
-            local_volatility: BilinearInterpolator::default(),
+            calibration: LocalVolatilityCalibration::default(),
+            previous_calibration: LocalVolatilityCalibration::default(),
             //
             name,
             code,
@@ -299,6 +339,29 @@ impl LocalVolatilitySurface {
             ExtraPolationType::Flat,
         )?;
 
+        // the local volatility is calibrated on the first call of get_local_volatility,
+        // so the rebuilds in bumping do not calibrate unless a pricer uses the local volatility
+        if !self
+            .calibration
+            .is_calibrated_to(&forward_monenyess_array, &self.interpolated_imvol)
+        {
+            if self
+                .previous_calibration
+                .is_calibrated_to(&forward_monenyess_array, &self.interpolated_imvol)
+            {
+                std::mem::swap(&mut self.calibration, &mut self.previous_calibration);
+            } else {
+                self.previous_calibration = std::mem::replace(
+                    &mut self.calibration,
+                    LocalVolatilityCalibration {
+                        forward_moneyness: forward_monenyess_array,
+                        implied_volatility: self.interpolated_imvol.clone(),
+                        local_volatility: OnceCell::new(),
+                    },
+                );
+            }
+        }
+
         Ok(())
     }
 
@@ -376,8 +439,46 @@ impl VolatilityTrait for LocalVolatilitySurface {
             .expect("Failed to interpolate implied volatility")
     }
 
-    fn get_local_volatility(&self, _t: Time, _forward_moneyness: Real) -> Real {
-        panic!("Not implemented")
+    /// The local volatility is calibrated by lv_interpolator on the first call after build
+    fn get_local_volatility(&self, t: Time, forward_moneyness: Real) -> Result<Real> {
+        let local_volatility = match self.calibration.local_volatility.get() {
+            Some(local_volatility) => local_volatility,
+            None => {
+                let local_volatility = self
+                    .lv_interpolator
+                    .local_volatility(
+                        &self.imvol_maturity_times,
+                        &self.calibration.forward_moneyness,
+                        &self.calibration.implied_volatility,
+                    )
+                    .with_context(|| {
+                        anyhow!(
+                            "({}:{}) failed to calibrate local volatility of {} ({})",
+                            file!(),
+                            line!(),
+                            self.name,
+                            self.code
+                        )
+                    })?;
+                self.calibration
+                    .local_volatility
+                    .get_or_init(|| local_volatility)
+            }
+        };
+        local_volatility
+            .interpolate(t, forward_moneyness)
+            .with_context(|| {
+                anyhow!(
+                    "({}:{}) failed to interpolate local volatility\n\
+                t: {}, forward_moneyness: {}, name: {}, code: {}",
+                    file!(),
+                    line!(),
+                    t,
+                    forward_moneyness,
+                    self.name,
+                    self.code
+                )
+            })
     }
 
     fn get_name(&self) -> &String {
@@ -549,6 +650,30 @@ mod tests {
 
         local_volatility_surface.build()?;
 
+        // the local volatility is calibrated on the first use, and a bump put back reuses the calibration
+        assert!(local_volatility_surface
+            .calibration
+            .local_volatility
+            .get()
+            .is_none());
+        let local_volatility = local_volatility_surface.get_local_volatility(1.0, 1.0)?;
+        local_volatility_surface.bump_volatility(None, None, None, None, 0.01)?;
+        assert!(local_volatility_surface
+            .calibration
+            .local_volatility
+            .get()
+            .is_none());
+        local_volatility_surface.bump_volatility(None, None, None, None, -0.01)?;
+        assert!(local_volatility_surface
+            .calibration
+            .local_volatility
+            .get()
+            .is_some());
+        assert_eq!(
+            local_volatility_surface.get_local_volatility(1.0, 1.0)?,
+            local_volatility
+        );
+
         let mut calc_vol = Array2::zeros((times.len(), vega_spot_moneyness.len()));
 
         for i in 0..times.len() {
@@ -644,4 +769,54 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_local_volatility_calibration_error() -> Result<()> {
+        let eval_date = datetime!(2024-01-02 00:00:00 +09:00);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_date)));
+        let equity = Rc::new(RefCell::new(MarketPrice::new(
+            350.0,
+            eval_date,
+            None,
+            Currency::KRW,
+            "KOSPI2".to_string(),
+            "KOSPI2".to_string(),
+        )));
+        let dummy_data = vectordatasample!(0.00, Currency::KRW, "mock curve data")?;
+        let zero_curve = Rc::new(RefCell::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &dummy_data,
+            "KRWGOV".to_string(),
+            "zero curve".to_string(),
+        )?));
+        let constant_volatility = ValueData::new(
+            0.2,
+            Some(eval_date),
+            Currency::KRW,
+            "KOSPI2".to_string(),
+            "KOSPI2".to_string(),
+        )?;
+
+        // a single moneyness can not be calibrated, which is reported by get_local_volatility instead of a panic in the pricers
+        let mut local_volatility_surface = LocalVolatilitySurface::initialize(
+            evaluation_date.clone(),
+            equity.clone(),
+            zero_curve.clone(),
+            zero_curve.clone(),
+            StickynessType::default(),
+            VolatilityInterplator::AndreasenHuge(AndreasenHuge::default()),
+            "local vol".to_string(),
+            "local vol".to_string(),
+        )
+        .with_constant_volatility(
+            &constant_volatility,
+            vec!["3M".to_string(), "1Y".to_string()],
+            array![1.0],
+        )?;
+        local_volatility_surface.build()?;
+        assert!(local_volatility_surface
+            .get_local_volatility(0.5, 1.0)
+            .is_err());
+        Ok(())
+    }
 }
@@ -1,3 +1,5 @@
 pub mod constant_volatility;
+pub mod fx_volatility_surface;
 pub mod local_volatility_surface;
+pub mod svi_volatility_surface;
 pub mod volatiltiy_interpolator;
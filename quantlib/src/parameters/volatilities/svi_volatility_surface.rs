@@ -0,0 +1,1047 @@
+use crate::data::surface_data::SurfaceData;
+use crate::definitions::{Real, Time};
+use crate::enums::{StickynessType, VolatilitySurfaceType};
+use crate::evaluation_date::EvaluationDate;
+use crate::parameters::{
+    market_price::MarketPrice, volatility::VolatilityTrait, zero_curve::ZeroCurve,
+};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use anyhow::{anyhow, Context, Result};
+use argmin::core::{CostFunction, Error, Executor};
+use argmin::solver::neldermead::NelderMead;
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, rc::Rc};
+use time::OffsetDateTime;
+
+const SVI_CALIBRATION_MAX_ITERATION: u64 = 2_000;
+const SVI_CALIBRATION_ROUND: usize = 3;
+const SVI_PENALTY: f64 = 1.0e3;
+const SVI_ARBITRAGE_TOLERANCE: f64 = 1.0e-6;
+
+/// log forward moneyness on which the static arbitrage is checked
+fn arbitrage_check_grid() -> Vec<f64> {
+    (-40..=40).map(|i| i as f64 * 0.05).collect()
+}
+
+/// Durrleman's condition. The smile is free of butterfly arbitrage where g >= 0.
+/// w, w1, w2 are the total variance and its first and second derivatives in the log forward moneyness k
+fn butterfly_density(k: f64, w: f64, w1: f64, w2: f64) -> f64 {
+    let x = 1.0 - k * w1 / (2.0 * w);
+    x * x - 0.25 * w1 * w1 * (1.0 / w + 0.25) + 0.5 * w2
+}
+
+/// Raw SVI of Gatheral (2004) on a single expiry:
+/// w(k) = a + b (rho (k - m) + sqrt((k - m)^2 + sigma^2))
+/// where w is the total variance and k is the log forward moneyness
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RawSviParameters {
+    a: Real,
+    b: Real,
+    rho: Real,
+    m: Real,
+    sigma: Real,
+}
+
+impl RawSviParameters {
+    pub fn new(a: Real, b: Real, rho: Real, m: Real, sigma: Real) -> RawSviParameters {
+        RawSviParameters {
+            a,
+            b,
+            rho,
+            m,
+            sigma,
+        }
+    }
+
+    /// unconstrained parameters of the calibration: (a, ln b, atanh rho, m, ln sigma)
+    fn from_unconstrained(x: &[f64]) -> RawSviParameters {
+        RawSviParameters {
+            a: x[0] as Real,
+            b: x[1].clamp(-30.0, 5.0).exp() as Real,
+            rho: x[2].tanh().clamp(-0.999, 0.999) as Real,
+            m: x[3] as Real,
+            sigma: x[4].clamp(-30.0, 5.0).exp() as Real,
+        }
+    }
+
+    /// (w, dw/dk, d^2w/dk^2)
+    fn evaluate(&self, k: f64) -> (f64, f64, f64) {
+        let (a, b, rho, m, sigma) = (
+            self.a as f64,
+            self.b as f64,
+            self.rho as f64,
+            self.m as f64,
+            self.sigma as f64,
+        );
+        let x = k - m;
+        let r = (x * x + sigma * sigma).sqrt();
+        (
+            a + b * (rho * x + r),
+            b * (rho + x / r),
+            b * sigma * sigma / (r * r * r),
+        )
+    }
+
+    /// minimum of the total variance attained at k = m - rho sigma / sqrt(1 - rho^2)
+    fn minimum_total_variance(&self) -> f64 {
+        let rho = self.rho as f64;
+        self.a as f64 + (self.b * self.sigma) as f64 * (1.0 - rho * rho).sqrt()
+    }
+
+    /// the slope of the total variance in the wings, bounded by 2 by Lee's moment formula
+    fn wing_slope(&self) -> f64 {
+        (self.b * (1.0 + self.rho.abs())) as f64
+    }
+
+    pub fn get_a(&self) -> Real {
+        self.a
+    }
+
+    pub fn get_b(&self) -> Real {
+        self.b
+    }
+
+    pub fn get_rho(&self) -> Real {
+        self.rho
+    }
+
+    pub fn get_m(&self) -> Real {
+        self.m
+    }
+
+    pub fn get_sigma(&self) -> Real {
+        self.sigma
+    }
+}
+
+/// SSVI of Gatheral and Jacquier (2014) with the power-law phi:
+/// w(k, theta) = theta / 2 (1 + rho phi k + sqrt((phi k + rho)^2 + 1 - rho^2)),
+/// phi(theta) = eta / (theta^gamma (1 + theta)^(1 - gamma))
+/// where theta is the at-the-money total variance on each expiry.
+/// The surface is free of static arbitrage if eta (1 + |rho|) <= 2, 0 < gamma <= 1/2,
+/// and theta is non-decreasing in time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SsviParameters {
+    rho: Real,
+    eta: Real,
+    gamma: Real,
+    atm_total_variances: Vec<Real>,
+}
+
+impl SsviParameters {
+    pub fn new(
+        rho: Real,
+        eta: Real,
+        gamma: Real,
+        atm_total_variances: Vec<Real>,
+    ) -> SsviParameters {
+        SsviParameters {
+            rho,
+            eta,
+            gamma,
+            atm_total_variances,
+        }
+    }
+
+    /// unconstrained parameters of the calibration: (atanh rho, logit of eta (1 + |rho|) / 2, logit of 2 gamma)
+    fn from_unconstrained(x: &[f64], atm_total_variances: Vec<Real>) -> SsviParameters {
+        let sigmoid = |y: f64| 1.0 / (1.0 + (-y).exp());
+        let rho = x[0].tanh().clamp(-0.999, 0.999);
+        SsviParameters {
+            rho: rho as Real,
+            eta: (2.0 / (1.0 + rho.abs()) * sigmoid(x[1])) as Real,
+            gamma: (0.5 * sigmoid(x[2])).max(1.0e-4) as Real,
+            atm_total_variances,
+        }
+    }
+
+    fn phi(&self, theta: f64) -> f64 {
+        let gamma = self.gamma as f64;
+        self.eta as f64 / (theta.powf(gamma) * (1.0 + theta).powf(1.0 - gamma))
+    }
+
+    /// (w, dw/dk, d^2w/dk^2) on the i-th expiry
+    fn evaluate(&self, i: usize, k: f64) -> (f64, f64, f64) {
+        let theta = self.atm_total_variances[i] as f64;
+        let rho = self.rho as f64;
+        let phi = self.phi(theta);
+        let x = phi * k + rho;
+        let r = (x * x + 1.0 - rho * rho).sqrt();
+        (
+            0.5 * theta * (1.0 + rho * phi * k + r),
+            0.5 * theta * phi * (rho + x / r),
+            0.5 * theta * phi * phi * (1.0 - rho * rho) / (r * r * r),
+        )
+    }
+
+    pub fn get_rho(&self) -> Real {
+        self.rho
+    }
+
+    pub fn get_eta(&self) -> Real {
+        self.eta
+    }
+
+    pub fn get_gamma(&self) -> Real {
+        self.gamma
+    }
+
+    pub fn get_atm_total_variances(&self) -> &Vec<Real> {
+        &self.atm_total_variances
+    }
+}
+
+/// Smile parameters on the expiries of SviVolatilitySurface
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SviParameters {
+    RawSvi(Vec<RawSviParameters>),
+    Ssvi(SsviParameters),
+}
+
+impl SviParameters {
+    fn slice_number(&self) -> usize {
+        match self {
+            SviParameters::RawSvi(slices) => slices.len(),
+            SviParameters::Ssvi(ssvi) => ssvi.atm_total_variances.len(),
+        }
+    }
+
+    /// (w, dw/dk, d^2w/dk^2) on the i-th expiry
+    fn evaluate(&self, i: usize, k: f64) -> (f64, f64, f64) {
+        match self {
+            SviParameters::RawSvi(slices) => slices[i].evaluate(k),
+            SviParameters::Ssvi(ssvi) => ssvi.evaluate(i, k),
+        }
+    }
+
+    /// parameters of the volatilities multiplied by scale. The raw SVI slices scale exactly,
+    /// and SSVI scales the at-the-money volatilities through theta with phi(theta) re-evaluated.
+    pub fn scaled(&self, scale: Real) -> SviParameters {
+        let variance_scale = scale * scale;
+        match self {
+            SviParameters::RawSvi(slices) => SviParameters::RawSvi(
+                slices
+                    .iter()
+                    .map(|slice| RawSviParameters {
+                        a: slice.a * variance_scale,
+                        b: slice.b * variance_scale,
+                        ..*slice
+                    })
+                    .collect(),
+            ),
+            SviParameters::Ssvi(ssvi) => SviParameters::Ssvi(SsviParameters {
+                atm_total_variances: ssvi
+                    .atm_total_variances
+                    .iter()
+                    .map(|theta| theta * variance_scale)
+                    .collect(),
+                ..ssvi.clone()
+            }),
+        }
+    }
+
+    /// Check the static arbitrage: the positivity of the total variance, Lee's wing bound,
+    /// the butterfly arbitrage (Durrleman's condition) and the calendar arbitrage on the check grid
+    pub fn check_static_arbitrage(&self) -> Result<()> {
+        let tol = SVI_ARBITRAGE_TOLERANCE;
+        match self {
+            SviParameters::RawSvi(slices) => {
+                for (i, slice) in slices.iter().enumerate() {
+                    if slice.b < 0.0 || slice.rho.abs() >= 1.0 || slice.sigma <= 0.0 {
+                        return Err(anyhow!(
+                            "({}:{}) raw SVI parameters on the {}-th expiry are out of the domain: {:?}",
+                            file!(),
+                            line!(),
+                            i,
+                            slice
+                        ));
+                    }
+                    if slice.minimum_total_variance() < -tol {
+                        return Err(anyhow!(
+                            "({}:{}) raw SVI on the {}-th expiry has negative total variance {}: {:?}",
+                            file!(),
+                            line!(),
+                            i,
+                            slice.minimum_total_variance(),
+                            slice
+                        ));
+                    }
+                    if slice.wing_slope() > 2.0 + tol {
+                        return Err(anyhow!(
+                            "({}:{}) raw SVI on the {}-th expiry violates Lee's bound b (1 + |rho|) = {} > 2: {:?}",
+                            file!(),
+                            line!(),
+                            i,
+                            slice.wing_slope(),
+                            slice
+                        ));
+                    }
+                }
+            }
+            SviParameters::Ssvi(ssvi) => {
+                let rho = ssvi.rho.abs();
+                if rho >= 1.0
+                    || ssvi.eta <= 0.0
+                    || ssvi.gamma <= 0.0
+                    || ssvi.gamma > 0.5
+                    || (ssvi.eta * (1.0 + rho)) as f64 > 2.0 + tol
+                {
+                    return Err(anyhow!(
+                        "({}:{}) SSVI parameters do not satisfy |rho| < 1, eta (1 + |rho|) <= 2 \
+                        and 0 < gamma <= 1/2: rho = {}, eta = {}, gamma = {}",
+                        file!(),
+                        line!(),
+                        ssvi.rho,
+                        ssvi.eta,
+                        ssvi.gamma
+                    ));
+                }
+                if ssvi.atm_total_variances.iter().any(|theta| *theta <= 0.0)
+                    || ssvi.atm_total_variances.windows(2).any(|w| w[1] < w[0])
+                {
+                    return Err(anyhow!(
+                        "({}:{}) SSVI at-the-money total variances must be positive and non-decreasing: {:?}",
+                        file!(),
+                        line!(),
+                        ssvi.atm_total_variances
+                    ));
+                }
+            }
+        }
+
+        let grid = arbitrage_check_grid();
+        for i in 0..self.slice_number() {
+            for k in grid.iter() {
+                let (w, w1, w2) = self.evaluate(i, *k);
+                if w <= 0.0 || butterfly_density(*k, w, w1, w2) < -tol {
+                    return Err(anyhow!(
+                        "({}:{}) butterfly arbitrage on the {}-th expiry at log moneyness {}: \
+                        w = {}, g = {}",
+                        file!(),
+                        line!(),
+                        i,
+                        k,
+                        w,
+                        butterfly_density(*k, w, w1, w2)
+                    ));
+                }
+                if i > 0 && self.evaluate(i - 1, *k).0 > w + tol {
+                    return Err(anyhow!(
+                        "({}:{}) calendar arbitrage between the {}-th and {}-th expiries \
+                        at log moneyness {}: {} > {}",
+                        file!(),
+                        line!(),
+                        i - 1,
+                        i,
+                        k,
+                        self.evaluate(i - 1, *k).0,
+                        w
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// market total variances on an expiry
+struct SmileQuotes {
+    log_moneyness: Vec<f64>,
+    total_variances: Vec<f64>,
+}
+
+impl SmileQuotes {
+    /// total variance at the money by linear interpolation with flat extrapolation
+    fn atm_total_variance(&self) -> f64 {
+        let (k, w) = (&self.log_moneyness, &self.total_variances);
+        let n = k.len();
+        if k[0] >= 0.0 {
+            return w[0];
+        }
+        if k[n - 1] <= 0.0 {
+            return w[n - 1];
+        }
+        let i = k.partition_point(|x| *x <= 0.0) - 1;
+        w[i] + (w[i + 1] - w[i]) * (0.0 - k[i]) / (k[i + 1] - k[i])
+    }
+
+    fn scale(&self) -> f64 {
+        self.total_variances.iter().sum::<f64>() / self.total_variances.len() as f64
+    }
+}
+
+struct RawSviCost<'a> {
+    quotes: &'a SmileQuotes,
+    previous: Option<RawSviParameters>,
+}
+
+impl CostFunction for RawSviCost<'_> {
+    type Param = Vec<f64>;
+    type Output = f64;
+
+    fn cost(&self, x: &Self::Param) -> Result<Self::Output, Error> {
+        let svi = RawSviParameters::from_unconstrained(x);
+        let scale = self.quotes.scale();
+        let n = self.quotes.log_moneyness.len() as f64;
+        let mut error = 0.0;
+        for (k, w) in self
+            .quotes
+            .log_moneyness
+            .iter()
+            .zip(self.quotes.total_variances.iter())
+        {
+            error += ((svi.evaluate(*k).0 - w) / scale).powi(2) / n;
+        }
+
+        let mut penalty = (svi.minimum_total_variance().min(0.0) / scale).powi(2)
+            + (svi.wing_slope() - 2.0).max(0.0).powi(2);
+        for k in arbitrage_check_grid() {
+            let (w, w1, w2) = svi.evaluate(k);
+            if w > 0.0 {
+                penalty += butterfly_density(k, w, w1, w2).min(0.0).powi(2);
+            }
+            if let Some(previous) = self.previous {
+                penalty += ((previous.evaluate(k).0 - w).max(0.0) / scale).powi(2);
+            }
+        }
+        Ok(error + SVI_PENALTY * penalty)
+    }
+}
+
+struct SsviCost<'a> {
+    quotes: &'a [SmileQuotes],
+    atm_total_variances: Vec<Real>,
+}
+
+impl CostFunction for SsviCost<'_> {
+    type Param = Vec<f64>;
+    type Output = f64;
+
+    fn cost(&self, x: &Self::Param) -> Result<Self::Output, Error> {
+        let ssvi = SsviParameters::from_unconstrained(x, self.atm_total_variances.clone());
+        let mut error = 0.0;
+        for (i, quotes) in self.quotes.iter().enumerate() {
+            let scale = quotes.scale();
+            let n = quotes.log_moneyness.len() as f64;
+            for (k, w) in quotes
+                .log_moneyness
+                .iter()
+                .zip(quotes.total_variances.iter())
+            {
+                error += ((ssvi.evaluate(i, *k).0 - w) / scale).powi(2) / n;
+            }
+        }
+        Ok(error)
+    }
+}
+
+/// Nelder-Mead restarted from the best point a few times, which helps the simplex to escape from collapsing
+fn minimize<C>(cost: C, initial: Vec<f64>, steps: &[f64]) -> Result<Vec<f64>>
+where
+    C: CostFunction<Param = Vec<f64>, Output = f64>,
+{
+    let mut best = initial;
+    let mut cost = Some(cost);
+    for _ in 0..SVI_CALIBRATION_ROUND {
+        let mut simplex = vec![best.clone()];
+        for (i, step) in steps.iter().enumerate() {
+            let mut vertex = best.clone();
+            vertex[i] += step;
+            simplex.push(vertex);
+        }
+        let solver = NelderMead::new(simplex).with_sd_tolerance(1.0e-14)?;
+        let res = Executor::new(cost.take().unwrap(), solver)
+            .configure(|state| state.max_iters(SVI_CALIBRATION_MAX_ITERATION))
+            .run()?;
+        best = res.state.best_param.clone().ok_or_else(|| {
+            anyhow!(
+                "({}:{}) Nelder-Mead did not return a parameter",
+                file!(),
+                line!()
+            )
+        })?;
+        cost = Some(res.problem.problem.ok_or_else(|| {
+            anyhow!(
+                "({}:{}) failed to take back the cost function",
+                file!(),
+                line!()
+            )
+        })?);
+    }
+    Ok(best)
+}
+
+/// a bump of the implied volatility on the expiries in (time1, time2]
+/// and the spot moneyness in (left_spot_moneyness, right_spot_moneyness]
+#[derive(Debug, Clone, PartialEq)]
+struct SviVolatilityBump {
+    time1: Time,
+    time2: Time,
+    left_spot_moneyness: Real,
+    right_spot_moneyness: Real,
+    bump: Real,
+}
+
+/// Parametric implied volatility surface whose smile on each expiry is raw SVI or SSVI.
+/// The parameters are given directly (e.g., marked by traders) or calibrated to SurfaceData,
+/// and are checked for static arbitrage in both cases.
+///
+/// The smiles are in the log forward moneyness. Between the expiries, the total variance
+/// is linearly interpolated in time at the same log forward moneyness,
+/// which keeps the surface free of calendar arbitrage.
+/// Before the first expiry and after the last expiry, the implied volatility is flat in time.
+///
+/// For StickyToStrike, the smiles stay on the strikes of the reference spot when the market price moves.
+#[derive(Debug, Clone)]
+pub struct SviVolatilitySurface {
+    parameters: SviParameters,
+    maturity_dates: Vec<OffsetDateTime>,
+    maturity_times: Vec<Time>,
+    reference_spot: Real,
+    reference_forwards: Vec<Real>,
+    /// ln(calculating forward / reference forward) on each expiry, which is zero for StickyToMoneyness
+    log_moneyness_shifts: Vec<Real>,
+    volatility_bumps: Vec<SviVolatilityBump>,
+    //
+    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    market_price: Rc<RefCell<MarketPrice>>,
+    collateral_curve: Rc<RefCell<ZeroCurve>>,
+    borrowing_curve: Rc<RefCell<ZeroCurve>>,
+    stickyness_type: StickynessType,
+    //
+    name: String,
+    code: String,
+}
+
+impl SviVolatilitySurface {
+    pub fn initialize(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        market_price: Rc<RefCell<MarketPrice>>,
+        collateral_curve: Rc<RefCell<ZeroCurve>>,
+        borrowing_curve: Rc<RefCell<ZeroCurve>>,
+        stickyness_type: StickynessType,
+        name: String,
+        code: String,
+    ) -> SviVolatilitySurface {
+        SviVolatilitySurface {
+            parameters: SviParameters::RawSvi(Vec::new()),
+            maturity_dates: Vec::new(),
+            maturity_times: Vec::new(),
+            reference_spot: 0.0,
+            reference_forwards: Vec::new(),
+            log_moneyness_shifts: Vec::new(),
+            volatility_bumps: Vec::new(),
+            evaluation_date,
+            market_price,
+            collateral_curve,
+            borrowing_curve,
+            stickyness_type,
+            name,
+            code,
+        }
+    }
+
+    /// smile parameters given on the maturity dates, where reference_spot is the spot at the marking
+    pub fn with_parameters(
+        mut self,
+        maturity_dates: Vec<OffsetDateTime>,
+        reference_spot: Real,
+        parameters: SviParameters,
+    ) -> Result<SviVolatilitySurface> {
+        if parameters.slice_number() != maturity_dates.len() {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has {} maturities but {} smiles",
+                file!(),
+                line!(),
+                self.name,
+                self.code,
+                maturity_dates.len(),
+                parameters.slice_number()
+            ));
+        }
+        self.set_maturities(maturity_dates, reference_spot)?;
+        parameters.check_static_arbitrage().with_context(|| {
+            anyhow!(
+                "({}:{}) static arbitrage in {} ({})",
+                file!(),
+                line!(),
+                self.name,
+                self.code
+            )
+        })?;
+        self.parameters = parameters;
+        Ok(self)
+    }
+
+    /// Calibrate raw SVI on each expiry in order (penalizing the calendar arbitrage against the previous expiry),
+    /// or SSVI on all the expiries where theta is the market at-the-money total variance.
+    /// The quotes which are not positive are ignored.
+    pub fn with_market_surface(
+        mut self,
+        market_implied_volatility_surface: &SurfaceData,
+        surface_type: VolatilitySurfaceType,
+    ) -> Result<SviVolatilitySurface> {
+        let spot = market_implied_volatility_surface
+            .get_spot()
+            .ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) Error getting spot from market_implied_volatility_surface of {}",
+                    file!(),
+                    line!(),
+                    market_implied_volatility_surface.get_name()
+                )
+            })?;
+        self.set_maturities(market_implied_volatility_surface.get_dates().clone(), spot)?;
+
+        let strikes = market_implied_volatility_surface.get_strike();
+        let vols = market_implied_volatility_surface.get_value();
+        let mut quotes = Vec::new();
+        for (i, t) in self.maturity_times.iter().enumerate() {
+            let forward = self.reference_forwards[i] as f64;
+            let mut smile = SmileQuotes {
+                log_moneyness: Vec::new(),
+                total_variances: Vec::new(),
+            };
+            for (j, strike) in strikes.iter().enumerate() {
+                let vol = vols[[i, j]] as f64;
+                if vol.is_finite() && vol > 0.0 && *strike > 0.0 {
+                    smile.log_moneyness.push((*strike as f64 / forward).ln());
+                    smile.total_variances.push(vol * vol * *t as f64);
+                }
+            }
+            if smile.log_moneyness.len() < 3 {
+                return Err(anyhow!(
+                    "({}:{}) {} ({}) has less than 3 quotes on {}",
+                    file!(),
+                    line!(),
+                    self.name,
+                    self.code,
+                    self.maturity_dates[i]
+                ));
+            }
+            quotes.push(smile);
+        }
+
+        let parameters = match surface_type {
+            VolatilitySurfaceType::RawSvi => {
+                let mut slices: Vec<RawSviParameters> = Vec::new();
+                for smile in quotes.iter() {
+                    let (b, rho, sigma) = (0.1, -0.3, 0.1);
+                    let a = (smile.atm_total_variance() - b * sigma).max(1.0e-4);
+                    let initial = vec![a, f64::ln(b), f64::atanh(rho), 0.0, f64::ln(sigma)];
+                    let steps = [0.1 * smile.scale(), 0.5, 0.5, 0.1, 0.5];
+                    let cost = RawSviCost {
+                        quotes: smile,
+                        previous: slices.last().copied(),
+                    };
+                    let best = minimize(cost, initial, &steps)?;
+                    slices.push(RawSviParameters::from_unconstrained(&best));
+                }
+                SviParameters::RawSvi(slices)
+            }
+            VolatilitySurfaceType::Ssvi => {
+                let mut atm_total_variances: Vec<Real> = Vec::new();
+                for smile in quotes.iter() {
+                    let theta = smile.atm_total_variance().max(1.0e-8) as Real;
+                    let previous = atm_total_variances.last().copied().unwrap_or(0.0);
+                    atm_total_variances.push(theta.max(previous));
+                }
+                let cost = SsviCost {
+                    quotes: &quotes,
+                    atm_total_variances: atm_total_variances.clone(),
+                };
+                let best = minimize(cost, vec![-0.3, 0.0, 0.0], &[0.5, 1.0, 1.0])?;
+                SviParameters::Ssvi(SsviParameters::from_unconstrained(
+                    &best,
+                    atm_total_variances,
+                ))
+            }
+            VolatilitySurfaceType::LocalVolatility => {
+                return Err(anyhow!(
+                    "({}:{}) {:?} is not a parametric surface type ({})",
+                    file!(),
+                    line!(),
+                    surface_type,
+                    self.code
+                ));
+            }
+        };
+
+        parameters.check_static_arbitrage().with_context(|| {
+            anyhow!(
+                "({}:{}) calibrated {} ({}) has static arbitrage",
+                file!(),
+                line!(),
+                self.name,
+                self.code
+            )
+        })?;
+        self.parameters = parameters;
+        Ok(self)
+    }
+
+    fn set_maturities(
+        &mut self,
+        maturity_dates: Vec<OffsetDateTime>,
+        reference_spot: Real,
+    ) -> Result<()> {
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        if maturity_dates.is_empty()
+            || maturity_dates.windows(2).any(|w| w[0] >= w[1])
+            || maturity_dates[0] <= eval_date
+        {
+            return Err(anyhow!(
+                "({}:{}) maturity dates of {} ({}) must be increasing and after the evaluation date {}: {:?}",
+                file!(),
+                line!(),
+                self.name,
+                self.code,
+                eval_date,
+                maturity_dates
+            ));
+        }
+        let time_calculator = NullCalendar::new();
+        self.maturity_times = maturity_dates
+            .iter()
+            .map(|date| time_calculator.get_time_difference(&eval_date, date))
+            .collect();
+        self.reference_forwards = maturity_dates
+            .iter()
+            .map(|date| self.get_forward(reference_spot, date))
+            .collect::<Result<Vec<Real>>>()?;
+        self.maturity_dates = maturity_dates;
+        self.reference_spot = reference_spot;
+        self.log_moneyness_shifts = vec![0.0; self.maturity_dates.len()];
+        Ok(())
+    }
+
+    pub fn build(&mut self) -> Result<()> {
+        self.log_moneyness_shifts = match self.stickyness_type {
+            StickynessType::StickyToMoneyness => vec![0.0; self.maturity_dates.len()],
+            StickynessType::StickyToStrike => {
+                let spot = self.market_price.borrow().get_value();
+                self.maturity_dates
+                    .iter()
+                    .zip(self.reference_forwards.iter())
+                    .map(|(date, reference)| Ok((self.get_forward(spot, date)? / reference).ln()))
+                    .collect::<Result<Vec<Real>>>()?
+            }
+        };
+        Ok(())
+    }
+
+    fn get_forward(&self, spot: Real, maturity: &OffsetDateTime) -> Result<Real> {
+        let collateral_discount = self
+            .collateral_curve
+            .borrow()
+            .get_discount_factor_at_date(maturity)?;
+        let borrowing_discount = self
+            .borrowing_curve
+            .borrow()
+            .get_discount_factor_at_date(maturity)?;
+        let dividend_deduction_ratio = self
+            .market_price
+            .borrow()
+            .get_dividend_deduction_ratio(maturity)?;
+        Ok(spot * borrowing_discount / collateral_discount * dividend_deduction_ratio)
+    }
+
+    /// (w, dw/dk, d^2w/dk^2) on the i-th expiry at the log forward moneyness y of the calculating forward
+    fn slice_total_variance(&self, i: usize, y: f64) -> (f64, f64, f64) {
+        let k = y + self.log_moneyness_shifts[i] as f64;
+        let (w, w1, w2) = self.parameters.evaluate(i, k);
+        let t = self.maturity_times[i] as f64;
+        let spot_moneyness =
+            (k.exp() * self.reference_forwards[i] as f64 / self.reference_spot as f64) as Real;
+        let eps = 1.0e-4;
+        let bump: f64 = self
+            .volatility_bumps
+            .iter()
+            .filter(|b| {
+                b.time1 + eps < t as Time
+                    && t as Time <= b.time2 + eps
+                    && b.left_spot_moneyness + eps < spot_moneyness
+                    && spot_moneyness <= b.right_spot_moneyness + eps
+            })
+            .map(|b| b.bump as f64)
+            .sum();
+        if bump == 0.0 {
+            return (w, w1, w2);
+        }
+        // w = v^2 t, so that v' = w' / (2 v t) and v'' = (w'' / (2 t) - v'^2) / v
+        let v = (w.max(0.0) / t).sqrt().max(1.0e-8);
+        let v1 = w1 / (2.0 * v * t);
+        let v2 = (w2 / (2.0 * t) - v1 * v1) / v;
+        let bumped = v + bump;
+        (
+            bumped * bumped * t,
+            2.0 * bumped * v1 * t,
+            2.0 * t * (v1 * v1 + bumped * v2),
+        )
+    }
+
+    /// (w, dw/dk, d^2w/dk^2, dw/dt) at time t and the log forward moneyness y
+    fn total_variance_with_derivatives(&self, t: Time, y: f64) -> (f64, f64, f64, f64) {
+        let times = &self.maturity_times;
+        let n = times.len();
+        let t = t as f64;
+        let flat = |i: usize| {
+            let ti = times[i] as f64;
+            let (w, w1, w2) = self.slice_total_variance(i, y);
+            (w * t / ti, w1 * t / ti, w2 * t / ti, w / ti)
+        };
+        if t <= times[0] as f64 {
+            return flat(0);
+        }
+        if t >= times[n - 1] as f64 {
+            return flat(n - 1);
+        }
+        let i = times.partition_point(|s| (*s as f64) <= t) - 1;
+        let (t0, t1) = (times[i] as f64, times[i + 1] as f64);
+        let x = (t - t0) / (t1 - t0);
+        let (w0, w01, w02) = self.slice_total_variance(i, y);
+        let (w1, w11, w12) = self.slice_total_variance(i + 1, y);
+        (
+            w0 + x * (w1 - w0),
+            w01 + x * (w11 - w01),
+            w02 + x * (w12 - w02),
+            (w1 - w0) / (t1 - t0),
+        )
+    }
+
+    pub fn get_parameters(&self) -> &SviParameters {
+        &self.parameters
+    }
+
+    pub fn get_maturity_dates(&self) -> &Vec<OffsetDateTime> {
+        &self.maturity_dates
+    }
+
+    pub fn get_maturity_times(&self) -> &Vec<Time> {
+        &self.maturity_times
+    }
+}
+
+impl VolatilityTrait for SviVolatilitySurface {
+    fn get_value(&self, t: Time, forward_moneyness: Real) -> Real {
+        let y = (forward_moneyness as f64).ln();
+        let t = t.max(1.0e-6);
+        let (w, _, _, _) = self.total_variance_with_derivatives(t, y);
+        (w.max(0.0) / t as f64).sqrt() as Real
+    }
+
+    /// Gatheral's formula of the local variance in the total variance:
+    /// dw/dt / (1 - k w' / (2 w))^2 - w'^2 / 4 (1 / w + 1 / 4) + w'' / 2)
+    /// Falls back to the implied volatility where the formula is not defined
+    fn get_local_volatility(&self, t: Time, forward_moneyness: Real) -> Result<Real> {
+        let y = (forward_moneyness as f64).ln();
+        let t = t.max(1.0e-6);
+        let (w, w1, w2, wt) = self.total_variance_with_derivatives(t, y);
+        if w <= 0.0 {
+            return Ok(0.0);
+        }
+        let g = butterfly_density(y, w, w1, w2);
+        if g <= 1.0e-8 || wt <= 0.0 {
+            return Ok(self.get_value(t, forward_moneyness));
+        }
+        Ok((wt / g).sqrt() as Real)
+    }
+
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    fn total_variance(&self, t: Time, forward_moneyness: Real) -> Result<Real> {
+        if forward_moneyness <= 0.0 {
+            return Err(anyhow!(
+                "({}:{}) non-positive forward moneyness {} in {} ({})",
+                file!(),
+                line!(),
+                forward_moneyness,
+                self.name,
+                self.code
+            ));
+        }
+        if t <= 0.0 {
+            return Ok(0.0);
+        }
+        let y = (forward_moneyness as f64).ln();
+        Ok(self.total_variance_with_derivatives(t, y).0.max(0.0) as Real)
+    }
+
+    fn total_deviation(&self, t: Time, forward_moneyness: Real) -> Result<Real> {
+        Ok(self.total_variance(t, forward_moneyness)?.sqrt())
+    }
+
+    /// add bump to the implied volatility on the expiries in time1 < t <= time2
+    /// and the spot moneyness in left_spot_moneyness < x <= right_spot_moneyness.
+    /// The parameters are kept, and the bump is applied on the smiles.
+    fn bump_volatility(
+        &mut self,
+        time1: Option<Time>,
+        time2: Option<Time>,
+        left_spot_moneyness: Option<Real>,
+        right_spot_moneyness: Option<Real>,
+        bump: Real,
+    ) -> Result<()> {
+        let new_bump = SviVolatilityBump {
+            time1: time1.unwrap_or(Time::MIN + 10.0),
+            time2: time2.unwrap_or(Time::MAX - 10.0),
+            left_spot_moneyness: left_spot_moneyness.unwrap_or(Real::MIN + 10.0),
+            right_spot_moneyness: right_spot_moneyness.unwrap_or(Real::MAX - 10.0),
+            bump,
+        };
+        let same_region = self.volatility_bumps.iter().position(|b| {
+            b.time1 == new_bump.time1
+                && b.time2 == new_bump.time2
+                && b.left_spot_moneyness == new_bump.left_spot_moneyness
+                && b.right_spot_moneyness == new_bump.right_spot_moneyness
+        });
+        match same_region {
+            Some(i) => {
+                self.volatility_bumps[i].bump += bump;
+                if self.volatility_bumps[i].bump.abs() < 1.0e-10 {
+                    self.volatility_bumps.remove(i);
+                }
+            }
+            None => self.volatility_bumps.push(new_bump),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::data;
+    use crate::vectordatasample;
+    use ndarray::{Array1, Array2};
+    use time::macros::datetime;
+
+    #[test]
+    fn test_svi_volatility_surface() -> Result<()> {
+        let eval_date = datetime!(2024-01-02 00:00:00 +09:00);
+        let spot = 350.0;
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_date)));
+        let equity = Rc::new(RefCell::new(MarketPrice::new(
+            spot,
+            eval_date,
+            None,
+            Currency::KRW,
+            "KOSPI2".to_string(),
+            "KOSPI2".to_string(),
+        )));
+        let dummy_data = vectordatasample!(0.00, Currency::KRW, "mock curve data")?;
+        let zero_curve = Rc::new(RefCell::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &dummy_data,
+            "KRWGOV".to_string(),
+            "zero curve".to_string(),
+        )?));
+        let surface = || {
+            SviVolatilitySurface::initialize(
+                evaluation_date.clone(),
+                equity.clone(),
+                zero_curve.clone(),
+                zero_curve.clone(),
+                StickynessType::default(),
+                "svi".to_string(),
+                "svi".to_string(),
+            )
+        };
+
+        let dates = vec![
+            datetime!(2024-04-02 00:00:00 +09:00),
+            datetime!(2024-07-02 00:00:00 +09:00),
+            datetime!(2025-01-02 00:00:00 +09:00),
+        ];
+        let strikes = Array1::linspace(0.6 * spot, 1.4 * spot, 17);
+        let raw_svi = SviParameters::RawSvi(vec![
+            RawSviParameters::new(0.01, 0.10, -0.4, 0.0, 0.2),
+            RawSviParameters::new(0.02, 0.10, -0.4, 0.0, 0.2),
+            RawSviParameters::new(0.04, 0.10, -0.4, 0.0, 0.2),
+        ]);
+        let ssvi = SviParameters::Ssvi(SsviParameters::new(-0.5, 1.0, 0.4, vec![0.01, 0.02, 0.04]));
+
+        for (parameters, surface_type) in [
+            (raw_svi, VolatilitySurfaceType::RawSvi),
+            (ssvi, VolatilitySurfaceType::Ssvi),
+        ] {
+            // the market quotes are generated from the given parameters
+            let mut given = surface().with_parameters(dates.clone(), spot, parameters)?;
+            given.build()?;
+            let times = given.get_maturity_times().clone();
+            let mut vols = Array2::zeros((dates.len(), strikes.len()));
+            for i in 0..dates.len() {
+                for j in 0..strikes.len() {
+                    vols[[i, j]] = given.get_value(times[i], strikes[j] / spot);
+                }
+            }
+            let surface_data = SurfaceData::new(
+                Some(spot),
+                vols.clone(),
+                dates.clone(),
+                strikes.clone(),
+                Some(eval_date),
+                Currency::KRW,
+                "KOSPI2".to_string(),
+                "KOSPI2".to_string(),
+            );
+
+            let mut calibrated = surface().with_market_surface(&surface_data, surface_type)?;
+            calibrated.build()?;
+            let mut max_error: Real = 0.0;
+            for i in 0..dates.len() {
+                for j in 0..strikes.len() {
+                    let vol = calibrated.get_value(times[i], strikes[j] / spot);
+                    max_error = max_error.max((vol - vols[[i, j]]).abs());
+                }
+            }
+            assert!(
+                max_error < 2.0e-3,
+                "{:?} max error: {}",
+                surface_type,
+                max_error
+            );
+
+            for t in [0.1, 0.4, 0.75, 1.5] {
+                for x in [0.7, 1.0, 1.3] {
+                    let local_vol = calibrated.get_local_volatility(t, x)?;
+                    assert!(
+                        local_vol > 0.0 && local_vol.is_finite(),
+                        "{:?} local vol at ({}, {}): {}",
+                        surface_type,
+                        t,
+                        x,
+                        local_vol
+                    );
+                }
+            }
+
+            let base = calibrated.get_value(0.5, 1.1);
+            calibrated.bump_volatility(None, None, None, None, 0.01)?;
+            assert!((calibrated.get_value(0.5, 1.1) - base - 0.01).abs() < 1.0e-5);
+            calibrated.bump_volatility(None, None, None, None, -0.01)?;
+            assert!((calibrated.get_value(0.5, 1.1) - base).abs() < 1.0e-6);
+        }
+
+        // b (1 + |rho|) = 2.25 violates Lee's bound
+        let arbitrage =
+            SviParameters::RawSvi(vec![RawSviParameters::new(0.01, 1.5, 0.5, 0.0, 0.2)]);
+        assert!(surface()
+            .with_parameters(vec![dates[0]], spot, arbitrage)
+            .is_err());
+        // decreasing at-the-money total variance is a calendar arbitrage
+        let arbitrage = SviParameters::Ssvi(SsviParameters::new(-0.5, 1.0, 0.4, vec![0.02, 0.01]));
+        assert!(surface()
+            .with_parameters(dates[0..2].to_vec(), spot, arbitrage)
+            .is_err());
+        Ok(())
+    }
+}
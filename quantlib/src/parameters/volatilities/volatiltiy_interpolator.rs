@@ -1,26 +1,445 @@
-use crate::definitions::Real;
+use crate::definitions::{Real, Time};
+use crate::math::interpolator::ExtraPolationType;
+use crate::math::interpolators::{
+    bilinear_interpolator::BilinearInterpolator, linear_interpolator::LinearInterpolator1D,
+};
+use crate::math::tridiagonal_solver::solve_tridiagonal;
+//
+use anyhow::{anyhow, Context, Result};
 use enum_dispatch::enum_dispatch;
+use ndarray::{Array1, Array2};
 use serde::{Deserialize, Serialize};
+use statrs::distribution::{Continuous, ContinuousCDF, Normal};
+use tracing::warn;
 
+#[enum_dispatch]
 pub trait VolatilityInterplatorTrait {
-    fn interpolate(&self) -> Real {
-        0.0
+    /// Make the local volatility on (time, forward moneyness) from the implied volatility
+    /// given on times[i] x forward_moneyness[i][j] where forward_moneyness[i] is sorted.
+    fn local_volatility(
+        &self,
+        times: &Array1<Time>,
+        forward_moneyness: &[Array1<Real>],
+        implied_volatility: &Array2<Real>,
+    ) -> Result<BilinearInterpolator>;
+}
+
+/// Andreasen, J., & Huge, B. (2011). Volatility interpolation. Risk, 24(3), 76.
+///
+/// The normalized call price c(t, k) = C(t, K) / (D(t) F(t)), k = K / F(t),
+/// is propagated from the previous maturity by the implicit scheme of
+///
+/// dc/dt = 0.5 * theta(k)^2 * (d^2c/dx^2 - dc/dx), x = ln k
+///
+/// where theta is piecewise linear in k (one value per quoted forward moneyness) and constant between maturities.
+/// theta of each maturity is calibrated so that the model prices reproduce the implied volatilities.
+/// The implicit scheme keeps the prices convex in strike and increasing in maturity,
+/// hence the resulting local volatility is free of static arbitrage.
+/// The interval between maturities is divided by time_steps_per_year (the original paper takes a single step)
+/// so that theta is close to the continuous time local volatility used by path and PDE pricers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AndreasenHuge {
+    space_grid_number: usize,
+    time_steps_per_year: usize,
+}
+
+impl Default for AndreasenHuge {
+    fn default() -> AndreasenHuge {
+        AndreasenHuge {
+            space_grid_number: 1000,
+            time_steps_per_year: 50,
+        }
+    }
+}
+
+/// the number of standard deviations covered by the log-moneyness grid in each direction
+const AH_GRID_STANDARD_DEVIATIONS: Real = 6.0;
+const AH_MAX_ITERATION: usize = 50;
+/// calibration tolerance in implied volatility
+const AH_TOLERANCE: Real = 1.0e-4;
+/// quotes with smaller normalized vega are not calibrated (theta is set to the implied volatility)
+const AH_MIN_VEGA: Real = 1.0e-4;
+const AH_MIN_LOCAL_VOLATILITY: Real = 0.01;
+const AH_MAX_LOCAL_VOLATILITY: Real = 5.0;
+/// the minimum number of implicit steps between maturities
+const AH_MIN_STEPS: usize = 10;
+/// the gap between the end of a maturity interval and the start of the next one
+const AH_TIME_SEPARATION: Time = 1.0e-5;
+
+impl AndreasenHuge {
+    pub fn new(space_grid_number: usize, time_steps_per_year: usize) -> AndreasenHuge {
+        AndreasenHuge {
+            space_grid_number,
+            time_steps_per_year,
+        }
+    }
+
+    /// propagate the normalized call prices by dt with the implicit scheme
+    fn propagate(
+        &self,
+        prices: &Array1<Real>,
+        x: &Array1<Real>,
+        node_theta: &Array1<Real>,
+        dt: Time,
+    ) -> Result<Array1<Real>> {
+        let n = x.len();
+        let dx = x[1] - x[0];
+        let steps = (dt * self.time_steps_per_year.max(1) as Real)
+            .ceil()
+            .max(AH_MIN_STEPS as Real) as usize;
+        let h = dt / steps as Real;
+
+        let (mut lower, mut diag, mut upper) =
+            (vec![0.0; n - 2], vec![0.0; n - 2], vec![0.0; n - 2]);
+        for j in 0..n - 2 {
+            let a = 0.5 * h * node_theta[j + 1] * node_theta[j + 1];
+            lower[j] = -a * (1.0 / (dx * dx) + 0.5 / dx);
+            diag[j] = 1.0 + 2.0 * a / (dx * dx);
+            upper[j] = -a * (1.0 / (dx * dx) - 0.5 / dx);
+        }
+        // c(t, k_min) = 1 - k_min, c(t, k_max) = 0
+        let lower_boundary = prices[0];
+        let upper_boundary = prices[n - 1];
+
+        let mut res = prices.clone();
+        for _ in 0..steps {
+            let mut rhs = res.slice(ndarray::s![1..n - 1]).to_vec();
+            rhs[0] -= lower[0] * lower_boundary;
+            rhs[n - 3] -= upper[n - 3] * upper_boundary;
+            let interior = solve_tridiagonal(&lower, &diag, &upper, &rhs)?;
+            res.slice_mut(ndarray::s![1..n - 1]).assign(&interior);
+        }
+        Ok(res)
+    }
+
+    /// uniform log-moneyness grid covering the knots and the distribution up to the last maturity
+    fn log_moneyness_grid(
+        &self,
+        times: &Array1<Time>,
+        forward_moneyness: &[Array1<Real>],
+        implied_volatility: &Array2<Real>,
+    ) -> Result<Array1<Real>> {
+        // the wing volatilities are excluded by taking the median of each maturity
+        let mut width: Real = 0.0;
+        for (i, &t) in times.iter().enumerate() {
+            let mut vols = implied_volatility.row(i).to_vec();
+            vols.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let median_vol = vols[vols.len() / 2].max(0.05);
+            width = width.max(AH_GRID_STANDARD_DEVIATIONS * median_vol * t.max(0.01).sqrt());
+        }
+        let (mut x_lo, mut x_hi) = (-width, width);
+        for knots in forward_moneyness.iter() {
+            if knots.iter().any(|&k| k <= 0.0) {
+                return Err(anyhow!(
+                    "({}:{}) forward moneyness must be positive: {:?}",
+                    file!(),
+                    line!(),
+                    knots
+                ));
+            }
+            x_lo = x_lo.min(knots[0].ln() - 0.1);
+            x_hi = x_hi.max(knots[knots.len() - 1].ln() + 0.1);
+        }
+        let n = self.space_grid_number.max(50) + 1;
+        Ok(Array1::linspace(x_lo, x_hi, n))
+    }
+
+    /// calibrated theta on the given knots
+    fn calibrate(
+        &self,
+        times: &Array1<Time>,
+        forward_moneyness: &[Array1<Real>],
+        implied_volatility: &Array2<Real>,
+    ) -> Result<Array2<Real>> {
+        let n_t = times.len();
+        let x = self.log_moneyness_grid(times, forward_moneyness, implied_volatility)?;
+        let mut prices: Array1<Real> = x.mapv(|v| (1.0 - v.exp()).max(0.0));
+
+        let mut res = Array2::zeros(implied_volatility.raw_dim());
+        let mut t_prev: Time = 0.0;
+        for i in 0..n_t {
+            let knots = &forward_moneyness[i];
+            let vols = implied_volatility.row(i);
+            let dt = times[i] - t_prev;
+            if dt <= 0.0 {
+                res.row_mut(i).assign(&vols);
+                continue;
+            }
+
+            let log_knots = knots.mapv(|k| k.ln());
+
+            let (targets, vegas): (Vec<Real>, Vec<Real>) = knots
+                .iter()
+                .zip(vols.iter())
+                .map(|(&k, &v)| {
+                    (
+                        normalized_black_call(k, v, times[i]),
+                        normalized_black_vega(k, v, times[i]),
+                    )
+                })
+                .unzip();
+
+            let mut theta = vols.to_owned();
+            let mut slice = prices.clone();
+            for iteration in 0..AH_MAX_ITERATION {
+                let node_theta = nodewise_theta(&x, &log_knots, &theta);
+                slice = self.propagate(&prices, &x, &node_theta, dt)?;
+
+                let mut max_error: Real = 0.0;
+                let mut updates = Vec::with_capacity(knots.len());
+                for j in 0..knots.len() {
+                    if vegas[j] < AH_MIN_VEGA {
+                        updates.push(1.0);
+                        continue;
+                    }
+                    let model = interpolate_on_uniform_grid(&x, &slice, log_knots[j]);
+                    let vol_error = (model - targets[j]) / vegas[j];
+                    max_error = max_error.max(vol_error.abs());
+                    let model_vol = (vols[j] + vol_error).max(0.5 * vols[j]);
+                    updates.push((vols[j] / model_vol).clamp(0.5, 2.0));
+                }
+                if max_error < AH_TOLERANCE || iteration == AH_MAX_ITERATION - 1 {
+                    break;
+                }
+                for (th, u) in theta.iter_mut().zip(updates.iter()) {
+                    *th = (*th * u).clamp(AH_MIN_LOCAL_VOLATILITY, AH_MAX_LOCAL_VOLATILITY);
+                }
+            }
+            res.row_mut(i).assign(&theta);
+            prices = slice;
+            t_prev = times[i];
+        }
+        Ok(res)
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
-pub struct AndreasenHuge {}
 impl VolatilityInterplatorTrait for AndreasenHuge {
-    fn interpolate(&self) -> Real {
-        0.0
+    /// theta is constant on (T_{i-1}, T_i], so it is put on both ends of the interval
+    /// (the left end is shifted by AH_TIME_SEPARATION) to keep the interpolation in time stepwise.
+    fn local_volatility(
+        &self,
+        times: &Array1<Time>,
+        forward_moneyness: &[Array1<Real>],
+        implied_volatility: &Array2<Real>,
+    ) -> Result<BilinearInterpolator> {
+        check_local_volatility_input(times, forward_moneyness, implied_volatility)?;
+        // a flat implied volatility is its own local volatility, e.g., the constant volatility in Engine
+        let flat = implied_volatility[[0, 0]];
+        if implied_volatility.iter().all(|&v| v == flat) {
+            return make_bilinear_interpolator(
+                times.clone(),
+                forward_moneyness,
+                implied_volatility,
+            );
+        }
+        let theta = self
+            .calibrate(times, forward_moneyness, implied_volatility)
+            .context("(AndreasenHuge::local_volatility) failed to calibrate")?;
+
+        let mut step_times: Vec<Time> = Vec::new();
+        let mut step_moneyness: Vec<Array1<Real>> = Vec::new();
+        let mut step_rows: Vec<usize> = Vec::new();
+        let mut t_prev: Time = 0.0;
+        for (i, &t) in times.iter().enumerate() {
+            if t - t_prev > 2.0 * AH_TIME_SEPARATION {
+                step_times.push(if i == 0 {
+                    t_prev
+                } else {
+                    t_prev + AH_TIME_SEPARATION
+                });
+                step_moneyness.push(forward_moneyness[i].clone());
+                step_rows.push(i);
+            }
+            if t > t_prev || i == 0 {
+                step_times.push(t);
+                step_moneyness.push(forward_moneyness[i].clone());
+                step_rows.push(i);
+                t_prev = t;
+            }
+        }
+        let step_theta = theta.select(ndarray::Axis(0), &step_rows);
+        make_bilinear_interpolator(Array1::from_vec(step_times), &step_moneyness, &step_theta)
+    }
+}
+
+/// Dupire local volatility from the implied total variance w(T, y) = sigma_imp^2 * T, y = ln(K / F(T)):
+///
+/// sigma_loc^2 = (dw/dT) / (1 - y / w * dw/dy + 1/4 * (-1/4 - 1/w + y^2 / w^2) * (dw/dy)^2 + 1/2 * d^2w/dy^2)
+///
+/// The slices of w are linear in forward moneyness between the knots (as in LocalVolatilitySurface),
+/// so the derivatives in y are taken by central differences over the knot spacing which smooths the kinks.
+/// In the wings, w is extrapolated linearly in y with the slope bounded by Roger Lee's moment formula (|dw/dy| <= 2).
+/// The local variance is floored by min_volatility^2 and capped by max_volatility^2.
+/// The points of negative local variance (calendar or butterfly arbitrage in the surface) are
+/// reported by negative_local_variance and logged when the local volatility is made.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Dupire {
+    min_volatility: Real,
+    max_volatility: Real,
+}
+
+impl Default for Dupire {
+    fn default() -> Dupire {
+        Dupire {
+            min_volatility: 0.01,
+            max_volatility: 5.0,
+        }
+    }
+}
+
+/// the minimum total variance in the wing extrapolation
+const DUPIRE_MIN_TOTAL_VARIANCE: Real = 1.0e-8;
+/// Roger Lee's bound of the slope of the total variance in log-moneyness
+const DUPIRE_MAX_WING_SLOPE: Real = 2.0;
+
+impl Dupire {
+    pub fn new(min_volatility: Real, max_volatility: Real) -> Dupire {
+        Dupire {
+            min_volatility,
+            max_volatility,
+        }
+    }
+
+    /// total variance of the i-th slice at log-moneyness y
+    fn total_variance(
+        times: &Array1<Time>,
+        log_knots: &[Array1<Real>],
+        implied_volatility: &Array2<Real>,
+        i: usize,
+        y: Real,
+    ) -> Real {
+        let knots = &log_knots[i];
+        let vols = implied_volatility.row(i);
+        let t = times[i];
+        let last = knots.len() - 1;
+        let w_at = |j: usize| vols[j] * vols[j] * t;
+        let w = if y < knots[0] {
+            let slope = ((w_at(1) - w_at(0)) / (knots[1] - knots[0]))
+                .clamp(-DUPIRE_MAX_WING_SLOPE, DUPIRE_MAX_WING_SLOPE);
+            w_at(0) + slope * (y - knots[0])
+        } else if y > knots[last] {
+            let slope = ((w_at(last) - w_at(last - 1)) / (knots[last] - knots[last - 1]))
+                .clamp(-DUPIRE_MAX_WING_SLOPE, DUPIRE_MAX_WING_SLOPE);
+            w_at(last) + slope * (y - knots[last])
+        } else {
+            // linear in forward moneyness as the implied volatility of LocalVolatilitySurface
+            let (k, k0) = (y.exp(), knots.mapv(|v| v.exp()));
+            let mut j = 0;
+            while j + 1 < last && k0[j + 1] < k {
+                j += 1;
+            }
+            let weight = (k - k0[j]) / (k0[j + 1] - k0[j]);
+            let vol = vols[j] * (1.0 - weight) + vols[j + 1] * weight;
+            vol * vol * t
+        };
+        w.max(DUPIRE_MIN_TOTAL_VARIANCE)
+    }
+
+    /// local variance on the knots before flooring
+    fn raw_local_variance(
+        &self,
+        times: &Array1<Time>,
+        forward_moneyness: &[Array1<Real>],
+        implied_volatility: &Array2<Real>,
+    ) -> Result<Array2<Real>> {
+        check_local_volatility_input(times, forward_moneyness, implied_volatility)?;
+        if times.iter().any(|&t| t <= 0.0) || times.windows(2).into_iter().any(|w| w[0] >= w[1]) {
+            return Err(anyhow!(
+                "({}:{}) times must be positive and strictly increasing: {:?}",
+                file!(),
+                line!(),
+                times
+            ));
+        }
+        let log_knots: Vec<Array1<Real>> = forward_moneyness
+            .iter()
+            .map(|k| k.mapv(|v| v.ln()))
+            .collect();
+        let w =
+            |i: usize, y: Real| Dupire::total_variance(times, &log_knots, implied_volatility, i, y);
+
+        let n_t = times.len();
+        let mut res = Array2::zeros(implied_volatility.raw_dim());
+        for i in 0..n_t {
+            let knots = &log_knots[i];
+            let h = (knots[knots.len() - 1] - knots[0]) / (knots.len() - 1) as Real;
+            for (j, &y) in knots.iter().enumerate() {
+                let w0 = w(i, y);
+                // dw/dT by the central difference of the neighboring slices (w(0, y) = 0)
+                let (t_prev, w_prev) = if i == 0 {
+                    (0.0, 0.0)
+                } else {
+                    (times[i - 1], w(i - 1, y))
+                };
+                let dw_dt = if i + 1 < n_t {
+                    (w(i + 1, y) - w_prev) / (times[i + 1] - t_prev)
+                } else {
+                    (w0 - w_prev) / (times[i] - t_prev)
+                };
+                let (w_up, w_down) = (w(i, y + h), w(i, y - h));
+                let dw_dy = (w_up - w_down) / (2.0 * h);
+                let d2w_dy2 = (w_up - 2.0 * w0 + w_down) / (h * h);
+                let denominator = 1.0 - y / w0 * dw_dy
+                    + 0.25 * (-0.25 - 1.0 / w0 + y * y / (w0 * w0)) * dw_dy * dw_dy
+                    + 0.5 * d2w_dy2;
+                res[[i, j]] = if denominator > 0.0 {
+                    dw_dt / denominator
+                } else {
+                    -dw_dt.abs().max(Real::EPSILON)
+                };
+            }
+        }
+        Ok(res)
+    }
+
+    /// (time, forward moneyness, local variance) where the surface implies negative local variance
+    pub fn negative_local_variance(
+        &self,
+        times: &Array1<Time>,
+        forward_moneyness: &[Array1<Real>],
+        implied_volatility: &Array2<Real>,
+    ) -> Result<Vec<(Time, Real, Real)>> {
+        let local_variance =
+            self.raw_local_variance(times, forward_moneyness, implied_volatility)?;
+        let mut res = Vec::new();
+        for ((i, j), &v) in local_variance.indexed_iter() {
+            if v < 0.0 {
+                res.push((times[i], forward_moneyness[i][j], v));
+            }
+        }
+        Ok(res)
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
-pub struct Dupire {}
 impl VolatilityInterplatorTrait for Dupire {
-    fn interpolate(&self) -> Real {
-        0.0
+    fn local_volatility(
+        &self,
+        times: &Array1<Time>,
+        forward_moneyness: &[Array1<Real>],
+        implied_volatility: &Array2<Real>,
+    ) -> Result<BilinearInterpolator> {
+        let local_variance = self
+            .raw_local_variance(times, forward_moneyness, implied_volatility)
+            .context("(Dupire::local_volatility) failed to compute local variance")?;
+        let negative_count = local_variance.iter().filter(|&&v| v < 0.0).count();
+        if negative_count > 0 {
+            warn!(
+                "({}:{}) Dupire local variance is negative on {} of {} points, floored by {}",
+                file!(),
+                line!(),
+                negative_count,
+                local_variance.len(),
+                self.min_volatility
+            );
+        }
+        let local_volatility = local_variance.mapv(|v| {
+            v.max(self.min_volatility * self.min_volatility)
+                .sqrt()
+                .min(self.max_volatility)
+        });
+        make_bilinear_interpolator(times.clone(), forward_moneyness, &local_volatility)
     }
 }
 
@@ -33,6 +452,241 @@ pub enum VolatilityInterplator {
 
 impl Default for VolatilityInterplator {
     fn default() -> VolatilityInterplator {
-        VolatilityInterplator::AndreasenHuge(AndreasenHuge {})
+        VolatilityInterplator::AndreasenHuge(AndreasenHuge::default())
+    }
+}
+
+fn check_local_volatility_input(
+    times: &Array1<Time>,
+    forward_moneyness: &[Array1<Real>],
+    implied_volatility: &Array2<Real>,
+) -> Result<()> {
+    if times.is_empty()
+        || forward_moneyness.len() != times.len()
+        || implied_volatility.nrows() != times.len()
+    {
+        return Err(anyhow!(
+            "({}:{}) size mismatch: times = {}, forward_moneyness = {}, implied_volatility = {:?}",
+            file!(),
+            line!(),
+            times.len(),
+            forward_moneyness.len(),
+            implied_volatility.shape()
+        ));
+    }
+    for knots in forward_moneyness.iter() {
+        if knots.len() != implied_volatility.ncols() || knots.len() < 2 {
+            return Err(anyhow!(
+                "({}:{}) forward moneyness {:?} does not match implied volatility columns ({})",
+                file!(),
+                line!(),
+                knots,
+                implied_volatility.ncols()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// theta on the grid nodes, linearly interpolated in moneyness between the knots and flat outside
+/// which is the same interpolation as the local volatility given to the pricers.
+fn nodewise_theta(
+    x: &Array1<Real>,
+    log_knots: &Array1<Real>,
+    theta: &Array1<Real>,
+) -> Array1<Real> {
+    let knots = log_knots.mapv(|v| v.exp());
+    let last = knots.len() - 1;
+    let mut j = 0;
+    x.mapv(|xv| {
+        let k = xv.exp();
+        if k <= knots[0] {
+            return theta[0];
+        }
+        if k >= knots[last] {
+            return theta[last];
+        }
+        while knots[j + 1] < k {
+            j += 1;
+        }
+        let w = (k - knots[j]) / (knots[j + 1] - knots[j]);
+        theta[j] * (1.0 - w) + theta[j + 1] * w
+    })
+}
+
+fn make_bilinear_interpolator(
+    times: Array1<Time>,
+    forward_moneyness: &[Array1<Real>],
+    values: &Array2<Real>,
+) -> Result<BilinearInterpolator> {
+    let mut interpolators = Vec::with_capacity(times.len());
+    for (i, knots) in forward_moneyness.iter().enumerate() {
+        interpolators.push(LinearInterpolator1D::new(
+            knots.to_owned(),
+            values.row(i).to_owned(),
+            ExtraPolationType::Flat,
+            true,
+        )?);
+    }
+    BilinearInterpolator::new(times, interpolators, true, ExtraPolationType::Flat)
+}
+
+/// linear interpolation on the uniform grid with flat extrapolation
+pub(crate) fn interpolate_on_uniform_grid(
+    x: &Array1<Real>,
+    values: &Array1<Real>,
+    target: Real,
+) -> Real {
+    let n = x.len();
+    let position = (target - x[0]) / (x[1] - x[0]);
+    if position <= 0.0 {
+        return values[0];
+    }
+    let i = position.floor() as usize;
+    if i >= n - 1 {
+        return values[n - 1];
+    }
+    let w = position - i as Real;
+    values[i] * (1.0 - w) + values[i + 1] * w
+}
+
+/// Black call price normalized by the discounted forward, k = K / F
+fn normalized_black_call(k: Real, vol: Real, t: Time) -> Real {
+    let intrinsic = (1.0 - k).max(0.0);
+    let w = (vol * vol * t) as f64;
+    if w <= 0.0 {
+        return intrinsic;
+    }
+    let normal = Normal::new(0.0, 1.0).unwrap();
+    let d1 = (-(k as f64).ln() + 0.5 * w) / w.sqrt();
+    let d2 = d1 - w.sqrt();
+    (normal.cdf(d1) - k as f64 * normal.cdf(d2)) as Real
+}
+
+/// derivative of normalized_black_call with respect to vol
+fn normalized_black_vega(k: Real, vol: Real, t: Time) -> Real {
+    let w = (vol * vol * t) as f64;
+    if w <= 0.0 {
+        return 0.0;
+    }
+    let normal = Normal::new(0.0, 1.0).unwrap();
+    let d1 = (-(k as f64).ln() + 0.5 * w) / w.sqrt();
+    (normal.pdf(d1) * (t as f64).sqrt()) as Real
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_andreasen_huge_calibration() -> Result<()> {
+        let times = Array1::from_vec(vec![0.25, 0.5, 1.0, 2.0]);
+        let spot_moneyness = Array1::linspace(0.6, 1.4, 17);
+        let forward_moneyness: Vec<Array1<Real>> = times
+            .iter()
+            .map(|&t: &Time| spot_moneyness.mapv(|m| m * (-0.02 * t).exp()))
+            .collect();
+        let ah = AndreasenHuge::default();
+
+        // flat implied volatility gives flat local volatility
+        let flat = Array2::from_elem((times.len(), spot_moneyness.len()), 0.2);
+        let theta = ah.calibrate(&times, &forward_moneyness, &flat)?;
+        for (&lv, &k) in theta.row(2).iter().zip(forward_moneyness[2].iter()) {
+            if (0.8..1.2).contains(&k) {
+                assert!((lv - 0.2).abs() < 5.0e-3, "flat: k = {}, lv = {}", k, lv);
+            }
+        }
+
+        // skewed implied volatility: the model reproduces the market prices
+        // and the local volatility skew is about twice the implied volatility skew around ATM
+        let skew = Array2::from_shape_fn((times.len(), spot_moneyness.len()), |(i, j)| {
+            0.2 - 0.1 * forward_moneyness[i][j].ln()
+        });
+        let theta = ah.calibrate(&times, &forward_moneyness, &skew)?;
+        let x = ah.log_moneyness_grid(&times, &forward_moneyness, &skew)?;
+        let mut prices = x.mapv(|v: Real| (1.0 - v.exp()).max(0.0));
+        let mut t_prev = 0.0;
+        for i in 0..times.len() {
+            let knots = &forward_moneyness[i];
+            let node_theta = nodewise_theta(&x, &knots.mapv(|k| k.ln()), &theta.row(i).to_owned());
+            prices = ah.propagate(&prices, &x, &node_theta, times[i] - t_prev)?;
+            t_prev = times[i];
+            for (j, &k) in knots.iter().enumerate() {
+                if !(0.7..1.3).contains(&k) {
+                    continue;
+                }
+                let model = interpolate_on_uniform_grid(&x, &prices, k.ln());
+                let market = normalized_black_call(k, skew[[i, j]], times[i]);
+                let vega = normalized_black_vega(k, skew[[i, j]], times[i]);
+                assert!(
+                    ((model - market) / vega).abs() < 2.0e-3,
+                    "t = {}, k = {}, model = {}, market = {}",
+                    times[i],
+                    k,
+                    model,
+                    market
+                );
+            }
+        }
+
+        let atm = 8;
+        let lv_slope = (theta[[2, atm + 1]] - theta[[2, atm - 1]])
+            / (forward_moneyness[2][atm + 1].ln() - forward_moneyness[2][atm - 1].ln());
+        assert!(
+            (lv_slope + 0.2).abs() < 0.05,
+            "local volatility slope = {}",
+            lv_slope
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_dupire_local_volatility() -> Result<()> {
+        let times = Array1::from_vec(vec![0.25, 0.5, 1.0, 2.0]);
+        let spot_moneyness = Array1::linspace(0.6, 1.4, 17);
+        let forward_moneyness: Vec<Array1<Real>> = times
+            .iter()
+            .map(|&t: &Time| spot_moneyness.mapv(|m| m * (-0.02 * t).exp()))
+            .collect();
+        let dupire = Dupire::default();
+
+        // flat implied volatility gives flat local volatility
+        let flat = Array2::from_elem((times.len(), spot_moneyness.len()), 0.2);
+        let lv = dupire.local_volatility(&times, &forward_moneyness, &flat)?;
+        for &k in forward_moneyness[1].iter() {
+            let v = lv.interpolate(0.5, k)?;
+            assert!((v - 0.2).abs() < 1.0e-4, "flat: k = {}, lv = {}", k, v);
+        }
+
+        // skewed implied volatility: Dupire agrees with Andreasen-Huge around ATM
+        let skew = Array2::from_shape_fn((times.len(), spot_moneyness.len()), |(i, j)| {
+            0.2 - 0.1 * forward_moneyness[i][j].ln()
+        });
+        assert!(dupire
+            .negative_local_variance(&times, &forward_moneyness, &skew)?
+            .is_empty());
+        let dupire_lv = dupire.local_volatility(&times, &forward_moneyness, &skew)?;
+        let theta = AndreasenHuge::default().calibrate(&times, &forward_moneyness, &skew)?;
+        for j in 6..11 {
+            let k = forward_moneyness[2][j];
+            let v = dupire_lv.interpolate(1.0, k)?;
+            assert!(
+                (v - theta[[2, j]]).abs() < 0.01,
+                "k = {}, dupire = {}, andreasen-huge = {}",
+                k,
+                v,
+                theta[[2, j]]
+            );
+        }
+
+        // the implied volatility decreasing fast in maturity is a calendar arbitrage
+        let calendar = Array2::from_shape_fn((times.len(), spot_moneyness.len()), |(i, _)| {
+            0.4 / (1.0 + 2.0 * i as Real)
+        });
+        let negative = dupire.negative_local_variance(&times, &forward_moneyness, &calendar)?;
+        assert!(!negative.is_empty());
+        let lv = dupire.local_volatility(&times, &forward_moneyness, &calendar)?;
+        assert!((lv.interpolate(1.0, 1.0)? - dupire.min_volatility).abs() < 1.0e-6);
+        Ok(())
     }
 }
@@ -1,6 +1,7 @@
 use crate::definitions::{Real, Time};
 use crate::parameters::volatilities::{
-    constant_volatility::ConstantVolatility, local_volatility_surface::LocalVolatilitySurface,
+    constant_volatility::ConstantVolatility, fx_volatility_surface::FxVolatilitySurface,
+    local_volatility_surface::LocalVolatilitySurface, svi_volatility_surface::SviVolatilitySurface,
 };
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
@@ -9,6 +10,8 @@ use serde::{Deserialize, Serialize};
 pub enum VolatilityType {
     ConstantVolatility,
     LocalVolatilitySurface,
+    SviVolatilitySurface,
+    FxVolatilitySurface,
 }
 
 pub trait VolatilityTrait {
@@ -26,8 +29,8 @@ pub trait VolatilityTrait {
         bump: Real,
     ) -> Result<()>;
 
-    fn get_local_volatility(&self, t: Time, forward_moneyness: Real) -> Real {
-        self.get_value(t, forward_moneyness)
+    fn get_local_volatility(&self, t: Time, forward_moneyness: Real) -> Result<Real> {
+        Ok(self.get_value(t, forward_moneyness))
     }
 }
 
@@ -35,6 +38,8 @@ pub trait VolatilityTrait {
 pub enum Volatility {
     ConstantVolatility(ConstantVolatility),
     LocalVolatilitySurface(LocalVolatilitySurface),
+    SviVolatilitySurface(SviVolatilitySurface),
+    FxVolatilitySurface(FxVolatilitySurface),
 }
 
 impl Volatility {
@@ -42,6 +47,8 @@ impl Volatility {
         match self {
             Volatility::ConstantVolatility(volatility) => volatility.get_name(),
             Volatility::LocalVolatilitySurface(volatility) => volatility.get_name(),
+            Volatility::SviVolatilitySurface(volatility) => volatility.get_name(),
+            Volatility::FxVolatilitySurface(volatility) => volatility.get_name(),
         }
     }
 
@@ -49,6 +56,8 @@ impl Volatility {
         match self {
             Volatility::ConstantVolatility(volatility) => volatility.get_code(),
             Volatility::LocalVolatilitySurface(volatility) => volatility.get_code(),
+            Volatility::SviVolatilitySurface(volatility) => volatility.get_code(),
+            Volatility::FxVolatilitySurface(volatility) => volatility.get_code(),
         }
     }
 
@@ -60,6 +69,30 @@ impl Volatility {
             Volatility::LocalVolatilitySurface(volatility) => {
                 volatility.get_value(t, forward_moneyness)
             }
+            Volatility::SviVolatilitySurface(volatility) => {
+                volatility.get_value(t, forward_moneyness)
+            }
+            Volatility::FxVolatilitySurface(volatility) => {
+                volatility.get_value(t, forward_moneyness)
+            }
+        }
+    }
+
+    /// local volatility at time t where the spot is located at forward_moneyness * forward(t)
+    pub fn get_local_volatility(&self, t: Time, forward_moneyness: Real) -> Result<Real> {
+        match self {
+            Volatility::ConstantVolatility(volatility) => {
+                volatility.get_local_volatility(t, forward_moneyness)
+            }
+            Volatility::LocalVolatilitySurface(volatility) => {
+                volatility.get_local_volatility(t, forward_moneyness)
+            }
+            Volatility::SviVolatilitySurface(volatility) => {
+                volatility.get_local_volatility(t, forward_moneyness)
+            }
+            Volatility::FxVolatilitySurface(volatility) => {
+                volatility.get_local_volatility(t, forward_moneyness)
+            }
         }
     }
 
@@ -71,6 +104,12 @@ impl Volatility {
             Volatility::LocalVolatilitySurface(volatility) => {
                 volatility.total_variance(t, forward_moneyness)
             }
+            Volatility::SviVolatilitySurface(volatility) => {
+                volatility.total_variance(t, forward_moneyness)
+            }
+            Volatility::FxVolatilitySurface(volatility) => {
+                volatility.total_variance(t, forward_moneyness)
+            }
         }
     }
 
@@ -82,6 +121,12 @@ impl Volatility {
             Volatility::LocalVolatilitySurface(volatility) => {
                 volatility.total_deviation(t, forward_moneyness)
             }
+            Volatility::SviVolatilitySurface(volatility) => {
+                volatility.total_deviation(t, forward_moneyness)
+            }
+            Volatility::FxVolatilitySurface(volatility) => {
+                volatility.total_deviation(t, forward_moneyness)
+            }
         }
     }
 
@@ -92,6 +137,12 @@ impl Volatility {
                 volatility.build()?;
                 Ok(())
             }
+            Volatility::SviVolatilitySurface(volatility) => {
+                volatility.build()?;
+                Ok(())
+            }
+            // the smiles are solved from the quotes on construction
+            Volatility::FxVolatilitySurface(_volatility) => Ok(()),
         }
     }
     pub fn bump_volatility(
@@ -117,6 +168,20 @@ impl Volatility {
                 right_spot_moneyness,
                 bump,
             ),
+            Volatility::SviVolatilitySurface(volatility) => volatility.bump_volatility(
+                time1,
+                time2,
+                left_spot_moneyness,
+                right_spot_moneyness,
+                bump,
+            ),
+            Volatility::FxVolatilitySurface(volatility) => volatility.bump_volatility(
+                time1,
+                time2,
+                left_spot_moneyness,
+                right_spot_moneyness,
+                bump,
+            ),
         }
     }
 
@@ -124,6 +189,8 @@ impl Volatility {
         match self {
             Volatility::ConstantVolatility(_) => VolatilityType::ConstantVolatility,
             Volatility::LocalVolatilitySurface(_) => VolatilityType::LocalVolatilitySurface,
+            Volatility::SviVolatilitySurface(_) => VolatilityType::SviVolatilitySurface,
+            Volatility::FxVolatilitySurface(_) => VolatilityType::FxVolatilitySurface,
         }
     }
 }
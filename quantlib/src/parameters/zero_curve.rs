@@ -1,11 +1,12 @@
 use crate::currency::Currency;
 use crate::data::vector_data::VectorData;
 use crate::definitions::{Real, Time};
-use crate::enums::Compounding;
+use crate::enums::{Compounding, ZeroCurveInterpolationType};
 use crate::evaluation_date::EvaluationDate;
 use crate::math::interpolator::ExtraPolationType;
 use crate::math::interpolator::Interpolator1D;
 use crate::math::interpolator::InterpolatorReal1D;
+use crate::math::interpolators::cubic_spline_interpolator::CubicSplineInterpolator1D;
 use crate::math::interpolators::linear_interpolator::LinearInterpolator1D;
 use crate::math::interpolators::stepwise_interpolatior::ConstantInterpolator1D;
 use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
@@ -24,6 +25,221 @@ enum ZeroCurveInterpolator {
     Linear(LinearInterpolator1D),
 }
 
+/// Interpolation on the input nodes for the schemes other than ZeroCurveInterpolationType::Linear.
+/// Each variant gives the integrated forward, i.e., r(t) * t = -ln(discount factor)
+#[derive(Clone, Debug)]
+enum NodeInterpolator {
+    Flat(Real),
+    /// r * t is linear between the nodes starting from (0, 0).
+    /// After the last node, the last forward is extended if extend_forward, otherwise the zero rate is flat
+    LogLinear {
+        times: Vec<Time>,
+        integrated: Vec<Real>,
+        extend_forward: bool,
+    },
+    /// Hagan and West (2006), "Interpolation Methods for Curve Construction".
+    /// discrete_forwards[i] is the average forward on (times[i], times[i+1]),
+    /// and node_forwards[i] is the instantaneous forward at times[i]
+    MonotoneConvex {
+        times: Vec<Time>,
+        integrated: Vec<Real>,
+        discrete_forwards: Vec<Real>,
+        node_forwards: Vec<Real>,
+    },
+    NaturalCubicRate(CubicSplineInterpolator1D),
+}
+
+impl NodeInterpolator {
+    fn new(
+        interpolation_type: ZeroCurveInterpolationType,
+        rate_times: &Array1<Time>,
+        zero_rates: &Array1<Real>,
+    ) -> Result<Option<NodeInterpolator>> {
+        if interpolation_type == ZeroCurveInterpolationType::Linear {
+            return Ok(None);
+        }
+        if rate_times.windows(2).into_iter().any(|w| w[1] <= w[0]) {
+            return Err(anyhow!(
+                "({}:{}) {:?} needs strictly increasing times: {:?}",
+                file!(),
+                line!(),
+                interpolation_type,
+                rate_times
+            ));
+        }
+        let last_rate = zero_rates[zero_rates.len() - 1];
+        if interpolation_type == ZeroCurveInterpolationType::NaturalCubicRate {
+            if rate_times.len() < 2 {
+                return Ok(Some(NodeInterpolator::Flat(last_rate)));
+            }
+            return Ok(Some(NodeInterpolator::NaturalCubicRate(
+                CubicSplineInterpolator1D::new(
+                    rate_times.clone(),
+                    zero_rates.clone(),
+                    ExtraPolationType::Flat,
+                )?,
+            )));
+        }
+
+        // the discount factor is one at time zero, so the nodes up to time zero are not used
+        let mut times: Vec<Time> = vec![0.0];
+        let mut integrated: Vec<Real> = vec![0.0];
+        for (t, r) in rate_times.iter().zip(zero_rates.iter()) {
+            if *t > 0.0 {
+                times.push(*t);
+                integrated.push(r * t);
+            }
+        }
+        if times.len() < 2 {
+            return Ok(Some(NodeInterpolator::Flat(last_rate)));
+        }
+
+        match interpolation_type {
+            ZeroCurveInterpolationType::Linear | ZeroCurveInterpolationType::NaturalCubicRate => {
+                unreachable!()
+            }
+            ZeroCurveInterpolationType::LogLinearDiscount => {
+                Ok(Some(NodeInterpolator::LogLinear {
+                    times,
+                    integrated,
+                    extend_forward: false,
+                }))
+            }
+            ZeroCurveInterpolationType::FlatForward => Ok(Some(NodeInterpolator::LogLinear {
+                times,
+                integrated,
+                extend_forward: true,
+            })),
+            ZeroCurveInterpolationType::MonotoneConvex => {
+                let n = times.len() - 1;
+                let discrete_forwards: Vec<Real> = (0..n)
+                    .map(|i| (integrated[i + 1] - integrated[i]) / (times[i + 1] - times[i]))
+                    .collect();
+                let mut node_forwards = vec![discrete_forwards[0]; n + 1];
+                if n > 1 {
+                    for i in 1..n {
+                        let w = (times[i] - times[i - 1]) / (times[i + 1] - times[i - 1]);
+                        node_forwards[i] =
+                            w * discrete_forwards[i] + (1.0 - w) * discrete_forwards[i - 1];
+                    }
+                    node_forwards[0] =
+                        discrete_forwards[0] - 0.5 * (node_forwards[1] - discrete_forwards[0]);
+                    node_forwards[n] = discrete_forwards[n - 1]
+                        - 0.5 * (node_forwards[n - 1] - discrete_forwards[n - 1]);
+                }
+                // positivity preserving collar
+                if discrete_forwards.iter().all(|f| *f > 0.0) {
+                    node_forwards[0] = node_forwards[0].clamp(0.0, 2.0 * discrete_forwards[0]);
+                    for i in 1..n {
+                        let cap = 2.0 * discrete_forwards[i - 1].min(discrete_forwards[i]);
+                        node_forwards[i] = node_forwards[i].clamp(0.0, cap);
+                    }
+                    node_forwards[n] = node_forwards[n].clamp(0.0, 2.0 * discrete_forwards[n - 1]);
+                }
+                Ok(Some(NodeInterpolator::MonotoneConvex {
+                    times,
+                    integrated,
+                    discrete_forwards,
+                    node_forwards,
+                }))
+            }
+        }
+    }
+
+    /// integral of g from 0 to x in Hagan-West, where the forward is
+    /// discrete_forward + g(x) on the interval and g(0) = g0, g(1) = g1, and the integral of g over [0, 1] is zero
+    fn monotone_convex_integral(g0: Real, g1: Real, x: Real) -> Real {
+        if g0 == 0.0 && g1 == 0.0 {
+            return 0.0;
+        }
+        let cube = |y: Real| y * y * y;
+        if g0 * g1 >= 0.0 {
+            // both sides are on the same side of the discrete forward
+            let eta = g1 / (g1 + g0);
+            let a = -g0 * g1 / (g0 + g1);
+            let mut res = a * x;
+            if eta > 0.0 {
+                res += (g0 - a) * eta / 3.0 * (1.0 - cube((eta - x.min(eta)) / eta));
+            }
+            if x > eta && eta < 1.0 {
+                res += (g1 - a) * cube(x - eta) / (3.0 * (1.0 - eta) * (1.0 - eta));
+            }
+            return res;
+        }
+        let ratio = -g1 / g0;
+        if (0.5..=2.0).contains(&ratio) {
+            g0 * (x - 2.0 * x * x + x * x * x) + g1 * (-x * x + x * x * x)
+        } else if ratio > 2.0 {
+            let eta = (g1 + 2.0 * g0) / (g1 - g0);
+            let mut res = g0 * x;
+            if x > eta {
+                res += (g1 - g0) * cube(x - eta) / (3.0 * (1.0 - eta) * (1.0 - eta));
+            }
+            res
+        } else {
+            let eta = 3.0 * g1 / (g1 - g0);
+            g1 * x + (g0 - g1) * eta / 3.0 * (1.0 - cube((eta - x.min(eta)) / eta))
+        }
+    }
+
+    /// r(t) * t
+    fn integrated_forward(&self, t: Time) -> Result<Real> {
+        if t < 0.0 {
+            return Err(anyhow!(
+                "({}:{}) negative time {} in ZeroCurve",
+                file!(),
+                line!(),
+                t
+            ));
+        }
+        match self {
+            NodeInterpolator::Flat(r) => Ok(r * t),
+            NodeInterpolator::NaturalCubicRate(spline) => Ok(spline.interpolate(t)? * t),
+            NodeInterpolator::LogLinear {
+                times,
+                integrated,
+                extend_forward,
+            } => {
+                let n = times.len() - 1;
+                if t >= times[n] {
+                    return match extend_forward {
+                        true => {
+                            let last_forward =
+                                (integrated[n] - integrated[n - 1]) / (times[n] - times[n - 1]);
+                            Ok(integrated[n] + last_forward * (t - times[n]))
+                        }
+                        false => Ok(integrated[n] / times[n] * t),
+                    };
+                }
+                let i = times.partition_point(|s| *s <= t) - 1;
+                let w = (t - times[i]) / (times[i + 1] - times[i]);
+                Ok(integrated[i] + w * (integrated[i + 1] - integrated[i]))
+            }
+            NodeInterpolator::MonotoneConvex {
+                times,
+                integrated,
+                discrete_forwards,
+                node_forwards,
+            } => {
+                let n = times.len() - 1;
+                if t >= times[n] {
+                    return Ok(integrated[n] + node_forwards[n] * (t - times[n]));
+                }
+                let i = times.partition_point(|s| *s <= t) - 1;
+                let h = times[i + 1] - times[i];
+                let x = (t - times[i]) / h;
+                let fd = discrete_forwards[i];
+                let g = NodeInterpolator::monotone_convex_integral(
+                    node_forwards[i] - fd,
+                    node_forwards[i + 1] - fd,
+                    x,
+                );
+                Ok(integrated[i] + h * (fd * x + g))
+            }
+        }
+    }
+}
+
 /// ZeroCurve is a curve of zero rates which implements Parameter (Observer) trait.
 /// Input is a vector of dates and a vector of zero rates of Data (observable) type.
 /// when the zero rates are updated, the zero curve will be updated.
@@ -35,6 +251,11 @@ pub struct ZeroCurve {
     discount_times: Array1<Time>,
     discount_factors: Array1<Real>,
     discount_interpolator: LinearInterpolator1D,
+    input_times: Array1<Time>,
+    input_rates: Array1<Real>,
+    interpolation_type: ZeroCurveInterpolationType,
+    node_interpolator: Option<NodeInterpolator>,
+    base_curve: Option<Rc<RefCell<ZeroCurve>>>,
     time_calculator: NullCalendar,
     name: String,
     code: String,
@@ -65,7 +286,6 @@ impl ZeroCurve {
     ) -> Result<ZeroCurve> {
         let rate_times = data.get_times_clone();
         let zero_rates = data.get_value_clone();
-        let time_calculator = NullCalendar::default();
 
         if rate_times.len() != zero_rates.len() {
             let error = anyhow!(
@@ -96,6 +316,32 @@ impl ZeroCurve {
             return Err(error);
         }
 
+        ZeroCurve::build(
+            evaluation_date,
+            rate_times,
+            zero_rates,
+            ZeroCurveInterpolationType::Linear,
+            name,
+            code,
+        )
+    }
+
+    /// Build the curve on the input nodes with the interpolation scheme.
+    /// For ZeroCurveInterpolationType::Linear, the rates are interpolated on the tenor grid as described in ZeroCurve::new.
+    /// For the other schemes, the discount factors are given by the scheme on the input nodes,
+    /// and the rates and discount factors on the tenor grid are cached only for inspection.
+    fn build(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        rate_times: Array1<Time>,
+        zero_rates: Array1<Real>,
+        interpolation_type: ZeroCurveInterpolationType,
+        name: String,
+        code: String,
+    ) -> Result<ZeroCurve> {
+        let time_calculator = NullCalendar::default();
+        let node_interpolator = NodeInterpolator::new(interpolation_type, &rate_times, &zero_rates)
+            .with_context(|| format!("({}:{}) failed to build {}", file!(), line!(), name))?;
+
         let rate_interpolator: ZeroCurveInterpolator = if zero_rates.len() == 1 {
             ZeroCurveInterpolator::Constant(ConstantInterpolator1D::new(zero_rates[0])?)
         } else {
@@ -123,18 +369,35 @@ impl ZeroCurve {
             );
         }
 
-        let interpolated_rates = match &rate_interpolator {
-            ZeroCurveInterpolator::Constant(c) => {
-                c.vectorized_interpolate_for_sorted_ndarray(&discount_times)?
+        let (interpolated_rates, discount_factors) = match &node_interpolator {
+            None => {
+                let interpolated_rates = match &rate_interpolator {
+                    ZeroCurveInterpolator::Constant(c) => {
+                        c.vectorized_interpolate_for_sorted_ndarray(&discount_times)?
+                    }
+                    ZeroCurveInterpolator::Linear(l) => {
+                        l.vectorized_interpolate_for_sorted_ndarray(&discount_times)?
+                    }
+                };
+                let discount_factors: Array1<Real> =
+                    (&interpolated_rates * &discount_times).mapv(|x| (-x).exp());
+                (interpolated_rates, discount_factors)
             }
-            ZeroCurveInterpolator::Linear(l) => {
-                l.vectorized_interpolate_for_sorted_ndarray(&discount_times)?
+            Some(node) => {
+                let integrated = discount_times
+                    .iter()
+                    .map(|t| node.integrated_forward(*t))
+                    .collect::<Result<Array1<Real>>>()?;
+                let short_rate = node.integrated_forward(1.0e-4)? / 1.0e-4;
+                let interpolated_rates = integrated
+                    .iter()
+                    .zip(discount_times.iter())
+                    .map(|(x, t)| if *t > 0.0 { x / t } else { short_rate })
+                    .collect::<Array1<Real>>();
+                (interpolated_rates, integrated.mapv(|x| (-x).exp()))
             }
         };
 
-        let discount_factors: Array1<Real> =
-            (&interpolated_rates * &discount_times).mapv(|x| (-x).exp());
-
         let discount_interpolator = LinearInterpolator1D::new(
             discount_times.clone(),
             discount_factors.clone(),
@@ -143,12 +406,17 @@ impl ZeroCurve {
         )?;
 
         let res = ZeroCurve {
-            evaluation_date: evaluation_date.clone(),
+            evaluation_date,
             rate_interpolator,
             interpolated_rates,
             discount_times,
             discount_factors,
             discount_interpolator,
+            input_times: rate_times,
+            input_rates: zero_rates,
+            interpolation_type,
+            node_interpolator,
+            base_curve: None,
             time_calculator,
             name,
             code,
@@ -156,6 +424,44 @@ impl ZeroCurve {
         Ok(res)
     }
 
+    /// Rebuild the curve on the same input nodes with the interpolation scheme.
+    /// A bump applied before is discarded.
+    pub fn with_interpolation_type(
+        self,
+        interpolation_type: ZeroCurveInterpolationType,
+    ) -> Result<ZeroCurve> {
+        if interpolation_type == self.interpolation_type {
+            return Ok(self);
+        }
+        let mut res = ZeroCurve::build(
+            self.evaluation_date,
+            self.input_times,
+            self.input_rates,
+            interpolation_type,
+            self.name,
+            self.code,
+        )?;
+        res.base_curve = self.base_curve;
+        Ok(res)
+    }
+
+    /// Layer the curve on top of base_curve, e.g., a credit spread curve on a risk-free curve.
+    /// The rates of self are then spreads over base_curve,
+    /// so that the discount factor is the product of those of self and base_curve.
+    /// A bump on either curve is reflected in the discount factors.
+    pub fn with_base_curve(mut self, base_curve: Rc<RefCell<ZeroCurve>>) -> ZeroCurve {
+        self.base_curve = Some(base_curve);
+        self
+    }
+
+    pub fn get_base_curve(&self) -> Option<&Rc<RefCell<ZeroCurve>>> {
+        self.base_curve.as_ref()
+    }
+
+    pub fn get_interpolation_type(&self) -> ZeroCurveInterpolationType {
+        self.interpolation_type
+    }
+
     /// For self.interpolated_rates in the time_interval (date1 < date <= date2)
     /// bump self.interpolated_rates by bump_val
     /// then reset
@@ -211,6 +517,25 @@ impl ZeroCurve {
         }
         // sanity check is done
 
+        // the schemes on the input nodes bump the input rates in the interval and rebuild the curve
+        if self.node_interpolator.is_some() {
+            let mask = self
+                .input_times
+                .mapv(|x| if (x > t1) & (x <= t2) { 1.0 } else { 0.0 });
+            let bumped_rates = &self.input_rates + mask * bump_val;
+            let base_curve = self.base_curve.take();
+            *self = ZeroCurve::build(
+                self.evaluation_date.clone(),
+                self.input_times.clone(),
+                bumped_rates,
+                self.interpolation_type,
+                self.name.clone(),
+                self.code.clone(),
+            )?;
+            self.base_curve = base_curve;
+            return Ok(());
+        }
+
         // bump rates
         let mask = self
             .discount_times
@@ -247,6 +572,25 @@ impl ZeroCurve {
         self.interpolated_rates.clone()
     }
 
+    /// zero rate of the curve itself at time, i.e., the base curve is not added.
+    /// This is for the curves of quotes (e.g., CDS par spreads) rather than discounting.
+    pub fn get_zero_rate(&self, time: Time) -> Result<Real> {
+        match &self.node_interpolator {
+            None => match &self.rate_interpolator {
+                ZeroCurveInterpolator::Constant(c) => c.interpolate(time),
+                ZeroCurveInterpolator::Linear(l) => l.interpolate(time),
+            },
+            Some(node) => {
+                let t = time.max(1.0e-4);
+                Ok(node.integrated_forward(t)? / t)
+            }
+        }
+    }
+
+    pub fn get_input_times(&self) -> &Array1<Time> {
+        &self.input_times
+    }
+
     pub fn dummy_curve() -> Result<ZeroCurve> {
         let dt = EvaluationDate::new(datetime!(1970-01-01 00:00:00 UTC));
         let evaluation_date = Rc::new(RefCell::new(dt));
@@ -270,15 +614,36 @@ impl ZeroCurve {
         )
     }
     pub fn get_discount_factor(&self, time: Time) -> Result<Real> {
-        self.discount_interpolator.interpolate(time)
+        let res = match &self.node_interpolator {
+            None => self.discount_interpolator.interpolate(time)?,
+            Some(node) => (-node.integrated_forward(time)?).exp(),
+        };
+        match &self.base_curve {
+            None => Ok(res),
+            Some(base_curve) => Ok(res * base_curve.borrow().get_discount_factor(time)?),
+        }
     }
 
     pub fn get_vectorized_discount_factor_for_sorted_time(
         &self,
         times: &Array1<Time>,
     ) -> Result<Array1<Real>> {
-        self.discount_interpolator
-            .vectorized_interpolate_for_sorted_ndarray(times)
+        let res = match &self.node_interpolator {
+            None => self
+                .discount_interpolator
+                .vectorized_interpolate_for_sorted_ndarray(times)?,
+            Some(node) => times
+                .iter()
+                .map(|t| Ok((-node.integrated_forward(*t)?).exp()))
+                .collect::<Result<Array1<Real>>>()?,
+        };
+        match &self.base_curve {
+            None => Ok(res),
+            Some(base_curve) => Ok(res
+                * base_curve
+                    .borrow()
+                    .get_vectorized_discount_factor_for_sorted_time(times)?),
+        }
     }
 
     pub fn get_discount_factor_at_date(&self, date: &OffsetDateTime) -> Result<Real> {
@@ -528,4 +893,113 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_zero_curve_interpolation_types() -> Result<()> {
+        let eval_dt = datetime!(2024-01-02 00:00:00 UTC);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_dt)));
+        // the discrete forwards are positive, but the rate drops steeply enough after 1Y
+        // that the linear interpolation on the rates gives negative forwards before 3Y
+        let times = array![0.25, 1.0, 3.0, 5.0, 10.0];
+        let rates = array![0.045, 0.05, 0.026, 0.03, 0.032];
+        let data = VectorData::new(
+            rates.clone(),
+            None,
+            Some(times.clone()),
+            None,
+            Currency::KRW,
+            "test".to_string(),
+            "test".to_string(),
+        )?;
+
+        let grid: Vec<Time> = (0..480).map(|i| 0.001 + i as Time * 0.025).collect();
+        let min_short_rate = |curve: &ZeroCurve| -> Result<Real> {
+            let short_rates = curve.get_vectorized_short_rate_for_sorted_times(&grid)?;
+            Ok(short_rates.into_iter().fold(Real::MAX, Real::min))
+        };
+
+        let linear = ZeroCurve::new(
+            evaluation_date.clone(),
+            &data,
+            "test".to_string(),
+            "test".to_string(),
+        )?;
+        assert_eq!(
+            linear.get_interpolation_type(),
+            ZeroCurveInterpolationType::Linear
+        );
+        assert!(min_short_rate(&linear)? < -0.001);
+
+        for interpolation_type in [
+            ZeroCurveInterpolationType::LogLinearDiscount,
+            ZeroCurveInterpolationType::MonotoneConvex,
+            ZeroCurveInterpolationType::NaturalCubicRate,
+            ZeroCurveInterpolationType::FlatForward,
+        ] {
+            let mut curve = linear.clone().with_interpolation_type(interpolation_type)?;
+            // the input nodes are reproduced
+            for (t, r) in times.iter().zip(rates.iter()) {
+                let df = curve.get_discount_factor(*t)?;
+                assert!(
+                    (df - (-r * t).exp()).abs() < 1.0e-6,
+                    "{:?}: t = {}, df = {}, expected = {}",
+                    interpolation_type,
+                    t,
+                    df,
+                    (-r * t).exp()
+                );
+            }
+
+            match interpolation_type {
+                ZeroCurveInterpolationType::MonotoneConvex
+                | ZeroCurveInterpolationType::LogLinearDiscount
+                | ZeroCurveInterpolationType::FlatForward => {
+                    let min_rate = min_short_rate(&curve)?;
+                    assert!(min_rate > -1.0e-4, "{:?}: {}", interpolation_type, min_rate);
+                }
+                _ => {}
+            }
+
+            // the forwards of the smooth schemes are continuous at the nodes
+            if interpolation_type == ZeroCurveInterpolationType::MonotoneConvex
+                || interpolation_type == ZeroCurveInterpolationType::NaturalCubicRate
+            {
+                for t in [1.0, 3.0, 5.0] {
+                    let left = curve.get_forward_rate_between_times(
+                        t - 0.004,
+                        t - 0.002,
+                        Compounding::Continuous,
+                    )?;
+                    let right = curve.get_forward_rate_between_times(
+                        t + 0.002,
+                        t + 0.004,
+                        Compounding::Continuous,
+                    )?;
+                    assert!(
+                        (left - right).abs() < 2.0e-3,
+                        "{:?}: t = {}, left = {}, right = {}",
+                        interpolation_type,
+                        t,
+                        left,
+                        right
+                    );
+                }
+            }
+
+            // a parallel bump shifts the zero rates
+            let t = 2.0;
+            let df = curve.get_discount_factor(t)?;
+            curve.bump_time_interval(None, None, 0.0001)?;
+            let bumped = curve.get_discount_factor(t)?;
+            assert!(
+                (bumped / df - (-0.0001 * t).exp()).abs() < 1.0e-5,
+                "{:?}: df = {}, bumped = {}",
+                interpolation_type,
+                df,
+                bumped
+            );
+        }
+
+        Ok(())
+    }
 }
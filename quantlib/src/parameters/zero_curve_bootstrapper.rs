@@ -0,0 +1,648 @@
+use crate::currency::Currency;
+use crate::data::vector_data::VectorData;
+use crate::definitions::{Real, Time};
+use crate::evaluation_date::EvaluationDate;
+use crate::instrument::{Instrument, InstrumentTrait};
+use crate::instruments::{ktbf::KTBF, plain_swap::PlainSwap};
+use crate::parameters::zero_curve::ZeroCurve;
+use crate::pricing_engines::{
+    ktbf_pricer::KtbfPricer, plain_swap_pricer::PlainSwapPricer, pricer::PricerTrait,
+};
+use crate::time::conventions::DayCountConvention;
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use anyhow::{anyhow, Context, Result};
+use ndarray::Array1;
+use std::{cell::RefCell, rc::Rc};
+use time::OffsetDateTime;
+
+const BOOTSTRAP_MAX_SWEEP: usize = 30;
+const BOOTSTRAP_MAX_ITERATION: usize = 30;
+const BOOTSTRAP_RATE_SHIFT: Real = 1.0e-4;
+const BOOTSTRAP_DEFAULT_RATE: Real = 0.03;
+
+/// Quoted instrument for ZeroCurveBootstrapper.
+/// The residual of each instrument is the pv error per unit notional at the quote.
+enum CurveInstrument {
+    /// simple rate from the evaluation date to maturity
+    Deposit {
+        maturity: OffsetDateTime,
+        rate: Real,
+        daycounter: DayCountConvention,
+    },
+    /// simple forward rate from start_date to end_date
+    Fra {
+        start_date: OffsetDateTime,
+        end_date: OffsetDateTime,
+        rate: Real,
+        daycounter: DayCountConvention,
+    },
+    /// KTB futures price repriced by KtbfPricer on the curve
+    Ktbf {
+        ktbf: Instrument,
+        price: Real,
+        borrowing_curve: Rc<RefCell<ZeroCurve>>,
+    },
+    /// swap whose fixed rate is the par quote, repriced by PlainSwapPricer to zero npv
+    ParSwap { swap: Instrument },
+}
+
+impl CurveInstrument {
+    fn get_name(&self) -> String {
+        match self {
+            CurveInstrument::Deposit { maturity, .. } => format!("Deposit ({})", maturity.date()),
+            CurveInstrument::Fra {
+                start_date,
+                end_date,
+                ..
+            } => format!("FRA ({} ~ {})", start_date.date(), end_date.date()),
+            CurveInstrument::Ktbf { ktbf, .. } => format!("KTBF {}", ktbf.get_code()),
+            CurveInstrument::ParSwap { swap } => format!("Swap {}", swap.get_code()),
+        }
+    }
+
+    /// the last date on which the instrument depends on the curve
+    fn pillar_date(&self) -> Result<OffsetDateTime> {
+        match self {
+            CurveInstrument::Deposit { maturity, .. } => Ok(*maturity),
+            CurveInstrument::Fra { end_date, .. } => Ok(*end_date),
+            CurveInstrument::Ktbf { ktbf, .. } => ktbf
+                .get_underlying_bonds()?
+                .iter()
+                .filter_map(|bond| bond.get_maturity().copied())
+                .max()
+                .ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) KTBF {} has no underlying bond with maturity",
+                        file!(),
+                        line!(),
+                        ktbf.get_code()
+                    )
+                }),
+            CurveInstrument::ParSwap { swap } => swap.get_maturity().copied().ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) swap {} has no maturity",
+                    file!(),
+                    line!(),
+                    swap.get_code()
+                )
+            }),
+        }
+    }
+
+    fn initial_rate(&self) -> Option<Real> {
+        match self {
+            CurveInstrument::Deposit { rate, .. } => Some(*rate),
+            CurveInstrument::Fra { rate, .. } => Some(*rate),
+            CurveInstrument::Ktbf { .. } => None,
+            CurveInstrument::ParSwap { swap } => match swap {
+                Instrument::PlainSwap(plain_swap) => plain_swap.get_fixed_rate(),
+                _ => None,
+            },
+        }
+    }
+
+    fn par_residual(
+        &self,
+        evaluation_date: &Rc<RefCell<EvaluationDate>>,
+        curve: &Rc<RefCell<ZeroCurve>>,
+        discount_curve: &Rc<RefCell<ZeroCurve>>,
+    ) -> Result<Real> {
+        let eval_date = evaluation_date.borrow().get_date_clone();
+        let time_calculator = NullCalendar::default();
+        match self {
+            CurveInstrument::Deposit {
+                maturity,
+                rate,
+                daycounter,
+            } => {
+                let frac = time_calculator.year_fraction(&eval_date, maturity, daycounter)?;
+                let df = curve.borrow().get_discount_factor_at_date(maturity)?;
+                Ok(df * (1.0 + rate * frac) - 1.0)
+            }
+            CurveInstrument::Fra {
+                start_date,
+                end_date,
+                rate,
+                daycounter,
+            } => {
+                let frac = time_calculator.year_fraction(start_date, end_date, daycounter)?;
+                let df_start = curve.borrow().get_discount_factor_at_date(start_date)?;
+                let df_end = curve.borrow().get_discount_factor_at_date(end_date)?;
+                Ok(df_end / df_start * (1.0 + rate * frac) - 1.0)
+            }
+            CurveInstrument::Ktbf {
+                ktbf,
+                price,
+                borrowing_curve,
+            } => {
+                let pricer = KtbfPricer::new(
+                    evaluation_date.clone(),
+                    curve.clone(),
+                    borrowing_curve.clone(),
+                );
+                Ok((pricer.npv(ktbf)? - price) / price)
+            }
+            CurveInstrument::ParSwap { swap } => {
+                let pricer = PlainSwapPricer::new(
+                    evaluation_date.clone(),
+                    discount_curve.clone(),
+                    discount_curve.clone(),
+                    Some(curve.clone()),
+                    None,
+                    None,
+                )?;
+                pricer.npv(swap)
+            }
+        }
+    }
+}
+
+/// Bootstrap a ZeroCurve from quoted deposits, FRAs, KTB futures and par swaps.
+///
+/// Each instrument gives a pillar at the last date on which it depends on the curve,
+/// and the zero rate of the pillar is solved by the secant method so that the instrument is repriced at par.
+/// Since ZeroCurve interpolates the rates on its own tenor grid,
+/// a pillar can move the discount factors slightly before the previous pillar.
+/// Therefore the pillars are swept repeatedly until all the instruments are repriced within the tolerance.
+///
+/// Swaps are discounted by discount_curve if it is given (e.g., CD91 IRS on the KOFR OIS discounting),
+/// otherwise by the curve itself.
+pub struct ZeroCurveBootstrapper {
+    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    instruments: Vec<CurveInstrument>,
+    discount_curve: Option<Rc<RefCell<ZeroCurve>>>,
+    tolerance: Real,
+    currency: Currency,
+    name: String,
+    code: String,
+}
+
+impl ZeroCurveBootstrapper {
+    pub fn new(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        currency: Currency,
+        name: String,
+        code: String,
+    ) -> ZeroCurveBootstrapper {
+        ZeroCurveBootstrapper {
+            evaluation_date,
+            instruments: Vec::new(),
+            discount_curve: None,
+            tolerance: 1.0e-5,
+            currency,
+            name,
+            code,
+        }
+    }
+
+    pub fn with_discount_curve(
+        mut self,
+        discount_curve: Rc<RefCell<ZeroCurve>>,
+    ) -> ZeroCurveBootstrapper {
+        self.discount_curve = Some(discount_curve);
+        self
+    }
+
+    /// tolerance of the pv error per unit notional
+    pub fn with_tolerance(mut self, tolerance: Real) -> ZeroCurveBootstrapper {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn with_deposit(
+        mut self,
+        maturity: OffsetDateTime,
+        rate: Real,
+        daycounter: DayCountConvention,
+    ) -> ZeroCurveBootstrapper {
+        self.instruments.push(CurveInstrument::Deposit {
+            maturity,
+            rate,
+            daycounter,
+        });
+        self
+    }
+
+    pub fn with_fra(
+        mut self,
+        start_date: OffsetDateTime,
+        end_date: OffsetDateTime,
+        rate: Real,
+        daycounter: DayCountConvention,
+    ) -> ZeroCurveBootstrapper {
+        self.instruments.push(CurveInstrument::Fra {
+            start_date,
+            end_date,
+            rate,
+            daycounter,
+        });
+        self
+    }
+
+    /// price is in the unit of KtbfPricer::npv, i.e., the virtual bond price discounted by borrowing_curve
+    pub fn with_ktbf(
+        mut self,
+        ktbf: KTBF,
+        price: Real,
+        borrowing_curve: Rc<RefCell<ZeroCurve>>,
+    ) -> ZeroCurveBootstrapper {
+        self.instruments.push(CurveInstrument::Ktbf {
+            ktbf: Instrument::KTBF(ktbf),
+            price,
+            borrowing_curve,
+        });
+        self
+    }
+
+    /// the fixed rate of the swap is the par quote
+    pub fn with_par_swap(mut self, swap: PlainSwap) -> Result<ZeroCurveBootstrapper> {
+        if swap.get_fixed_rate().is_none() || swap.get_rate_index()?.is_none() {
+            return Err(anyhow!(
+                "({}:{}) par swap {} ({}) must have both a fixed rate and a rate index",
+                file!(),
+                line!(),
+                swap.get_name(),
+                swap.get_code()
+            ));
+        }
+        self.instruments.push(CurveInstrument::ParSwap {
+            swap: Instrument::PlainSwap(swap),
+        });
+        Ok(self)
+    }
+
+    fn make_curve(&self, times: &Array1<Time>, rates: &Array1<Real>) -> Result<ZeroCurve> {
+        let data = VectorData::new(
+            rates.clone(),
+            None,
+            Some(times.clone()),
+            None,
+            self.currency,
+            self.name.clone(),
+            self.code.clone(),
+        )?;
+        ZeroCurve::new(
+            self.evaluation_date.clone(),
+            &data,
+            self.name.clone(),
+            self.code.clone(),
+        )
+    }
+
+    /// pv errors per unit notional of the instruments (in the order of input) on the curve
+    pub fn get_par_residuals(&self, curve: Rc<RefCell<ZeroCurve>>) -> Result<Vec<Real>> {
+        let discount_curve = self.discount_curve.clone().unwrap_or(curve.clone());
+        self.instruments
+            .iter()
+            .map(|inst| inst.par_residual(&self.evaluation_date, &curve, &discount_curve))
+            .collect()
+    }
+
+    pub fn bootstrap(&self) -> Result<ZeroCurve> {
+        if self.instruments.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) no instrument is given to bootstrap {} ({})",
+                file!(),
+                line!(),
+                self.name,
+                self.code
+            ));
+        }
+
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let time_calculator = NullCalendar::default();
+        let mut order: Vec<(Time, usize)> = Vec::new();
+        for (i, inst) in self.instruments.iter().enumerate() {
+            let pillar = inst.pillar_date()?;
+            let t = time_calculator.get_time_difference(&eval_date, &pillar);
+            if t <= 0.0 {
+                return Err(anyhow!(
+                    "({}:{}) {} in {} ({}) has a pillar {} not after the evaluation date {}",
+                    file!(),
+                    line!(),
+                    inst.get_name(),
+                    self.name,
+                    self.code,
+                    pillar,
+                    eval_date
+                ));
+            }
+            order.push((t, i));
+        }
+        order.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        if let Some(w) = order.windows(2).find(|w| w[1].0 - w[0].0 < 1.0e-6) {
+            return Err(anyhow!(
+                "({}:{}) {} and {} in {} ({}) have the same pillar",
+                file!(),
+                line!(),
+                self.instruments[w[0].1].get_name(),
+                self.instruments[w[1].1].get_name(),
+                self.name,
+                self.code
+            ));
+        }
+
+        let times: Array1<Time> = order.iter().map(|(t, _)| *t).collect();
+        let mut rates: Array1<Real> = Array1::zeros(times.len());
+        let mut previous_rate = BOOTSTRAP_DEFAULT_RATE;
+        for (k, (_, i)) in order.iter().enumerate() {
+            rates[k] = self.instruments[*i].initial_rate().unwrap_or(previous_rate);
+            previous_rate = rates[k];
+        }
+
+        let curve = Rc::new(RefCell::new(self.make_curve(&times, &rates)?));
+        let discount_curve = self.discount_curve.clone().unwrap_or(curve.clone());
+        let residual = |rates: &Array1<Real>, i: usize| -> Result<Real> {
+            *curve.borrow_mut() = self.make_curve(&times, rates)?;
+            self.instruments[i].par_residual(&self.evaluation_date, &curve, &discount_curve)
+        };
+
+        for _ in 0..BOOTSTRAP_MAX_SWEEP {
+            for (k, (_, i)) in order.iter().enumerate() {
+                let mut r0 = rates[k];
+                let mut f0 = residual(&rates, *i)?;
+                if f0.abs() < 0.1 * self.tolerance {
+                    continue;
+                }
+                let mut r1 = r0 + BOOTSTRAP_RATE_SHIFT;
+                for _ in 0..BOOTSTRAP_MAX_ITERATION {
+                    rates[k] = r1;
+                    let f1 = residual(&rates, *i)?;
+                    if f1.abs() < 0.1 * self.tolerance || f1 == f0 {
+                        break;
+                    }
+                    let r2 = r1 - f1 * (r1 - r0) / (f1 - f0);
+                    (r0, f0, r1) = (r1, f1, r2);
+                }
+                if !rates[k].is_finite() {
+                    return Err(anyhow!(
+                        "({}:{}) the zero rate of {} in {} ({}) diverged",
+                        file!(),
+                        line!(),
+                        self.instruments[*i].get_name(),
+                        self.name,
+                        self.code
+                    ));
+                }
+            }
+
+            *curve.borrow_mut() = self.make_curve(&times, &rates)?;
+            let residuals = self.get_par_residuals(curve.clone())?;
+            if residuals.iter().all(|r| r.abs() < self.tolerance) {
+                return self.make_curve(&times, &rates);
+            }
+        }
+
+        let residuals = self
+            .get_par_residuals(curve.clone())
+            .context("failed to get the residuals of the bootstrapping")?;
+        Err(anyhow!(
+            "({}:{}) {} ({}) did not reprice the instruments within {} after {} sweeps\n\
+            instruments: {:?}\nresiduals: {:?}",
+            file!(),
+            line!(),
+            self.name,
+            self.code,
+            self.tolerance,
+            BOOTSTRAP_MAX_SWEEP,
+            self.instruments
+                .iter()
+                .map(|inst| inst.get_name())
+                .collect::<Vec<String>>(),
+            residuals
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::{Compounding, CreditRating, IssuerType, RankType};
+    use crate::instruments::{bond::Bond, ktbf::KtbfVirtualBond, schedule::Schedule};
+    use crate::parameters::rate_index::RateIndex;
+    use crate::time::{
+        calendar::Calendar,
+        calendars::southkorea::{SouthKorea, SouthKoreaType},
+        conventions::{BusinessDayConvention, PaymentFrequency},
+        jointcalendar::JointCalendar,
+    };
+    use crate::utils::test_data::flat_zero_curve;
+    use time::{macros::datetime, Duration};
+
+    #[test]
+    fn test_zero_curve_bootstrapper() -> Result<()> {
+        let eval_date = datetime!(2024-01-02 16:30:00 +09:00);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_date)));
+        let effective_date = eval_date + Duration::days(1);
+        let sk = Calendar::SouthKorea(SouthKorea::new(SouthKoreaType::Settlement));
+        let calendar = JointCalendar::new(vec![sk])?;
+
+        let mut bootstrapper = ZeroCurveBootstrapper::new(
+            evaluation_date.clone(),
+            Currency::KRW,
+            "KRWIRS".to_string(),
+            "KRWIRS".to_string(),
+        )
+        .with_tolerance(1.0e-6)
+        .with_deposit(
+            eval_date + Duration::days(91),
+            0.0365,
+            DayCountConvention::Actual365Fixed,
+        )
+        .with_fra(
+            eval_date + Duration::days(91),
+            eval_date + Duration::days(182),
+            0.0355,
+            DayCountConvention::Actual365Fixed,
+        );
+
+        for (years, fixed_rate) in [(1, 0.0345), (2, 0.0330), (3, 0.0325), (5, 0.0320)] {
+            let rate_index = RateIndex::new(
+                String::from("91D"),
+                Currency::KRW,
+                String::from("CD 91D"),
+                String::from("CD 91D"),
+            )?;
+            let swap = PlainSwap::new_from_conventions(
+                Currency::KRW,
+                Currency::KRW,
+                //
+                None,
+                None,
+                None,
+                None,
+                //
+                1.0,
+                eval_date,
+                effective_date,
+                effective_date.replace_year(2024 + years)?,
+                //
+                Some(fixed_rate),
+                Some(rate_index),
+                None,
+                //
+                true,
+                DayCountConvention::Actual365Fixed,
+                DayCountConvention::Actual365Fixed,
+                BusinessDayConvention::ModifiedFollowing,
+                BusinessDayConvention::ModifiedFollowing,
+                PaymentFrequency::Quarterly,
+                PaymentFrequency::Quarterly,
+                //
+                1,
+                0,
+                //
+                calendar.clone(),
+                format!("IRS{}Y", years),
+                format!("IRS{}Y", years),
+            )?;
+            bootstrapper = bootstrapper.with_par_swap(swap)?;
+        }
+
+        let curve = Rc::new(RefCell::new(bootstrapper.bootstrap()?));
+        let residuals = bootstrapper.get_par_residuals(curve.clone())?;
+        for residual in residuals.iter() {
+            assert!(residual.abs() < 1.0e-6, "residuals: {:?}", residuals);
+        }
+
+        // the deposit is repriced by the discount factor
+        let df = curve
+            .borrow()
+            .get_discount_factor_at_date(&(eval_date + Duration::days(91)))?;
+        assert!((df * (1.0 + 0.0365 * 91.0 / 365.0) - 1.0).abs() < 1.0e-6);
+
+        // a swap without the fixed rate and the rate index, e.g., an fx forward, can not be a par instrument
+        let fx_forward = PlainSwap::new(
+            Schedule::default(),
+            Schedule::default(),
+            None,
+            None,
+            None,
+            calendar,
+            1.0,
+            eval_date,
+            effective_date,
+            effective_date.replace_year(2025)?,
+            Currency::KRW,
+            Currency::USD,
+            None,
+            None,
+            Some(1_300.0),
+            Some(1.0),
+            DayCountConvention::Dummy,
+            DayCountConvention::Dummy,
+            BusinessDayConvention::Dummy,
+            BusinessDayConvention::Dummy,
+            PaymentFrequency::None,
+            PaymentFrequency::None,
+            0,
+            0,
+            "USDKRW Forward".to_string(),
+            "USDKRW Forward".to_string(),
+        )?;
+        assert!(bootstrapper.with_par_swap(fx_forward).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_zero_curve_bootstrapper_ktbf() -> Result<()> {
+        let eval_date = datetime!(2024-01-02 16:30:00 +09:00);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_date)));
+        let sk = Calendar::SouthKorea(SouthKorea::new(SouthKoreaType::Settlement));
+        let calendar = JointCalendar::new(vec![sk])?;
+        let ktbf_maturity = eval_date + Duration::days(90);
+        let issue_date = datetime!(2023-12-10 00:00:00 +09:00);
+        let mut bonds = vec![];
+        for (maturity, code) in [
+            (datetime!(2026-12-10 00:00:00 +09:00), "KTB 3.25 2026"),
+            (datetime!(2027-06-10 00:00:00 +09:00), "KTB 3.25 2027"),
+        ] {
+            bonds.push(Bond::new_from_conventions(
+                IssuerType::Government,
+                CreditRating::None,
+                "Korea Government".to_string(),
+                RankType::Senior,
+                Currency::KRW,
+                10_000.0,
+                false,
+                issue_date,
+                issue_date,
+                Some(ktbf_maturity),
+                maturity,
+                Some(0.0325),
+                None,
+                None,
+                None,
+                calendar.clone(),
+                true,
+                DayCountConvention::StreetConvention,
+                BusinessDayConvention::Unadjusted,
+                PaymentFrequency::SemiAnnually,
+                0,
+                0,
+                code.to_string(),
+                code.to_string(),
+            )?);
+        }
+        let ktbf = KTBF::new(
+            Currency::KRW,
+            1_000_000.0,
+            datetime!(2023-12-20 16:30:00 +09:00),
+            ktbf_maturity,
+            ktbf_maturity,
+            KtbfVirtualBond::new(3, 0.05, PaymentFrequency::SemiAnnually, 100.0),
+            bonds,
+            "KTBF3Y".to_string(),
+            "KTBF3Y".to_string(),
+            "KTBF3Y".to_string(),
+        )?;
+
+        // the KTBF price on the flat 3.3% curve is repriced with the 3.3% deposit
+        let borrowing_curve = flat_zero_curve(&evaluation_date, 0.035, Currency::KRW, "KTBF3Y")?;
+        let flat_curve = flat_zero_curve(&evaluation_date, 0.033, Currency::KRW, "KRWGOV")?;
+        let price = KtbfPricer::new(
+            evaluation_date.clone(),
+            flat_curve.clone(),
+            borrowing_curve.clone(),
+        )
+        .npv(&Instrument::KTBF(ktbf.clone()))?;
+        // the KTBF price is quoted around 100, so its residual is on the resolution of Real at 100
+        let bootstrapper = ZeroCurveBootstrapper::new(
+            evaluation_date.clone(),
+            Currency::KRW,
+            "KRWGOV".to_string(),
+            "KRWGOV".to_string(),
+        )
+        .with_tolerance(1.0e-4)
+        .with_deposit(
+            eval_date + Duration::days(91),
+            flat_curve.borrow().get_forward_rate_from_evaluation_date(
+                &(eval_date + Duration::days(91)),
+                Compounding::Simple,
+            )?,
+            DayCountConvention::Actual365Fixed,
+        )
+        .with_ktbf(ktbf, price, borrowing_curve);
+
+        let curve = Rc::new(RefCell::new(bootstrapper.bootstrap()?));
+        let residuals = bootstrapper.get_par_residuals(curve.clone())?;
+        assert!(
+            residuals.iter().all(|r| r.abs() < 1.0e-4),
+            "residuals: {:?}",
+            residuals
+        );
+        // the pillar of the KTBF is on the maturity of the longest underlying bond
+        let pillar = datetime!(2027-06-10 00:00:00 +09:00);
+        let expected = flat_curve.borrow().get_discount_factor_at_date(&pillar)?;
+        let df = curve.borrow().get_discount_factor_at_date(&pillar)?;
+        assert!(
+            (df - expected).abs() < 1.0e-4,
+            "discount factor at {}: {} vs {}",
+            pillar,
+            df,
+            expected
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use crate::definitions::Real;
+use std::collections::HashMap;
+
+/// Greeks given in closed form by a pricer. They are not multiplied by unit_notional.
+/// delta = dV/dS, gamma = d^2V/dS^2, vega = dV/dvol (parallel), theta = dV/dt (per year),
+/// rho: curve code -> dV/dr (parallel zero rate shift),
+/// vanna = d^2V/dSdvol, volga = d^2V/dvol^2
+/// The greeks which are None are left to the bumped greeks in Engine.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnalyticGreeks {
+    delta: Real,
+    gamma: Real,
+    vega: Option<Real>,
+    theta: Real,
+    rho: Option<HashMap<String, Real>>,
+    vanna: Option<Real>,
+    volga: Option<Real>,
+}
+
+impl AnalyticGreeks {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        delta: Real,
+        gamma: Real,
+        vega: Real,
+        theta: Real,
+        rho: HashMap<String, Real>,
+        vanna: Real,
+        volga: Real,
+    ) -> AnalyticGreeks {
+        AnalyticGreeks {
+            delta,
+            gamma,
+            vega: Some(vega),
+            theta,
+            rho: Some(rho),
+            vanna: Some(vanna),
+            volga: Some(volga),
+        }
+    }
+
+    /// delta, gamma and theta only, e.g., read from a finite difference grid
+    pub fn new_delta_gamma_theta(delta: Real, gamma: Real, theta: Real) -> AnalyticGreeks {
+        AnalyticGreeks {
+            delta,
+            gamma,
+            theta,
+            ..Default::default()
+        }
+    }
+
+    pub fn get_delta(&self) -> Real {
+        self.delta
+    }
+
+    pub fn get_gamma(&self) -> Real {
+        self.gamma
+    }
+
+    pub fn get_vega(&self) -> Option<Real> {
+        self.vega
+    }
+
+    pub fn get_theta(&self) -> Real {
+        self.theta
+    }
+
+    pub fn get_rho(&self) -> Option<&HashMap<String, Real>> {
+        self.rho.as_ref()
+    }
+
+    /// rho on the curve, zero if the curve is not used
+    pub fn get_single_rho(&self, curve_code: &str) -> Option<Real> {
+        self.rho
+            .as_ref()
+            .map(|rho| rho.get(curve_code).copied().unwrap_or(0.0))
+    }
+
+    pub fn get_vanna(&self) -> Option<Real> {
+        self.vanna
+    }
+
+    pub fn get_volga(&self) -> Option<Real> {
+        self.volga
+    }
+}
@@ -0,0 +1,452 @@
+use crate::definitions::{Real, Time};
+use crate::enums::{AveragingType, OptionType};
+use crate::evaluation_date::EvaluationDate;
+use crate::instrument::{Instrument, InstrumentTrait};
+use crate::instruments::asian_option::AsianOption;
+use crate::parameters::market_price::MarketPrice;
+use crate::parameters::past_price::DailyClosePrice;
+use crate::parameters::{quanto::Quanto, volatility::Volatility, zero_curve::ZeroCurve};
+use crate::pricing_engines::barrier_option_analytic_pricer::vanilla_black;
+use crate::pricing_engines::pricer::PricerTrait;
+use crate::pricing_engines::{futures_pricer::FuturesPricer, npv_result::NpvResult};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use anyhow::{anyhow, Context, Result};
+use std::{cell::RefCell, rc::Rc};
+use time::OffsetDateTime;
+
+/// The fixings of AsianOption split at the evaluation date:
+/// the closes on the fixing dates before the evaluation date, and the remaining fixing dates.
+pub(crate) fn split_fixings(
+    option: &AsianOption,
+    eval_date: &OffsetDateTime,
+    past_close_data: Option<&DailyClosePrice>,
+) -> Result<(Vec<Real>, Vec<OffsetDateTime>)> {
+    let mut past_fixings = Vec::new();
+    let mut future_dates = Vec::new();
+    for date in option.get_fixing_dates() {
+        if date.date() >= eval_date.date() {
+            future_dates.push(*date);
+            continue;
+        }
+        let close = past_close_data
+            .and_then(|data| data.get(&date.date()))
+            .ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) {} ({}) has no past close of {} on the fixing date {}",
+                    file!(),
+                    line!(),
+                    option.get_name(),
+                    option.get_code(),
+                    option.get_underlying_codes()[0],
+                    date.date()
+                )
+            })?;
+        past_fixings.push(*close);
+    }
+    Ok((past_fixings, future_dates))
+}
+
+/// forwards and total variances on the remaining fixing dates (sorted)
+struct FixingMoments {
+    past_fixings: Vec<Real>,
+    forwards: Vec<f64>,
+    total_variances: Vec<f64>,
+    discount: Real,
+}
+
+impl FixingMoments {
+    fn fixing_number(&self) -> f64 {
+        (self.past_fixings.len() + self.forwards.len()) as f64
+    }
+
+    fn covariance(&self, i: usize, j: usize) -> f64 {
+        self.total_variances[i.min(j)]
+    }
+}
+
+fn payoff(option_type: OptionType, strike: Real, average: Real) -> Real {
+    match option_type {
+        OptionType::Call => (average - strike).max(0.0),
+        OptionType::Put => (strike - average).max(0.0),
+    }
+}
+
+/// Black price on the lognormal variable of the given forward and total variance
+fn lognormal_npv(
+    option_type: OptionType,
+    strike: Real,
+    forward: f64,
+    total_variance: f64,
+    discount: Real,
+) -> Real {
+    if total_variance <= 1.0e-12 {
+        return discount * payoff(option_type, strike, forward as Real);
+    }
+    vanilla_black(
+        option_type,
+        strike,
+        1.0,
+        forward as Real,
+        discount,
+        total_variance.sqrt() as Real,
+    )
+}
+
+/// Turnbull and Wakeman (1991) approximation of the discrete arithmetic average
+/// by the lognormal variable of the same first two moments.
+/// The observed fixings reduce the strike by their contribution to the average.
+fn turnbull_wakeman(option_type: OptionType, strike: Real, moments: &FixingMoments) -> Real {
+    let n = moments.fixing_number();
+    let observed = moments.past_fixings.iter().map(|x| *x as f64).sum::<f64>() / n;
+    let effective_strike = strike as f64 - observed;
+    let m = moments.forwards.len();
+    let first = moments.forwards.iter().sum::<f64>() / n;
+    if m == 0 || effective_strike <= 0.0 {
+        // the payoff is linear in the average
+        let value = moments.discount as f64 * (first - effective_strike);
+        return match option_type {
+            OptionType::Call => value.max(0.0) as Real,
+            OptionType::Put => (-value).max(0.0) as Real,
+        };
+    }
+    let mut second = 0.0;
+    for i in 0..m {
+        for j in 0..m {
+            second += moments.forwards[i] * moments.forwards[j] * moments.covariance(i, j).exp();
+        }
+    }
+    second /= n * n;
+    let total_variance = (second / (first * first)).ln().max(0.0);
+    lognormal_npv(
+        option_type,
+        effective_strike as Real,
+        first,
+        total_variance,
+        moments.discount,
+    )
+}
+
+/// The discrete geometric average is lognormal, so the Black formula is exact.
+fn geometric_closed_form(option_type: OptionType, strike: Real, moments: &FixingMoments) -> Real {
+    let n = moments.fixing_number();
+    let m = moments.forwards.len();
+    let mut mean = moments
+        .past_fixings
+        .iter()
+        .map(|x| (*x as f64).ln())
+        .sum::<f64>();
+    for i in 0..m {
+        mean += moments.forwards[i].ln() - 0.5 * moments.total_variances[i];
+    }
+    mean /= n;
+    let mut variance = 0.0;
+    for i in 0..m {
+        for j in 0..m {
+            variance += moments.covariance(i, j);
+        }
+    }
+    variance /= n * n;
+    lognormal_npv(
+        option_type,
+        strike,
+        (mean + 0.5 * variance).exp(),
+        variance,
+        moments.discount,
+    )
+}
+
+/// Analytic pricer for AsianOption.
+/// The arithmetic average is priced by the Turnbull-Wakeman approximation,
+/// and the geometric average in closed form. The volatility of each fixing is taken at
+/// the strike, and the forwards are quanto-adjusted in the same way as OptionAnalyticPricer.
+/// The fixings before the evaluation date are read from past_close_data.
+pub struct AsianOptionAnalyticPricer {
+    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    market_price: Rc<RefCell<MarketPrice>>,
+    futures_helper: FuturesPricer,
+    discount_curve: Rc<RefCell<ZeroCurve>>,
+    volatility: Rc<RefCell<Volatility>>,
+    quanto: Option<Rc<RefCell<Quanto>>>,
+    past_close_data: Option<Rc<DailyClosePrice>>,
+    time_calculator: NullCalendar,
+}
+
+impl AsianOptionAnalyticPricer {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        market_price: Rc<RefCell<MarketPrice>>,
+        collateral_curve: Rc<RefCell<ZeroCurve>>,
+        borrowing_curve: Rc<RefCell<ZeroCurve>>,
+        discount_curve: Rc<RefCell<ZeroCurve>>,
+        volatility: Rc<RefCell<Volatility>>,
+        quanto: Option<Rc<RefCell<Quanto>>>,
+        past_close_data: Option<Rc<DailyClosePrice>>,
+    ) -> AsianOptionAnalyticPricer {
+        let futures_helper =
+            FuturesPricer::new(market_price.clone(), collateral_curve, borrowing_curve);
+
+        AsianOptionAnalyticPricer {
+            evaluation_date,
+            market_price,
+            futures_helper,
+            discount_curve,
+            volatility,
+            quanto,
+            past_close_data,
+            time_calculator: NullCalendar::new(),
+        }
+    }
+
+    fn get_asian_option<'a>(&self, instrument: &'a Instrument) -> Result<&'a AsianOption> {
+        let option = match instrument {
+            Instrument::AsianOption(option) => option,
+            _ => {
+                return Err(anyhow!(
+                    "({}:{}) AsianOptionAnalyticPricer: not supported instrument type: {}",
+                    file!(),
+                    line!(),
+                    instrument.get_type_name()
+                ))
+            }
+        };
+        if instrument.get_currency() != instrument.get_underlying_currency()?
+            && self.quanto.is_none()
+        {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has different currency from underlying market_price ({}) but no quanto is provided",
+                file!(), line!(),
+                instrument.get_name(), instrument.get_code(), self.market_price.borrow().get_name(),
+            ));
+        }
+        Ok(option)
+    }
+
+    fn fixing_moments(&self, option: &AsianOption) -> Result<FixingMoments> {
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let (past_fixings, future_dates) =
+            split_fixings(option, &eval_date, self.past_close_data.as_deref())?;
+        let strike = option.get_strike()?;
+        let spot = self.market_price.borrow().get_value();
+        let volatility = self.volatility.borrow();
+
+        let mut forwards = Vec::with_capacity(future_dates.len());
+        let mut total_variances = Vec::with_capacity(future_dates.len());
+        for date in future_dates.iter() {
+            let t: Time = self
+                .time_calculator
+                .get_time_difference(&eval_date, date)
+                .max(0.0);
+            if t <= 0.0 {
+                forwards.push(spot as f64);
+                total_variances.push(0.0);
+                continue;
+            }
+            let fwd = self.futures_helper.fair_forward(date)?;
+            let forward_moneyness = strike / fwd;
+            let vol = volatility.get_value(t, forward_moneyness);
+            let quanto_adjustment = match &self.quanto {
+                Some(quanto) => vol * t * quanto.borrow().quanto_adjust(t, forward_moneyness),
+                None => 0.0,
+            };
+            forwards.push((fwd * (-quanto_adjustment).exp()) as f64);
+            total_variances.push((vol * vol * t) as f64);
+        }
+
+        let maturity = option
+            .get_maturity()
+            .context("(AsianOptionAnalyticPricer:fixing_moments) Failed to get maturity")?;
+        let t = self
+            .time_calculator
+            .get_time_difference(&eval_date, maturity)
+            .max(0.0);
+        let discount = self.discount_curve.borrow().get_discount_factor(t)?;
+        Ok(FixingMoments {
+            past_fixings,
+            forwards,
+            total_variances,
+            discount,
+        })
+    }
+
+    /// npv of the geometric average option with the same fixings,
+    /// which is the control variate of the arithmetic average in PathDependentMonteCarloPricer
+    pub fn geometric_average_npv(&self, instrument: &Instrument) -> Result<Real> {
+        let option = self.get_asian_option(instrument)?;
+        let moments = self.fixing_moments(option)?;
+        Ok(geometric_closed_form(
+            option.get_option_type()?,
+            option.get_strike()?,
+            &moments,
+        ))
+    }
+}
+
+impl PricerTrait for AsianOptionAnalyticPricer {
+    fn npv(&self, instrument: &Instrument) -> Result<Real> {
+        let option = self.get_asian_option(instrument)?;
+        let moments = self.fixing_moments(option)?;
+        let option_type = option.get_option_type()?;
+        let strike = option.get_strike()?;
+        Ok(match option.get_averaging_type() {
+            AveragingType::Arithmetic => turnbull_wakeman(option_type, strike, &moments),
+            AveragingType::Geometric => geometric_closed_form(option_type, strike, &moments),
+        })
+    }
+
+    fn npv_result(&self, instrument: &Instrument) -> Result<NpvResult> {
+        let npv = self.npv(instrument)?;
+        Ok(NpvResult::new_from_npv(npv))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::data;
+    use crate::enums::OptionDailySettlementType;
+    use crate::parameters::volatilities::constant_volatility::ConstantVolatility;
+    use crate::pricing_engines::montecarlo::path_dependent_montecarlo_pricer::PathDependentMonteCarloPricer;
+    use crate::time::calendar::Calendar;
+    use crate::time::calendars::southkorea::{SouthKorea, SouthKoreaType};
+    use crate::vectordatasample;
+    use std::collections::HashMap;
+    use time::macros::datetime;
+    use time::{Duration, UtcOffset};
+
+    #[test]
+    fn test_asian_option_analytic_pricer() -> Result<()> {
+        let issue_date = datetime!(2024-01-02 16:30:00 +09:00);
+        let fixing_dates: Vec<OffsetDateTime> = (1..=12)
+            .map(|k| issue_date + Duration::days(30 * k))
+            .collect();
+        let maturity = fixing_dates[11];
+        let option = |averaging_type: AveragingType, option_type: OptionType, strike: Real| {
+            AsianOption::new(
+                strike,
+                averaging_type,
+                fixing_dates.clone(),
+                1.0,
+                issue_date,
+                maturity,
+                maturity,
+                maturity,
+                vec!["KOSPI2".to_string()],
+                Currency::KRW,
+                Currency::KRW,
+                option_type,
+                OptionDailySettlementType::NotSettled,
+                "Asian Test".to_string(),
+                "Asian Test".to_string(),
+            )
+            .map(Instrument::AsianOption)
+        };
+        // the fixings of the first half are 120
+        let past_close_data = Rc::new(DailyClosePrice::new(
+            fixing_dates[..6]
+                .iter()
+                .map(|date| (date.date(), 120.0))
+                .collect::<HashMap<_, _>>(),
+            time::Time::from_hms(15, 40, 0)?,
+            UtcOffset::from_hms(9, 0, 0)?,
+            Calendar::SouthKorea(SouthKorea::new(SouthKoreaType::Krx)),
+            "KOSPI2".to_string(),
+            "KOSPI2".to_string(),
+        ));
+
+        let pricers = |eval_date: OffsetDateTime, past: Option<Rc<DailyClosePrice>>| {
+            let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_date)));
+            let market_price = Rc::new(RefCell::new(MarketPrice::new(
+                100.0,
+                eval_date,
+                None,
+                Currency::KRW,
+                "KOSPI2".to_string(),
+                "KOSPI2".to_string(),
+            )));
+            let curve_data = vectordatasample!(0.03, Currency::KRW, "Asian Test Curve")?;
+            let curve = Rc::new(RefCell::new(ZeroCurve::new(
+                evaluation_date.clone(),
+                &curve_data,
+                "Asian Test Curve".to_string(),
+                "Asian Test Curve".to_string(),
+            )?));
+            let volatility = Rc::new(RefCell::new(Volatility::ConstantVolatility(
+                ConstantVolatility::new(0.2, "KOSPI2".to_string(), "KOSPI2".to_string()),
+            )));
+            let analytic = AsianOptionAnalyticPricer::new(
+                evaluation_date.clone(),
+                market_price.clone(),
+                curve.clone(),
+                curve.clone(),
+                curve.clone(),
+                volatility.clone(),
+                None,
+                past.clone(),
+            );
+            let montecarlo = PathDependentMonteCarloPricer::new(
+                evaluation_date,
+                market_price,
+                curve.clone(),
+                curve.clone(),
+                curve,
+                volatility,
+                None,
+                past,
+                20_000,
+                20,
+                1,
+            );
+            Ok::<_, anyhow::Error>((analytic, montecarlo))
+        };
+
+        for (eval_date, past) in [
+            (issue_date, None),
+            (fixing_dates[5] + Duration::days(1), Some(past_close_data)),
+        ] {
+            let (analytic, montecarlo) = pricers(eval_date, past)?;
+            for (option_type, strike) in [(OptionType::Call, 100.0), (OptionType::Put, 105.0)] {
+                let arithmetic = option(AveragingType::Arithmetic, option_type, strike)?;
+                let geometric = option(AveragingType::Geometric, option_type, strike)?;
+
+                let turnbull_wakeman = analytic.npv(&arithmetic)?;
+                let mc = montecarlo.npv_result(&arithmetic)?;
+                assert!(
+                    (turnbull_wakeman - mc.get_npv()).abs() < 0.05,
+                    "{:?} {:?}: Turnbull-Wakeman {}, Monte Carlo {} (standard error {:?})",
+                    eval_date,
+                    option_type,
+                    turnbull_wakeman,
+                    mc.get_npv(),
+                    mc.get_standard_error()
+                );
+
+                let closed_form = analytic.npv(&geometric)?;
+                let mc = montecarlo.npv_result(&geometric)?;
+                let standard_error = mc.get_standard_error().unwrap();
+                assert!(
+                    (closed_form - mc.get_npv()).abs() < 3.0 * standard_error + 1.0e-3,
+                    "{:?} {:?}: geometric closed form {}, Monte Carlo {} (standard error {})",
+                    eval_date,
+                    option_type,
+                    closed_form,
+                    mc.get_npv(),
+                    standard_error
+                );
+                // the geometric average is below the arithmetic average
+                match option_type {
+                    OptionType::Call => assert!(closed_form < turnbull_wakeman),
+                    OptionType::Put => assert!(closed_form > turnbull_wakeman),
+                }
+            }
+        }
+
+        // the running deal needs the past fixings
+        let (analytic, _) = pricers(fixing_dates[5] + Duration::days(1), None)?;
+        let arithmetic = option(AveragingType::Arithmetic, OptionType::Call, 100.0)?;
+        assert!(analytic.npv(&arithmetic).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,508 @@
+use crate::definitions::{Real, Time};
+use crate::enums::{BarrierType, OptionType};
+use crate::evaluation_date::EvaluationDate;
+use crate::instrument::{Instrument, InstrumentTrait};
+use crate::parameters::market_price::MarketPrice;
+use crate::parameters::{quanto::Quanto, volatility::Volatility, zero_curve::ZeroCurve};
+use crate::pricing_engines::pricer::PricerTrait;
+use crate::pricing_engines::{futures_pricer::FuturesPricer, npv_result::NpvResult};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use anyhow::{anyhow, Context, Result};
+use statrs::distribution::{ContinuousCDF, Normal};
+use std::{cell::RefCell, rc::Rc};
+
+/// -zeta(1/2) / sqrt(2 pi) of Broadie, Glasserman and Kou (1997)
+const BROADIE_GLASSERMAN_BETA: Real = 0.5826;
+
+/// Black formula on the forward with the discount factor to the payment
+pub(crate) fn vanilla_black(
+    option_type: OptionType,
+    strike: Real,
+    t: Time,
+    forward: Real,
+    discount: Real,
+    vol: Real,
+) -> Real {
+    let normal = Normal::new(0.0, 1.0).unwrap();
+    let deviation = vol * t.sqrt();
+    let d1 = ((forward / strike).ln() + 0.5 * deviation * deviation) / deviation;
+    let d2 = d1 - deviation;
+    let nd1 = normal.cdf(d1 as f64) as Real;
+    let nd2 = normal.cdf(d2 as f64) as Real;
+    match option_type {
+        OptionType::Call => discount * (forward * nd1 - strike * nd2),
+        OptionType::Put => discount * (strike * (1.0 - nd2) - forward * (1.0 - nd1)),
+    }
+}
+
+/// Reiner-Rubinstein (1991) closed form of a continuously monitored barrier option
+/// in the notation of Haug, where the cost of carry and the short rate are implied
+/// by the forward and the discount factor to maturity.
+#[allow(clippy::too_many_arguments)]
+fn reiner_rubinstein(
+    option_type: OptionType,
+    barrier_type: BarrierType,
+    spot: Real,
+    strike: Real,
+    barrier: Real,
+    rebate: Real,
+    t: Time,
+    forward: Real,
+    discount: Real,
+    vol: Real,
+) -> Real {
+    let (s, x, h, k) = (spot as f64, strike as f64, barrier as f64, rebate as f64);
+    let (t, f, dsc, sigma) = (t as f64, forward as f64, discount as f64, vol as f64);
+    let normal = Normal::new(0.0, 1.0).unwrap();
+    let n = |x: f64| normal.cdf(x);
+
+    let r = -dsc.ln() / t;
+    let b = (f / s).ln() / t;
+    let sigma_sqrt_t = sigma * t.sqrt();
+    let mu = (b - 0.5 * sigma * sigma) / (sigma * sigma);
+    let lambda = (mu * mu + 2.0 * r / (sigma * sigma)).max(0.0).sqrt();
+    let phi = match option_type {
+        OptionType::Call => 1.0,
+        OptionType::Put => -1.0,
+    };
+    let eta = match barrier_type {
+        BarrierType::DownAndIn | BarrierType::DownAndOut => 1.0,
+        BarrierType::UpAndIn | BarrierType::UpAndOut => -1.0,
+    };
+    // S e^{(b - r) T} and X e^{-r T}
+    let s_dsc = f * dsc;
+    let x_dsc = x * dsc;
+    let hs = h / s;
+
+    let x1 = (s / x).ln() / sigma_sqrt_t + (1.0 + mu) * sigma_sqrt_t;
+    let x2 = (s / h).ln() / sigma_sqrt_t + (1.0 + mu) * sigma_sqrt_t;
+    let y1 = (h * h / (s * x)).ln() / sigma_sqrt_t + (1.0 + mu) * sigma_sqrt_t;
+    let y2 = (h / s).ln() / sigma_sqrt_t + (1.0 + mu) * sigma_sqrt_t;
+    let z = (h / s).ln() / sigma_sqrt_t + lambda * sigma_sqrt_t;
+
+    let a = phi * s_dsc * n(phi * x1) - phi * x_dsc * n(phi * x1 - phi * sigma_sqrt_t);
+    let bb = phi * s_dsc * n(phi * x2) - phi * x_dsc * n(phi * x2 - phi * sigma_sqrt_t);
+    let c = phi * s_dsc * hs.powf(2.0 * (mu + 1.0)) * n(eta * y1)
+        - phi * x_dsc * hs.powf(2.0 * mu) * n(eta * y1 - eta * sigma_sqrt_t);
+    let d = phi * s_dsc * hs.powf(2.0 * (mu + 1.0)) * n(eta * y2)
+        - phi * x_dsc * hs.powf(2.0 * mu) * n(eta * y2 - eta * sigma_sqrt_t);
+    // rebate at maturity for knock-in, and at hit for knock-out
+    let e = k
+        * dsc
+        * (n(eta * x2 - eta * sigma_sqrt_t) - hs.powf(2.0 * mu) * n(eta * y2 - eta * sigma_sqrt_t));
+    let ff = k
+        * (hs.powf(mu + lambda) * n(eta * z)
+            + hs.powf(mu - lambda) * n(eta * z - 2.0 * eta * lambda * sigma_sqrt_t));
+
+    let above = x > h;
+    let res = match (barrier_type, option_type) {
+        (BarrierType::DownAndIn, OptionType::Call) if above => c + e,
+        (BarrierType::DownAndIn, OptionType::Call) => a - bb + d + e,
+        (BarrierType::UpAndIn, OptionType::Call) if above => a + e,
+        (BarrierType::UpAndIn, OptionType::Call) => bb - c + d + e,
+        (BarrierType::DownAndIn, OptionType::Put) if above => bb - c + d + e,
+        (BarrierType::DownAndIn, OptionType::Put) => a + e,
+        (BarrierType::UpAndIn, OptionType::Put) if above => a - bb + d + e,
+        (BarrierType::UpAndIn, OptionType::Put) => c + e,
+        (BarrierType::DownAndOut, OptionType::Call) if above => a - c + ff,
+        (BarrierType::DownAndOut, OptionType::Call) => bb - d + ff,
+        (BarrierType::UpAndOut, OptionType::Call) if above => ff,
+        (BarrierType::UpAndOut, OptionType::Call) => a - bb + c - d + ff,
+        (BarrierType::DownAndOut, OptionType::Put) if above => a - bb + c - d + ff,
+        (BarrierType::DownAndOut, OptionType::Put) => ff,
+        (BarrierType::UpAndOut, OptionType::Put) if above => bb - d + ff,
+        (BarrierType::UpAndOut, OptionType::Put) => a - c + ff,
+    };
+    res as Real
+}
+
+/// Reiner-Rubinstein pricer of BarrierOption with the volatility at the strike.
+/// Discrete monitoring is priced by the continuity correction of Broadie, Glasserman and Kou (1997),
+/// which shifts the barrier away from the spot by exp(0.5826 vol sqrt(dt))
+/// where dt is the average interval of the remaining monitoring dates.
+/// If the spot is already beyond the (shifted) barrier, the barrier is regarded as hit:
+/// knock-in options are priced as vanilla options and knock-out options are worth the rebate.
+pub struct BarrierOptionAnalyticPricer {
+    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    market_price: Rc<RefCell<MarketPrice>>,
+    futures_helper: FuturesPricer,
+    discount_curve: Rc<RefCell<ZeroCurve>>,
+    volatility: Rc<RefCell<Volatility>>,
+    quanto: Option<Rc<RefCell<Quanto>>>,
+    time_calculator: NullCalendar,
+}
+
+impl BarrierOptionAnalyticPricer {
+    pub fn new(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        market_price: Rc<RefCell<MarketPrice>>,
+        collateral_curve: Rc<RefCell<ZeroCurve>>,
+        borrowing_curve: Rc<RefCell<ZeroCurve>>,
+        discount_curve: Rc<RefCell<ZeroCurve>>,
+        volatility: Rc<RefCell<Volatility>>,
+        quanto: Option<Rc<RefCell<Quanto>>>,
+    ) -> BarrierOptionAnalyticPricer {
+        let futures_helper =
+            FuturesPricer::new(market_price.clone(), collateral_curve, borrowing_curve);
+
+        BarrierOptionAnalyticPricer {
+            evaluation_date,
+            market_price,
+            futures_helper,
+            discount_curve,
+            volatility,
+            quanto,
+            time_calculator: NullCalendar::new(),
+        }
+    }
+}
+
+impl PricerTrait for BarrierOptionAnalyticPricer {
+    fn npv(&self, instrument: &Instrument) -> Result<Real> {
+        let option = match instrument {
+            Instrument::BarrierOption(option) => option,
+            _ => {
+                return Err(anyhow!(
+                    "({}:{}) BarrierOptionAnalyticPricer::npv: not supported instrument type: {}",
+                    file!(),
+                    line!(),
+                    instrument.get_type_name()
+                ))
+            }
+        };
+        if instrument.get_currency() != instrument.get_underlying_currency()?
+            && self.quanto.is_none()
+        {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has different currency from underlying market_price ({}) but no quanto is provided",
+                file!(), line!(),
+                instrument.get_name(), instrument.get_code(), self.market_price.borrow().get_name(),
+            ));
+        }
+
+        let maturity = instrument
+            .get_maturity()
+            .context("(BarrierOptionAnalyticPricer:npv) Failed to get maturity")?;
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let t = self
+            .time_calculator
+            .get_time_difference(&eval_date, maturity);
+        let spot = self.market_price.borrow().get_value();
+        let strike = instrument.get_strike()?;
+        let option_type = option.get_option_type()?;
+        let barrier_type = option.get_barrier_type();
+        let rebate = option.get_rebate();
+        let is_up = matches!(barrier_type, BarrierType::UpAndIn | BarrierType::UpAndOut);
+        let is_in = matches!(barrier_type, BarrierType::UpAndIn | BarrierType::DownAndIn);
+        let payoff = |s: Real| match option_type {
+            OptionType::Call => (s - strike).max(0.0),
+            OptionType::Put => (strike - s).max(0.0),
+        };
+
+        if t <= 0.0 {
+            let hit = match is_up {
+                true => spot >= option.get_barrier(),
+                false => spot <= option.get_barrier(),
+            };
+            return Ok(match is_in == hit {
+                true => payoff(spot),
+                false => rebate,
+            });
+        }
+
+        let fwd = self.futures_helper.fair_forward(maturity)?;
+        let forward_moneyness = strike / fwd;
+        let vol = self.volatility.borrow().get_value(t, forward_moneyness);
+        let quanto_adjustment = match &self.quanto {
+            Some(quanto) => vol * t * quanto.borrow().quanto_adjust(t, forward_moneyness),
+            None => 0.0,
+        };
+        let fwd = fwd * (-quanto_adjustment).exp();
+        let dsc = self.discount_curve.borrow().get_discount_factor(t)?;
+
+        let barrier = match option.get_monitoring_dates().is_empty() {
+            true => option.get_barrier(),
+            false => {
+                let monitoring_times: Vec<Time> = option
+                    .get_monitoring_dates()
+                    .iter()
+                    .map(|date| self.time_calculator.get_time_difference(&eval_date, date))
+                    .filter(|s| *s > 0.0 && *s <= t + 1.0e-6)
+                    .collect();
+                if monitoring_times.is_empty() {
+                    // no monitoring remains, so the barrier can not be hit any more
+                    return Ok(match is_in {
+                        true => rebate * dsc,
+                        false => vanilla_black(option_type, strike, t, fwd, dsc, vol),
+                    });
+                }
+                let last = monitoring_times
+                    .iter()
+                    .fold(0.0, |acc: Time, s| acc.max(*s));
+                let dt = last / monitoring_times.len() as Time;
+                let shift = (BROADIE_GLASSERMAN_BETA * vol * dt.sqrt()).exp();
+                match is_up {
+                    true => option.get_barrier() * shift,
+                    false => option.get_barrier() / shift,
+                }
+            }
+        };
+
+        let hit = match is_up {
+            true => spot >= barrier,
+            false => spot <= barrier,
+        };
+        if hit {
+            return Ok(match is_in {
+                true => vanilla_black(option_type, strike, t, fwd, dsc, vol),
+                false => rebate,
+            });
+        }
+
+        Ok(reiner_rubinstein(
+            option_type,
+            barrier_type,
+            spot,
+            strike,
+            barrier,
+            rebate,
+            t,
+            fwd,
+            dsc,
+            vol,
+        ))
+    }
+
+    fn npv_result(&self, instrument: &Instrument) -> Result<NpvResult> {
+        let npv = self.npv(instrument)?;
+        Ok(NpvResult::new_from_npv(npv))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::data;
+    use crate::enums::{OptionDailySettlementType, OptionExerciseType};
+    use crate::instruments::{barrier_option::BarrierOption, vanilla_option::VanillaOption};
+    use crate::parameters::volatilities::constant_volatility::ConstantVolatility;
+    use crate::pricing_engines::option_analytic_pricer::OptionAnalyticPricer;
+    use crate::vectordatasample;
+    use time::{macros::datetime, Duration};
+
+    #[test]
+    fn test_barrier_option_analytic_pricer() -> Result<()> {
+        // Haug, The Complete Guide to Option Pricing Formulas, Table 4-13
+        // S = 100, K = 3, T = 0.5, r = 0.08, b = 0.04, vol = 0.25
+        let (spot, t, vol) = (100.0, 0.5, 0.25);
+        let forward = spot * (0.04 * t as Real).exp();
+        let dsc = (-0.08 * t as Real).exp();
+        let expected = [
+            (
+                BarrierType::DownAndOut,
+                OptionType::Call,
+                95.0,
+                [9.0246, 6.7924, 4.8759],
+            ),
+            (
+                BarrierType::UpAndOut,
+                OptionType::Call,
+                105.0,
+                [2.6789, 2.3580, 2.3453],
+            ),
+            (
+                BarrierType::DownAndIn,
+                OptionType::Call,
+                95.0,
+                [7.7627, 4.0109, 2.0576],
+            ),
+            (
+                BarrierType::UpAndIn,
+                OptionType::Call,
+                105.0,
+                [14.1112, 8.4482, 4.5910],
+            ),
+            (
+                BarrierType::DownAndOut,
+                OptionType::Put,
+                95.0,
+                [2.2798, 2.2947, 2.6252],
+            ),
+            (
+                BarrierType::UpAndOut,
+                OptionType::Put,
+                105.0,
+                [3.7760, 5.4932, 7.5187],
+            ),
+            (
+                BarrierType::DownAndIn,
+                OptionType::Put,
+                95.0,
+                [2.9586, 6.5677, 11.9752],
+            ),
+            (
+                BarrierType::UpAndIn,
+                OptionType::Put,
+                105.0,
+                [1.4653, 3.3721, 7.0846],
+            ),
+        ];
+        for (barrier_type, option_type, barrier, values) in expected {
+            for (strike, value) in [90.0, 100.0, 110.0].iter().zip(values) {
+                let npv = reiner_rubinstein(
+                    option_type,
+                    barrier_type,
+                    spot,
+                    *strike,
+                    barrier,
+                    3.0,
+                    t,
+                    forward,
+                    dsc,
+                    vol,
+                );
+                assert!(
+                    (npv - value).abs() < 1.0e-3,
+                    "{:?} {:?} strike {}: npv {}, expected {}",
+                    barrier_type,
+                    option_type,
+                    strike,
+                    npv,
+                    value
+                );
+            }
+        }
+
+        // in-out parity and the discrete monitoring through the pricer
+        let eval_date = datetime!(2024-01-02 16:30:00 +09:00);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_date)));
+        let spot = 357.38;
+        let market_price = Rc::new(RefCell::new(MarketPrice::new(
+            spot,
+            eval_date,
+            None,
+            Currency::KRW,
+            "KOSPI2".to_string(),
+            "KOSPI2".to_string(),
+        )));
+        let curve_data = vectordatasample!(0.03, Currency::KRW, "Barrier Test Curve")?;
+        let curve = Rc::new(RefCell::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &curve_data,
+            "Barrier Test Curve".to_string(),
+            "Barrier Test Curve".to_string(),
+        )?));
+        let volatility = Rc::new(RefCell::new(Volatility::ConstantVolatility(
+            ConstantVolatility::new(0.2, "KOSPI2".to_string(), "KOSPI2".to_string()),
+        )));
+        let pricer = BarrierOptionAnalyticPricer::new(
+            evaluation_date.clone(),
+            market_price.clone(),
+            curve.clone(),
+            curve.clone(),
+            curve.clone(),
+            volatility.clone(),
+            None,
+        );
+        let vanilla_pricer = OptionAnalyticPricer::new(
+            evaluation_date.clone(),
+            market_price.clone(),
+            curve.clone(),
+            curve.clone(),
+            curve.clone(),
+            volatility.clone(),
+            None,
+        );
+
+        let maturity = datetime!(2024-07-02 16:30:00 +09:00);
+        let strike = spot;
+        let barrier_option = |barrier: Real, barrier_type: BarrierType| {
+            BarrierOption::new(
+                strike,
+                barrier,
+                barrier_type,
+                0.0,
+                250_000.0,
+                eval_date,
+                maturity,
+                maturity,
+                maturity,
+                vec!["KOSPI2".to_string()],
+                Currency::KRW,
+                Currency::KRW,
+                OptionType::Call,
+                OptionDailySettlementType::NotSettled,
+                "KOSPI2 Barrier Call".to_string(),
+                "KOSPI2 Barrier Call".to_string(),
+            )
+        };
+        let vanilla = Instrument::VanillaOption(VanillaOption::new(
+            strike,
+            250_000.0,
+            eval_date,
+            maturity,
+            maturity,
+            maturity,
+            vec!["KOSPI2".to_string()],
+            Currency::KRW,
+            Currency::KRW,
+            OptionType::Call,
+            OptionExerciseType::European,
+            OptionDailySettlementType::NotSettled,
+            "KOSPI2 Call".to_string(),
+            "KOSPI2 Call".to_string(),
+        ));
+        let vanilla_npv = vanilla_pricer.npv(&vanilla)?;
+
+        for (barrier, knock_in, knock_out) in [
+            (0.9 * spot, BarrierType::DownAndIn, BarrierType::DownAndOut),
+            (1.2 * spot, BarrierType::UpAndIn, BarrierType::UpAndOut),
+        ] {
+            let in_npv = pricer.npv(&Instrument::BarrierOption(barrier_option(
+                barrier, knock_in,
+            )))?;
+            let out_npv = pricer.npv(&Instrument::BarrierOption(barrier_option(
+                barrier, knock_out,
+            )))?;
+            assert!(
+                (in_npv + out_npv - vanilla_npv).abs() < 1.0e-2,
+                "{:?} + {:?}: {} + {} != {}",
+                knock_in,
+                knock_out,
+                in_npv,
+                out_npv,
+                vanilla_npv
+            );
+        }
+
+        // a discretely monitored knock-out is hit less often than the continuous one
+        let daily_dates: Vec<_> = (1..=182).map(|i| eval_date + Duration::days(i)).collect();
+        let continuous = barrier_option(0.9 * spot, BarrierType::DownAndOut);
+        let discrete = continuous.clone().with_monitoring_dates(daily_dates);
+        let continuous_npv = pricer.npv(&Instrument::BarrierOption(continuous))?;
+        let discrete_npv = pricer.npv(&Instrument::BarrierOption(discrete))?;
+        assert!(
+            continuous_npv < discrete_npv && discrete_npv < vanilla_npv,
+            "continuous {}, discrete {}, vanilla {}",
+            continuous_npv,
+            discrete_npv,
+            vanilla_npv
+        );
+
+        // the spot below the down barrier means that the barrier has been hit
+        market_price.borrow_mut().set_price(0.85 * spot);
+        let knocked_in = pricer.npv(&Instrument::BarrierOption(barrier_option(
+            0.9 * spot,
+            BarrierType::DownAndIn,
+        )))?;
+        let knocked_out = pricer.npv(&Instrument::BarrierOption(barrier_option(
+            0.9 * spot,
+            BarrierType::DownAndOut,
+        )))?;
+        assert!((knocked_in - vanilla_pricer.npv(&vanilla)?).abs() < 1.0e-3);
+        assert_eq!(knocked_out, 0.0);
+        Ok(())
+    }
+}
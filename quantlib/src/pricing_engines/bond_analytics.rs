@@ -0,0 +1,399 @@
+use crate::definitions::Real;
+use crate::enums::YieldCompounding;
+use crate::instrument::InstrumentTrait;
+use crate::instruments::bond::Bond;
+use crate::time::{calendar_trait::CalendarTrait, conventions::DayCountConvention};
+//
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use time::OffsetDateTime;
+
+/// Yield-based analytics of a bond on its dirty price.
+/// All prices are per unit notional as the npv of the bond,
+/// so that the KRX quotation (per 10,000 face value) is the price times the unit_notional of 10,000.
+/// * accrued_interest: the coupon of the current period times
+///   the actual days from the start of the period over the actual days in the period
+/// * clean_price: dirty_price - accrued_interest
+/// * macaulay_duration: cashflow-weighted average time to the cashflows discounted on the yield
+/// * modified_duration: -(dP/dy) / P
+/// * convexity: (d^2P/dy^2) / P
+/// * dv01: the decrease of the dirty price on 1bp rise of the yield
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct BondAnalytics {
+    yield_compounding: YieldCompounding,
+    yield_to_maturity: Real,
+    dirty_price: Real,
+    accrued_interest: Real,
+    clean_price: Real,
+    macaulay_duration: Real,
+    modified_duration: Real,
+    convexity: Real,
+    dv01: Real,
+}
+
+/// The coupon of the period containing the date times the actual days from the start of the period
+/// over the actual days in the period, where cashflows are those of Bond::get_cashflows from the date.
+pub fn accrued_interest(
+    bond: &Bond,
+    date: &OffsetDateTime,
+    cashflows: &HashMap<OffsetDateTime, Real>,
+) -> Result<Real> {
+    let current_period = bond.get_schedule()?.iter().find(|base_schedule| {
+        base_schedule.get_calc_start_date().date() <= date.date()
+            && date.date() < base_schedule.get_calc_end_date().date()
+    });
+    let res = match current_period {
+        None => 0.0,
+        Some(base_schedule) => {
+            let payment_date = base_schedule.get_payment_date();
+            let principal =
+                match Some(payment_date) == bond.get_maturity() && !bond.is_coupon_strip()? {
+                    true => 1.0,
+                    false => 0.0,
+                };
+            let coupon = cashflows.get(payment_date).map_or(0.0, |x| x - principal);
+            let start_date = base_schedule.get_calc_start_date().date();
+            let accrued_days = (date.date() - start_date).whole_days();
+            let period_days = (base_schedule.get_calc_end_date().date() - start_date).whole_days();
+            coupon * accrued_days as Real / period_days as Real
+        }
+    };
+    Ok(res)
+}
+
+/// time to a cashflow in years and in coupon periods (counted from the next coupon date for the KRX convention)
+struct CashflowTime {
+    amount: f64,
+    time: f64,
+    periods: f64,
+}
+
+impl BondAnalytics {
+    /// cashflows (payment date -> amount per unit notional) are those of Bond::get_cashflows
+    /// whose floating coupons are already projected. The cashflows on or before the pricing date are ignored.
+    pub fn new(
+        bond: &Bond,
+        pricing_date: &OffsetDateTime,
+        dirty_price: Real,
+        cashflows: &HashMap<OffsetDateTime, Real>,
+        yield_compounding: YieldCompounding,
+    ) -> Result<BondAnalytics> {
+        let calendar = bond.get_calendar()?;
+        let frequency = match bond.get_coupon_frequency()?.as_real() {
+            f if f > 0.0 => f as f64,
+            _ => 1.0,
+        };
+
+        let mut future_cashflows = cashflows
+            .iter()
+            .filter(|(date, _)| date.date() > pricing_date.date())
+            .collect::<Vec<(&OffsetDateTime, &Real)>>();
+        future_cashflows.sort_by(|a, b| a.0.cmp(b.0));
+        if future_cashflows.is_empty() || dirty_price <= 0.0 {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has no cashflow after {} or non-positive dirty price = {}",
+                file!(),
+                line!(),
+                bond.get_name(),
+                bond.get_code(),
+                pricing_date,
+                dirty_price
+            ));
+        }
+
+        let accrued_interest = accrued_interest(bond, pricing_date, cashflows)?;
+
+        // 금융투자회사의 영업 및 업무에 관한 규정 별표 14:
+        // the fraction of the period to the next coupon date over the days of the period
+        let next_date = future_cashflows[0].0;
+        let previous_date = bond
+            .get_schedule()?
+            .iter()
+            .filter(|base_schedule| base_schedule.get_payment_date().date() <= pricing_date.date())
+            .map(|base_schedule| base_schedule.get_payment_date())
+            .max()
+            .unwrap_or(bond.get_issue_date()?);
+        let broken_period = (next_date.date() - pricing_date.date()).whole_days() as f64
+            / (next_date.date() - previous_date.date()).whole_days() as f64;
+
+        let cashflow_times = future_cashflows
+            .iter()
+            .map(|(date, amount)| {
+                let (time, periods) = match yield_compounding {
+                    YieldCompounding::Krx => {
+                        let periods = calendar.year_fraction(
+                            next_date,
+                            date,
+                            &DayCountConvention::StreetConvention,
+                        )? as f64
+                            * frequency;
+                        ((periods + broken_period) / frequency, periods)
+                    }
+                    _ => {
+                        let time =
+                            calendar.year_fraction(pricing_date, date, bond.get_daycounter())?
+                                as f64;
+                        (time, time * frequency)
+                    }
+                };
+                Ok(CashflowTime {
+                    amount: **amount as f64,
+                    time,
+                    periods,
+                })
+            })
+            .collect::<Result<Vec<CashflowTime>>>()?;
+
+        // discount factor with its first and second derivatives on the yield
+        let discount = |y: f64, cf: &CashflowTime| -> (f64, f64, f64) {
+            let t = cf.time;
+            match yield_compounding {
+                YieldCompounding::Simple => {
+                    let df = 1.0 / (1.0 + y * t);
+                    (df, -t * df * df, 2.0 * t * t * df * df * df)
+                }
+                YieldCompounding::Continuous => {
+                    let df = (-y * t).exp();
+                    (df, -t * df, t * t * df)
+                }
+                YieldCompounding::Annual => {
+                    let df = (1.0 + y).powf(-t);
+                    (
+                        df,
+                        -t * df / (1.0 + y),
+                        t * (t + 1.0) * df / (1.0 + y).powi(2),
+                    )
+                }
+                YieldCompounding::CouponFrequency => {
+                    let a = 1.0 + y / frequency;
+                    let df = a.powf(-t * frequency);
+                    (df, -t * df / a, t * (t + 1.0 / frequency) * df / a.powi(2))
+                }
+                YieldCompounding::Krx => {
+                    let a = 1.0 + y / frequency;
+                    let b = 1.0 + y / frequency * broken_period;
+                    let df = a.powf(-cf.periods) / b;
+                    let g = -cf.periods / (frequency * a) - broken_period / (frequency * b);
+                    let dg = cf.periods / (frequency * a).powi(2)
+                        + (broken_period / (frequency * b)).powi(2);
+                    (df, df * g, df * (g * g + dg))
+                }
+            }
+        };
+        let price = |y: f64| -> f64 {
+            cashflow_times
+                .iter()
+                .map(|cf| cf.amount * discount(y, cf).0)
+                .sum()
+        };
+
+        // the price is decreasing on the yield, so the yield is found by bisection
+        let max_time = cashflow_times.last().unwrap().time;
+        let mut lower: f64 = match yield_compounding {
+            YieldCompounding::Simple => (-0.99 / max_time).max(-0.5),
+            _ => -0.5,
+        };
+        let mut upper: f64 = 2.0;
+        let target = dirty_price as f64;
+        if price(lower) < target || price(upper) > target {
+            return Err(anyhow!(
+                "({}:{}) the dirty price = {} of {} ({}) is out of the range of the yield in [{}, {}]",
+                file!(),
+                line!(),
+                dirty_price,
+                bond.get_name(),
+                bond.get_code(),
+                lower,
+                upper
+            ));
+        }
+        for _ in 0..200 {
+            let mid = 0.5 * (lower + upper);
+            if price(mid) > target {
+                lower = mid;
+            } else {
+                upper = mid;
+            }
+            if upper - lower < 1.0e-14 {
+                break;
+            }
+        }
+        let y = 0.5 * (lower + upper);
+
+        let (mut p, mut dp, mut d2p, mut weighted_time) = (0.0, 0.0, 0.0, 0.0);
+        for cf in cashflow_times.iter() {
+            let (df, d1, d2) = discount(y, cf);
+            p += cf.amount * df;
+            dp += cf.amount * d1;
+            d2p += cf.amount * d2;
+            weighted_time += cf.time * cf.amount * df;
+        }
+
+        Ok(BondAnalytics {
+            yield_compounding,
+            yield_to_maturity: y as Real,
+            dirty_price,
+            accrued_interest,
+            clean_price: dirty_price - accrued_interest,
+            macaulay_duration: (weighted_time / p) as Real,
+            modified_duration: (-dp / p) as Real,
+            convexity: (d2p / p) as Real,
+            dv01: (-dp * 0.0001) as Real,
+        })
+    }
+
+    pub fn get_yield_compounding(&self) -> YieldCompounding {
+        self.yield_compounding
+    }
+
+    pub fn get_yield_to_maturity(&self) -> Real {
+        self.yield_to_maturity
+    }
+
+    pub fn get_dirty_price(&self) -> Real {
+        self.dirty_price
+    }
+
+    pub fn get_accrued_interest(&self) -> Real {
+        self.accrued_interest
+    }
+
+    pub fn get_clean_price(&self) -> Real {
+        self.clean_price
+    }
+
+    pub fn get_macaulay_duration(&self) -> Real {
+        self.macaulay_duration
+    }
+
+    pub fn get_modified_duration(&self) -> Real {
+        self.modified_duration
+    }
+
+    pub fn get_convexity(&self) -> Real {
+        self.convexity
+    }
+
+    pub fn get_dv01(&self) -> Real {
+        self.dv01
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::enums::{CreditRating, IssuerType, RankType};
+    use crate::evaluation_date::EvaluationDate;
+    use crate::instrument::Instrument;
+    use crate::pricing_engines::{krx_yield_pricer::KrxYieldPricer, pricer::PricerTrait};
+    use crate::time::calendars::southkorea::{SouthKorea, SouthKoreaType};
+    use crate::time::conventions::{BusinessDayConvention, PaymentFrequency};
+    use crate::time::{calendar::Calendar, jointcalendar::JointCalendar};
+    use std::{cell::RefCell, rc::Rc};
+    use time::macros::datetime;
+
+    #[test]
+    fn test_bond_analytics() -> Result<()> {
+        let dt = datetime!(2024-03-19 16:30:00 +09:00);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(dt)));
+        let issue_date = datetime!(2022-12-10 16:30:00 +09:00);
+        let calendar = JointCalendar::new(vec![Calendar::SouthKorea(SouthKorea::new(
+            SouthKoreaType::Settlement,
+        ))])?;
+        let bond = Bond::new_from_conventions(
+            IssuerType::Government,
+            CreditRating::None,
+            "Korea Gov".to_string(),
+            RankType::Senior,
+            Currency::KRW,
+            10_000.0,
+            false,
+            issue_date,
+            issue_date,
+            None,
+            datetime!(2025-12-10 16:30:00 +09:00),
+            Some(0.0425),
+            None,
+            None,
+            None,
+            calendar,
+            true,
+            DayCountConvention::StreetConvention,
+            BusinessDayConvention::Unadjusted,
+            PaymentFrequency::SemiAnnually,
+            0,
+            0,
+            "국고채권 04250-2512(22-13)".to_string(),
+            "KR103501GCC0".to_string(),
+        )?;
+        let cashflows = bond.get_cashflows(&dt, None, None)?;
+        let instrument = Instrument::Bond(bond.clone());
+        let krx_price = |bond_yield: Real| -> Result<Real> {
+            KrxYieldPricer::new(evaluation_date.clone(), bond_yield, None, None).npv(&instrument)
+        };
+
+        // the KRX yield is recovered from the price of KrxYieldPricer
+        let dirty_price = krx_price(0.035)?;
+        let analytics =
+            BondAnalytics::new(&bond, &dt, dirty_price, &cashflows, YieldCompounding::Krx)?;
+        assert!(
+            (analytics.get_yield_to_maturity() - 0.035).abs() < 1.0e-5,
+            "yield = {}",
+            analytics.get_yield_to_maturity()
+        );
+
+        // 100 days accrued in the coupon period from 2023-12-10 to 2024-06-10 (183 days)
+        let accrued = 0.0425 / 2.0 * 100.0 / 183.0;
+        assert!((analytics.get_accrued_interest() - accrued).abs() < 1.0e-6);
+        assert!(
+            (analytics.get_clean_price() + analytics.get_accrued_interest() - dirty_price).abs()
+                < 1.0e-6
+        );
+
+        // dv01 and modified duration against the KRX repricing on the yield
+        let dv01 = (krx_price(0.0349)? - krx_price(0.0351)?) / 2.0;
+        assert!(
+            (analytics.get_dv01() - dv01).abs() / dv01 < 0.01,
+            "dv01 = {}, repriced = {}",
+            analytics.get_dv01(),
+            dv01
+        );
+        assert!(
+            (analytics.get_modified_duration() * dirty_price * 0.0001 - analytics.get_dv01()).abs()
+                < 1.0e-7
+        );
+        assert!(analytics.get_convexity() > 0.0);
+
+        // the continuous yield is the periodic yield converted, and its modified duration is the Macaulay duration
+        let periodic = BondAnalytics::new(
+            &bond,
+            &dt,
+            dirty_price,
+            &cashflows,
+            YieldCompounding::CouponFrequency,
+        )?;
+        let continuous = BondAnalytics::new(
+            &bond,
+            &dt,
+            dirty_price,
+            &cashflows,
+            YieldCompounding::Continuous,
+        )?;
+        let converted = 2.0 * (1.0 + periodic.get_yield_to_maturity() / 2.0).ln();
+        assert!((continuous.get_yield_to_maturity() - converted).abs() < 1.0e-6);
+        assert!(
+            (continuous.get_modified_duration() - continuous.get_macaulay_duration()).abs()
+                < 1.0e-6
+        );
+        assert!(
+            (periodic.get_modified_duration()
+                - periodic.get_macaulay_duration()
+                    / (1.0 + periodic.get_yield_to_maturity() / 2.0))
+                .abs()
+                < 1.0e-6
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,470 @@
+use crate::definitions::Real;
+use crate::evaluation_date::EvaluationDate;
+use crate::instrument::{Instrument, InstrumentTrait};
+use crate::instruments::{bond::Bond, bond_futures::BondFutures};
+use crate::parameters::zero_curve::ZeroCurve;
+use crate::pricing_engines::{
+    bond_analytics::accrued_interest, bond_pricer::BondPricer, npv_result::NpvResult,
+    pricer::PricerTrait,
+};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, rc::Rc};
+
+/// A deliverable bond of bond futures against the futures price, all per unit face value.
+/// * clean_price: spot clean price on the discount curve
+/// * forward_clean_price: clean price at the delivery date where the bond is financed on the repo curve
+/// * gross_basis: clean_price - futures price * conversion_factor
+/// * net_basis: forward_clean_price - futures price * conversion_factor, i.e., the gross basis less the carry
+/// * implied_repo_rate: simple rate earned by buying the bond, delivering it and reinvesting the coupons in between
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeliverableBondAnalytics {
+    code: String,
+    conversion_factor: Real,
+    clean_price: Real,
+    forward_clean_price: Real,
+    gross_basis: Real,
+    net_basis: Real,
+    implied_repo_rate: Real,
+}
+
+impl DeliverableBondAnalytics {
+    pub fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    pub fn get_conversion_factor(&self) -> Real {
+        self.conversion_factor
+    }
+
+    pub fn get_clean_price(&self) -> Real {
+        self.clean_price
+    }
+
+    pub fn get_forward_clean_price(&self) -> Real {
+        self.forward_clean_price
+    }
+
+    pub fn get_gross_basis(&self) -> Real {
+        self.gross_basis
+    }
+
+    pub fn get_net_basis(&self) -> Real {
+        self.net_basis
+    }
+
+    pub fn get_implied_repo_rate(&self) -> Real {
+        self.implied_repo_rate
+    }
+}
+
+/// Bond futures against futures_price: the analytics of the deliverables and the cheapest-to-deliver among them
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BondFuturesAnalytics {
+    code: String,
+    futures_price: Real,
+    deliverables: Vec<DeliverableBondAnalytics>,
+    cheapest_to_deliver: DeliverableBondAnalytics,
+}
+
+impl BondFuturesAnalytics {
+    pub fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    pub fn get_futures_price(&self) -> Real {
+        self.futures_price
+    }
+
+    pub fn get_deliverables(&self) -> &Vec<DeliverableBondAnalytics> {
+        &self.deliverables
+    }
+
+    pub fn get_cheapest_to_deliver(&self) -> &DeliverableBondAnalytics {
+        &self.cheapest_to_deliver
+    }
+}
+
+/// Spot and forward prices of a deliverable bond
+struct DeliverablePrices {
+    dirty_price: Real,
+    clean_price: Real,
+    forward_clean_price: Real,
+    accrued_at_delivery: Real,
+    coupons: Vec<(Real, Real)>, // (time, amount) of the coupons until the delivery date
+    time_to_delivery: Real,
+}
+
+/// Bond futures on a deliverable basket.
+/// The deliverables are priced on discount_curve and financed until the delivery date on borrowing_curve (repo),
+/// and the futures price is the lowest forward clean price over conversion factor in the basket,
+/// which is that of the cheapest-to-deliver.
+pub struct BondFuturesPricer {
+    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    discount_curve: Rc<RefCell<ZeroCurve>>,
+    borrowing_curve: Rc<RefCell<ZeroCurve>>,
+    time_calculator: NullCalendar,
+}
+
+impl BondFuturesPricer {
+    pub fn new(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        discount_curve: Rc<RefCell<ZeroCurve>>,
+        borrowing_curve: Rc<RefCell<ZeroCurve>>,
+    ) -> BondFuturesPricer {
+        BondFuturesPricer {
+            evaluation_date,
+            discount_curve,
+            borrowing_curve,
+            time_calculator: NullCalendar::new(),
+        }
+    }
+
+    fn get_bond_futures<'a>(&self, instrument: &'a Instrument) -> Result<&'a BondFutures> {
+        match instrument {
+            Instrument::BondFutures(bond_futures) => Ok(bond_futures),
+            _ => Err(anyhow!(
+                "({}:{}) BondFuturesPricer does not support {} ({})",
+                file!(),
+                line!(),
+                instrument.get_name(),
+                instrument.get_code()
+            )),
+        }
+    }
+
+    fn deliverable_prices(
+        &self,
+        bond: &Bond,
+        delivery_date: &time::OffsetDateTime,
+    ) -> Result<DeliverablePrices> {
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let bond_pricer = BondPricer::new(
+            self.evaluation_date.clone(),
+            self.discount_curve.clone(),
+            None,
+            None,
+        );
+        let dirty_price = bond_pricer.npv(&Instrument::Bond(bond.clone()))?;
+        let cashflows = bond.get_cashflows(&eval_date, None, None)?;
+        let clean_price = dirty_price - accrued_interest(bond, &eval_date, &cashflows)?;
+
+        let mut coupons = vec![];
+        let mut coupon_value = 0.0;
+        for (date, amount) in cashflows.iter() {
+            if date.date() > eval_date.date() && date.date() <= delivery_date.date() {
+                coupon_value += amount
+                    * self
+                        .borrowing_curve
+                        .borrow()
+                        .get_discount_factor_at_date(date)?;
+                coupons.push((
+                    self.time_calculator.get_time_difference(&eval_date, date),
+                    *amount,
+                ));
+            }
+        }
+        let forward_dirty_price = (dirty_price - coupon_value)
+            / self
+                .borrowing_curve
+                .borrow()
+                .get_discount_factor_at_date(delivery_date)?;
+        let accrued_at_delivery = accrued_interest(
+            bond,
+            delivery_date,
+            &bond.get_cashflows(delivery_date, None, None)?,
+        )?;
+
+        Ok(DeliverablePrices {
+            dirty_price,
+            clean_price,
+            forward_clean_price: forward_dirty_price - accrued_at_delivery,
+            accrued_at_delivery,
+            coupons,
+            time_to_delivery: self
+                .time_calculator
+                .get_time_difference(&eval_date, delivery_date),
+        })
+    }
+
+    /// conversion factors, basis and implied repo rates of the deliverables against futures_price
+    pub fn deliverable_analytics(
+        &self,
+        instrument: &Instrument,
+        futures_price: Real,
+    ) -> Result<Vec<DeliverableBondAnalytics>> {
+        let bond_futures = self.get_bond_futures(instrument)?;
+        let delivery_date = bond_futures.get_delivery_date();
+        let mut res = vec![];
+        for (bond, conversion_factor) in bond_futures
+            .get_underlying_bonds()?
+            .iter()
+            .zip(bond_futures.get_conversion_factors().iter())
+        {
+            let prices = self
+                .deliverable_prices(bond, delivery_date)
+                .with_context(|| {
+                    anyhow!(
+                        "({}:{}) failed to price the deliverable {} ({}) of {} ({})",
+                        file!(),
+                        line!(),
+                        bond.get_name(),
+                        bond.get_code(),
+                        instrument.get_name(),
+                        instrument.get_code()
+                    )
+                })?;
+            let invoice_price = futures_price * conversion_factor + prices.accrued_at_delivery;
+            let coupon_sum: Real = prices.coupons.iter().map(|(_, amount)| amount).sum();
+            let coupon_time: Real = prices
+                .coupons
+                .iter()
+                .map(|(t, amount)| amount * (prices.time_to_delivery - t))
+                .sum();
+            let implied_repo_rate = (invoice_price + coupon_sum - prices.dirty_price)
+                / (prices.dirty_price * prices.time_to_delivery - coupon_time);
+
+            res.push(DeliverableBondAnalytics {
+                code: bond.get_code().clone(),
+                conversion_factor: *conversion_factor,
+                clean_price: prices.clean_price,
+                forward_clean_price: prices.forward_clean_price,
+                gross_basis: prices.clean_price - futures_price * conversion_factor,
+                net_basis: prices.forward_clean_price - futures_price * conversion_factor,
+                implied_repo_rate,
+            });
+        }
+        Ok(res)
+    }
+
+    /// the deliverable with the highest implied repo rate against futures_price
+    pub fn cheapest_to_deliver(
+        &self,
+        instrument: &Instrument,
+        futures_price: Real,
+    ) -> Result<DeliverableBondAnalytics> {
+        Ok(self
+            .analytics(instrument, futures_price)?
+            .cheapest_to_deliver)
+    }
+
+    /// deliverable analytics and the cheapest-to-deliver against futures_price
+    pub fn analytics(
+        &self,
+        instrument: &Instrument,
+        futures_price: Real,
+    ) -> Result<BondFuturesAnalytics> {
+        let deliverables = self.deliverable_analytics(instrument, futures_price)?;
+        let cheapest_to_deliver = deliverables
+            .iter()
+            .max_by(|a, b| a.implied_repo_rate.total_cmp(&b.implied_repo_rate))
+            .ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) {} ({}) has no deliverable bond",
+                    file!(),
+                    line!(),
+                    instrument.get_name(),
+                    instrument.get_code()
+                )
+            })?
+            .clone();
+        Ok(BondFuturesAnalytics {
+            code: instrument.get_code().clone(),
+            futures_price,
+            deliverables,
+            cheapest_to_deliver,
+        })
+    }
+}
+
+impl PricerTrait for BondFuturesPricer {
+    fn npv(&self, instrument: &Instrument) -> Result<Real> {
+        let bond_futures = self.get_bond_futures(instrument)?;
+        let delivery_date = bond_futures.get_delivery_date();
+        let mut res = Real::MAX;
+        for (bond, conversion_factor) in bond_futures
+            .get_underlying_bonds()?
+            .iter()
+            .zip(bond_futures.get_conversion_factors().iter())
+        {
+            let prices = self.deliverable_prices(bond, delivery_date)?;
+            res = res.min(prices.forward_clean_price / conversion_factor);
+        }
+        Ok(res)
+    }
+
+    fn npv_result(&self, instrument: &Instrument) -> Result<NpvResult> {
+        let npv = self.npv(instrument)?;
+        Ok(NpvResult::new_from_npv(npv))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::enums::{CreditRating, IssuerType, RankType};
+    use crate::time::{
+        calendar::Calendar,
+        calendars::unitedstates::{UnitedStates, UnitedStatesType},
+        conventions::{BusinessDayConvention, DayCountConvention, PaymentFrequency},
+        jointcalendar::JointCalendar,
+    };
+    use crate::utils::test_data::flat_zero_curve;
+    use time::macros::{date, datetime};
+
+    fn treasury(
+        coupon_rate: Real,
+        issue_date: time::OffsetDateTime,
+        maturity: time::OffsetDateTime,
+    ) -> Result<Bond> {
+        let calendar = JointCalendar::new(vec![Calendar::UnitedStates(UnitedStates::new(
+            UnitedStatesType::Settlement,
+        ))])?;
+        let code = format!("T {} {}", coupon_rate, maturity.date());
+        Bond::new_from_conventions(
+            IssuerType::Government,
+            CreditRating::None,
+            "US Treasury".to_string(),
+            RankType::Senior,
+            Currency::USD,
+            1.0,
+            false,
+            issue_date,
+            issue_date,
+            None,
+            maturity,
+            Some(coupon_rate),
+            None,
+            None,
+            None,
+            calendar,
+            true,
+            DayCountConvention::ActActIsda,
+            BusinessDayConvention::Unadjusted,
+            PaymentFrequency::SemiAnnually,
+            0,
+            0,
+            code.clone(),
+            code,
+        )
+    }
+
+    #[test]
+    fn test_bond_futures_pricer() -> Result<()> {
+        // 6% coupon gives one, and 4% with 10 years from the delivery month is 0.8512
+        let bond = treasury(
+            0.06,
+            datetime!(2014-03-15 16:00:00 -05:00),
+            datetime!(2034-03-15 16:00:00 -05:00),
+        )?;
+        assert_eq!(
+            BondFutures::conversion_factor(&bond, date!(2024 - 03 - 01), 0.06, 3)?,
+            1.0
+        );
+        let bond = treasury(
+            0.04,
+            datetime!(2014-03-01 16:00:00 -05:00),
+            datetime!(2034-03-01 16:00:00 -05:00),
+        )?;
+        assert_eq!(
+            BondFutures::conversion_factor(&bond, date!(2024 - 03 - 01), 0.06, 3)?,
+            0.8512
+        );
+
+        let dt = datetime!(2024-01-02 16:00:00 -05:00);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(dt)));
+        let curve =
+            |rate: Real, name: &str| flat_zero_curve(&evaluation_date, rate, Currency::USD, name);
+        let pricer = BondFuturesPricer::new(
+            evaluation_date.clone(),
+            curve(0.042, "USGOV")?,
+            curve(0.05, "USREPO")?,
+        );
+
+        let deliverables = vec![
+            treasury(
+                0.02875,
+                datetime!(2022-05-15 16:00:00 -05:00),
+                datetime!(2032-05-15 16:00:00 -05:00),
+            )?,
+            treasury(
+                0.04125,
+                datetime!(2022-11-15 16:00:00 -05:00),
+                datetime!(2032-11-15 16:00:00 -05:00),
+            )?,
+            treasury(
+                0.045,
+                datetime!(2023-11-15 16:00:00 -05:00),
+                datetime!(2033-11-15 16:00:00 -05:00),
+            )?,
+        ];
+        let bond_futures = Instrument::BondFutures(BondFutures::new(
+            Currency::USD,
+            100_000.0,
+            datetime!(2023-12-15 16:00:00 -05:00),
+            datetime!(2024-03-19 16:00:00 -05:00),
+            datetime!(2024-03-28 16:00:00 -05:00),
+            0.06,
+            3,
+            deliverables.clone(),
+            "USREPO".to_string(),
+            "TYH4".to_string(),
+            "TYH4".to_string(),
+        )?);
+
+        let futures_price = pricer.npv(&bond_futures)?;
+        let analytics = pricer.deliverable_analytics(&bond_futures, futures_price)?;
+        let ctd = pricer.cheapest_to_deliver(&bond_futures, futures_price)?;
+        assert!(analytics
+            .iter()
+            .all(|x| x.get_net_basis() >= ctd.get_net_basis() - 1.0e-6));
+        assert!(ctd.get_net_basis().abs() < 1.0e-5);
+        // at the theoretical price, the cheapest-to-deliver earns the repo rate
+        assert!(
+            (ctd.get_implied_repo_rate() - 0.05).abs() < 2.0e-3,
+            "implied repo rate of {} = {}",
+            ctd.get_code(),
+            ctd.get_implied_repo_rate()
+        );
+        // the carry is the coupon income less the financing of the dirty price at the repo rate 5%
+        let delivery_date = datetime!(2024-03-28 16:00:00 -05:00);
+        let time_calculator = NullCalendar::new();
+        let t = time_calculator.get_time_difference(&dt, &delivery_date);
+        let bond_pricer =
+            BondPricer::new(evaluation_date.clone(), curve(0.042, "USGOV")?, None, None);
+        for (x, bond) in analytics.iter().zip(deliverables.iter()) {
+            assert!(x.get_conversion_factor() < 1.0);
+            let dirty_price = bond_pricer.npv(&Instrument::Bond(bond.clone()))?;
+            let accrued = |date: &time::OffsetDateTime| -> Result<Real> {
+                accrued_interest(bond, date, &bond.get_cashflows(date, None, None)?)
+            };
+            let mut coupon_income = accrued(&delivery_date)? - accrued(&dt)?;
+            for (date, amount) in bond.get_cashflows(&dt, None, None)?.iter() {
+                if date > &dt && date <= &delivery_date {
+                    let reinvested = t - time_calculator.get_time_difference(&dt, date);
+                    coupon_income += amount * (0.05 * reinvested).exp();
+                }
+            }
+            let financing = dirty_price * ((0.05 * t).exp() - 1.0);
+            let carry = coupon_income - financing;
+            assert!(
+                (x.get_gross_basis() - x.get_net_basis() - carry).abs() < 1.0e-5,
+                "carry of {}: {} vs {}",
+                x.get_code(),
+                x.get_gross_basis() - x.get_net_basis(),
+                carry
+            );
+        }
+
+        let futures_analytics = pricer.analytics(&bond_futures, futures_price)?;
+        assert_eq!(
+            futures_analytics.get_cheapest_to_deliver().get_code(),
+            ctd.get_code()
+        );
+        assert_eq!(futures_analytics.get_deliverables().len(), analytics.len());
+        Ok(())
+    }
+}
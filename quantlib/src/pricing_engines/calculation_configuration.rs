@@ -1,5 +1,8 @@
 use crate::definitions::{Integer, Real};
-use crate::enums::{StickynessType, VanillaOptionCalculationMethod};
+use crate::enums::{
+    StickynessType, VanillaOptionCalculationMethod, VolatilitySurfaceType, YieldCompounding,
+    ZeroCurveInterpolationType,
+};
 use crate::parameters::volatilities::volatiltiy_interpolator::VolatilityInterplator;
 use anyhow::{anyhow, Result};
 use ndarray::Array1;
@@ -9,7 +12,9 @@ use serde::{Deserialize, Serialize};
 /// StickynessType is an enum that represents the stickyness of the calculation.
 /// If the stickyness_type is StickyToMoneyness, the delta will be calculated with respect to moneyness:
 /// In other words, delta = dV/dS + dvol/dS * dV/dvols
+/// The missing fields are deserialized to the default, so the configurations saved before a field was added still load.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CalculationConfiguration {
     npv: bool,
     fx_exposure: bool,
@@ -23,15 +28,25 @@ pub struct CalculationConfiguration {
     rho_structure: bool,
     div_structure: bool,
     vega_matrix: bool,
+    normal_vega: bool,
+    bond_analytics: bool,
+    cs01_structure: bool,
+    cds_analytics: bool,
+    ktbf_analytics: bool,
+    bond_futures_analytics: bool,
     //
     stickyness_type: StickynessType,
     lv_interpolator: VolatilityInterplator,
+    zero_curve_interpolation_type: ZeroCurveInterpolationType,
+    volatility_surface_type: VolatilitySurfaceType,
+    yield_compounding: YieldCompounding,
     //
     delta_bump_ratio: Real,
     gamma_bump_ratio: Real,
     vega_bump_value: Real,
     vega_structure_bump_value: Real,
     vega_matrix_bump_value: Real,
+    normal_vega_bump_value: Real,
     rho_bump_value: Real,
     div_bump_value: Real,
     theta_day: Integer,
@@ -43,6 +58,12 @@ pub struct CalculationConfiguration {
     //
     vanilla_option_calculation_method: VanillaOptionCalculationMethod,
     //
+    montecarlo_path_number: usize,
+    montecarlo_time_steps_per_year: usize,
+    montecarlo_seed: u64,
+    //
+    finite_difference_space_grid_number: usize,
+    finite_difference_time_steps_per_year: usize,
 }
 
 impl Default for CalculationConfiguration {
@@ -100,13 +121,23 @@ impl Default for CalculationConfiguration {
             rho_structure: false,
             div_structure: false,
             vega_matrix: false,
+            normal_vega: false,
+            bond_analytics: false,
+            cs01_structure: false,
+            cds_analytics: false,
+            ktbf_analytics: false,
+            bond_futures_analytics: false,
             stickyness_type: StickynessType::StickyToMoneyness,
             lv_interpolator: VolatilityInterplator::default(),
+            zero_curve_interpolation_type: ZeroCurveInterpolationType::default(),
+            volatility_surface_type: VolatilitySurfaceType::default(),
+            yield_compounding: YieldCompounding::default(),
             delta_bump_ratio: 0.01,
             gamma_bump_ratio: 0.01,
             vega_bump_value: 0.01,
             vega_structure_bump_value: 0.01,
             vega_matrix_bump_value: 0.001,
+            normal_vega_bump_value: 0.0001,
             rho_bump_value: 0.0001,
             div_bump_value: 0.0001,
             theta_day: 1,
@@ -115,6 +146,11 @@ impl Default for CalculationConfiguration {
             div_structure_tenors: div_tenors,
             vega_matrix_spot_moneyness,
             vanilla_option_calculation_method: VanillaOptionCalculationMethod::Analytic,
+            montecarlo_path_number: 20_000,
+            montecarlo_time_steps_per_year: 50,
+            montecarlo_seed: 0,
+            finite_difference_space_grid_number: 400,
+            finite_difference_time_steps_per_year: 250,
         }
     }
 }
@@ -212,15 +248,25 @@ impl CalculationConfiguration {
             div_structure,
             rho_structure,
             vega_matrix,
+            normal_vega: false,
+            bond_analytics: false,
+            cs01_structure: false,
+            cds_analytics: false,
+            ktbf_analytics: false,
+            bond_futures_analytics: false,
             //
             stickyness_type,
             lv_interpolator,
+            zero_curve_interpolation_type: ZeroCurveInterpolationType::default(),
+            volatility_surface_type: VolatilitySurfaceType::default(),
+            yield_compounding: YieldCompounding::default(),
             //
             delta_bump_ratio,
             gamma_bump_ratio,
             vega_bump_value,
             vega_structure_bump_value,
             vega_matrix_bump_value,
+            normal_vega_bump_value: 0.0001,
             rho_bump_value,
             div_bump_value,
             theta_day,
@@ -230,9 +276,37 @@ impl CalculationConfiguration {
             vega_matrix_spot_moneyness,
             //
             vanilla_option_calculation_method,
+            //
+            montecarlo_path_number: 20_000,
+            montecarlo_time_steps_per_year: 50,
+            montecarlo_seed: 0,
+            //
+            finite_difference_space_grid_number: 400,
+            finite_difference_time_steps_per_year: 250,
         })
     }
 
+    /// npv (and fx exposure) only, e.g., for the revaluations on scenarios
+    pub fn without_sensitivities(mut self) -> CalculationConfiguration {
+        self.delta = false;
+        self.gamma = false;
+        self.vega = false;
+        self.rho = false;
+        self.div_delta = false;
+        self.theta = false;
+        self.vega_strucure = false;
+        self.rho_structure = false;
+        self.div_structure = false;
+        self.vega_matrix = false;
+        self.normal_vega = false;
+        self.bond_analytics = false;
+        self.cs01_structure = false;
+        self.cds_analytics = false;
+        self.ktbf_analytics = false;
+        self.bond_futures_analytics = false;
+        self
+    }
+
     pub fn with_theta_day(mut self, theta_day: Integer) -> CalculationConfiguration {
         self.theta_day = theta_day;
         self
@@ -266,6 +340,67 @@ impl CalculationConfiguration {
         self
     }
 
+    /// vega on the parallel bump of the normal volatility of the rate volatilities (caps, floors, swaptions)
+    pub fn with_normal_vega_calculation(mut self, normal_vega: bool) -> CalculationConfiguration {
+        self.normal_vega = normal_vega;
+        self
+    }
+
+    /// yield, duration, convexity, dv01 and accrued interest of bonds on the npv,
+    /// where the yield is compounded by with_yield_compounding (KRX convention by default)
+    pub fn with_bond_analytics_calculation(
+        mut self,
+        bond_analytics: bool,
+    ) -> CalculationConfiguration {
+        self.bond_analytics = bond_analytics;
+        self
+    }
+
+    /// credit spread sensitivity of the instruments discounted by credit spread curves
+    /// on the bucket bumps of the spreads over rho_structure_tenors
+    pub fn with_cs01_structure_calculation(
+        mut self,
+        cs01_structure: bool,
+    ) -> CalculationConfiguration {
+        self.cs01_structure = cs01_structure;
+        self
+    }
+
+    /// upfront, par spread, risky annuity, cs01 and recovery sensitivity of CDS per unit notional
+    pub fn with_cds_analytics_calculation(
+        mut self,
+        cds_analytics: bool,
+    ) -> CalculationConfiguration {
+        self.cds_analytics = cds_analytics;
+        self
+    }
+
+    /// basket yield, implied yield, basis, dv01 and hedge ratios of KTBF at their theoretical prices
+    pub fn with_ktbf_analytics_calculation(
+        mut self,
+        ktbf_analytics: bool,
+    ) -> CalculationConfiguration {
+        self.ktbf_analytics = ktbf_analytics;
+        self
+    }
+
+    /// conversion factors, basis, implied repo rates and cheapest-to-deliver of bond futures at their theoretical prices
+    pub fn with_bond_futures_analytics_calculation(
+        mut self,
+        bond_futures_analytics: bool,
+    ) -> CalculationConfiguration {
+        self.bond_futures_analytics = bond_futures_analytics;
+        self
+    }
+
+    pub fn with_yield_compounding(
+        mut self,
+        yield_compounding: YieldCompounding,
+    ) -> CalculationConfiguration {
+        self.yield_compounding = yield_compounding;
+        self
+    }
+
     pub fn with_theta_calculation(mut self, theta: bool) -> CalculationConfiguration {
         self.theta = theta;
         self
@@ -336,6 +471,14 @@ impl CalculationConfiguration {
         self
     }
 
+    pub fn with_normal_vega_bump_value(
+        mut self,
+        normal_vega_bump_value: Real,
+    ) -> CalculationConfiguration {
+        self.normal_vega_bump_value = normal_vega_bump_value;
+        self
+    }
+
     pub fn with_rho_bump_value(mut self, rho_bump_value: Real) -> CalculationConfiguration {
         self.rho_bump_value = rho_bump_value;
         self
@@ -381,6 +524,46 @@ impl CalculationConfiguration {
         self
     }
 
+    /// the number of paths including the antithetic paths
+    pub fn with_montecarlo_path_number(
+        mut self,
+        montecarlo_path_number: usize,
+    ) -> CalculationConfiguration {
+        self.montecarlo_path_number = montecarlo_path_number;
+        self
+    }
+
+    pub fn with_montecarlo_time_steps_per_year(
+        mut self,
+        montecarlo_time_steps_per_year: usize,
+    ) -> CalculationConfiguration {
+        self.montecarlo_time_steps_per_year = montecarlo_time_steps_per_year;
+        self
+    }
+
+    /// The seed is fixed so that the bumped npvs in the greek calculation share the same random numbers
+    pub fn with_montecarlo_seed(mut self, montecarlo_seed: u64) -> CalculationConfiguration {
+        self.montecarlo_seed = montecarlo_seed;
+        self
+    }
+
+    /// the number of intervals of the log-spot grid
+    pub fn with_finite_difference_space_grid_number(
+        mut self,
+        finite_difference_space_grid_number: usize,
+    ) -> CalculationConfiguration {
+        self.finite_difference_space_grid_number = finite_difference_space_grid_number;
+        self
+    }
+
+    pub fn with_finite_difference_time_steps_per_year(
+        mut self,
+        finite_difference_time_steps_per_year: usize,
+    ) -> CalculationConfiguration {
+        self.finite_difference_time_steps_per_year = finite_difference_time_steps_per_year;
+        self
+    }
+
     pub fn with_lv_interpolator(
         mut self,
         lv_interpolator: VolatilityInterplator,
@@ -389,10 +572,46 @@ impl CalculationConfiguration {
         self
     }
 
+    pub fn with_zero_curve_interpolation_type(
+        mut self,
+        zero_curve_interpolation_type: ZeroCurveInterpolationType,
+    ) -> CalculationConfiguration {
+        self.zero_curve_interpolation_type = zero_curve_interpolation_type;
+        self
+    }
+
+    pub fn with_volatility_surface_type(
+        mut self,
+        volatility_surface_type: VolatilitySurfaceType,
+    ) -> CalculationConfiguration {
+        self.volatility_surface_type = volatility_surface_type;
+        self
+    }
+
     pub fn get_vanilla_option_calculation_method(&self) -> VanillaOptionCalculationMethod {
         self.vanilla_option_calculation_method
     }
 
+    pub fn get_montecarlo_path_number(&self) -> usize {
+        self.montecarlo_path_number
+    }
+
+    pub fn get_montecarlo_time_steps_per_year(&self) -> usize {
+        self.montecarlo_time_steps_per_year
+    }
+
+    pub fn get_montecarlo_seed(&self) -> u64 {
+        self.montecarlo_seed
+    }
+
+    pub fn get_finite_difference_space_grid_number(&self) -> usize {
+        self.finite_difference_space_grid_number
+    }
+
+    pub fn get_finite_difference_time_steps_per_year(&self) -> usize {
+        self.finite_difference_time_steps_per_year
+    }
+
     pub fn get_div_structure_tenors(&self) -> &Vec<String> {
         &self.div_structure_tenors
     }
@@ -429,6 +648,10 @@ impl CalculationConfiguration {
         self.vega_bump_value
     }
 
+    pub fn get_normal_vega_bump_value(&self) -> Real {
+        self.normal_vega_bump_value
+    }
+
     pub fn get_rho_bump_value(&self) -> Real {
         self.rho_bump_value
     }
@@ -473,6 +696,34 @@ impl CalculationConfiguration {
         self.vega_strucure
     }
 
+    pub fn get_normal_vega_calculation(&self) -> bool {
+        self.normal_vega
+    }
+
+    pub fn get_bond_analytics_calculation(&self) -> bool {
+        self.bond_analytics
+    }
+
+    pub fn get_cs01_structure_calculation(&self) -> bool {
+        self.cs01_structure
+    }
+
+    pub fn get_cds_analytics_calculation(&self) -> bool {
+        self.cds_analytics
+    }
+
+    pub fn get_ktbf_analytics_calculation(&self) -> bool {
+        self.ktbf_analytics
+    }
+
+    pub fn get_bond_futures_analytics_calculation(&self) -> bool {
+        self.bond_futures_analytics
+    }
+
+    pub fn get_yield_compounding(&self) -> YieldCompounding {
+        self.yield_compounding
+    }
+
     pub fn get_theta_calculation(&self) -> bool {
         self.theta
     }
@@ -492,6 +743,14 @@ impl CalculationConfiguration {
     pub fn get_lv_interpolator(&self) -> VolatilityInterplator {
         self.lv_interpolator.clone()
     }
+
+    pub fn get_zero_curve_interpolation_type(&self) -> ZeroCurveInterpolationType {
+        self.zero_curve_interpolation_type
+    }
+
+    pub fn get_volatility_surface_type(&self) -> VolatilitySurfaceType {
+        self.volatility_surface_type
+    }
 }
 
 #[cfg(test)]
@@ -508,4 +767,33 @@ mod tests {
         println!("deserialized = {:?}", deserialized);
         assert_eq!(config, deserialized);
     }
+
+    #[test]
+    fn test_calculation_configuration_serde_missing_fields() {
+        let config = CalculationConfiguration::default();
+        let mut value = serde_json::to_value(&config).unwrap();
+        // the fields added after the configurations were saved
+        let added_fields = [
+            "montecarlo_path_number",
+            "montecarlo_time_steps_per_year",
+            "montecarlo_seed",
+            "finite_difference_space_grid_number",
+            "finite_difference_time_steps_per_year",
+            "zero_curve_interpolation_type",
+            "volatility_surface_type",
+            "normal_vega",
+            "normal_vega_bump_value",
+            "bond_analytics",
+            "yield_compounding",
+            "cs01_structure",
+            "cds_analytics",
+            "ktbf_analytics",
+            "bond_futures_analytics",
+        ];
+        for field in added_fields {
+            value.as_object_mut().unwrap().remove(field);
+        }
+        let deserialized: CalculationConfiguration = serde_json::from_value(value).unwrap();
+        assert_eq!(config, deserialized);
+    }
 }
@@ -1,7 +1,10 @@
 use crate::currency::Currency;
 use crate::definitions::{Integer, Real};
 use crate::instruments::instrument_info::InstrumentInfo;
-use crate::pricing_engines::npv_result::NpvResult;
+use crate::pricing_engines::{
+    bond_analytics::BondAnalytics, bond_futures_pricer::BondFuturesAnalytics,
+    credit_default_swap_pricer::CdsAnalytics, ktbf_pricer::KtbfAnalytics, npv_result::NpvResult,
+};
 use crate::utils::number_format::{formatted_number, write_number_with_commas};
 use anyhow::{anyhow, Result};
 use ndarray::Array2;
@@ -33,12 +36,20 @@ pub struct CalculationResult {
     vega: Option<HashMap<String, Real>>,
     vega_strucure: Option<HashMap<String, Vec<Real>>>, // underlying code -> Vec::<Real> on vega_tenor in CalculationConfiguration
     vega_matrix: Option<HashMap<String, Array2<Real>>>, // underlying code -> Vec<Vec<Real>> vega_matrix
+    normal_vega: Option<HashMap<String, Real>>,         // rate index code -> normal vega
+    vanna: Option<HashMap<String, Real>>, // underlying code -> vanna (analytic greeks only)
+    volga: Option<HashMap<String, Real>>, // underlying code -> volga (analytic greeks only)
     theta: Option<Real>,
     div_delta: Option<HashMap<String, Real>>,
     div_structure: Option<HashMap<String, Vec<Real>>>, // underlying code -> Vec::<Real> on div_tenor in CalculationConfiguration
     rho: Option<HashMap<String, Real>>,                // Curve Code -> rho
     rho_structure: Option<HashMap<String, Vec<Real>>>, // curve code -> Vec::<Real> on rho_tenor in CalculationConfig
+    cs01_structure: Option<HashMap<String, Vec<Real>>>, // credit spread curve code -> Vec::<Real> on rho_tenor in CalculationConfig
     theta_day: Option<Integer>,
+    bond_analytics: Option<BondAnalytics>,
+    cds_analytics: Option<CdsAnalytics>,
+    bond_futures_analytics: Option<BondFuturesAnalytics>,
+    ktbf_analytics: Option<KtbfAnalytics>,
     #[serde(skip)]
     cashflows: Option<HashMap<OffsetDateTime, Real>>, //expected cashflow inbetween
     representation_currency: Option<Currency>,
@@ -107,6 +118,28 @@ impl std::fmt::Debug for CalculationResult {
             writeln!(f)?;
         }
 
+        if let Some(ref normal_vega) = self.normal_vega {
+            writeln!(f, " * normal_vega: ")?;
+            for (key, value) in normal_vega {
+                write!(f, "        {}: ", key)?;
+                write_number_with_commas(f, *value)?;
+                writeln!(f)?;
+            }
+            writeln!(f)?;
+        }
+
+        for (name, greek) in [("vanna", &self.vanna), ("volga", &self.volga)] {
+            if let Some(greek) = greek {
+                writeln!(f, " * {}: ", name)?;
+                for (key, value) in greek {
+                    write!(f, "        {}: ", key)?;
+                    write_number_with_commas(f, *value)?;
+                    writeln!(f)?;
+                }
+                writeln!(f)?;
+            }
+        }
+
         if let Some(ref vega_structure) = self.vega_strucure {
             writeln!(f, " * vega_structure: ")?;
             for (key, value) in vega_structure {
@@ -151,6 +184,23 @@ impl std::fmt::Debug for CalculationResult {
             writeln!(f)?;
         }
 
+        if let Some(ref cs01_structure) = self.cs01_structure {
+            writeln!(f, " * cs01_structure: ")?;
+            for (key, value) in cs01_structure {
+                let vector_sum = value.iter().sum::<Real>();
+                write!(f, "        {} (sum = ", key)?;
+                write_number_with_commas(f, vector_sum)?;
+                write!(f, "): ")?;
+
+                for v in value {
+                    write_number_with_commas(f, *v)?;
+                    write!(f, " | ")?;
+                }
+                writeln!(f)?;
+            }
+            writeln!(f)?;
+        }
+
         if let Some(div_delta) = self.div_delta.as_ref() {
             writeln!(f, " * div_delta: ")?;
             for (key, value) in div_delta {
@@ -199,6 +249,18 @@ impl std::fmt::Debug for CalculationResult {
             }
             writeln!(f)?;
         }
+        if let Some(ref analytics) = self.bond_analytics {
+            writeln!(f, " * bond_analytics: {:?}\n", analytics)?;
+        }
+        if let Some(ref analytics) = self.cds_analytics {
+            writeln!(f, " * cds_analytics: {:?}\n", analytics)?;
+        }
+        if let Some(ref analytics) = self.ktbf_analytics {
+            writeln!(f, " * ktbf_analytics: {:?}\n", analytics)?;
+        }
+        if let Some(ref analytics) = self.bond_futures_analytics {
+            writeln!(f, " * bond_futures_analytics: {:?}\n", analytics)?;
+        }
         if let Some(ref currency) = self.representation_currency {
             writeln!(f, " * representation_currency: {:?}", currency)?;
         }
@@ -226,12 +288,20 @@ impl CalculationResult {
             vega: None,
             vega_strucure: None,
             vega_matrix: None,
+            normal_vega: None,
+            vanna: None,
+            volga: None,
             theta: None,
             div_delta: None,
             div_structure: None,
             rho: None,
             rho_structure: None,
+            cs01_structure: None,
             theta_day: None,
+            bond_analytics: None,
+            cds_analytics: None,
+            bond_futures_analytics: None,
+            ktbf_analytics: None,
             cashflows: None,
             representation_currency: Some(representation_currency),
         }
@@ -309,6 +379,19 @@ impl CalculationResult {
         }
     }
 
+    pub fn set_single_normal_vega(&mut self, rate_index_code: &str, v: Real) {
+        match &mut self.normal_vega {
+            None => {
+                let mut normal_vega = HashMap::new();
+                normal_vega.insert(rate_index_code.to_owned(), v);
+                self.normal_vega = Some(normal_vega);
+            }
+            Some(normal_vega) => {
+                normal_vega.insert(rate_index_code.to_owned(), v);
+            }
+        }
+    }
+
     pub fn set_single_vega(&mut self, und_code: &str, v: Real) {
         match &mut self.vega {
             None => {
@@ -322,6 +405,18 @@ impl CalculationResult {
         }
     }
 
+    pub fn set_single_vanna(&mut self, und_code: &str, v: Real) {
+        self.vanna
+            .get_or_insert_with(HashMap::new)
+            .insert(und_code.to_owned(), v);
+    }
+
+    pub fn set_single_volga(&mut self, und_code: &str, v: Real) {
+        self.volga
+            .get_or_insert_with(HashMap::new)
+            .insert(und_code.to_owned(), v);
+    }
+
     pub fn set_single_rho(&mut self, curve_code: &str, v: Real) {
         match &mut self.rho {
             None => {
@@ -335,6 +430,19 @@ impl CalculationResult {
         }
     }
 
+    pub fn set_single_cs01_structure(&mut self, curve_code: &str, cs01_structure: Vec<Real>) {
+        match &mut self.cs01_structure {
+            None => {
+                let mut cs01_structure_map = HashMap::new();
+                cs01_structure_map.insert(curve_code.to_owned(), cs01_structure);
+                self.cs01_structure = Some(cs01_structure_map);
+            }
+            Some(cs01_structure_map) => {
+                cs01_structure_map.insert(curve_code.to_owned(), cs01_structure);
+            }
+        }
+    }
+
     pub fn set_single_rho_structure(&mut self, curve_code: &str, rho_structure: Vec<Real>) {
         match &mut self.rho_structure {
             None => {
@@ -386,6 +494,38 @@ impl CalculationResult {
         self.cashflows = Some(cashflows);
     }
 
+    pub fn set_bond_analytics(&mut self, bond_analytics: BondAnalytics) {
+        self.bond_analytics = Some(bond_analytics);
+    }
+
+    pub fn get_bond_analytics(&self) -> Option<&BondAnalytics> {
+        self.bond_analytics.as_ref()
+    }
+
+    pub fn set_cds_analytics(&mut self, cds_analytics: CdsAnalytics) {
+        self.cds_analytics = Some(cds_analytics);
+    }
+
+    pub fn get_cds_analytics(&self) -> Option<&CdsAnalytics> {
+        self.cds_analytics.as_ref()
+    }
+
+    pub fn set_ktbf_analytics(&mut self, ktbf_analytics: KtbfAnalytics) {
+        self.ktbf_analytics = Some(ktbf_analytics);
+    }
+
+    pub fn get_ktbf_analytics(&self) -> Option<&KtbfAnalytics> {
+        self.ktbf_analytics.as_ref()
+    }
+
+    pub fn set_bond_futures_analytics(&mut self, bond_futures_analytics: BondFuturesAnalytics) {
+        self.bond_futures_analytics = Some(bond_futures_analytics);
+    }
+
+    pub fn get_bond_futures_analytics(&self) -> Option<&BondFuturesAnalytics> {
+        self.bond_futures_analytics.as_ref()
+    }
+
     pub fn get_instrument_info(&self) -> Option<&InstrumentInfo> {
         self.instrument_info.as_ref()
     }
@@ -410,6 +550,20 @@ impl CalculationResult {
         self.vega.as_ref()
     }
 
+    pub fn get_normal_vega(&self) -> Option<&HashMap<String, Real>> {
+        self.normal_vega.as_ref()
+    }
+
+    /// d^2V/dSdvol on (S * DELTA_PNL_UNIT) and VEGA_PNL_UNIT, considering unit_notional
+    pub fn get_vanna(&self) -> Option<&HashMap<String, Real>> {
+        self.vanna.as_ref()
+    }
+
+    /// 0.5 * d^2V/dvol^2 on VEGA_PNL_UNIT^2, considering unit_notional, as gamma
+    pub fn get_volga(&self) -> Option<&HashMap<String, Real>> {
+        self.volga.as_ref()
+    }
+
     pub fn get_vega_structure(&self) -> Option<&HashMap<String, Vec<Real>>> {
         self.vega_strucure.as_ref()
     }
@@ -430,6 +584,10 @@ impl CalculationResult {
         self.rho_structure.as_ref()
     }
 
+    pub fn get_cs01_structure(&self) -> Option<&HashMap<String, Vec<Real>>> {
+        self.cs01_structure.as_ref()
+    }
+
     pub fn get_cashflows(&self) -> Option<&HashMap<OffsetDateTime, Real>> {
         self.cashflows.as_ref()
     }
@@ -537,6 +695,28 @@ impl CalculationResult {
             }
             None => None,
         };
+        let normal_vega: Option<HashMap<String, Real>> = match &self.normal_vega {
+            Some(normal_vega) => {
+                let mut new_normal_vega = HashMap::new();
+                for (rate_index_code, v) in normal_vega {
+                    new_normal_vega.insert(rate_index_code.clone(), v * fx_rate);
+                }
+                Some(new_normal_vega)
+            }
+            None => None,
+        };
+        let vanna: Option<HashMap<String, Real>> = self.vanna.as_ref().map(|vanna| {
+            vanna
+                .iter()
+                .map(|(und_code, v)| (und_code.clone(), v * fx_rate))
+                .collect()
+        });
+        let volga: Option<HashMap<String, Real>> = self.volga.as_ref().map(|volga| {
+            volga
+                .iter()
+                .map(|(und_code, v)| (und_code.clone(), v * fx_rate))
+                .collect()
+        });
 
         let theta: Option<Real> = self.theta.map(|x| x * fx_rate);
         let div_delta: Option<HashMap<String, Real>> = match &self.div_delta {
@@ -581,7 +761,25 @@ impl CalculationResult {
             }
             None => None,
         };
+        let cs01_structure: Option<HashMap<String, Vec<Real>>> = match &self.cs01_structure {
+            Some(cs01_structure) => {
+                let mut new_cs01_structure = HashMap::new();
+                for (curve_code, v) in cs01_structure {
+                    let new_v = v.iter().map(|x| x * fx_rate).collect();
+                    new_cs01_structure.insert(curve_code.clone(), new_v);
+                }
+                Some(new_cs01_structure)
+            }
+            None => None,
+        };
         let theta_day: Option<Integer> = self.theta_day;
+        // bond, cds and bond futures analytics are per unit notional, so they do not depend on the currency
+        let bond_analytics: Option<BondAnalytics> = self.bond_analytics.clone();
+        let cds_analytics: Option<CdsAnalytics> = self.cds_analytics.clone();
+        let bond_futures_analytics: Option<BondFuturesAnalytics> =
+            self.bond_futures_analytics.clone();
+        // ktbf analytics are on the quoted price and in KRW as traded
+        let ktbf_analytics: Option<KtbfAnalytics> = self.ktbf_analytics.clone();
         let cashflows: Option<HashMap<OffsetDateTime, Real>> = self.cashflows.clone();
         let representation_currency: Option<Currency> = Some(currency);
 
@@ -596,12 +794,20 @@ impl CalculationResult {
             vega,
             vega_strucure,
             vega_matrix,
+            normal_vega,
+            vanna,
+            volga,
             theta,
             div_delta,
             div_structure,
             rho,
             rho_structure,
+            cs01_structure,
             theta_day,
+            bond_analytics,
+            cds_analytics,
+            bond_futures_analytics,
+            ktbf_analytics,
             cashflows,
             representation_currency,
         };
@@ -0,0 +1,491 @@
+use crate::definitions::{Real, Time};
+use crate::enums::ProtectionSide;
+use crate::evaluation_date::EvaluationDate;
+use crate::instrument::{Instrument, InstrumentTrait};
+use crate::instruments::{credit_default_swap::CreditDefaultSwap, schedule::cds_standard_maturity};
+use crate::parameters::{survival_curve::SurvivalCurve, zero_curve::ZeroCurve};
+use crate::pricing_engines::{npv_result::NpvResult, pricer::PricerTrait};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, rc::Rc};
+use time::OffsetDateTime;
+
+const CS01_BUMP: Real = 0.0001;
+const RECOVERY_BUMP: Real = 0.01;
+
+/// The legs of a CDS per unit notional and unit coupon, discounted to the evaluation date.
+/// * protection_leg: (1 - recovery) paid on default until the maturity
+/// * risky_annuity: the premiums with the accrued premium on default, i.e., the dirty RPV01
+/// * accrual_fraction: the accrued fraction of the current premium period at the evaluation date
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct CdsLegs {
+    protection_leg: Real,
+    risky_annuity: Real,
+    accrual_fraction: Real,
+}
+
+impl CdsLegs {
+    pub fn get_protection_leg(&self) -> Real {
+        self.protection_leg
+    }
+
+    pub fn get_risky_annuity(&self) -> Real {
+        self.risky_annuity
+    }
+
+    pub fn get_accrual_fraction(&self) -> Real {
+        self.accrual_fraction
+    }
+
+    /// the coupon with which the clean value of the CDS is zero
+    pub fn get_par_spread(&self) -> Real {
+        self.protection_leg / (self.risky_annuity - self.accrual_fraction)
+    }
+}
+
+/// Value the legs of the CDS by the ISDA standard model,
+/// i.e., the hazard rate and the forward rate are flat in between the nodes of the curves and the premium dates
+/// so that the default integrals are given in closed forms.
+pub fn cds_legs(
+    evaluation_date: &OffsetDateTime,
+    cds: &CreditDefaultSwap,
+    discount_curve: &ZeroCurve,
+    survival_curve: &SurvivalCurve,
+) -> Result<CdsLegs> {
+    let time_calculator = NullCalendar::default();
+    let calendar = cds.get_calendar()?;
+    let maturity = cds.get_maturity().ok_or_else(|| {
+        anyhow!(
+            "({}:{}) {} has no maturity",
+            file!(),
+            line!(),
+            cds.get_code()
+        )
+    })?;
+    let maturity_time = time_calculator.get_time_difference(evaluation_date, maturity);
+    if maturity_time <= 0.0 {
+        return Ok(CdsLegs {
+            protection_leg: 0.0,
+            risky_annuity: 0.0,
+            accrual_fraction: 0.0,
+        });
+    }
+
+    let schedule = cds.get_schedule()?;
+    // nodes of the default integrals: the pillars of the survival curve and the premium dates
+    let mut nodes: Vec<Time> = vec![0.0, maturity_time];
+    nodes.extend(
+        survival_curve
+            .get_pillar_times()
+            .iter()
+            .filter(|t| **t > 0.0 && **t < maturity_time),
+    );
+    for base_schedule in schedule.iter() {
+        let t =
+            time_calculator.get_time_difference(evaluation_date, base_schedule.get_calc_end_date());
+        if t > 0.0 && t < maturity_time {
+            nodes.push(t);
+        }
+    }
+    nodes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    nodes.dedup_by(|a, b| (*a - *b).abs() < 1.0e-6);
+
+    let discount_factors = discount_curve
+        .get_vectorized_discount_factor_for_sorted_time(&nodes.iter().copied().collect())?;
+    let survival_probabilities = nodes
+        .iter()
+        .map(|t| survival_curve.get_survival_probability(*t))
+        .collect::<Vec<Real>>();
+    // (hazard rate, hazard + forward rate) on each interval
+    let rates = (0..nodes.len() - 1)
+        .map(|i| {
+            let dt = nodes[i + 1] - nodes[i];
+            let hazard = (survival_probabilities[i] / survival_probabilities[i + 1]).ln() / dt;
+            let forward = (discount_factors[i] / discount_factors[i + 1]).ln() / dt;
+            (hazard, hazard + forward)
+        })
+        .collect::<Vec<(Real, Real)>>();
+
+    let mut protection_leg = 0.0;
+    for i in 0..nodes.len() - 1 {
+        let (hazard, k) = rates[i];
+        let dt = nodes[i + 1] - nodes[i];
+        let qd_start = survival_probabilities[i] * discount_factors[i];
+        protection_leg += match k.abs() < 1.0e-8 {
+            true => hazard * qd_start * dt,
+            false => {
+                hazard / k * (qd_start - survival_probabilities[i + 1] * discount_factors[i + 1])
+            }
+        };
+    }
+    protection_leg *= 1.0 - cds.get_recovery_rate();
+
+    let mut risky_annuity = 0.0;
+    let mut accrual_fraction = 0.0;
+    for base_schedule in schedule.iter() {
+        let start_date = base_schedule.get_calc_start_date();
+        let end_date = base_schedule.get_calc_end_date();
+        let payment_date = base_schedule.get_payment_date();
+        if end_date.date() <= evaluation_date.date() {
+            continue;
+        }
+        let frac = calendar.year_fraction(start_date, end_date, cds.get_daycounter())?;
+        if start_date.date() < evaluation_date.date() {
+            accrual_fraction =
+                calendar.year_fraction(start_date, evaluation_date, cds.get_daycounter())?;
+        }
+        let start_time = time_calculator.get_time_difference(evaluation_date, start_date);
+        let end_time = time_calculator.get_time_difference(evaluation_date, end_date);
+        let payment_time = time_calculator.get_time_difference(evaluation_date, payment_date);
+        risky_annuity += frac
+            * survival_curve.get_survival_probability(end_time)
+            * discount_curve.get_discount_factor(payment_time)?;
+
+        // accrued premium paid on default: frac / (end_time - start_time) per unit time from the period start
+        let accrual_rate = frac / (end_time - start_time);
+        for i in 0..nodes.len() - 1 {
+            if nodes[i] < start_time.max(0.0) - 1.0e-6 || nodes[i + 1] > end_time + 1.0e-6 {
+                continue;
+            }
+            let (hazard, k) = rates[i];
+            let dt = nodes[i + 1] - nodes[i];
+            let t0 = nodes[i] - start_time;
+            let integral = match k.abs() < 1.0e-8 {
+                true => t0 * dt + 0.5 * dt * dt,
+                false => {
+                    let decay = (-k * dt).exp();
+                    t0 * (1.0 - decay) / k + (1.0 - decay * (1.0 + k * dt)) / (k * k)
+                }
+            };
+            risky_annuity +=
+                accrual_rate * hazard * survival_probabilities[i] * discount_factors[i] * integral;
+        }
+    }
+
+    Ok(CdsLegs {
+        protection_leg,
+        risky_annuity,
+        accrual_fraction,
+    })
+}
+
+/// Analytics of a CDS position per unit notional. The values are of the side of the CDS.
+/// * upfront: clean value, i.e., the points upfront paid to enter the position
+/// * accrued_premium: premium accrued in the current period at the evaluation date
+/// * par_spread: the coupon with which the upfront is zero
+/// * risky_annuity: clean RPV01
+/// * cs01: change of the value when the par spread curve is bumped by 1bp in parallel
+/// * recovery_sensitivity: change of the value when the recovery rate is bumped by 1%
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CdsAnalytics {
+    upfront: Real,
+    accrued_premium: Real,
+    par_spread: Real,
+    risky_annuity: Real,
+    cs01: Real,
+    recovery_sensitivity: Real,
+}
+
+impl CdsAnalytics {
+    pub fn get_upfront(&self) -> Real {
+        self.upfront
+    }
+
+    pub fn get_accrued_premium(&self) -> Real {
+        self.accrued_premium
+    }
+
+    pub fn get_par_spread(&self) -> Real {
+        self.par_spread
+    }
+
+    pub fn get_risky_annuity(&self) -> Real {
+        self.risky_annuity
+    }
+
+    pub fn get_cs01(&self) -> Real {
+        self.cs01
+    }
+
+    pub fn get_recovery_sensitivity(&self) -> Real {
+        self.recovery_sensitivity
+    }
+}
+
+/// CDS pricer on the ISDA standard model.
+/// The survival curve is bootstrapped from spread_curve whose zero rates are the par spreads of the standard CDS
+/// maturing at the input times of the curve (rounded to the nearest month), with the recovery rate of the CDS priced.
+pub struct CreditDefaultSwapPricer {
+    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    discount_curve: Rc<RefCell<ZeroCurve>>,
+    spread_curve: Rc<RefCell<ZeroCurve>>,
+}
+
+impl CreditDefaultSwapPricer {
+    pub fn new(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        discount_curve: Rc<RefCell<ZeroCurve>>,
+        spread_curve: Rc<RefCell<ZeroCurve>>,
+    ) -> CreditDefaultSwapPricer {
+        CreditDefaultSwapPricer {
+            evaluation_date,
+            discount_curve,
+            spread_curve,
+        }
+    }
+
+    fn get_cds<'a>(&self, instrument: &'a Instrument) -> Result<&'a CreditDefaultSwap> {
+        match instrument {
+            Instrument::CreditDefaultSwap(cds) => Ok(cds),
+            _ => Err(anyhow!(
+                "({}:{}) CreditDefaultSwapPricer does not support {} ({})",
+                file!(),
+                line!(),
+                instrument.get_name(),
+                instrument.get_code()
+            )),
+        }
+    }
+
+    pub fn survival_curve(
+        &self,
+        cds: &CreditDefaultSwap,
+        spread_curve: &ZeroCurve,
+    ) -> Result<SurvivalCurve> {
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let mut quotes: Vec<(CreditDefaultSwap, Real)> = vec![];
+        let mut previous_maturity: Option<OffsetDateTime> = None;
+        for t in spread_curve.get_input_times().iter() {
+            let months = ((t * 12.0).round() as i64).max(1);
+            let maturity = cds_standard_maturity(&eval_date, &format!("{}M", months));
+            // the short tenors may have matured since the last roll
+            if maturity <= eval_date || previous_maturity == Some(maturity) {
+                continue;
+            }
+            previous_maturity = Some(maturity);
+            let pillar = CreditDefaultSwap::new_from_conventions(
+                ProtectionSide::Buyer,
+                0.0,
+                cds.get_recovery_rate(),
+                cds.get_reference_entity().clone(),
+                1.0,
+                eval_date,
+                maturity,
+                cds.get_calendar()?.clone(),
+                *cds.get_currency(),
+                format!("{} {}M", spread_curve.get_name_clone(), months),
+                format!("{} {}M", spread_curve.get_code(), months),
+            )?;
+            quotes.push((pillar, spread_curve.get_zero_rate(*t)?));
+        }
+
+        SurvivalCurve::bootstrap(
+            self.evaluation_date.clone(),
+            &self.discount_curve.borrow(),
+            &quotes,
+            cds.get_recovery_rate(),
+            spread_curve.get_name_clone(),
+            spread_curve.get_code().clone(),
+        )
+        .with_context(|| {
+            anyhow!(
+                "({}:{}) failed to bootstrap the survival curve of {} for {} ({})",
+                file!(),
+                line!(),
+                cds.get_reference_entity(),
+                cds.get_name(),
+                cds.get_code()
+            )
+        })
+    }
+
+    fn side_sign(cds: &CreditDefaultSwap) -> Real {
+        match cds.get_side() {
+            ProtectionSide::Buyer => 1.0,
+            ProtectionSide::Seller => -1.0,
+        }
+    }
+
+    /// dirty value of the position per unit notional
+    fn value(&self, cds: &CreditDefaultSwap, spread_curve: &ZeroCurve) -> Result<Real> {
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let survival_curve = self.survival_curve(cds, spread_curve)?;
+        let legs = cds_legs(
+            &eval_date,
+            cds,
+            &self.discount_curve.borrow(),
+            &survival_curve,
+        )?;
+        Ok(Self::side_sign(cds)
+            * (legs.get_protection_leg() - cds.get_coupon_rate() * legs.get_risky_annuity()))
+    }
+
+    pub fn analytics(&self, instrument: &Instrument) -> Result<CdsAnalytics> {
+        let cds = self.get_cds(instrument)?;
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let spread_curve = self.spread_curve.borrow().clone();
+        let sign = Self::side_sign(cds);
+        let coupon_rate = cds.get_coupon_rate();
+
+        let survival_curve = self.survival_curve(cds, &spread_curve)?;
+        let legs = cds_legs(
+            &eval_date,
+            cds,
+            &self.discount_curve.borrow(),
+            &survival_curve,
+        )?;
+        let value = sign * (legs.get_protection_leg() - coupon_rate * legs.get_risky_annuity());
+        let accrued_premium = coupon_rate * legs.get_accrual_fraction();
+
+        let mut bumped_curve = spread_curve.clone();
+        bumped_curve.bump_time_interval(None, None, CS01_BUMP)?;
+        let cs01 = self.value(cds, &bumped_curve)? - value;
+
+        let bumped_cds =
+            cds.with_coupon_and_recovery(coupon_rate, cds.get_recovery_rate() + RECOVERY_BUMP)?;
+        let recovery_sensitivity = self.value(&bumped_cds, &spread_curve)? - value;
+
+        Ok(CdsAnalytics {
+            upfront: value + sign * accrued_premium,
+            accrued_premium,
+            par_spread: legs.get_par_spread(),
+            risky_annuity: legs.get_risky_annuity() - legs.get_accrual_fraction(),
+            cs01,
+            recovery_sensitivity,
+        })
+    }
+}
+
+impl PricerTrait for CreditDefaultSwapPricer {
+    fn npv(&self, instrument: &Instrument) -> Result<Real> {
+        let cds = self.get_cds(instrument)?;
+        self.value(cds, &self.spread_curve.borrow())
+    }
+
+    fn npv_result(&self, instrument: &Instrument) -> Result<NpvResult> {
+        let npv = self.npv(instrument)?;
+        Ok(NpvResult::new_from_npv(npv))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::data::vector_data::VectorData;
+    use crate::instruments::schedule::{next_cds_date, previous_cds_date};
+    use crate::time::{
+        calendar::Calendar,
+        calendars::unitedstates::{UnitedStates, UnitedStatesType},
+        jointcalendar::JointCalendar,
+    };
+    use ndarray::{array, Array1};
+    use time::macros::datetime;
+
+    #[test]
+    fn test_credit_default_swap_pricer() -> Result<()> {
+        let dt = datetime!(2024-05-02 16:00:00 -05:00);
+        assert_eq!(
+            previous_cds_date(&dt),
+            datetime!(2024-03-20 16:00:00 -05:00)
+        );
+        assert_eq!(next_cds_date(&dt), datetime!(2024-06-20 16:00:00 -05:00));
+        assert_eq!(
+            cds_standard_maturity(&dt, "5Y"),
+            datetime!(2029-06-20 16:00:00 -05:00)
+        );
+
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(dt)));
+        let curve = |times: Array1<Time>, rates: Array1<Real>, name: &str| {
+            let data = VectorData::new(
+                rates,
+                None,
+                Some(times),
+                None,
+                Currency::USD,
+                name.to_string(),
+                name.to_string(),
+            )?;
+            Ok::<_, anyhow::Error>(Rc::new(RefCell::new(ZeroCurve::new(
+                evaluation_date.clone(),
+                &data,
+                name.to_string(),
+                name.to_string(),
+            )?)))
+        };
+        let discount_curve = curve(array![0.5, 30.0], array![0.045, 0.045], "USDSOFR")?;
+        let spread_curve = curve(
+            array![1.0, 3.0, 5.0, 7.0, 10.0],
+            array![0.006, 0.009, 0.012, 0.0135, 0.015],
+            "ACME CDS",
+        )?;
+        let pricer =
+            CreditDefaultSwapPricer::new(evaluation_date.clone(), discount_curve, spread_curve);
+
+        let calendar = JointCalendar::new(vec![Calendar::UnitedStates(UnitedStates::new(
+            UnitedStatesType::Settlement,
+        ))])?;
+        let cds = |side: ProtectionSide, coupon_rate: Real, tenor: &str| {
+            CreditDefaultSwap::new_from_conventions(
+                side,
+                coupon_rate,
+                0.4,
+                "ACME".to_string(),
+                10_000_000.0,
+                dt,
+                cds_standard_maturity(&dt, tenor),
+                calendar.clone(),
+                Currency::USD,
+                format!("ACME {}", tenor),
+                format!("ACME {}", tenor),
+            )
+            .map(Instrument::CreditDefaultSwap)
+        };
+
+        // a pillar CDS with the coupon of its quote is at par
+        let pillar = cds(ProtectionSide::Buyer, 0.012, "5Y")?;
+        let analytics = pricer.analytics(&pillar)?;
+        assert!(
+            (analytics.get_par_spread() - 0.012).abs() < 1.0e-6,
+            "par spread = {}",
+            analytics.get_par_spread()
+        );
+        assert!(
+            analytics.get_upfront().abs() < 1.0e-5,
+            "upfront = {}",
+            analytics.get_upfront()
+        );
+
+        // standard coupon of 100bp: the buyer pays the upfront
+        let buyer = cds(ProtectionSide::Buyer, 0.01, "5Y")?;
+        let seller = cds(ProtectionSide::Seller, 0.01, "5Y")?;
+        let buyer_analytics = pricer.analytics(&buyer)?;
+        let seller_analytics = pricer.analytics(&seller)?;
+        assert!(buyer_analytics.get_upfront() > 0.0);
+        assert!((buyer_analytics.get_upfront() + seller_analytics.get_upfront()).abs() < 1.0e-6);
+        assert!((pricer.npv(&buyer)? + pricer.npv(&seller)?).abs() < 1.0e-6);
+        // the dirty value is the clean value less the accrued premium paid by the buyer
+        assert!(
+            (pricer.npv(&buyer)? - buyer_analytics.get_upfront()
+                + buyer_analytics.get_accrued_premium())
+            .abs()
+                < 1.0e-6
+        );
+        // 1bp on the spreads is about 1bp times the risky annuity
+        let cs01 = buyer_analytics.get_cs01();
+        let approx = CS01_BUMP * buyer_analytics.get_risky_annuity();
+        assert!(
+            cs01 > 0.0 && (cs01 - approx).abs() < 0.1 * approx,
+            "cs01 = {}, risky annuity * 1bp = {}",
+            cs01,
+            approx
+        );
+        assert!((cs01 + seller_analytics.get_cs01()).abs() < 1.0e-6);
+        // with the spreads fixed, a higher recovery means a higher default probability
+        // which changes the value of the off-market coupon only slightly
+        assert!(buyer_analytics.get_recovery_sensitivity().abs() < 1.0e-4);
+        Ok(())
+    }
+}
@@ -1,24 +1,31 @@
 use crate::currency::{Currency, FxCode};
 use crate::definitions::{
-    Real, Time, DELTA_PNL_UNIT, DIV_PNL_UNIT, RHO_PNL_UNIT, THETA_PNL_UNIT, VEGA_PNL_UNIT,
+    Real, Time, DELTA_PNL_UNIT, DIV_PNL_UNIT, NORMAL_VEGA_PNL_UNIT, RHO_PNL_UNIT, THETA_PNL_UNIT,
+    VEGA_PNL_UNIT,
 };
+use crate::enums::VolatilitySurfaceType;
 use crate::evaluation_date::EvaluationDate;
 use crate::instrument::{Instrument, InstrumentTrait, Instruments};
 use crate::instruments::instrument_info::InstrumentInfo;
+use crate::parameters::volatilities::fx_volatility_surface::FxVolatilitySurface;
 use crate::parameters::volatilities::local_volatility_surface::LocalVolatilitySurface;
+use crate::parameters::volatilities::svi_volatility_surface::SviVolatilitySurface;
 use crate::parameters::{
     discrete_ratio_dividend::DiscreteRatioDividend, market_price::MarketPrice,
-    past_price::DailyClosePrice, quanto::Quanto,
+    past_price::DailyClosePrice, quanto::Quanto, rate_volatility::RateVolatility,
     volatilities::constant_volatility::ConstantVolatility, volatility::Volatility,
     zero_curve::ZeroCurve,
 };
 use tracing::{info, warn, Level};
 
 use crate::data::{
-    daily_value_data::DailyValueData, surface_data::SurfaceData, value_data::ValueData,
-    vector_data::VectorData,
+    daily_value_data::DailyValueData, fx_volatility_quote_data::FxVolatilityQuoteData,
+    rate_volatility_data::RateVolatilityData, surface_data::SurfaceData,
+    svi_parameter_data::SviParameterData, value_data::ValueData, vector_data::VectorData,
 };
 use crate::pricing_engines::{
+    analytic_greeks::AnalyticGreeks,
+    bond_analytics::BondAnalytics,
     calculation_configuration::CalculationConfiguration,
     calculation_result::CalculationResult,
     match_parameter::MatchParameter,
@@ -58,12 +65,24 @@ pub struct Engine {
     volatilities: HashMap<String, Rc<RefCell<Volatility>>>,
     quantos: HashMap<(String, FxCode), Rc<RefCell<Quanto>>>,
     past_daily_close_prices: HashMap<String, Rc<DailyClosePrice>>,
+    // SVI parameters given directly, which take precedence over the volatility data
+    svi_parameter_data: Arc<HashMap<String, SviParameterData>>,
+    equity_correlation_data: Arc<HashMap<(String, String), ValueData>>,
+    // rate index code -> volatility cube of caps, floors and swaptions
+    rate_volatility_data: Arc<HashMap<String, RateVolatilityData>>,
+    rate_volatilities: HashMap<String, Rc<RefCell<RateVolatility>>>,
+    // fx code -> ATM, 25-delta risk reversal and butterfly quotes of FX options
+    fx_volatility_quote_data: Arc<HashMap<FxCode, FxVolatilityQuoteData>>,
+    fx_volatilities: HashMap<FxCode, Rc<RefCell<Volatility>>>,
     // instruments
     instruments: Instruments,         // all instruments
     pricers: HashMap<String, Pricer>, // pricers for each instrument
     // selected instuments for calculation,
     // e.g., if we calcualte a delta of a single stock, we do not need calculate all instruments
     instruments_in_action: Vec<Rc<Instrument>>,
+    // analytic greeks on the base market, None if the pricer does not give them.
+    // They are calculated once in calculate and reused in the greeks of each underlying
+    analytic_greeks: RefCell<HashMap<String, Option<AnalyticGreeks>>>,
     match_parameter: Rc<MatchParameter>, // this must be cloned
 }
 
@@ -87,13 +106,62 @@ impl Engine {
             volatilities: HashMap::new(),
             quantos: HashMap::new(),
             past_daily_close_prices: HashMap::new(),
+            svi_parameter_data: Arc::new(HashMap::new()),
+            equity_correlation_data: Arc::new(HashMap::new()),
+            rate_volatility_data: Arc::new(HashMap::new()),
+            rate_volatilities: HashMap::new(),
+            fx_volatility_quote_data: Arc::new(HashMap::new()),
+            fx_volatilities: HashMap::new(),
             instruments: Instruments::default(),
             instruments_in_action: vec![],
             pricers: HashMap::new(),
+            analytic_greeks: RefCell::new(HashMap::new()),
             match_parameter: Rc::new(match_parameter),
         }
     }
 
+    /// SVI or SSVI parameters on the underlyings, which must be given before with_parameter_data.
+    /// The volatility of an underlying in svi_parameter_data is SviVolatilitySurface of the parameters
+    /// regardless of the volatility data in with_parameter_data.
+    pub fn with_svi_parameter_data(
+        mut self,
+        svi_parameter_data: Arc<HashMap<String, SviParameterData>>,
+    ) -> Engine {
+        self.svi_parameter_data = svi_parameter_data;
+        self
+    }
+
+    /// correlations between the underlyings of multi-asset instruments such as Autocallable.
+    /// The key is a pair of the underlying codes in any order
+    pub fn with_equity_correlation_data(
+        mut self,
+        equity_correlation_data: Arc<HashMap<(String, String), ValueData>>,
+    ) -> Engine {
+        self.equity_correlation_data = equity_correlation_data;
+        self
+    }
+
+    /// volatility cubes of the rate indices keyed by the rate index code,
+    /// which must be given before with_parameter_data
+    pub fn with_rate_volatility_data(
+        mut self,
+        rate_volatility_data: Arc<HashMap<String, RateVolatilityData>>,
+    ) -> Engine {
+        self.rate_volatility_data = rate_volatility_data;
+        self
+    }
+
+    /// FX volatility quotes keyed by the fx code, which must be given before with_parameter_data.
+    /// The volatility of FX options on an fx code in fx_volatility_quote_data is FxVolatilitySurface of the quotes,
+    /// and otherwise the constant volatility in fx_constant_volatility_data.
+    pub fn with_fx_volatility_quote_data(
+        mut self,
+        fx_volatility_quote_data: Arc<HashMap<FxCode, FxVolatilityQuoteData>>,
+    ) -> Engine {
+        self.fx_volatility_quote_data = fx_volatility_quote_data;
+        self
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn with_parameter_data(
         mut self,
@@ -170,12 +238,33 @@ impl Engine {
         for curve_name in all_curve_names {
             if curve_data.contains_key(curve_name) {
                 let data = curve_data.get(curve_name).unwrap();
-                let zero_curve = Rc::new(RefCell::new(ZeroCurve::new(
+                let mut zero_curve = ZeroCurve::new(
                     self.evaluation_date.clone(),
                     data,
                     curve_name.clone(),
                     curve_name.clone(),
-                )?));
+                )?
+                .with_interpolation_type(
+                    self.calculation_configuration
+                        .get_zero_curve_interpolation_type(),
+                )?;
+                // credit spread curves are layered on the risk-free curve which is listed before
+                if let Some(base_curve_name) = self
+                    .match_parameter
+                    .get_credit_spread_base_curve_name(curve_name)?
+                {
+                    let base_curve = zero_curves.get(base_curve_name).with_context(|| {
+                        anyhow!(
+                            "({}:{}) failed to get the risk-free curve {} of the credit spread curve {}",
+                            file!(),
+                            line!(),
+                            base_curve_name,
+                            curve_name
+                        )
+                    })?;
+                    zero_curve = zero_curve.with_base_curve(Rc::clone(base_curve));
+                }
+                let zero_curve = Rc::new(RefCell::new(zero_curve));
                 zero_curves.insert(curve_name.clone(), zero_curve.clone());
             } else {
                 bail!(
@@ -243,12 +332,18 @@ impl Engine {
         for und_code in all_underlying_codes {
             if curve_data.contains_key(und_code) {
                 let data = curve_data.get(und_code).unwrap();
-                let zero_curve = Rc::new(RefCell::new(ZeroCurve::new(
-                    self.evaluation_date.clone(),
-                    data,
-                    und_code.clone(),
-                    und_code.clone(),
-                )?));
+                let zero_curve = Rc::new(RefCell::new(
+                    ZeroCurve::new(
+                        self.evaluation_date.clone(),
+                        data,
+                        und_code.clone(),
+                        und_code.clone(),
+                    )?
+                    .with_interpolation_type(
+                        self.calculation_configuration
+                            .get_zero_curve_interpolation_type(),
+                    )?,
+                ));
                 zero_curves.insert(und_code.clone(), zero_curve.clone());
             } else {
                 bail!(
@@ -295,7 +390,59 @@ impl Engine {
             .instruments
             .get_all_unerlying_codes_requiring_volatility(None);
         for und_code in all_underlying_codes {
-            if equity_constant_volatility_data.contains_key(&und_code) {
+            if self.svi_parameter_data.contains_key(&und_code) {
+                let data = self.svi_parameter_data.get(&und_code).unwrap();
+                let market_price = equities
+                    .get(&und_code)
+                    .with_context(|| {
+                        anyhow!(
+                            "({}:{}) failed to get market price for {}",
+                            file!(),
+                            line!(),
+                            und_code
+                        )
+                    })?
+                    .clone();
+                let collateral_curve_map = self.match_parameter.get_collateral_curve_map()
+                    .get(&und_code)
+                    .with_context(|| anyhow!(
+                        "({}:{}) failed to get collateral curve map for {} from match_parameter in creating volatility surface",
+                        file!(), line!(), und_code))?;
+                let collateral_curve = zero_curves.get(collateral_curve_map)
+                    .with_context(|| anyhow!(
+                        "({}:{}) failed to get collateral curve for {} in creating volatility surface",
+                        file!(), line!(), und_code))?.clone();
+                let borrowing_curve_map = self.match_parameter.get_borrowing_curve_map()
+                    .get(&und_code)
+                    .with_context(|| anyhow!(
+                        "({}:{}) failed to get borrowing curve map for {} from match_parameter in creating volatility surface",
+                        file!(), line!(), und_code))?;
+                let borrowing_curve = zero_curves.get(borrowing_curve_map)
+                    .with_context(|| anyhow!(
+                        "({}:{}) failed to get borrowing curve for {} in creating volatility surface\n\
+                        zero curves list:\n {:?}",
+                        file!(), line!(), und_code,
+                        zero_curves.keys().map(|s| s.as_str()).collect::<Vec<&str>>().join(" | "),
+                    ))?.clone();
+                let stickyness = self.calculation_configuration.get_stickyness_type();
+                let mut svi = SviVolatilitySurface::initialize(
+                    self.evaluation_date.clone(),
+                    market_price,
+                    collateral_curve,
+                    borrowing_curve,
+                    stickyness,
+                    und_code.clone(),
+                    und_code.clone(),
+                )
+                .with_parameters(
+                    data.get_dates().clone(),
+                    data.get_spot(),
+                    data.get_parameters().clone(),
+                )?;
+                svi.build()?;
+                let rc = Rc::new(RefCell::new(Volatility::SviVolatilitySurface(svi)));
+                volatilities.insert(und_code.clone(), rc);
+            } else if equity_constant_volatility_data.contains_key(&und_code) {
                 let data = equity_constant_volatility_data.get(&und_code).unwrap();
                 let vega_matrix_spot_moneyness = self
                     .calculation_configuration
@@ -394,24 +541,44 @@ impl Engine {
                         zero_curves.keys().map(|s| s.as_str()).collect::<Vec<&str>>().join(" | "), 
                     ))?.clone();
                 let stickyness = self.calculation_configuration.get_stickyness_type();
-                let lv_interpolator = self.calculation_configuration.get_lv_interpolator();
-                let mut lv = LocalVolatilitySurface::initialize(
-                    self.evaluation_date.clone(),
-                    market_price,
-                    collateral_curve,
-                    borrowing_curve,
-                    stickyness,
-                    lv_interpolator,
-                    und_code.clone(),
-                    und_code.clone(),
-                )
-                .with_market_surface(
-                    data,
-                    vega_structure_tenors.clone(),
-                    vega_matrix_spot_moneyness.clone(),
-                )?;
-                lv.build()?;
-                let rc = Rc::new(RefCell::new(Volatility::LocalVolatilitySurface(lv)));
+                let surface_type = self.calculation_configuration.get_volatility_surface_type();
+                let volatility = match surface_type {
+                    VolatilitySurfaceType::LocalVolatility => {
+                        let lv_interpolator = self.calculation_configuration.get_lv_interpolator();
+                        let mut lv = LocalVolatilitySurface::initialize(
+                            self.evaluation_date.clone(),
+                            market_price,
+                            collateral_curve,
+                            borrowing_curve,
+                            stickyness,
+                            lv_interpolator,
+                            und_code.clone(),
+                            und_code.clone(),
+                        )
+                        .with_market_surface(
+                            data,
+                            vega_structure_tenors.clone(),
+                            vega_matrix_spot_moneyness.clone(),
+                        )?;
+                        lv.build()?;
+                        Volatility::LocalVolatilitySurface(lv)
+                    }
+                    VolatilitySurfaceType::RawSvi | VolatilitySurfaceType::Ssvi => {
+                        let mut svi = SviVolatilitySurface::initialize(
+                            self.evaluation_date.clone(),
+                            market_price,
+                            collateral_curve,
+                            borrowing_curve,
+                            stickyness,
+                            und_code.clone(),
+                            und_code.clone(),
+                        )
+                        .with_market_surface(data, surface_type)?;
+                        svi.build()?;
+                        Volatility::SviVolatilitySurface(svi)
+                    }
+                };
+                let rc = Rc::new(RefCell::new(volatility));
                 volatilities.insert(und_code.clone(), rc);
             } else {
                 bail!(
@@ -452,6 +619,50 @@ impl Engine {
             }
         }
         //
+        // fx volatility of FX options
+        let mut fx_option_volatilities = HashMap::new();
+        for fx_code in self.instruments.get_all_fxcodes_requiring_volatility() {
+            if let Some(data) = self.fx_volatility_quote_data.get(&fx_code) {
+                // the base currency curve is the same for all the instruments on fx_code
+                let instruments = self.instruments.instruments_with_fx_volatility(&fx_code);
+                let base_currency_curve_name = self
+                    .match_parameter
+                    .get_floating_crs_curve_name(&instruments[0])?;
+                let base_currency_curve = zero_curves.get(base_currency_curve_name)
+                    .with_context(|| anyhow!(
+                        "({}:{}) failed to get base currency curve {} for {} in creating fx volatility surface",
+                        file!(), line!(), base_currency_curve_name, fx_code))?
+                    .clone();
+                let surface = FxVolatilitySurface::new(
+                    self.evaluation_date.clone(),
+                    base_currency_curve,
+                    data,
+                    fx_code.to_string(),
+                    fx_code.to_string(),
+                )?;
+                let rc = Rc::new(RefCell::new(Volatility::FxVolatilitySurface(surface)));
+                fx_option_volatilities.insert(fx_code, rc);
+            } else if let Some(rc) = fx_volatilities.get(&fx_code) {
+                fx_option_volatilities.insert(fx_code, rc.clone());
+            } else if let Some(data) = fx_constant_volatility_data.get(&fx_code) {
+                let rc = Rc::new(RefCell::new(Volatility::ConstantVolatility(
+                    ConstantVolatility::new(
+                        data.get_value(),
+                        fx_code.to_string(),
+                        fx_code.to_string(),
+                    ),
+                )));
+                fx_option_volatilities.insert(fx_code, rc);
+            } else {
+                bail!(
+                    "({}:{}) failed to get fx volatility data for {}",
+                    file!(),
+                    line!(),
+                    fx_code
+                );
+            }
+        }
+        //
         // quanto parameter
         let mut quantos = HashMap::new();
         for (und_code, fxcode) in quanto_fx_und_pair {
@@ -495,6 +706,32 @@ impl Engine {
             past_daily_close_prices.insert(key.clone(), rc);
         }
 
+        // rate volatility parameter
+        let mut rate_volatilities = HashMap::new();
+        for rate_index_code in self
+            .instruments
+            .get_all_rate_index_codes_requiring_volatility()
+        {
+            let data = self
+                .rate_volatility_data
+                .get(&rate_index_code)
+                .with_context(|| {
+                    anyhow!(
+                        "({}:{}) failed to get rate volatility data for {}",
+                        file!(),
+                        line!(),
+                        rate_index_code
+                    )
+                })?;
+            let rate_volatility = RateVolatility::new(
+                self.evaluation_date.clone(),
+                data,
+                data.get_name().to_string(),
+                rate_index_code.clone(),
+            )?;
+            rate_volatilities.insert(rate_index_code, Rc::new(RefCell::new(rate_volatility)));
+        }
+
         self.fxs = fxs;
         self.equities = equities;
         self.zero_curves = zero_curves;
@@ -502,6 +739,8 @@ impl Engine {
         self.volatilities = volatilities;
         self.quantos = quantos;
         self.past_daily_close_prices = past_daily_close_prices;
+        self.rate_volatilities = rate_volatilities;
+        self.fx_volatilities = fx_option_volatilities;
 
         // add marketprice_observers
         for (_, fx) in self.fxs.iter() {
@@ -670,7 +909,10 @@ impl Engine {
             self.past_daily_close_prices.clone(),
             Rc::clone(&self.match_parameter),
             Rc::clone(&self.calculation_configuration),
-        );
+        )
+        .with_equity_correlation_data(self.equity_correlation_data.clone())
+        .with_rate_volatilities(self.rate_volatilities.clone())
+        .with_fx_volatilities(self.fx_volatilities.clone());
 
         for inst in inst_vec.iter() {
             let pricer = pricer_factory.create_pricer(inst).with_context(|| {
@@ -722,6 +964,53 @@ impl Engine {
         Ok(npvs)
     }
 
+    /// analytic greeks of instruments_in_action whose pricer gives them in closed form.
+    /// They are calculated on the first call, or taken from set_npv_results, and reused afterwards
+    pub fn get_analytic_greeks(&self) -> Result<HashMap<String, AnalyticGreeks>> {
+        let stickyness = self.calculation_configuration.get_stickyness_type();
+        let mut analytic_greeks = self.analytic_greeks.borrow_mut();
+        let mut greeks_map = HashMap::new();
+        for inst in &self.instruments_in_action {
+            let inst_code = inst.get_code();
+            if !analytic_greeks.contains_key(inst_code) {
+                let pricer = self.pricers.get(inst_code).with_context(|| {
+                    anyhow!(
+                        "({}:{}) <Engine::get_analytic_greeks> failed to get pricer for {}\n{}",
+                        file!(),
+                        line!(),
+                        inst_code,
+                        self.msg_tag
+                    )
+                })?;
+
+                let greeks = pricer.analytic_greeks(inst, stickyness).with_context(|| {
+                    anyhow!(
+                        "({}:{}) <Engine::get_analytic_greeks> failed to get analytic greeks for {}\n{}",
+                        file!(),
+                        line!(),
+                        inst_code,
+                        self.msg_tag
+                    )
+                })?;
+                analytic_greeks.insert(inst_code.clone(), greeks);
+            }
+
+            if let Some(Some(greeks)) = analytic_greeks.get(inst_code) {
+                greeks_map.insert(inst_code.clone(), greeks.clone());
+            }
+        }
+        Ok(greeks_map)
+    }
+
+    /// whether any greek which uses the analytic greeks is calculated
+    fn analytic_greeks_calculation(&self) -> bool {
+        let config = &self.calculation_configuration;
+        config.get_delta_calculation()
+            || config.get_theta_calculation()
+            || config.get_vega_calculation()
+            || config.get_rho_calculation()
+    }
+
     pub fn get_npv_results(&self) -> Result<HashMap<String, NpvResult>> {
         let mut npvs = HashMap::new();
         for inst in &self.instruments_in_action {
@@ -742,8 +1031,33 @@ impl Engine {
         Ok(npvs)
     }
 
+    /// The analytic greeks of the same calculation as the npv are kept for the greeks
     pub fn set_npv_results(&mut self) -> Result<()> {
-        let npvs = self.get_npv_results()?;
+        let npvs = match self.analytic_greeks_calculation() {
+            true => {
+                let stickyness = self.calculation_configuration.get_stickyness_type();
+                let mut npvs = HashMap::new();
+                for inst in &self.instruments_in_action {
+                    let inst_code = inst.get_code();
+                    let pricer = self.pricers.get(inst_code).with_context(|| {
+                        anyhow!(
+                            "({}:{}) <Engine::set_npv_results> failed to get pricer for {}\n{}",
+                            file!(),
+                            line!(),
+                            inst_code,
+                            self.msg_tag,
+                        )
+                    })?;
+                    let (npv, greeks) = pricer.npv_result_with_analytic_greeks(inst, stickyness)?;
+                    npvs.insert(inst_code.clone(), npv);
+                    self.analytic_greeks
+                        .borrow_mut()
+                        .insert(inst_code.clone(), greeks);
+                }
+                npvs
+            }
+            false => self.get_npv_results()?,
+        };
 
         for (code, result) in self.calculation_results.iter() {
             result.borrow_mut().set_npv(
@@ -819,103 +1133,304 @@ impl Engine {
         Ok(())
     }
 
-    pub fn preprocess_delta_gamma(&mut self) -> Result<()> {
-        let preprocess_types = vec!["Stock", "Futures"];
-        let insts = self.instruments.instruments_with_types(preprocess_types);
-
-        // set delta for values * DELTA_PNL_UNIT, gamma = 0.0
-        for inst in insts {
+    /// Set the yield-based analytics of the bonds on their npv and the cashflows in the npv results
+    pub fn set_bond_analytics(&mut self) -> Result<()> {
+        let yield_compounding = self.calculation_configuration.get_yield_compounding();
+        let eval_dt = self.evaluation_date.borrow().get_date_clone();
+        for inst in self.instruments_in_action.iter() {
+            let bond = match inst.as_ref() {
+                Instrument::Bond(bond) => bond,
+                _ => continue,
+            };
             let inst_code = inst.get_code();
-            let unitamt = inst.get_unit_notional();
-            let npv = self
-                .calculation_results
-                .get(inst_code)
-                .ok_or_else(|| {
-                    anyhow!(
-                        "({}:{}) result is not set in {} ({})",
-                        file!(),
-                        line!(),
-                        inst_code,
-                        inst.get_type_name(),
-                    )
-                })?
+            let result = self.calculation_results.get(inst_code).ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) result is not set in {}\n{}",
+                    file!(),
+                    line!(),
+                    inst_code,
+                    self.msg_tag
+                )
+            })?;
+            let npv_result = result
                 .borrow()
                 .get_npv_result()
                 .ok_or_else(|| {
                     anyhow!(
-                        "({}:{}) value is not set in {} ({})",
+                        "({}:{}) npv_result is not set for {}\n{}",
                         file!(),
                         line!(),
                         inst_code,
-                        inst.get_type_name(),
+                        self.msg_tag
                     )
                 })?
-                .get_npv();
-            let delta = npv * DELTA_PNL_UNIT * unitamt;
-            let gamma = 0.0;
-
-            (*self.calculation_results.get(inst_code).ok_or_else(|| {
-                anyhow!(
-                    "({}:{}) result is not set in {} ({})",
-                    file!(),
-                    line!(),
-                    inst_code,
-                    inst.get_type_name(),
-                )
-            })?)
-            .borrow_mut()
-            .set_single_delta(inst_code, delta);
-            (*self.calculation_results.get(inst_code).ok_or_else(|| {
+                .clone();
+            // BondPricer values the cashflows after the pricing date on the pricing date,
+            // so the npv is already the dirty price for the settlement on the pricing date
+            let pricing_date = bond.get_pricing_date()?.unwrap_or(&eval_dt);
+            let analytics = BondAnalytics::new(
+                bond,
+                pricing_date,
+                npv_result.get_npv(),
+                &npv_result.get_expected_coupon_amount()?,
+                yield_compounding,
+            )
+            .with_context(|| {
                 anyhow!(
-                    "({}:{}) result is not set in {} ({})",
+                    "({}:{}) failed to get bond analytics for {}\n{}",
                     file!(),
                     line!(),
                     inst_code,
-                    inst.get_type_name(),
+                    self.msg_tag
                 )
-            })?)
-            .borrow_mut()
-            .set_single_gamma(inst_code, gamma);
+            })?;
+            result.borrow_mut().set_bond_analytics(analytics);
         }
         Ok(())
     }
 
-    pub fn set_delta_gamma(&mut self) -> Result<()> {
-        self.reset_instruments_in_action();
-
-        let all_underlying_codes = self.instruments.get_all_underlying_codes();
-        let delta_bump_ratio = self.calculation_configuration.get_delta_bump_ratio();
-
-        let mut delta_up_map: HashMap<String, Real>;
-        let mut delta_down_map: HashMap<String, Real>;
-
-        let mut delta_up: Real;
-        let mut delta_down: Real;
-        let mut delta: Real;
-        let mut gamma: Real;
-        let mut mid: Real;
-        let mut original_price: Real;
-
-        let up_bump = 1.0 + delta_bump_ratio;
-        let down_bump = 1.0 - delta_bump_ratio;
-        let exclude_type = vec!["Stock", "Futures"];
-        let exclude_type_clone = exclude_type.clone();
-        for und_code in all_underlying_codes.iter() {
-            self.instruments_in_action = self
-                .instruments
-                .instruments_with_underlying(und_code, Some(exclude_type_clone.clone()));
-
-            if self.instruments_in_action.is_empty() {
+    pub fn set_cds_analytics(&mut self) -> Result<()> {
+        for inst in self.instruments_in_action.iter() {
+            if !matches!(inst.as_ref(), Instrument::CreditDefaultSwap(_)) {
                 continue;
             }
-
-            original_price = self
+            let inst_code = inst.get_code();
+            let analytics = match self.pricers.get(inst_code) {
+                Some(Pricer::CreditDefaultSwapPricer(pricer)) => pricer.analytics(inst)?,
+                _ => {
+                    return Err(anyhow!(
+                        "({}:{}) CreditDefaultSwapPricer is not set for {}\n{}",
+                        file!(),
+                        line!(),
+                        inst_code,
+                        self.msg_tag
+                    ))
+                }
+            };
+            self.calculation_results
+                .get(inst_code)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) result is not set in {}\n{}",
+                        file!(),
+                        line!(),
+                        inst_code,
+                        self.msg_tag
+                    )
+                })?
+                .borrow_mut()
+                .set_cds_analytics(analytics);
+        }
+        Ok(())
+    }
+
+    pub fn set_bond_futures_analytics(&mut self) -> Result<()> {
+        for inst in self.instruments_in_action.iter() {
+            if !matches!(inst.as_ref(), Instrument::BondFutures(_)) {
+                continue;
+            }
+            let inst_code = inst.get_code();
+            // the analytics are against the theoretical price of the futures
+            let analytics = match self.pricers.get(inst_code) {
+                Some(Pricer::BondFuturesPricer(pricer)) => {
+                    let futures_price = pricer.npv(inst)?;
+                    pricer.analytics(inst, futures_price)?
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "({}:{}) BondFuturesPricer is not set for {}\n{}",
+                        file!(),
+                        line!(),
+                        inst_code,
+                        self.msg_tag
+                    ))
+                }
+            };
+            self.calculation_results
+                .get(inst_code)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) result is not set in {}\n{}",
+                        file!(),
+                        line!(),
+                        inst_code,
+                        self.msg_tag
+                    )
+                })?
+                .borrow_mut()
+                .set_bond_futures_analytics(analytics);
+        }
+        Ok(())
+    }
+
+    pub fn set_ktbf_analytics(&mut self) -> Result<()> {
+        for inst in self.instruments_in_action.iter() {
+            if !matches!(inst.as_ref(), Instrument::KTBF(_)) {
+                continue;
+            }
+            let inst_code = inst.get_code();
+            // the analytics are against the theoretical price of the futures
+            let analytics = match self.pricers.get(inst_code) {
+                Some(Pricer::KtbfPricer(pricer)) => {
+                    let futures_price = pricer.npv(inst)?;
+                    pricer.analytics(inst, futures_price)?
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "({}:{}) KtbfPricer is not set for {}\n{}",
+                        file!(),
+                        line!(),
+                        inst_code,
+                        self.msg_tag
+                    ))
+                }
+            };
+            self.calculation_results
+                .get(inst_code)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) result is not set in {}\n{}",
+                        file!(),
+                        line!(),
+                        inst_code,
+                        self.msg_tag
+                    )
+                })?
+                .borrow_mut()
+                .set_ktbf_analytics(analytics);
+        }
+        Ok(())
+    }
+
+    pub fn preprocess_delta_gamma(&mut self) -> Result<()> {
+        let preprocess_types = vec!["Stock", "Futures"];
+        let insts = self.instruments.instruments_with_types(preprocess_types);
+
+        // set delta for values * DELTA_PNL_UNIT, gamma = 0.0
+        for inst in insts {
+            let inst_code = inst.get_code();
+            let unitamt = inst.get_unit_notional();
+            let npv = self
+                .calculation_results
+                .get(inst_code)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) result is not set in {} ({})",
+                        file!(),
+                        line!(),
+                        inst_code,
+                        inst.get_type_name(),
+                    )
+                })?
+                .borrow()
+                .get_npv_result()
+                .ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) value is not set in {} ({})",
+                        file!(),
+                        line!(),
+                        inst_code,
+                        inst.get_type_name(),
+                    )
+                })?
+                .get_npv();
+            let delta = npv * DELTA_PNL_UNIT * unitamt;
+            let gamma = 0.0;
+
+            (*self.calculation_results.get(inst_code).ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) result is not set in {} ({})",
+                    file!(),
+                    line!(),
+                    inst_code,
+                    inst.get_type_name(),
+                )
+            })?)
+            .borrow_mut()
+            .set_single_delta(inst_code, delta);
+            (*self.calculation_results.get(inst_code).ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) result is not set in {} ({})",
+                    file!(),
+                    line!(),
+                    inst_code,
+                    inst.get_type_name(),
+                )
+            })?)
+            .borrow_mut()
+            .set_single_gamma(inst_code, gamma);
+        }
+        Ok(())
+    }
+
+    pub fn set_delta_gamma(&mut self) -> Result<()> {
+        self.reset_instruments_in_action();
+
+        let all_underlying_codes = self.instruments.get_all_underlying_codes();
+        let delta_bump_ratio = self.calculation_configuration.get_delta_bump_ratio();
+
+        let mut delta_up_map: HashMap<String, Real>;
+        let mut delta_down_map: HashMap<String, Real>;
+
+        let mut delta_up: Real;
+        let mut delta_down: Real;
+        let mut delta: Real;
+        let mut gamma: Real;
+        let mut mid: Real;
+        let mut original_price: Real;
+
+        let up_bump = 1.0 + delta_bump_ratio;
+        let down_bump = 1.0 - delta_bump_ratio;
+        let exclude_type = vec!["Stock", "Futures"];
+        let exclude_type_clone = exclude_type.clone();
+        for und_code in all_underlying_codes.iter() {
+            self.instruments_in_action = self
+                .instruments
+                .instruments_with_underlying(und_code, Some(exclude_type_clone.clone()));
+
+            if self.instruments_in_action.is_empty() {
+                continue;
+            }
+
+            original_price = self
                 .equities
                 .get(*und_code)
                 .ok_or_else(|| anyhow!("there is no equity {}", und_code))?
                 .borrow()
                 .get_value();
 
+            // instruments with analytic greeks are not repriced
+            let analytic_greeks = self.get_analytic_greeks()?;
+            for inst in &self.instruments_in_action {
+                let inst_code = inst.get_code();
+                let Some(greeks) = analytic_greeks.get(inst_code) else {
+                    continue;
+                };
+                let unitamt = inst.get_unit_notional();
+                delta = greeks.get_delta() * original_price * DELTA_PNL_UNIT;
+                gamma = 0.5
+                    * greeks.get_gamma()
+                    * (original_price * DELTA_PNL_UNIT)
+                    * (original_price * DELTA_PNL_UNIT);
+
+                let mut result = (*self.calculation_results.get(inst_code).ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) result is not set for {}",
+                        file!(),
+                        line!(),
+                        inst_code,
+                    )
+                })?)
+                .borrow_mut();
+                result.set_single_delta(und_code, delta * unitamt);
+                result.set_single_gamma(und_code, gamma * unitamt);
+            }
+            self.instruments_in_action
+                .retain(|inst| !analytic_greeks.contains_key(inst.get_code()));
+            if self.instruments_in_action.is_empty() {
+                continue;
+            }
+
             // set instruments that needs to be calculated
             {
                 let mut equity = (*self.equities.get(*und_code).ok_or_else(|| {
@@ -964,19 +1479,350 @@ impl Engine {
                 .borrow_mut()
                 .set_single_delta(und_code, delta * unitamt);
 
-                mid = self
+                mid = self
+                    .calculation_results
+                    .get(inst.get_code())
+                    .ok_or_else(|| anyhow!("result is not set"))?
+                    .borrow()
+                    .get_npv_result()
+                    .ok_or_else(|| anyhow!("npv is not set"))?
+                    .get_npv();
+
+                gamma = delta_up - mid + delta_down - mid;
+                gamma *= DELTA_PNL_UNIT / delta_bump_ratio;
+                gamma *= 0.5 * (DELTA_PNL_UNIT / delta_bump_ratio);
+
+                (*self
+                    .calculation_results
+                    .get(inst.get_code())
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "({}:{}) result is not set for {}",
+                            file!(),
+                            line!(),
+                            inst.get_code(),
+                        )
+                    })?)
+                .borrow_mut()
+                .set_single_gamma(und_code, gamma * unitamt);
+            }
+
+            {
+                (*self.equities.get(*und_code).ok_or_else(|| {
+                    anyhow!("({}:{}) there is no stock {}", file!(), line!(), und_code)
+                })?)
+                .as_ref()
+                .borrow_mut()
+                .set_price(original_price);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// delta and gamma on the fx rates which are the underlying of the instruments, e.g., FX forwards.
+    /// They are the same as the equity delta and gamma, i.e., the PnL on 1% move of the fx rate,
+    /// keyed by the fx code (e.g., "USDKRW")
+    pub fn set_fx_delta_gamma(&mut self) -> Result<()> {
+        let delta_bump_ratio = self.calculation_configuration.get_delta_bump_ratio();
+        let up_bump = 1.0 + delta_bump_ratio;
+        let down_bump = 1.0 - delta_bump_ratio;
+
+        let all_fxcodes = self
+            .instruments
+            .get_all_underlying_fxcodes()
+            .into_iter()
+            .copied()
+            .collect::<Vec<FxCode>>();
+        for fx_code in all_fxcodes.iter() {
+            self.instruments_in_action =
+                self.instruments.instruments_with_underlying_fxcode(fx_code);
+            if self.instruments_in_action.is_empty() {
+                continue;
+            }
+            let fx = self
+                .fxs
+                .get(fx_code)
+                .ok_or_else(|| anyhow!("({}:{}) there is no fx {}", file!(), line!(), fx_code))?
+                .clone();
+            let original_price = fx.borrow().get_value();
+
+            *fx.borrow_mut() *= up_bump;
+            let delta_up_map = self.get_npvs().context("failed to get npvs")?;
+            fx.borrow_mut().set_price(original_price);
+            *fx.borrow_mut() *= down_bump;
+            let delta_down_map = self.get_npvs().context("failed to get npvs")?;
+            fx.borrow_mut().set_price(original_price);
+
+            let fx_str = fx_code.to_string();
+            for inst in &self.instruments_in_action {
+                let inst_code = inst.get_code();
+                let unitamt = inst.get_unit_notional();
+                let delta_up = *delta_up_map
+                    .get(inst_code)
+                    .ok_or_else(|| anyhow!("delta_up is not set"))?;
+                let delta_down = *delta_down_map
+                    .get(inst_code)
+                    .ok_or_else(|| anyhow!("delta_down is not set"))?;
+                let delta = (delta_up - delta_down) / (2.0 * delta_bump_ratio) * DELTA_PNL_UNIT;
+
+                let mut result = (*self.calculation_results.get(inst_code).ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) result is not set for {}",
+                        file!(),
+                        line!(),
+                        inst_code,
+                    )
+                })?)
+                .borrow_mut();
+                let mid = result
+                    .get_npv_result()
+                    .ok_or_else(|| anyhow!("npv is not set"))?
+                    .get_npv();
+                let mut gamma = delta_up - mid + delta_down - mid;
+                gamma *= DELTA_PNL_UNIT / delta_bump_ratio;
+                gamma *= 0.5 * (DELTA_PNL_UNIT / delta_bump_ratio);
+
+                result.set_single_delta(&fx_str, delta * unitamt);
+                result.set_single_gamma(&fx_str, gamma * unitamt);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn set_rho(&mut self) -> Result<()> {
+        let mut npvs_up: HashMap<String, Real>;
+        let all_curve_names = self
+            .instruments
+            .get_all_curve_names(&self.match_parameter)?;
+        let bump_val = self.calculation_configuration.get_rho_bump_value();
+        let exclude_type = vec!["Stock"];
+        let exclude_type_clone = exclude_type.clone();
+
+        for curve_name in all_curve_names {
+            // the sensitivity on credit spread curves is cs01, not rho
+            if self.match_parameter.is_credit_spread_curve(curve_name) {
+                continue;
+            }
+            self.instruments_in_action = self.instruments.instruments_using_curve(
+                curve_name,
+                &self.match_parameter,
+                Some(exclude_type_clone.clone()),
+            )?;
+            if self.instruments_in_action.is_empty() {
+                continue;
+            }
+
+            // instruments with analytic greeks are not repriced
+            let analytic_greeks = self.get_analytic_greeks()?;
+            for inst in &self.instruments_in_action {
+                let inst_code = inst.get_code();
+                let Some(rho) = analytic_greeks
+                    .get(inst_code)
+                    .and_then(|greeks| greeks.get_single_rho(curve_name))
+                else {
+                    continue;
+                };
+                let rho = rho * RHO_PNL_UNIT * inst.get_unit_notional();
+                (*self.calculation_results.get(inst_code).ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) result is not set for {}",
+                        file!(),
+                        line!(),
+                        inst_code,
+                    )
+                })?)
+                .borrow_mut()
+                .set_single_rho(curve_name, rho);
+            }
+            self.instruments_in_action.retain(|inst| {
+                analytic_greeks
+                    .get(inst.get_code())
+                    .is_none_or(|greeks| greeks.get_rho().is_none())
+            });
+            if self.instruments_in_action.is_empty() {
+                continue;
+            }
+
+            // bump the curve but limit the scope that the zero_curve ismutably borrowed
+            {
+                (*self.zero_curves.get(curve_name).with_context(|| {
+                    anyhow!(
+                        "({}:{}) no zero curve: {}\n{}",
+                        file!(),
+                        line!(),
+                        curve_name,
+                        self.msg_tag,
+                    )
+                })?)
+                .as_ref()
+                .borrow_mut()
+                .bump_time_interval(None, None, bump_val)?;
+            }
+
+            npvs_up = self.get_npvs().context("failed to get npvs")?;
+
+            for inst in &self.instruments_in_action {
+                let inst_code = inst.get_code();
+                let unitamt = inst.get_unit_notional();
+                let npv_up = npvs_up
+                    .get(inst_code)
+                    .ok_or_else(|| anyhow!("npv_up is not set"))?;
+                let npv = self
+                    .calculation_results
+                    .get(inst_code)
+                    .ok_or_else(|| anyhow!("result is not set"))?
+                    .borrow()
+                    .get_npv_result()
+                    .ok_or_else(|| anyhow!("npv is not set"))?
+                    .get_npv();
+
+                let rho = (npv_up - npv) / bump_val * RHO_PNL_UNIT * unitamt;
+                (*self
+                    .calculation_results
+                    .get(inst.get_code())
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "({}:{}) result is not set for {}",
+                            file!(),
+                            line!(),
+                            inst.get_code(),
+                        )
+                    })?)
+                .borrow_mut()
+                .set_single_rho(curve_name, rho);
+            }
+            // put back the bump value
+            {
+                (*self.zero_curves.get(curve_name).with_context(|| {
+                    anyhow!(
+                        "({}:{}) no zero curve: {}\n{}",
+                        file!(),
+                        line!(),
+                        curve_name,
+                        self.msg_tag,
+                    )
+                })?)
+                .as_ref()
+                .borrow_mut()
+                .bump_time_interval(None, None, -bump_val)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_vega(&mut self) -> Result<()> {
+        let mut npvs_up: HashMap<String, Real>;
+        let all_underlying_codes = self.instruments.get_all_underlying_codes();
+        let bump_val = self.calculation_configuration.get_vega_bump_value();
+        let mut npv: Real;
+        let exclude_type = vec!["Futures", "Stock"];
+        let exclude_type_clone = exclude_type.clone();
+        for vol_code in all_underlying_codes {
+            self.instruments_in_action = self
+                .instruments
+                .instruments_with_underlying(vol_code, Some(exclude_type_clone.clone()));
+
+            if self.instruments_in_action.is_empty() {
+                continue;
+            }
+
+            // instruments with analytic greeks are not repriced
+            let analytic_greeks = self.get_analytic_greeks()?;
+            for inst in &self.instruments_in_action {
+                let inst_code = inst.get_code();
+                let Some(greeks) = analytic_greeks.get(inst_code) else {
+                    continue;
+                };
+                let Some(vega) = greeks.get_vega() else {
+                    continue;
+                };
+                let unitamt = inst.get_unit_notional();
+                let vega = vega * VEGA_PNL_UNIT * unitamt;
+                let spot = self
+                    .equities
+                    .get(vol_code)
+                    .map(|equity| equity.borrow().get_value());
+                let mut result = (*self.calculation_results.get(inst_code).ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) result is not set for {}",
+                        file!(),
+                        line!(),
+                        inst_code
+                    )
+                })?)
+                .borrow_mut();
+                result.set_single_vega(vol_code, vega);
+                // on the same units as delta and gamma so that the P&L is
+                // delta * m + gamma * m^2 + vega * v + vanna * m * v + volga * v^2
+                if let Some(volga) = greeks.get_volga() {
+                    let volga = 0.5 * volga * VEGA_PNL_UNIT * VEGA_PNL_UNIT * unitamt;
+                    result.set_single_volga(vol_code, volga);
+                }
+                if let (Some(vanna), Some(spot)) = (greeks.get_vanna(), spot) {
+                    let vanna = vanna * spot * DELTA_PNL_UNIT * VEGA_PNL_UNIT * unitamt;
+                    result.set_single_vanna(vol_code, vanna);
+                }
+            }
+            self.instruments_in_action.retain(|inst| {
+                analytic_greeks
+                    .get(inst.get_code())
+                    .is_none_or(|greeks| greeks.get_vega().is_none())
+            });
+            if self.instruments_in_action.is_empty() {
+                continue;
+            }
+
+            // bump the volatility but limit the scope that is mutably borrowed
+            {
+                (*self.volatilities.get(vol_code).ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) volatility {} is not set\ntag:\n{}",
+                        file!(),
+                        line!(),
+                        vol_code,
+                        self.msg_tag
+                    )
+                })?)
+                .as_ref()
+                .borrow_mut()
+                .bump_volatility(None, None, None, None, bump_val)?;
+            }
+
+            npvs_up = self.get_npvs().context("failed to get npvs")?; // instrument code (String) -> npv (Real
+
+            for inst in &self.instruments_in_action {
+                let inst_code = inst.get_code();
+                let unitamt = inst.get_unit_notional();
+                let npv_up = npvs_up.get(inst_code).ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) npv_up is not set for {}",
+                        file!(),
+                        line!(),
+                        inst_code
+                    )
+                })?;
+
+                npv = self
                     .calculation_results
                     .get(inst.get_code())
-                    .ok_or_else(|| anyhow!("result is not set"))?
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "({}:{}) result is not set for {}",
+                            file!(),
+                            line!(),
+                            inst_code
+                        )
+                    })?
                     .borrow()
                     .get_npv_result()
-                    .ok_or_else(|| anyhow!("npv is not set"))?
+                    .ok_or_else(|| {
+                        anyhow!("({}:{}) npv is not set for {}", file!(), line!(), inst_code)
+                    })?
                     .get_npv();
 
-                gamma = delta_up - mid + delta_down - mid;
-                gamma *= DELTA_PNL_UNIT / delta_bump_ratio;
-                gamma *= 0.5 * (DELTA_PNL_UNIT / delta_bump_ratio);
-
+                let vega = (npv_up - npv) / bump_val * VEGA_PNL_UNIT * unitamt;
                 (*self
                     .calculation_results
                     .get(inst.get_code())
@@ -985,144 +1831,148 @@ impl Engine {
                             "({}:{}) result is not set for {}",
                             file!(),
                             line!(),
-                            inst.get_code(),
+                            inst.get_code()
                         )
                     })?)
                 .borrow_mut()
-                .set_single_gamma(und_code, gamma * unitamt);
+                .set_single_vega(vol_code, vega);
             }
-
+            // put back the bump
             {
-                (*self.equities.get(*und_code).ok_or_else(|| {
-                    anyhow!("({}:{}) there is no stock {}", file!(), line!(), und_code)
+                (*self.volatilities.get(vol_code).ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) volatility {} is not set\ntag:\n{}",
+                        file!(),
+                        line!(),
+                        vol_code,
+                        self.msg_tag
+                    )
                 })?)
                 .as_ref()
                 .borrow_mut()
-                .set_price(original_price);
+                .bump_volatility(None, None, None, None, -bump_val)?;
             }
         }
-
         Ok(())
     }
 
-    pub fn set_rho(&mut self) -> Result<()> {
+    /// vega on the parallel bump of the volatility of FX options, keyed by the fx code (e.g., "USDKRW")
+    pub fn set_fx_vega(&mut self) -> Result<()> {
         let mut npvs_up: HashMap<String, Real>;
-        let all_curve_names = self
-            .instruments
-            .get_all_curve_names(&self.match_parameter)?;
-        let bump_val = self.calculation_configuration.get_rho_bump_value();
-        let exclude_type = vec!["Stock"];
-        let exclude_type_clone = exclude_type.clone();
-
-        for curve_name in all_curve_names {
-            self.instruments_in_action = self.instruments.instruments_using_curve(
-                curve_name,
-                &self.match_parameter,
-                Some(exclude_type_clone.clone()),
-            )?;
+        let all_fxcodes = self.instruments.get_all_fxcodes_requiring_volatility();
+        let bump_val = self.calculation_configuration.get_vega_bump_value();
+        for fx_code in all_fxcodes.iter() {
+            self.instruments_in_action = self.instruments.instruments_with_fx_volatility(fx_code);
             if self.instruments_in_action.is_empty() {
                 continue;
             }
-            // bump the curve but limit the scope that the zero_curve ismutably borrowed
+            // bump the volatility but limit the scope that is mutably borrowed
             {
-                (*self.zero_curves.get(curve_name).with_context(|| {
+                (*self.fx_volatilities.get(fx_code).ok_or_else(|| {
                     anyhow!(
-                        "({}:{}) no zero curve: {}\n{}",
+                        "({}:{}) fx volatility {} is not set\ntag:\n{}",
                         file!(),
                         line!(),
-                        curve_name,
-                        self.msg_tag,
+                        fx_code,
+                        self.msg_tag
                     )
                 })?)
-                .as_ref()
                 .borrow_mut()
-                .bump_time_interval(None, None, bump_val)?;
+                .bump_volatility(None, None, None, None, bump_val)?;
             }
 
             npvs_up = self.get_npvs().context("failed to get npvs")?;
 
+            let fx_str = fx_code.to_string();
             for inst in &self.instruments_in_action {
                 let inst_code = inst.get_code();
                 let unitamt = inst.get_unit_notional();
-                let npv_up = npvs_up
-                    .get(inst_code)
-                    .ok_or_else(|| anyhow!("npv_up is not set"))?;
+                let npv_up = npvs_up.get(inst_code).ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) npv_up is not set for {}",
+                        file!(),
+                        line!(),
+                        inst_code
+                    )
+                })?;
                 let npv = self
                     .calculation_results
                     .get(inst_code)
-                    .ok_or_else(|| anyhow!("result is not set"))?
-                    .borrow()
-                    .get_npv_result()
-                    .ok_or_else(|| anyhow!("npv is not set"))?
-                    .get_npv();
-
-                let rho = (npv_up - npv) / bump_val * RHO_PNL_UNIT * unitamt;
-                (*self
-                    .calculation_results
-                    .get(inst.get_code())
                     .ok_or_else(|| {
                         anyhow!(
                             "({}:{}) result is not set for {}",
                             file!(),
                             line!(),
-                            inst.get_code(),
+                            inst_code
                         )
-                    })?)
+                    })?
+                    .borrow()
+                    .get_npv_result()
+                    .ok_or_else(|| {
+                        anyhow!("({}:{}) npv is not set for {}", file!(), line!(), inst_code)
+                    })?
+                    .get_npv();
+
+                let vega = (npv_up - npv) / bump_val * VEGA_PNL_UNIT * unitamt;
+                (*self.calculation_results.get(inst_code).ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) result is not set for {}",
+                        file!(),
+                        line!(),
+                        inst_code
+                    )
+                })?)
                 .borrow_mut()
-                .set_single_rho(curve_name, rho);
+                .set_single_vega(&fx_str, vega);
             }
-            // put back the bump value
+            // put back the bump
             {
-                (*self.zero_curves.get(curve_name).with_context(|| {
+                (*self.fx_volatilities.get(fx_code).ok_or_else(|| {
                     anyhow!(
-                        "({}:{}) no zero curve: {}\n{}",
+                        "({}:{}) fx volatility {} is not set\ntag:\n{}",
                         file!(),
                         line!(),
-                        curve_name,
-                        self.msg_tag,
+                        fx_code,
+                        self.msg_tag
                     )
                 })?)
-                .as_ref()
                 .borrow_mut()
-                .bump_time_interval(None, None, -bump_val)?;
+                .bump_volatility(None, None, None, None, -bump_val)?;
             }
         }
         Ok(())
     }
 
-    pub fn set_vega(&mut self) -> Result<()> {
+    /// vega on the parallel bump of the normal volatility of each rate volatility
+    pub fn set_normal_vega(&mut self) -> Result<()> {
         let mut npvs_up: HashMap<String, Real>;
-        let all_underlying_codes = self.instruments.get_all_underlying_codes();
-        let bump_val = self.calculation_configuration.get_vega_bump_value();
-        let mut npv: Real;
-        let exclude_type = vec!["Futures", "Stock"];
-        let exclude_type_clone = exclude_type.clone();
-        for vol_code in all_underlying_codes {
+        let all_rate_index_codes = self
+            .instruments
+            .get_all_rate_index_codes_requiring_volatility();
+        let bump_val = self.calculation_configuration.get_normal_vega_bump_value();
+        for rate_index_code in all_rate_index_codes.iter() {
             self.instruments_in_action = self
                 .instruments
-                .instruments_with_underlying(vol_code, Some(exclude_type_clone.clone()));
-
+                .instruments_with_rate_volatility(rate_index_code);
             if self.instruments_in_action.is_empty() {
                 continue;
             }
-
             // bump the volatility but limit the scope that is mutably borrowed
             {
-                (*self.volatilities.get(vol_code).ok_or_else(|| {
+                (*self.rate_volatilities.get(rate_index_code).ok_or_else(|| {
                     anyhow!(
-                        "({}:{}) volatility {} is not set\ntag:\n{}",
+                        "({}:{}) rate volatility {} is not set\ntag:\n{}",
                         file!(),
                         line!(),
-                        vol_code,
+                        rate_index_code,
                         self.msg_tag
                     )
                 })?)
-                .as_ref()
                 .borrow_mut()
-                .bump_volatility(None, None, None, None, bump_val)?;
+                .bump_normal_volatility(bump_val);
             }
 
-            npvs_up = self.get_npvs().context("failed to get npvs")?; // instrument code (String) -> npv (Real
+            npvs_up = self.get_npvs().context("failed to get npvs")?;
 
             for inst in &self.instruments_in_action {
                 let inst_code = inst.get_code();
@@ -1135,10 +1985,9 @@ impl Engine {
                         inst_code
                     )
                 })?;
-
-                npv = self
+                let npv = self
                     .calculation_results
-                    .get(inst.get_code())
+                    .get(inst_code)
                     .ok_or_else(|| {
                         anyhow!(
                             "({}:{}) result is not set for {}",
@@ -1154,35 +2003,31 @@ impl Engine {
                     })?
                     .get_npv();
 
-                let vega = (npv_up - npv) / bump_val * VEGA_PNL_UNIT * unitamt;
-                (*self
-                    .calculation_results
-                    .get(inst.get_code())
-                    .ok_or_else(|| {
-                        anyhow!(
-                            "({}:{}) result is not set for {}",
-                            file!(),
-                            line!(),
-                            inst.get_code()
-                        )
-                    })?)
+                let normal_vega = (npv_up - npv) / bump_val * NORMAL_VEGA_PNL_UNIT * unitamt;
+                (*self.calculation_results.get(inst_code).ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) result is not set for {}",
+                        file!(),
+                        line!(),
+                        inst_code
+                    )
+                })?)
                 .borrow_mut()
-                .set_single_vega(vol_code, vega);
+                .set_single_normal_vega(rate_index_code, normal_vega);
             }
             // put back the bump
             {
-                (*self.volatilities.get(vol_code).ok_or_else(|| {
+                (*self.rate_volatilities.get(rate_index_code).ok_or_else(|| {
                     anyhow!(
-                        "({}:{}) volatility {} is not set\ntag:\n{}",
+                        "({}:{}) rate volatility {} is not set\ntag:\n{}",
                         file!(),
                         line!(),
-                        vol_code,
+                        rate_index_code,
                         self.msg_tag
                     )
                 })?)
-                .as_ref()
                 .borrow_mut()
-                .bump_volatility(None, None, None, None, -bump_val)?;
+                .bump_normal_volatility(-bump_val);
             }
         }
         Ok(())
@@ -1626,6 +2471,41 @@ impl Engine {
         let time_diff =
             time_calculator.get_time_difference(&original_evaluation_date, &bumped_date);
 
+        // instruments with analytic greeks are not repriced unless they mature in the theta period,
+        // where the theta is the value change up to the maturity
+        let analytic_greeks = self.get_analytic_greeks()?;
+        let mut analytic_codes = vec![];
+        for inst in &self.instruments_in_action {
+            let inst_code = inst.get_code();
+            let Some(greeks) = analytic_greeks.get(inst_code) else {
+                continue;
+            };
+            if inst
+                .get_maturity()
+                .is_some_and(|m| m.date() <= bumped_date.date())
+            {
+                continue;
+            }
+            // the bumped theta is the value change per day
+            let theta = greeks.get_theta() * inst.get_unit_notional() / 365.0 * THETA_PNL_UNIT;
+            (*self.calculation_results.get(inst_code).ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) result is not set for {}",
+                    file!(),
+                    line!(),
+                    inst_code
+                )
+            })?)
+            .borrow_mut()
+            .set_theta(theta);
+            analytic_codes.push(inst_code.clone());
+        }
+        self.instruments_in_action
+            .retain(|inst| !analytic_codes.contains(inst.get_code()));
+        if self.instruments_in_action.is_empty() {
+            return Ok(());
+        }
+
         let mut cash_sum: Real;
         let npvs = self
             .get_npvs()
@@ -1702,6 +2582,17 @@ impl Engine {
     }
 
     pub fn set_rho_structure(&mut self) -> Result<()> {
+        self.set_curve_structure(false)
+    }
+
+    /// credit spread sensitivity on the bucket bumps of the credit spread curves over rho_structure_tenors
+    pub fn set_cs01_structure(&mut self) -> Result<()> {
+        self.set_curve_structure(true)
+    }
+
+    /// bucket sensitivities on the credit spread curves if credit_spread is true,
+    /// otherwise on the other curves
+    fn set_curve_structure(&mut self, credit_spread: bool) -> Result<()> {
         let all_curve_codes = self
             .instruments
             .get_all_curve_names(&self.match_parameter)?;
@@ -1730,6 +2621,9 @@ impl Engine {
         let exclude_type_clone = exclude_type.clone();
 
         for curve_code in all_curve_codes {
+            if self.match_parameter.is_credit_spread_curve(curve_code) != credit_spread {
+                continue;
+            }
             self.instruments_in_action = self.instruments.instruments_using_curve(
                 curve_code,
                 &self.match_parameter,
@@ -1821,7 +2715,7 @@ impl Engine {
             }
 
             for (inst_code, rho_structure) in single_rho_structure.iter() {
-                (*self.calculation_results.get(inst_code).with_context(|| {
+                let mut result = (*self.calculation_results.get(inst_code).with_context(|| {
                     anyhow!(
                         "({}:{}) failed to get result of {}",
                         file!(),
@@ -1829,8 +2723,11 @@ impl Engine {
                         inst_code,
                     )
                 })?)
-                .borrow_mut()
-                .set_single_rho_structure(curve_code, rho_structure.clone());
+                .borrow_mut();
+                match credit_spread {
+                    true => result.set_single_cs01_structure(curve_code, rho_structure.clone()),
+                    false => result.set_single_rho_structure(curve_code, rho_structure.clone()),
+                }
             }
         }
         Ok(())
@@ -1962,6 +2859,7 @@ impl Engine {
 
         let mut timer = std::time::Instant::now();
         let start_time = std::time::Instant::now();
+        self.analytic_greeks.borrow_mut().clear();
 
         if !self.instruments_in_action.is_empty() {
             warn!(
@@ -1983,6 +2881,62 @@ impl Engine {
             );
         }
 
+        if self
+            .calculation_configuration
+            .get_bond_analytics_calculation()
+        {
+            timer = std::time::Instant::now();
+            self.set_bond_analytics()?;
+            info!(
+                "* bond analytics calculation is done (engine id: {}, time = {} whole time elapsed: {})",
+                self.engine_id,
+                format_duration(timer.elapsed().as_secs_f64()),
+                format_duration(start_time.elapsed().as_secs_f64()),
+            );
+        }
+
+        if self
+            .calculation_configuration
+            .get_cds_analytics_calculation()
+        {
+            timer = std::time::Instant::now();
+            self.set_cds_analytics()?;
+            info!(
+                "* cds analytics calculation is done (engine id: {}, time = {} whole time elapsed: {})",
+                self.engine_id,
+                format_duration(timer.elapsed().as_secs_f64()),
+                format_duration(start_time.elapsed().as_secs_f64()),
+            );
+        }
+
+        if self
+            .calculation_configuration
+            .get_ktbf_analytics_calculation()
+        {
+            timer = std::time::Instant::now();
+            self.set_ktbf_analytics()?;
+            info!(
+                "* ktbf analytics calculation is done (engine id: {}, time = {} whole time elapsed: {})",
+                self.engine_id,
+                format_duration(timer.elapsed().as_secs_f64()),
+                format_duration(start_time.elapsed().as_secs_f64()),
+            );
+        }
+
+        if self
+            .calculation_configuration
+            .get_bond_futures_analytics_calculation()
+        {
+            timer = std::time::Instant::now();
+            self.set_bond_futures_analytics()?;
+            info!(
+                "* bond futures analytics calculation is done (engine id: {}, time = {} whole time elapsed: {})",
+                self.engine_id,
+                format_duration(timer.elapsed().as_secs_f64()),
+                format_duration(start_time.elapsed().as_secs_f64()),
+            );
+        }
+
         if self.calculation_configuration.get_fx_exposure_calculation() {
             timer = std::time::Instant::now();
             self.set_fx_exposures()?;
@@ -1998,6 +2952,7 @@ impl Engine {
             timer = std::time::Instant::now();
             self.preprocess_delta_gamma()?;
             self.set_delta_gamma()?;
+            self.set_fx_delta_gamma()?;
             info!(
                 "* delta calculation is done (engine id: {}, time = {} whole time elapsed: {})",
                 self.engine_id,
@@ -2075,6 +3030,7 @@ impl Engine {
         if self.calculation_configuration.get_vega_calculation() {
             timer = std::time::Instant::now();
             self.set_vega()?;
+            self.set_fx_vega()?;
             info!(
                 "* vega calculation is done (engine id: {}, time = {} whole time elapsed: {})",
                 self.engine_id,
@@ -2083,6 +3039,17 @@ impl Engine {
             );
         }
 
+        if self.calculation_configuration.get_normal_vega_calculation() {
+            timer = std::time::Instant::now();
+            self.set_normal_vega()?;
+            info!(
+                "* normal_vega calculation is done (engine id: {}, time = {} whole time elapsed: {})",
+                self.engine_id,
+                format_duration(timer.elapsed().as_secs_f64()),
+                format_duration(start_time.elapsed().as_secs_f64())
+            );
+        }
+
         if self.calculation_configuration.get_rho_calculation() {
             timer = std::time::Instant::now();
             self.set_rho()?;
@@ -2133,6 +3100,20 @@ impl Engine {
             );
         }
 
+        if self
+            .calculation_configuration
+            .get_cs01_structure_calculation()
+        {
+            timer = std::time::Instant::now();
+            self.set_cs01_structure()?;
+            info!(
+                "* cs01_structure calculation is done (engine id: {}, time = {} whole time elapsed: {})",
+                self.engine_id,
+                format_duration(timer.elapsed().as_secs_f64()),
+                format_duration(start_time.elapsed().as_secs_f64())
+            );
+        }
+
         if self
             .calculation_configuration
             .get_div_structure_calculation()
@@ -1,16 +1,24 @@
 use crate::currency::{Currency, FxCode};
 use crate::data::{
-    daily_value_data::DailyValueData, surface_data::SurfaceData, value_data::ValueData,
-    vector_data::VectorData,
+    daily_value_data::DailyValueData, fx_volatility_quote_data::FxVolatilityQuoteData,
+    rate_volatility_data::RateVolatilityData, surface_data::SurfaceData,
+    svi_parameter_data::SviParameterData, value_data::ValueData, vector_data::VectorData,
 };
+use crate::definitions::Real;
+use crate::enums::PnlExplainMethod;
 use crate::evaluation_date::EvaluationDate;
 use crate::instrument::{Instrument, InstrumentTrait, Instruments};
 use crate::pricing_engines::{
-    calculation_configuration::CalculationConfiguration, calculation_result::CalculationResult,
-    engine::Engine, match_parameter::MatchParameter,
+    calculation_configuration::CalculationConfiguration,
+    calculation_result::CalculationResult,
+    engine::Engine,
+    match_parameter::MatchParameter,
+    pnl_explain::{MarketMove, PnlExplain, PnlMarketData},
+    scenario_market_data::ScenarioMarketData,
+    stress_scenario::{StressScenario, StressTestResult},
 };
 //
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::{
@@ -71,6 +79,7 @@ impl InstrumentCategory {
     }
 }
 
+#[derive(Clone)]
 pub struct EngineGenerator {
     instruments: Instruments,
     instrument_group_vec: Vec<Vec<Instrument>>,
@@ -92,6 +101,13 @@ pub struct EngineGenerator {
     fx_constant_volatility_data: Arc<HashMap<FxCode, ValueData>>,
     quanto_correlation_data: Arc<HashMap<(String, FxCode), ValueData>>,
     past_daily_value_data: Arc<HashMap<String, DailyValueData>>,
+    svi_parameter_data: Arc<HashMap<String, SviParameterData>>,
+    equity_correlation_data: Arc<HashMap<(String, String), ValueData>>,
+    rate_volatility_data: Arc<HashMap<String, RateVolatilityData>>,
+    fx_volatility_quote_data: Arc<HashMap<FxCode, FxVolatilityQuoteData>>,
+    // stress test
+    stress_scenarios: Vec<StressScenario>,
+    stress_test_result: Option<StressTestResult>,
 }
 
 impl Default for EngineGenerator {
@@ -117,6 +133,12 @@ impl Default for EngineGenerator {
             fx_constant_volatility_data: Arc::new(HashMap::new()),
             quanto_correlation_data: Arc::new(HashMap::new()),
             past_daily_value_data: Arc::new(HashMap::new()),
+            svi_parameter_data: Arc::new(HashMap::new()),
+            equity_correlation_data: Arc::new(HashMap::new()),
+            rate_volatility_data: Arc::new(HashMap::new()),
+            fx_volatility_quote_data: Arc::new(HashMap::new()),
+            stress_scenarios: vec![],
+            stress_test_result: None,
         }
     }
 }
@@ -176,6 +198,59 @@ impl EngineGenerator {
         Ok(self)
     }
 
+    pub fn with_svi_parameter_data(
+        &mut self,
+        svi_parameter_data: HashMap<String, SviParameterData>,
+    ) -> Result<&mut Self> {
+        self.svi_parameter_data = Arc::new(svi_parameter_data);
+        Ok(self)
+    }
+
+    pub fn with_equity_correlation_data(
+        &mut self,
+        equity_correlation_data: HashMap<(String, String), ValueData>,
+    ) -> Result<&mut Self> {
+        self.equity_correlation_data = Arc::new(equity_correlation_data);
+        Ok(self)
+    }
+
+    pub fn with_rate_volatility_data(
+        &mut self,
+        rate_volatility_data: HashMap<String, RateVolatilityData>,
+    ) -> Result<&mut Self> {
+        self.rate_volatility_data = Arc::new(rate_volatility_data);
+        Ok(self)
+    }
+
+    pub fn with_fx_volatility_quote_data(
+        &mut self,
+        fx_volatility_quote_data: HashMap<FxCode, FxVolatilityQuoteData>,
+    ) -> Result<&mut Self> {
+        self.fx_volatility_quote_data = Arc::new(fx_volatility_quote_data);
+        Ok(self)
+    }
+
+    pub fn with_stress_scenarios(
+        &mut self,
+        stress_scenarios: Vec<StressScenario>,
+    ) -> Result<&mut Self> {
+        for (i, scenario) in stress_scenarios.iter().enumerate() {
+            if stress_scenarios[..i]
+                .iter()
+                .any(|other| other.get_name() == scenario.get_name())
+            {
+                return Err(anyhow!(
+                    "({}:{}) the stress scenario {} is duplicated",
+                    file!(),
+                    line!(),
+                    scenario.get_name()
+                ));
+            }
+        }
+        self.stress_scenarios = stress_scenarios;
+        Ok(self)
+    }
+
     pub fn distribute_instruments(&mut self) -> Result<()> {
         let mut distribution_checker: Vec<bool> = vec![false; self.instruments.len()];
 
@@ -227,17 +302,64 @@ impl EngineGenerator {
     /// spawn threads to create engine and calculate
     pub fn calculate(&mut self) -> Result<()> {
         let shared_results = Arc::new(Mutex::new(HashMap::<String, CalculationResult>::new()));
+        let calc_res = self.calculate_on_data(
+            &self.calculation_configuration,
+            self.fx_data.clone(),
+            self.stock_data.clone(),
+            self.curve_data.clone(),
+            self.equity_constant_volatility_data.clone(),
+            self.equity_volatility_surface_data.clone(),
+            self.fx_constant_volatility_data.clone(),
+            self.quanto_correlation_data.clone(),
+            self.svi_parameter_data.clone(),
+            self.rate_volatility_data.clone(),
+            self.fx_volatility_quote_data.clone(),
+            &shared_results,
+        );
+
+        //self.calculation_results = shared_results.lock().unwrap().clone();
+        self.calculation_results
+            .clone_from(&shared_results.lock().unwrap());
+
+        match calc_res {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// create the engines on the instrument groups in parallel
+    /// and calculate on the given market data into shared_results
+    #[allow(clippy::too_many_arguments)]
+    fn calculate_on_data(
+        &self,
+        calculation_configuration: &CalculationConfiguration,
+        fx_data: Arc<HashMap<FxCode, ValueData>>,
+        stock_data: Arc<HashMap<String, ValueData>>,
+        curve_data: Arc<HashMap<String, VectorData>>,
+        equity_constant_volatility_data: Arc<HashMap<String, ValueData>>,
+        equity_volatility_surface_data: Arc<HashMap<String, SurfaceData>>,
+        fx_constant_volatility_data: Arc<HashMap<FxCode, ValueData>>,
+        quanto_correlation_data: Arc<HashMap<(String, FxCode), ValueData>>,
+        svi_parameter_data: Arc<HashMap<String, SviParameterData>>,
+        rate_volatility_data: Arc<HashMap<String, RateVolatilityData>>,
+        fx_volatility_quote_data: Arc<HashMap<FxCode, FxVolatilityQuoteData>>,
+        shared_results: &Mutex<HashMap<String, CalculationResult>>,
+    ) -> Result<()> {
         let dt = self.evaluation_date.get_date_clone();
-        let calc_res: Result<()> = self
-            .instrument_group_vec
+        // borrow the fields only, since the instruments are not shared between threads
+        let match_parameter = &self.match_parameter;
+        let dividend_data = &self.dividend_data;
+        let past_daily_value_data = &self.past_daily_value_data;
+        let equity_correlation_data = &self.equity_correlation_data;
+        self.instrument_group_vec
             .par_iter()
             .enumerate()
             .map(|(group_id, instrument_group)| {
                 let engine = Engine::builder(
                     group_id,
-                    self.calculation_configuration.clone(),
+                    calculation_configuration.clone(),
                     dt,
-                    self.match_parameter.clone(),
+                    match_parameter.clone(),
                 );
 
                 let engine = match engine.with_instruments(instrument_group.clone()) {
@@ -245,16 +367,22 @@ impl EngineGenerator {
                     Err(e) => return Err(e),
                 };
 
+                let engine = engine
+                    .with_svi_parameter_data(svi_parameter_data.clone())
+                    .with_equity_correlation_data(equity_correlation_data.clone())
+                    .with_rate_volatility_data(rate_volatility_data.clone())
+                    .with_fx_volatility_quote_data(fx_volatility_quote_data.clone());
+
                 let mut engine = match engine.with_parameter_data(
-                    self.fx_data.clone(),
-                    self.stock_data.clone(),
-                    self.curve_data.clone(),
-                    self.dividend_data.clone(),
-                    self.equity_constant_volatility_data.clone(),
-                    self.equity_volatility_surface_data.clone(),
-                    self.fx_constant_volatility_data.clone(),
-                    self.quanto_correlation_data.clone(),
-                    self.past_daily_value_data.clone(),
+                    fx_data.clone(),
+                    stock_data.clone(),
+                    curve_data.clone(),
+                    dividend_data.clone(),
+                    equity_constant_volatility_data.clone(),
+                    equity_volatility_surface_data.clone(),
+                    fx_constant_volatility_data.clone(),
+                    quanto_correlation_data.clone(),
+                    past_daily_value_data.clone(),
                 ) {
                     Ok(engine) => engine,
                     Err(e) => return Err(e),
@@ -272,16 +400,337 @@ impl EngineGenerator {
 
                 Ok(())
             })
-            .collect();
+            .collect()
+    }
 
-        //self.calculation_results = shared_results.lock().unwrap().clone();
-        self.calculation_results
-            .clone_from(&shared_results.lock().unwrap());
+    /// NPVs on the base market data and on each stress scenario.
+    /// The scenarios are calculated without the sensitivities.
+    pub fn calculate_stress_scenarios(&mut self) -> Result<()> {
+        let calculation_configuration = self
+            .calculation_configuration
+            .clone()
+            .without_sensitivities();
+        let base_results = Mutex::new(HashMap::<String, CalculationResult>::new());
+        self.calculate_on_data(
+            &calculation_configuration,
+            self.fx_data.clone(),
+            self.stock_data.clone(),
+            self.curve_data.clone(),
+            self.equity_constant_volatility_data.clone(),
+            self.equity_volatility_surface_data.clone(),
+            self.fx_constant_volatility_data.clone(),
+            self.quanto_correlation_data.clone(),
+            self.svi_parameter_data.clone(),
+            self.rate_volatility_data.clone(),
+            self.fx_volatility_quote_data.clone(),
+            &base_results,
+        )
+        .context("failed to calculate the base of the stress scenarios")?;
+        let base_results = base_results.into_inner().unwrap();
 
-        match calc_res {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e),
+        let base_market_data = ScenarioMarketData {
+            fx_data: (*self.fx_data).clone(),
+            stock_data: (*self.stock_data).clone(),
+            curve_data: (*self.curve_data).clone(),
+            equity_constant_volatility_data: (*self.equity_constant_volatility_data).clone(),
+            equity_volatility_surface_data: (*self.equity_volatility_surface_data).clone(),
+            fx_constant_volatility_data: (*self.fx_constant_volatility_data).clone(),
+            quanto_correlation_data: (*self.quanto_correlation_data).clone(),
+            svi_parameter_data: (*self.svi_parameter_data).clone(),
+            fx_volatility_quote_data: (*self.fx_volatility_quote_data).clone(),
+            rate_volatility_data: (*self.rate_volatility_data).clone(),
+        };
+
+        let npv = |result: &CalculationResult| -> Result<(Real, Real)> {
+            let npv = result
+                .get_npv_result()
+                .ok_or_else(|| anyhow!("({}:{}) npv is not set", file!(), line!()))?
+                .get_npv();
+            let value = result
+                .get_value()
+                .ok_or_else(|| anyhow!("({}:{}) value is not set", file!(), line!()))?;
+            Ok((npv, value))
+        };
+
+        let mut base_npvs = HashMap::new();
+        for (code, result) in base_results.iter() {
+            base_npvs.insert(code.clone(), npv(result)?.0);
+        }
+        let mut npv_differences = HashMap::new();
+        let mut value_differences = HashMap::new();
+        for scenario in &self.stress_scenarios {
+            let market_data = scenario.apply(&base_market_data)?;
+            let scenario_results = Mutex::new(HashMap::<String, CalculationResult>::new());
+            self.calculate_on_data(
+                &calculation_configuration,
+                Arc::new(market_data.fx_data),
+                Arc::new(market_data.stock_data),
+                Arc::new(market_data.curve_data),
+                Arc::new(market_data.equity_constant_volatility_data),
+                Arc::new(market_data.equity_volatility_surface_data),
+                Arc::new(market_data.fx_constant_volatility_data),
+                Arc::new(market_data.quanto_correlation_data),
+                Arc::new(market_data.svi_parameter_data),
+                Arc::new(market_data.rate_volatility_data),
+                Arc::new(market_data.fx_volatility_quote_data),
+                &scenario_results,
+            )
+            .with_context(|| {
+                format!(
+                    "failed to calculate the stress scenario {}",
+                    scenario.get_name()
+                )
+            })?;
+
+            let mut npv_difference = HashMap::new();
+            let mut value_difference = HashMap::new();
+            for (code, result) in scenario_results.into_inner().unwrap().iter() {
+                let (base_npv, base_value) = npv(&base_results[code])?;
+                let (scenario_npv, scenario_value) = npv(result)?;
+                npv_difference.insert(code.clone(), scenario_npv - base_npv);
+                value_difference.insert(code.clone(), scenario_value - base_value);
+            }
+            npv_differences.insert(scenario.get_name().clone(), npv_difference);
+            value_differences.insert(scenario.get_name().clone(), value_difference);
+        }
+
+        self.stress_test_result = Some(StressTestResult::new(
+            self.stress_scenarios
+                .iter()
+                .map(|scenario| scenario.get_name().clone())
+                .collect(),
+            base_npvs,
+            npv_differences,
+            value_differences,
+        ));
+        Ok(())
+    }
+
+    pub fn get_stress_test_result(&self) -> Option<&StressTestResult> {
+        self.stress_test_result.as_ref()
+    }
+
+    fn pnl_market_data(&self) -> PnlMarketData<'_> {
+        PnlMarketData {
+            evaluation_date: self.evaluation_date.get_date_clone(),
+            fx_data: &self.fx_data,
+            stock_data: &self.stock_data,
+            curve_data: &self.curve_data,
+            dividend_data: &self.dividend_data,
+            equity_constant_volatility_data: &self.equity_constant_volatility_data,
+            equity_volatility_surface_data: &self.equity_volatility_surface_data,
+            fx_constant_volatility_data: &self.fx_constant_volatility_data,
+            fx_volatility_quote_data: &self.fx_volatility_quote_data,
+        }
+    }
+
+    /// values (considering unit_notional) of the instruments without the sensitivities
+    fn calculate_values(
+        &self,
+        calculation_configuration: &CalculationConfiguration,
+    ) -> Result<HashMap<String, Real>> {
+        let results = Mutex::new(HashMap::<String, CalculationResult>::new());
+        self.calculate_on_data(
+            calculation_configuration,
+            self.fx_data.clone(),
+            self.stock_data.clone(),
+            self.curve_data.clone(),
+            self.equity_constant_volatility_data.clone(),
+            self.equity_volatility_surface_data.clone(),
+            self.fx_constant_volatility_data.clone(),
+            self.quanto_correlation_data.clone(),
+            self.svi_parameter_data.clone(),
+            self.rate_volatility_data.clone(),
+            self.fx_volatility_quote_data.clone(),
+            &results,
+        )?;
+        let mut res = HashMap::new();
+        for (code, result) in results.into_inner().unwrap().iter() {
+            let value = result.get_value().ok_or_else(|| {
+                anyhow!("({}:{}) value is not set for {}", file!(), line!(), code)
+            })?;
+            res.insert(code.clone(), value);
+        }
+        Ok(res)
+    }
+
+    /// P&L explain of the instruments from this run to the calculated run of end on a later evaluation date.
+    /// The instruments in both of the results are explained, and the cashflows are
+    /// the expected cashflows of this run paid after this evaluation date up to the end.
+    /// RiskBased needs the delta, gamma, vega, rho, div_delta and theta of this run,
+    /// and StepByStep reprices this run while moving the evaluation date with the past fixings,
+    /// the stock prices, the volatilities with the correlations, each curve (in the name order),
+    /// the dividends and the fx rates to the end.
+    pub fn explain_pnl(
+        &self,
+        end: &EngineGenerator,
+        method: PnlExplainMethod,
+    ) -> Result<HashMap<String, PnlExplain>> {
+        let start_date = self.evaluation_date.get_date_clone();
+        let end_date = end.evaluation_date.get_date_clone();
+        if end_date <= start_date {
+            return Err(anyhow!(
+                "({}:{}) the end date {:?} of the P&L explain is not after the start date {:?}",
+                file!(),
+                line!(),
+                end_date,
+                start_date
+            ));
+        }
+
+        let value = |result: &CalculationResult, code: &str| -> Result<Real> {
+            result
+                .get_value()
+                .ok_or_else(|| anyhow!("({}:{}) value is not set for {}", file!(), line!(), code))
+        };
+        // instrument, start value, end value and cashflow
+        let mut targets = Vec::new();
+        for instrument in self.instruments.iter() {
+            let code = instrument.get_code();
+            let (Some(start_result), Some(end_result)) = (
+                self.calculation_results.get(code),
+                end.calculation_results.get(code),
+            ) else {
+                continue;
+            };
+            let cashflow = start_result
+                .get_cashflows()
+                .map(|cashflows| {
+                    cashflows
+                        .iter()
+                        .filter(|(date, _)| {
+                            date.date() > start_date.date() && date.date() <= end_date.date()
+                        })
+                        .map(|(_, amount)| amount)
+                        .sum::<Real>()
+                })
+                .unwrap_or(0.0)
+                * instrument.get_unit_notional();
+            targets.push((
+                instrument,
+                start_result,
+                value(start_result, code)?,
+                value(end_result, code)?,
+                cashflow,
+            ));
+        }
+
+        let mut res = HashMap::new();
+        match method {
+            PnlExplainMethod::RiskBased => {
+                let market_move = MarketMove::new(self.pnl_market_data(), end.pnl_market_data());
+                for (instrument, result, start_value, end_value, cashflow) in targets {
+                    let explain = market_move
+                        .explain(instrument, result, start_value, end_value, cashflow)
+                        .with_context(|| {
+                            format!("failed to explain the P&L of {}", instrument.get_code())
+                        })?;
+                    res.insert(instrument.get_code().clone(), explain);
+                }
+            }
+            PnlExplainMethod::StepByStep => {
+                let calculation_configuration = self
+                    .calculation_configuration
+                    .clone()
+                    .without_sensitivities();
+                let mut step = self.clone();
+
+                step.evaluation_date = EvaluationDate::new(end_date);
+                step.past_daily_value_data = end.past_daily_value_data.clone();
+                let carry_values = step
+                    .calculate_values(&calculation_configuration)
+                    .context("failed to calculate the carry step of the P&L explain")?;
+
+                step.stock_data = end.stock_data.clone();
+                let spot_values = step
+                    .calculate_values(&calculation_configuration)
+                    .context("failed to calculate the spot step of the P&L explain")?;
+
+                step.equity_constant_volatility_data = end.equity_constant_volatility_data.clone();
+                step.equity_volatility_surface_data = end.equity_volatility_surface_data.clone();
+                step.fx_constant_volatility_data = end.fx_constant_volatility_data.clone();
+                step.fx_volatility_quote_data = end.fx_volatility_quote_data.clone();
+                step.svi_parameter_data = end.svi_parameter_data.clone();
+                step.rate_volatility_data = end.rate_volatility_data.clone();
+                step.quanto_correlation_data = end.quanto_correlation_data.clone();
+                step.equity_correlation_data = end.equity_correlation_data.clone();
+                let vega_values = step
+                    .calculate_values(&calculation_configuration)
+                    .context("failed to calculate the volatility step of the P&L explain")?;
+
+                let mut curve_names: Vec<&String> = end.curve_data.keys().collect();
+                curve_names.sort();
+                let mut curve_values = Vec::new();
+                for name in curve_names {
+                    let mut curve_data = (*step.curve_data).clone();
+                    curve_data.insert(name.clone(), end.curve_data[name].clone());
+                    step.curve_data = Arc::new(curve_data);
+                    let values = step
+                        .calculate_values(&calculation_configuration)
+                        .with_context(|| {
+                            format!(
+                                "failed to calculate the curve step of {} in the P&L explain",
+                                name
+                            )
+                        })?;
+                    curve_values.push((name.clone(), values));
+                }
+
+                step.dividend_data = end.dividend_data.clone();
+                let dividend_values = step
+                    .calculate_values(&calculation_configuration)
+                    .context("failed to calculate the dividend step of the P&L explain")?;
+
+                step.fx_data = end.fx_data.clone();
+                let fx_values = step
+                    .calculate_values(&calculation_configuration)
+                    .context("failed to calculate the fx step of the P&L explain")?;
+
+                for (instrument, _, start_value, end_value, cashflow) in targets {
+                    let code = instrument.get_code();
+                    let step_value = |values: &HashMap<String, Real>| -> Result<Real> {
+                        values.get(code).copied().ok_or_else(|| {
+                            anyhow!(
+                                "({}:{}) {} is not calculated in the P&L explain",
+                                file!(),
+                                line!(),
+                                code
+                            )
+                        })
+                    };
+                    let carry_value = step_value(&carry_values)?;
+                    let spot_value = step_value(&spot_values)?;
+                    let vega_value = step_value(&vega_values)?;
+                    let mut rho = HashMap::new();
+                    let mut previous = vega_value;
+                    for (name, values) in &curve_values {
+                        let current = step_value(values)?;
+                        // the curves which the instrument does not depend on are left out
+                        if current != previous {
+                            rho.insert(name.clone(), current - previous);
+                        }
+                        previous = current;
+                    }
+                    let dividend_value = step_value(&dividend_values)?;
+                    let fx_value = step_value(&fx_values)?;
+
+                    let explain = PnlExplain::new(
+                        *instrument.get_currency(),
+                        start_value,
+                        end_value,
+                        cashflow,
+                        carry_value - start_value,
+                        spot_value - carry_value,
+                        vega_value - spot_value,
+                        rho,
+                        dividend_value - previous,
+                        fx_value - dividend_value,
+                    );
+                    res.insert(code.clone(), explain);
+                }
+            }
         }
+        Ok(res)
     }
 
     pub fn get_calculation_results(&self) -> &HashMap<String, CalculationResult> {
@@ -0,0 +1,284 @@
+use crate::currency::Currency;
+use crate::definitions::Real;
+use crate::enums::FxSettlementType;
+use crate::evaluation_date::EvaluationDate;
+use crate::instrument::{Instrument, InstrumentTrait};
+use crate::instruments::fx_forward::FxForward;
+use crate::parameters::zero_curve::ZeroCurve;
+use crate::parameters::{market_price::MarketPrice, past_price::DailyClosePrice};
+use crate::pricing_engines::{npv_result::NpvResult, pricer::PricerTrait};
+//
+use anyhow::{anyhow, Result};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+/// FX forward pricer on the discount curves of the base and the quote currencies.
+/// The forward to a date is fx * base discount / quote discount.
+/// Before the fixing date, the non-deliverable forward fixes at the forward to the fixing date,
+/// and after that at the close of the fixing source.
+pub struct FxForwardPricer {
+    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    fx: Rc<RefCell<MarketPrice>>,
+    base_currency_curve: Rc<RefCell<ZeroCurve>>,
+    quote_currency_curve: Rc<RefCell<ZeroCurve>>,
+    past_fixing_data: Option<Rc<DailyClosePrice>>,
+}
+
+impl FxForwardPricer {
+    pub fn new(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        fx: Rc<RefCell<MarketPrice>>,
+        base_currency_curve: Rc<RefCell<ZeroCurve>>,
+        quote_currency_curve: Rc<RefCell<ZeroCurve>>,
+        past_fixing_data: Option<Rc<DailyClosePrice>>,
+    ) -> FxForwardPricer {
+        FxForwardPricer {
+            evaluation_date,
+            fx,
+            base_currency_curve,
+            quote_currency_curve,
+            past_fixing_data,
+        }
+    }
+
+    fn get_fx_forward<'a>(&self, instrument: &'a Instrument) -> Result<&'a FxForward> {
+        match instrument {
+            Instrument::FxForward(fx_forward) => Ok(fx_forward),
+            _ => Err(anyhow!(
+                "({}:{}) FxForwardPricer does not support {} ({})",
+                file!(),
+                line!(),
+                instrument.get_name(),
+                instrument.get_code()
+            )),
+        }
+    }
+
+    /// the fixing of the non-deliverable forward, which is the forward to the fixing date before it is fixed
+    fn fixing(&self, fx_forward: &FxForward) -> Result<Real> {
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let fixing_date = fx_forward.get_fixing_date();
+        if eval_date.date() >= fixing_date.date() {
+            let past_fixing = self
+                .past_fixing_data
+                .as_ref()
+                .and_then(|data| data.get(&fixing_date.date()));
+            match past_fixing {
+                Some(fixing) => return Ok(*fixing),
+                None if eval_date.date() > fixing_date.date() => {
+                    return Err(anyhow!(
+                        "({}:{}) the fixing of {} ({}) on {} is not found in {:?}",
+                        file!(),
+                        line!(),
+                        fx_forward.get_name(),
+                        fx_forward.get_code(),
+                        fixing_date.date(),
+                        fx_forward.get_fixing_source(),
+                    ))
+                }
+                // not fixed yet on the fixing date
+                None => {}
+            }
+        }
+        let base_discount = self
+            .base_currency_curve
+            .borrow()
+            .get_discount_factor_at_date(fixing_date)?;
+        let quote_discount = self
+            .quote_currency_curve
+            .borrow()
+            .get_discount_factor_at_date(fixing_date)?;
+        Ok(self.fx.borrow().get_value() * base_discount / quote_discount)
+    }
+
+    /// present values of the legs per unit notional in (base currency, quote currency),
+    /// so that the npv in the quote currency is fx * base + quote
+    fn legs(&self, fx_forward: &FxForward) -> Result<(Real, Real)> {
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let maturity = fx_forward.get_maturity().unwrap();
+        if eval_date.date() >= maturity.date() {
+            return Ok((0.0, 0.0));
+        }
+        let forward_rate = fx_forward.get_forward_rate();
+        let base_discount = self
+            .base_currency_curve
+            .borrow()
+            .get_discount_factor_at_date(maturity)?;
+        let quote_discount = self
+            .quote_currency_curve
+            .borrow()
+            .get_discount_factor_at_date(maturity)?;
+
+        match fx_forward.get_settlement_type() {
+            FxSettlementType::Deliverable => Ok((base_discount, -forward_rate * quote_discount)),
+            FxSettlementType::NonDeliverable => {
+                let fixing_date = fx_forward.get_fixing_date();
+                let fixed = eval_date.date() >= fixing_date.date()
+                    && self
+                        .past_fixing_data
+                        .as_ref()
+                        .and_then(|data| data.get(&fixing_date.date()))
+                        .is_some();
+                let fixing = self.fixing(fx_forward)?;
+                let settles_in_base = fx_forward.get_settlement_currency()
+                    == fx_forward.get_fx_code().get_currency1();
+                match (fixed, settles_in_base) {
+                    (true, false) => Ok((0.0, (fixing - forward_rate) * quote_discount)),
+                    (true, true) => Ok(((fixing - forward_rate) / fixing * base_discount, 0.0)),
+                    // the forward to the fixing date is paid on the maturity
+                    (false, false) => Ok((
+                        fixing / self.fx.borrow().get_value() * quote_discount,
+                        -forward_rate * quote_discount,
+                    )),
+                    (false, true) => Ok((
+                        base_discount,
+                        -forward_rate * base_discount * self.fx.borrow().get_value() / fixing,
+                    )),
+                }
+            }
+        }
+    }
+}
+
+impl PricerTrait for FxForwardPricer {
+    fn npv(&self, instrument: &Instrument) -> Result<Real> {
+        let fx_forward = self.get_fx_forward(instrument)?;
+        let (base, quote) = self.legs(fx_forward)?;
+        Ok(self.fx.borrow().get_value() * base + quote)
+    }
+
+    fn npv_result(&self, instrument: &Instrument) -> Result<NpvResult> {
+        let npv = self.npv(instrument)?;
+        Ok(NpvResult::new_from_npv(npv))
+    }
+
+    fn fx_exposure(&self, instrument: &Instrument, _npv: Real) -> Result<HashMap<Currency, Real>> {
+        let fx_forward = self.get_fx_forward(instrument)?;
+        let (base, quote) = self.legs(fx_forward)?;
+        let unit_notional = instrument.get_unit_notional();
+        let mut res: HashMap<Currency, Real> = HashMap::new();
+        res.insert(
+            *fx_forward.get_fx_code().get_currency1(),
+            base * unit_notional,
+        );
+        res.insert(
+            *fx_forward.get_fx_code().get_currency2(),
+            quote * unit_notional,
+        );
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::FxCode;
+    use crate::time::calendar::Calendar;
+    use crate::time::calendars::southkorea::{SouthKorea, SouthKoreaType};
+    use crate::utils::test_data::flat_zero_curve;
+    use time::macros::{datetime, time};
+    use time::UtcOffset;
+
+    #[test]
+    fn test_fx_forward_pricer() -> Result<()> {
+        let dt = datetime!(2024-01-02 16:00:00 +09:00);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(dt)));
+        let fx = Rc::new(RefCell::new(MarketPrice::new(
+            1300.0,
+            dt,
+            None,
+            Currency::KRW,
+            "USDKRW".to_string(),
+            "USDKRW".to_string(),
+        )));
+        let curve =
+            |rate: Real, name: &str| flat_zero_curve(&evaluation_date, rate, Currency::KRW, name);
+        let fixings = DailyClosePrice::new(
+            HashMap::from([(datetime!(2024-06-28 00:00:00 +09:00).date(), 1320.0)]),
+            time!(15:30:00),
+            UtcOffset::from_hms(9, 0, 0)?,
+            Calendar::SouthKorea(SouthKorea::new(SouthKoreaType::Settlement)),
+            "USDKRW MAR".to_string(),
+            "USDKRW MAR".to_string(),
+        );
+        let pricer = FxForwardPricer::new(
+            evaluation_date.clone(),
+            fx.clone(),
+            curve(0.05, "USDOIS")?,
+            curve(0.035, "KRWCRS")?,
+            Some(Rc::new(fixings)),
+        );
+
+        let fx_code = FxCode::new(Currency::USD, Currency::KRW);
+        let maturity = datetime!(2024-07-02 16:00:00 +09:00);
+        let fixing_date = datetime!(2024-06-28 16:00:00 +09:00);
+        let deliverable = Instrument::FxForward(FxForward::new_deliverable(
+            fx_code,
+            1295.0,
+            dt,
+            maturity,
+            1_000_000.0,
+            "USDKRW 6M".to_string(),
+            "USDKRW 6M".to_string(),
+        )?);
+        let ndf = |settlement_currency: Currency| -> Result<Instrument> {
+            Ok(Instrument::FxForward(FxForward::new(
+                fx_code,
+                1295.0,
+                FxSettlementType::NonDeliverable,
+                settlement_currency,
+                fixing_date,
+                Some("USDKRW MAR".to_string()),
+                dt,
+                maturity,
+                1_000_000.0,
+                "USDKRW NDF 6M".to_string(),
+                "USDKRW NDF 6M".to_string(),
+            )?))
+        };
+
+        // (fx * base discount - forward rate * quote discount) in KRW
+        let base_discount = pricer
+            .base_currency_curve
+            .borrow()
+            .get_discount_factor_at_date(&maturity)?;
+        let quote_discount = pricer
+            .quote_currency_curve
+            .borrow()
+            .get_discount_factor_at_date(&maturity)?;
+        let npv = pricer.npv(&deliverable)?;
+        let expected = 1300.0 * base_discount - 1295.0 * quote_discount;
+        assert!((npv - expected).abs() < 1.0e-3, "npv = {}", npv);
+        assert!(
+            npv < 0.0,
+            "the forward is below 1295 with the USD rate higher"
+        );
+        let exposure = pricer.fx_exposure(&deliverable, npv)?;
+        assert!((exposure[&Currency::USD] - 1_000_000.0 * base_discount).abs() < 1.0);
+
+        // before the fixing, the NDF is the deliverable up to the carry from the fixing date to the maturity
+        // and the settlement currency does not matter
+        let ndf_krw = ndf(Currency::KRW)?;
+        let ndf_usd = ndf(Currency::USD)?;
+        assert!((pricer.npv(&ndf_krw)? - npv).abs() < 0.5);
+        assert!((pricer.npv(&ndf_krw)? - pricer.npv(&ndf_usd)?).abs() < 1.0e-3);
+
+        // after the fixing, only the settlement currency is exposed
+        evaluation_date
+            .borrow_mut()
+            .set_date(datetime!(2024-07-01 16:00:00 +09:00));
+        let quote_discount = pricer
+            .quote_currency_curve
+            .borrow()
+            .get_discount_factor_at_date(&maturity)?;
+        let npv_krw = pricer.npv(&ndf_krw)?;
+        assert!(
+            (npv_krw - 25.0 * quote_discount).abs() < 0.1,
+            "npv = {}",
+            npv_krw
+        );
+        let exposure = pricer.fx_exposure(&ndf_usd, pricer.npv(&ndf_usd)?)?;
+        assert_eq!(exposure[&Currency::KRW], 0.0);
+        assert!(exposure[&Currency::USD] > 0.0);
+        Ok(())
+    }
+}
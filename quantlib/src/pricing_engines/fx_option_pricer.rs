@@ -0,0 +1,284 @@
+use crate::currency::Currency;
+use crate::definitions::Real;
+use crate::enums::OptionType;
+use crate::evaluation_date::EvaluationDate;
+use crate::instrument::{Instrument, InstrumentTrait};
+use crate::instruments::fx_option::FxOption;
+use crate::parameters::{market_price::MarketPrice, volatility::Volatility, zero_curve::ZeroCurve};
+use crate::pricing_engines::{npv_result::NpvResult, pricer::PricerTrait};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use anyhow::{anyhow, Result};
+use statrs::distribution::{ContinuousCDF, Normal};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+/// Garman-Kohlhagen pricer of European FX options.
+/// The forward is fx * base discount / quote discount, and the premium is
+/// quote discount * Black(forward, strike, total deviation) where the volatility is taken
+/// at the forward moneyness strike / forward.
+pub struct FxOptionPricer {
+    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    fx: Rc<RefCell<MarketPrice>>,
+    base_currency_curve: Rc<RefCell<ZeroCurve>>,
+    quote_currency_curve: Rc<RefCell<ZeroCurve>>,
+    volatility: Rc<RefCell<Volatility>>,
+    time_calculator: NullCalendar,
+}
+
+impl FxOptionPricer {
+    pub fn new(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        fx: Rc<RefCell<MarketPrice>>,
+        base_currency_curve: Rc<RefCell<ZeroCurve>>,
+        quote_currency_curve: Rc<RefCell<ZeroCurve>>,
+        volatility: Rc<RefCell<Volatility>>,
+    ) -> FxOptionPricer {
+        FxOptionPricer {
+            evaluation_date,
+            fx,
+            base_currency_curve,
+            quote_currency_curve,
+            volatility,
+            time_calculator: NullCalendar::new(),
+        }
+    }
+
+    fn get_fx_option<'a>(&self, instrument: &'a Instrument) -> Result<&'a FxOption> {
+        match instrument {
+            Instrument::FxOption(fx_option) => Ok(fx_option),
+            _ => Err(anyhow!(
+                "({}:{}) FxOptionPricer does not support {} ({})",
+                file!(),
+                line!(),
+                instrument.get_name(),
+                instrument.get_code()
+            )),
+        }
+    }
+
+    /// present values of the replicating legs per unit notional in (base currency, quote currency),
+    /// i.e., the spot delta DF_base N(d1) in the base currency and -DF_quote K N(d2) in the quote currency for a call,
+    /// so that the npv in the quote currency is fx * base + quote
+    fn legs(&self, fx_option: &FxOption) -> Result<(Real, Real)> {
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let maturity = fx_option.get_maturity().unwrap();
+        if eval_date.date() >= maturity.date() {
+            return Ok((0.0, 0.0));
+        }
+        let base_discount = self
+            .base_currency_curve
+            .borrow()
+            .get_discount_factor_at_date(maturity)?;
+        let quote_discount = self
+            .quote_currency_curve
+            .borrow()
+            .get_discount_factor_at_date(maturity)?;
+        let fx = self.fx.borrow().get_value();
+        let forward = fx * base_discount / quote_discount;
+        let strike = fx_option.get_strike()?;
+        let t = self
+            .time_calculator
+            .get_time_difference(&eval_date, maturity);
+        let total_deviation = self
+            .volatility
+            .borrow()
+            .total_deviation(t, strike / forward)?;
+
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        let (base_weight, quote_weight) = if total_deviation <= 0.0 {
+            // no volatility left, which is the intrinsic value on the forward
+            let in_the_money = match fx_option.get_option_type()? {
+                OptionType::Call => forward > strike,
+                OptionType::Put => forward < strike,
+            };
+            if in_the_money {
+                (1.0, 1.0)
+            } else {
+                (0.0, 0.0)
+            }
+        } else {
+            let d1 = ((forward / strike).ln() as f64 + 0.5 * (total_deviation as f64).powi(2))
+                / total_deviation as f64;
+            let d2 = d1 - total_deviation as f64;
+            match fx_option.get_option_type()? {
+                OptionType::Call => (normal.cdf(d1) as Real, normal.cdf(d2) as Real),
+                OptionType::Put => (normal.cdf(-d1) as Real, normal.cdf(-d2) as Real),
+            }
+        };
+        match fx_option.get_option_type()? {
+            OptionType::Call => Ok((
+                base_discount * base_weight,
+                -quote_discount * strike * quote_weight,
+            )),
+            OptionType::Put => Ok((
+                -base_discount * base_weight,
+                quote_discount * strike * quote_weight,
+            )),
+        }
+    }
+}
+
+impl PricerTrait for FxOptionPricer {
+    fn npv(&self, instrument: &Instrument) -> Result<Real> {
+        let fx_option = self.get_fx_option(instrument)?;
+        let (base, quote) = self.legs(fx_option)?;
+        Ok(self.fx.borrow().get_value() * base + quote)
+    }
+
+    fn npv_result(&self, instrument: &Instrument) -> Result<NpvResult> {
+        let npv = self.npv(instrument)?;
+        Ok(NpvResult::new_from_npv(npv))
+    }
+
+    fn fx_exposure(&self, instrument: &Instrument, _npv: Real) -> Result<HashMap<Currency, Real>> {
+        let fx_option = self.get_fx_option(instrument)?;
+        let (base, quote) = self.legs(fx_option)?;
+        let unit_notional = instrument.get_unit_notional();
+        let mut res: HashMap<Currency, Real> = HashMap::new();
+        res.insert(
+            *fx_option.get_fx_code().get_currency1(),
+            base * unit_notional,
+        );
+        res.insert(
+            *fx_option.get_fx_code().get_currency2(),
+            quote * unit_notional,
+        );
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::FxCode;
+    use crate::data::fx_volatility_quote_data::FxVolatilityQuoteData;
+    use crate::enums::{FxAtmType, FxDeltaType};
+    use crate::parameters::volatilities::{
+        constant_volatility::ConstantVolatility, fx_volatility_surface::FxVolatilitySurface,
+    };
+    use crate::utils::test_data::flat_zero_curve;
+    use ndarray::array;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_fx_option_pricer() -> Result<()> {
+        let dt = datetime!(2024-01-02 16:00:00 +09:00);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(dt)));
+        let fx = Rc::new(RefCell::new(MarketPrice::new(
+            1300.0,
+            dt,
+            None,
+            Currency::KRW,
+            "USDKRW".to_string(),
+            "USDKRW".to_string(),
+        )));
+        let curve =
+            |rate: Real, name: &str| flat_zero_curve(&evaluation_date, rate, Currency::KRW, name);
+        let usd_curve = curve(0.05, "USDOIS")?;
+        let krw_curve = curve(0.035, "KRWCRS")?;
+        let constant = Rc::new(RefCell::new(Volatility::ConstantVolatility(
+            ConstantVolatility::new(0.1, "USDKRW".to_string(), "USDKRW".to_string()),
+        )));
+        let pricer = FxOptionPricer::new(
+            evaluation_date.clone(),
+            fx.clone(),
+            usd_curve.clone(),
+            krw_curve.clone(),
+            constant,
+        );
+
+        let fx_code = FxCode::new(Currency::USD, Currency::KRW);
+        let maturity = datetime!(2024-07-02 16:00:00 +09:00);
+        let option = |strike: Real, option_type: OptionType| {
+            Instrument::FxOption(FxOption::new(
+                fx_code,
+                strike,
+                option_type,
+                dt,
+                maturity,
+                1_000_000.0,
+                "USDKRW 6M".to_string(),
+                "USDKRW 6M".to_string(),
+            ))
+        };
+        let call = option(1300.0, OptionType::Call);
+        let put = option(1300.0, OptionType::Put);
+
+        let base_discount = usd_curve.borrow().get_discount_factor_at_date(&maturity)?;
+        let quote_discount = krw_curve.borrow().get_discount_factor_at_date(&maturity)?;
+        let forward = 1300.0 * base_discount / quote_discount;
+        let t = NullCalendar::new().get_time_difference(&dt, &maturity);
+        let s = 0.1 * t.sqrt();
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        let d1 = ((forward / 1300.0).ln() + 0.5 * s * s) / s;
+        let d2 = d1 - s;
+        let expected = quote_discount
+            * (forward * normal.cdf(d1 as f64) as Real - 1300.0 * normal.cdf(d2 as f64) as Real);
+        let npv = pricer.npv(&call)?;
+        assert!(
+            (npv - expected).abs() < 1.0e-2,
+            "npv = {}, expected = {}",
+            npv,
+            expected
+        );
+
+        // put-call parity
+        let parity = 1300.0 * base_discount - 1300.0 * quote_discount;
+        assert!((npv - pricer.npv(&put)? - parity).abs() < 1.0e-2);
+
+        // the exposures are the delta-equivalent legs
+        let exposure = pricer.fx_exposure(&call, npv)?;
+        assert!(exposure[&Currency::USD] > 0.0 && exposure[&Currency::KRW] < 0.0);
+        let total = 1300.0 * exposure[&Currency::USD] + exposure[&Currency::KRW];
+        assert!((total / 1_000_000.0 - npv).abs() < 1.0e-2);
+
+        // on the delta-quoted surface, the premium is priced at the volatility of the strike
+        let quotes = FxVolatilityQuoteData::new(
+            vec![
+                datetime!(2024-04-02 16:00:00 +09:00),
+                datetime!(2025-01-02 16:00:00 +09:00),
+            ],
+            array![0.08, 0.09],
+            array![0.01, 0.015],
+            array![0.003, 0.004],
+            FxDeltaType::SpotPremiumAdjusted,
+            FxAtmType::DeltaNeutralStraddle,
+            Some(dt),
+            fx_code,
+            "USDKRW vol".to_string(),
+            "USDKRW vol".to_string(),
+        );
+        let surface = Rc::new(RefCell::new(Volatility::FxVolatilitySurface(
+            FxVolatilitySurface::new(
+                evaluation_date.clone(),
+                usd_curve.clone(),
+                &quotes,
+                "USDKRW".to_string(),
+                "USDKRW".to_string(),
+            )?,
+        )));
+        let pricer = FxOptionPricer::new(
+            evaluation_date.clone(),
+            fx.clone(),
+            usd_curve,
+            krw_curve,
+            surface.clone(),
+        );
+        let high_strike = option(1400.0, OptionType::Call);
+        let vol = surface.borrow().get_value(t, 1400.0 / forward);
+        let s = vol * t.sqrt();
+        let d1 = ((forward / 1400.0).ln() + 0.5 * s * s) / s;
+        let d2 = d1 - s;
+        let expected = quote_discount
+            * (forward * normal.cdf(d1 as f64) as Real - 1400.0 * normal.cdf(d2 as f64) as Real);
+        let npv = pricer.npv(&high_strike)?;
+        assert!((npv - expected).abs() < 1.0e-2, "npv = {}", npv);
+        // the risk reversal lifts the out-of-the-money calls above the at-the-money volatility
+        assert!(vol > surface.borrow().get_value(t, 1.0));
+
+        // expired
+        evaluation_date.borrow_mut().set_date(maturity);
+        assert_eq!(pricer.npv(&high_strike)?, 0.0);
+        Ok(())
+    }
+}
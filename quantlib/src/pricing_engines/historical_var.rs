@@ -0,0 +1,1455 @@
+use crate::currency::{Currency, FxCode};
+use crate::data::{
+    daily_value_data::DailyValueData, fx_volatility_quote_data::FxVolatilityQuoteData,
+    market_data_history::MarketDataHistory, rate_volatility_data::RateVolatilityData,
+    surface_data::SurfaceData, svi_parameter_data::SviParameterData, value_data::ValueData,
+    vector_data::VectorData,
+};
+use crate::definitions::{Real, DELTA_PNL_UNIT, RHO_PNL_UNIT, VEGA_PNL_UNIT};
+use crate::enums::VarMethod;
+use crate::instrument::{Instrument, InstrumentTrait, Instruments};
+use crate::math::interpolator::{ExtraPolationType, InterpolatorReal1D};
+use crate::math::interpolators::linear_interpolator::LinearInterpolator1D;
+use crate::pricing_engines::{
+    calculation_configuration::CalculationConfiguration,
+    calculation_result::CalculationResult,
+    engine_generator::{EngineGenerator, InstrumentCategory},
+    match_parameter::MatchParameter,
+    scenario_market_data::ScenarioMarketData,
+};
+//
+use anyhow::{anyhow, Context, Result};
+use ndarray::{Array1, Array2, Array3};
+use rayon::prelude::*;
+use std::{collections::HashMap, fmt, rc::Rc};
+use time::OffsetDateTime;
+
+/// floor of the shocked volatilities
+const MIN_VOLATILITY: Real = 1.0e-4;
+
+/// Risk factors of the P&L attribution in HistoricalVarResult.
+/// Spot: a stock price or an fx rate keyed as its delta, e.g., "005930" and "USDKRW".
+/// The fx rates also take the translation of the values into the reporting currency.
+/// Volatility: an equity or fx volatility keyed as its vega.
+/// Curve: a parallel move of the curve keyed as its rho.
+/// Residual: the P&L of the full revaluation which is not explained by the Greeks.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RiskFactor {
+    Spot(String),
+    Volatility(String),
+    Curve(String),
+    Residual,
+}
+
+impl fmt::Display for RiskFactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskFactor::Spot(code) => write!(f, "{}", code),
+            RiskFactor::Volatility(code) => write!(f, "{} vol", code),
+            RiskFactor::Curve(code) => write!(f, "{} curve", code),
+            RiskFactor::Residual => write!(f, "residual"),
+        }
+    }
+}
+
+/// changes of (atm, risk reversal, butterfly) on the expiries
+type FxVolatilityQuoteShift = (Array1<Real>, Array1<Real>, Array1<Real>);
+
+/// Changes of the market data from start_date to end_date in MarketDataHistory.
+/// The fx rates and the stock prices move by their relative returns,
+/// and the curves and the volatilities by their absolute changes.
+/// The curve changes are taken on the times of the shocked curve.
+/// The fx volatility quotes move by the changes of the ATM, the risk reversal and the butterfly
+/// on each expiry, and the rate volatility cubes by the changes on each point.
+/// The market data which is not in the history is held at the base.
+#[derive(Debug, Clone)]
+pub struct HistoricalScenario {
+    start_date: OffsetDateTime,
+    end_date: OffsetDateTime,
+    fx_returns: HashMap<FxCode, Real>,
+    stock_returns: HashMap<String, Real>,
+    curve_moves: HashMap<String, (LinearInterpolator1D, LinearInterpolator1D)>,
+    equity_volatility_shifts: HashMap<String, Real>,
+    equity_volatility_surface_shifts: HashMap<String, Array2<Real>>,
+    fx_volatility_shifts: HashMap<FxCode, Real>,
+    fx_volatility_quote_shifts: HashMap<FxCode, FxVolatilityQuoteShift>,
+    rate_volatility_shifts: HashMap<String, Array3<Real>>,
+}
+
+impl HistoricalScenario {
+    pub fn new(history: &MarketDataHistory, start: usize, end: usize) -> Result<Self> {
+        let dates = history.get_dates();
+        if start >= end || end >= dates.len() {
+            return Err(anyhow!(
+                "({}:{}) invalid scenario from {} to {} on {} dates",
+                file!(),
+                line!(),
+                start,
+                end,
+                dates.len()
+            ));
+        }
+
+        let relative_return = |series: &Vec<ValueData>| -> Result<Real> {
+            let start_value = series[start].get_value();
+            if start_value == 0.0 {
+                return Err(anyhow!(
+                    "({}:{}) {} is zero on {:?}",
+                    file!(),
+                    line!(),
+                    series[start].get_name(),
+                    dates[start]
+                ));
+            }
+            Ok(series[end].get_value() / start_value - 1.0)
+        };
+        let curve_interpolator = |data: &VectorData| -> Result<LinearInterpolator1D> {
+            LinearInterpolator1D::new(
+                data.get_times_clone(),
+                data.get_value_clone(),
+                ExtraPolationType::Flat,
+                true,
+            )
+        };
+
+        let mut fx_returns = HashMap::new();
+        for (fx_code, series) in history.get_fx_data() {
+            fx_returns.insert(*fx_code, relative_return(series)?);
+        }
+        let mut stock_returns = HashMap::new();
+        for (code, series) in history.get_stock_data() {
+            stock_returns.insert(code.clone(), relative_return(series)?);
+        }
+        let mut curve_moves = HashMap::new();
+        for (name, series) in history.get_curve_data() {
+            let moves = (
+                curve_interpolator(&series[start])?,
+                curve_interpolator(&series[end])?,
+            );
+            curve_moves.insert(name.clone(), moves);
+        }
+        let mut equity_volatility_shifts = HashMap::new();
+        for (code, series) in history.get_equity_constant_volatility_data() {
+            let shift = series[end].get_value() - series[start].get_value();
+            equity_volatility_shifts.insert(code.clone(), shift);
+        }
+        let mut equity_volatility_surface_shifts = HashMap::new();
+        for (code, series) in history.get_equity_volatility_surface_data() {
+            let shift = series[end].get_value() - series[start].get_value();
+            equity_volatility_surface_shifts.insert(code.clone(), shift);
+        }
+        let mut fx_volatility_shifts = HashMap::new();
+        for (fx_code, series) in history.get_fx_constant_volatility_data() {
+            let shift = series[end].get_value() - series[start].get_value();
+            fx_volatility_shifts.insert(*fx_code, shift);
+        }
+        let mut fx_volatility_quote_shifts = HashMap::new();
+        for (fx_code, series) in history.get_fx_volatility_quote_data() {
+            let shifts = (
+                series[end].get_atm() - series[start].get_atm(),
+                series[end].get_risk_reversal_25() - series[start].get_risk_reversal_25(),
+                series[end].get_butterfly_25() - series[start].get_butterfly_25(),
+            );
+            fx_volatility_quote_shifts.insert(*fx_code, shifts);
+        }
+        let mut rate_volatility_shifts = HashMap::new();
+        for (code, series) in history.get_rate_volatility_data() {
+            let shift = series[end].get_value() - series[start].get_value();
+            rate_volatility_shifts.insert(code.clone(), shift);
+        }
+
+        Ok(HistoricalScenario {
+            start_date: dates[start],
+            end_date: dates[end],
+            fx_returns,
+            stock_returns,
+            curve_moves,
+            equity_volatility_shifts,
+            equity_volatility_surface_shifts,
+            fx_volatility_shifts,
+            fx_volatility_quote_shifts,
+            rate_volatility_shifts,
+        })
+    }
+
+    pub fn get_start_date(&self) -> OffsetDateTime {
+        self.start_date
+    }
+
+    pub fn get_end_date(&self) -> OffsetDateTime {
+        self.end_date
+    }
+
+    /// relative return of the stock or the fx rate keyed as the delta
+    pub fn get_spot_return(&self, code: &str) -> Option<Real> {
+        if let Some(r) = self.stock_returns.get(code) {
+            return Some(*r);
+        }
+        self.fx_returns
+            .iter()
+            .find(|(fx_code, _)| fx_code.to_string() == code)
+            .map(|(_, r)| *r)
+    }
+
+    /// change of the curve on the given times
+    pub fn get_curve_shift(
+        &self,
+        name: &str,
+        times: &Array1<Real>,
+    ) -> Result<Option<Array1<Real>>> {
+        let Some((start, end)) = self.curve_moves.get(name) else {
+            return Ok(None);
+        };
+        let mut shift = Array1::zeros(times.len());
+        for (i, t) in times.iter().enumerate() {
+            shift[i] = end.interpolate(*t)? - start.interpolate(*t)?;
+        }
+        Ok(Some(shift))
+    }
+
+    /// change of the equity or fx volatility keyed as the vega.
+    /// The surfaces move by the average change on the grid,
+    /// and the fx volatility quotes by the average change of the ATM.
+    fn get_volatility_shift(&self, code: &str) -> Option<Real> {
+        if let Some(shift) = self.equity_volatility_shifts.get(code) {
+            return Some(*shift);
+        }
+        if let Some(shift) = self.equity_volatility_surface_shifts.get(code) {
+            return shift.mean();
+        }
+        if let Some((_, (atm, _, _))) = self
+            .fx_volatility_quote_shifts
+            .iter()
+            .find(|(fx_code, _)| fx_code.to_string() == code)
+        {
+            return atm.mean();
+        }
+        self.fx_volatility_shifts
+            .iter()
+            .find(|(fx_code, _)| fx_code.to_string() == code)
+            .map(|(_, shift)| *shift)
+    }
+
+    /// parallel move of the curve, i.e., the average change on the times of the base curve
+    fn get_parallel_curve_shift(
+        &self,
+        name: &str,
+        market_data: &ScenarioMarketData,
+    ) -> Result<Option<Real>> {
+        let Some(curve) = market_data.curve_data.get(name) else {
+            return Ok(None);
+        };
+        Ok(self
+            .get_curve_shift(name, &curve.get_times_clone())?
+            .and_then(|shift| shift.mean()))
+    }
+
+    fn apply(&self, market_data: &ScenarioMarketData) -> Result<ScenarioMarketData> {
+        let shock_value = |data: &ValueData, value: Real| -> Result<ValueData> {
+            ValueData::new(
+                value,
+                *data.get_market_datetime(),
+                *data.get_currency(),
+                data.get_name().clone(),
+                data.get_code().clone(),
+            )
+        };
+
+        let mut res = market_data.clone();
+        for (fx_code, data) in res.fx_data.iter_mut() {
+            if let Some(r) = self.fx_returns.get(fx_code) {
+                *data = shock_value(data, data.get_value() * (1.0 + r))?;
+            }
+        }
+        for (code, data) in res.stock_data.iter_mut() {
+            if let Some(r) = self.stock_returns.get(code) {
+                *data = shock_value(data, data.get_value() * (1.0 + r))?;
+            }
+        }
+        for (name, data) in res.curve_data.iter_mut() {
+            let times = data.get_times_clone();
+            if let Some(shift) = self.get_curve_shift(name, &times)? {
+                *data = VectorData::new(
+                    data.get_value_clone() + shift,
+                    data.get_dates_clone(),
+                    Some(times),
+                    data.get_market_datetime(),
+                    *data.get_currency(),
+                    data.get_name_clone(),
+                    data.get_code_clone(),
+                )
+                .with_context(|| format!("({}:{}) failed to shock {}", file!(), line!(), name))?;
+            }
+        }
+        for (code, data) in res.equity_constant_volatility_data.iter_mut() {
+            if let Some(shift) = self.equity_volatility_shifts.get(code) {
+                *data = shock_value(data, (data.get_value() + shift).max(MIN_VOLATILITY))?;
+            }
+        }
+        for (code, data) in res.equity_volatility_surface_data.iter_mut() {
+            if let Some(shift) = self.equity_volatility_surface_shifts.get(code) {
+                if shift.dim() != data.get_value().dim() {
+                    return Err(anyhow!(
+                        "({}:{}) the history of the volatility surface {} is {:?}, but the surface is {:?}",
+                        file!(),
+                        line!(),
+                        code,
+                        shift.dim(),
+                        data.get_value().dim()
+                    ));
+                }
+                let value = (data.get_value() + shift).mapv(|v| v.max(MIN_VOLATILITY));
+                *data = SurfaceData::new(
+                    data.get_spot(),
+                    value,
+                    data.get_dates().clone(),
+                    data.get_strike().clone(),
+                    data.get_market_datetime(),
+                    *data.get_currency(),
+                    data.get_name().to_string(),
+                    data.get_code().to_string(),
+                );
+            }
+        }
+        for (fx_code, data) in res.fx_constant_volatility_data.iter_mut() {
+            if let Some(shift) = self.fx_volatility_shifts.get(fx_code) {
+                *data = shock_value(data, (data.get_value() + shift).max(MIN_VOLATILITY))?;
+            }
+        }
+        for (fx_code, data) in res.fx_volatility_quote_data.iter_mut() {
+            if let Some((atm, risk_reversal, butterfly)) =
+                self.fx_volatility_quote_shifts.get(fx_code)
+            {
+                if atm.len() != data.get_atm().len()
+                    || risk_reversal.len() != data.get_risk_reversal_25().len()
+                    || butterfly.len() != data.get_butterfly_25().len()
+                {
+                    return Err(anyhow!(
+                        "({}:{}) the history of the fx volatility quotes {} has {} expiries, but the quotes have {}",
+                        file!(),
+                        line!(),
+                        fx_code,
+                        atm.len(),
+                        data.get_atm().len()
+                    ));
+                }
+                *data = FxVolatilityQuoteData::new(
+                    data.get_expiry_dates().clone(),
+                    (data.get_atm() + atm).mapv(|v| v.max(MIN_VOLATILITY)),
+                    data.get_risk_reversal_25() + risk_reversal,
+                    data.get_butterfly_25() + butterfly,
+                    data.get_delta_type(),
+                    data.get_atm_type(),
+                    data.get_market_datetime(),
+                    *data.get_fx_code(),
+                    data.get_name().to_string(),
+                    data.get_code().to_string(),
+                )
+                .with_strangle_type(data.get_strangle_type());
+            }
+        }
+        for (code, data) in res.rate_volatility_data.iter_mut() {
+            if let Some(shift) = self.rate_volatility_shifts.get(code) {
+                if shift.dim() != data.get_value().dim() {
+                    return Err(anyhow!(
+                        "({}:{}) the history of the rate volatility {} is {:?}, but the cube is {:?}",
+                        file!(),
+                        line!(),
+                        code,
+                        shift.dim(),
+                        data.get_value().dim()
+                    ));
+                }
+                *data = RateVolatilityData::new(
+                    (data.get_value() + shift).mapv(|v| v.max(MIN_VOLATILITY)),
+                    data.get_expiry_dates().clone(),
+                    data.get_tenors().clone(),
+                    data.get_strikes().clone(),
+                    data.get_volatility_type(),
+                    data.get_shift(),
+                    data.get_market_datetime(),
+                    *data.get_currency(),
+                    data.get_name().to_string(),
+                    data.get_code().to_string(),
+                );
+            }
+        }
+        Ok(res)
+    }
+}
+
+/// P&Ls of one scenario in the reporting currency
+struct ScenarioPnl {
+    portfolio: Real,
+    instruments: HashMap<String, Real>,
+    risk_factors: HashMap<RiskFactor, Real>,
+}
+
+/// VaR and expected shortfall (ES) of the historical scenarios with their contributions.
+/// The VaR is the loss of the scenario at the confidence level, i.e.,
+/// the k-th worst P&L where k = ceil((1 - confidence_level) * the number of scenarios),
+/// and the ES is the average loss of the k worst scenarios.
+/// The contributions are the losses of the instruments and the risk factors
+/// in the VaR scenario and the average in the k worst scenarios respectively,
+/// so that they add up to the VaR and the ES.
+/// The P&Ls and the losses are in the reporting currency.
+#[derive(Debug, Clone)]
+pub struct HistoricalVarResult {
+    var_method: VarMethod,
+    confidence_level: Real,
+    horizon_days: usize,
+    reporting_currency: Currency,
+    value: Real,
+    var: Real,
+    expected_shortfall: Real,
+    scenario_dates: Vec<(OffsetDateTime, OffsetDateTime)>,
+    portfolio_pnls: Vec<Real>,
+    instrument_pnls: HashMap<String, Vec<Real>>,
+    risk_factor_pnls: HashMap<RiskFactor, Vec<Real>>,
+    var_scenario_index: usize,
+    tail_scenario_indices: Vec<usize>,
+    instrument_var_contributions: HashMap<String, Real>,
+    instrument_es_contributions: HashMap<String, Real>,
+    risk_factor_var_contributions: HashMap<RiskFactor, Real>,
+    risk_factor_es_contributions: HashMap<RiskFactor, Real>,
+}
+
+impl HistoricalVarResult {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        var_method: VarMethod,
+        confidence_level: Real,
+        horizon_days: usize,
+        reporting_currency: Currency,
+        value: Real,
+        scenario_dates: Vec<(OffsetDateTime, OffsetDateTime)>,
+        portfolio_pnls: Vec<Real>,
+        instrument_pnls: HashMap<String, Vec<Real>>,
+        risk_factor_pnls: HashMap<RiskFactor, Vec<Real>>,
+    ) -> HistoricalVarResult {
+        let n = portfolio_pnls.len();
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&i, &j| portfolio_pnls[i].total_cmp(&portfolio_pnls[j]));
+        let tail_number = (((1.0 - confidence_level) * n as Real).ceil() as usize).clamp(1, n);
+        let tail_scenario_indices = order[..tail_number].to_vec();
+        let var_scenario_index = order[tail_number - 1];
+
+        let var_contribution = |pnls: &Vec<Real>| -pnls[var_scenario_index];
+        let es_contribution = |pnls: &Vec<Real>| {
+            -tail_scenario_indices.iter().map(|&i| pnls[i]).sum::<Real>() / tail_number as Real
+        };
+
+        HistoricalVarResult {
+            var_method,
+            confidence_level,
+            horizon_days,
+            reporting_currency,
+            value,
+            var: var_contribution(&portfolio_pnls),
+            expected_shortfall: es_contribution(&portfolio_pnls),
+            instrument_var_contributions: instrument_pnls
+                .iter()
+                .map(|(code, pnls)| (code.clone(), var_contribution(pnls)))
+                .collect(),
+            instrument_es_contributions: instrument_pnls
+                .iter()
+                .map(|(code, pnls)| (code.clone(), es_contribution(pnls)))
+                .collect(),
+            risk_factor_var_contributions: risk_factor_pnls
+                .iter()
+                .map(|(factor, pnls)| (factor.clone(), var_contribution(pnls)))
+                .collect(),
+            risk_factor_es_contributions: risk_factor_pnls
+                .iter()
+                .map(|(factor, pnls)| (factor.clone(), es_contribution(pnls)))
+                .collect(),
+            scenario_dates,
+            portfolio_pnls,
+            instrument_pnls,
+            risk_factor_pnls,
+            var_scenario_index,
+            tail_scenario_indices,
+        }
+    }
+
+    pub fn get_var_method(&self) -> VarMethod {
+        self.var_method
+    }
+
+    pub fn get_confidence_level(&self) -> Real {
+        self.confidence_level
+    }
+
+    pub fn get_horizon_days(&self) -> usize {
+        self.horizon_days
+    }
+
+    pub fn get_reporting_currency(&self) -> Currency {
+        self.reporting_currency
+    }
+
+    /// value of the portfolio on the base market data
+    pub fn get_value(&self) -> Real {
+        self.value
+    }
+
+    pub fn get_var(&self) -> Real {
+        self.var
+    }
+
+    pub fn get_expected_shortfall(&self) -> Real {
+        self.expected_shortfall
+    }
+
+    /// (start, end) dates of the history of the scenarios
+    pub fn get_scenario_dates(&self) -> &Vec<(OffsetDateTime, OffsetDateTime)> {
+        &self.scenario_dates
+    }
+
+    pub fn get_portfolio_pnls(&self) -> &Vec<Real> {
+        &self.portfolio_pnls
+    }
+
+    /// instrument code -> P&Ls of the scenarios
+    pub fn get_instrument_pnls(&self) -> &HashMap<String, Vec<Real>> {
+        &self.instrument_pnls
+    }
+
+    pub fn get_risk_factor_pnls(&self) -> &HashMap<RiskFactor, Vec<Real>> {
+        &self.risk_factor_pnls
+    }
+
+    pub fn get_var_scenario_index(&self) -> usize {
+        self.var_scenario_index
+    }
+
+    /// the worst scenarios from the worst
+    pub fn get_tail_scenario_indices(&self) -> &Vec<usize> {
+        &self.tail_scenario_indices
+    }
+
+    pub fn get_instrument_var_contributions(&self) -> &HashMap<String, Real> {
+        &self.instrument_var_contributions
+    }
+
+    pub fn get_instrument_es_contributions(&self) -> &HashMap<String, Real> {
+        &self.instrument_es_contributions
+    }
+
+    pub fn get_risk_factor_var_contributions(&self) -> &HashMap<RiskFactor, Real> {
+        &self.risk_factor_var_contributions
+    }
+
+    pub fn get_risk_factor_es_contributions(&self) -> &HashMap<RiskFactor, Real> {
+        &self.risk_factor_es_contributions
+    }
+}
+
+/// Historical-simulation VaR and ES of the instruments on the evaluation date.
+/// The scenarios are the overlapping changes over horizon_days dates of MarketDataHistory
+/// applied to the base market data (see HistoricalScenario).
+/// The dividends, the correlations and the other data are held at the base.
+/// The SVI parameters have no history, so the instruments on them are not supported.
+///
+/// FullRevaluation reprices the instruments on each scenario through EngineGenerator,
+/// and DeltaGamma approximates the P&Ls by the delta, gamma, vega and rho on the base.
+/// The scenarios run in parallel as the instrument groups in EngineGenerator.
+/// In both methods, the P&Ls are attributed to the risk factors by the Greeks,
+/// and the rest of the full revaluation is the residual.
+pub struct HistoricalVarEngine {
+    instruments: Vec<Instrument>,
+    instrument_categories: Vec<InstrumentCategory>,
+    //
+    calculation_configuration: CalculationConfiguration,
+    evaluation_datetime: OffsetDateTime,
+    match_parameter: MatchParameter,
+    // data
+    market_data: ScenarioMarketData,
+    dividend_data: HashMap<String, VectorData>,
+    past_daily_value_data: HashMap<String, DailyValueData>,
+    equity_correlation_data: HashMap<(String, String), ValueData>,
+    market_data_history: MarketDataHistory,
+    // var
+    confidence_level: Real,
+    horizon_days: usize,
+    var_method: VarMethod,
+    reporting_currency: Currency,
+    //
+    var_result: Option<HistoricalVarResult>,
+}
+
+impl Default for HistoricalVarEngine {
+    fn default() -> Self {
+        HistoricalVarEngine {
+            instruments: vec![],
+            instrument_categories: vec![],
+            //
+            calculation_configuration: CalculationConfiguration::default(),
+            evaluation_datetime: OffsetDateTime::now_utc(),
+            match_parameter: MatchParameter::default(),
+            //
+            market_data: ScenarioMarketData::default(),
+            dividend_data: HashMap::new(),
+            past_daily_value_data: HashMap::new(),
+            equity_correlation_data: HashMap::new(),
+            market_data_history: MarketDataHistory::default(),
+            //
+            confidence_level: 0.99,
+            horizon_days: 1,
+            var_method: VarMethod::default(),
+            reporting_currency: Currency::KRW,
+            //
+            var_result: None,
+        }
+    }
+}
+
+impl HistoricalVarEngine {
+    pub fn builder() -> HistoricalVarEngine {
+        HistoricalVarEngine::default()
+    }
+
+    pub fn with_configuration(
+        &mut self,
+        calculation_configuration: CalculationConfiguration,
+        evalutation_datetime: OffsetDateTime,
+        match_parameter: MatchParameter,
+    ) -> Result<&mut Self> {
+        self.calculation_configuration = calculation_configuration;
+        self.evaluation_datetime = evalutation_datetime;
+        self.match_parameter = match_parameter;
+        Ok(self)
+    }
+
+    pub fn with_instruments(&mut self, instruments: Instruments) -> Result<&mut Self> {
+        self.instruments = instruments
+            .iter()
+            .map(|instrument| instrument.as_ref().clone())
+            .collect();
+        Ok(self)
+    }
+
+    /// the instrument groups of EngineGenerator. All the instruments are in one group if not given.
+    pub fn with_instrument_categories(
+        &mut self,
+        instrument_categories: Vec<InstrumentCategory>,
+    ) -> Result<&mut Self> {
+        self.instrument_categories = instrument_categories;
+        Ok(self)
+    }
+
+    /// the base market data as in EngineGenerator
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_data(
+        &mut self,
+        fx_data: HashMap<FxCode, ValueData>,
+        stock_data: HashMap<String, ValueData>,
+        curve_data: HashMap<String, VectorData>,
+        dividend_data: HashMap<String, VectorData>,
+        equity_constant_volatility_data: HashMap<String, ValueData>,
+        equity_volatility_surface_data: HashMap<String, SurfaceData>,
+        fx_constant_volatility_data: HashMap<FxCode, ValueData>,
+        quanto_correlation_data: HashMap<(String, FxCode), ValueData>,
+        past_daily_value_data: HashMap<String, DailyValueData>,
+    ) -> Result<&mut Self> {
+        self.market_data.fx_data = fx_data;
+        self.market_data.stock_data = stock_data;
+        self.market_data.curve_data = curve_data;
+        self.market_data.equity_constant_volatility_data = equity_constant_volatility_data;
+        self.market_data.equity_volatility_surface_data = equity_volatility_surface_data;
+        self.market_data.fx_constant_volatility_data = fx_constant_volatility_data;
+        self.market_data.quanto_correlation_data = quanto_correlation_data;
+        self.dividend_data = dividend_data;
+        self.past_daily_value_data = past_daily_value_data;
+        Ok(self)
+    }
+
+    pub fn with_svi_parameter_data(
+        &mut self,
+        svi_parameter_data: HashMap<String, SviParameterData>,
+    ) -> Result<&mut Self> {
+        self.market_data.svi_parameter_data = svi_parameter_data;
+        Ok(self)
+    }
+
+    pub fn with_equity_correlation_data(
+        &mut self,
+        equity_correlation_data: HashMap<(String, String), ValueData>,
+    ) -> Result<&mut Self> {
+        self.equity_correlation_data = equity_correlation_data;
+        Ok(self)
+    }
+
+    pub fn with_rate_volatility_data(
+        &mut self,
+        rate_volatility_data: HashMap<String, RateVolatilityData>,
+    ) -> Result<&mut Self> {
+        self.market_data.rate_volatility_data = rate_volatility_data;
+        Ok(self)
+    }
+
+    pub fn with_fx_volatility_quote_data(
+        &mut self,
+        fx_volatility_quote_data: HashMap<FxCode, FxVolatilityQuoteData>,
+    ) -> Result<&mut Self> {
+        self.market_data.fx_volatility_quote_data = fx_volatility_quote_data;
+        Ok(self)
+    }
+
+    pub fn with_market_data_history(
+        &mut self,
+        market_data_history: MarketDataHistory,
+    ) -> Result<&mut Self> {
+        self.market_data_history = market_data_history;
+        Ok(self)
+    }
+
+    pub fn with_var_parameters(
+        &mut self,
+        confidence_level: Real,
+        horizon_days: usize,
+        var_method: VarMethod,
+        reporting_currency: Currency,
+    ) -> Result<&mut Self> {
+        if confidence_level <= 0.0 || confidence_level >= 1.0 {
+            return Err(anyhow!(
+                "({}:{}) confidence_level must be in (0, 1), got {}",
+                file!(),
+                line!(),
+                confidence_level
+            ));
+        }
+        if horizon_days == 0 {
+            return Err(anyhow!(
+                "({}:{}) horizon_days must be positive",
+                file!(),
+                line!()
+            ));
+        }
+        self.confidence_level = confidence_level;
+        self.horizon_days = horizon_days;
+        self.var_method = var_method;
+        self.reporting_currency = reporting_currency;
+        Ok(self)
+    }
+
+    /// calculation results of the instruments on the market data through EngineGenerator
+    fn revalue(
+        &self,
+        calculation_configuration: CalculationConfiguration,
+        market_data: ScenarioMarketData,
+    ) -> Result<HashMap<String, CalculationResult>> {
+        let instruments = Instruments::new(
+            self.instruments
+                .iter()
+                .map(|instrument| Rc::new(instrument.clone()))
+                .collect(),
+        );
+        let instrument_categories = if self.instrument_categories.is_empty() {
+            vec![InstrumentCategory::default()]
+        } else {
+            self.instrument_categories.clone()
+        };
+
+        let mut engine_generator = EngineGenerator::builder();
+        engine_generator
+            .with_configuration(
+                calculation_configuration,
+                self.evaluation_datetime,
+                self.match_parameter.clone(),
+            )?
+            .with_instruments(instruments)?
+            .with_instrument_categories(instrument_categories)?
+            .with_data(
+                market_data.fx_data,
+                market_data.stock_data,
+                market_data.curve_data,
+                self.dividend_data.clone(),
+                market_data.equity_constant_volatility_data,
+                market_data.equity_volatility_surface_data,
+                market_data.fx_constant_volatility_data,
+                market_data.quanto_correlation_data,
+                self.past_daily_value_data.clone(),
+            )?
+            .with_svi_parameter_data(market_data.svi_parameter_data)?
+            .with_equity_correlation_data(self.equity_correlation_data.clone())?
+            .with_rate_volatility_data(market_data.rate_volatility_data)?
+            .with_fx_volatility_quote_data(market_data.fx_volatility_quote_data)?;
+        engine_generator.distribute_instruments()?;
+        engine_generator.calculate()?;
+        Ok(engine_generator.get_calculation_results().clone())
+    }
+
+    /// amount of the reporting currency for one unit of the currency on the fx data,
+    /// and the fx code of the conversion
+    fn conversion_rate(
+        &self,
+        fx_data: &HashMap<FxCode, ValueData>,
+        currency: Currency,
+    ) -> Result<(Real, Option<String>)> {
+        if currency == self.reporting_currency {
+            return Ok((1.0, None));
+        }
+        let fx_code = FxCode::new(currency, self.reporting_currency);
+        if let Some(fx) = fx_data.get(&fx_code) {
+            return Ok((fx.get_value(), Some(fx_code.to_string())));
+        }
+        let fx_code = fx_code.reciprocal();
+        if let Some(fx) = fx_data.get(&fx_code) {
+            return Ok((1.0 / fx.get_value(), Some(fx_code.to_string())));
+        }
+        Err(anyhow!(
+            "({}:{}) there is no fx rate from {} to {}",
+            file!(),
+            line!(),
+            currency,
+            self.reporting_currency
+        ))
+    }
+
+    /// P&Ls of the instrument in its currency approximated by the Greeks on the scenario
+    fn greek_pnls(
+        &self,
+        instrument: &Instrument,
+        result: &CalculationResult,
+        scenario: &HistoricalScenario,
+    ) -> Result<HashMap<RiskFactor, Real>> {
+        let mut res: HashMap<RiskFactor, Real> = HashMap::new();
+        if let Some(delta) = result.get_delta() {
+            let gamma = result.get_gamma();
+            for (code, delta) in delta {
+                // the delta of stocks and futures is keyed by the instrument code
+                let found = scenario
+                    .get_spot_return(code)
+                    .map(|r| (code.clone(), r))
+                    .or_else(|| {
+                        instrument
+                            .get_underlying_codes()
+                            .into_iter()
+                            .find_map(|und| scenario.get_spot_return(und).map(|r| (und.clone(), r)))
+                    });
+                let Some((factor, r)) = found else {
+                    continue;
+                };
+                let m = r / DELTA_PNL_UNIT;
+                let gamma = gamma.and_then(|g| g.get(code)).copied().unwrap_or(0.0);
+                *res.entry(RiskFactor::Spot(factor)).or_insert(0.0) += delta * m + gamma * m * m;
+            }
+        }
+        if let Some(vega) = result.get_vega() {
+            for (code, vega) in vega {
+                if let Some(shift) = scenario.get_volatility_shift(code) {
+                    *res.entry(RiskFactor::Volatility(code.clone()))
+                        .or_insert(0.0) += vega * shift / VEGA_PNL_UNIT;
+                }
+            }
+        }
+        if let Some(rho) = result.get_rho() {
+            for (name, rho) in rho {
+                if let Some(shift) = scenario.get_parallel_curve_shift(name, &self.market_data)? {
+                    *res.entry(RiskFactor::Curve(name.clone())).or_insert(0.0) +=
+                        rho * shift / RHO_PNL_UNIT;
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    fn scenario_pnl(
+        &self,
+        scenario: &HistoricalScenario,
+        base_results: &HashMap<String, CalculationResult>,
+        scenario_configuration: &CalculationConfiguration,
+    ) -> Result<ScenarioPnl> {
+        let market_data = scenario.apply(&self.market_data)?;
+        let scenario_results = match self.var_method {
+            VarMethod::FullRevaluation => Some(
+                self.revalue(scenario_configuration.clone(), market_data.clone())
+                    .with_context(|| {
+                        format!(
+                            "({}:{}) failed to revalue the scenario from {:?} to {:?}",
+                            file!(),
+                            line!(),
+                            scenario.get_start_date(),
+                            scenario.get_end_date()
+                        )
+                    })?,
+            ),
+            VarMethod::DeltaGamma => None,
+        };
+
+        let mut res = ScenarioPnl {
+            portfolio: 0.0,
+            instruments: HashMap::new(),
+            risk_factors: HashMap::new(),
+        };
+        for instrument in &self.instruments {
+            let code = instrument.get_code();
+            let base_result = base_results.get(code).ok_or_else(|| {
+                anyhow!("({}:{}) result is not set for {}", file!(), line!(), code)
+            })?;
+            let base_value = base_result.get_value().ok_or_else(|| {
+                anyhow!("({}:{}) value is not set for {}", file!(), line!(), code)
+            })?;
+            let currency = *instrument.get_currency();
+            let (base_fx, fx_code) = self.conversion_rate(&self.market_data.fx_data, currency)?;
+            let (scenario_fx, _) = self.conversion_rate(&market_data.fx_data, currency)?;
+
+            let greek_pnls = self.greek_pnls(instrument, base_result, scenario)?;
+            let value = match &scenario_results {
+                Some(scenario_results) => scenario_results
+                    .get(code)
+                    .and_then(|result| result.get_value())
+                    .ok_or_else(|| {
+                        anyhow!("({}:{}) value is not set for {}", file!(), line!(), code)
+                    })?,
+                None => base_value + greek_pnls.values().sum::<Real>(),
+            };
+            let pnl = value * scenario_fx - base_value * base_fx;
+
+            let mut explained = 0.0;
+            for (factor, greek_pnl) in greek_pnls {
+                explained += greek_pnl * scenario_fx;
+                *res.risk_factors.entry(factor).or_insert(0.0) += greek_pnl * scenario_fx;
+            }
+            if let Some(fx_code) = fx_code {
+                let translation = base_value * (scenario_fx - base_fx);
+                explained += translation;
+                *res.risk_factors
+                    .entry(RiskFactor::Spot(fx_code))
+                    .or_insert(0.0) += translation;
+            }
+            if self.var_method == VarMethod::FullRevaluation {
+                *res.risk_factors.entry(RiskFactor::Residual).or_insert(0.0) += pnl - explained;
+            }
+
+            res.portfolio += pnl;
+            *res.instruments.entry(code.clone()).or_insert(0.0) += pnl;
+        }
+        Ok(res)
+    }
+
+    pub fn calculate(&mut self) -> Result<()> {
+        let dates = self.market_data_history.get_dates();
+        if dates.len() <= self.horizon_days {
+            return Err(anyhow!(
+                "({}:{}) {} dates in the market data history are not enough for the horizon of {} days",
+                file!(),
+                line!(),
+                dates.len(),
+                self.horizon_days
+            ));
+        }
+        for instrument in &self.instruments {
+            if let Some(code) = instrument
+                .get_underlying_codes_requiring_volatility()
+                .into_iter()
+                .find(|code| self.market_data.svi_parameter_data.contains_key(*code))
+            {
+                return Err(anyhow!(
+                    "({}:{}) {} depends on the SVI parameters of {}, which have no history",
+                    file!(),
+                    line!(),
+                    instrument.get_code(),
+                    code
+                ));
+            }
+        }
+        let scenarios = (0..dates.len() - self.horizon_days)
+            .map(|i| HistoricalScenario::new(&self.market_data_history, i, i + self.horizon_days))
+            .collect::<Result<Vec<HistoricalScenario>>>()?;
+
+        // the Greeks are for the attribution and the delta-gamma approximation
+        let base_configuration = self
+            .calculation_configuration
+            .clone()
+            .with_delta_calculation(true)
+            .with_gamma_calculation(true)
+            .with_vega_calculation(true)
+            .with_rho_calculation(true);
+        let scenario_configuration = self
+            .calculation_configuration
+            .clone()
+            .without_sensitivities();
+        let base_results = self
+            .revalue(base_configuration, self.market_data.clone())
+            .context("failed to calculate the base")?;
+
+        let mut value = 0.0;
+        for instrument in &self.instruments {
+            let code = instrument.get_code();
+            let base_value = base_results
+                .get(code)
+                .and_then(|result| result.get_value())
+                .ok_or_else(|| {
+                    anyhow!("({}:{}) value is not set for {}", file!(), line!(), code)
+                })?;
+            let (fx, _) =
+                self.conversion_rate(&self.market_data.fx_data, *instrument.get_currency())?;
+            value += base_value * fx;
+        }
+
+        let scenario_pnls = scenarios
+            .par_iter()
+            .map(|scenario| self.scenario_pnl(scenario, &base_results, &scenario_configuration))
+            .collect::<Result<Vec<ScenarioPnl>>>()?;
+
+        let n = scenario_pnls.len();
+        let mut instrument_pnls: HashMap<String, Vec<Real>> = HashMap::new();
+        let mut risk_factor_pnls: HashMap<RiskFactor, Vec<Real>> = HashMap::new();
+        for (i, scenario_pnl) in scenario_pnls.iter().enumerate() {
+            for (code, pnl) in &scenario_pnl.instruments {
+                instrument_pnls
+                    .entry(code.clone())
+                    .or_insert_with(|| vec![0.0; n])[i] = *pnl;
+            }
+            for (factor, pnl) in &scenario_pnl.risk_factors {
+                risk_factor_pnls
+                    .entry(factor.clone())
+                    .or_insert_with(|| vec![0.0; n])[i] = *pnl;
+            }
+        }
+
+        self.var_result = Some(HistoricalVarResult::new(
+            self.var_method,
+            self.confidence_level,
+            self.horizon_days,
+            self.reporting_currency,
+            value,
+            scenarios
+                .iter()
+                .map(|scenario| (scenario.get_start_date(), scenario.get_end_date()))
+                .collect(),
+            scenario_pnls.iter().map(|pnl| pnl.portfolio).collect(),
+            instrument_pnls,
+            risk_factor_pnls,
+        ));
+        Ok(())
+    }
+
+    pub fn get_var_result(&self) -> Option<&HistoricalVarResult> {
+        self.var_result.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::{
+        FxAtmType, FxDeltaType, OptionDailySettlementType, OptionExerciseType, OptionType,
+    };
+    use crate::instruments::{stock::Stock, vanilla_option::VanillaOption};
+    use crate::parameters::volatilities::svi_volatility_surface::{
+        RawSviParameters, SviParameters,
+    };
+    use crate::utils::test_data::{
+        flat_curve_data, krw_value, usdkrw_call, usdkrw_match_parameter,
+    };
+    use ndarray::array;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_historical_var() -> Result<()> {
+        let dt = datetime!(2024-01-02 16:30:00 +09:00);
+        let usdkrw = FxCode::new(Currency::USD, Currency::KRW);
+        let instruments = Instruments::new(vec![
+            Rc::new(Instrument::FxOption(usdkrw_call(dt))),
+            Rc::new(Instrument::Stock(Stock::new(
+                "Samsung Electronics".to_string(),
+                "005930".to_string(),
+                vec!["005930".to_string()],
+                Currency::KRW,
+                None,
+            ))),
+            Rc::new(Instrument::Stock(Stock::new(
+                "Apple".to_string(),
+                "AAPL".to_string(),
+                vec!["AAPL".to_string()],
+                Currency::USD,
+                None,
+            ))),
+        ]);
+
+        let value = |v: Real, currency: Currency, name: &str| {
+            ValueData::new(v, Some(dt), currency, name.to_string(), name.to_string())
+        };
+        let curve = |rate: Real, name: &str| flat_curve_data(rate, Currency::KRW, name);
+        let mut fx_data = HashMap::new();
+        fx_data.insert(usdkrw, value(1300.0, Currency::KRW, "USDKRW")?);
+        let mut stock_data = HashMap::new();
+        stock_data.insert(
+            "005930".to_string(),
+            value(71_000.0, Currency::KRW, "005930")?,
+        );
+        stock_data.insert("AAPL".to_string(), value(192.0, Currency::USD, "AAPL")?);
+        let mut curve_data = HashMap::new();
+        curve_data.insert("USDOIS".to_string(), curve(0.05, "USDOIS")?);
+        curve_data.insert("KRWCRS".to_string(), curve(0.035, "KRWCRS")?);
+        curve_data.insert("005930".to_string(), curve(0.0, "005930")?);
+        curve_data.insert("AAPL".to_string(), curve(0.0, "AAPL")?);
+        let mut fx_constant_volatility_data = HashMap::new();
+        fx_constant_volatility_data.insert(usdkrw, value(0.1, Currency::KRW, "USDKRW")?);
+
+        let history_dates = vec![
+            datetime!(2023-12-21 16:00:00 +09:00),
+            datetime!(2023-12-22 16:00:00 +09:00),
+            datetime!(2023-12-26 16:00:00 +09:00),
+            datetime!(2023-12-27 16:00:00 +09:00),
+            datetime!(2023-12-28 16:00:00 +09:00),
+            datetime!(2023-12-29 16:00:00 +09:00),
+            datetime!(2024-01-02 16:00:00 +09:00),
+        ];
+        let value_series = |values: &[Real], currency: Currency, name: &str| {
+            values
+                .iter()
+                .map(|v| value(*v, currency, name))
+                .collect::<Result<Vec<ValueData>>>()
+        };
+        let curve_series = |rates: &[Real], name: &str| {
+            rates
+                .iter()
+                .map(|r| curve(*r, name))
+                .collect::<Result<Vec<VectorData>>>()
+        };
+        let usdkrw_history = [1290.0, 1300.0, 1285.0, 1310.0, 1305.0, 1295.0, 1300.0];
+        let samsung_history = [
+            70_000.0, 71_000.0, 69_500.0, 72_000.0, 71_500.0, 70_500.0, 71_000.0,
+        ];
+        let mut fx_history = HashMap::new();
+        fx_history.insert(
+            usdkrw,
+            value_series(&usdkrw_history, Currency::KRW, "USDKRW")?,
+        );
+        let mut stock_history = HashMap::new();
+        stock_history.insert(
+            "005930".to_string(),
+            value_series(&samsung_history, Currency::KRW, "005930")?,
+        );
+        stock_history.insert(
+            "AAPL".to_string(),
+            value_series(
+                &[190.0, 192.0, 188.0, 195.0, 193.0, 191.0, 192.0],
+                Currency::USD,
+                "AAPL",
+            )?,
+        );
+        let mut curve_history = HashMap::new();
+        curve_history.insert(
+            "USDOIS".to_string(),
+            curve_series(
+                &[0.0500, 0.0502, 0.0498, 0.0505, 0.0503, 0.0500, 0.0501],
+                "USDOIS",
+            )?,
+        );
+        curve_history.insert(
+            "KRWCRS".to_string(),
+            curve_series(
+                &[0.0350, 0.0348, 0.0352, 0.0347, 0.0350, 0.0351, 0.0350],
+                "KRWCRS",
+            )?,
+        );
+        let mut fx_volatility_history = HashMap::new();
+        fx_volatility_history.insert(
+            usdkrw,
+            value_series(
+                &[0.100, 0.105, 0.098, 0.110, 0.107, 0.102, 0.100],
+                Currency::KRW,
+                "USDKRW",
+            )?,
+        );
+        let history = MarketDataHistory::new(
+            history_dates.clone(),
+            fx_history,
+            stock_history,
+            curve_history,
+            HashMap::new(),
+            HashMap::new(),
+            fx_volatility_history,
+        )?;
+
+        let mut crs_curve_map = HashMap::new();
+        crs_curve_map.insert(Currency::USD, "USDOIS".to_string());
+        crs_curve_map.insert(Currency::KRW, "KRWCRS".to_string());
+        let mut collateral_curve_map = HashMap::new();
+        collateral_curve_map.insert("005930".to_string(), "KRWCRS".to_string());
+        collateral_curve_map.insert("AAPL".to_string(), "USDOIS".to_string());
+        let mut borrowing_curve_map = HashMap::new();
+        borrowing_curve_map.insert("005930".to_string(), "005930".to_string());
+        borrowing_curve_map.insert("AAPL".to_string(), "AAPL".to_string());
+        let match_parameter = MatchParameter::new(
+            collateral_curve_map,
+            borrowing_curve_map,
+            HashMap::new(),
+            crs_curve_map,
+            HashMap::new(),
+            HashMap::new(),
+        );
+
+        let var_result = |var_method: VarMethod| -> Result<HistoricalVarResult> {
+            let mut var_engine = HistoricalVarEngine::builder();
+            var_engine
+                .with_configuration(
+                    CalculationConfiguration::default(),
+                    dt,
+                    match_parameter.clone(),
+                )?
+                .with_instruments(instruments.clone())?
+                .with_data(
+                    fx_data.clone(),
+                    stock_data.clone(),
+                    curve_data.clone(),
+                    HashMap::new(),
+                    HashMap::new(),
+                    HashMap::new(),
+                    fx_constant_volatility_data.clone(),
+                    HashMap::new(),
+                    HashMap::new(),
+                )?
+                .with_market_data_history(history.clone())?
+                .with_var_parameters(0.8, 1, var_method, Currency::KRW)?;
+            var_engine.calculate()?;
+            Ok(var_engine.get_var_result().unwrap().clone())
+        };
+
+        let full = var_result(VarMethod::FullRevaluation)?;
+        assert_eq!(full.get_portfolio_pnls().len(), 6);
+        assert_eq!(
+            full.get_scenario_dates()[0],
+            (history_dates[0], history_dates[1])
+        );
+        // ceil(0.2 * 6) = 2 worst scenarios
+        assert_eq!(full.get_tail_scenario_indices().len(), 2);
+        assert!(full.get_var() > 0.0);
+        assert!(full.get_expected_shortfall() >= full.get_var());
+
+        // the contributions add up to the VaR and the ES
+        let var = full.get_var();
+        let es = full.get_expected_shortfall();
+        let sum = |contributions: Vec<Real>| contributions.iter().sum::<Real>();
+        for total in [
+            sum(full
+                .get_instrument_var_contributions()
+                .values()
+                .copied()
+                .collect()),
+            sum(full
+                .get_risk_factor_var_contributions()
+                .values()
+                .copied()
+                .collect()),
+        ] {
+            assert!((total - var).abs() < 1.0e-4 * var, "{} != {}", total, var);
+        }
+        for total in [
+            sum(full
+                .get_instrument_es_contributions()
+                .values()
+                .copied()
+                .collect()),
+            sum(full
+                .get_risk_factor_es_contributions()
+                .values()
+                .copied()
+                .collect()),
+        ] {
+            assert!((total - es).abs() < 1.0e-4 * es, "{} != {}", total, es);
+        }
+
+        // a stock is revalued exactly on its return
+        for (i, pnl) in full.get_instrument_pnls()["005930"].iter().enumerate() {
+            let expected = 71_000.0 * (samsung_history[i + 1] / samsung_history[i] - 1.0);
+            assert!((pnl - expected).abs() < 0.1, "{} != {}", pnl, expected);
+        }
+        // the P&L of a USD stock includes the translation into KRW
+        let apple = &full.get_instrument_pnls()["AAPL"];
+        let usdkrw_ratio = usdkrw_history[2] / usdkrw_history[1];
+        let expected = 192.0 * (188.0 / 192.0) * 1300.0 * usdkrw_ratio - 192.0 * 1300.0;
+        assert!(
+            (apple[1] - expected).abs() < 1.0,
+            "{} != {}",
+            apple[1],
+            expected
+        );
+
+        // the fx option is mostly explained by the Greeks
+        let fx_pnl = full.get_risk_factor_pnls()[&RiskFactor::Spot("USDKRW".to_string())][1];
+        let residual = full.get_risk_factor_pnls()[&RiskFactor::Residual][1];
+        assert!(fx_pnl < 0.0);
+        assert!(
+            residual.abs() < 0.1 * fx_pnl.abs(),
+            "residual = {}",
+            residual
+        );
+        assert!(full
+            .get_risk_factor_pnls()
+            .contains_key(&RiskFactor::Volatility("USDKRW".to_string())));
+        assert!(full
+            .get_risk_factor_pnls()
+            .contains_key(&RiskFactor::Curve("KRWCRS".to_string())));
+
+        // the delta-gamma approximation is close to the full revaluation on small moves
+        let delta_gamma = var_result(VarMethod::DeltaGamma)?;
+        assert!(!delta_gamma
+            .get_risk_factor_pnls()
+            .contains_key(&RiskFactor::Residual));
+        assert!(
+            (delta_gamma.get_var() - var).abs() < 0.1 * var,
+            "delta-gamma VaR = {}, full revaluation VaR = {}",
+            delta_gamma.get_var(),
+            var
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_historical_var_volatility_quotes() -> Result<()> {
+        let dt = datetime!(2024-01-02 16:30:00 +09:00);
+        let usdkrw = FxCode::new(Currency::USD, Currency::KRW);
+        let instruments = Instruments::new(vec![Rc::new(Instrument::FxOption(usdkrw_call(dt)))]);
+
+        let curve = |rate: Real, name: &str| flat_curve_data(rate, Currency::KRW, name);
+        let quotes = |atm: Real| {
+            FxVolatilityQuoteData::new(
+                vec![
+                    datetime!(2024-04-02 16:00:00 +09:00),
+                    datetime!(2025-01-02 16:00:00 +09:00),
+                ],
+                array![atm, atm + 0.01],
+                array![0.01, 0.015],
+                array![0.003, 0.004],
+                FxDeltaType::SpotPremiumAdjusted,
+                FxAtmType::DeltaNeutralStraddle,
+                Some(dt),
+                usdkrw,
+                "USDKRW vol".to_string(),
+                "USDKRW vol".to_string(),
+            )
+        };
+        let mut fx_data = HashMap::new();
+        fx_data.insert(usdkrw, krw_value(1300.0, dt, "USDKRW")?);
+        let mut curve_data = HashMap::new();
+        curve_data.insert("USDOIS".to_string(), curve(0.05, "USDOIS")?);
+        curve_data.insert("KRWCRS".to_string(), curve(0.035, "KRWCRS")?);
+        let mut fx_volatility_quote_data = HashMap::new();
+        fx_volatility_quote_data.insert(usdkrw, quotes(0.08));
+
+        // the ATM rises by 1% and falls back
+        let history_dates = vec![
+            datetime!(2023-12-28 16:00:00 +09:00),
+            datetime!(2023-12-29 16:00:00 +09:00),
+            datetime!(2024-01-02 16:00:00 +09:00),
+        ];
+        let mut quote_history = HashMap::new();
+        quote_history.insert(usdkrw, vec![quotes(0.08), quotes(0.09), quotes(0.08)]);
+        let history = MarketDataHistory::new(
+            history_dates,
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+        )?
+        .with_fx_volatility_quote_data(quote_history)?;
+        let match_parameter = usdkrw_match_parameter(&[]);
+
+        let mut var_engine = HistoricalVarEngine::builder();
+        var_engine
+            .with_configuration(CalculationConfiguration::default(), dt, match_parameter)?
+            .with_instruments(instruments)?
+            .with_data(
+                fx_data,
+                HashMap::new(),
+                curve_data,
+                HashMap::new(),
+                HashMap::new(),
+                HashMap::new(),
+                HashMap::new(),
+                HashMap::new(),
+                HashMap::new(),
+            )?
+            .with_fx_volatility_quote_data(fx_volatility_quote_data)?
+            .with_market_data_history(history)?
+            .with_var_parameters(0.5, 1, VarMethod::FullRevaluation, Currency::KRW)?;
+        var_engine.calculate()?;
+        let result = var_engine.get_var_result().unwrap();
+
+        // the option is revalued on the moved quotes and the move is explained by the vega
+        let pnls = &result.get_instrument_pnls()["USDKRWC13006M"];
+        assert!(pnls[0] > 0.0 && pnls[1] < 0.0, "{:?}", pnls);
+        let vega_pnls =
+            &result.get_risk_factor_pnls()[&RiskFactor::Volatility("USDKRW".to_string())];
+        for (pnl, vega_pnl) in pnls.iter().zip(vega_pnls.iter()) {
+            assert!(
+                (pnl - vega_pnl).abs() < 0.1 * pnl.abs(),
+                "P&L = {}, vega P&L = {}",
+                pnl,
+                vega_pnl
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_historical_var_svi_without_history() -> Result<()> {
+        let dt = datetime!(2024-01-02 16:30:00 +09:00);
+        let maturity = datetime!(2024-07-02 16:00:00 +09:00);
+        let instruments = Instruments::new(vec![Rc::new(Instrument::VanillaOption(
+            VanillaOption::new(
+                70_000.0,
+                1.0,
+                dt,
+                maturity,
+                maturity,
+                maturity,
+                vec!["005930".to_string()],
+                Currency::KRW,
+                Currency::KRW,
+                OptionType::Call,
+                OptionExerciseType::European,
+                OptionDailySettlementType::NotSettled,
+                "005930 C 70000".to_string(),
+                "005930C70000".to_string(),
+            ),
+        ))]);
+        let mut svi_parameter_data = HashMap::new();
+        svi_parameter_data.insert(
+            "005930".to_string(),
+            SviParameterData::new(
+                71_000.0,
+                SviParameters::RawSvi(vec![RawSviParameters::new(0.01, 0.1, -0.3, 0.0, 0.1)]),
+                vec![maturity],
+                Some(dt),
+                Currency::KRW,
+                "005930".to_string(),
+                "005930".to_string(),
+            ),
+        );
+        let history = MarketDataHistory::new(
+            vec![
+                datetime!(2023-12-29 16:00:00 +09:00),
+                datetime!(2024-01-02 16:00:00 +09:00),
+            ],
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+        )?;
+
+        let mut var_engine = HistoricalVarEngine::builder();
+        var_engine
+            .with_configuration(
+                CalculationConfiguration::default(),
+                dt,
+                MatchParameter::default(),
+            )?
+            .with_instruments(instruments)?
+            .with_svi_parameter_data(svi_parameter_data)?
+            .with_market_data_history(history)?;
+        let err = var_engine.calculate().unwrap_err();
+        assert!(err.to_string().contains("SVI"), "{}", err);
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+use crate::definitions::{Real, Time};
+use crate::enums::OptionType;
+//
+use anyhow::{anyhow, Result};
+use statrs::distribution::{Continuous, ContinuousCDF, Normal};
+
+const IMPLIED_VOLATILITY_MAX_ITERATION: usize = 200;
+/// tolerance on the premium relative to the discounted forward
+const IMPLIED_VOLATILITY_PRICE_TOLERANCE: f64 = 1.0e-12;
+/// tolerance on the total deviation
+const IMPLIED_VOLATILITY_DEVIATION_TOLERANCE: f64 = 1.0e-12;
+const IMPLIED_VOLATILITY_MIN_DEVIATION: f64 = 1.0e-8;
+const IMPLIED_VOLATILITY_MAX_VOLATILITY: f64 = 10.0;
+
+/// Black premium in the convention of OptionAnalyticPricer, calculated in f64.
+/// The quanto drift enters d1 as -vol * t * quanto_drift as in the analytic pricer.
+/// s is the total deviation vol * sqrt(t). Returns (premium, d premium / d s)
+fn black_premium_and_derivative(
+    forward: f64,
+    strike: f64,
+    discount: f64,
+    sqrt_t: f64,
+    quanto_drift: f64,
+    option_type: OptionType,
+    s: f64,
+) -> (f64, f64) {
+    let normal = Normal::new(0.0, 1.0).unwrap();
+    let y = (strike / forward).ln();
+    let a = quanto_drift * sqrt_t;
+    let d1 = -y / s + 0.5 * s - a;
+    let d2 = d1 - s;
+    let premium = match option_type {
+        OptionType::Call => discount * (forward * normal.cdf(d1) - strike * normal.cdf(d2)),
+        OptionType::Put => discount * (strike * normal.cdf(-d2) - forward * normal.cdf(-d1)),
+    };
+    // the derivative is the same for call and put
+    let derivative = discount
+        * (forward * normal.pdf(d1) * (y / (s * s) + 0.5)
+            - strike * normal.pdf(d2) * (y / (s * s) - 0.5));
+    (premium, derivative)
+}
+
+/// Rational initial guess of the total deviation by Corrado and Miller (1996)
+/// on the undiscounted call premium. Falls back to Brenner and Subrahmanyam (1988)
+/// when the square root of Corrado-Miller is not real, which is the case deep out of the money.
+fn initial_deviation(
+    forward: f64,
+    strike: f64,
+    undiscounted_premium: f64,
+    option_type: OptionType,
+) -> f64 {
+    let call = match option_type {
+        OptionType::Call => undiscounted_premium,
+        OptionType::Put => undiscounted_premium + forward - strike,
+    };
+    let half_intrinsic = 0.5 * (forward - strike);
+    let x = call - half_intrinsic;
+    let discriminant = x * x - (forward - strike).powi(2) / std::f64::consts::PI;
+    if discriminant >= 0.0 {
+        (2.0 * std::f64::consts::PI).sqrt() / (forward + strike) * (x + discriminant.sqrt())
+    } else {
+        (2.0 * std::f64::consts::PI).sqrt() * call.max(0.0) / forward
+    }
+}
+
+/// Implied volatility that reproduces the premium in the formula of OptionAnalyticPricer.
+/// The root is searched in the total deviation vol * sqrt(t) by Newton's method
+/// safeguarded by a bisection bracket, so that a Newton step leaving the bracket
+/// or a vanishing vega (near expiry, deep out of the money) falls back to bisection.
+/// premium is the discounted premium for a unit of underlying,
+/// and quanto_drift is the value of Quanto::quanto_adjust (zero if no quanto).
+pub fn black_implied_volatility(
+    premium: Real,
+    forward: Real,
+    strike: Real,
+    t: Time,
+    discount: Real,
+    quanto_drift: Real,
+    option_type: OptionType,
+) -> Result<Real> {
+    if t <= 0.0 {
+        return Err(anyhow!(
+            "({}:{}) implied volatility is not defined for non-positive time to maturity: {}",
+            file!(),
+            line!(),
+            t
+        ));
+    }
+    if forward <= 0.0 || strike <= 0.0 || discount <= 0.0 {
+        return Err(anyhow!(
+            "({}:{}) forward ({}), strike ({}) and discount ({}) must be positive",
+            file!(),
+            line!(),
+            forward,
+            strike,
+            discount
+        ));
+    }
+
+    let (forward, strike, discount) = (forward as f64, strike as f64, discount as f64);
+    let sqrt_t = (t as f64).sqrt();
+    let target = premium as f64;
+    let price_tolerance = IMPLIED_VOLATILITY_PRICE_TOLERANCE * discount * forward.max(strike);
+    let objective = |s: f64| {
+        let (p, dp) = black_premium_and_derivative(
+            forward,
+            strike,
+            discount,
+            sqrt_t,
+            quanto_drift as f64,
+            option_type,
+            s,
+        );
+        (p - target, dp)
+    };
+
+    let mut lower = IMPLIED_VOLATILITY_MIN_DEVIATION;
+    let mut upper = IMPLIED_VOLATILITY_MAX_VOLATILITY * sqrt_t;
+    let (f_lower, _) = objective(lower);
+    let (f_upper, _) = objective(upper);
+    if f_lower.abs() <= price_tolerance {
+        return Ok((lower / sqrt_t) as Real);
+    }
+    if f_upper.abs() <= price_tolerance {
+        return Ok((upper / sqrt_t) as Real);
+    }
+    if f_lower * f_upper > 0.0 {
+        return Err(anyhow!(
+            "({}:{}) premium {} is out of the attainable range [{}, {}] \
+            (forward = {}, strike = {}, t = {}, {:?})",
+            file!(),
+            line!(),
+            premium,
+            f_lower + target,
+            f_upper + target,
+            forward,
+            strike,
+            t,
+            option_type
+        ));
+    }
+    // orient the bracket so that the objective is negative at lower
+    let increasing = f_lower < 0.0;
+
+    let mut s = initial_deviation(forward, strike, target / discount, option_type);
+    if !s.is_finite() || s <= lower || s >= upper {
+        s = 0.5 * (lower + upper);
+    }
+
+    for _ in 0..IMPLIED_VOLATILITY_MAX_ITERATION {
+        let (f, df) = objective(s);
+        if f.abs() <= price_tolerance {
+            return Ok((s / sqrt_t) as Real);
+        }
+        if (f < 0.0) == increasing {
+            lower = s;
+        } else {
+            upper = s;
+        }
+        if upper - lower <= IMPLIED_VOLATILITY_DEVIATION_TOLERANCE {
+            return Ok((0.5 * (lower + upper) / sqrt_t) as Real);
+        }
+
+        let newton = s - f / df;
+        s = if df.abs() > 0.0 && newton.is_finite() && newton > lower && newton < upper {
+            newton
+        } else {
+            0.5 * (lower + upper)
+        };
+    }
+
+    Err(anyhow!(
+        "({}:{}) implied volatility did not converge in {} iterations \
+        (premium = {}, forward = {}, strike = {}, t = {}, {:?})",
+        file!(),
+        line!(),
+        IMPLIED_VOLATILITY_MAX_ITERATION,
+        premium,
+        forward,
+        strike,
+        t,
+        option_type
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_black_implied_volatility() -> Result<()> {
+        let forward: Real = 100.0;
+        let discount: Real = 0.97;
+        let cases: Vec<(Real, Time, Real, OptionType)> = vec![
+            (100.0, 1.0, 0.2, OptionType::Call),
+            (100.0, 1.0, 0.2, OptionType::Put),
+            // near expiry
+            (101.0, 1.0 / 365.0, 0.15, OptionType::Call),
+            (99.0, 1.0 / 365.0, 0.15, OptionType::Put),
+            // deep out of the money
+            (180.0, 0.25, 0.3, OptionType::Call),
+            (50.0, 0.5, 0.45, OptionType::Put),
+            // deep in the money
+            (60.0, 1.0, 0.25, OptionType::Call),
+            // high volatility
+            (100.0, 2.0, 1.5, OptionType::Put),
+        ];
+
+        for quanto_drift in [0.0, 0.05] {
+            for (strike, t, vol, option_type) in cases.iter() {
+                let (premium, _) = black_premium_and_derivative(
+                    forward as f64,
+                    *strike as f64,
+                    discount as f64,
+                    (*t as f64).sqrt(),
+                    quanto_drift,
+                    *option_type,
+                    (*vol * t.sqrt()) as f64,
+                );
+                let implied = black_implied_volatility(
+                    premium as Real,
+                    forward,
+                    *strike,
+                    *t,
+                    discount,
+                    quanto_drift as Real,
+                    *option_type,
+                )?;
+                assert!(
+                    (implied - vol).abs() < 1.0e-3,
+                    "strike = {}, t = {}, {:?}, quanto_drift = {}: implied = {}, expected = {}",
+                    strike,
+                    t,
+                    option_type,
+                    quanto_drift,
+                    implied,
+                    vol
+                );
+            }
+        }
+
+        // a premium below the intrinsic value can not be inverted
+        let res =
+            black_implied_volatility(30.0, forward, 60.0, 1.0, discount, 0.0, OptionType::Call);
+        assert!(res.is_err());
+        Ok(())
+    }
+}
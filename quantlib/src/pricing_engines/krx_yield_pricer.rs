@@ -11,7 +11,11 @@ use anyhow::{anyhow, Context, Result};
 use argmin::core::{CostFunction, Error, Executor, Gradient};
 use argmin::solver::gradientdescent::SteepestDescent;
 use argmin::solver::linesearch::MoreThuenteLineSearch;
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use time::OffsetDateTime;
+
+const KRX_YIELD_NEWTON_ITERATION: usize = 10;
+const KRX_YIELD_TOLERANCE: Real = 1.0e-8;
 
 /// 금융투자회사의 영업 및 업무에 관한 규정 별표 14
 /// https://law.kofia.or.kr/service/law/lawFullScreenContent.do?seq=136&historySeq=263
@@ -50,7 +54,7 @@ impl KrxYieldPricer {
 
     pub fn find_bond_yield(&self, bond: Bond, npv: Real, init_guess: Option<Real>) -> Result<Real> {
         let pricer = self.clone();
-        let problem = KrxYieldPricerCostFunction::new(bond, npv, pricer);
+        let problem = KrxYieldPricerCostFunction::new(bond.clone(), npv, pricer);
         let linesearch = MoreThuenteLineSearch::new();
 
         let solver = SteepestDescent::new(linesearch);
@@ -59,10 +63,31 @@ impl KrxYieldPricer {
             .configure(|state| state.param(init_param).max_iters(30).target_cost(1.0e-9));
 
         let res = executor.run()?;
-        match res.state.best_param {
-            Some(param) => Ok(param),
-            None => Err(anyhow!("Failed to find bond yield")),
+        let mut bond_yield = match res.state.best_param {
+            Some(param) => param,
+            None => return Err(anyhow!("Failed to find bond yield")),
+        };
+        // the descent stops at the cost tolerance, which is loose in the price,
+        // so the yield is refined by Newton steps on the price
+        let inst = Instrument::Bond(bond);
+        let mut pricer = self.clone();
+        let mut price_at = |y: Real| -> Result<Real> {
+            pricer.set_bond_yield(y);
+            pricer.npv(&inst)
+        };
+        for _ in 0..KRX_YIELD_NEWTON_ITERATION {
+            let h = 1.0e-4;
+            let slope = (price_at(bond_yield + h)? - price_at(bond_yield - h)?) / (2.0 * h);
+            if slope == 0.0 {
+                break;
+            }
+            let step = (price_at(bond_yield)? - npv) / slope;
+            bond_yield -= step;
+            if step.abs() < KRX_YIELD_TOLERANCE {
+                break;
+            }
         }
+        Ok(bond_yield)
     }
 }
 
@@ -174,9 +199,32 @@ impl PricerTrait for KrxYieldPricer {
         Ok(res)
     }
 
+    /// the cashflows from the pricing date are kept as in BondPricer
     fn npv_result(&self, instrument: &Instrument) -> Result<NpvResult> {
         let npv = self.npv(instrument)?;
-        Ok(NpvResult::new_from_npv(npv))
+        let eval_dt = self.evaluation_date.borrow().get_date_clone();
+        let pricing_date = instrument.get_pricing_date()?.unwrap_or(&eval_dt);
+        let cashflow = instrument
+            .get_cashflows(
+                pricing_date,
+                self.forward_curve.clone(),
+                self.past_fixing_data.clone(),
+            )
+            .context("Failed to get coupon cashflow in calculating KrxYieldPricer::npv_result")?;
+
+        let mut coupon_amounts: HashMap<usize, (OffsetDateTime, Real)> = HashMap::new();
+        let mut coupon_payment_probability: HashMap<usize, (OffsetDateTime, Real)> = HashMap::new();
+        for (i, (payment_date, amount)) in cashflow.iter().enumerate() {
+            if pricing_date.date() <= payment_date.date() {
+                coupon_amounts.insert(i, (*payment_date, *amount));
+                coupon_payment_probability.insert(i, (*payment_date, 1.0));
+            }
+        }
+        Ok(NpvResult::new(
+            npv,
+            coupon_amounts,
+            coupon_payment_probability,
+        ))
     }
 }
 
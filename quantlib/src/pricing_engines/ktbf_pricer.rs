@@ -1,16 +1,122 @@
-use crate::definitions::Real;
+use crate::definitions::{Integer, Real};
 use crate::enums::Compounding;
 use crate::evaluation_date::EvaluationDate;
 use crate::instrument::{Instrument, InstrumentTrait};
+use crate::instruments::ktbf::KTBF;
 use crate::parameters::zero_curve::ZeroCurve;
 use crate::pricing_engines::{
     bond_pricer::BondPricer, krx_yield_pricer::KrxYieldPricer, npv_result::NpvResult,
     pricer::PricerTrait,
 };
 //
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
 use std::{cell::RefCell, rc::Rc};
 
+/// An underlying bond of KTBF in the basket yield
+/// * bond_yield: KRX yield of the bond at the KTBF maturity
+/// * yield_contribution: bond_yield over the number of bonds, so that they sum to the basket yield
+/// * dv01: value change of a unit of the bond for 1bp decrease of its yield
+/// * hedge_ratio: units of the bond whose dv01 is that of a KTBF contract
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KtbfUnderlyingAnalytics {
+    code: String,
+    bond_yield: Real,
+    yield_contribution: Real,
+    dv01: Real,
+    hedge_ratio: Real,
+}
+
+impl KtbfUnderlyingAnalytics {
+    pub fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    pub fn get_bond_yield(&self) -> Real {
+        self.bond_yield
+    }
+
+    pub fn get_yield_contribution(&self) -> Real {
+        self.yield_contribution
+    }
+
+    pub fn get_dv01(&self) -> Real {
+        self.dv01
+    }
+
+    pub fn get_hedge_ratio(&self) -> Real {
+        self.hedge_ratio
+    }
+}
+
+/// KTBF against its market price
+/// * basket_yield: average yield of the underlying bonds which gives theoretical_price
+/// * implied_yield: yield of the virtual bond at market_price
+/// * basis: market_price - theoretical_price
+/// * yield_basis: basket_yield - implied_yield, which has the same sign as basis
+/// * dv01: value change of a contract for 1bp decrease of the basket yield
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KtbfAnalytics {
+    code: String,
+    tenor: Integer,
+    basket_yield: Real,
+    theoretical_price: Real,
+    market_price: Real,
+    implied_yield: Real,
+    basis: Real,
+    yield_basis: Real,
+    dv01: Real,
+    underlying_analytics: Vec<KtbfUnderlyingAnalytics>,
+}
+
+impl KtbfAnalytics {
+    pub fn get_code(&self) -> &String {
+        &self.code
+    }
+
+    /// year of the virtual bond, e.g., 3 for KTBF3Y
+    pub fn get_tenor(&self) -> Integer {
+        self.tenor
+    }
+
+    pub fn get_basket_yield(&self) -> Real {
+        self.basket_yield
+    }
+
+    pub fn get_theoretical_price(&self) -> Real {
+        self.theoretical_price
+    }
+
+    pub fn get_market_price(&self) -> Real {
+        self.market_price
+    }
+
+    pub fn get_implied_yield(&self) -> Real {
+        self.implied_yield
+    }
+
+    pub fn get_basis(&self) -> Real {
+        self.basis
+    }
+
+    pub fn get_yield_basis(&self) -> Real {
+        self.yield_basis
+    }
+
+    pub fn get_dv01(&self) -> Real {
+        self.dv01
+    }
+
+    pub fn get_underlying_analytics(&self) -> &Vec<KtbfUnderlyingAnalytics> {
+        &self.underlying_analytics
+    }
+
+    /// contracts of other (e.g., KTBF10Y) whose dv01 is that of a contract of self (e.g., KTBF3Y)
+    pub fn get_hedge_ratio_against(&self, other: &KtbfAnalytics) -> Real {
+        self.dv01 / other.dv01
+    }
+}
+
 pub struct KtbfPricer {
     evaluation_date: Rc<RefCell<EvaluationDate>>,
     discount_curve: Rc<RefCell<ZeroCurve>>,
@@ -29,10 +135,9 @@ impl KtbfPricer {
             borrowing_curve,
         }
     }
-}
 
-impl PricerTrait for KtbfPricer {
-    fn npv(&self, instrument: &Instrument) -> Result<Real> {
+    /// KRX yields of the underlying bonds at the KTBF maturity
+    fn underlying_yields(&self, instrument: &Instrument) -> Result<Vec<Real>> {
         let bond_pricer = BondPricer::new(
             self.evaluation_date.clone(),
             self.discount_curve.clone(),
@@ -60,17 +165,97 @@ impl PricerTrait for KtbfPricer {
             let yield_ = krx_yield_pricer.find_bond_yield(bond.clone(), npv, Some(init_guess))?;
             bond_yields.push(yield_);
         }
+        Ok(bond_yields)
+    }
+
+    fn borrowing_cost(&self, instrument: &Instrument) -> Result<Real> {
+        self.borrowing_curve
+            .borrow()
+            .get_discount_factor_at_date(instrument.get_maturity().unwrap())
+    }
+
+    /// implied yield, basis, yield contributions and hedge ratios of the KTBF at market_price
+    pub fn analytics(&self, instrument: &Instrument, market_price: Real) -> Result<KtbfAnalytics> {
+        let ktbf: &KTBF = match instrument {
+            Instrument::KTBF(ktbf) => ktbf,
+            _ => {
+                return Err(anyhow!(
+                    "({}:{}) KtbfPricer does not support {} ({})",
+                    file!(),
+                    line!(),
+                    instrument.get_name(),
+                    instrument.get_code()
+                ))
+            }
+        };
+        let virtual_bond = ktbf.get_virtual_bond();
+        let bond_yields = self.underlying_yields(instrument)?;
+        let bond_number = bond_yields.len() as Real;
+        let basket_yield = bond_yields.iter().sum::<Real>() / bond_number;
+        let borrowing_cost = self.borrowing_cost(instrument)?;
+
+        let theoretical_price = virtual_bond.npv(basket_yield) * borrowing_cost;
+        let implied_yield = virtual_bond
+            .implied_yield(market_price / borrowing_cost, Some(basket_yield))
+            .map_err(|e| {
+                anyhow!(
+                    "({}:{}) failed to find the implied yield of {} ({})\n{}",
+                    file!(),
+                    line!(),
+                    instrument.get_name(),
+                    instrument.get_code(),
+                    e
+                )
+            })?;
+        let dv01 = -virtual_bond.npv_derivative(basket_yield)
+            * borrowing_cost
+            * 0.0001
+            * instrument.get_unit_notional();
+
+        let bump = 0.0001;
+        let mut underlying_analytics = vec![];
+        for (bond, bond_yield) in ktbf.get_underlying_bonds().iter().zip(bond_yields.iter()) {
+            let inst = Instrument::Bond(bond.clone());
+            let up =
+                KrxYieldPricer::new(self.evaluation_date.clone(), bond_yield + bump, None, None)
+                    .npv(&inst)?;
+            let down =
+                KrxYieldPricer::new(self.evaluation_date.clone(), bond_yield - bump, None, None)
+                    .npv(&inst)?;
+            let bond_dv01 = (down - up) * 0.5 * bond.get_unit_notional();
+            underlying_analytics.push(KtbfUnderlyingAnalytics {
+                code: bond.get_code().clone(),
+                bond_yield: *bond_yield,
+                yield_contribution: bond_yield / bond_number,
+                dv01: bond_dv01,
+                hedge_ratio: dv01 / bond_dv01,
+            });
+        }
+
+        Ok(KtbfAnalytics {
+            code: instrument.get_code().clone(),
+            tenor: virtual_bond.get_year(),
+            basket_yield,
+            theoretical_price,
+            market_price,
+            implied_yield,
+            basis: market_price - theoretical_price,
+            yield_basis: basket_yield - implied_yield,
+            dv01,
+            underlying_analytics,
+        })
+    }
+}
+
+impl PricerTrait for KtbfPricer {
+    fn npv(&self, instrument: &Instrument) -> Result<Real> {
+        let bond_yields = self.underlying_yields(instrument)?;
 
         let average_yield = bond_yields.iter().sum::<Real>() / bond_yields.len() as Real;
 
         let mut ktbf_price = instrument.get_virtual_bond_npv(average_yield)?;
 
-        let borrowing_cost = self
-            .borrowing_curve
-            .borrow()
-            .get_discount_factor_at_date(instrument.get_maturity().unwrap())?;
-
-        ktbf_price *= borrowing_cost;
+        ktbf_price *= self.borrowing_cost(instrument)?;
 
         Ok(ktbf_price)
     }
@@ -85,6 +270,7 @@ impl PricerTrait for KtbfPricer {
 mod tests {
     use crate::currency::Currency;
     use crate::data::vector_data::VectorData;
+    use crate::definitions::{Integer, Real};
     use crate::enums::{CreditRating, IssuerType, RankType};
     use crate::evaluation_date::EvaluationDate;
     use crate::instrument::Instrument;
@@ -101,6 +287,7 @@ mod tests {
         conventions::{BusinessDayConvention, DayCountConvention, PaymentFrequency},
         jointcalendar::JointCalendar,
     };
+    use crate::utils::test_data::flat_zero_curve;
     //
     use anyhow::Result;
     use ndarray::array;
@@ -246,4 +433,117 @@ mod tests {
 
         Ok(())
     }
+
+    /// government bond of 3% semi-annual coupon issued on the evaluation date
+    fn government_bond(
+        maturity: time::OffsetDateTime,
+        pricing_date: time::OffsetDateTime,
+        code: &str,
+    ) -> Result<Bond> {
+        let issue_date = datetime!(2024-01-02 00:00:00 UTC);
+        let sk = Calendar::SouthKorea(SouthKorea::new(SouthKoreaType::Settlement));
+        Bond::new_from_conventions(
+            IssuerType::Government,
+            CreditRating::None,
+            "Korea Government".to_string(),
+            RankType::Senior,
+            Currency::KRW,
+            10_000.0,
+            false,
+            issue_date,
+            issue_date,
+            Some(pricing_date),
+            maturity,
+            Some(0.03),
+            None,
+            None,
+            None,
+            JointCalendar::new(vec![sk])?,
+            true,
+            DayCountConvention::StreetConvention,
+            BusinessDayConvention::Unadjusted,
+            PaymentFrequency::SemiAnnually,
+            0,
+            0,
+            code.to_string(),
+            code.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_ktbf_analytics() -> Result<()> {
+        let eval_date = datetime!(2024-01-02 00:00:00 UTC);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_date)));
+        let curve =
+            |rate: Real, name: &str| flat_zero_curve(&evaluation_date, rate, Currency::KRW, name);
+        let ktbf_maturity = eval_date + Duration::days(90);
+        let bonds = vec![
+            government_bond(datetime!(2027-01-02 00:00:00 UTC), ktbf_maturity, "Bond1")?,
+            government_bond(datetime!(2029-01-02 00:00:00 UTC), ktbf_maturity, "Bond2")?,
+        ];
+        let ktbf = |tenor: Integer, tag: &str| -> Result<Instrument> {
+            Ok(Instrument::KTBF(KTBF::new(
+                Currency::KRW,
+                1_000_000.0,
+                eval_date,
+                ktbf_maturity,
+                ktbf_maturity,
+                KtbfVirtualBond::new(tenor, 0.05, PaymentFrequency::SemiAnnually, 100.0),
+                bonds.clone(),
+                tag.to_string(),
+                tag.to_string(),
+                tag.to_string(),
+            )?))
+        };
+        let (ktbf3y, ktbf5y) = (ktbf(3, "KTBF3Y")?, ktbf(5, "KTBF5Y")?);
+        let pricer = |tag: &str| -> Result<KtbfPricer> {
+            Ok(KtbfPricer::new(
+                evaluation_date.clone(),
+                curve(0.035, "KRWGOV")?,
+                curve(0.003, tag)?,
+            ))
+        };
+        let (pricer3y, pricer5y) = (pricer("KTBF3Y")?, pricer("KTBF5Y")?);
+        let npv = pricer3y.npv(&ktbf3y)?;
+
+        // at the theoretical price, the implied yield is the basket yield
+        let analytics_at_npv = pricer3y.analytics(&ktbf3y, npv)?;
+        assert!((analytics_at_npv.get_theoretical_price() - npv).abs() < 1.0e-4);
+        assert!(analytics_at_npv.get_basis().abs() < 1.0e-4);
+        assert!(analytics_at_npv.get_yield_basis().abs() < 1.0e-5);
+
+        // market over theoretical price implies lower yield
+        let analytics = pricer3y.analytics(&ktbf3y, npv + 0.1)?;
+        assert!((analytics.get_basis() - 0.1).abs() < 1.0e-4);
+        assert!(analytics.get_yield_basis() > 0.0);
+        assert!(analytics.get_implied_yield() < analytics.get_basket_yield());
+
+        let contribution: Real = analytics
+            .get_underlying_analytics()
+            .iter()
+            .map(|x| x.get_yield_contribution())
+            .sum();
+        assert!((contribution - analytics.get_basket_yield()).abs() < 1.0e-6);
+        for x in analytics.get_underlying_analytics().iter() {
+            assert!(x.get_dv01() > 0.0);
+            assert!(
+                (x.get_hedge_ratio() * x.get_dv01() - analytics.get_dv01()).abs()
+                    < 1.0e-3 * analytics.get_dv01()
+            );
+        }
+        // the longer bond has more dv01, so less of it hedges the contract
+        let hedge_ratios: Vec<Real> = analytics
+            .get_underlying_analytics()
+            .iter()
+            .map(|x| x.get_hedge_ratio())
+            .collect();
+        assert!(hedge_ratios[0] > hedge_ratios[1]);
+
+        // KTBF3Y is hedged by less than a contract of KTBF5Y
+        let analytics5y = pricer5y.analytics(&ktbf5y, pricer5y.npv(&ktbf5y)?)?;
+        let ratio = analytics.get_hedge_ratio_against(&analytics5y);
+        assert!(ratio > 0.0 && ratio < 1.0);
+        assert!((ratio * analytics5y.get_hedge_ratio_against(&analytics) - 1.0).abs() < 1.0e-6);
+        Ok(())
+    }
 }
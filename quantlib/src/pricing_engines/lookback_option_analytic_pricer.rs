@@ -0,0 +1,417 @@
+use crate::definitions::{Real, Time};
+use crate::enums::{LookbackType, OptionType};
+use crate::evaluation_date::EvaluationDate;
+use crate::instrument::{Instrument, InstrumentTrait};
+use crate::instruments::lookback_option::LookbackOption;
+use crate::parameters::market_price::MarketPrice;
+use crate::parameters::past_price::DailyClosePrice;
+use crate::parameters::{quanto::Quanto, volatility::Volatility, zero_curve::ZeroCurve};
+use crate::pricing_engines::pricer::PricerTrait;
+use crate::pricing_engines::{futures_pricer::FuturesPricer, npv_result::NpvResult};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use anyhow::{anyhow, Context, Result};
+use statrs::distribution::{ContinuousCDF, Normal};
+use std::{cell::RefCell, rc::Rc};
+use time::OffsetDateTime;
+
+/// The minimum and the maximum of the underlying from the issue date to the evaluation date,
+/// from the past closes and the current spot.
+pub(crate) fn running_extremes(
+    option: &LookbackOption,
+    eval_date: &OffsetDateTime,
+    spot: Real,
+    past_close_data: Option<&DailyClosePrice>,
+) -> Result<(Real, Real)> {
+    let issue_date = option.get_issue_date()?.date();
+    if issue_date >= eval_date.date() {
+        return Ok((spot, spot));
+    }
+    let past_close_data = past_close_data.ok_or_else(|| {
+        anyhow!(
+            "({}:{}) {} ({}) has been issued on {} but the past closes of {} are not given",
+            file!(),
+            line!(),
+            option.get_name(),
+            option.get_code(),
+            issue_date,
+            option.get_underlying_codes()[0]
+        )
+    })?;
+    Ok(
+        match past_close_data.get_min_max(&issue_date, &eval_date.date()) {
+            Some((min, max)) => (min.min(spot), max.max(spot)),
+            None => (spot, spot),
+        },
+    )
+}
+
+/// cost of carry close to zero makes the closed forms singular
+const MIN_CARRY: f64 = 1.0e-4;
+
+/// Terms of the closed forms of Goldman, Sosin and Gatto (1979) and Conze and Viswanathan (1991)
+/// in the notation of Haug, where the cost of carry b and the short rate r are implied
+/// by the forward and the discount factor to maturity.
+struct LookbackClosedForm {
+    spot: f64,
+    t: f64,
+    b: f64,
+    discount: f64,
+    forward_discount: f64,
+    sigma: f64,
+    normal: Normal,
+}
+
+impl LookbackClosedForm {
+    fn new(spot: Real, t: Time, forward: Real, discount: Real, vol: Real) -> LookbackClosedForm {
+        let (spot, t) = (spot as f64, t as f64);
+        let b = (forward as f64 / spot).ln() / t;
+        let b = match b.abs() < MIN_CARRY {
+            true => MIN_CARRY.copysign(b),
+            false => b,
+        };
+        LookbackClosedForm {
+            spot,
+            t,
+            b,
+            discount: discount as f64,
+            forward_discount: forward as f64 * discount as f64,
+            sigma: vol as f64,
+            normal: Normal::new(0.0, 1.0).unwrap(),
+        }
+    }
+
+    fn n(&self, x: f64) -> f64 {
+        self.normal.cdf(x)
+    }
+
+    fn d1(&self, x: f64) -> f64 {
+        ((self.spot / x).ln() + (self.b + 0.5 * self.sigma * self.sigma) * self.t)
+            / (self.sigma * self.t.sqrt())
+    }
+
+    /// the terms with sigma^2 / (2b) which come from the reflection of the extreme,
+    /// sign = -1 for the maximum and 1 for the minimum
+    fn reflection(&self, x: f64, d1: f64, sign: f64) -> f64 {
+        let sigma2 = self.sigma * self.sigma;
+        let shift = 2.0 * self.b * self.t.sqrt() / self.sigma;
+        self.spot * self.discount * sigma2 / (2.0 * self.b)
+            * (sign * (self.spot / x).powf(-2.0 * self.b / sigma2) * self.n(sign * (shift - d1))
+                - sign * (self.b * self.t).exp() * self.n(-sign * d1))
+    }
+
+    /// discounted E[max(M, x)] - x * discount where M is the maximum until maturity
+    fn maximum_excess(&self, x: f64) -> f64 {
+        let d1 = self.d1(x);
+        let d2 = d1 - self.sigma * self.t.sqrt();
+        self.forward_discount * self.n(d1) - x * self.discount * self.n(d2)
+            + self.reflection(x, d1, -1.0)
+    }
+
+    /// x * discount - discounted E[min(m, x)] where m is the minimum until maturity
+    fn minimum_shortfall(&self, x: f64) -> f64 {
+        let d1 = self.d1(x);
+        let d2 = d1 - self.sigma * self.t.sqrt();
+        x * self.discount * self.n(-d2) - self.forward_discount * self.n(-d1)
+            + self.reflection(x, d1, 1.0)
+    }
+}
+
+/// Analytic pricer for LookbackOption with continuous monitoring.
+/// Fixed-strike lookbacks are priced by Conze and Viswanathan (1991) with the volatility at the strike,
+/// and floating-strike lookbacks by Goldman, Sosin and Gatto (1979) with the at-the-money volatility.
+/// The running minimum and maximum include the past closes from the issue date.
+pub struct LookbackOptionAnalyticPricer {
+    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    market_price: Rc<RefCell<MarketPrice>>,
+    futures_helper: FuturesPricer,
+    discount_curve: Rc<RefCell<ZeroCurve>>,
+    volatility: Rc<RefCell<Volatility>>,
+    quanto: Option<Rc<RefCell<Quanto>>>,
+    past_close_data: Option<Rc<DailyClosePrice>>,
+    time_calculator: NullCalendar,
+}
+
+impl LookbackOptionAnalyticPricer {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        market_price: Rc<RefCell<MarketPrice>>,
+        collateral_curve: Rc<RefCell<ZeroCurve>>,
+        borrowing_curve: Rc<RefCell<ZeroCurve>>,
+        discount_curve: Rc<RefCell<ZeroCurve>>,
+        volatility: Rc<RefCell<Volatility>>,
+        quanto: Option<Rc<RefCell<Quanto>>>,
+        past_close_data: Option<Rc<DailyClosePrice>>,
+    ) -> LookbackOptionAnalyticPricer {
+        let futures_helper =
+            FuturesPricer::new(market_price.clone(), collateral_curve, borrowing_curve);
+
+        LookbackOptionAnalyticPricer {
+            evaluation_date,
+            market_price,
+            futures_helper,
+            discount_curve,
+            volatility,
+            quanto,
+            past_close_data,
+            time_calculator: NullCalendar::new(),
+        }
+    }
+}
+
+impl PricerTrait for LookbackOptionAnalyticPricer {
+    fn npv(&self, instrument: &Instrument) -> Result<Real> {
+        let option = match instrument {
+            Instrument::LookbackOption(option) => option,
+            _ => {
+                return Err(anyhow!(
+                    "({}:{}) LookbackOptionAnalyticPricer::npv: not supported instrument type: {}",
+                    file!(),
+                    line!(),
+                    instrument.get_type_name()
+                ))
+            }
+        };
+        if instrument.get_currency() != instrument.get_underlying_currency()?
+            && self.quanto.is_none()
+        {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has different currency from underlying market_price ({}) but no quanto is provided",
+                file!(), line!(),
+                instrument.get_name(), instrument.get_code(), self.market_price.borrow().get_name(),
+            ));
+        }
+
+        let maturity = instrument
+            .get_maturity()
+            .context("(LookbackOptionAnalyticPricer:npv) Failed to get maturity")?;
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let t = self
+            .time_calculator
+            .get_time_difference(&eval_date, maturity);
+        let spot = self.market_price.borrow().get_value();
+        let (running_min, running_max) =
+            running_extremes(option, &eval_date, spot, self.past_close_data.as_deref())?;
+        let option_type = option.get_option_type()?;
+        let lookback_type = option.get_lookback_type();
+
+        if t <= 0.0 {
+            return Ok(match (lookback_type, option_type) {
+                (LookbackType::FixedStrike, OptionType::Call) => {
+                    (running_max - option.get_strike()?).max(0.0)
+                }
+                (LookbackType::FixedStrike, OptionType::Put) => {
+                    (option.get_strike()? - running_min).max(0.0)
+                }
+                (LookbackType::FloatingStrike, OptionType::Call) => spot - running_min,
+                (LookbackType::FloatingStrike, OptionType::Put) => running_max - spot,
+            });
+        }
+
+        let fwd = self.futures_helper.fair_forward(maturity)?;
+        let forward_moneyness = match lookback_type {
+            LookbackType::FixedStrike => option.get_strike()? / fwd,
+            LookbackType::FloatingStrike => 1.0,
+        };
+        let vol = self.volatility.borrow().get_value(t, forward_moneyness);
+        let quanto_adjustment = match &self.quanto {
+            Some(quanto) => vol * t * quanto.borrow().quanto_adjust(t, forward_moneyness),
+            None => 0.0,
+        };
+        let fwd = fwd * (-quanto_adjustment).exp();
+        let dsc = self.discount_curve.borrow().get_discount_factor(t)?;
+
+        let closed_form = LookbackClosedForm::new(spot, t, fwd, dsc, vol);
+        let (running_min, running_max) = (running_min as f64, running_max as f64);
+        let dsc = dsc as f64;
+        let res = match (lookback_type, option_type) {
+            (LookbackType::FixedStrike, OptionType::Call) => {
+                let strike = option.get_strike()? as f64;
+                let x = strike.max(running_max);
+                dsc * (x - strike) + closed_form.maximum_excess(x)
+            }
+            (LookbackType::FixedStrike, OptionType::Put) => {
+                let strike = option.get_strike()? as f64;
+                let x = strike.min(running_min);
+                dsc * (strike - x) + closed_form.minimum_shortfall(x)
+            }
+            (LookbackType::FloatingStrike, OptionType::Call) => {
+                closed_form.forward_discount - dsc * running_min
+                    + closed_form.minimum_shortfall(running_min)
+            }
+            (LookbackType::FloatingStrike, OptionType::Put) => {
+                dsc * running_max + closed_form.maximum_excess(running_max)
+                    - closed_form.forward_discount
+            }
+        };
+        Ok(res as Real)
+    }
+
+    fn npv_result(&self, instrument: &Instrument) -> Result<NpvResult> {
+        let npv = self.npv(instrument)?;
+        Ok(NpvResult::new_from_npv(npv))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::data;
+    use crate::enums::OptionDailySettlementType;
+    use crate::parameters::volatilities::constant_volatility::ConstantVolatility;
+    use crate::pricing_engines::montecarlo::path_dependent_montecarlo_pricer::PathDependentMonteCarloPricer;
+    use crate::time::calendar::Calendar;
+    use crate::time::calendars::southkorea::{SouthKorea, SouthKoreaType};
+    use crate::vectordatasample;
+    use std::collections::HashMap;
+    use time::macros::datetime;
+    use time::{Duration, UtcOffset};
+
+    #[test]
+    fn test_lookback_option_analytic_pricer() -> Result<()> {
+        // Haug, The Complete Guide to Option Pricing Formulas, floating strike lookback call
+        // S = 120, S_min = 100, T = 0.5, r = 0.10, b = 0.04, vol = 0.30
+        let t = 0.5;
+        let closed_form =
+            LookbackClosedForm::new(120.0, t, 120.0 * (0.04 * t).exp(), (-0.1 * t).exp(), 0.3);
+        let floating_call = closed_form.forward_discount - closed_form.discount * 100.0
+            + closed_form.minimum_shortfall(100.0);
+        assert!(
+            (floating_call - 25.3533).abs() < 1.0e-3,
+            "floating strike lookback call: {}",
+            floating_call
+        );
+
+        let issue_date = datetime!(2023-07-03 16:30:00 +09:00);
+        let eval_date = datetime!(2024-01-02 16:30:00 +09:00);
+        let maturity = eval_date + Duration::days(182);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_date)));
+        let market_price = Rc::new(RefCell::new(MarketPrice::new(
+            100.0,
+            eval_date,
+            None,
+            Currency::KRW,
+            "KOSPI2".to_string(),
+            "KOSPI2".to_string(),
+        )));
+        let curve_data = vectordatasample!(0.03, Currency::KRW, "Lookback Test Curve")?;
+        let curve = Rc::new(RefCell::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &curve_data,
+            "Lookback Test Curve".to_string(),
+            "Lookback Test Curve".to_string(),
+        )?));
+        let volatility = Rc::new(RefCell::new(Volatility::ConstantVolatility(
+            ConstantVolatility::new(0.25, "KOSPI2".to_string(), "KOSPI2".to_string()),
+        )));
+        // the underlying has moved between 90 and 110 since the issue date
+        let mut closes = HashMap::new();
+        let mut date = issue_date.date();
+        while date < eval_date.date() {
+            closes.insert(date, 100.0 + 10.0 * ((closes.len() as Real) * 0.1).sin());
+            date = date.next_day().unwrap();
+        }
+        let past_close_data = Rc::new(DailyClosePrice::new(
+            closes,
+            time::Time::from_hms(15, 40, 0)?,
+            UtcOffset::from_hms(9, 0, 0)?,
+            Calendar::SouthKorea(SouthKorea::new(SouthKoreaType::Krx)),
+            "KOSPI2".to_string(),
+            "KOSPI2".to_string(),
+        ));
+
+        let analytic = |past: Option<Rc<DailyClosePrice>>| {
+            LookbackOptionAnalyticPricer::new(
+                evaluation_date.clone(),
+                market_price.clone(),
+                curve.clone(),
+                curve.clone(),
+                curve.clone(),
+                volatility.clone(),
+                None,
+                past,
+            )
+        };
+        let montecarlo = PathDependentMonteCarloPricer::new(
+            evaluation_date.clone(),
+            market_price.clone(),
+            curve.clone(),
+            curve.clone(),
+            curve.clone(),
+            volatility.clone(),
+            None,
+            Some(past_close_data.clone()),
+            6_000,
+            252,
+            1,
+        );
+        let option = |lookback_type: LookbackType, option_type: OptionType, issue_date| {
+            let strike = match lookback_type {
+                LookbackType::FixedStrike => Some(105.0),
+                LookbackType::FloatingStrike => None,
+            };
+            LookbackOption::new(
+                lookback_type,
+                strike,
+                1.0,
+                issue_date,
+                maturity,
+                maturity,
+                maturity,
+                vec!["KOSPI2".to_string()],
+                Currency::KRW,
+                Currency::KRW,
+                option_type,
+                OptionDailySettlementType::NotSettled,
+                "Lookback Test".to_string(),
+                "Lookback Test".to_string(),
+            )
+            .map(Instrument::LookbackOption)
+        };
+
+        let dsc = curve.borrow().get_discount_factor_at_date(&maturity)?;
+        for lookback_type in [LookbackType::FixedStrike, LookbackType::FloatingStrike] {
+            for option_type in [OptionType::Call, OptionType::Put] {
+                let new_deal = option(lookback_type, option_type, eval_date)?;
+                let running_deal = option(lookback_type, option_type, issue_date)?;
+                let continuous = analytic(None).npv(&new_deal)?;
+                let running = analytic(Some(past_close_data.clone())).npv(&running_deal)?;
+                // the past extremes are 90 and 110
+                assert!(running > continuous, "{} <= {}", running, continuous);
+                match (lookback_type, option_type) {
+                    (LookbackType::FixedStrike, OptionType::Call) => {
+                        assert!(running >= dsc * 5.0)
+                    }
+                    (LookbackType::FixedStrike, OptionType::Put) => {
+                        assert!(running >= dsc * 15.0)
+                    }
+                    _ => assert!(running >= 10.0 * dsc),
+                }
+
+                // the daily monitoring gives less extreme values than the continuous monitoring
+                // by about 0.5826 vol sqrt(dt) of the spot (Broadie, Glasserman and Kou)
+                let monitoring_bias = 0.5826 * 0.25 * (1.0 / 252.0 as Real).sqrt() * 100.0;
+                for (deal, value) in [(&new_deal, continuous), (&running_deal, running)] {
+                    let mc = montecarlo.npv_result(deal)?;
+                    let standard_error = mc.get_standard_error().unwrap();
+                    assert!(
+                        mc.get_npv() < value + 3.0 * standard_error
+                            && mc.get_npv() > value - 1.5 * monitoring_bias - 3.0 * standard_error,
+                        "{:?} {:?}: continuous {}, daily Monte Carlo {} (standard error {})",
+                        lookback_type,
+                        option_type,
+                        value,
+                        mc.get_npv(),
+                        standard_error
+                    );
+                }
+            }
+        }
+        // the running deal needs the past closes
+        let running_deal = option(LookbackType::FloatingStrike, OptionType::Call, issue_date)?;
+        assert!(analytic(None).npv(&running_deal).is_err());
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use crate::enums::{
     IssuerType,
     //RateIndexCode,
     OptionDailySettlementType,
+    RankType,
 };
 use crate::instrument::{Instrument, InstrumentTrait};
 use crate::instruments::plain_swap::PlainSwapType;
@@ -32,6 +33,21 @@ pub struct MatchParameter {
     crs_curve_map: HashMap<Currency, String>,
     //
     funding_cost_map: HashMap<Currency, String>,
+    // (issuer_type: IssuerType,
+    //  credit_rating: CreditRating,
+    //  rank: RankType,
+    //  currency: Currency) -> credit spread curve name
+    // as in the rating grids of the bond pricing agencies.
+    // The spreads are over the curve of the currency in risk_free_curve_map
+    #[serde(default)]
+    credit_spread_curve_map: HashMap<(IssuerType, CreditRating, RankType, Currency), String>,
+    // Currency::KRW -> String::from("KRWGOV")
+    #[serde(default)]
+    risk_free_curve_map: HashMap<Currency, String>,
+    // reference entity: String -> curve of the CDS par spreads,
+    // e.g., String::from("ACME") -> String::from("ACME CDS")
+    #[serde(default)]
+    cds_spread_curve_map: HashMap<String, String>,
     //
     dummy_string: String,
 }
@@ -55,6 +71,9 @@ impl Default for MatchParameter {
             rate_index_forward_curve_map,
             crs_curve_map,
             funding_cost_map,
+            credit_spread_curve_map: HashMap::new(),
+            risk_free_curve_map: HashMap::new(),
+            cds_spread_curve_map: HashMap::new(),
             dummy_string: String::from("Dummy"),
         }
     }
@@ -76,10 +95,93 @@ impl MatchParameter {
             rate_index_forward_curve_map,
             crs_curve_map,
             funding_cost_map,
+            credit_spread_curve_map: HashMap::new(),
+            risk_free_curve_map: HashMap::new(),
+            cds_spread_curve_map: HashMap::new(),
             dummy_string: String::from("Dummy"),
         }
     }
 
+    /// Bonds which are not in bond_discount_curve_map are discounted by the credit spread curve
+    /// of their issuer type, credit rating, rank and currency,
+    /// which is layered on the risk-free curve of the currency
+    pub fn with_credit_spread_curve_map(
+        mut self,
+        credit_spread_curve_map: HashMap<(IssuerType, CreditRating, RankType, Currency), String>,
+        risk_free_curve_map: HashMap<Currency, String>,
+    ) -> MatchParameter {
+        self.credit_spread_curve_map = credit_spread_curve_map;
+        self.risk_free_curve_map = risk_free_curve_map;
+        self
+    }
+
+    /// CDS are discounted by the risk-free curve of their currency,
+    /// and their survival curves are bootstrapped from the par spread curves of their reference entities.
+    /// risk_free_curve_map is merged into the one given in with_credit_spread_curve_map
+    pub fn with_cds_spread_curve_map(
+        mut self,
+        cds_spread_curve_map: HashMap<String, String>,
+        risk_free_curve_map: HashMap<Currency, String>,
+    ) -> MatchParameter {
+        self.cds_spread_curve_map = cds_spread_curve_map;
+        self.risk_free_curve_map.extend(risk_free_curve_map);
+        self
+    }
+
+    pub fn get_cds_spread_curve_name(&self, instrument: &Instrument) -> Result<&String> {
+        match instrument {
+            Instrument::CreditDefaultSwap(cds) => {
+                let reference_entity = cds.get_reference_entity();
+                self.cds_spread_curve_map.get(reference_entity).ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) {} ({}) has reference entity {}, \
+                        but its par spread curve is not found in MatchParameter.cds_spread_curve_map",
+                        file!(),
+                        line!(),
+                        instrument.get_name(),
+                        instrument.get_code(),
+                        reference_entity,
+                    )
+                })
+            }
+            _ => Ok(&self.dummy_string),
+        }
+    }
+
+    /// the risk-free curve under the credit spread curve, or None if curve_name is not a credit spread curve
+    pub fn get_credit_spread_base_curve_name(
+        &self,
+        curve_name: &String,
+    ) -> Result<Option<&String>> {
+        let currency = self
+            .credit_spread_curve_map
+            .iter()
+            .find(|(_, name)| *name == curve_name)
+            .map(|((_, _, _, currency), _)| currency);
+        match currency {
+            None => Ok(None),
+            Some(currency) => match self.risk_free_curve_map.get(currency) {
+                Some(base_curve_name) => Ok(Some(base_curve_name)),
+                None => Err(anyhow!(
+                    "({}:{}) {} is a credit spread curve in {:?}, \
+                    but the risk-free curve is not found in MatchParameter.risk_free_curve_map",
+                    file!(),
+                    line!(),
+                    curve_name,
+                    currency,
+                )),
+            },
+        }
+    }
+
+    /// credit spread curves and CDS par spread curves, which are bumped for CS01 rather than rho
+    pub fn is_credit_spread_curve(&self, curve_name: &String) -> bool {
+        self.credit_spread_curve_map
+            .values()
+            .chain(self.cds_spread_curve_map.values())
+            .any(|name| name == curve_name)
+    }
+
     /// In the cases of crs, fx products, etc, this means the base_curve
     /// For example, if the undrlying fx is usdkrw, then crs_curve is krwcrs
     pub fn get_crs_curve_name(&self, instrument: &Instrument) -> Result<&String> {
@@ -99,7 +201,7 @@ impl MatchParameter {
                     ))?;
                 Ok(res)
             }
-            Instrument::FxFutures(instrument) => {
+            Instrument::FxFutures(_) | Instrument::FxForward(_) | Instrument::FxOption(_) => {
                 let currency = instrument.get_currency();
                 let res = self.crs_curve_map.get(currency)
                     .ok_or_else(|| anyhow!(
@@ -131,7 +233,7 @@ impl MatchParameter {
                     ))?;
                 Ok(res)
             }
-            Instrument::FxFutures(instrument) => {
+            Instrument::FxFutures(_) | Instrument::FxForward(_) | Instrument::FxOption(_) => {
                 let underlying_currency = instrument.get_underlying_currency()?;
                 let res = self.crs_curve_map.get(underlying_currency)
                     .ok_or_else(|| anyhow!(
@@ -182,11 +284,20 @@ impl MatchParameter {
                     *instrument.get_currency(),
                 )) {
                     Some(curve_name) => Ok(curve_name),
-                    None => Ok(&self.dummy_string),
+                    None => match self.credit_spread_curve_map.get(&(
+                        *instrument.get_issuer_type()?,
+                        *instrument.get_credit_rating()?,
+                        *instrument.get_rank_type()?,
+                        *instrument.get_currency(),
+                    )) {
+                        Some(curve_name) => Ok(curve_name),
+                        None => Ok(&self.dummy_string),
+                    },
                 }
             }
             // IRS (or OIS) uses rate index forward curve as discount curve
-            Instrument::PlainSwap(instrument) => {
+            // so do caps, floors and swaptions on the rate index
+            Instrument::PlainSwap(_) | Instrument::CapFloor(_) | Instrument::Swaption(_) => {
                 let rate_index = instrument
                     .get_rate_index()
                     .context("Rate index is not found")
@@ -205,7 +316,10 @@ impl MatchParameter {
                     }
                 }
             }
-            Instrument::VanillaOption(instrument) => {
+            Instrument::VanillaOption(_)
+            | Instrument::BarrierOption(_)
+            | Instrument::AsianOption(_)
+            | Instrument::LookbackOption(_) => {
                 match instrument.get_option_daily_settlement_type()? {
                     OptionDailySettlementType::Settled => Ok(&self.dummy_string),
                     OptionDailySettlementType::NotSettled => {
@@ -222,10 +336,52 @@ impl MatchParameter {
                     }
                 }
             }
+            // structured notes are discounted by the funding cost of the issuer
+            Instrument::Autocallable(_) => {
+                match self.funding_cost_map.get(instrument.get_currency()) {
+                    Some(curve_name) => Ok(curve_name),
+                    None => Err(anyhow!(
+                        "({}:{}) Funding cost curve is not found for {} ({}).\n\
+                        The currency is {:?} but its curve is not found in MatchParameter.funding_cost",
+                        file!(),
+                        line!(),
+                        instrument.get_name(),
+                        instrument.get_code(),
+                        instrument.get_currency(),
+                    )),
+                }
+            }
+            // the deliverables of bond futures and KTBF are priced on the discount curve of the deliverable bonds
+            Instrument::BondFutures(_) | Instrument::KTBF(_) => {
+                let bond = instrument.get_underlying_bonds()?.first().ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) {} ({}) has no deliverable bond",
+                        file!(),
+                        line!(),
+                        instrument.get_name(),
+                        instrument.get_code()
+                    )
+                })?;
+                self.get_discount_curve_name(&Instrument::Bond(bond.clone()))
+            }
+            Instrument::CreditDefaultSwap(_) => {
+                match self.risk_free_curve_map.get(instrument.get_currency()) {
+                    Some(curve_name) => Ok(curve_name),
+                    None => Err(anyhow!(
+                        "({}:{}) Risk free rate curve is not found for {} ({}).\n\
+                        The currency is {:?} but its curve is not found in MatchParameter.risk_free_curve_map",
+                        file!(),
+                        line!(),
+                        instrument.get_name(),
+                        instrument.get_code(),
+                        instrument.get_currency(),
+                    )),
+                }
+            }
+            // FX forwards and options are discounted on the crs curves of both currencies
+            Instrument::FxForward(_) | Instrument::FxOption(_) => Ok(&self.dummy_string),
             // these are indestruments that do not need to be discounted
             Instrument::Futures(_)
-            | Instrument::BondFutures(_)
-            | Instrument::KTBF(_)
             | Instrument::FxFutures(_)
             | Instrument::Stock(_)
             | Instrument::Cash(_) => Ok(&self.dummy_string),
@@ -291,7 +447,7 @@ impl MatchParameter {
                 };
                 res
             }
-            Instrument::PlainSwap(instrument) => {
+            Instrument::PlainSwap(_) | Instrument::CapFloor(_) | Instrument::Swaption(_) => {
                 let rate_index = instrument.get_rate_index()?;
                 let res = match rate_index {
                     None => Ok(&self.dummy_string),
@@ -318,6 +474,12 @@ impl MatchParameter {
     pub fn get_borrowing_curve_map(&self) -> &HashMap<String, String> {
         &self.borrowing_curve_map
     }
+
+    pub fn get_credit_spread_curve_map(
+        &self,
+    ) -> &HashMap<(IssuerType, CreditRating, RankType, Currency), String> {
+        &self.credit_spread_curve_map
+    }
 }
 
 #[cfg(test)]
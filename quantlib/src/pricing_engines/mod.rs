@@ -1,21 +1,40 @@
+pub mod analytic_greeks;
+pub mod asian_option_analytic_pricer;
+pub mod barrier_option_analytic_pricer;
 pub mod calculation_configuration;
 pub mod calculation_result;
 pub mod engine;
+pub mod implied_volatility;
+pub mod lookback_option_analytic_pricer;
 pub mod option_analytic_pricer;
+pub mod option_fd_pricer;
 pub mod pricer;
 pub mod montecarlo {
+    pub mod autocallable_montecarlo_pricer;
+    pub mod option_montecarlo_pricer;
+    pub mod path_dependent_montecarlo_pricer;
     pub mod rand_generator;
 }
+pub mod bond_analytics;
+pub mod bond_futures_pricer;
 pub mod bond_pricer;
 pub mod cash_pricer;
+pub mod credit_default_swap_pricer;
 pub mod engine_generator;
 pub mod futures_pricer;
+pub mod fx_forward_pricer;
 pub mod fx_futures_pricer;
+pub mod fx_option_pricer;
+pub mod historical_var;
 pub mod identity_pricer;
 pub mod krx_yield_pricer;
 pub mod ktbf_pricer;
 pub mod match_parameter;
 pub mod npv_result;
 pub mod plain_swap_pricer;
+pub mod pnl_explain;
 pub mod pricer_factory;
+pub mod rate_option_pricer;
+pub mod scenario_market_data;
+pub mod stress_scenario;
 pub mod unit_pricer;
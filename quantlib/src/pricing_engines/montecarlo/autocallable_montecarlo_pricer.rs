@@ -0,0 +1,656 @@
+use crate::definitions::{Real, Time};
+use crate::evaluation_date::EvaluationDate;
+use crate::instrument::{Instrument, InstrumentTrait};
+use crate::instruments::autocallable::Autocallable;
+use crate::math::cholescky_factorization::cholesky_decomposition;
+use crate::parameters::market_price::MarketPrice;
+use crate::parameters::past_price::DailyClosePrice;
+use crate::parameters::{quanto::Quanto, volatility::Volatility, zero_curve::ZeroCurve};
+use crate::pricing_engines::montecarlo::{
+    option_montecarlo_pricer::MonteCarloOptionPricer, rand_generator::correlated_path_with_cholesky,
+};
+use crate::pricing_engines::pricer::PricerTrait;
+use crate::pricing_engines::{futures_pricer::FuturesPricer, npv_result::NpvResult};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use anyhow::{anyhow, Context, Result};
+use ndarray::{Array1, Array2};
+use rand::{rngs::StdRng, SeedableRng};
+use std::{cell::RefCell, rc::Rc};
+use time::{Duration, OffsetDateTime};
+
+/// what has happened on the autocallable before the evaluation date
+struct PastEvents {
+    knocked_in: bool,
+    unpaid_coupon: Real,
+    /// discounted amounts which are fixed but not paid yet
+    fixed_value: Real,
+    redeemed: bool,
+}
+
+/// Multi-asset Monte Carlo pricer for Autocallable.
+/// Each underlying follows the local volatility dynamics of MonteCarloOptionPricer
+/// in the forward moneyness, and the Brownian motions are correlated by the Cholesky factor
+/// of the correlation matrix (in the order of the underlying codes of the instrument).
+/// The paths are stepped on the time grid of time_steps_per_year merged with the observation dates.
+/// If the knock-in barrier is not hit yet, the paths are stepped on the business days of the instrument calendar
+/// instead, so that the knock-in is monitored on the daily closes as in the term sheet.
+///
+/// The observations and the daily closes before the evaluation date are read from DailyClosePrice,
+/// so that the deals already running are valued with the knock-in, the unpaid memory coupons
+/// and the early redemption that have happened.
+/// The paths are generated with antithetic variates and a fixed seed.
+pub struct AutocallableMonteCarloPricer {
+    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    market_prices: Vec<Rc<RefCell<MarketPrice>>>,
+    futures_helpers: Vec<FuturesPricer>,
+    volatilities: Vec<Rc<RefCell<Volatility>>>,
+    quantos: Vec<Option<Rc<RefCell<Quanto>>>>,
+    past_close_data: Vec<Option<Rc<DailyClosePrice>>>,
+    correlation: Array2<Real>,
+    discount_curve: Rc<RefCell<ZeroCurve>>,
+    path_number: usize,
+    time_steps_per_year: usize,
+    seed: u64,
+    time_calculator: NullCalendar,
+}
+
+impl AutocallableMonteCarloPricer {
+    /// market_prices, collateral_curves, borrowing_curves, volatilities, quantos and past_close_data
+    /// are in the order of the underlying codes of the instrument
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        market_prices: Vec<Rc<RefCell<MarketPrice>>>,
+        collateral_curves: Vec<Rc<RefCell<ZeroCurve>>>,
+        borrowing_curves: Vec<Rc<RefCell<ZeroCurve>>>,
+        volatilities: Vec<Rc<RefCell<Volatility>>>,
+        quantos: Vec<Option<Rc<RefCell<Quanto>>>>,
+        past_close_data: Vec<Option<Rc<DailyClosePrice>>>,
+        correlation: Array2<Real>,
+        discount_curve: Rc<RefCell<ZeroCurve>>,
+        path_number: usize,
+        time_steps_per_year: usize,
+        seed: u64,
+    ) -> Result<AutocallableMonteCarloPricer> {
+        let n = market_prices.len();
+        if collateral_curves.len() != n
+            || borrowing_curves.len() != n
+            || volatilities.len() != n
+            || quantos.len() != n
+            || past_close_data.len() != n
+            || correlation.shape() != [n, n]
+        {
+            return Err(anyhow!(
+                "({}:{}) the market data of the {} underlyings do not have the same length \
+                (correlation shape: {:?})",
+                file!(),
+                line!(),
+                n,
+                correlation.shape()
+            ));
+        }
+        let futures_helpers = market_prices
+            .iter()
+            .zip(collateral_curves)
+            .zip(borrowing_curves)
+            .map(|((market_price, collateral), borrowing)| {
+                FuturesPricer::new(market_price.clone(), collateral, borrowing)
+            })
+            .collect();
+
+        Ok(AutocallableMonteCarloPricer {
+            evaluation_date,
+            market_prices,
+            futures_helpers,
+            volatilities,
+            quantos,
+            past_close_data,
+            correlation,
+            discount_curve,
+            path_number,
+            time_steps_per_year,
+            seed,
+            time_calculator: NullCalendar::new(),
+        })
+    }
+
+    fn discount_factor_at(&self, date: &OffsetDateTime) -> Result<Real> {
+        let t = self
+            .time_calculator
+            .get_time_difference(self.evaluation_date.borrow().get_date(), date);
+        self.discount_curve.borrow().get_discount_factor(t)
+    }
+
+    /// worst performance on a past date from DailyClosePrice, None if any close is missing
+    fn past_worst_performance(
+        &self,
+        autocallable: &Autocallable,
+        date: &time::Date,
+    ) -> Result<Option<Real>> {
+        let mut worst = Real::MAX;
+        for (k, initial) in autocallable.get_initial_prices().iter().enumerate() {
+            let past = self.past_close_data[k].as_ref().ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) {} ({}) needs the past close prices of {} on {}",
+                    file!(),
+                    line!(),
+                    autocallable.get_name(),
+                    autocallable.get_code(),
+                    autocallable.get_underlying_codes()[k],
+                    date
+                )
+            })?;
+            match past.get(date) {
+                Some(close) => worst = worst.min(close / initial),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(worst))
+    }
+
+    /// knock-in on the daily closes, and the observations before the evaluation date
+    fn past_events(&self, autocallable: &Autocallable) -> Result<PastEvents> {
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let mut events = PastEvents {
+            knocked_in: false,
+            unpaid_coupon: 0.0,
+            fixed_value: 0.0,
+            redeemed: false,
+        };
+
+        let issue_date = autocallable.get_issue_date()?.date();
+        if let Some(knock_in_barrier) = autocallable.get_knock_in_barrier() {
+            if issue_date < eval_date.date() {
+                let past = self.past_close_data[0].as_ref().ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) {} ({}) is running but the past close prices of {} are not given",
+                        file!(),
+                        line!(),
+                        autocallable.get_name(),
+                        autocallable.get_code(),
+                        autocallable.get_underlying_codes()[0]
+                    )
+                })?;
+                for date in past.get_value().keys() {
+                    if *date <= issue_date || *date >= eval_date.date() {
+                        continue;
+                    }
+                    if let Some(worst) = self.past_worst_performance(autocallable, date)? {
+                        if worst < knock_in_barrier {
+                            events.knocked_in = true;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        let schedule = autocallable.get_schedule()?;
+        let last = schedule.len() - 1;
+        for (i, base_schedule) in schedule.iter().enumerate() {
+            let observation_date = base_schedule.get_calc_end_date().date();
+            if observation_date >= eval_date.date() {
+                break;
+            }
+            let worst = self
+                .past_worst_performance(autocallable, &observation_date)?
+                .ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) {} ({}) misses the close prices on the observation date {}",
+                        file!(),
+                        line!(),
+                        autocallable.get_name(),
+                        autocallable.get_code(),
+                        observation_date
+                    )
+                })?;
+            let payment_date = base_schedule.get_payment_date();
+            let unpaid = payment_date > &eval_date;
+            if let Some(knock_in_barrier) = autocallable.get_knock_in_barrier() {
+                events.knocked_in |= worst < knock_in_barrier;
+            }
+
+            if !autocallable.get_coupons().is_empty() {
+                if worst >= autocallable.get_coupon_barriers()[i] {
+                    let amount = autocallable.get_coupons()[i] + events.unpaid_coupon;
+                    events.unpaid_coupon = 0.0;
+                    if unpaid {
+                        events.fixed_value += amount * self.discount_factor_at(payment_date)?;
+                    }
+                } else if autocallable.get_coupon_memory() {
+                    events.unpaid_coupon += autocallable.get_coupons()[i];
+                }
+            }
+
+            let redemption = if worst >= autocallable.get_autocall_barriers()[i] {
+                Some(1.0 + autocallable.get_autocall_coupons()[i])
+            } else if i == last {
+                Some(autocallable.get_maturity_redemption(worst, events.knocked_in))
+            } else {
+                None
+            };
+            if let Some(amount) = redemption {
+                if unpaid {
+                    events.fixed_value += amount * self.discount_factor_at(payment_date)?;
+                }
+                events.redeemed = true;
+                break;
+            }
+        }
+        Ok(events)
+    }
+}
+
+impl PricerTrait for AutocallableMonteCarloPricer {
+    fn npv(&self, instrument: &Instrument) -> Result<Real> {
+        Ok(self.npv_result(instrument)?.get_npv())
+    }
+
+    fn npv_result(&self, instrument: &Instrument) -> Result<NpvResult> {
+        let autocallable = match instrument {
+            Instrument::Autocallable(autocallable) => autocallable,
+            _ => {
+                return Err(anyhow!(
+                    "({}:{}) AutocallableMonteCarloPricer: not supported instrument type: {}",
+                    file!(),
+                    line!(),
+                    instrument.get_type_name()
+                ))
+            }
+        };
+        let n_assets = autocallable.get_initial_prices().len();
+        if n_assets != self.market_prices.len() {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has {} underlyings but the pricer has {}",
+                file!(),
+                line!(),
+                autocallable.get_name(),
+                autocallable.get_code(),
+                n_assets,
+                self.market_prices.len()
+            ));
+        }
+        if instrument.get_currency() != instrument.get_underlying_currency()?
+            && self.quantos.iter().any(|q| q.is_none())
+        {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has different currency from the underlyings but no quanto is provided",
+                file!(),
+                line!(),
+                autocallable.get_name(),
+                autocallable.get_code(),
+            ));
+        }
+
+        let past = self.past_events(autocallable).with_context(|| {
+            anyhow!(
+                "({}:{}) failed to read the past events of {} ({})",
+                file!(),
+                line!(),
+                autocallable.get_name(),
+                autocallable.get_code()
+            )
+        })?;
+        if past.redeemed {
+            return Ok(NpvResult::new_from_npv(past.fixed_value));
+        }
+
+        // the observations from the evaluation date
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let schedule = autocallable.get_schedule()?;
+        let first = schedule
+            .iter()
+            .position(|s| s.get_calc_end_date().date() >= eval_date.date())
+            .unwrap_or(schedule.len());
+        let observation_dates: Vec<OffsetDateTime> = schedule
+            .iter()
+            .skip(first)
+            .map(|s| match s.get_calc_end_date() < &eval_date {
+                true => eval_date,
+                false => *s.get_calc_end_date(),
+            })
+            .collect();
+        let payment_discounts = schedule
+            .iter()
+            .skip(first)
+            .map(|s| self.discount_factor_at(s.get_payment_date()))
+            .collect::<Result<Vec<Real>>>()?;
+
+        // the time grid of the time steps merged with the observation dates.
+        // The knock-in barrier is observed on the daily closes, so the paths are stepped on every business day
+        // until the knock-in, which does not need to be monitored once it has happened.
+        let last_observation = *observation_dates.last().unwrap();
+        let mut grid_dates: Vec<OffsetDateTime> = vec![eval_date];
+        if autocallable.get_knock_in_barrier().is_some() && !past.knocked_in {
+            let calendar = autocallable.get_calendar()?;
+            let mut date = eval_date + Duration::days(1);
+            while date < last_observation {
+                if calendar.is_business_day(&date) {
+                    grid_dates.push(date);
+                }
+                date += Duration::days(1);
+            }
+        } else {
+            let step_days = (365.0 / self.time_steps_per_year.max(1) as Real).max(1.0);
+            let mut k = 1;
+            loop {
+                let date = eval_date + Duration::days((k as Real * step_days).round() as i64);
+                if date >= last_observation {
+                    break;
+                }
+                grid_dates.push(date);
+                k += 1;
+            }
+        }
+        grid_dates.extend(observation_dates.iter().copied());
+        grid_dates.sort();
+        grid_dates.dedup();
+        let grid_times: Vec<Time> = grid_dates
+            .iter()
+            .map(|date| self.time_calculator.get_time_difference(&eval_date, date))
+            .collect();
+        let observation_index: Vec<Option<usize>> = grid_dates
+            .iter()
+            .map(|date| observation_dates.iter().position(|d| d == date))
+            .collect();
+        let mut forwards: Array2<Real> = Array2::zeros((n_assets, grid_dates.len()));
+        for (k, helper) in self.futures_helpers.iter().enumerate() {
+            for (j, date) in grid_dates.iter().enumerate() {
+                forwards[[k, j]] = match j {
+                    0 => self.market_prices[k].borrow().get_value(),
+                    _ => helper.fair_forward(date)?,
+                };
+            }
+        }
+
+        let cholesky = cholesky_decomposition(&self.correlation).map_err(|e| {
+            anyhow!(
+                "({}:{}) failed to decompose the correlation of {} ({}): {}\n{:?}",
+                file!(),
+                line!(),
+                autocallable.get_name(),
+                autocallable.get_code(),
+                e,
+                self.correlation
+            )
+        })?;
+
+        let volatilities: Vec<_> = self.volatilities.iter().map(|v| v.borrow()).collect();
+        let quantos: Vec<_> = self
+            .quantos
+            .iter()
+            .map(|q| q.as_ref().map(|q| q.borrow()))
+            .collect();
+        let initial_prices = autocallable.get_initial_prices();
+        let knock_in_barrier = autocallable.get_knock_in_barrier();
+        let coupons = autocallable.get_coupons();
+        let coupon_barriers = autocallable.get_coupon_barriers();
+        let autocall_barriers = autocallable.get_autocall_barriers();
+        let autocall_coupons = autocallable.get_autocall_coupons();
+        let n_observations = observation_dates.len();
+
+        let half = (self.path_number / 2).max(1);
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut samples: Array1<Real> = Array1::zeros(2 * half);
+        let steps = grid_dates.len() - 1;
+        for i in 0..half {
+            let z = correlated_path_with_cholesky(&mut rng, steps, &cholesky);
+            for (sample_index, sign) in [(i, 1.0), (i + half, -1.0)] {
+                let mut log_x: Array1<Real> = Array1::zeros(n_assets);
+                let mut knocked_in = past.knocked_in;
+                let mut unpaid_coupon = past.unpaid_coupon;
+                let mut value = past.fixed_value;
+                for j in 0..grid_dates.len() {
+                    if j > 0 {
+                        let t = grid_times[j - 1];
+                        let dt = grid_times[j] - t;
+                        let sqrt_dt = dt.max(0.0).sqrt();
+                        for k in 0..n_assets {
+                            let x = log_x[k].exp();
+                            let vol = volatilities[k].get_local_volatility(t, x)?;
+                            let quanto_drift = match &quantos[k] {
+                                Some(q) => vol * q.quanto_adjust(t, x),
+                                None => 0.0,
+                            };
+                            log_x[k] += (-0.5 * vol * vol - quanto_drift) * dt
+                                + vol * sqrt_dt * sign * z[[k, j - 1]];
+                        }
+                    }
+                    let worst = (0..n_assets)
+                        .map(|k| forwards[[k, j]] * log_x[k].exp() / initial_prices[k])
+                        .fold(Real::MAX, Real::min);
+                    if let Some(barrier) = knock_in_barrier {
+                        knocked_in |= worst < barrier;
+                    }
+                    let Some(o) = observation_index[j] else {
+                        continue;
+                    };
+                    let m = first + o;
+                    if !coupons.is_empty() {
+                        if worst >= coupon_barriers[m] {
+                            value += (coupons[m] + unpaid_coupon) * payment_discounts[o];
+                            unpaid_coupon = 0.0;
+                        } else if autocallable.get_coupon_memory() {
+                            unpaid_coupon += coupons[m];
+                        }
+                    }
+                    if worst >= autocall_barriers[m] {
+                        value += (1.0 + autocall_coupons[m]) * payment_discounts[o];
+                        break;
+                    }
+                    if o == n_observations - 1 {
+                        value += autocallable.get_maturity_redemption(worst, knocked_in)
+                            * payment_discounts[o];
+                    }
+                }
+                samples[sample_index] = value;
+            }
+        }
+
+        let (mean, standard_error) = MonteCarloOptionPricer::antithetic_estimate(&samples);
+        Ok(NpvResult::new_from_npv(mean).with_standard_error(standard_error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::data;
+    use crate::parameters::volatilities::constant_volatility::ConstantVolatility;
+    use crate::time::calendar::Calendar;
+    use crate::time::calendars::southkorea::{SouthKorea, SouthKoreaType};
+    use crate::time::conventions::{BusinessDayConvention, PaymentFrequency};
+    use crate::time::jointcalendar::JointCalendar;
+    use crate::vectordatasample;
+    use ndarray::array;
+    use std::collections::HashMap;
+    use time::macros::datetime;
+    use time::{UtcOffset, Weekday};
+
+    fn els(issue_date: OffsetDateTime, autocall_barriers: Vec<Real>) -> Result<Autocallable> {
+        let calendar = JointCalendar::new(vec![Calendar::SouthKorea(SouthKorea::new(
+            SouthKoreaType::Krx,
+        ))])?;
+        Autocallable::new_from_conventions(
+            vec!["KOSPI2".to_string(), "HSCEI".to_string()],
+            vec![100.0, 100.0],
+            autocall_barriers,
+            vec![0.03, 0.06, 0.09, 0.12, 0.15, 0.18],
+            Some(0.5),
+            0.18,
+            10_000.0,
+            issue_date,
+            issue_date + Duration::days(365 * 3),
+            calendar,
+            BusinessDayConvention::ModifiedFollowing,
+            PaymentFrequency::SemiAnnually,
+            2,
+            Currency::KRW,
+            Currency::KRW,
+            "ELS Test".to_string(),
+            "ELS Test".to_string(),
+        )
+    }
+
+    /// the weekday closes from the issue date to the day before the evaluation date
+    fn past_closes(
+        issue_date: OffsetDateTime,
+        eval_date: OffsetDateTime,
+        close: Real,
+        code: &str,
+    ) -> Result<Rc<DailyClosePrice>> {
+        let mut value = HashMap::new();
+        let mut date = issue_date.date();
+        while date < eval_date.date() {
+            if !matches!(date.weekday(), Weekday::Saturday | Weekday::Sunday) {
+                value.insert(date, close);
+            }
+            date = date.next_day().unwrap();
+        }
+        Ok(Rc::new(DailyClosePrice::new(
+            value,
+            time::Time::from_hms(15, 40, 0)?,
+            UtcOffset::from_hms(9, 0, 0)?,
+            Calendar::SouthKorea(SouthKorea::new(SouthKoreaType::Krx)),
+            code.to_string(),
+            code.to_string(),
+        )))
+    }
+
+    fn els_pricer(
+        eval_date: OffsetDateTime,
+        spots: [Real; 2],
+        past_close_data: Vec<Option<Rc<DailyClosePrice>>>,
+        time_steps_per_year: usize,
+    ) -> Result<(AutocallableMonteCarloPricer, Rc<RefCell<ZeroCurve>>)> {
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_date)));
+        let curve_data = vectordatasample!(0.03, Currency::KRW, "ELS Test Curve")?;
+        let curve = Rc::new(RefCell::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &curve_data,
+            "ELS Test Curve".to_string(),
+            "ELS Test Curve".to_string(),
+        )?));
+        let market_prices = ["KOSPI2", "HSCEI"]
+            .iter()
+            .zip(spots)
+            .map(|(code, spot)| {
+                Rc::new(RefCell::new(MarketPrice::new(
+                    spot,
+                    eval_date,
+                    None,
+                    Currency::KRW,
+                    code.to_string(),
+                    code.to_string(),
+                )))
+            })
+            .collect();
+        let volatilities = [(0.2, "KOSPI2"), (0.25, "HSCEI")]
+            .iter()
+            .map(|(vol, code)| {
+                Rc::new(RefCell::new(Volatility::ConstantVolatility(
+                    ConstantVolatility::new(*vol, code.to_string(), code.to_string()),
+                )))
+            })
+            .collect();
+        let pricer = AutocallableMonteCarloPricer::new(
+            evaluation_date,
+            market_prices,
+            vec![curve.clone(), curve.clone()],
+            vec![curve.clone(), curve.clone()],
+            volatilities,
+            vec![None, None],
+            past_close_data,
+            array![[1.0, 0.5], [0.5, 1.0]],
+            curve.clone(),
+            4_000,
+            time_steps_per_year,
+            1,
+        )?;
+        Ok((pricer, curve))
+    }
+
+    #[test]
+    fn test_autocallable_montecarlo_pricer() -> Result<()> {
+        let step_down = vec![0.90, 0.90, 0.85, 0.85, 0.80, 0.75];
+        // a new deal
+        let issue_date = datetime!(2024-01-02 16:30:00 +09:00);
+        let autocallable = Instrument::Autocallable(els(issue_date, step_down.clone())?);
+        let (pricer, _) = els_pricer(issue_date, [100.0, 100.0], vec![None, None], 52)?;
+        let result = pricer.npv_result(&autocallable)?;
+        let npv = result.get_npv();
+        let standard_error = result.get_standard_error().unwrap();
+        assert!(
+            npv > 0.8 && npv < 1.1,
+            "npv of the new ELS: {} (standard error: {})",
+            npv,
+            standard_error
+        );
+        assert!(standard_error > 0.0 && standard_error < 0.01);
+
+        // the knock-in is monitored on the daily closes whatever the time steps are
+        let (daily, _) = els_pricer(issue_date, [100.0, 100.0], vec![None, None], 365)?;
+        let (quarterly, _) = els_pricer(issue_date, [100.0, 100.0], vec![None, None], 4)?;
+        let npv_daily = daily.npv(&autocallable)?;
+        let npv_quarterly = quarterly.npv(&autocallable)?;
+        assert!(
+            (npv_daily - npv).abs() < 1.0e-6 && (npv_quarterly - npv).abs() < 1.0e-6,
+            "npv: {}, on the daily time steps: {}, on the quarterly time steps: {}",
+            npv,
+            npv_daily,
+            npv_quarterly
+        );
+
+        // redeemed on the first observation in every path
+        let mut sure_call = step_down.clone();
+        sure_call[0] = 0.0;
+        let autocallable = Instrument::Autocallable(els(issue_date, sure_call)?);
+        let (pricer, curve) = els_pricer(issue_date, [100.0, 100.0], vec![None, None], 52)?;
+        let first_payment = *autocallable.get_schedule()?[0].get_payment_date();
+        let expected = 1.03 * curve.borrow().get_discount_factor_at_date(&first_payment)?;
+        let npv = pricer.npv(&autocallable)?;
+        assert!(
+            (npv - expected).abs() < 1.0e-5,
+            "npv: {}, expected: {}",
+            npv,
+            expected
+        );
+
+        // a running deal whose first observation has passed without the early redemption
+        let issue_date = datetime!(2023-06-01 16:30:00 +09:00);
+        let eval_date = datetime!(2024-01-02 16:30:00 +09:00);
+        let autocallable = Instrument::Autocallable(els(issue_date, step_down)?);
+        let closes = |worst_close: Real| -> Result<Vec<Option<Rc<DailyClosePrice>>>> {
+            let kospi = past_closes(issue_date, eval_date, 85.0, "KOSPI2")?;
+            let mut hscei = past_closes(issue_date, eval_date, 85.0, "HSCEI")?;
+            let mut value = hscei.get_value().clone();
+            value.insert(datetime!(2023-09-04 00:00:00 +09:00).date(), worst_close);
+            hscei = Rc::new(DailyClosePrice::new(
+                value,
+                time::Time::from_hms(15, 40, 0)?,
+                UtcOffset::from_hms(9, 0, 0)?,
+                Calendar::SouthKorea(SouthKorea::new(SouthKoreaType::Krx)),
+                "HSCEI".to_string(),
+                "HSCEI".to_string(),
+            ));
+            Ok(vec![Some(kospi), Some(hscei)])
+        };
+        let (not_knocked_in, _) = els_pricer(eval_date, [85.0, 85.0], closes(80.0)?, 52)?;
+        let (knocked_in, _) = els_pricer(eval_date, [85.0, 85.0], closes(45.0)?, 52)?;
+        let npv_not_knocked_in = not_knocked_in.npv(&autocallable)?;
+        let npv_knocked_in = knocked_in.npv(&autocallable)?;
+        assert!(
+            npv_knocked_in + 0.05 < npv_not_knocked_in,
+            "knocked in: {}, not knocked in: {}",
+            npv_knocked_in,
+            npv_not_knocked_in
+        );
+
+        // the running deal can not be valued without the past closes
+        let (no_history, _) = els_pricer(eval_date, [85.0, 85.0], vec![None, None], 52)?;
+        assert!(no_history.npv(&autocallable).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,350 @@
+use crate::definitions::{Real, Time};
+use crate::enums::{OptionExerciseType, OptionType};
+use crate::evaluation_date::EvaluationDate;
+use crate::instrument::Instrument;
+use crate::instrument::InstrumentTrait;
+use crate::parameters::market_price::MarketPrice;
+use crate::parameters::{quanto::Quanto, volatility::Volatility, zero_curve::ZeroCurve};
+use crate::pricing_engines::pricer::PricerTrait;
+use crate::pricing_engines::{futures_pricer::FuturesPricer, npv_result::NpvResult};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use anyhow::{anyhow, Context, Result};
+use ndarray::{Array1, Array2};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand_distr::StandardNormal;
+use std::{cell::RefCell, rc::Rc};
+
+/// Monte Carlo pricer for VanillaOption.
+/// The simulated state is the forward moneyness X(t) = S(t) / F(t) where F(t) is the fair forward
+/// of FuturesPricer. Since the discrete ratio dividends and the funding cost are inherent in F(t),
+/// X(t) is a driftless process (except the quanto drift):
+///
+/// dX(t) / X(t) = sigma_loc(t, X(t)) dW(t) - sigma_loc(t, X(t)) * sigma_fx * rho dt
+///
+/// The paths are generated with antithetic variates and a fixed seed
+/// so that the bumped npvs in the greek calculation share the same random numbers.
+pub struct MonteCarloOptionPricer {
+    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    market_price: Rc<RefCell<MarketPrice>>,
+    futures_helper: FuturesPricer,
+    discount_curve: Rc<RefCell<ZeroCurve>>,
+    volatility: Rc<RefCell<Volatility>>,
+    quanto: Option<Rc<RefCell<Quanto>>>,
+    path_number: usize,
+    time_steps_per_year: usize,
+    seed: u64,
+    time_calculator: NullCalendar,
+}
+
+impl MonteCarloOptionPricer {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        market_price: Rc<RefCell<MarketPrice>>,
+        collateral_curve: Rc<RefCell<ZeroCurve>>,
+        borrowing_curve: Rc<RefCell<ZeroCurve>>,
+        discount_curve: Rc<RefCell<ZeroCurve>>,
+        volatility: Rc<RefCell<Volatility>>,
+        quanto: Option<Rc<RefCell<Quanto>>>,
+        path_number: usize,
+        time_steps_per_year: usize,
+        seed: u64,
+    ) -> MonteCarloOptionPricer {
+        let futures_helper = FuturesPricer::new(
+            market_price.clone(),
+            collateral_curve.clone(),
+            borrowing_curve.clone(),
+        );
+
+        MonteCarloOptionPricer {
+            evaluation_date,
+            market_price,
+            futures_helper,
+            discount_curve,
+            volatility,
+            quanto,
+            path_number,
+            time_steps_per_year,
+            seed,
+            time_calculator: NullCalendar::new(),
+        }
+    }
+
+    /// Simulate the forward moneyness X(t) = S(t) / F(t) at the given (sorted) observation times.
+    /// The result has the shape of (path_number, observation_times.len()).
+    /// The first half of the paths and the second half are antithetic to each other.
+    pub fn simulate_forward_moneyness(&self, observation_times: &[Time]) -> Result<Array2<Real>> {
+        if observation_times.windows(2).any(|w| w[0] > w[1]) {
+            return Err(anyhow!(
+                "({}:{}) observation times are not sorted: {:?}",
+                file!(),
+                line!(),
+                observation_times
+            ));
+        }
+        if self.path_number < 2 {
+            return Err(anyhow!(
+                "({}:{}) path_number (= {}) must be at least 2",
+                file!(),
+                line!(),
+                self.path_number
+            ));
+        }
+
+        let half = self.path_number / 2;
+        let n = 2 * half;
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut log_x: Array1<Real> = Array1::zeros(n);
+        let mut res: Array2<Real> = Array2::ones((n, observation_times.len()));
+
+        let volatility = self.volatility.borrow();
+        let quanto = self.quanto.as_ref().map(|q| q.borrow());
+        let steps_per_year = self.time_steps_per_year.max(1) as Real;
+
+        let mut t_prev: Time = 0.0;
+        for (k, &t_obs) in observation_times.iter().enumerate() {
+            if t_obs > t_prev {
+                let steps = ((t_obs - t_prev) * steps_per_year).ceil().max(1.0) as usize;
+                let dt = (t_obs - t_prev) / steps as Real;
+                let sqrt_dt = dt.sqrt();
+                for step in 0..steps {
+                    let t = t_prev + dt * step as Real;
+                    for i in 0..half {
+                        let z: Real = rng.sample(StandardNormal);
+                        for (j, dw) in [(i, z * sqrt_dt), (i + half, -z * sqrt_dt)] {
+                            let x = log_x[j].exp();
+                            let vol = volatility.get_local_volatility(t, x)?;
+                            let quanto_drift = match &quanto {
+                                Some(q) => vol * q.quanto_adjust(t, x),
+                                None => 0.0,
+                            };
+                            log_x[j] += (-0.5 * vol * vol - quanto_drift) * dt + vol * dw;
+                        }
+                    }
+                }
+                t_prev = t_obs;
+            }
+            res.column_mut(k).assign(&log_x.mapv(|v| v.exp()));
+        }
+        Ok(res)
+    }
+
+    /// average of the antithetic pairs and its standard error
+    pub fn antithetic_estimate(samples: &Array1<Real>) -> (Real, Real) {
+        let half = samples.len() / 2;
+        if half == 0 {
+            return (0.0, 0.0);
+        }
+        let mut sum = 0.0_f64;
+        let mut sum_sq = 0.0_f64;
+        for i in 0..half {
+            let y = 0.5 * (samples[i] as f64 + samples[i + half] as f64);
+            sum += y;
+            sum_sq += y * y;
+        }
+        let mean = sum / half as f64;
+        let variance = if half > 1 {
+            ((sum_sq - half as f64 * mean * mean) / (half as f64 - 1.0)).max(0.0)
+        } else {
+            0.0
+        };
+        (mean as Real, (variance / half as f64).sqrt() as Real)
+    }
+}
+
+impl PricerTrait for MonteCarloOptionPricer {
+    fn npv(&self, instrument: &Instrument) -> Result<Real> {
+        Ok(self.npv_result(instrument)?.get_npv())
+    }
+
+    fn npv_result(&self, instrument: &Instrument) -> Result<NpvResult> {
+        let exercise_type = instrument.get_option_exercise_type()?;
+        if exercise_type != OptionExerciseType::European {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has {:?} exercise which is not supported by MonteCarloOptionPricer",
+                file!(),
+                line!(),
+                instrument.get_name(),
+                instrument.get_code(),
+                exercise_type,
+            ));
+        }
+
+        if instrument.get_currency() != instrument.get_underlying_currency()?
+            && self.quanto.is_none()
+        {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has different currency from underlying market_price ({}) but no quanto is provided",
+                file!(), line!(),
+                instrument.get_name(), instrument.get_code(), self.market_price.borrow().get_name(),
+            ));
+        }
+
+        let maturity = instrument
+            .get_maturity()
+            .context("(MonteCarloOptionPricer:npv) Failed to get maturity")?;
+        let fwd = self.futures_helper.fair_forward(maturity)?;
+        let strike = instrument.get_strike()?;
+        let t = self
+            .time_calculator
+            .get_time_difference(self.evaluation_date.borrow().get_date(), maturity);
+
+        let dsc = self.discount_curve.borrow().get_discount_factor(t)?;
+        let paths = self.simulate_forward_moneyness(&[t])?;
+        let terminal = paths.column(0).mapv(|x| fwd * x);
+        let payoffs = match instrument.get_option_type()? {
+            OptionType::Call => terminal.mapv(|s| (s - strike).max(0.0)),
+            OptionType::Put => terminal.mapv(|s| (strike - s).max(0.0)),
+        };
+
+        let (mean, standard_error) = MonteCarloOptionPricer::antithetic_estimate(&payoffs);
+        Ok(NpvResult::new_from_npv(dsc * mean).with_standard_error(dsc * standard_error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::{Currency, FxCode};
+    use crate::data;
+    use crate::data::value_data::ValueData;
+    use crate::enums::{OptionDailySettlementType, StickynessType};
+    use crate::instruments::vanilla_option::VanillaOption;
+    use crate::parameters::volatilities::{
+        constant_volatility::ConstantVolatility, local_volatility_surface::LocalVolatilitySurface,
+        volatiltiy_interpolator::VolatilityInterplator,
+    };
+    use crate::pricing_engines::option_analytic_pricer::OptionAnalyticPricer;
+    use crate::vectordatasample;
+    use ndarray::Array1;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_montecarlo_option_pricer_against_analytic() -> Result<()> {
+        let eval_date = datetime!(2024-01-02 16:30:00 +09:00);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_date)));
+        let spot = 357.38;
+        let market_price = Rc::new(RefCell::new(MarketPrice::new(
+            spot,
+            eval_date,
+            None,
+            Currency::USD,
+            "SPX".to_string(),
+            "SPX".to_string(),
+        )));
+
+        let curve_data = vectordatasample!(0.03, Currency::KRW, "MC Test Curve")?;
+        let curve = Rc::new(RefCell::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &curve_data,
+            "MC Test Curve".to_string(),
+            "MC Test Curve".to_string(),
+        )?));
+
+        let vol_data = ValueData::new(
+            0.2,
+            Some(eval_date),
+            Currency::USD,
+            "SPX".to_string(),
+            "SPX".to_string(),
+        )?;
+        let mut lv = LocalVolatilitySurface::initialize(
+            evaluation_date.clone(),
+            market_price.clone(),
+            curve.clone(),
+            curve.clone(),
+            StickynessType::StickyToMoneyness,
+            VolatilityInterplator::default(),
+            "SPX".to_string(),
+            "SPX".to_string(),
+        )
+        .with_constant_volatility(
+            &vol_data,
+            vec![
+                "1M".to_string(),
+                "6M".to_string(),
+                "1Y".to_string(),
+                "2Y".to_string(),
+            ],
+            Array1::linspace(0.6, 1.4, 17),
+        )?;
+        lv.build()?;
+        let volatility = Rc::new(RefCell::new(Volatility::LocalVolatilitySurface(lv)));
+
+        let fx_volatility = Rc::new(RefCell::new(Volatility::ConstantVolatility(
+            ConstantVolatility::new(0.1, "USDKRW".to_string(), "USDKRW".to_string()),
+        )));
+        let quanto = Rc::new(RefCell::new(Quanto::new(
+            fx_volatility,
+            0.3,
+            FxCode::new(Currency::USD, Currency::KRW),
+            "SPX".to_string(),
+        )));
+
+        let maturity = datetime!(2024-09-15 16:30:00 +09:00);
+        for (option_type, strike, currency) in [
+            (OptionType::Call, spot * 1.05, Currency::USD),
+            (OptionType::Put, spot * 0.9, Currency::USD),
+            (OptionType::Call, spot, Currency::KRW),
+        ] {
+            let quanto = match currency {
+                Currency::USD => None,
+                _ => Some(quanto.clone()),
+            };
+            let analytic = OptionAnalyticPricer::new(
+                evaluation_date.clone(),
+                market_price.clone(),
+                curve.clone(),
+                curve.clone(),
+                curve.clone(),
+                volatility.clone(),
+                quanto.clone(),
+            );
+            let montecarlo = MonteCarloOptionPricer::new(
+                evaluation_date.clone(),
+                market_price.clone(),
+                curve.clone(),
+                curve.clone(),
+                curve.clone(),
+                volatility.clone(),
+                quanto,
+                20_000,
+                20,
+                1,
+            );
+
+            let option = VanillaOption::new(
+                strike,
+                100.0,
+                datetime!(2023-09-15 16:30:00 +09:00),
+                maturity,
+                maturity,
+                maturity,
+                vec!["SPX".to_string()],
+                Currency::USD,
+                currency,
+                option_type,
+                OptionExerciseType::European,
+                OptionDailySettlementType::NotSettled,
+                "SPX Option".to_string(),
+                "SPX Option".to_string(),
+            );
+            let inst = Instrument::VanillaOption(option);
+
+            let expected = analytic.npv(&inst)?;
+            let res = montecarlo.npv_result(&inst)?;
+            let se = res.get_standard_error().unwrap();
+            assert!(
+                (res.get_npv() - expected).abs() < 4.0 * se + 1.0e-3,
+                "{:?} {:?}: mc = {}, analytic = {}, se = {}",
+                option_type,
+                currency,
+                res.get_npv(),
+                expected,
+                se
+            );
+        }
+        Ok(())
+    }
+}
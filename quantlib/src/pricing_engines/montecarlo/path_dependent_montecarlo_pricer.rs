@@ -0,0 +1,273 @@
+use crate::definitions::{Real, Time};
+use crate::enums::{AveragingType, LookbackType, OptionType};
+use crate::evaluation_date::EvaluationDate;
+use crate::instrument::{Instrument, InstrumentTrait};
+use crate::parameters::market_price::MarketPrice;
+use crate::parameters::past_price::DailyClosePrice;
+use crate::parameters::{quanto::Quanto, volatility::Volatility, zero_curve::ZeroCurve};
+use crate::pricing_engines::asian_option_analytic_pricer::{
+    split_fixings, AsianOptionAnalyticPricer,
+};
+use crate::pricing_engines::lookback_option_analytic_pricer::running_extremes;
+use crate::pricing_engines::montecarlo::option_montecarlo_pricer::MonteCarloOptionPricer;
+use crate::pricing_engines::pricer::PricerTrait;
+use crate::pricing_engines::{futures_pricer::FuturesPricer, npv_result::NpvResult};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use anyhow::{anyhow, Context, Result};
+use ndarray::Array1;
+use std::{cell::RefCell, rc::Rc};
+use time::{Duration, OffsetDateTime, Weekday};
+
+/// Monte Carlo pricer for AsianOption and LookbackOption on the paths of MonteCarloOptionPricer.
+/// Asian options are observed on the remaining fixing dates, and the arithmetic average uses
+/// the geometric average of the same paths as the control variate whose npv is
+/// the closed form of AsianOptionAnalyticPricer (exact under a flat volatility).
+/// Lookback options are observed on the weekdays until maturity.
+/// The fixings and the closes before the evaluation date are read from past_close_data.
+pub struct PathDependentMonteCarloPricer {
+    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    market_price: Rc<RefCell<MarketPrice>>,
+    futures_helper: FuturesPricer,
+    discount_curve: Rc<RefCell<ZeroCurve>>,
+    quanto: Option<Rc<RefCell<Quanto>>>,
+    past_close_data: Option<Rc<DailyClosePrice>>,
+    path_generator: MonteCarloOptionPricer,
+    control_variate_pricer: AsianOptionAnalyticPricer,
+    time_calculator: NullCalendar,
+}
+
+impl PathDependentMonteCarloPricer {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        market_price: Rc<RefCell<MarketPrice>>,
+        collateral_curve: Rc<RefCell<ZeroCurve>>,
+        borrowing_curve: Rc<RefCell<ZeroCurve>>,
+        discount_curve: Rc<RefCell<ZeroCurve>>,
+        volatility: Rc<RefCell<Volatility>>,
+        quanto: Option<Rc<RefCell<Quanto>>>,
+        past_close_data: Option<Rc<DailyClosePrice>>,
+        path_number: usize,
+        time_steps_per_year: usize,
+        seed: u64,
+    ) -> PathDependentMonteCarloPricer {
+        let futures_helper = FuturesPricer::new(
+            market_price.clone(),
+            collateral_curve.clone(),
+            borrowing_curve.clone(),
+        );
+        let path_generator = MonteCarloOptionPricer::new(
+            evaluation_date.clone(),
+            market_price.clone(),
+            collateral_curve.clone(),
+            borrowing_curve.clone(),
+            discount_curve.clone(),
+            volatility.clone(),
+            quanto.clone(),
+            path_number,
+            time_steps_per_year,
+            seed,
+        );
+        let control_variate_pricer = AsianOptionAnalyticPricer::new(
+            evaluation_date.clone(),
+            market_price.clone(),
+            collateral_curve,
+            borrowing_curve,
+            discount_curve.clone(),
+            volatility,
+            quanto.clone(),
+            past_close_data.clone(),
+        );
+
+        PathDependentMonteCarloPricer {
+            evaluation_date,
+            market_price,
+            futures_helper,
+            discount_curve,
+            quanto,
+            past_close_data,
+            path_generator,
+            control_variate_pricer,
+            time_calculator: NullCalendar::new(),
+        }
+    }
+
+    /// times and forwards of the observation dates from the evaluation date
+    fn observation_grid(&self, dates: &[OffsetDateTime]) -> Result<(Vec<Time>, Vec<Real>)> {
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let spot = self.market_price.borrow().get_value();
+        let mut times = Vec::with_capacity(dates.len());
+        let mut forwards = Vec::with_capacity(dates.len());
+        for date in dates {
+            let t = self
+                .time_calculator
+                .get_time_difference(&eval_date, date)
+                .max(0.0);
+            times.push(t);
+            forwards.push(match t > 0.0 {
+                true => self.futures_helper.fair_forward(date)?,
+                false => spot,
+            });
+        }
+        Ok((times, forwards))
+    }
+
+    fn discount_to_maturity(&self, instrument: &Instrument) -> Result<Real> {
+        let maturity = instrument
+            .get_maturity()
+            .context("(PathDependentMonteCarloPricer) Failed to get maturity")?;
+        let t = self
+            .time_calculator
+            .get_time_difference(self.evaluation_date.borrow().get_date(), maturity)
+            .max(0.0);
+        self.discount_curve.borrow().get_discount_factor(t)
+    }
+
+    /// discounted payoffs, or the npv repeated if all the fixings have been observed
+    fn asian_samples(&self, instrument: &Instrument) -> Result<Array1<Real>> {
+        let Instrument::AsianOption(option) = instrument else {
+            unreachable!()
+        };
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let (past_fixings, future_dates) =
+            split_fixings(option, &eval_date, self.past_close_data.as_deref())?;
+        if future_dates.is_empty() {
+            let npv = self.control_variate_pricer.npv(instrument)?;
+            return Ok(Array1::from_elem(2, npv));
+        }
+        let (times, forwards) = self.observation_grid(&future_dates)?;
+        let paths = self.path_generator.simulate_forward_moneyness(&times)?;
+
+        let n = (past_fixings.len() + future_dates.len()) as Real;
+        let past_sum: Real = past_fixings.iter().sum();
+        let past_log_sum: Real = past_fixings.iter().map(|x| x.ln()).sum();
+        let strike = option.get_strike()?;
+        let option_type = option.get_option_type()?;
+        let payoff = |average: Real| match option_type {
+            OptionType::Call => (average - strike).max(0.0),
+            OptionType::Put => (strike - average).max(0.0),
+        };
+        let dsc = self.discount_to_maturity(instrument)?;
+        let averaging_type = option.get_averaging_type();
+        let geometric_npv = match averaging_type {
+            AveragingType::Arithmetic => self
+                .control_variate_pricer
+                .geometric_average_npv(instrument)?,
+            AveragingType::Geometric => 0.0,
+        };
+
+        let samples = paths
+            .rows()
+            .into_iter()
+            .map(|path| {
+                let mut sum = past_sum;
+                let mut log_sum = past_log_sum;
+                for (x, fwd) in path.iter().zip(forwards.iter()) {
+                    let s = fwd * x;
+                    sum += s;
+                    log_sum += s.ln();
+                }
+                let geometric = payoff((log_sum / n).exp());
+                match averaging_type {
+                    AveragingType::Arithmetic => {
+                        dsc * (payoff(sum / n) - geometric) + geometric_npv
+                    }
+                    AveragingType::Geometric => dsc * geometric,
+                }
+            })
+            .collect();
+        Ok(samples)
+    }
+
+    /// discounted payoffs, or the payoff repeated if the option has matured
+    fn lookback_samples(&self, instrument: &Instrument) -> Result<Array1<Real>> {
+        let Instrument::LookbackOption(option) = instrument else {
+            unreachable!()
+        };
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let maturity = *instrument
+            .get_maturity()
+            .context("(PathDependentMonteCarloPricer) Failed to get maturity")?;
+        let spot = self.market_price.borrow().get_value();
+        let (running_min, running_max) =
+            running_extremes(option, &eval_date, spot, self.past_close_data.as_deref())?;
+
+        let option_type = option.get_option_type()?;
+        let lookback_type = option.get_lookback_type();
+        let strike = match lookback_type {
+            LookbackType::FixedStrike => option.get_strike()?,
+            LookbackType::FloatingStrike => 0.0,
+        };
+        let payoff = |s: Real, min: Real, max: Real| match (lookback_type, option_type) {
+            (LookbackType::FixedStrike, OptionType::Call) => (max - strike).max(0.0),
+            (LookbackType::FixedStrike, OptionType::Put) => (strike - min).max(0.0),
+            (LookbackType::FloatingStrike, OptionType::Call) => s - min,
+            (LookbackType::FloatingStrike, OptionType::Put) => max - s,
+        };
+        if maturity <= eval_date {
+            return Ok(Array1::from_elem(2, payoff(spot, running_min, running_max)));
+        }
+
+        let mut dates = Vec::new();
+        let mut date = eval_date + Duration::days(1);
+        while date.date() < maturity.date() {
+            if !matches!(date.weekday(), Weekday::Saturday | Weekday::Sunday) {
+                dates.push(date);
+            }
+            date += Duration::days(1);
+        }
+        dates.push(maturity);
+        let (times, forwards) = self.observation_grid(&dates)?;
+        let paths = self.path_generator.simulate_forward_moneyness(&times)?;
+
+        let dsc = self.discount_to_maturity(instrument)?;
+        let samples = paths
+            .rows()
+            .into_iter()
+            .map(|path| {
+                let (mut min, mut max) = (running_min, running_max);
+                let mut s = spot;
+                for (x, fwd) in path.iter().zip(forwards.iter()) {
+                    s = fwd * x;
+                    min = min.min(s);
+                    max = max.max(s);
+                }
+                dsc * payoff(s, min, max)
+            })
+            .collect();
+        Ok(samples)
+    }
+}
+
+impl PricerTrait for PathDependentMonteCarloPricer {
+    fn npv(&self, instrument: &Instrument) -> Result<Real> {
+        Ok(self.npv_result(instrument)?.get_npv())
+    }
+
+    fn npv_result(&self, instrument: &Instrument) -> Result<NpvResult> {
+        if instrument.get_currency() != instrument.get_underlying_currency()?
+            && self.quanto.is_none()
+        {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has different currency from underlying market_price ({}) but no quanto is provided",
+                file!(), line!(),
+                instrument.get_name(), instrument.get_code(), self.market_price.borrow().get_name(),
+            ));
+        }
+        let samples = match instrument {
+            Instrument::AsianOption(_) => self.asian_samples(instrument)?,
+            Instrument::LookbackOption(_) => self.lookback_samples(instrument)?,
+            _ => {
+                return Err(anyhow!(
+                    "({}:{}) PathDependentMonteCarloPricer: not supported instrument type: {}",
+                    file!(),
+                    line!(),
+                    instrument.get_type_name()
+                ))
+            }
+        };
+        let (mean, standard_error) = MonteCarloOptionPricer::antithetic_estimate(&samples);
+        Ok(NpvResult::new_from_npv(mean).with_standard_error(standard_error))
+    }
+}
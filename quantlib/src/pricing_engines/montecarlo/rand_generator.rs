@@ -3,8 +3,8 @@ use ndarray::Array2;
 use crate::definitions::Real;
 use crate::math::cholescky_factorization::cholesky_decomposition;
 use log::info;
-use rand::thread_rng;
-use rand_distr::{Distribution, Normal};
+use rand::{thread_rng, Rng};
+use rand_distr::{Distribution, Normal, StandardNormal};
 
 // make a 2-D array of shape (n, steps) of f32 (Real) random numbers
 // This indicates a path of n objects with steps observations
@@ -25,6 +25,19 @@ pub fn correlated_path(steps: usize, correlation: &Array2<Real>) -> Array2<Real>
     }))
 }
 
+// the same as correlated_path, but with a given (e.g., seeded) rng and a precomputed cholesky factor,
+// so that the factorization is done once for all paths and the paths are reproducible
+pub fn correlated_path_with_cholesky<R: Rng>(
+    rng: &mut R,
+    steps: usize,
+    cholesky: &Array2<Real>,
+) -> Array2<Real> {
+    let n = cholesky.shape()[0];
+    cholesky.dot(&Array2::from_shape_fn((n, steps), |_| {
+        rng.sample::<Real, _>(StandardNormal)
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
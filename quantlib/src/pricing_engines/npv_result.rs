@@ -9,11 +9,13 @@ use time::OffsetDateTime;
 /// npv: Real
 /// coupon_amounts: id -> (datetimes, amount)
 /// coupon_paymeent_probability: id -> (datetime, probability)
+/// standard_error: Option<Real>, the standard error of the npv estimate (simulation based pricers only)
 #[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct NpvResult {
     npv: Real,
     cashflow_amounts: HashMap<usize, (OffsetDateTime, Real)>,
     cashflow_probabilities: HashMap<usize, (OffsetDateTime, Real)>,
+    standard_error: Option<Real>,
 }
 
 impl std::fmt::Debug for NpvResult {
@@ -22,6 +24,9 @@ impl std::fmt::Debug for NpvResult {
         write!(f, "    npv: ")?;
         write_number_with_commas(f, self.npv)?;
         writeln!(f)?;
+        if let Some(standard_error) = self.standard_error {
+            writeln!(f, "    standard_error: {}", standard_error)?;
+        }
 
         let mut keys = self.cashflow_amounts.keys().collect::<Vec<&usize>>();
         keys.sort();
@@ -55,6 +60,7 @@ impl NpvResult {
             npv,
             cashflow_amounts: HashMap::new(),
             cashflow_probabilities: HashMap::new(),
+            standard_error: None,
         }
     }
 
@@ -67,9 +73,19 @@ impl NpvResult {
             npv,
             cashflow_amounts,
             cashflow_probabilities,
+            standard_error: None,
         }
     }
 
+    pub fn with_standard_error(mut self, standard_error: Real) -> NpvResult {
+        self.standard_error = Some(standard_error);
+        self
+    }
+
+    pub fn get_standard_error(&self) -> Option<Real> {
+        self.standard_error
+    }
+
     pub fn get_npv(&self) -> Real {
         self.npv
     }
@@ -97,6 +113,7 @@ impl Default for NpvResult {
             npv: 0.0,
             cashflow_amounts: HashMap::new(),
             cashflow_probabilities: HashMap::new(),
+            standard_error: None,
         }
     }
 }
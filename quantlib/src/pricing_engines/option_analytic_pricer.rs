@@ -1,23 +1,36 @@
+use crate::data::surface_data::SurfaceData;
 use crate::definitions::Real;
-use crate::enums::OptionType;
+use crate::enums::{OptionType, StickynessType};
 use crate::evaluation_date::EvaluationDate;
 use crate::instrument::Instrument;
 use crate::instrument::InstrumentTrait;
 use crate::parameters::market_price::MarketPrice;
 use crate::parameters::{quanto::Quanto, volatility::Volatility, zero_curve::ZeroCurve};
+use crate::pricing_engines::implied_volatility::black_implied_volatility;
 use crate::pricing_engines::pricer::PricerTrait;
-use crate::pricing_engines::{futures_pricer::FuturesPricer, npv_result::NpvResult};
+use crate::pricing_engines::{
+    analytic_greeks::AnalyticGreeks, futures_pricer::FuturesPricer, npv_result::NpvResult,
+};
 use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
 //
 use anyhow::{anyhow, Context, Result};
 
-use statrs::distribution::{ContinuousCDF, Normal};
-use std::{cell::RefCell, rc::Rc};
+use ndarray::{Array1, Array2};
+use statrs::distribution::{Continuous, ContinuousCDF, Normal};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use time::OffsetDateTime;
+
+/// relative forward bump for the smile slope and curvature in the analytic greeks.
+/// This is the default delta_bump_ratio of CalculationConfiguration
+/// so that the skew term matches the bumped delta and gamma.
+const SMILE_DIFFERENCE_RATIO: Real = 0.01;
 
 pub struct OptionAnalyticPricer {
     evaluation_date: Rc<RefCell<EvaluationDate>>,
     market_price: Rc<RefCell<MarketPrice>>,
     futures_helper: FuturesPricer,
+    collateral_curve: Rc<RefCell<ZeroCurve>>,
+    borrowing_curve: Rc<RefCell<ZeroCurve>>,
     discount_curve: Rc<RefCell<ZeroCurve>>,
     volatility: Rc<RefCell<Volatility>>,
     quanto: Option<Rc<RefCell<Quanto>>>,
@@ -45,12 +58,164 @@ impl OptionAnalyticPricer {
             evaluation_date,
             market_price,
             futures_helper,
+            collateral_curve,
+            borrowing_curve,
             discount_curve,
             volatility,
             quanto,
             time_calculator: NullCalendar::new(),
         }
     }
+
+    /// Implied volatility reproducing the premium (in the unit of npv) under the same forward,
+    /// discounting and quanto adjustment as the npv of this pricer.
+    pub fn implied_volatility(&self, instrument: &Instrument, premium: Real) -> Result<Real> {
+        let maturity = instrument
+            .get_maturity()
+            .context("(OptionAnalyticPricer:implied_volatility) Failed to get maturity")?;
+        let fwd = self.futures_helper.fair_forward(maturity)?;
+        let strike = instrument.get_strike()?;
+        let forward_moneyness = strike / fwd;
+        let t = self
+            .time_calculator
+            .get_time_difference(self.evaluation_date.borrow().get_date(), maturity);
+
+        if instrument.get_currency() != instrument.get_underlying_currency()?
+            && self.quanto.is_none()
+        {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has different currency from underlying market_price ({}) but no quanto is provided",
+                file!(), line!(),
+                instrument.get_name(), instrument.get_code(), self.market_price.borrow().get_name(),
+            ));
+        }
+        let quanto_drift = match &self.quanto {
+            Some(quanto) => quanto.borrow().quanto_adjust(t, forward_moneyness),
+            None => 0.0,
+        };
+        let dsc = self.discount_curve.borrow().get_discount_factor(t)?;
+
+        black_implied_volatility(
+            premium,
+            fwd,
+            strike,
+            t,
+            dsc,
+            quanto_drift,
+            instrument.get_option_type()?,
+        )
+        .with_context(|| {
+            anyhow!(
+                "({}:{}) failed to get implied volatility of {} ({})",
+                file!(),
+                line!(),
+                instrument.get_name(),
+                instrument.get_code()
+            )
+        })
+    }
+
+    /// Invert an option chain into a SurfaceData on the maturities and strikes in the chain.
+    /// When both a call and a put are quoted on a node, the out-of-the-money one is used.
+    /// Missing strikes on a maturity are filled by linear interpolation in strike
+    /// with flat extrapolation, so that the result feeds LocalVolatilitySurface::with_market_surface.
+    pub fn implied_volatility_surface(
+        &self,
+        options: &[Instrument],
+        premiums: &[Real],
+        name: String,
+        code: String,
+    ) -> Result<SurfaceData> {
+        if options.len() != premiums.len() || options.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) options ({}) and premiums ({}) must have the same non-zero length",
+                file!(),
+                line!(),
+                options.len(),
+                premiums.len()
+            ));
+        }
+
+        let mut maturities: Vec<OffsetDateTime> = Vec::new();
+        let mut dates: Vec<OffsetDateTime> = Vec::new();
+        let mut strikes: Vec<Real> = Vec::new();
+        for option in options.iter() {
+            let maturity = *option.get_maturity().ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) failed to get maturity of {} ({})",
+                    file!(),
+                    line!(),
+                    option.get_name(),
+                    option.get_code()
+                )
+            })?;
+            if !dates.contains(&maturity) {
+                dates.push(maturity);
+            }
+            maturities.push(maturity);
+            let strike = option.get_strike()?;
+            if !strikes.contains(&strike) {
+                strikes.push(strike);
+            }
+        }
+        dates.sort();
+        strikes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        // (implied volatility, is out of the money) on each node
+        let mut nodes: Vec<Vec<Option<(Real, bool)>>> =
+            vec![vec![None; strikes.len()]; dates.len()];
+        for ((option, premium), maturity) in
+            options.iter().zip(premiums.iter()).zip(maturities.iter())
+        {
+            let strike = option.get_strike()?;
+            let i = dates.iter().position(|d| d == maturity).unwrap();
+            let j = strikes.iter().position(|k| *k == strike).unwrap();
+            let fwd = self.futures_helper.fair_forward(maturity)?;
+            let otm = match option.get_option_type()? {
+                OptionType::Call => strike >= fwd,
+                OptionType::Put => strike <= fwd,
+            };
+            match nodes[i][j] {
+                Some((_, true)) => continue,
+                Some((_, false)) if !otm => continue,
+                _ => {}
+            }
+            let vol = self.implied_volatility(option, *premium)?;
+            nodes[i][j] = Some((vol, otm));
+        }
+
+        let mut value = Array2::<Real>::zeros((dates.len(), strikes.len()));
+        for (i, row) in nodes.iter().enumerate() {
+            let quoted: Vec<(Real, Real)> = row
+                .iter()
+                .zip(strikes.iter())
+                .filter_map(|(node, k)| node.map(|(vol, _)| (*k, vol)))
+                .collect();
+            for (j, k) in strikes.iter().enumerate() {
+                value[[i, j]] = match quoted.iter().position(|(qk, _)| qk >= k) {
+                    Some(0) => quoted[0].1,
+                    Some(p) => {
+                        let (k0, v0) = quoted[p - 1];
+                        let (k1, v1) = quoted[p];
+                        v0 + (v1 - v0) * (k - k0) / (k1 - k0)
+                    }
+                    None => quoted[quoted.len() - 1].1,
+                };
+            }
+        }
+
+        let market_price = self.market_price.borrow();
+        Ok(SurfaceData::new(
+            Some(market_price.get_value()),
+            value,
+            dates,
+            Array1::from_vec(strikes),
+            Some(self.evaluation_date.borrow().get_date_clone()),
+            *market_price.get_currency(),
+            name,
+            code,
+        ))
+    }
 }
 
 impl PricerTrait for OptionAnalyticPricer {
@@ -112,6 +277,134 @@ impl PricerTrait for OptionAnalyticPricer {
         let npv = self.npv(instrument)?;
         Ok(NpvResult::new_from_npv(npv))
     }
+
+    /// Black greeks with the same forward, discounting and quanto adjustment as npv.
+    /// Under StickyToMoneyness the volatility moves along the smile as the forward moves,
+    /// which is what the bumped greeks see since the surface is a function of forward moneyness.
+    /// Under StickyToStrike the volatility of the strike is held.
+    /// The quanto adjustment is held at the current forward moneyness,
+    /// and theta holds the spot, the zero rates, the carry and the volatility.
+    fn analytic_greeks(
+        &self,
+        instrument: &Instrument,
+        stickyness: StickynessType,
+    ) -> Result<Option<AnalyticGreeks>> {
+        let maturity = instrument
+            .get_maturity()
+            .context("(OptionAnalyticPricer:analytic_greeks) Failed to get maturity")?;
+        let t = self
+            .time_calculator
+            .get_time_difference(self.evaluation_date.borrow().get_date(), maturity);
+        // expired or expiring today, leave it to the bumped greeks
+        if t <= 0.0 {
+            return Ok(None);
+        }
+
+        if instrument.get_currency() != instrument.get_underlying_currency()?
+            && self.quanto.is_none()
+        {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has different currency from underlying market_price ({}) but no quanto is provided",
+                file!(), line!(),
+                instrument.get_name(), instrument.get_code(), self.market_price.borrow().get_name(),
+            ));
+        }
+
+        let spot = self.market_price.borrow().get_value();
+        let fwd = self.futures_helper.fair_forward(maturity)?;
+        let strike = instrument.get_strike()?;
+        let forward_moneyness = strike / fwd;
+        let vol = self.volatility.borrow().get_value(t, forward_moneyness);
+        let quanto_drift = match &self.quanto {
+            Some(quanto) => quanto.borrow().quanto_adjust(t, forward_moneyness),
+            None => 0.0,
+        };
+        let dsc = self.discount_curve.borrow().get_discount_factor(t)?;
+        let option_type = instrument.get_option_type()?;
+
+        // d1 = x / s + s / 2 - a as in npv where s = vol * sqrt(t) and a = quanto_drift * sqrt(t)
+        let sqrt_t = t.sqrt();
+        let s = vol * sqrt_t;
+        let a = quanto_drift * sqrt_t;
+        let x = (fwd / strike).ln();
+        let d1 = x / s + 0.5 * s - a;
+        let d2 = d1 - s;
+
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        let nd1 = normal.cdf(d1 as f64) as Real;
+        let nd2 = normal.cdf(d2 as f64) as Real;
+        let pd1 = normal.pdf(d1 as f64) as Real;
+        let npv = match option_type {
+            OptionType::Call => dsc * (fwd * nd1 - strike * nd2),
+            OptionType::Put => dsc * (strike * (1.0 - nd2) - fwd * (1.0 - nd1)),
+        };
+
+        // K phi(d2) = F phi(d1) exp(-a s), so that c vanishes without quanto
+        let e = (-a * s).exp();
+        let c = 1.0 - e;
+        let dd1_ds = -x / (s * s) + 0.5;
+        let h = -x * c / (s * s) + 0.5 * (1.0 + e);
+        let dh_ds = -x * (a * e / (s * s) - 2.0 * c / (s * s * s)) - 0.5 * a * e;
+
+        // partial derivatives in the forward and the total deviation.
+        // call and put differ by dsc * (F - K), so only the first order in F differs
+        let v_f = match option_type {
+            OptionType::Call => dsc * (nd1 + pd1 * c / s),
+            OptionType::Put => dsc * (nd1 + pd1 * c / s - 1.0),
+        };
+        let v_ff = dsc * pd1 / (fwd * s) * (1.0 - c * d1 / s);
+        let v_s = dsc * fwd * pd1 * h;
+        let v_fs = dsc * pd1 * (dd1_ds * (1.0 - d1 * c / s) + (a * e * s - c) / (s * s));
+        let v_ss = dsc * fwd * pd1 * (dh_ds - d1 * dd1_ds * h);
+        let v_a = -dsc * fwd * pd1 * c;
+
+        let vega = v_s * sqrt_t;
+        let v_fvol = v_fs * sqrt_t;
+        let volga = v_ss * t;
+
+        let (vol_f, vol_ff) = match stickyness {
+            StickynessType::StickyToMoneyness => {
+                let bump = SMILE_DIFFERENCE_RATIO * fwd;
+                let vol_up = self.volatility.borrow().get_value(t, strike / (fwd + bump));
+                let vol_down = self.volatility.borrow().get_value(t, strike / (fwd - bump));
+                (
+                    (vol_up - vol_down) / (2.0 * bump),
+                    (vol_up - 2.0 * vol + vol_down) / (bump * bump),
+                )
+            }
+            StickynessType::StickyToStrike => (0.0, 0.0),
+        };
+
+        // total derivatives in the forward, F is proportional to S
+        let v_f_total = v_f + vega * vol_f;
+        let v_ff_total = v_ff + 2.0 * v_fvol * vol_f + volga * vol_f * vol_f + vega * vol_ff;
+        let forward_ratio = fwd / spot;
+        let delta = v_f_total * forward_ratio;
+        let gamma = v_ff_total * forward_ratio * forward_ratio;
+        let vanna = (v_fvol + volga * vol_f) * forward_ratio;
+
+        // theta = -dV/dtau with rate = -ln(dsc) / t and carry = ln(F / S) / t held
+        let rate = -dsc.ln() / t;
+        let carry = forward_ratio.ln() / t;
+        let dv_dtau = -rate * npv
+            + v_f * carry * fwd
+            + v_s * vol / (2.0 * sqrt_t)
+            + v_a * quanto_drift / (2.0 * sqrt_t);
+        let theta = -dv_dtau;
+
+        // F = S * borrowing_df / collateral_df * dividend_deduction_ratio
+        let mut rho: HashMap<String, Real> = HashMap::new();
+        *rho.entry(self.discount_curve.borrow().get_code().clone())
+            .or_insert(0.0) += -t * npv;
+        *rho.entry(self.borrowing_curve.borrow().get_code().clone())
+            .or_insert(0.0) += -t * fwd * v_f_total;
+        *rho.entry(self.collateral_curve.borrow().get_code().clone())
+            .or_insert(0.0) += t * fwd * v_f_total;
+
+        Ok(Some(AnalyticGreeks::new(
+            delta, gamma, vega, theta, rho, vanna, volga,
+        )))
+    }
 }
 
 #[cfg(test)]
@@ -119,11 +412,13 @@ pub mod test {
     use super::*;
     use crate::currency;
     use crate::currency::Currency;
+    use crate::currency::FxCode;
     use crate::data;
     use crate::enums::{OptionDailySettlementType, OptionExerciseType, OptionType, StickynessType};
     use crate::instrument::Instrument;
     use crate::instruments::vanilla_option::VanillaOption;
     use crate::parameters::market_price::MarketPrice;
+    use crate::parameters::volatilities::constant_volatility::ConstantVolatility;
     use crate::parameters::volatilities::local_volatility_surface::LocalVolatilitySurface;
     use crate::parameters::{
         quanto::Quanto, volatilities::volatiltiy_interpolator::VolatilityInterplator,
@@ -236,4 +531,381 @@ pub mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_option_analytic_pricer_implied_volatility() -> Result<()> {
+        let eval_date = datetime!(2024-01-02 16:30:00 +09:00);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_date)));
+        let spot = 357.38;
+        let market_price = Rc::new(RefCell::new(MarketPrice::new(
+            spot,
+            eval_date,
+            None,
+            Currency::KRW,
+            "KOSPI2".to_string(),
+            "KOSPI2".to_string(),
+        )));
+
+        let discount_curve_data = vectordatasample!(0.03, Currency::KRW, "Option Test Curve")?;
+        let discount_curve = Rc::new(RefCell::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &discount_curve_data,
+            "Option Test Curve".to_string(),
+            "Option Test Curve".to_string(),
+        )?));
+
+        let surface_data = surfacedatasample!(&eval_date, spot);
+        let vega_structure_tenors = vec![
+            String::from("1M"),
+            String::from("3M"),
+            String::from("6M"),
+            String::from("1Y"),
+        ];
+        let moneyness = Array1::linspace(0.8, 1.2, 9);
+
+        let market_surface = LocalVolatilitySurface::initialize(
+            evaluation_date.clone(),
+            market_price.clone(),
+            discount_curve.clone(),
+            discount_curve.clone(),
+            StickynessType::StickyToMoneyness,
+            VolatilityInterplator::default(),
+            "KOSPI2 Local Volatility".to_string(),
+            "KOSPI2 Local Volatility".to_string(),
+        )
+        .with_market_surface(
+            &surface_data,
+            vega_structure_tenors.clone(),
+            moneyness.clone(),
+        )?;
+        let volatility = Rc::new(RefCell::new(Volatility::LocalVolatilitySurface(
+            market_surface,
+        )));
+        volatility.borrow_mut().build()?;
+
+        let pricer = OptionAnalyticPricer::new(
+            evaluation_date.clone(),
+            market_price.clone(),
+            discount_curve.clone(),
+            discount_curve.clone(),
+            discount_curve.clone(),
+            volatility.clone(),
+            None,
+        );
+
+        // an option chain on the nodes of the surface, calls and puts on every strike
+        let mut options = Vec::new();
+        for tenor in vega_structure_tenors.iter() {
+            let maturity = utils::string_arithmetic::add_period(&eval_date, tenor);
+            for m in moneyness.iter() {
+                for option_type in [OptionType::Call, OptionType::Put] {
+                    options.push(Instrument::VanillaOption(VanillaOption::new(
+                        spot * m,
+                        250_000.0,
+                        eval_date,
+                        maturity,
+                        maturity,
+                        maturity,
+                        vec!["KOSPI2".to_string()],
+                        Currency::KRW,
+                        Currency::KRW,
+                        option_type,
+                        OptionExerciseType::European,
+                        OptionDailySettlementType::NotSettled,
+                        format!("KOSPI2 {:?} {} {}", option_type, tenor, m),
+                        format!("KOSPI2 {:?} {} {}", option_type, tenor, m),
+                    )));
+                }
+            }
+        }
+        let premiums = options
+            .iter()
+            .map(|option| pricer.npv(option))
+            .collect::<Result<Vec<Real>>>()?;
+
+        let chain_surface = pricer.implied_volatility_surface(
+            &options,
+            &premiums,
+            "KOSPI2 Implied Volatility".to_string(),
+            "KOSPI2 Implied Volatility".to_string(),
+        )?;
+        assert_eq!(chain_surface.get_value().dim(), (4, 9));
+
+        // the implied surface reproduces the premiums when fed back into LocalVolatilitySurface
+        let implied_surface = LocalVolatilitySurface::initialize(
+            evaluation_date.clone(),
+            market_price.clone(),
+            discount_curve.clone(),
+            discount_curve.clone(),
+            StickynessType::StickyToMoneyness,
+            VolatilityInterplator::default(),
+            "KOSPI2 Implied Volatility".to_string(),
+            "KOSPI2 Implied Volatility".to_string(),
+        )
+        .with_market_surface(&chain_surface, vega_structure_tenors.clone(), moneyness)?;
+        let implied_volatility = Rc::new(RefCell::new(Volatility::LocalVolatilitySurface(
+            implied_surface,
+        )));
+        implied_volatility.borrow_mut().build()?;
+
+        let implied_pricer = OptionAnalyticPricer::new(
+            evaluation_date.clone(),
+            market_price.clone(),
+            discount_curve.clone(),
+            discount_curve.clone(),
+            discount_curve.clone(),
+            implied_volatility.clone(),
+            None,
+        );
+        for (option, premium) in options.iter().zip(premiums.iter()) {
+            let npv = implied_pricer.npv(option)?;
+            assert!(
+                (npv - premium).abs() < 1.0e-3,
+                "{}: npv = {}, expected = {}",
+                option.get_name(),
+                npv,
+                premium
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_option_analytic_pricer_greeks() -> Result<()> {
+        let eval_date = datetime!(2024-01-02 16:30:00 +09:00);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_date)));
+        let spot = 357.38;
+        let market_price = Rc::new(RefCell::new(MarketPrice::new(
+            spot,
+            eval_date,
+            None,
+            Currency::KRW,
+            "KOSPI2".to_string(),
+            "KOSPI2".to_string(),
+        )));
+
+        let mut curves = Vec::new();
+        for (rate, name) in [
+            (0.035, "Collateral Curve"),
+            (0.005, "Borrowing Curve"),
+            (0.03, "Discount Curve"),
+        ] {
+            let data = vectordatasample!(rate, Currency::KRW, name)?;
+            curves.push(Rc::new(RefCell::new(ZeroCurve::new(
+                evaluation_date.clone(),
+                &data,
+                name.to_string(),
+                name.to_string(),
+            )?)));
+        }
+        let (collateral_curve, borrowing_curve, discount_curve) =
+            (curves[0].clone(), curves[1].clone(), curves[2].clone());
+
+        let fx_volatility = Rc::new(RefCell::new(Volatility::ConstantVolatility(
+            ConstantVolatility::new(0.1, "USDKRW".to_string(), "USDKRW".to_string()),
+        )));
+        let quanto = Rc::new(RefCell::new(Quanto::new(
+            fx_volatility,
+            0.3,
+            FxCode::new(Currency::USD, Currency::KRW),
+            "KOSPI2".to_string(),
+        )));
+
+        let surface_data = surfacedatasample!(&eval_date, spot);
+        let make_pricer = |stickyness: StickynessType| -> Result<OptionAnalyticPricer> {
+            let lv = LocalVolatilitySurface::initialize(
+                evaluation_date.clone(),
+                market_price.clone(),
+                collateral_curve.clone(),
+                borrowing_curve.clone(),
+                stickyness,
+                VolatilityInterplator::default(),
+                "KOSPI2 Local Volatility".to_string(),
+                "KOSPI2 Local Volatility".to_string(),
+            )
+            .with_market_surface(
+                &surface_data,
+                vec![
+                    "1M".to_string(),
+                    "3M".to_string(),
+                    "6M".to_string(),
+                    "1Y".to_string(),
+                ],
+                Array1::linspace(0.6, 1.4, 17),
+            )?;
+            let volatility = Rc::new(RefCell::new(Volatility::LocalVolatilitySurface(lv)));
+            volatility.borrow_mut().build()?;
+            Ok(OptionAnalyticPricer::new(
+                evaluation_date.clone(),
+                market_price.clone(),
+                collateral_curve.clone(),
+                borrowing_curve.clone(),
+                discount_curve.clone(),
+                volatility,
+                Some(quanto.clone()),
+            ))
+        };
+        let moneyness_pricer = make_pricer(StickynessType::StickyToMoneyness)?;
+        let strike_pricer = make_pricer(StickynessType::StickyToStrike)?;
+
+        let maturity = datetime!(2024-07-02 16:30:00 +09:00);
+        let h = 0.01;
+        for (option_type, strike) in [
+            (OptionType::Call, spot * 1.1),
+            (OptionType::Put, spot * 0.9),
+        ] {
+            let inst = Instrument::VanillaOption(VanillaOption::new(
+                strike,
+                250_000.0,
+                eval_date,
+                maturity,
+                maturity,
+                maturity,
+                vec!["KOSPI2".to_string()],
+                Currency::KRW,
+                Currency::KRW,
+                option_type,
+                OptionExerciseType::European,
+                OptionDailySettlementType::NotSettled,
+                "KOSPI2 Option".to_string(),
+                "KOSPI2 Option".to_string(),
+            ));
+            let greeks = moneyness_pricer
+                .analytic_greeks(&inst, StickynessType::StickyToMoneyness)?
+                .unwrap();
+            let npv = moneyness_pricer.npv(&inst)?;
+
+            // spot bump without rebuilding the surface as in Engine::set_delta_gamma
+            market_price.borrow_mut().set_price(spot * (1.0 + h));
+            let npv_up = moneyness_pricer.npv(&inst)?;
+            market_price.borrow_mut().set_price(spot * (1.0 - h));
+            let npv_down = moneyness_pricer.npv(&inst)?;
+            market_price.borrow_mut().set_price(spot);
+            let delta = (npv_up - npv_down) / (2.0 * h * spot);
+            let gamma = (npv_up - 2.0 * npv + npv_down) / (h * spot * h * spot);
+            // the smile is piecewise linear in moneyness, so the bumped delta carries
+            // the curvature of the smile over the bump in addition to the skew
+            assert!(
+                (greeks.get_delta() - delta).abs() < 0.01 * delta.abs(),
+                "{:?} delta: analytic = {}, bumped = {}",
+                option_type,
+                greeks.get_delta(),
+                delta
+            );
+            assert!(
+                (greeks.get_gamma() - gamma).abs() < 0.05 * gamma.abs(),
+                "{:?} gamma: analytic = {}, bumped = {}",
+                option_type,
+                greeks.get_gamma(),
+                gamma
+            );
+
+            // vega, volga and vanna by parallel volatility bumps
+            let vol_bump = 0.01;
+            moneyness_pricer
+                .volatility
+                .borrow_mut()
+                .bump_volatility(None, None, None, None, vol_bump)?;
+            let npv_vol_up = moneyness_pricer.npv(&inst)?;
+            let delta_vol_up = moneyness_pricer
+                .analytic_greeks(&inst, StickynessType::StickyToMoneyness)?
+                .unwrap()
+                .get_delta();
+            moneyness_pricer.volatility.borrow_mut().bump_volatility(
+                None,
+                None,
+                None,
+                None,
+                -2.0 * vol_bump,
+            )?;
+            let npv_vol_down = moneyness_pricer.npv(&inst)?;
+            let delta_vol_down = moneyness_pricer
+                .analytic_greeks(&inst, StickynessType::StickyToMoneyness)?
+                .unwrap()
+                .get_delta();
+            moneyness_pricer
+                .volatility
+                .borrow_mut()
+                .bump_volatility(None, None, None, None, vol_bump)?;
+            let vega = (npv_vol_up - npv_vol_down) / (2.0 * vol_bump);
+            let volga = (npv_vol_up - 2.0 * npv + npv_vol_down) / (vol_bump * vol_bump);
+            let vanna = (delta_vol_up - delta_vol_down) / (2.0 * vol_bump);
+            for (name, analytic, bumped, tolerance) in [
+                ("vega", greeks.get_vega().unwrap(), vega, 0.01 * vega.abs()),
+                (
+                    "volga",
+                    greeks.get_volga().unwrap(),
+                    volga,
+                    0.05 * volga.abs(),
+                ),
+                (
+                    "vanna",
+                    greeks.get_vanna().unwrap(),
+                    vanna,
+                    0.05 * vanna.abs(),
+                ),
+            ] {
+                assert!(
+                    (analytic - bumped).abs() < tolerance,
+                    "{:?} {}: analytic = {}, bumped = {}",
+                    option_type,
+                    name,
+                    analytic,
+                    bumped
+                );
+            }
+
+            // rho on each curve by a parallel bump of 1bp
+            let rate_bump = 0.0001;
+            for curve in curves.iter() {
+                curve
+                    .borrow_mut()
+                    .bump_time_interval(None, None, rate_bump)?;
+                let npv_rate_up = moneyness_pricer.npv(&inst)?;
+                curve
+                    .borrow_mut()
+                    .bump_time_interval(None, None, -rate_bump)?;
+                let rho = (npv_rate_up - npv) / rate_bump;
+                let code = curve.borrow().get_code().clone();
+                let analytic = greeks.get_single_rho(&code).unwrap();
+                assert!(
+                    (analytic - rho).abs() < 0.05 * rho.abs() + 0.5,
+                    "{:?} rho on {}: analytic = {}, bumped = {}",
+                    option_type,
+                    code,
+                    analytic,
+                    rho
+                );
+            }
+
+            // sticky to strike: the surface is rebuilt on the bumped spot to hold the volatility of the strike
+            let strike_greeks = strike_pricer
+                .analytic_greeks(&inst, StickynessType::StickyToStrike)?
+                .unwrap();
+            market_price.borrow_mut().set_price(spot * (1.0 + h));
+            strike_pricer.volatility.borrow_mut().build()?;
+            let npv_up = strike_pricer.npv(&inst)?;
+            market_price.borrow_mut().set_price(spot * (1.0 - h));
+            strike_pricer.volatility.borrow_mut().build()?;
+            let npv_down = strike_pricer.npv(&inst)?;
+            market_price.borrow_mut().set_price(spot);
+            strike_pricer.volatility.borrow_mut().build()?;
+            let delta = (npv_up - npv_down) / (2.0 * h * spot);
+            assert!(
+                (strike_greeks.get_delta() - delta).abs() < 1.0e-3,
+                "{:?} sticky strike delta: analytic = {}, bumped = {}",
+                option_type,
+                strike_greeks.get_delta(),
+                delta
+            );
+            assert!(
+                (strike_greeks.get_delta() - greeks.get_delta()).abs() > 1.0e-3,
+                "{:?} sticky strike and sticky moneyness deltas should differ on a skewed surface",
+                option_type
+            );
+        }
+
+        Ok(())
+    }
 }
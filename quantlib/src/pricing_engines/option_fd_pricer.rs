@@ -0,0 +1,807 @@
+use crate::definitions::{Real, Time};
+use crate::enums::{OptionExerciseType, OptionType, StickynessType};
+use crate::evaluation_date::EvaluationDate;
+use crate::instrument::Instrument;
+use crate::instrument::InstrumentTrait;
+use crate::math::tridiagonal_solver::solve_tridiagonal;
+use crate::parameters::market_price::MarketPrice;
+use crate::parameters::volatilities::volatiltiy_interpolator::interpolate_on_uniform_grid;
+use crate::parameters::{quanto::Quanto, volatility::Volatility, zero_curve::ZeroCurve};
+use crate::pricing_engines::pricer::PricerTrait;
+use crate::pricing_engines::{analytic_greeks::AnalyticGreeks, npv_result::NpvResult};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use anyhow::{anyhow, Context, Result};
+use ndarray::Array1;
+use std::{cell::RefCell, rc::Rc};
+
+/// the number of standard deviations covered by the spatial grid in each direction
+const GRID_STANDARD_DEVIATIONS: Real = 5.0;
+/// the number of fully implicit steps (Rannacher smoothing) after the payoff and each discontinuity
+const RANNACHER_STEPS: usize = 2;
+/// time nodes closer than this are merged
+const TIME_NODE_TOLERANCE: Time = 1.0e-6;
+
+/// Greeks read from the grid of FiniteDifferenceOptionPricer. They are not multiplied by unit_notional.
+/// delta = dV/dS, gamma = d^2V/dS^2, theta = dV/dt (per year, spot fixed)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FiniteDifferenceGreeks {
+    delta: Real,
+    gamma: Real,
+    theta: Real,
+}
+
+impl FiniteDifferenceGreeks {
+    pub fn get_delta(&self) -> Real {
+        self.delta
+    }
+
+    pub fn get_gamma(&self) -> Real {
+        self.gamma
+    }
+
+    pub fn get_theta(&self) -> Real {
+        self.theta
+    }
+}
+
+/// Crank-Nicolson pricer for VanillaOption on the log-spot grid.
+///
+/// dS(t) / S(t) = (mu(t) - sigma_loc * sigma_fx * rho) dt + sigma_loc(t, S(t)/F(t)) dW(t)
+///
+/// mu(t) is implied by the collateral and borrowing curves as in FuturesPricer,
+/// and the discrete ratio dividends are applied as jumps S -> S * (1 - dividend_yield) on the ex-dividend dates.
+/// American options are exercised at every time node and Bermudan options at the exercise dates of the instrument.
+/// The boundary condition is V_xx = 0 in x = ln S.
+pub struct FiniteDifferenceOptionPricer {
+    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    market_price: Rc<RefCell<MarketPrice>>,
+    collateral_curve: Rc<RefCell<ZeroCurve>>,
+    borrowing_curve: Rc<RefCell<ZeroCurve>>,
+    discount_curve: Rc<RefCell<ZeroCurve>>,
+    volatility: Rc<RefCell<Volatility>>,
+    quanto: Option<Rc<RefCell<Quanto>>>,
+    space_grid_number: usize,
+    time_steps_per_year: usize,
+    time_calculator: NullCalendar,
+}
+
+/// event on a time node
+#[derive(Debug, Clone, Copy)]
+struct TimeNode {
+    time: Time,
+    dividend_ratio: Real, // S(t+) = S(t-) * dividend_ratio
+    exercisable: bool,
+}
+
+impl FiniteDifferenceOptionPricer {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        market_price: Rc<RefCell<MarketPrice>>,
+        collateral_curve: Rc<RefCell<ZeroCurve>>,
+        borrowing_curve: Rc<RefCell<ZeroCurve>>,
+        discount_curve: Rc<RefCell<ZeroCurve>>,
+        volatility: Rc<RefCell<Volatility>>,
+        quanto: Option<Rc<RefCell<Quanto>>>,
+        space_grid_number: usize,
+        time_steps_per_year: usize,
+    ) -> FiniteDifferenceOptionPricer {
+        FiniteDifferenceOptionPricer {
+            evaluation_date,
+            market_price,
+            collateral_curve,
+            borrowing_curve,
+            discount_curve,
+            volatility,
+            quanto,
+            space_grid_number,
+            time_steps_per_year,
+            time_calculator: NullCalendar::new(),
+        }
+    }
+
+    /// forward ratio F(t) / S(0) excluding dividends
+    fn carry_ratio(&self, t: Time) -> Result<Real> {
+        let borrowing = self.borrowing_curve.borrow().get_discount_factor(t)?;
+        let collateral = self.collateral_curve.borrow().get_discount_factor(t)?;
+        Ok(borrowing / collateral)
+    }
+
+    /// dividend, exercise and step nodes sorted by time. The first node is 0 and the last node is the maturity.
+    fn time_nodes(&self, instrument: &Instrument, maturity_time: Time) -> Result<Vec<TimeNode>> {
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let maturity = instrument
+            .get_maturity()
+            .ok_or_else(|| anyhow!("({}:{}) maturity is not given", file!(), line!()))?;
+        let exercise_type = instrument.get_option_exercise_type()?;
+
+        let mut events: Vec<TimeNode> = vec![
+            TimeNode {
+                time: 0.0,
+                dividend_ratio: 1.0,
+                exercisable: exercise_type == OptionExerciseType::American,
+            },
+            TimeNode {
+                time: maturity_time,
+                dividend_ratio: 1.0,
+                exercisable: false,
+            },
+        ];
+
+        if let Some(dividend) = self.market_price.borrow().get_dividend() {
+            for (date, ratio) in dividend.borrow().get_dividend_ratio() {
+                if date > eval_date && date <= *maturity {
+                    let time = self.time_calculator.get_time_difference(&eval_date, &date);
+                    events.push(TimeNode {
+                        time: time.clamp(0.0, maturity_time),
+                        dividend_ratio: 1.0 - ratio,
+                        exercisable: exercise_type == OptionExerciseType::American,
+                    });
+                }
+            }
+        }
+
+        if exercise_type == OptionExerciseType::Bermudan {
+            for date in instrument.get_exercise_dates()? {
+                if *date > eval_date && date < maturity {
+                    let time = self.time_calculator.get_time_difference(&eval_date, date);
+                    events.push(TimeNode {
+                        time,
+                        dividend_ratio: 1.0,
+                        exercisable: true,
+                    });
+                }
+            }
+        }
+
+        events.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
+        let mut merged: Vec<TimeNode> = Vec::new();
+        for event in events {
+            match merged.last_mut() {
+                Some(last) if (event.time - last.time).abs() < TIME_NODE_TOLERANCE => {
+                    last.dividend_ratio *= event.dividend_ratio;
+                    last.exercisable |= event.exercisable;
+                }
+                _ => merged.push(event),
+            }
+        }
+
+        // refine the intervals between events
+        let steps_per_year = self.time_steps_per_year.max(1) as Real;
+        let american = exercise_type == OptionExerciseType::American;
+        let mut nodes = vec![merged[0]];
+        for window in merged.windows(2) {
+            let (start, end) = (window[0], window[1]);
+            let steps = ((end.time - start.time) * steps_per_year).ceil().max(1.0) as usize;
+            let dt = (end.time - start.time) / steps as Real;
+            for step in 1..steps {
+                nodes.push(TimeNode {
+                    time: start.time + dt * step as Real,
+                    dividend_ratio: 1.0,
+                    exercisable: american,
+                });
+            }
+            nodes.push(end);
+        }
+        Ok(nodes)
+    }
+
+    /// npv and the greeks read from the grid in one backward induction.
+    /// Under StickyToStrike, delta and gamma are read on the neighboring nodes where the local volatility is held on the strike.
+    /// Under StickyToMoneyness, the local volatility on the nodes moves with the spot,
+    /// and delta and gamma are the spot derivatives of the nodes solved along with the npv.
+    /// The two are the same if the volatility is flat, where the spot derivatives are not solved.
+    pub fn npv_result_with_greeks(
+        &self,
+        instrument: &Instrument,
+        stickyness: StickynessType,
+    ) -> Result<(NpvResult, FiniteDifferenceGreeks)> {
+        let smile = !matches!(*self.volatility.borrow(), Volatility::ConstantVolatility(_))
+            || self.quanto.is_some();
+        let spot_derivatives = stickyness == StickynessType::StickyToMoneyness && smile;
+        self.backward_induction(instrument, spot_derivatives)
+    }
+
+    /// theta holds the spot, so it is dV/dt on the grid node of the spot
+    fn backward_induction(
+        &self,
+        instrument: &Instrument,
+        spot_derivatives: bool,
+    ) -> Result<(NpvResult, FiniteDifferenceGreeks)> {
+        if instrument.get_currency() != instrument.get_underlying_currency()?
+            && self.quanto.is_none()
+        {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has different currency from underlying market_price ({}) but no quanto is provided",
+                file!(), line!(),
+                instrument.get_name(), instrument.get_code(), self.market_price.borrow().get_name(),
+            ));
+        }
+
+        let maturity = instrument
+            .get_maturity()
+            .context("(FiniteDifferenceOptionPricer:npv) Failed to get maturity")?;
+        let maturity_time = self
+            .time_calculator
+            .get_time_difference(self.evaluation_date.borrow().get_date(), maturity);
+        if maturity_time <= 0.0 {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) is already matured",
+                file!(),
+                line!(),
+                instrument.get_name(),
+                instrument.get_code(),
+            ));
+        }
+
+        let strike = instrument.get_strike()?;
+        let option_type = instrument.get_option_type()?;
+        let payoff = |s: Real| match option_type {
+            OptionType::Call => (s - strike).max(0.0),
+            OptionType::Put => (strike - s).max(0.0),
+        };
+
+        let nodes = self.time_nodes(instrument, maturity_time)?;
+        let bermudan = instrument.get_option_exercise_type()? == OptionExerciseType::Bermudan;
+        let spot = self.market_price.borrow().get_value();
+        let dividend_ratios: Vec<(Time, Real)> = nodes
+            .iter()
+            .filter(|node| node.dividend_ratio != 1.0)
+            .map(|node| (node.time, node.dividend_ratio))
+            .collect();
+        let dividend_deduction = |t: Time| -> Real {
+            dividend_ratios
+                .iter()
+                .filter(|(time, _)| *time <= t)
+                .map(|(_, ratio)| ratio)
+                .product()
+        };
+        let forward =
+            |t: Time| -> Result<Real> { Ok(spot * self.carry_ratio(t)? * dividend_deduction(t)) };
+
+        // spatial grid such that the spot is located on a node
+        let volatility = self.volatility.borrow();
+        let quanto = self.quanto.as_ref().map(|q| q.borrow());
+        let forward_at_maturity = forward(maturity_time)?;
+        let reference_vol = volatility
+            .get_value(maturity_time, strike / forward_at_maturity)
+            .max(0.05);
+        let half_width = GRID_STANDARD_DEVIATIONS * reference_vol * maturity_time.sqrt()
+            + (forward_at_maturity / spot).ln().abs()
+            + (strike / spot).ln().abs();
+        let half_number = self.space_grid_number.max(10).div_ceil(2);
+        let n = 2 * half_number + 1;
+        let dx = half_width / half_number as Real;
+        let x0 = spot.ln();
+        let x: Array1<Real> =
+            Array1::from_shape_fn(n, |i| x0 + (i as Real - half_number as Real) * dx);
+        let s: Array1<Real> = x.mapv(|v| v.exp());
+
+        let mut values: Array1<Real> = s.mapv(payoff);
+        // The payoff on the node i is payoff(spot * e^{x_i - x_0}) and the local volatility on the node does not depend on the spot,
+        // so dV/dS and d^2V/dS^2 on the nodes follow the same scheme from the spot derivatives of the payoff.
+        // The second derivative of the payoff is the Dirac delta on the strike, which is spread on the two nodes around it
+        let payoff_delta = |si: Real| match option_type {
+            OptionType::Call if si > strike => si / spot,
+            OptionType::Put if si < strike => -si / spot,
+            _ => 0.0,
+        };
+        let mut derivatives: Option<(Array1<Real>, Array1<Real>)> = None;
+        if spot_derivatives {
+            let mut payoff_gamma: Array1<Real> = Array1::zeros(n);
+            let position = (strike.ln() - x[0]) / dx;
+            if position >= 0.0 && position < (n - 1) as Real {
+                let i = position.floor() as usize;
+                let w = position - i as Real;
+                for (j, weight) in [(i, 1.0 - w), (i + 1, w)] {
+                    payoff_gamma[j] = weight / (dx * strike) * (s[j] / spot).powi(2);
+                }
+            }
+            // the jump of the payoff delta on the strike is averaged over the cell around each node
+            let payoff_delta = Array1::from_shape_fn(n, |i| {
+                let in_the_money = ((x[i] - strike.ln()) / dx + 0.5).clamp(0.0, 1.0);
+                match option_type {
+                    OptionType::Call => in_the_money * s[i] / spot,
+                    OptionType::Put => -(1.0 - in_the_money) * s[i] / spot,
+                }
+            });
+            derivatives = Some((payoff_delta, payoff_gamma));
+        }
+        let mut theta_value: Option<Real> = None;
+        let mut implicit_steps_left = RANNACHER_STEPS;
+
+        for k in (0..nodes.len()).rev() {
+            let node = nodes[k];
+            // V(t-, S) = V(t+, S * dividend_ratio)
+            if node.dividend_ratio != 1.0 {
+                let shift = node.dividend_ratio.ln();
+                let jump = |v: &Array1<Real>| {
+                    Array1::from_shape_fn(n, |i| interpolate_on_uniform_grid(&x, v, x[i] + shift))
+                };
+                values = jump(&values);
+                if let Some((delta, gamma)) = derivatives.as_mut() {
+                    *delta = jump(delta);
+                    *gamma = jump(gamma);
+                }
+                implicit_steps_left = RANNACHER_STEPS;
+            }
+            if node.exercisable {
+                for i in 0..n {
+                    let exercise = payoff(s[i]);
+                    if exercise > values[i] {
+                        values[i] = exercise;
+                        if let Some((delta, gamma)) = derivatives.as_mut() {
+                            delta[i] = payoff_delta(s[i]);
+                            gamma[i] = 0.0;
+                        }
+                    }
+                }
+                if k < nodes.len() - 1 && bermudan {
+                    implicit_steps_left = RANNACHER_STEPS;
+                }
+            }
+            if k == 1 {
+                theta_value = Some(values[half_number]);
+            }
+            if k == 0 {
+                break;
+            }
+
+            // step from nodes[k] to nodes[k-1]
+            let (t_start, t_end) = (nodes[k - 1].time, node.time);
+            let dt = t_end - t_start;
+            let t_mid = 0.5 * (t_start + t_end);
+            let carry_dt = (self.carry_ratio(t_end)? / self.carry_ratio(t_start)?).ln();
+            let discount_dt = -self
+                .discount_curve
+                .borrow()
+                .get_discount_factor_between_times(t_start, t_end)?
+                .ln();
+            let forward_mid = forward(t_mid)?;
+            let theta = if implicit_steps_left > 0 {
+                implicit_steps_left -= 1;
+                1.0
+            } else {
+                0.5
+            };
+
+            // L V_i = lo_i V_{i-1} + di_i V_i + up_i V_{i+1} (multiplied by dt) on the interior nodes
+            let operator = (1..n - 1)
+                .map(|i| -> Result<(Real, Real, Real)> {
+                    let moneyness = s[i] / forward_mid;
+                    let vol = volatility.get_local_volatility(t_mid, moneyness)?;
+                    let quanto_drift = match &quanto {
+                        Some(q) => vol * q.quanto_adjust(t_mid, moneyness),
+                        None => 0.0,
+                    };
+                    let diffusion = 0.5 * vol * vol * dt / (dx * dx);
+                    let convection =
+                        (carry_dt - (quanto_drift + 0.5 * vol * vol) * dt) / (2.0 * dx);
+                    Ok((
+                        diffusion - convection,
+                        -2.0 * diffusion - discount_dt,
+                        diffusion + convection,
+                    ))
+                })
+                .collect::<Result<Vec<(Real, Real, Real)>>>()?;
+
+            values = theta_scheme_step(&operator, theta, &values)?;
+            if let Some((delta, gamma)) = derivatives.as_mut() {
+                *delta = theta_scheme_step(&operator, theta, delta)?;
+                *gamma = theta_scheme_step(&operator, theta, gamma)?;
+            }
+        }
+
+        let npv = values[half_number];
+        let theta = match theta_value {
+            Some(v) if nodes.len() > 1 => (v - npv) / nodes[1].time,
+            _ => 0.0,
+        };
+        let (delta, gamma) = match derivatives {
+            Some((delta, gamma)) => (delta[half_number], gamma[half_number]),
+            None => {
+                let (v_down, v_up) = (values[half_number - 1], values[half_number + 1]);
+                let v_x = (v_up - v_down) / (2.0 * dx);
+                let v_xx = (v_up - 2.0 * npv + v_down) / (dx * dx);
+                (v_x / spot, (v_xx - v_x) / (spot * spot))
+            }
+        };
+        let greeks = FiniteDifferenceGreeks {
+            delta,
+            gamma,
+            theta,
+        };
+        Ok((NpvResult::new_from_npv(npv), greeks))
+    }
+}
+
+/// one step of the theta scheme, (1 - theta L) V(t-) = (1 + (1 - theta) L) V(t+), on the interior nodes
+/// with V_0 = 2 V_1 - V_2 and V_{n-1} = 2 V_{n-2} - V_{n-3} (V_xx = 0 on the boundaries)
+fn theta_scheme_step(
+    operator: &[(Real, Real, Real)],
+    theta: Real,
+    values: &Array1<Real>,
+) -> Result<Array1<Real>> {
+    let n = values.len();
+    let (mut lower, mut diag, mut upper, mut rhs) = (
+        vec![0.0; n - 2],
+        vec![0.0; n - 2],
+        vec![0.0; n - 2],
+        vec![0.0; n - 2],
+    );
+    for (j, &(lo, di, up)) in operator.iter().enumerate() {
+        let i = j + 1;
+        rhs[j] =
+            values[i] + (1.0 - theta) * (lo * values[i - 1] + di * values[i] + up * values[i + 1]);
+        lower[j] = -theta * lo;
+        diag[j] = 1.0 - theta * di;
+        upper[j] = -theta * up;
+    }
+    diag[0] += 2.0 * lower[0];
+    upper[0] -= lower[0];
+    lower[0] = 0.0;
+    let last = n - 3;
+    diag[last] += 2.0 * upper[last];
+    lower[last] -= upper[last];
+    upper[last] = 0.0;
+
+    let interior = solve_tridiagonal(&lower, &diag, &upper, &rhs)?;
+    let mut next = Array1::zeros(n);
+    for j in 0..n - 2 {
+        next[j + 1] = interior[j];
+    }
+    next[0] = 2.0 * next[1] - next[2];
+    next[n - 1] = 2.0 * next[n - 2] - next[n - 3];
+    Ok(next)
+}
+
+impl PricerTrait for FiniteDifferenceOptionPricer {
+    fn npv(&self, instrument: &Instrument) -> Result<Real> {
+        Ok(self.npv_result(instrument)?.get_npv())
+    }
+
+    fn npv_result(&self, instrument: &Instrument) -> Result<NpvResult> {
+        Ok(self.backward_induction(instrument, false)?.0)
+    }
+
+    /// delta, gamma and theta on the grid, and vega and rho are left to the bumped greeks
+    fn analytic_greeks(
+        &self,
+        instrument: &Instrument,
+        stickyness: StickynessType,
+    ) -> Result<Option<AnalyticGreeks>> {
+        Ok(self
+            .npv_result_with_analytic_greeks(instrument, stickyness)?
+            .1)
+    }
+
+    /// the greeks of the same backward induction as the npv
+    fn npv_result_with_analytic_greeks(
+        &self,
+        instrument: &Instrument,
+        stickyness: StickynessType,
+    ) -> Result<(NpvResult, Option<AnalyticGreeks>)> {
+        let maturity = instrument
+            .get_maturity()
+            .context("(FiniteDifferenceOptionPricer:analytic_greeks) Failed to get maturity")?;
+        let t = self
+            .time_calculator
+            .get_time_difference(self.evaluation_date.borrow().get_date(), maturity);
+        // expired or expiring today, leave it to the bumped greeks
+        if t <= 0.0 {
+            return Ok((self.npv_result(instrument)?, None));
+        }
+        let (npv_result, greeks) = self.npv_result_with_greeks(instrument, stickyness)?;
+        let greeks = AnalyticGreeks::new_delta_gamma_theta(
+            greeks.get_delta(),
+            greeks.get_gamma(),
+            greeks.get_theta(),
+        );
+        Ok((npv_result, Some(greeks)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::data;
+    use crate::enums::OptionDailySettlementType;
+    use crate::instruments::vanilla_option::VanillaOption;
+    use crate::parameters::discrete_ratio_dividend::DiscreteRatioDividend;
+    use crate::parameters::volatilities::constant_volatility::ConstantVolatility;
+    use crate::pricing_engines::option_analytic_pricer::OptionAnalyticPricer;
+    use crate::vectordatasample;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_finite_difference_option_pricer() -> Result<()> {
+        let eval_date = datetime!(2024-01-02 16:30:00 +09:00);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_date)));
+        let spot = 100.0;
+        let market_price = Rc::new(RefCell::new(MarketPrice::new(
+            spot,
+            eval_date,
+            None,
+            Currency::KRW,
+            "KOSPI2".to_string(),
+            "KOSPI2".to_string(),
+        )));
+        let curve_data = vectordatasample!(0.05, Currency::KRW, "FD Test Curve")?;
+        let curve = Rc::new(RefCell::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &curve_data,
+            "FD Test Curve".to_string(),
+            "FD Test Curve".to_string(),
+        )?));
+        let zero_borrowing_data = vectordatasample!(0.0, Currency::KRW, "Zero Borrowing")?;
+        let borrowing_curve = Rc::new(RefCell::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &zero_borrowing_data,
+            "Zero Borrowing".to_string(),
+            "Zero Borrowing".to_string(),
+        )?));
+        let volatility = Rc::new(RefCell::new(Volatility::ConstantVolatility(
+            ConstantVolatility::new(0.25, "KOSPI2".to_string(), "KOSPI2".to_string()),
+        )));
+
+        let fd = FiniteDifferenceOptionPricer::new(
+            evaluation_date.clone(),
+            market_price.clone(),
+            curve.clone(),
+            borrowing_curve.clone(),
+            curve.clone(),
+            volatility.clone(),
+            None,
+            400,
+            250,
+        );
+        let analytic = OptionAnalyticPricer::new(
+            evaluation_date.clone(),
+            market_price.clone(),
+            curve.clone(),
+            borrowing_curve.clone(),
+            curve.clone(),
+            volatility.clone(),
+            None,
+        );
+
+        let maturity = datetime!(2025-01-02 16:30:00 +09:00);
+        let make_option = |option_type: OptionType, exercise_type: OptionExerciseType| {
+            Instrument::VanillaOption(VanillaOption::new(
+                100.0,
+                1.0,
+                eval_date,
+                maturity,
+                maturity,
+                maturity,
+                vec!["KOSPI2".to_string()],
+                Currency::KRW,
+                Currency::KRW,
+                option_type,
+                exercise_type,
+                OptionDailySettlementType::NotSettled,
+                "KOSPI2 Option".to_string(),
+                "KOSPI2 Option".to_string(),
+            ))
+        };
+
+        // European options are close to the closed form
+        for option_type in [OptionType::Call, OptionType::Put] {
+            let option = make_option(option_type, OptionExerciseType::European);
+            let (res, greeks) =
+                fd.npv_result_with_greeks(&option, StickynessType::StickyToStrike)?;
+            let expected = analytic.npv(&option)?;
+            assert!(
+                (res.get_npv() - expected).abs() < 1.0e-2,
+                "{:?}: fd = {}, analytic = {}",
+                option_type,
+                res.get_npv(),
+                expected
+            );
+            assert!(greeks.get_gamma() > 0.0);
+            assert!(greeks.get_theta() < 0.0);
+
+            // the spot derivatives solved along with the npv, used under StickyToMoneyness with a smile,
+            // are the same as the neighboring nodes on the flat volatility
+            let (_, spot_greeks) = fd.backward_induction(&option, true)?;
+            assert!(
+                (spot_greeks.get_delta() - greeks.get_delta()).abs() < 1.0e-3,
+                "{:?} delta: spot derivative = {}, grid = {}",
+                option_type,
+                spot_greeks.get_delta(),
+                greeks.get_delta()
+            );
+            assert!(
+                (spot_greeks.get_gamma() - greeks.get_gamma()).abs() < 0.02 * greeks.get_gamma(),
+                "{:?} gamma: spot derivative = {}, grid = {}",
+                option_type,
+                spot_greeks.get_gamma(),
+                greeks.get_gamma()
+            );
+
+            // the grid greeks against the closed form, vega and rho are left to the bumped greeks
+            let grid_greeks = fd
+                .analytic_greeks(&option, StickynessType::StickyToMoneyness)?
+                .unwrap();
+            let expected = analytic
+                .analytic_greeks(&option, StickynessType::StickyToMoneyness)?
+                .unwrap();
+            for (name, grid, closed_form, tolerance) in [
+                (
+                    "delta",
+                    grid_greeks.get_delta(),
+                    expected.get_delta(),
+                    1.0e-3,
+                ),
+                (
+                    "gamma",
+                    grid_greeks.get_gamma(),
+                    expected.get_gamma(),
+                    0.02 * expected.get_gamma().abs(),
+                ),
+                (
+                    "theta",
+                    grid_greeks.get_theta(),
+                    expected.get_theta(),
+                    0.02 * expected.get_theta().abs(),
+                ),
+            ] {
+                assert!(
+                    (grid - closed_form).abs() < tolerance,
+                    "{:?} {}: fd = {}, analytic = {}",
+                    option_type,
+                    name,
+                    grid,
+                    closed_form
+                );
+            }
+            assert!(grid_greeks.get_vega().is_none() && grid_greeks.get_rho().is_none());
+        }
+
+        // The American call without dividend is the European call, and the American put has the early exercise premium.
+        let european_call = fd.npv(&make_option(OptionType::Call, OptionExerciseType::European))?;
+        let american_call = fd.npv(&make_option(OptionType::Call, OptionExerciseType::American))?;
+        assert!((american_call - european_call).abs() < 1.0e-3);
+
+        // reference value of the American put by a CRR binomial tree
+        let (steps, r, vol, t) = (2000, 0.05_f64, 0.25_f64, 1.0_f64);
+        let dt = t / steps as f64;
+        let u = (vol * dt.sqrt()).exp();
+        let p = ((r * dt).exp() - 1.0 / u) / (u - 1.0 / u);
+        let disc = (-r * dt).exp();
+        let mut tree: Vec<f64> = (0..=steps)
+            .map(|j| (100.0 - 100.0 * u.powi(2 * j as i32 - steps as i32)).max(0.0))
+            .collect();
+        for step in (0..steps).rev() {
+            for j in 0..=step {
+                let continuation = disc * (p * tree[j + 1] + (1.0 - p) * tree[j]);
+                let exercise = 100.0 - 100.0 * u.powi(2 * j as i32 - step as i32);
+                tree[j] = continuation.max(exercise);
+            }
+        }
+
+        let european_put = fd.npv(&make_option(OptionType::Put, OptionExerciseType::European))?;
+        let american_put_option = make_option(OptionType::Put, OptionExerciseType::American);
+        let (american_put, greeks) =
+            fd.npv_result_with_greeks(&american_put_option, StickynessType::StickyToStrike)?;
+        assert!(american_put.get_npv() > european_put + 0.1);
+        assert!(
+            (american_put.get_npv() as f64 - tree[0]).abs() < 2.0e-2,
+            "american put: fd = {}, binomial = {}",
+            american_put.get_npv(),
+            tree[0]
+        );
+        assert!(greeks.get_delta() < 0.0 && greeks.get_delta() > -1.0);
+        let (_, spot_greeks) = fd.backward_induction(&american_put_option, true)?;
+        assert!(
+            (spot_greeks.get_delta() - greeks.get_delta()).abs() < 1.0e-2,
+            "american put delta: spot derivative = {}, grid = {}",
+            spot_greeks.get_delta(),
+            greeks.get_delta()
+        );
+
+        // Bermudan put exercisable quarterly against the same tree exercised on the nearest steps
+        let exercise_dates = vec![
+            datetime!(2024-04-02 16:30:00 +09:00),
+            datetime!(2024-07-02 16:30:00 +09:00),
+            datetime!(2024-10-02 16:30:00 +09:00),
+        ];
+        let time_calculator = NullCalendar::new();
+        let exercise_steps: Vec<usize> = exercise_dates
+            .iter()
+            .map(|date| {
+                let time = time_calculator.get_time_difference(&eval_date, date) as f64;
+                (time / dt).round() as usize
+            })
+            .collect();
+        let mut tree: Vec<f64> = (0..=steps)
+            .map(|j| (100.0 - 100.0 * u.powi(2 * j as i32 - steps as i32)).max(0.0))
+            .collect();
+        for step in (0..steps).rev() {
+            for j in 0..=step {
+                let continuation = disc * (p * tree[j + 1] + (1.0 - p) * tree[j]);
+                tree[j] = if exercise_steps.contains(&step) {
+                    let exercise = 100.0 - 100.0 * u.powi(2 * j as i32 - step as i32);
+                    continuation.max(exercise)
+                } else {
+                    continuation
+                };
+            }
+        }
+        let bermudan_put_option = match make_option(OptionType::Put, OptionExerciseType::Bermudan) {
+            Instrument::VanillaOption(option) => {
+                Instrument::VanillaOption(option.with_exercise_dates(exercise_dates))
+            }
+            _ => unreachable!(),
+        };
+        let bermudan_put = fd.npv(&bermudan_put_option)?;
+        assert!(bermudan_put > european_put && bermudan_put < american_put.get_npv());
+        assert!(
+            (bermudan_put as f64 - tree[0]).abs() < 2.0e-2,
+            "bermudan put: fd = {}, binomial = {}",
+            bermudan_put,
+            tree[0]
+        );
+        // discrete ratio dividend: the European option agrees with the closed form on the dividend-adjusted forward,
+        // and the American call is exercised right before the ex-dividend date
+        let dividend_data = data::vector_data::VectorData::new(
+            ndarray::array![5.0],
+            Some(vec![datetime!(2024-06-14 00:00:00 +09:00)]),
+            None,
+            Some(eval_date),
+            Currency::KRW,
+            "KOSPI2 Dividend".to_string(),
+            "KOSPI2 Dividend".to_string(),
+        )?;
+        let dividend = DiscreteRatioDividend::new(
+            evaluation_date.clone(),
+            &dividend_data,
+            spot,
+            "KOSPI2".to_string(),
+            "KOSPI2".to_string(),
+        )?;
+        let market_price = Rc::new(RefCell::new(MarketPrice::new(
+            spot,
+            eval_date,
+            Some(Rc::new(RefCell::new(dividend))),
+            Currency::KRW,
+            "KOSPI2".to_string(),
+            "KOSPI2".to_string(),
+        )));
+        let fd = FiniteDifferenceOptionPricer::new(
+            evaluation_date.clone(),
+            market_price.clone(),
+            curve.clone(),
+            borrowing_curve.clone(),
+            curve.clone(),
+            volatility.clone(),
+            None,
+            400,
+            250,
+        );
+        let analytic = OptionAnalyticPricer::new(
+            evaluation_date.clone(),
+            market_price.clone(),
+            curve.clone(),
+            borrowing_curve.clone(),
+            curve.clone(),
+            volatility.clone(),
+            None,
+        );
+
+        let european_call_option = make_option(OptionType::Call, OptionExerciseType::European);
+        let european_call = fd.npv(&european_call_option)?;
+        let expected = analytic.npv(&european_call_option)?;
+        assert!(
+            (european_call - expected).abs() < 1.0e-2,
+            "dividend call: fd = {}, analytic = {}",
+            european_call,
+            expected
+        );
+        let american_call = fd.npv(&make_option(OptionType::Call, OptionExerciseType::American))?;
+        assert!(american_call > european_call);
+        Ok(())
+    }
+}
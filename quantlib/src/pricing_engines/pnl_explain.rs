@@ -0,0 +1,470 @@
+use crate::currency::{Currency, FxCode};
+use crate::data::{
+    fx_volatility_quote_data::FxVolatilityQuoteData, surface_data::SurfaceData,
+    value_data::ValueData, vector_data::VectorData,
+};
+use crate::definitions::{Real, DELTA_PNL_UNIT, DIV_PNL_UNIT, RHO_PNL_UNIT, VEGA_PNL_UNIT};
+use crate::instrument::{Instrument, InstrumentTrait};
+use crate::math::interpolator::{ExtraPolationType, InterpolatorReal1D};
+use crate::math::interpolators::linear_interpolator::LinearInterpolator1D;
+use crate::pricing_engines::calculation_result::CalculationResult;
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use time::OffsetDateTime;
+
+/// P&L of an instrument between two evaluation dates split into the market moves,
+/// in the instrument currency considering unit_notional.
+/// total = end_value - start_value + cashflow, where cashflow is the expected cashflows paid in between.
+/// carry: the value change by the time passing, excluding the cashflows.
+/// spot: the stock prices, vega: the equity and fx volatilities, rho: the curves keyed by the curve name,
+/// dividend: the dividends, fx: the fx rates on the instrument currency (e.g., fx forwards and options).
+/// residual: the total which is not explained by the above.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PnlExplain {
+    currency: Currency,
+    start_value: Real,
+    end_value: Real,
+    cashflow: Real,
+    carry: Real,
+    spot: Real,
+    vega: Real,
+    rho: HashMap<String, Real>,
+    dividend: Real,
+    fx: Real,
+    residual: Real,
+}
+
+impl PnlExplain {
+    /// the residual is the total minus the other components
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        currency: Currency,
+        start_value: Real,
+        end_value: Real,
+        cashflow: Real,
+        carry: Real,
+        spot: Real,
+        vega: Real,
+        rho: HashMap<String, Real>,
+        dividend: Real,
+        fx: Real,
+    ) -> PnlExplain {
+        let explained = carry + spot + vega + rho.values().sum::<Real>() + dividend + fx + cashflow;
+        PnlExplain {
+            currency,
+            start_value,
+            end_value,
+            cashflow,
+            carry,
+            spot,
+            vega,
+            rho,
+            dividend,
+            fx,
+            residual: end_value - start_value + cashflow - explained,
+        }
+    }
+
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn get_start_value(&self) -> Real {
+        self.start_value
+    }
+
+    pub fn get_end_value(&self) -> Real {
+        self.end_value
+    }
+
+    pub fn get_total(&self) -> Real {
+        self.end_value - self.start_value + self.cashflow
+    }
+
+    pub fn get_cashflow(&self) -> Real {
+        self.cashflow
+    }
+
+    pub fn get_carry(&self) -> Real {
+        self.carry
+    }
+
+    pub fn get_spot(&self) -> Real {
+        self.spot
+    }
+
+    pub fn get_vega(&self) -> Real {
+        self.vega
+    }
+
+    /// curve name -> P&L
+    pub fn get_rho(&self) -> &HashMap<String, Real> {
+        &self.rho
+    }
+
+    pub fn get_dividend(&self) -> Real {
+        self.dividend
+    }
+
+    pub fn get_fx(&self) -> Real {
+        self.fx
+    }
+
+    pub fn get_residual(&self) -> Real {
+        self.residual
+    }
+}
+
+/// market data of a run keyed as in EngineGenerator
+pub(crate) struct PnlMarketData<'a> {
+    pub evaluation_date: OffsetDateTime,
+    pub fx_data: &'a HashMap<FxCode, ValueData>,
+    pub stock_data: &'a HashMap<String, ValueData>,
+    pub curve_data: &'a HashMap<String, VectorData>,
+    pub dividend_data: &'a HashMap<String, VectorData>,
+    pub equity_constant_volatility_data: &'a HashMap<String, ValueData>,
+    pub equity_volatility_surface_data: &'a HashMap<String, SurfaceData>,
+    pub fx_constant_volatility_data: &'a HashMap<FxCode, ValueData>,
+    pub fx_volatility_quote_data: &'a HashMap<FxCode, FxVolatilityQuoteData>,
+}
+
+/// moves of the market data from the start run to the end run for the risk-based explain
+pub(crate) struct MarketMove<'a> {
+    start: PnlMarketData<'a>,
+    end: PnlMarketData<'a>,
+}
+
+impl<'a> MarketMove<'a> {
+    pub fn new(start: PnlMarketData<'a>, end: PnlMarketData<'a>) -> MarketMove<'a> {
+        MarketMove { start, end }
+    }
+
+    fn relative_return(start: &ValueData, end: &ValueData) -> Option<Real> {
+        if start.get_value() == 0.0 {
+            return None;
+        }
+        Some(end.get_value() / start.get_value() - 1.0)
+    }
+
+    fn stock_return(&self, code: &str) -> Option<Real> {
+        let start = self.start.stock_data.get(code)?;
+        let end = self.end.stock_data.get(code)?;
+        Self::relative_return(start, end)
+    }
+
+    /// relative return of the fx rate keyed as the delta, e.g., "USDKRW"
+    fn fx_return(&self, code: &str) -> Option<Real> {
+        let (fx_code, start) = self
+            .start
+            .fx_data
+            .iter()
+            .find(|(fx_code, _)| fx_code.to_string() == code)?;
+        let end = self.end.fx_data.get(fx_code)?;
+        Self::relative_return(start, end)
+    }
+
+    /// change of the equity or fx volatility keyed as the vega.
+    /// The surfaces move by the change of the average on the grid,
+    /// and the fx volatility quotes by the change of the average ATM.
+    fn volatility_shift(&self, code: &str) -> Option<Real> {
+        if let (Some(start), Some(end)) = (
+            self.start.equity_constant_volatility_data.get(code),
+            self.end.equity_constant_volatility_data.get(code),
+        ) {
+            return Some(end.get_value() - start.get_value());
+        }
+        if let (Some(start), Some(end)) = (
+            self.start.equity_volatility_surface_data.get(code),
+            self.end.equity_volatility_surface_data.get(code),
+        ) {
+            return Some(end.get_value().mean()? - start.get_value().mean()?);
+        }
+        if let Some((fx_code, start)) = self
+            .start
+            .fx_volatility_quote_data
+            .iter()
+            .find(|(fx_code, _)| fx_code.to_string() == code)
+        {
+            if let Some(end) = self.end.fx_volatility_quote_data.get(fx_code) {
+                return Some(end.get_atm().mean()? - start.get_atm().mean()?);
+            }
+        }
+        let (fx_code, start) = self
+            .start
+            .fx_constant_volatility_data
+            .iter()
+            .find(|(fx_code, _)| fx_code.to_string() == code)?;
+        let end = self.end.fx_constant_volatility_data.get(fx_code)?;
+        Some(end.get_value() - start.get_value())
+    }
+
+    /// parallel move of the curve, i.e., the average change on the times of the start curve
+    fn curve_shift(&self, name: &str) -> Result<Option<Real>> {
+        let (Some(start), Some(end)) = (
+            self.start.curve_data.get(name),
+            self.end.curve_data.get(name),
+        ) else {
+            return Ok(None);
+        };
+        let times = start.get_times_clone();
+        let end_curve = LinearInterpolator1D::new(
+            end.get_times_clone(),
+            end.get_value_clone(),
+            ExtraPolationType::Flat,
+            true,
+        )?;
+        let mut shift = start.get_value_clone();
+        for (i, t) in times.iter().enumerate() {
+            shift[i] = end_curve.interpolate(*t)? - shift[i];
+        }
+        Ok(shift.mean())
+    }
+
+    /// change of the dividend yields (amount / spot) averaged on the ex-dividend dates
+    /// after the end date which are in both the start and the end data
+    fn dividend_shift(&self, code: &str) -> Option<Real> {
+        let start = self.start.dividend_data.get(code)?;
+        let end = self.end.dividend_data.get(code)?;
+        let start_spot = self.start.stock_data.get(code)?.get_value();
+        let end_spot = self.end.stock_data.get(code)?.get_value();
+        if start_spot == 0.0 || end_spot == 0.0 {
+            return None;
+        }
+        let start_dates = start.get_dates_clone()?;
+        let end_dates = end.get_dates_clone()?;
+        let start_amounts = start.get_value_clone();
+        let end_amounts = end.get_value_clone();
+        let shifts: Vec<Real> = start_dates
+            .iter()
+            .enumerate()
+            .filter(|(_, date)| date.date() > self.end.evaluation_date.date())
+            .filter_map(|(i, date)| {
+                let j = end_dates.iter().position(|d| d.date() == date.date())?;
+                Some(end_amounts[j] / end_spot - start_amounts[i] / start_spot)
+            })
+            .collect();
+        if shifts.is_empty() {
+            return None;
+        }
+        Some(shifts.iter().sum::<Real>() / shifts.len() as Real)
+    }
+
+    /// P&L explain by the Greeks of the start result,
+    /// where the end value and the cashflow are in the instrument currency considering unit_notional
+    pub fn explain(
+        &self,
+        instrument: &Instrument,
+        result: &CalculationResult,
+        start_value: Real,
+        end_value: Real,
+        cashflow: Real,
+    ) -> Result<PnlExplain> {
+        let elapsed_days = NullCalendar::new()
+            .get_time_difference(&self.start.evaluation_date, &self.end.evaluation_date)
+            * 365.0;
+        // the theta includes the cashflows paid in the theta days
+        let carry = result
+            .get_theta()
+            .map(|theta| theta * elapsed_days - cashflow)
+            .unwrap_or(0.0);
+
+        let mut spot = 0.0;
+        let mut fx = 0.0;
+        if let Some(delta) = result.get_delta() {
+            let gamma = result.get_gamma();
+            for (code, delta) in delta {
+                let gamma = gamma.and_then(|g| g.get(code)).copied().unwrap_or(0.0);
+                let pnl = |r: Real| {
+                    let m = r / DELTA_PNL_UNIT;
+                    delta * m + gamma * m * m
+                };
+                if let Some(r) = self.fx_return(code) {
+                    fx += pnl(r);
+                    continue;
+                }
+                // the delta of stocks and futures is keyed by the instrument code
+                let r = self.stock_return(code).or_else(|| {
+                    instrument
+                        .get_underlying_codes()
+                        .into_iter()
+                        .find_map(|und| self.stock_return(und))
+                });
+                if let Some(r) = r {
+                    spot += pnl(r);
+                }
+            }
+        }
+
+        let mut vega = 0.0;
+        if let Some(vegas) = result.get_vega() {
+            for (code, v) in vegas {
+                if let Some(shift) = self.volatility_shift(code) {
+                    vega += v * shift / VEGA_PNL_UNIT;
+                }
+            }
+        }
+
+        let mut rho = HashMap::new();
+        if let Some(rhos) = result.get_rho() {
+            for (name, r) in rhos {
+                if let Some(shift) = self.curve_shift(name)? {
+                    rho.insert(name.clone(), r * shift / RHO_PNL_UNIT);
+                }
+            }
+        }
+
+        let mut dividend = 0.0;
+        if let Some(div_delta) = result.get_div_delta() {
+            for (code, d) in div_delta {
+                if let Some(shift) = self.dividend_shift(code) {
+                    dividend += d * shift / DIV_PNL_UNIT;
+                }
+            }
+        }
+
+        Ok(PnlExplain::new(
+            *instrument.get_currency(),
+            start_value,
+            end_value,
+            cashflow,
+            carry,
+            spot,
+            vega,
+            rho,
+            dividend,
+            fx,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::{FxAtmType, FxDeltaType};
+    use crate::instruments::stock::Stock;
+    use ndarray::array;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_risk_based_explain() -> Result<()> {
+        let start_date = datetime!(2023-01-02 16:00:00 +09:00);
+        let end_date = datetime!(2023-01-03 16:00:00 +09:00);
+        let value = |v: Real, code: &str| {
+            ValueData::new(v, None, Currency::KRW, code.to_string(), code.to_string())
+        };
+        let curve = |rate: Real| {
+            VectorData::new(
+                array![rate, rate + 0.01],
+                None,
+                Some(array![1.0, 5.0]),
+                None,
+                Currency::KRW,
+                "KRWCRS".to_string(),
+                "KRWCRS".to_string(),
+            )
+        };
+        let usdkrw = FxCode::new(Currency::USD, Currency::KRW);
+        let data = |spot: Real, fx: Real, vol: Real, rate: Real| -> Result<_> {
+            Ok((
+                HashMap::from([(usdkrw, value(fx, "USDKRW")?)]),
+                HashMap::from([("005930".to_string(), value(spot, "005930")?)]),
+                HashMap::from([("KRWCRS".to_string(), curve(rate)?)]),
+                HashMap::from([("005930".to_string(), value(vol, "005930")?)]),
+            ))
+        };
+        let (start_fx, start_stock, start_curve, start_vol) = data(70_000.0, 1300.0, 0.2, 0.03)?;
+        let (end_fx, end_stock, end_curve, end_vol) = data(71_400.0, 1313.0, 0.21, 0.031)?;
+        let empty_vector = HashMap::new();
+        let empty_surface = HashMap::new();
+        let empty_fx = HashMap::new();
+        let quotes = |atm: Real| {
+            HashMap::from([(
+                usdkrw,
+                FxVolatilityQuoteData::new(
+                    vec![start_date, end_date],
+                    array![atm, atm + 0.01],
+                    array![0.01, 0.015],
+                    array![0.003, 0.004],
+                    FxDeltaType::SpotPremiumAdjusted,
+                    FxAtmType::DeltaNeutralStraddle,
+                    None,
+                    usdkrw,
+                    "USDKRW".to_string(),
+                    "USDKRW".to_string(),
+                ),
+            )])
+        };
+        let (start_quotes, end_quotes) = (quotes(0.08), quotes(0.085));
+        let market_data = |date, fx, stock, curve, vol, quotes| PnlMarketData {
+            evaluation_date: date,
+            fx_data: fx,
+            stock_data: stock,
+            curve_data: curve,
+            dividend_data: &empty_vector,
+            equity_constant_volatility_data: vol,
+            equity_volatility_surface_data: &empty_surface,
+            fx_constant_volatility_data: &empty_fx,
+            fx_volatility_quote_data: quotes,
+        };
+        let market_move = MarketMove::new(
+            market_data(
+                start_date,
+                &start_fx,
+                &start_stock,
+                &start_curve,
+                &start_vol,
+                &start_quotes,
+            ),
+            market_data(
+                end_date,
+                &end_fx,
+                &end_stock,
+                &end_curve,
+                &end_vol,
+                &end_quotes,
+            ),
+        );
+        assert!((market_move.stock_return("005930").unwrap() - 0.02).abs() < 1.0e-6);
+        assert!((market_move.fx_return("USDKRW").unwrap() - 0.01).abs() < 1.0e-6);
+        assert!((market_move.volatility_shift("005930").unwrap() - 0.01).abs() < 1.0e-6);
+        assert!((market_move.volatility_shift("USDKRW").unwrap() - 0.005).abs() < 1.0e-6);
+        assert!((market_move.curve_shift("KRWCRS")?.unwrap() - 0.001).abs() < 1.0e-6);
+        assert_eq!(market_move.curve_shift("USDOIS")?, None);
+
+        // an option-like result on the start date keyed as the engine does
+        let instrument = Instrument::Stock(Stock::new(
+            "Samsung Electronics".to_string(),
+            "005930".to_string(),
+            vec!["005930".to_string()],
+            Currency::KRW,
+            None,
+        ));
+        let mut result = CalculationResult::default();
+        result.set_single_delta("005930", 100.0);
+        result.set_single_delta("USDKRW", -50.0);
+        result.set_single_gamma("005930", 10.0);
+        result.set_single_vega("005930", 30.0);
+        result.set_single_rho("KRWCRS", -2.0);
+        result.set_theta(-5.0);
+
+        let explain = market_move.explain(&instrument, &result, 1_000.0, 1_300.0, 20.0)?;
+        assert_eq!(explain.get_total(), 320.0);
+        assert!((explain.get_carry() + 25.0).abs() < 1.0e-3);
+        assert!((explain.get_spot() - 240.0).abs() < 1.0e-2);
+        assert!((explain.get_fx() + 50.0).abs() < 1.0e-2);
+        assert!((explain.get_vega() - 30.0).abs() < 1.0e-2);
+        assert!((explain.get_rho()["KRWCRS"] + 20.0).abs() < 1.0e-2);
+        let explained = explain.get_carry()
+            + explain.get_spot()
+            + explain.get_vega()
+            + explain.get_rho().values().sum::<Real>()
+            + explain.get_dividend()
+            + explain.get_fx()
+            + explain.get_cashflow();
+        assert!((explained + explain.get_residual() - explain.get_total()).abs() < 1.0e-3);
+        Ok(())
+    }
+}
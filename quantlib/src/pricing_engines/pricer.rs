@@ -1,11 +1,22 @@
 use crate::currency::Currency;
 use crate::definitions::Real;
+use crate::enums::StickynessType;
 use crate::instrument::{Instrument, InstrumentTrait};
-use crate::pricing_engines::npv_result::NpvResult;
+use crate::pricing_engines::{analytic_greeks::AnalyticGreeks, npv_result::NpvResult};
 use crate::pricing_engines::{
-    bond_pricer::BondPricer, futures_pricer::FuturesPricer, fx_futures_pricer::FxFuturesPricer,
-    identity_pricer::IdentityPricer, krx_yield_pricer::KrxYieldPricer, ktbf_pricer::KtbfPricer,
-    option_analytic_pricer::OptionAnalyticPricer, plain_swap_pricer::PlainSwapPricer,
+    asian_option_analytic_pricer::AsianOptionAnalyticPricer,
+    barrier_option_analytic_pricer::BarrierOptionAnalyticPricer,
+    bond_futures_pricer::BondFuturesPricer, bond_pricer::BondPricer,
+    credit_default_swap_pricer::CreditDefaultSwapPricer, futures_pricer::FuturesPricer,
+    fx_forward_pricer::FxForwardPricer, fx_futures_pricer::FxFuturesPricer,
+    fx_option_pricer::FxOptionPricer, identity_pricer::IdentityPricer,
+    krx_yield_pricer::KrxYieldPricer, ktbf_pricer::KtbfPricer,
+    lookback_option_analytic_pricer::LookbackOptionAnalyticPricer,
+    montecarlo::autocallable_montecarlo_pricer::AutocallableMonteCarloPricer,
+    montecarlo::option_montecarlo_pricer::MonteCarloOptionPricer,
+    montecarlo::path_dependent_montecarlo_pricer::PathDependentMonteCarloPricer,
+    option_analytic_pricer::OptionAnalyticPricer, option_fd_pricer::FiniteDifferenceOptionPricer,
+    plain_swap_pricer::PlainSwapPricer, rate_option_pricer::RateOptionPricer,
     unit_pricer::UnitPricer,
 };
 //
@@ -28,17 +39,50 @@ pub trait PricerTrait {
         );
         Ok(map)
     }
+    /// Greeks in closed form if the pricer has them, otherwise None and the engine bumps and reprices.
+    /// The smile effect follows stickyness in the same way as the bumped greeks
+    fn analytic_greeks(
+        &self,
+        _instrument: &Instrument,
+        _stickyness: StickynessType,
+    ) -> Result<Option<AnalyticGreeks>> {
+        Ok(None)
+    }
+    /// npv_result and analytic_greeks on the same market.
+    /// The pricers which get the greeks from the npv calculation, e.g., on the finite difference grid, give both at once
+    fn npv_result_with_analytic_greeks(
+        &self,
+        instrument: &Instrument,
+        stickyness: StickynessType,
+    ) -> Result<(NpvResult, Option<AnalyticGreeks>)> {
+        Ok((
+            self.npv_result(instrument)?,
+            self.analytic_greeks(instrument, stickyness)?,
+        ))
+    }
 }
 
 #[enum_dispatch(PricerTrait)]
 pub enum Pricer {
     FuturesPricer(FuturesPricer),
     OptionAnalyticPricer(OptionAnalyticPricer),
+    BarrierOptionAnalyticPricer(BarrierOptionAnalyticPricer),
+    AsianOptionAnalyticPricer(AsianOptionAnalyticPricer),
+    LookbackOptionAnalyticPricer(LookbackOptionAnalyticPricer),
+    MonteCarloOptionPricer(MonteCarloOptionPricer),
+    AutocallableMonteCarloPricer(AutocallableMonteCarloPricer),
+    PathDependentMonteCarloPricer(PathDependentMonteCarloPricer),
+    FiniteDifferenceOptionPricer(FiniteDifferenceOptionPricer),
     BondPricer(BondPricer),
+    BondFuturesPricer(BondFuturesPricer),
     KtbfPricer(KtbfPricer),
     KrxYieldPricer(KrxYieldPricer),
     PlainSwapPricer(PlainSwapPricer),
+    CreditDefaultSwapPricer(CreditDefaultSwapPricer),
+    RateOptionPricer(RateOptionPricer),
     FxFuturesPricer(FxFuturesPricer),
+    FxForwardPricer(FxForwardPricer),
+    FxOptionPricer(FxOptionPricer),
     IdentityPricer(IdentityPricer),
     UnitPricer(UnitPricer),
 }
@@ -1,22 +1,35 @@
 use crate::currency::FxCode;
-use crate::enums::VanillaOptionCalculationMethod;
+use crate::data::value_data::ValueData;
+use crate::definitions::Real;
+use crate::enums::{OptionExerciseType, VanillaOptionCalculationMethod};
 use crate::evaluation_date::EvaluationDate;
 use crate::instrument::{Instrument, InstrumentTrait};
 use crate::parameters::{market_price::MarketPrice, past_price::DailyClosePrice};
 use crate::parameters::{
-    quanto::Quanto, rate_index::RateIndex, volatility::Volatility, zero_curve::ZeroCurve,
+    quanto::Quanto, rate_index::RateIndex, rate_volatility::RateVolatility, volatility::Volatility,
+    zero_curve::ZeroCurve,
 };
 use crate::pricing_engines::calculation_configuration::CalculationConfiguration;
 use crate::pricing_engines::{
-    bond_pricer::BondPricer, futures_pricer::FuturesPricer, fx_futures_pricer::FxFuturesPricer,
-    identity_pricer::IdentityPricer, ktbf_pricer::KtbfPricer, match_parameter::MatchParameter,
-    option_analytic_pricer::OptionAnalyticPricer, plain_swap_pricer::PlainSwapPricer,
-    pricer::Pricer, unit_pricer::UnitPricer,
+    asian_option_analytic_pricer::AsianOptionAnalyticPricer,
+    barrier_option_analytic_pricer::BarrierOptionAnalyticPricer,
+    bond_futures_pricer::BondFuturesPricer, bond_pricer::BondPricer,
+    credit_default_swap_pricer::CreditDefaultSwapPricer, futures_pricer::FuturesPricer,
+    fx_forward_pricer::FxForwardPricer, fx_futures_pricer::FxFuturesPricer,
+    fx_option_pricer::FxOptionPricer, identity_pricer::IdentityPricer, ktbf_pricer::KtbfPricer,
+    lookback_option_analytic_pricer::LookbackOptionAnalyticPricer, match_parameter::MatchParameter,
+    montecarlo::autocallable_montecarlo_pricer::AutocallableMonteCarloPricer,
+    montecarlo::option_montecarlo_pricer::MonteCarloOptionPricer,
+    montecarlo::path_dependent_montecarlo_pricer::PathDependentMonteCarloPricer,
+    option_analytic_pricer::OptionAnalyticPricer, option_fd_pricer::FiniteDifferenceOptionPricer,
+    plain_swap_pricer::PlainSwapPricer, pricer::Pricer, rate_option_pricer::RateOptionPricer,
+    unit_pricer::UnitPricer,
 };
 //
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::Arc};
 
 use anyhow::{anyhow, Result};
+use ndarray::Array2;
 
 /// dividend is not needed for this pricer factory
 /// dividend is in herent in equities
@@ -28,6 +41,9 @@ pub struct PricerFactory {
     underlying_volatilities: HashMap<String, Rc<RefCell<Volatility>>>,
     quantos: HashMap<(String, FxCode), Rc<RefCell<Quanto>>>, // (underlying_code, fx_code) -> Quanto
     past_close_data: HashMap<String, Rc<DailyClosePrice>>,
+    equity_correlation_data: Arc<HashMap<(String, String), ValueData>>,
+    rate_volatilities: HashMap<String, Rc<RefCell<RateVolatility>>>, // rate index code -> RateVolatility
+    fx_volatilities: HashMap<FxCode, Rc<RefCell<Volatility>>>,
     match_parameter: Rc<MatchParameter>,
     calculation_configuration: Rc<CalculationConfiguration>,
 }
@@ -53,32 +69,64 @@ impl PricerFactory {
             underlying_volatilities,
             quantos,
             past_close_data,
+            equity_correlation_data: Arc::new(HashMap::new()),
+            rate_volatilities: HashMap::new(),
+            fx_volatilities: HashMap::new(),
             match_parameter,
             calculation_configuration,
         }
     }
 
+    /// correlations between the underlyings of multi-asset instruments.
+    /// The key is a pair of the underlying codes in any order
+    pub fn with_equity_correlation_data(
+        mut self,
+        equity_correlation_data: Arc<HashMap<(String, String), ValueData>>,
+    ) -> PricerFactory {
+        self.equity_correlation_data = equity_correlation_data;
+        self
+    }
+
+    /// volatilities of caps, floors and swaptions keyed by the rate index code
+    pub fn with_rate_volatilities(
+        mut self,
+        rate_volatilities: HashMap<String, Rc<RefCell<RateVolatility>>>,
+    ) -> PricerFactory {
+        self.rate_volatilities = rate_volatilities;
+        self
+    }
+
+    /// volatilities of FX options keyed by the fx code
+    pub fn with_fx_volatilities(
+        mut self,
+        fx_volatilities: HashMap<FxCode, Rc<RefCell<Volatility>>>,
+    ) -> PricerFactory {
+        self.fx_volatilities = fx_volatilities;
+        self
+    }
+
     pub fn create_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
         let pricer = match Rc::as_ref(instrument) {
             Instrument::Futures(_) => self.get_futures_pricer(instrument)?,
             Instrument::VanillaOption(_) => self.get_vanilla_option_pricer(instrument)?,
+            Instrument::BarrierOption(_) => self.get_barrier_option_pricer(instrument)?,
+            Instrument::AsianOption(_) | Instrument::LookbackOption(_) => {
+                self.get_path_dependent_option_pricer(instrument)?
+            }
+            Instrument::Autocallable(_) => self.get_autocallable_pricer(instrument)?,
             Instrument::Bond(_) => self.get_bond_pricer(instrument)?,
+            Instrument::BondFutures(_) => self.get_bond_futures_pricer(instrument)?,
             Instrument::KTBF(_) => self.get_ktbf_pricer(instrument)?,
             Instrument::FxFutures(_) => self.get_fx_futures_pricer(instrument)?,
+            Instrument::FxForward(_) => self.get_fx_forward_pricer(instrument)?,
+            Instrument::FxOption(_) => self.get_fx_option_pricer(instrument)?,
             Instrument::PlainSwap(_) => self.get_plain_swap_pricer(instrument)?,
+            Instrument::CreditDefaultSwap(_) => self.get_credit_default_swap_pricer(instrument)?,
+            Instrument::CapFloor(_) | Instrument::Swaption(_) => {
+                self.get_rate_option_pricer(instrument)?
+            }
             Instrument::Stock(_) => self.get_stock_pricer(instrument)?,
             Instrument::Cash(_) => self.get_cash_pricer(instrument)?,
-            //
-            //
-            _ => {
-                return Err(anyhow!(
-                    "({}:{})   pricer for {} ({}) is not implemented yet",
-                    file!(),
-                    line!(),
-                    instrument.get_code(),
-                    instrument.get_type_name(),
-                ));
-            }
         };
         Ok(pricer)
     }
@@ -180,7 +228,20 @@ impl PricerFactory {
         Ok(Pricer::FuturesPricer(core))
     }
 
-    fn get_vanilla_option_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
+    /// market data of an option on a single underlying:
+    /// (equity, collateral curve, borrowing curve, discount curve, volatility, quanto)
+    #[allow(clippy::type_complexity)]
+    fn get_option_market_data(
+        &self,
+        instrument: &Rc<Instrument>,
+    ) -> Result<(
+        Rc<RefCell<MarketPrice>>,
+        Rc<RefCell<ZeroCurve>>,
+        Rc<RefCell<ZeroCurve>>,
+        Rc<RefCell<ZeroCurve>>,
+        Rc<RefCell<Volatility>>,
+        Option<Rc<RefCell<Quanto>>>,
+    )> {
         let equity = self
             .equities
             .get(instrument.get_underlying_codes()[0])
@@ -257,11 +318,87 @@ impl PricerFactory {
             }
             true => None,
         };
+        Ok((
+            equity,
+            collatral_curve,
+            borrowing_curve,
+            discount_curve,
+            volatility,
+            quanto,
+        ))
+    }
+
+    fn get_vanilla_option_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
+        let (equity, collatral_curve, borrowing_curve, discount_curve, volatility, quanto) =
+            self.get_option_market_data(instrument)?;
         let core = match self
             .calculation_configuration
             .get_vanilla_option_calculation_method()
         {
-            VanillaOptionCalculationMethod::Analytic => OptionAnalyticPricer::new(
+            VanillaOptionCalculationMethod::Analytic => {
+                // the closed form is European, the early exercise is priced by FiniteDifference
+                let exercise_type = instrument.get_option_exercise_type()?;
+                if exercise_type != OptionExerciseType::European {
+                    return Err(anyhow!(
+                        "({}:{}) {} ({}) has {:?} exercise which is not supported by OptionAnalyticPricer. \
+                        Set vanilla_option_calculation_method to FiniteDifference",
+                        file!(),
+                        line!(),
+                        instrument.get_name(),
+                        instrument.get_code(),
+                        exercise_type,
+                    ));
+                }
+                Pricer::OptionAnalyticPricer(OptionAnalyticPricer::new(
+                    self.evaluation_date.clone(),
+                    equity,
+                    collatral_curve,
+                    borrowing_curve,
+                    discount_curve,
+                    volatility,
+                    quanto,
+                ))
+            }
+            VanillaOptionCalculationMethod::MonteCarlo => {
+                Pricer::MonteCarloOptionPricer(MonteCarloOptionPricer::new(
+                    self.evaluation_date.clone(),
+                    equity,
+                    collatral_curve,
+                    borrowing_curve,
+                    discount_curve,
+                    volatility,
+                    quanto,
+                    self.calculation_configuration.get_montecarlo_path_number(),
+                    self.calculation_configuration
+                        .get_montecarlo_time_steps_per_year(),
+                    self.calculation_configuration.get_montecarlo_seed(),
+                ))
+            }
+            VanillaOptionCalculationMethod::FiniteDifference => {
+                Pricer::FiniteDifferenceOptionPricer(FiniteDifferenceOptionPricer::new(
+                    self.evaluation_date.clone(),
+                    equity,
+                    collatral_curve,
+                    borrowing_curve,
+                    discount_curve,
+                    volatility,
+                    quanto,
+                    self.calculation_configuration
+                        .get_finite_difference_space_grid_number(),
+                    self.calculation_configuration
+                        .get_finite_difference_time_steps_per_year(),
+                ))
+            }
+        };
+        Ok(core)
+    }
+
+    /// barrier options are priced in closed form regardless of vanilla_option_calculation_method
+    fn get_barrier_option_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
+        let (equity, collatral_curve, borrowing_curve, discount_curve, volatility, quanto) =
+            self.get_option_market_data(instrument)?;
+        Ok(Pricer::BarrierOptionAnalyticPricer(
+            BarrierOptionAnalyticPricer::new(
                 self.evaluation_date.clone(),
                 equity,
                 collatral_curve,
@@ -270,16 +407,227 @@ impl PricerFactory {
                 volatility,
                 quanto,
             ),
-            _ => return Err(anyhow::Error::msg("Unsupported calculation method")),
+        ))
+    }
+
+    /// Asian and lookback options are priced in closed form unless
+    /// vanilla_option_calculation_method is MonteCarlo.
+    /// The past closes of the underlying give the fixings before the evaluation date
+    fn get_path_dependent_option_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
+        let (equity, collatral_curve, borrowing_curve, discount_curve, volatility, quanto) =
+            self.get_option_market_data(instrument)?;
+        let past_close_data = self
+            .past_close_data
+            .get(instrument.get_underlying_codes()[0])
+            .cloned();
+        let method = self
+            .calculation_configuration
+            .get_vanilla_option_calculation_method();
+        let pricer = match (Rc::as_ref(instrument), method) {
+            (_, VanillaOptionCalculationMethod::MonteCarlo) => {
+                Pricer::PathDependentMonteCarloPricer(PathDependentMonteCarloPricer::new(
+                    self.evaluation_date.clone(),
+                    equity,
+                    collatral_curve,
+                    borrowing_curve,
+                    discount_curve,
+                    volatility,
+                    quanto,
+                    past_close_data,
+                    self.calculation_configuration.get_montecarlo_path_number(),
+                    self.calculation_configuration
+                        .get_montecarlo_time_steps_per_year(),
+                    self.calculation_configuration.get_montecarlo_seed(),
+                ))
+            }
+            (Instrument::AsianOption(_), _) => {
+                Pricer::AsianOptionAnalyticPricer(AsianOptionAnalyticPricer::new(
+                    self.evaluation_date.clone(),
+                    equity,
+                    collatral_curve,
+                    borrowing_curve,
+                    discount_curve,
+                    volatility,
+                    quanto,
+                    past_close_data,
+                ))
+            }
+            _ => Pricer::LookbackOptionAnalyticPricer(LookbackOptionAnalyticPricer::new(
+                self.evaluation_date.clone(),
+                equity,
+                collatral_curve,
+                borrowing_curve,
+                discount_curve,
+                volatility,
+                quanto,
+                past_close_data,
+            )),
         };
-        Ok(Pricer::OptionAnalyticPricer(core))
+        Ok(pricer)
+    }
+
+    fn get_autocallable_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
+        let und_codes = instrument.get_underlying_codes();
+        let discount_curve_name = self.match_parameter.get_discount_curve_name(instrument)?;
+        let discount_curve = self
+            .zero_curves
+            .get(discount_curve_name)
+            .ok_or_else(|| {
+                anyhow!(
+                "({}:{}) failed to get discount curve of {}.\nself.zero_curves does not have {}",
+                file!(), line!(), instrument.get_code(), discount_curve_name,
+            )
+            })?
+            .clone();
+        let get_curve = |curve_name: &String, curve_type: &str| {
+            self.zero_curves.get(curve_name).cloned().ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) failed to get {} curve of {}.\nself.zero_curves does not have {}",
+                    file!(),
+                    line!(),
+                    curve_type,
+                    instrument.get_code(),
+                    curve_name,
+                )
+            })
+        };
+        let collateral_curves = self
+            .match_parameter
+            .get_collateral_curve_names(instrument)?
+            .into_iter()
+            .map(|name| get_curve(name, "collateral"))
+            .collect::<Result<Vec<_>>>()?;
+        let borrowing_curves = self
+            .match_parameter
+            .get_borrowing_curve_names(instrument)?
+            .into_iter()
+            .map(|name| get_curve(name, "borrowing"))
+            .collect::<Result<Vec<_>>>()?;
+
+        let curr = instrument.get_currency();
+        let und_curr = instrument.get_underlying_currency()?;
+        let mut equities = Vec::new();
+        let mut volatilities = Vec::new();
+        let mut quantos = Vec::new();
+        let mut past_close_data = Vec::new();
+        for code in und_codes.iter() {
+            let equity = self.equities.get(*code).ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) failed to get equity of {}.\nself.equities does not have {}",
+                    file!(),
+                    line!(),
+                    instrument.get_code(),
+                    code,
+                )
+            })?;
+            let volatility = self.underlying_volatilities.get(*code).ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) failed to get volatility of {}.\nself.underlying_volatilities does not have {}",
+                    file!(), line!(), instrument.get_code(), code,
+                )
+            })?;
+            let quanto = match und_curr == curr {
+                false => {
+                    let key = ((*code).clone(), FxCode::new(*und_curr, *curr));
+                    let quanto = self.quantos.get(&key).ok_or_else(|| {
+                        anyhow!(
+                            "({}:{}) failed to get quanto of {}.\nself.quantos does not have {:?}",
+                            file!(),
+                            line!(),
+                            instrument.get_code(),
+                            key,
+                        )
+                    })?;
+                    Some(quanto.clone())
+                }
+                true => None,
+            };
+            equities.push(equity.clone());
+            volatilities.push(volatility.clone());
+            quantos.push(quanto);
+            past_close_data.push(self.past_close_data.get(*code).cloned());
+        }
+
+        let n = und_codes.len();
+        let mut correlation: Array2<Real> = Array2::eye(n);
+        for i in 0..n {
+            for j in (i + 1)..n {
+                let key = (und_codes[i].clone(), und_codes[j].clone());
+                let data = self
+                    .equity_correlation_data
+                    .get(&key)
+                    .or_else(|| {
+                        self.equity_correlation_data
+                            .get(&(key.1.clone(), key.0.clone()))
+                    })
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "({}:{}) failed to get correlation of {}.\nself.equity_correlation_data does not have {:?}",
+                            file!(), line!(), instrument.get_code(), key,
+                        )
+                    })?;
+                correlation[[i, j]] = data.get_value();
+                correlation[[j, i]] = data.get_value();
+            }
+        }
+
+        Ok(Pricer::AutocallableMonteCarloPricer(
+            AutocallableMonteCarloPricer::new(
+                self.evaluation_date.clone(),
+                equities,
+                collateral_curves,
+                borrowing_curves,
+                volatilities,
+                quantos,
+                past_close_data,
+                correlation,
+                discount_curve,
+                self.calculation_configuration.get_montecarlo_path_number(),
+                self.calculation_configuration
+                    .get_montecarlo_time_steps_per_year(),
+                self.calculation_configuration.get_montecarlo_seed(),
+            )?,
+        ))
+    }
+
+    fn get_bond_futures_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
+        let discount_curve_name = self.match_parameter.get_discount_curve_name(instrument)?;
+        let discount_curve = self
+            .zero_curves
+            .get(discount_curve_name)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                "({}:{}) failed to get discount curve of {}.\nself.zero_curves does not have {}",
+                file!(), line!(), instrument.get_code(), discount_curve_name,
+            )
+            })?
+            .clone();
+        // bond futures has no underlying code but the repo curve tag
+        let borrowing_curve_name = self.match_parameter.get_borrowing_curve_names(instrument)?[0];
+        let borrowing_curve = self
+            .zero_curves
+            .get(borrowing_curve_name)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                "({}:{}) failed to get borrowing curve of {}.\nself.zero_curves does not have {}",
+                file!(), line!(), instrument.get_code(), borrowing_curve_name,
+            )
+            })?
+            .clone();
+        let core = BondFuturesPricer::new(
+            self.evaluation_date.clone(),
+            discount_curve,
+            borrowing_curve,
+        );
+
+        Ok(Pricer::BondFuturesPricer(core))
     }
 
     fn get_ktbf_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
-        let discount_curve_name = String::from("KRWGOV");
+        let discount_curve_name = self.match_parameter.get_discount_curve_name(instrument)?;
         let discount_curve = self
             .zero_curves
-            .get(&discount_curve_name)
+            .get(discount_curve_name)
             .ok_or_else(|| {
                 anyhow::anyhow!(
                 "({}:{}) failed to get discount curve of {}.\nself.zero_curves does not have {}",
@@ -287,23 +635,22 @@ impl PricerFactory {
             )
             })?
             .clone();
-        let collateral_curve_name = self
-            .match_parameter
-            .get_collateral_curve_names(instrument)?[0];
-        let collateral_curve = self
+        // KTBF has no underlying code but the borrowing curve tag
+        let borrowing_curve_name = self.match_parameter.get_borrowing_curve_names(instrument)?[0];
+        let borrowing_curve = self
             .zero_curves
-            .get(collateral_curve_name)
+            .get(borrowing_curve_name)
             .ok_or_else(|| {
                 anyhow::anyhow!(
-                "({}:{}) failed to get collateral curve of {}.\nself.zero_curves does not have {}",
-                file!(), line!(), instrument.get_code(), collateral_curve_name,
+                "({}:{}) failed to get borrowing curve of {}.\nself.zero_curves does not have {}",
+                file!(), line!(), instrument.get_code(), borrowing_curve_name,
             )
             })?
             .clone();
         let core = KtbfPricer::new(
             self.evaluation_date.clone(),
             discount_curve,
-            collateral_curve,
+            borrowing_curve,
         );
 
         Ok(Pricer::KtbfPricer(core))
@@ -349,6 +696,128 @@ impl PricerFactory {
         Ok(Pricer::FxFuturesPricer(core))
     }
 
+    fn get_fx_forward_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
+        let (fx_code, fixing_source) = match Rc::as_ref(instrument) {
+            Instrument::FxForward(fx_forward) => {
+                (fx_forward.get_fx_code(), fx_forward.get_fixing_source())
+            }
+            _ => {
+                return Err(anyhow!(
+                    "({}:{}) {} ({}) is not an FxForward",
+                    file!(),
+                    line!(),
+                    instrument.get_name(),
+                    instrument.get_code(),
+                ))
+            }
+        };
+
+        let fx = self
+            .fxs
+            .get(fx_code)
+            .ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) failed to get FX of {}.\nself.fxs does not have {:?}",
+                    file!(),
+                    line!(),
+                    instrument.get_code(),
+                    fx_code,
+                )
+            })?
+            .clone();
+        let base_currency_curve_name = self
+            .match_parameter
+            .get_floating_crs_curve_name(instrument)?;
+        let base_currency_curve = self.zero_curves.get(base_currency_curve_name)
+            .ok_or_else(|| anyhow!(
+                "({}:{}) failed to get base currency curve of {}.\nself.zero_curves does not have {}",
+                file!(), line!(), instrument.get_code(), base_currency_curve_name,
+            ))?.clone();
+        let quote_currency_curve_name = self.match_parameter.get_crs_curve_name(instrument)?;
+        let quote_currency_curve = self.zero_curves.get(quote_currency_curve_name)
+            .ok_or_else(|| anyhow!(
+                "({}:{}) failed to get quote currency curve of {}.\nself.zero_curves does not have {}",
+                file!(), line!(), instrument.get_code(), quote_currency_curve_name,
+            ))?.clone();
+        // the fixing is looked up only on and after the fixing date
+        let past_fixing_data =
+            fixing_source.and_then(|source| self.past_close_data.get(source).cloned());
+
+        let core = FxForwardPricer::new(
+            self.evaluation_date.clone(),
+            fx,
+            base_currency_curve,
+            quote_currency_curve,
+            past_fixing_data,
+        );
+        Ok(Pricer::FxForwardPricer(core))
+    }
+
+    fn get_fx_option_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
+        let fx_code = match Rc::as_ref(instrument) {
+            Instrument::FxOption(fx_option) => fx_option.get_fx_code(),
+            _ => {
+                return Err(anyhow!(
+                    "({}:{}) {} ({}) is not an FxOption",
+                    file!(),
+                    line!(),
+                    instrument.get_name(),
+                    instrument.get_code(),
+                ))
+            }
+        };
+
+        let fx = self
+            .fxs
+            .get(fx_code)
+            .ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) failed to get FX of {}.\nself.fxs does not have {:?}",
+                    file!(),
+                    line!(),
+                    instrument.get_code(),
+                    fx_code,
+                )
+            })?
+            .clone();
+        let base_currency_curve_name = self
+            .match_parameter
+            .get_floating_crs_curve_name(instrument)?;
+        let base_currency_curve = self.zero_curves.get(base_currency_curve_name)
+            .ok_or_else(|| anyhow!(
+                "({}:{}) failed to get base currency curve of {}.\nself.zero_curves does not have {}",
+                file!(), line!(), instrument.get_code(), base_currency_curve_name,
+            ))?.clone();
+        let quote_currency_curve_name = self.match_parameter.get_crs_curve_name(instrument)?;
+        let quote_currency_curve = self.zero_curves.get(quote_currency_curve_name)
+            .ok_or_else(|| anyhow!(
+                "({}:{}) failed to get quote currency curve of {}.\nself.zero_curves does not have {}",
+                file!(), line!(), instrument.get_code(), quote_currency_curve_name,
+            ))?.clone();
+        let volatility = self
+            .fx_volatilities
+            .get(fx_code)
+            .ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) failed to get fx volatility of {}.\nself.fx_volatilities does not have {:?}",
+                    file!(),
+                    line!(),
+                    instrument.get_code(),
+                    fx_code,
+                )
+            })?
+            .clone();
+
+        let core = FxOptionPricer::new(
+            self.evaluation_date.clone(),
+            fx,
+            base_currency_curve,
+            quote_currency_curve,
+            volatility,
+        );
+        Ok(Pricer::FxOptionPricer(core))
+    }
+
     fn get_plain_swap_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
         let fixed_leg_discount_curve_name = self.match_parameter.get_crs_curve_name(instrument)?;
         let fixed_leg_discount_curve = self.zero_curves.get(fixed_leg_discount_curve_name)
@@ -426,6 +895,93 @@ impl PricerFactory {
         Ok(Pricer::PlainSwapPricer(core))
     }
 
+    fn get_credit_default_swap_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
+        let discount_curve_name = self.match_parameter.get_discount_curve_name(instrument)?;
+        let discount_curve = self
+            .zero_curves
+            .get(discount_curve_name)
+            .ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) failed to get discount curve of {}.\nself.zero_curves does not have {}",
+                    file!(),
+                    line!(),
+                    instrument.get_code(),
+                    discount_curve_name,
+                )
+            })?
+            .clone();
+        let spread_curve_name = self.match_parameter.get_cds_spread_curve_name(instrument)?;
+        let spread_curve = self
+            .zero_curves
+            .get(spread_curve_name)
+            .ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) failed to get par spread curve of {}.\nself.zero_curves does not have {}",
+                    file!(),
+                    line!(),
+                    instrument.get_code(),
+                    spread_curve_name,
+                )
+            })?
+            .clone();
+        let core = CreditDefaultSwapPricer::new(
+            self.evaluation_date.clone(),
+            discount_curve,
+            spread_curve,
+        );
+
+        Ok(Pricer::CreditDefaultSwapPricer(core))
+    }
+
+    fn get_rate_option_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
+        let rate_index = instrument.get_rate_index()?.ok_or_else(|| {
+            anyhow!(
+                "({}:{}) {} ({}) has no rate index",
+                file!(),
+                line!(),
+                instrument.get_name(),
+                instrument.get_code(),
+            )
+        })?;
+        let discount_curve_name = self.match_parameter.get_discount_curve_name(instrument)?;
+        let discount_curve = self
+            .zero_curves
+            .get(discount_curve_name)
+            .ok_or_else(|| {
+                anyhow!(
+                "({}:{}) failed to get discount curve of {}.\nself.zero_curves does not have {}",
+                file!(), line!(), instrument.get_code(), discount_curve_name,
+            )
+            })?
+            .clone();
+        let forward_curve_name = self.match_parameter.get_rate_index_curve_name(instrument)?;
+        let forward_curve =
+            self.zero_curves
+                .get(forward_curve_name)
+                .ok_or_else(|| {
+                    anyhow!(
+                "({}:{}) failed to get forward curve of {}.\nself.zero_curves does not have {}",
+                file!(), line!(), instrument.get_code(), forward_curve_name,
+            )
+                })?
+                .clone();
+        let rate_volatility = self.rate_volatilities.get(rate_index.get_code())
+            .ok_or_else(|| anyhow!(
+                "({}:{}) failed to get rate volatility of {}.\nself.rate_volatilities does not have {}",
+                file!(), line!(), instrument.get_code(), rate_index.get_code(),
+            ))?.clone();
+        let past_fixing_data = self.past_close_data.get(rate_index.get_name()).cloned();
+
+        let core = RateOptionPricer::new(
+            self.evaluation_date.clone(),
+            discount_curve,
+            forward_curve,
+            rate_volatility,
+            past_fixing_data,
+        );
+        Ok(Pricer::RateOptionPricer(core))
+    }
+
     fn get_stock_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
         let equity = self
             .equities
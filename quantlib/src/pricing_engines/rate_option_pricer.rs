@@ -0,0 +1,555 @@
+use crate::definitions::{Real, Time};
+use crate::enums::{CapFloorType, Compounding, OptionType, RateVolatilityType, SwaptionType};
+use crate::evaluation_date::EvaluationDate;
+use crate::instrument::{Instrument, InstrumentTrait};
+use crate::instruments::{cap_floor::CapFloor, swaption::Swaption};
+use crate::parameters::{
+    past_price::DailyClosePrice, rate_volatility::RateVolatility, zero_curve::ZeroCurve,
+};
+use crate::pricing_engines::barrier_option_analytic_pricer::vanilla_black;
+use crate::pricing_engines::{npv_result::NpvResult, pricer::PricerTrait};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+use crate::utils::string_arithmetic::{add_period, from_period_string_to_float};
+//
+use anyhow::{anyhow, Context, Result};
+use statrs::distribution::{Continuous, ContinuousCDF, Normal};
+use std::{cell::RefCell, rc::Rc};
+
+/// undiscounted Black price on forward + shift struck at strike + shift,
+/// which is the intrinsic value if there is no time value or the shifted strike is not positive
+pub(crate) fn shifted_black(
+    option_type: OptionType,
+    forward: Real,
+    strike: Real,
+    shift: Real,
+    t: Time,
+    vol: Real,
+) -> Real {
+    let intrinsic = match option_type {
+        OptionType::Call => (forward - strike).max(0.0),
+        OptionType::Put => (strike - forward).max(0.0),
+    };
+    if t <= 0.0 || vol <= 0.0 || forward + shift <= 0.0 || strike + shift <= 0.0 {
+        return intrinsic;
+    }
+    vanilla_black(option_type, strike + shift, t, forward + shift, 1.0, vol)
+}
+
+/// undiscounted Bachelier price on the forward
+pub(crate) fn bachelier(
+    option_type: OptionType,
+    forward: Real,
+    strike: Real,
+    t: Time,
+    vol: Real,
+) -> Real {
+    let deviation = vol * t.max(0.0).sqrt();
+    let moneyness = match option_type {
+        OptionType::Call => forward - strike,
+        OptionType::Put => strike - forward,
+    };
+    if deviation <= 0.0 {
+        return moneyness.max(0.0);
+    }
+    let normal = Normal::new(0.0, 1.0).unwrap();
+    let d = (moneyness / deviation) as f64;
+    moneyness * normal.cdf(d) as Real + deviation * normal.pdf(d) as Real
+}
+
+/// Caps, floors and European swaptions in the Black (shifted lognormal) or the Bachelier (normal) model
+/// following the quotation of the RateVolatility of the rate index.
+///
+/// A caplet is the option on the forward of the rate index on its tenor from the fixing date,
+/// and a caplet already fixed before the evaluation date is valued at the intrinsic value of the past fixing.
+/// A swaption is the annuity times the option on the forward swap rate,
+/// where the floating leg is projected on forward_curve as in PlainSwapPricer.
+/// The volatility is read on the time to expiry, the tenor (that of the rate index for caplets
+/// and the swap length for swaptions), and the strike.
+pub struct RateOptionPricer {
+    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    discount_curve: Rc<RefCell<ZeroCurve>>,
+    forward_curve: Rc<RefCell<ZeroCurve>>,
+    rate_volatility: Rc<RefCell<RateVolatility>>,
+    past_fixing_data: Option<Rc<DailyClosePrice>>,
+    time_calculator: NullCalendar,
+}
+
+impl RateOptionPricer {
+    pub fn new(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        discount_curve: Rc<RefCell<ZeroCurve>>,
+        forward_curve: Rc<RefCell<ZeroCurve>>,
+        rate_volatility: Rc<RefCell<RateVolatility>>,
+        past_fixing_data: Option<Rc<DailyClosePrice>>,
+    ) -> RateOptionPricer {
+        RateOptionPricer {
+            evaluation_date,
+            discount_curve,
+            forward_curve,
+            rate_volatility,
+            past_fixing_data,
+            time_calculator: NullCalendar::new(),
+        }
+    }
+
+    /// undiscounted option value in the model of the volatility quotation
+    fn option_value(
+        &self,
+        option_type: OptionType,
+        forward: Real,
+        strike: Real,
+        expiry: &time::OffsetDateTime,
+        tenor: Time,
+    ) -> Real {
+        let rate_volatility = self.rate_volatility.borrow();
+        let t = self
+            .time_calculator
+            .get_time_difference(self.evaluation_date.borrow().get_date(), expiry)
+            .max(0.0);
+        let vol = rate_volatility.get_volatility(expiry, tenor, strike, forward);
+        match rate_volatility.get_volatility_type() {
+            RateVolatilityType::ShiftedLognormal => shifted_black(
+                option_type,
+                forward,
+                strike,
+                rate_volatility.get_shift(),
+                t,
+                vol,
+            ),
+            RateVolatilityType::Normal => bachelier(option_type, forward, strike, t, vol),
+        }
+    }
+
+    fn cap_floor_npv(&self, cap_floor: &CapFloor) -> Result<Real> {
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let rate_index = cap_floor
+            .get_rate_index()?
+            .context("(RateOptionPricer) CapFloor without rate index")?;
+        let curve_tenor = rate_index.get_curve_tenor();
+        let tenor = from_period_string_to_float(curve_tenor)?;
+        let strike = cap_floor.get_strike()?;
+        let calendar = cap_floor.get_calendar()?;
+        let option_type = match cap_floor.get_cap_floor_type() {
+            CapFloorType::Cap => OptionType::Call,
+            CapFloorType::Floor => OptionType::Put,
+        };
+
+        let mut res = 0.0;
+        for base_schedule in cap_floor.get_schedule()?.iter() {
+            let payment_date = base_schedule.get_payment_date();
+            if payment_date.date() <= eval_date.date() {
+                continue;
+            }
+            let frac = calendar.year_fraction(
+                base_schedule.get_calc_start_date(),
+                base_schedule.get_calc_end_date(),
+                cap_floor.get_daycounter(),
+            )?;
+            let discount = self
+                .discount_curve
+                .borrow()
+                .get_discount_factor_at_date(payment_date)?;
+            let fixing_date = base_schedule.get_fixing_date();
+
+            let value = if fixing_date < &eval_date {
+                let past_fixing = self
+                    .past_fixing_data
+                    .as_ref()
+                    .and_then(|data| data.get(&fixing_date.date()).copied());
+                let fixing = match past_fixing {
+                    Some(rate) => rate,
+                    None => {
+                        println!(
+                            "Warning! ({}:{}) fixing_date = {:?} of {} is before the evaluation date = {:?}, \
+                            but there is no rate in the fixing date, Thus, spot rate at the evalaution date is used",
+                            file!(), line!(), fixing_date, cap_floor.get_code(), eval_date
+                        );
+                        self.forward_curve
+                            .borrow()
+                            .get_forward_rate_from_evaluation_date(
+                                &add_period(&eval_date, curve_tenor),
+                                Compounding::Simple,
+                            )?
+                    }
+                };
+                match option_type {
+                    OptionType::Call => (fixing - strike).max(0.0),
+                    OptionType::Put => (strike - fixing).max(0.0),
+                }
+            } else {
+                let forward = self.forward_curve.borrow().get_forward_rate_between_dates(
+                    fixing_date,
+                    &add_period(fixing_date, curve_tenor),
+                    Compounding::Simple,
+                )?;
+                self.option_value(option_type, forward, strike, fixing_date, tenor)
+            };
+            res += value * frac * discount;
+        }
+        Ok(res)
+    }
+
+    /// annuity of the fixed leg and the forward swap rate of the underlying swap
+    pub fn annuity_and_swap_rate(&self, swaption: &Swaption) -> Result<(Real, Real)> {
+        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let rate_index = swaption
+            .get_rate_index()?
+            .context("(RateOptionPricer) Swaption without rate index")?;
+        let calendar = swaption.get_calendar()?;
+        let discount_curve = self.discount_curve.borrow();
+
+        let mut annuity = 0.0;
+        for base_schedule in swaption.get_fixed_legs().iter() {
+            let payment_date = base_schedule.get_payment_date();
+            if payment_date.date() <= eval_date.date() {
+                continue;
+            }
+            let frac = calendar.year_fraction(
+                base_schedule.get_calc_start_date(),
+                base_schedule.get_calc_end_date(),
+                swaption.get_fixed_daycounter(),
+            )?;
+            annuity += frac * discount_curve.get_discount_factor_at_date(payment_date)?;
+        }
+
+        let mut floating_leg = 0.0;
+        let no_fixing = Rc::new(DailyClosePrice::default());
+        for base_schedule in swaption.get_floating_legs().iter() {
+            let payment_date = base_schedule.get_payment_date();
+            if payment_date.date() <= eval_date.date() {
+                continue;
+            }
+            let amount = rate_index.get_coupon_amount(
+                base_schedule,
+                None,
+                self.forward_curve.clone(),
+                no_fixing.clone(),
+                &eval_date,
+                swaption.get_floating_compound_tenor(),
+                calendar,
+                swaption.get_floating_daycounter(),
+                swaption.get_fixing_gap_days(),
+            )?;
+            floating_leg += amount * discount_curve.get_discount_factor_at_date(payment_date)?;
+        }
+
+        if annuity <= 0.0 {
+            return Err(anyhow!(
+                "({}:{}) {} ({}) has no fixed payment after the evaluation date",
+                file!(),
+                line!(),
+                swaption.get_name(),
+                swaption.get_code()
+            ));
+        }
+        Ok((annuity, floating_leg / annuity))
+    }
+
+    fn swaption_npv(&self, swaption: &Swaption) -> Result<Real> {
+        let (annuity, swap_rate) = self.annuity_and_swap_rate(swaption)?;
+        let expiry = swaption
+            .get_maturity()
+            .context("(RateOptionPricer) Failed to get the expiry")?;
+        let tenor = self.time_calculator.get_time_difference(
+            swaption.get_swap_effective_date(),
+            swaption.get_swap_maturity(),
+        );
+        let option_type = match swaption.get_swaption_type() {
+            SwaptionType::Payer => OptionType::Call,
+            SwaptionType::Receiver => OptionType::Put,
+        };
+        let value = self.option_value(
+            option_type,
+            swap_rate,
+            swaption.get_fixed_rate(),
+            expiry,
+            tenor,
+        );
+        Ok(annuity * value)
+    }
+}
+
+impl PricerTrait for RateOptionPricer {
+    fn npv(&self, instrument: &Instrument) -> Result<Real> {
+        match instrument {
+            Instrument::CapFloor(cap_floor) => self.cap_floor_npv(cap_floor),
+            Instrument::Swaption(swaption) => self.swaption_npv(swaption),
+            _ => Err(anyhow!(
+                "({}:{}) RateOptionPricer: not supported instrument type: {}",
+                file!(),
+                line!(),
+                instrument.get_type_name()
+            )),
+        }
+    }
+
+    fn npv_result(&self, instrument: &Instrument) -> Result<NpvResult> {
+        Ok(NpvResult::new_from_npv(self.npv(instrument)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::data::{rate_volatility_data::RateVolatilityData, vector_data::VectorData};
+    use crate::instruments::plain_swap::PlainSwap;
+    use crate::parameters::rate_index::RateIndex;
+    use crate::pricing_engines::plain_swap_pricer::PlainSwapPricer;
+    use crate::time::{
+        calendar::Calendar,
+        calendars::southkorea::{SouthKorea, SouthKoreaType},
+        conventions::{BusinessDayConvention, DayCountConvention, PaymentFrequency},
+        jointcalendar::JointCalendar,
+    };
+    use ndarray::{array, Array3};
+    use time::{macros::datetime, OffsetDateTime};
+
+    fn flat_volatility(
+        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        volatility_type: RateVolatilityType,
+        value: Real,
+        shift: Real,
+    ) -> Result<Rc<RefCell<RateVolatility>>> {
+        let data = RateVolatilityData::new(
+            Array3::from_elem((2, 2, 2), value),
+            vec![
+                datetime!(2025-01-02 16:30:00 +09:00),
+                datetime!(2029-01-02 16:30:00 +09:00),
+            ],
+            array![0.25, 10.0],
+            array![0.01, 0.06],
+            volatility_type,
+            shift,
+            None,
+            Currency::KRW,
+            "CD91 Vol".to_string(),
+            "CD91".to_string(),
+        );
+        let vol = RateVolatility::new(
+            evaluation_date,
+            &data,
+            "CD91 Vol".to_string(),
+            "CD91".to_string(),
+        )?;
+        Ok(Rc::new(RefCell::new(vol)))
+    }
+
+    fn irs(
+        effective_date: OffsetDateTime,
+        maturity: OffsetDateTime,
+        fixed_rate: Real,
+        rate_index: &RateIndex,
+        calendar: &JointCalendar,
+    ) -> Result<Instrument> {
+        let swap = PlainSwap::new_from_conventions(
+            Currency::KRW,
+            Currency::KRW,
+            None,
+            None,
+            None,
+            None,
+            1.0,
+            datetime!(2024-01-02 16:30:00 +09:00),
+            effective_date,
+            maturity,
+            Some(fixed_rate),
+            Some(rate_index.clone()),
+            None,
+            true,
+            DayCountConvention::Actual365Fixed,
+            DayCountConvention::Actual365Fixed,
+            BusinessDayConvention::ModifiedFollowing,
+            BusinessDayConvention::ModifiedFollowing,
+            PaymentFrequency::Quarterly,
+            PaymentFrequency::Quarterly,
+            1,
+            0,
+            calendar.clone(),
+            "IRS".to_string(),
+            "IRS".to_string(),
+        )?;
+        Ok(Instrument::PlainSwap(swap))
+    }
+
+    #[test]
+    fn test_rate_option_pricer() -> Result<()> {
+        let dt = datetime!(2024-01-02 16:30:00 +09:00);
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(dt)));
+        let calendar = JointCalendar::new(vec![Calendar::SouthKorea(SouthKorea::new(
+            SouthKoreaType::Settlement,
+        ))])?;
+        let rate_index = RateIndex::new(
+            String::from("91D"),
+            Currency::KRW,
+            String::from("CD 91D"),
+            String::from("CD91"),
+        )?;
+        let curve_data = VectorData::new(
+            array![0.033, 0.036],
+            None,
+            Some(array![0.5, 5.0]),
+            None,
+            Currency::KRW,
+            "KRWIRS".to_string(),
+            "KRWIRS".to_string(),
+        )?;
+        let curve = Rc::new(RefCell::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &curve_data,
+            "KRWIRS".to_string(),
+            "KRWIRS".to_string(),
+        )?));
+        let strike = 0.035;
+        let normal_vol = flat_volatility(
+            evaluation_date.clone(),
+            RateVolatilityType::Normal,
+            0.007,
+            0.0,
+        )?;
+        let black_vol = flat_volatility(
+            evaluation_date.clone(),
+            RateVolatilityType::ShiftedLognormal,
+            0.2,
+            0.0,
+        )?;
+        let pricer = |vol: &Rc<RefCell<RateVolatility>>| {
+            RateOptionPricer::new(
+                evaluation_date.clone(),
+                curve.clone(),
+                curve.clone(),
+                vol.clone(),
+                None,
+            )
+        };
+        let swap_pricer = PlainSwapPricer::new(
+            evaluation_date.clone(),
+            curve.clone(),
+            curve.clone(),
+            Some(curve.clone()),
+            None,
+            None,
+        )?;
+
+        // 2Y cap and floor against the swap on the same schedule
+        let effective_date = datetime!(2024-01-03 16:30:00 +09:00);
+        let maturity = datetime!(2026-01-03 16:30:00 +09:00);
+        let cap_floor = |cap_floor_type: CapFloorType| -> Result<Instrument> {
+            Ok(Instrument::CapFloor(CapFloor::new_from_conventions(
+                cap_floor_type,
+                strike,
+                rate_index.clone(),
+                1.0,
+                dt,
+                effective_date,
+                maturity,
+                true,
+                DayCountConvention::Actual365Fixed,
+                BusinessDayConvention::ModifiedFollowing,
+                PaymentFrequency::Quarterly,
+                1,
+                0,
+                calendar.clone(),
+                Currency::KRW,
+                "CD91 Cap".to_string(),
+                "CD91 Cap".to_string(),
+            )?))
+        };
+        let cap = cap_floor(CapFloorType::Cap)?;
+        let floor = cap_floor(CapFloorType::Floor)?;
+        let swap = swap_pricer.npv(&irs(
+            effective_date,
+            maturity,
+            strike,
+            &rate_index,
+            &calendar,
+        )?)?;
+        let mut cap_npvs = vec![];
+        for vol in [&normal_vol, &black_vol] {
+            let (cap_npv, floor_npv) = (pricer(vol).npv(&cap)?, pricer(vol).npv(&floor)?);
+            assert!(cap_npv > 0.0 && floor_npv > 0.0);
+            assert!(
+                (cap_npv - floor_npv - swap).abs() < 1.0e-6,
+                "cap: {}, floor: {}, swap: {}",
+                cap_npv,
+                floor_npv,
+                swap
+            );
+            cap_npvs.push(cap_npv);
+        }
+        // 20% lognormal is close to 70bp normal near the 3.5% strike
+        assert!(
+            (cap_npvs[0] - cap_npvs[1]).abs() / cap_npvs[0] < 0.03,
+            "normal: {}, black: {}",
+            cap_npvs[0],
+            cap_npvs[1]
+        );
+
+        // 1Y into 2Y swaptions against the forward starting swap
+        let expiry = datetime!(2025-01-02 16:30:00 +09:00);
+        let swap_effective_date = datetime!(2025-01-03 16:30:00 +09:00);
+        let swap_maturity = datetime!(2027-01-03 16:30:00 +09:00);
+        let swaption = |swaption_type: SwaptionType| -> Result<Instrument> {
+            Ok(Instrument::Swaption(Swaption::new_from_conventions(
+                swaption_type,
+                strike,
+                expiry,
+                rate_index.clone(),
+                None,
+                1.0,
+                dt,
+                swap_effective_date,
+                swap_maturity,
+                true,
+                DayCountConvention::Actual365Fixed,
+                DayCountConvention::Actual365Fixed,
+                BusinessDayConvention::ModifiedFollowing,
+                BusinessDayConvention::ModifiedFollowing,
+                PaymentFrequency::Quarterly,
+                PaymentFrequency::Quarterly,
+                1,
+                0,
+                calendar.clone(),
+                Currency::KRW,
+                "CD91 Swaption".to_string(),
+                "CD91 Swaption".to_string(),
+            )?))
+        };
+        let payer = swaption(SwaptionType::Payer)?;
+        let receiver = swaption(SwaptionType::Receiver)?;
+        let forward_swap = swap_pricer.npv(&irs(
+            swap_effective_date,
+            swap_maturity,
+            strike,
+            &rate_index,
+            &calendar,
+        )?)?;
+        for vol in [&normal_vol, &black_vol] {
+            let (payer_npv, receiver_npv) = (pricer(vol).npv(&payer)?, pricer(vol).npv(&receiver)?);
+            assert!(
+                (payer_npv - receiver_npv - forward_swap).abs() < 1.0e-6,
+                "payer: {}, receiver: {}, forward swap: {}",
+                payer_npv,
+                receiver_npv,
+                forward_swap
+            );
+        }
+
+        // the normal bump gives about the same vega on both quotations
+        let mut vegas = vec![];
+        for vol in [&normal_vol, &black_vol] {
+            let npv = pricer(vol).npv(&payer)?;
+            vol.borrow_mut().bump_normal_volatility(0.0001);
+            vegas.push(pricer(vol).npv(&payer)? - npv);
+            vol.borrow_mut().bump_normal_volatility(-0.0001);
+        }
+        assert!(vegas[0] > 0.0);
+        assert!(
+            (vegas[0] - vegas[1]).abs() / vegas[0] < 0.05,
+            "normal vega: {}, black vega: {}",
+            vegas[0],
+            vegas[1]
+        );
+        Ok(())
+    }
+}
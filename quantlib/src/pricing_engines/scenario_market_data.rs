@@ -0,0 +1,23 @@
+use crate::currency::FxCode;
+use crate::data::{
+    fx_volatility_quote_data::FxVolatilityQuoteData, rate_volatility_data::RateVolatilityData,
+    surface_data::SurfaceData, svi_parameter_data::SviParameterData, value_data::ValueData,
+    vector_data::VectorData,
+};
+//
+use std::collections::HashMap;
+
+/// The market data which the scenarios move, keyed as in EngineGenerator.
+#[derive(Debug, Clone, Default)]
+pub struct ScenarioMarketData {
+    pub fx_data: HashMap<FxCode, ValueData>,
+    pub stock_data: HashMap<String, ValueData>,
+    pub curve_data: HashMap<String, VectorData>,
+    pub equity_constant_volatility_data: HashMap<String, ValueData>,
+    pub equity_volatility_surface_data: HashMap<String, SurfaceData>,
+    pub fx_constant_volatility_data: HashMap<FxCode, ValueData>,
+    pub quanto_correlation_data: HashMap<(String, FxCode), ValueData>,
+    pub svi_parameter_data: HashMap<String, SviParameterData>,
+    pub fx_volatility_quote_data: HashMap<FxCode, FxVolatilityQuoteData>,
+    pub rate_volatility_data: HashMap<String, RateVolatilityData>,
+}
@@ -0,0 +1,666 @@
+use crate::currency::FxCode;
+use crate::data::{
+    fx_volatility_quote_data::FxVolatilityQuoteData, rate_volatility_data::RateVolatilityData,
+    surface_data::SurfaceData, svi_parameter_data::SviParameterData, value_data::ValueData,
+    vector_data::VectorData,
+};
+use crate::definitions::Real;
+use crate::math::interpolator::{ExtraPolationType, InterpolatorReal1D};
+use crate::math::interpolators::linear_interpolator::LinearInterpolator1D;
+use crate::pricing_engines::scenario_market_data::ScenarioMarketData;
+use crate::utils::string_arithmetic::from_period_string_to_float;
+//
+use anyhow::{anyhow, Context, Result};
+use ndarray::Array1;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A shock on the market data. The codes select the data to shock, and None selects all of them.
+/// The volatility scales fail on a selected code without the volatility data of the kind.
+/// CurveParallelShift: the zero rates move by shift, e.g., 0.01 for 100bp.
+/// CurveKeyRateShift: the zero rates move by the shifts on the tenors (e.g., "2Y"),
+/// linearly interpolated in between and flat outside,
+/// e.g., a twist of -10bp on 2Y and +10bp on 10Y, or a bucket of 0, 1bp and 0 on 1Y, 2Y and 3Y.
+/// EquitySpotShock: the stock prices move by relative_shock, e.g., -0.2 for a 20% fall.
+/// EquityVolatilityScale: the equity volatilities (constant, surface and SVI) are multiplied by scale.
+/// FxVolatilityScale: the fx volatilities (constant and the quotes of ATM, RR and BF) are multiplied by scale.
+/// RateVolatilityScale: the rate volatility cubes keyed by the rate index code are multiplied by scale.
+/// FxShock: the fx rates (e.g., "USDKRW") move by relative_shock, and their reciprocals the other way around.
+/// QuantoCorrelationOverride: the quanto correlations are replaced by correlation.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum MarketShock {
+    CurveParallelShift {
+        curve_names: Option<Vec<String>>,
+        shift: Real,
+    },
+    CurveKeyRateShift {
+        curve_names: Option<Vec<String>>,
+        tenors: Vec<String>,
+        shifts: Vec<Real>,
+    },
+    EquitySpotShock {
+        underlying_codes: Option<Vec<String>>,
+        relative_shock: Real,
+    },
+    EquityVolatilityScale {
+        underlying_codes: Option<Vec<String>>,
+        scale: Real,
+    },
+    FxVolatilityScale {
+        fx_codes: Option<Vec<String>>,
+        scale: Real,
+    },
+    RateVolatilityScale {
+        rate_index_codes: Option<Vec<String>>,
+        scale: Real,
+    },
+    FxShock {
+        fx_codes: Vec<String>,
+        relative_shock: Real,
+    },
+    QuantoCorrelationOverride {
+        underlying_codes: Option<Vec<String>>,
+        fx_codes: Option<Vec<String>>,
+        correlation: Real,
+    },
+}
+
+fn is_selected(codes: &Option<Vec<String>>, code: &str) -> bool {
+    match codes {
+        Some(codes) => codes.iter().any(|c| c == code),
+        None => true,
+    }
+}
+
+fn check_scale(scale: Real) -> Result<()> {
+    if scale < 0.0 {
+        return Err(anyhow!(
+            "({}:{}) the volatility scale must be non-negative, got {}",
+            file!(),
+            line!(),
+            scale
+        ));
+    }
+    Ok(())
+}
+
+/// the selected codes must be in the keys of the data
+fn check_selected(
+    kind: &str,
+    codes: &Option<Vec<String>>,
+    keys: impl Iterator<Item = String> + Clone,
+) -> Result<()> {
+    let Some(codes) = codes else {
+        return Ok(());
+    };
+    for code in codes {
+        if !keys.clone().any(|key| &key == code) {
+            return Err(anyhow!(
+                "({}:{}) there is no {} volatility data of {}",
+                file!(),
+                line!(),
+                kind,
+                code
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn shocked_value(data: &ValueData, value: Real) -> Result<ValueData> {
+    ValueData::new(
+        value,
+        *data.get_market_datetime(),
+        *data.get_currency(),
+        data.get_name().clone(),
+        data.get_code().clone(),
+    )
+}
+
+fn shocked_curve(data: &VectorData, shift: &dyn Fn(Real) -> Result<Real>) -> Result<VectorData> {
+    let times = data.get_times_clone();
+    let shifts = times
+        .iter()
+        .map(|t| shift(*t))
+        .collect::<Result<Array1<Real>>>()?;
+    VectorData::new(
+        data.get_value_clone() + shifts,
+        data.get_dates_clone(),
+        Some(times),
+        data.get_market_datetime(),
+        *data.get_currency(),
+        data.get_name_clone(),
+        data.get_code_clone(),
+    )
+}
+
+impl MarketShock {
+    pub fn apply(&self, market_data: &mut ScenarioMarketData) -> Result<()> {
+        match self {
+            MarketShock::CurveParallelShift { curve_names, shift } => {
+                for (name, data) in market_data.curve_data.iter_mut() {
+                    if is_selected(curve_names, name) {
+                        *data = shocked_curve(data, &|_| Ok(*shift))?;
+                    }
+                }
+            }
+            MarketShock::CurveKeyRateShift {
+                curve_names,
+                tenors,
+                shifts,
+            } => {
+                let key_times = tenors
+                    .iter()
+                    .map(|tenor| from_period_string_to_float(tenor))
+                    .collect::<Result<Array1<Real>>>()?;
+                let interpolator = LinearInterpolator1D::new(
+                    key_times,
+                    Array1::from_vec(shifts.clone()),
+                    ExtraPolationType::Flat,
+                    true,
+                )
+                .with_context(|| {
+                    format!(
+                        "({}:{}) invalid key rate shifts {:?} on {:?}",
+                        file!(),
+                        line!(),
+                        shifts,
+                        tenors
+                    )
+                })?;
+                for (name, data) in market_data.curve_data.iter_mut() {
+                    if is_selected(curve_names, name) {
+                        *data = shocked_curve(data, &|t| interpolator.interpolate(t))?;
+                    }
+                }
+            }
+            MarketShock::EquitySpotShock {
+                underlying_codes,
+                relative_shock,
+            } => {
+                for (code, data) in market_data.stock_data.iter_mut() {
+                    if is_selected(underlying_codes, code) {
+                        *data = shocked_value(data, data.get_value() * (1.0 + relative_shock))?;
+                    }
+                }
+            }
+            MarketShock::EquityVolatilityScale {
+                underlying_codes,
+                scale,
+            } => {
+                check_scale(*scale)?;
+                check_selected(
+                    "equity",
+                    underlying_codes,
+                    market_data
+                        .equity_constant_volatility_data
+                        .keys()
+                        .chain(market_data.equity_volatility_surface_data.keys())
+                        .chain(market_data.svi_parameter_data.keys())
+                        .cloned(),
+                )?;
+                for (code, data) in market_data.equity_constant_volatility_data.iter_mut() {
+                    if is_selected(underlying_codes, code) {
+                        *data = shocked_value(data, data.get_value() * scale)?;
+                    }
+                }
+                for (code, data) in market_data.equity_volatility_surface_data.iter_mut() {
+                    if is_selected(underlying_codes, code) {
+                        *data = SurfaceData::new(
+                            data.get_spot(),
+                            data.get_value() * *scale,
+                            data.get_dates().clone(),
+                            data.get_strike().clone(),
+                            data.get_market_datetime(),
+                            *data.get_currency(),
+                            data.get_name().to_string(),
+                            data.get_code().to_string(),
+                        );
+                    }
+                }
+                for (code, data) in market_data.svi_parameter_data.iter_mut() {
+                    if is_selected(underlying_codes, code) {
+                        *data = SviParameterData::new(
+                            data.get_spot(),
+                            data.get_parameters().scaled(*scale),
+                            data.get_dates().clone(),
+                            data.get_market_datetime(),
+                            *data.get_currency(),
+                            data.get_name().to_string(),
+                            data.get_code().to_string(),
+                        );
+                    }
+                }
+            }
+            MarketShock::FxVolatilityScale { fx_codes, scale } => {
+                check_scale(*scale)?;
+                check_selected(
+                    "fx",
+                    fx_codes,
+                    market_data
+                        .fx_constant_volatility_data
+                        .keys()
+                        .chain(market_data.fx_volatility_quote_data.keys())
+                        .map(|fx_code| fx_code.to_string()),
+                )?;
+                for (fx_code, data) in market_data.fx_constant_volatility_data.iter_mut() {
+                    if is_selected(fx_codes, &fx_code.to_string()) {
+                        *data = shocked_value(data, data.get_value() * scale)?;
+                    }
+                }
+                for (fx_code, data) in market_data.fx_volatility_quote_data.iter_mut() {
+                    if is_selected(fx_codes, &fx_code.to_string()) {
+                        *data = FxVolatilityQuoteData::new(
+                            data.get_expiry_dates().clone(),
+                            data.get_atm() * *scale,
+                            data.get_risk_reversal_25() * *scale,
+                            data.get_butterfly_25() * *scale,
+                            data.get_delta_type(),
+                            data.get_atm_type(),
+                            data.get_market_datetime(),
+                            *data.get_fx_code(),
+                            data.get_name().to_string(),
+                            data.get_code().to_string(),
+                        )
+                        .with_strangle_type(data.get_strangle_type());
+                    }
+                }
+            }
+            MarketShock::RateVolatilityScale {
+                rate_index_codes,
+                scale,
+            } => {
+                check_scale(*scale)?;
+                check_selected(
+                    "rate",
+                    rate_index_codes,
+                    market_data.rate_volatility_data.keys().cloned(),
+                )?;
+                for (code, data) in market_data.rate_volatility_data.iter_mut() {
+                    if is_selected(rate_index_codes, code) {
+                        *data = RateVolatilityData::new(
+                            data.get_value() * *scale,
+                            data.get_expiry_dates().clone(),
+                            data.get_tenors().clone(),
+                            data.get_strikes().clone(),
+                            data.get_volatility_type(),
+                            data.get_shift(),
+                            data.get_market_datetime(),
+                            *data.get_currency(),
+                            data.get_name().to_string(),
+                            data.get_code().to_string(),
+                        );
+                    }
+                }
+            }
+            MarketShock::FxShock {
+                fx_codes,
+                relative_shock,
+            } => {
+                let shocked = fx_codes
+                    .iter()
+                    .map(|code| {
+                        if code.len() != 6 {
+                            return Err(anyhow!(
+                                "({}:{}) invalid fx code {}",
+                                file!(),
+                                line!(),
+                                code
+                            ));
+                        }
+                        Ok(FxCode::from(code.as_str()))
+                    })
+                    .collect::<Result<Vec<FxCode>>>()?;
+                for (fx_code, data) in market_data.fx_data.iter_mut() {
+                    if shocked.contains(fx_code) {
+                        *data = shocked_value(data, data.get_value() * (1.0 + relative_shock))?;
+                    } else if shocked.contains(&fx_code.reciprocal()) {
+                        *data = shocked_value(data, data.get_value() / (1.0 + relative_shock))?;
+                    }
+                }
+            }
+            MarketShock::QuantoCorrelationOverride {
+                underlying_codes,
+                fx_codes,
+                correlation,
+            } => {
+                if !(-1.0..=1.0).contains(correlation) {
+                    return Err(anyhow!(
+                        "({}:{}) the correlation must be in [-1, 1], got {}",
+                        file!(),
+                        line!(),
+                        correlation
+                    ));
+                }
+                for ((und_code, fx_code), data) in market_data.quanto_correlation_data.iter_mut() {
+                    if is_selected(underlying_codes, und_code)
+                        && is_selected(fx_codes, &fx_code.to_string())
+                    {
+                        *data = shocked_value(data, *correlation)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A named set of the market shocks applied in order.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StressScenario {
+    name: String,
+    shocks: Vec<MarketShock>,
+}
+
+impl StressScenario {
+    pub fn new(name: String, shocks: Vec<MarketShock>) -> StressScenario {
+        StressScenario { name, shocks }
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_shocks(&self) -> &Vec<MarketShock> {
+        &self.shocks
+    }
+
+    pub fn apply(&self, market_data: &ScenarioMarketData) -> Result<ScenarioMarketData> {
+        let mut res = market_data.clone();
+        for shock in &self.shocks {
+            shock.apply(&mut res).with_context(|| {
+                format!(
+                    "({}:{}) failed to apply {:?} in {}",
+                    file!(),
+                    line!(),
+                    shock,
+                    self.name
+                )
+            })?;
+        }
+        Ok(res)
+    }
+}
+
+/// NPVs of the instruments on the base and the stress scenarios.
+/// The differences are the scenario minus the base per instrument code,
+/// in the npv (per unit notional) and in the value (considering unit_notional) in the instrument currency.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StressTestResult {
+    scenario_names: Vec<String>,
+    base_npvs: HashMap<String, Real>,
+    npv_differences: HashMap<String, HashMap<String, Real>>,
+    value_differences: HashMap<String, HashMap<String, Real>>,
+}
+
+impl StressTestResult {
+    pub fn new(
+        scenario_names: Vec<String>,
+        base_npvs: HashMap<String, Real>,
+        npv_differences: HashMap<String, HashMap<String, Real>>,
+        value_differences: HashMap<String, HashMap<String, Real>>,
+    ) -> StressTestResult {
+        StressTestResult {
+            scenario_names,
+            base_npvs,
+            npv_differences,
+            value_differences,
+        }
+    }
+
+    pub fn get_scenario_names(&self) -> &Vec<String> {
+        &self.scenario_names
+    }
+
+    pub fn get_base_npvs(&self) -> &HashMap<String, Real> {
+        &self.base_npvs
+    }
+
+    /// scenario name -> instrument code -> npv difference
+    pub fn get_npv_differences(&self) -> &HashMap<String, HashMap<String, Real>> {
+        &self.npv_differences
+    }
+
+    /// scenario name -> instrument code -> value difference
+    pub fn get_value_differences(&self) -> &HashMap<String, HashMap<String, Real>> {
+        &self.value_differences
+    }
+
+    pub fn get_npv_difference(&self, scenario_name: &str, instrument_code: &str) -> Option<Real> {
+        self.npv_differences
+            .get(scenario_name)
+            .and_then(|differences| differences.get(instrument_code))
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::enums::{FxAtmType, FxDeltaType, RateVolatilityType};
+    use crate::parameters::volatilities::svi_volatility_surface::{
+        RawSviParameters, SsviParameters, SviParameters,
+    };
+    use ndarray::{array, Array3};
+    use time::macros::datetime;
+
+    #[test]
+    fn test_market_shocks() -> Result<()> {
+        let usdkrw = FxCode::new(Currency::USD, Currency::KRW);
+        let value = |v: Real, name: &str| {
+            ValueData::new(v, None, Currency::KRW, name.to_string(), name.to_string())
+        };
+        let mut market_data = ScenarioMarketData::default();
+        market_data.fx_data.insert(usdkrw, value(1300.0, "USDKRW")?);
+        market_data
+            .fx_data
+            .insert(usdkrw.reciprocal(), value(1.0 / 1300.0, "KRWUSD")?);
+        market_data
+            .stock_data
+            .insert("KOSPI2".to_string(), value(350.0, "KOSPI2")?);
+        market_data
+            .stock_data
+            .insert("SPX".to_string(), value(4700.0, "SPX")?);
+        market_data.curve_data.insert(
+            "KSD".to_string(),
+            VectorData::new(
+                array![0.03, 0.03, 0.03],
+                None,
+                Some(array![1.0, 5.0, 10.0]),
+                None,
+                Currency::KRW,
+                "KSD".to_string(),
+                "KSD".to_string(),
+            )?,
+        );
+        market_data
+            .equity_constant_volatility_data
+            .insert("KOSPI2".to_string(), value(0.2, "KOSPI2")?);
+        market_data
+            .quanto_correlation_data
+            .insert(("SPX".to_string(), usdkrw), value(0.3, "SPX USDKRW")?);
+
+        // declared as in the input json
+        let json = r#"{
+            "name": "crash",
+            "shocks": [
+                {"CurveParallelShift": {"shift": 0.01}},
+                {"CurveKeyRateShift": {"curve_names": ["KSD"], "tenors": ["1Y", "10Y"], "shifts": [-0.001, 0.001]}},
+                {"EquitySpotShock": {"underlying_codes": ["KOSPI2"], "relative_shock": -0.2}},
+                {"EquityVolatilityScale": {"scale": 1.5}},
+                {"FxShock": {"fx_codes": ["USDKRW"], "relative_shock": 0.1}},
+                {"QuantoCorrelationOverride": {"fx_codes": ["USDKRW"], "correlation": -0.5}}
+            ]
+        }"#;
+        let scenario: StressScenario = serde_json::from_str(json)?;
+        assert_eq!(scenario.get_shocks().len(), 6);
+        let shocked = scenario.apply(&market_data)?;
+
+        let rates = shocked.curve_data["KSD"].get_value_clone();
+        let expected = [0.039, 0.03 + 0.01 - 0.001 + 0.002 * 4.0 / 9.0, 0.041];
+        for (rate, expected) in rates.iter().zip(expected.iter()) {
+            assert!((rate - expected).abs() < 1.0e-6, "{} != {}", rate, expected);
+        }
+        assert!((shocked.stock_data["KOSPI2"].get_value() - 280.0).abs() < 1.0e-3);
+        assert_eq!(shocked.stock_data["SPX"].get_value(), 4700.0);
+        assert!(
+            (shocked.equity_constant_volatility_data["KOSPI2"].get_value() - 0.3).abs() < 1.0e-6
+        );
+        assert!((shocked.fx_data[&usdkrw].get_value() - 1430.0).abs() < 1.0e-2);
+        assert!((shocked.fx_data[&usdkrw.reciprocal()].get_value() * 1430.0 - 1.0).abs() < 1.0e-5);
+        assert_eq!(
+            shocked.quanto_correlation_data[&("SPX".to_string(), usdkrw)].get_value(),
+            -0.5
+        );
+        // the base is not changed
+        assert_eq!(market_data.stock_data["KOSPI2"].get_value(), 350.0);
+
+        let invalid = StressScenario::new(
+            "invalid".to_string(),
+            vec![MarketShock::QuantoCorrelationOverride {
+                underlying_codes: None,
+                fx_codes: None,
+                correlation: 1.5,
+            }],
+        );
+        assert!(invalid.apply(&market_data).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_volatility_scales() -> Result<()> {
+        let usdkrw = FxCode::new(Currency::USD, Currency::KRW);
+        let dt = datetime!(2024-01-02 16:00:00 +09:00);
+        let mut market_data = ScenarioMarketData::default();
+        market_data.svi_parameter_data.insert(
+            "KOSPI2".to_string(),
+            SviParameterData::new(
+                350.0,
+                SviParameters::RawSvi(vec![RawSviParameters::new(0.01, 0.1, -0.3, 0.0, 0.1)]),
+                vec![datetime!(2024-06-13 16:00:00 +09:00)],
+                Some(dt),
+                Currency::KRW,
+                "KOSPI2".to_string(),
+                "KOSPI2".to_string(),
+            ),
+        );
+        market_data.svi_parameter_data.insert(
+            "SPX".to_string(),
+            SviParameterData::new(
+                4700.0,
+                SviParameters::Ssvi(SsviParameters::new(-0.5, 1.0, 0.4, vec![0.01, 0.02])),
+                vec![
+                    datetime!(2024-06-13 16:00:00 +09:00),
+                    datetime!(2024-12-12 16:00:00 +09:00),
+                ],
+                Some(dt),
+                Currency::USD,
+                "SPX".to_string(),
+                "SPX".to_string(),
+            ),
+        );
+        market_data.fx_constant_volatility_data.insert(
+            usdkrw,
+            ValueData::new(
+                0.1,
+                None,
+                Currency::KRW,
+                "USDKRW".to_string(),
+                "USDKRW".to_string(),
+            )?,
+        );
+        market_data.fx_volatility_quote_data.insert(
+            usdkrw,
+            FxVolatilityQuoteData::new(
+                vec![datetime!(2024-04-02 16:00:00 +09:00)],
+                array![0.08],
+                array![0.01],
+                array![0.003],
+                FxDeltaType::SpotPremiumAdjusted,
+                FxAtmType::DeltaNeutralStraddle,
+                Some(dt),
+                usdkrw,
+                "USDKRW".to_string(),
+                "USDKRW".to_string(),
+            ),
+        );
+        market_data.rate_volatility_data.insert(
+            "CD91".to_string(),
+            RateVolatilityData::new(
+                Array3::from_elem((1, 1, 2), 0.01),
+                vec![datetime!(2025-01-02 16:00:00 +09:00)],
+                array![0.25],
+                array![0.03, 0.04],
+                RateVolatilityType::Normal,
+                0.0,
+                Some(dt),
+                Currency::KRW,
+                "CD91".to_string(),
+                "CD91".to_string(),
+            ),
+        );
+
+        let scenario = StressScenario::new(
+            "volatility up".to_string(),
+            vec![
+                MarketShock::EquityVolatilityScale {
+                    underlying_codes: None,
+                    scale: 2.0,
+                },
+                MarketShock::FxVolatilityScale {
+                    fx_codes: Some(vec!["USDKRW".to_string()]),
+                    scale: 1.5,
+                },
+                MarketShock::RateVolatilityScale {
+                    rate_index_codes: None,
+                    scale: 0.5,
+                },
+            ],
+        );
+        let shocked = scenario.apply(&market_data)?;
+
+        // the raw SVI total variance is multiplied by the square of the scale
+        let SviParameters::RawSvi(slices) = shocked.svi_parameter_data["KOSPI2"].get_parameters()
+        else {
+            panic!("the raw SVI parameters are changed to SSVI");
+        };
+        assert!((slices[0].get_a() - 0.04).abs() < 1.0e-6);
+        assert!((slices[0].get_b() - 0.4).abs() < 1.0e-6);
+        assert_eq!(slices[0].get_rho(), -0.3);
+        let SviParameters::Ssvi(ssvi) = shocked.svi_parameter_data["SPX"].get_parameters() else {
+            panic!("the SSVI parameters are changed to raw SVI");
+        };
+        assert!((ssvi.get_atm_total_variances()[1] - 0.08).abs() < 1.0e-6);
+
+        assert!((shocked.fx_constant_volatility_data[&usdkrw].get_value() - 0.15).abs() < 1.0e-6);
+        let quotes = &shocked.fx_volatility_quote_data[&usdkrw];
+        assert!((quotes.get_atm()[0] - 0.12).abs() < 1.0e-6);
+        assert!((quotes.get_risk_reversal_25()[0] - 0.015).abs() < 1.0e-6);
+        assert!((quotes.get_butterfly_25()[0] - 0.0045).abs() < 1.0e-6);
+        assert!(shocked.rate_volatility_data["CD91"]
+            .get_value()
+            .iter()
+            .all(|v| (v - 0.005).abs() < 1.0e-6));
+
+        // a selected code without the data of the kind
+        for shock in [
+            MarketShock::EquityVolatilityScale {
+                underlying_codes: Some(vec!["USDKRW".to_string()]),
+                scale: 2.0,
+            },
+            MarketShock::FxVolatilityScale {
+                fx_codes: Some(vec!["EURKRW".to_string()]),
+                scale: 2.0,
+            },
+            MarketShock::RateVolatilityScale {
+                rate_index_codes: Some(vec!["SOFR".to_string()]),
+                scale: 2.0,
+            },
+        ] {
+            let invalid = StressScenario::new("invalid".to_string(), vec![shock]);
+            assert!(invalid.apply(&market_data).is_err());
+        }
+        Ok(())
+    }
+}
@@ -693,7 +693,7 @@ impl Holidays for SouthKoreaType {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SouthKorea {
     name: String,
     utc_offset: UtcOffset,
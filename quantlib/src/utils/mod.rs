@@ -4,4 +4,6 @@ pub mod find_index_ndarray;
 pub mod make_fx;
 pub mod number_format;
 pub mod string_arithmetic;
+#[cfg(test)]
+pub mod test_data;
 pub mod tracing_timer;
@@ -0,0 +1,86 @@
+use crate::currency::{Currency, FxCode};
+use crate::data::{value_data::ValueData, vector_data::VectorData};
+use crate::definitions::Real;
+use crate::enums::OptionType;
+use crate::evaluation_date::EvaluationDate;
+use crate::instruments::fx_option::FxOption;
+use crate::parameters::zero_curve::ZeroCurve;
+use crate::pricing_engines::match_parameter::MatchParameter;
+use anyhow::Result;
+use ndarray::array;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use time::{macros::datetime, OffsetDateTime};
+
+/// curve data of a flat zero rate
+pub fn flat_curve_data(rate: Real, currency: Currency, name: &str) -> Result<VectorData> {
+    VectorData::new(
+        array![rate, rate],
+        None,
+        Some(array![0.5, 30.0]),
+        None,
+        currency,
+        name.to_string(),
+        name.to_string(),
+    )
+}
+
+pub fn flat_zero_curve(
+    evaluation_date: &Rc<RefCell<EvaluationDate>>,
+    rate: Real,
+    currency: Currency,
+    name: &str,
+) -> Result<Rc<RefCell<ZeroCurve>>> {
+    Ok(Rc::new(RefCell::new(ZeroCurve::new(
+        evaluation_date.clone(),
+        &flat_curve_data(rate, currency, name)?,
+        name.to_string(),
+        name.to_string(),
+    )?)))
+}
+
+/// a value in KRW named by the code
+pub fn krw_value(value: Real, dt: OffsetDateTime, code: &str) -> Result<ValueData> {
+    ValueData::new(
+        value,
+        Some(dt),
+        Currency::KRW,
+        code.to_string(),
+        code.to_string(),
+    )
+}
+
+/// USDKRW 1300 call on 1 million USD traded on dt and expiring on 2024-07-02
+pub fn usdkrw_call(dt: OffsetDateTime) -> FxOption {
+    FxOption::new(
+        FxCode::new(Currency::USD, Currency::KRW),
+        1300.0,
+        OptionType::Call,
+        dt,
+        datetime!(2024-07-02 16:00:00 +09:00),
+        1_000_000.0,
+        "USDKRW C 1300 6M".to_string(),
+        "USDKRWC13006M".to_string(),
+    )
+}
+
+/// USDOIS and KRWCRS for the fx instruments, and KRWCRS and the curve of the same code
+/// as the collateral and the borrowing curves of the KRW stocks
+pub fn usdkrw_match_parameter(stock_codes: &[&str]) -> MatchParameter {
+    let mut collateral_curve_map = HashMap::new();
+    let mut borrowing_curve_map = HashMap::new();
+    for code in stock_codes {
+        collateral_curve_map.insert(code.to_string(), "KRWCRS".to_string());
+        borrowing_curve_map.insert(code.to_string(), code.to_string());
+    }
+    let mut crs_curve_map = HashMap::new();
+    crs_curve_map.insert(Currency::USD, "USDOIS".to_string());
+    crs_curve_map.insert(Currency::KRW, "KRWCRS".to_string());
+    MatchParameter::new(
+        collateral_curve_map,
+        borrowing_curve_map,
+        HashMap::new(),
+        crs_curve_map,
+        HashMap::new(),
+        HashMap::new(),
+    )
+}
@@ -4,17 +4,26 @@ mod tests {
     use ndarray::array;
     use ndarray::Array1;
     use quantlib::currency::{Currency, FxCode};
+    use quantlib::data::fx_volatility_quote_data::FxVolatilityQuoteData;
     use quantlib::data::value_data::ValueData;
     use quantlib::data::vector_data::VectorData;
     use quantlib::definitions::Real;
-    use quantlib::enums::{CreditRating, IssuerType, RankType};
+    use quantlib::enums::VanillaOptionCalculationMethod;
+    use quantlib::enums::{CreditRating, FxSettlementType, IssuerType, ProtectionSide, RankType};
+    use quantlib::enums::{FxAtmType, FxDeltaType, PnlExplainMethod};
     use quantlib::enums::{OptionDailySettlementType, OptionExerciseType, OptionType};
-    use quantlib::instrument::{Instrument, Instruments};
+    use quantlib::evaluation_date::EvaluationDate;
+    use quantlib::instrument::{Instrument, InstrumentTrait, Instruments};
     use quantlib::instruments::{
-        bond::Bond, cash::Cash, futures::Futures, stock::Stock, vanilla_option::VanillaOption,
+        bond::Bond, bond_futures::BondFutures, cash::Cash, credit_default_swap::CreditDefaultSwap,
+        futures::Futures, fx_forward::FxForward, fx_option::FxOption, ktbf::KtbfVirtualBond,
+        ktbf::KTBF, schedule::cds_standard_maturity, stock::Stock, vanilla_option::VanillaOption,
     };
+    use quantlib::parameters::zero_curve::ZeroCurve;
+    use quantlib::pricing_engines::engine::Engine;
     use quantlib::pricing_engines::engine_generator::{EngineGenerator, InstrumentCategory};
     use quantlib::pricing_engines::match_parameter::MatchParameter;
+    use quantlib::pricing_engines::stress_scenario::{MarketShock, StressScenario};
     use quantlib::pricing_engines::{
         calculation_configuration::CalculationConfiguration, calculation_result::CalculationResult,
     };
@@ -25,16 +34,82 @@ mod tests {
     };
     use quantlib::time::jointcalendar::JointCalendar;
     use quantlib::utils::tracing_timer::CustomOffsetTime;
+    use std::cell::RefCell;
     use std::collections::HashMap;
     use std::rc::Rc;
     use std::time::Instant;
-    use time::{macros::datetime, Duration};
+    use time::{macros::datetime, Duration, OffsetDateTime};
     use tracing::{info, span, Level};
     use tracing_appender::non_blocking;
     use tracing_appender::rolling;
     use tracing_subscriber::fmt::{self, writer::MakeWriterExt};
     use tracing_subscriber::layer::SubscriberExt;
 
+    /// data of the flat zero rate curves keyed by the curve names
+    fn flat_curve_data(curves: &[(&str, Real)]) -> Result<HashMap<String, VectorData>> {
+        let mut res = HashMap::new();
+        for (name, rate) in curves {
+            let data = VectorData::new(
+                array![*rate, *rate],
+                None,
+                Some(array![0.5, 5.0]),
+                None,
+                Currency::KRW,
+                name.to_string(),
+                name.to_string(),
+            )?;
+            res.insert(name.to_string(), data);
+        }
+        Ok(res)
+    }
+
+    /// a value in KRW named by the code
+    fn krw_value(value: Real, dt: OffsetDateTime, code: &str) -> Result<ValueData> {
+        ValueData::new(
+            value,
+            Some(dt),
+            Currency::KRW,
+            code.to_string(),
+            code.to_string(),
+        )
+    }
+
+    /// USDKRW 1300 call on 1 million USD traded on dt and expiring on 2024-07-02
+    fn usdkrw_call(dt: OffsetDateTime) -> FxOption {
+        FxOption::new(
+            FxCode::new(Currency::USD, Currency::KRW),
+            1300.0,
+            OptionType::Call,
+            dt,
+            datetime!(2024-07-02 16:00:00 +09:00),
+            1_000_000.0,
+            "USDKRW C 1300 6M".to_string(),
+            "USDKRWC13006M".to_string(),
+        )
+    }
+
+    /// USDOIS and KRWCRS for the fx instruments, and KRWCRS and the curve of the same code
+    /// as the collateral and the borrowing curves of the KRW stocks
+    fn usdkrw_match_parameter(stock_codes: &[&str]) -> MatchParameter {
+        let mut collateral_curve_map = HashMap::new();
+        let mut borrowing_curve_map = HashMap::new();
+        for code in stock_codes {
+            collateral_curve_map.insert(code.to_string(), "KRWCRS".to_string());
+            borrowing_curve_map.insert(code.to_string(), code.to_string());
+        }
+        let mut crs_curve_map = HashMap::new();
+        crs_curve_map.insert(Currency::USD, "USDOIS".to_string());
+        crs_curve_map.insert(Currency::KRW, "KRWCRS".to_string());
+        MatchParameter::new(
+            collateral_curve_map,
+            borrowing_curve_map,
+            HashMap::new(),
+            crs_curve_map,
+            HashMap::new(),
+            HashMap::new(),
+        )
+    }
+
     #[test]
     fn test_engine() -> Result<()> {
         let theta_day = 100;
@@ -502,4 +577,1137 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_credit_spread_curve() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let calendar = JointCalendar::new(vec![Calendar::SouthKorea(SouthKorea::new(
+            SouthKoreaType::Settlement,
+        ))])?;
+        let bond = Bond::new_from_conventions(
+            IssuerType::CorporateUnguaranteed,
+            CreditRating::AAm,
+            "Some Corporate".to_string(),
+            RankType::Senior,
+            Currency::KRW,
+            10_000.0,
+            false,
+            datetime!(2023-06-20 00:00:00 +09:00),
+            datetime!(2023-06-20 00:00:00 +09:00),
+            None,
+            datetime!(2028-06-20 00:00:00 +09:00),
+            Some(0.045),
+            None,
+            None,
+            None,
+            calendar,
+            true,
+            DayCountConvention::StreetConvention,
+            BusinessDayConvention::Unadjusted,
+            PaymentFrequency::Quarterly,
+            0,
+            0,
+            "Corp AA- 5Y".to_string(),
+            "KRCORPAAM5Y".to_string(),
+        )?;
+
+        let curve_data =
+            flat_curve_data(&[("KRWGOV", 0.03), ("KRWCORP_AA-", 0.01), ("KRWCORP4", 0.04)])?;
+
+        let mut credit_spread_curve_map = HashMap::new();
+        credit_spread_curve_map.insert(
+            (
+                IssuerType::CorporateUnguaranteed,
+                CreditRating::AAm,
+                RankType::Senior,
+                Currency::KRW,
+            ),
+            "KRWCORP_AA-".to_string(),
+        );
+        let mut risk_free_curve_map = HashMap::new();
+        risk_free_curve_map.insert(Currency::KRW, "KRWGOV".to_string());
+        let spread_match_parameter = MatchParameter::default()
+            .with_credit_spread_curve_map(credit_spread_curve_map, risk_free_curve_map);
+        // the same bond on a 4% curve for comparison
+        let mut bond_discount_curve_map = HashMap::new();
+        bond_discount_curve_map.insert(
+            (
+                "Some Corporate".to_string(),
+                IssuerType::CorporateUnguaranteed,
+                CreditRating::AAm,
+                Currency::KRW,
+            ),
+            "KRWCORP4".to_string(),
+        );
+        let flat_match_parameter = MatchParameter::new(
+            HashMap::new(),
+            HashMap::new(),
+            bond_discount_curve_map,
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+
+        let calculation_configuration = CalculationConfiguration::default()
+            .with_rho_calculation(true)
+            .with_rho_structure_calculation(true)
+            .with_cs01_structure_calculation(true);
+
+        let curve_data = std::sync::Arc::new(curve_data);
+        let mut results = vec![];
+        for match_parameter in [spread_match_parameter, flat_match_parameter] {
+            let mut engine =
+                Engine::builder(0, calculation_configuration.clone(), dt, match_parameter)
+                    .with_instruments(vec![Instrument::Bond(bond.clone())])?
+                    .with_parameter_data(
+                        Default::default(),
+                        Default::default(),
+                        curve_data.clone(),
+                        Default::default(),
+                        Default::default(),
+                        Default::default(),
+                        Default::default(),
+                        Default::default(),
+                        Default::default(),
+                    )?;
+            engine.initialize_pricers()?;
+            engine.calculate()?;
+            results.push(engine.get_calculation_result_clone()["KRCORPAAM5Y"].clone());
+        }
+
+        // the spread curve is layered on the risk-free curve
+        let npv = results[0].get_npv_result().unwrap().get_npv();
+        let npv_flat = results[1].get_npv_result().unwrap().get_npv();
+        assert!(
+            (npv - npv_flat).abs() < 1.0e-4,
+            "npv on credit spread = {}, npv on the flat curve = {}",
+            npv,
+            npv_flat
+        );
+
+        // rho is on the risk-free curve and cs01 is on the spread curve
+        let rho = results[0].get_rho().unwrap();
+        assert!(rho.contains_key("KRWGOV") && !rho.contains_key("KRWCORP_AA-"));
+        assert!(!results[0]
+            .get_rho_structure()
+            .unwrap()
+            .contains_key("KRWCORP_AA-"));
+        let cs01 = results[0].get_cs01_structure().unwrap();
+        assert_eq!(cs01.len(), 1);
+        let cs01_sum: Real = cs01["KRWCORP_AA-"].iter().sum();
+        let rho_flat = results[1].get_rho().unwrap()["KRWCORP4"];
+        assert!(cs01_sum < 0.0);
+        assert!(
+            (cs01_sum / rho_flat - 1.0).abs() < 1.0e-2,
+            "cs01 = {}, rho on the flat curve = {}",
+            cs01_sum,
+            rho_flat
+        );
+        assert!((rho["KRWGOV"] / rho_flat - 1.0).abs() < 1.0e-2);
+        assert!(results[1].get_cs01_structure().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_credit_default_swap() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let calendar = JointCalendar::new(vec![Calendar::SouthKorea(SouthKorea::new(
+            SouthKoreaType::Settlement,
+        ))])?;
+        let cds = CreditDefaultSwap::new_from_conventions(
+            ProtectionSide::Buyer,
+            0.01,
+            0.4,
+            "Some Corporate".to_string(),
+            10_000_000.0,
+            dt,
+            cds_standard_maturity(&dt, "5Y"),
+            calendar,
+            Currency::KRW,
+            "Some Corporate CDS 5Y".to_string(),
+            "CDS5Y".to_string(),
+        )?;
+
+        let mut curve_data = HashMap::new();
+        curve_data.insert(
+            "KRWGOV".to_string(),
+            VectorData::new(
+                array![0.03, 0.03],
+                None,
+                Some(array![1.0, 10.0]),
+                None,
+                Currency::KRW,
+                "KRWGOV".to_string(),
+                "KRWGOV".to_string(),
+            )?,
+        );
+        curve_data.insert(
+            "SOMECORP_CDS".to_string(),
+            VectorData::new(
+                array![0.008, 0.011, 0.013],
+                None,
+                Some(array![1.0, 3.0, 5.0]),
+                None,
+                Currency::KRW,
+                "SOMECORP_CDS".to_string(),
+                "SOMECORP_CDS".to_string(),
+            )?,
+        );
+        let mut cds_spread_curve_map = HashMap::new();
+        cds_spread_curve_map.insert("Some Corporate".to_string(), "SOMECORP_CDS".to_string());
+        let mut risk_free_curve_map = HashMap::new();
+        risk_free_curve_map.insert(Currency::KRW, "KRWGOV".to_string());
+        let match_parameter = MatchParameter::default()
+            .with_cds_spread_curve_map(cds_spread_curve_map, risk_free_curve_map);
+
+        let calculation_configuration = CalculationConfiguration::default()
+            .with_rho_calculation(true)
+            .with_rho_structure_calculation(true)
+            .with_cs01_structure_calculation(true)
+            .with_cds_analytics_calculation(true);
+        let mut engine = Engine::builder(0, calculation_configuration, dt, match_parameter)
+            .with_instruments(vec![Instrument::CreditDefaultSwap(cds)])?
+            .with_parameter_data(
+                Default::default(),
+                Default::default(),
+                std::sync::Arc::new(curve_data),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+            )?;
+        engine.initialize_pricers()?;
+        engine.calculate()?;
+        let result = engine.get_calculation_result_clone()["CDS5Y"].clone();
+
+        // the buyer of 100bp protection below the 130bp par spread is in the money
+        assert!(result.get_npv_result().unwrap().get_npv() > 0.0);
+        // rho is on the risk-free curve and cs01 is on the par spread curve
+        let rho = result.get_rho().unwrap();
+        assert!(rho.contains_key("KRWGOV") && !rho.contains_key("SOMECORP_CDS"));
+        let cs01 = result.get_cs01_structure().unwrap();
+        assert_eq!(cs01.len(), 1);
+        let cs01_sum: Real = cs01["SOMECORP_CDS"].iter().sum();
+        assert!(cs01_sum > 0.0, "cs01 = {:?}", cs01);
+
+        // the 5Y pillar matures with the CDS, so the par spread is the quote
+        let analytics = result.get_cds_analytics().unwrap();
+        assert!(
+            (analytics.get_par_spread() - 0.013).abs() < 1.0e-5,
+            "par spread = {}",
+            analytics.get_par_spread()
+        );
+        let npv = result.get_npv_result().unwrap().get_npv();
+        assert!(
+            (analytics.get_upfront() - analytics.get_accrued_premium() - npv).abs() < 1.0e-6,
+            "upfront = {}, accrued premium = {}, npv = {}",
+            analytics.get_upfront(),
+            analytics.get_accrued_premium(),
+            npv
+        );
+        assert!(analytics.get_cs01() > 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fx_forward() -> Result<()> {
+        let dt = datetime!(2024-01-02 16:30:00 +09:00);
+        let usdkrw = FxCode::new(Currency::USD, Currency::KRW);
+        let ndf = FxForward::new(
+            usdkrw,
+            1295.0,
+            FxSettlementType::NonDeliverable,
+            Currency::USD,
+            datetime!(2024-06-28 16:00:00 +09:00),
+            Some("USDKRW MAR".to_string()),
+            dt,
+            datetime!(2024-07-02 16:00:00 +09:00),
+            1_000_000.0,
+            "USDKRW NDF 6M".to_string(),
+            "USDKRWNDF6M".to_string(),
+        )?;
+
+        let mut fx_data = HashMap::new();
+        fx_data.insert(usdkrw, krw_value(1300.0, dt, "USDKRW")?);
+        let curve_data = flat_curve_data(&[("USDOIS", 0.05), ("KRWCRS", 0.035)])?;
+        let match_parameter = usdkrw_match_parameter(&[]);
+
+        let calculation_configuration = CalculationConfiguration::default()
+            .with_delta_calculation(true)
+            .with_gamma_calculation(true)
+            .with_rho_calculation(true);
+        let mut engine = Engine::builder(0, calculation_configuration, dt, match_parameter)
+            .with_instruments(vec![Instrument::FxForward(ndf)])?
+            .with_parameter_data(
+                std::sync::Arc::new(fx_data),
+                Default::default(),
+                std::sync::Arc::new(curve_data),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+            )?;
+        engine.initialize_pricers()?;
+        engine.calculate()?;
+        let result = engine.get_calculation_result_clone()["USDKRWNDF6M"].clone();
+
+        // 1% on USDKRW is about 13 KRW on the forward of 1 million USD
+        let delta = result.get_delta().unwrap()["USDKRW"];
+        assert!(
+            (delta - 13_000_000.0).abs() < 0.05 * 13_000_000.0,
+            "delta = {}",
+            delta
+        );
+        // long USD: the USD rate up lowers the forward and the KRW rate up raises it
+        let rho = result.get_rho().unwrap();
+        assert!(
+            rho["USDOIS"] < 0.0 && rho["KRWCRS"] > 0.0,
+            "rho = {:?}",
+            rho
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fx_option() -> Result<()> {
+        let dt = datetime!(2024-01-02 16:30:00 +09:00);
+        let usdkrw = FxCode::new(Currency::USD, Currency::KRW);
+        let call = usdkrw_call(dt);
+
+        let mut fx_data = HashMap::new();
+        fx_data.insert(usdkrw, krw_value(1300.0, dt, "USDKRW")?);
+        let curve_data = flat_curve_data(&[("USDOIS", 0.05), ("KRWCRS", 0.035)])?;
+        let mut fx_volatility_quote_data = HashMap::new();
+        fx_volatility_quote_data.insert(
+            usdkrw,
+            FxVolatilityQuoteData::new(
+                vec![
+                    datetime!(2024-04-02 16:00:00 +09:00),
+                    datetime!(2025-01-02 16:00:00 +09:00),
+                ],
+                array![0.08, 0.09],
+                array![0.01, 0.015],
+                array![0.003, 0.004],
+                FxDeltaType::SpotPremiumAdjusted,
+                FxAtmType::DeltaNeutralStraddle,
+                Some(dt),
+                usdkrw,
+                "USDKRW vol".to_string(),
+                "USDKRW vol".to_string(),
+            ),
+        );
+        let match_parameter = usdkrw_match_parameter(&[]);
+
+        let calculation_configuration = CalculationConfiguration::default()
+            .with_delta_calculation(true)
+            .with_gamma_calculation(true)
+            .with_vega_calculation(true)
+            .with_rho_calculation(true);
+        let mut engine = Engine::builder(0, calculation_configuration, dt, match_parameter)
+            .with_instruments(vec![Instrument::FxOption(call)])?
+            .with_fx_volatility_quote_data(std::sync::Arc::new(fx_volatility_quote_data))
+            .with_parameter_data(
+                std::sync::Arc::new(fx_data),
+                Default::default(),
+                std::sync::Arc::new(curve_data),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+            )?;
+        engine.initialize_pricers()?;
+        engine.calculate()?;
+        let result = engine.get_calculation_result_clone()["USDKRWC13006M"].clone();
+
+        // slightly out of the money on the forward of about 1290, so that the call delta is below a half
+        // of the 13 million KRW on 1% of 1 million USD
+        let delta = result.get_delta().unwrap()["USDKRW"];
+        assert!(
+            delta > 0.3 * 13_000_000.0 && delta < 0.5 * 13_000_000.0,
+            "delta = {}",
+            delta
+        );
+        assert!(result.get_gamma().unwrap()["USDKRW"] > 0.0);
+        // Black vega DF F n(d1) sqrt(T) on 1%p is about 3.6 million KRW
+        let vega = result.get_vega().unwrap()["USDKRW"];
+        assert!(
+            (vega - 3_600_000.0).abs() < 0.1 * 3_600_000.0,
+            "vega = {}",
+            vega
+        );
+        let rho = result.get_rho().unwrap();
+        assert!(
+            rho["USDOIS"] < 0.0 && rho["KRWCRS"] > 0.0,
+            "rho = {:?}",
+            rho
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_stress_scenarios() -> Result<()> {
+        let dt = datetime!(2024-01-02 16:30:00 +09:00);
+        let usdkrw = FxCode::new(Currency::USD, Currency::KRW);
+        let call = usdkrw_call(dt);
+        let stock = Stock::new(
+            "Samsung Electronics".to_string(),
+            "005930".to_string(),
+            vec!["005930".to_string()],
+            Currency::KRW,
+            None,
+        );
+
+        let mut fx_data = HashMap::new();
+        fx_data.insert(usdkrw, krw_value(1300.0, dt, "USDKRW")?);
+        let mut stock_data = HashMap::new();
+        stock_data.insert("005930".to_string(), krw_value(71_000.0, dt, "005930")?);
+        let curve_data = flat_curve_data(&[("USDOIS", 0.05), ("KRWCRS", 0.035), ("005930", 0.0)])?;
+        let mut fx_constant_volatility_data = HashMap::new();
+        fx_constant_volatility_data.insert(usdkrw, krw_value(0.1, dt, "USDKRW")?);
+
+        let match_parameter = usdkrw_match_parameter(&["005930"]);
+
+        let stress_scenarios = vec![
+            StressScenario::new(
+                "USDKRW +10%".to_string(),
+                vec![MarketShock::FxShock {
+                    fx_codes: vec!["USDKRW".to_string()],
+                    relative_shock: 0.1,
+                }],
+            ),
+            StressScenario::new(
+                "equity crash".to_string(),
+                vec![MarketShock::EquitySpotShock {
+                    underlying_codes: None,
+                    relative_shock: -0.2,
+                }],
+            ),
+            StressScenario::new(
+                "KRW rates +100bp".to_string(),
+                vec![MarketShock::CurveParallelShift {
+                    curve_names: Some(vec!["KRWCRS".to_string()]),
+                    shift: 0.01,
+                }],
+            ),
+        ];
+
+        let mut engine_generator = EngineGenerator::builder();
+        engine_generator
+            .with_configuration(CalculationConfiguration::default(), dt, match_parameter)?
+            .with_instruments(Instruments::new(vec![
+                Rc::new(Instrument::FxOption(call)),
+                Rc::new(Instrument::Stock(stock)),
+            ]))?
+            .with_instrument_categories(vec![InstrumentCategory::default()])?
+            .with_data(
+                fx_data,
+                stock_data,
+                curve_data,
+                HashMap::new(),
+                HashMap::new(),
+                HashMap::new(),
+                fx_constant_volatility_data,
+                HashMap::new(),
+                HashMap::new(),
+            )?
+            .with_stress_scenarios(stress_scenarios.clone())?;
+        engine_generator.distribute_instruments()?;
+        engine_generator.calculate_stress_scenarios()?;
+
+        let result = engine_generator.get_stress_test_result().unwrap();
+        assert_eq!(result.get_scenario_names().len(), 3);
+        assert_eq!(result.get_base_npvs()["005930"], 71_000.0);
+
+        // the fx shock moves the call only
+        let call_up = result
+            .get_npv_difference("USDKRW +10%", "USDKRWC13006M")
+            .unwrap();
+        assert!(call_up > 0.0, "call_up = {}", call_up);
+        assert_eq!(
+            result.get_npv_difference("USDKRW +10%", "005930"),
+            Some(0.0)
+        );
+        // the value is on the unit notional
+        let value_up = result.get_value_differences()["USDKRW +10%"]["USDKRWC13006M"];
+        assert!((value_up / 1_000_000.0 - call_up).abs() < 1.0e-3);
+
+        // the equity shock moves the stock only
+        let crash = result.get_npv_difference("equity crash", "005930").unwrap();
+        assert!((crash + 14_200.0).abs() < 1.0e-2, "crash = {}", crash);
+        assert_eq!(
+            result.get_npv_difference("equity crash", "USDKRWC13006M"),
+            Some(0.0)
+        );
+
+        // the quote currency rates raise the forward and the call
+        assert!(
+            result
+                .get_npv_difference("KRW rates +100bp", "USDKRWC13006M")
+                .unwrap()
+                > 0.0
+        );
+
+        // the scenarios are declared in json as well
+        let json = serde_json::to_string(&stress_scenarios)?;
+        let deserialized: Vec<StressScenario> = serde_json::from_str(&json)?;
+        assert_eq!(deserialized, stress_scenarios);
+        assert!(engine_generator
+            .with_stress_scenarios(vec![
+                stress_scenarios[0].clone(),
+                stress_scenarios[0].clone()
+            ])
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pnl_explain() -> Result<()> {
+        let start_dt = datetime!(2024-01-02 16:30:00 +09:00);
+        let end_dt = datetime!(2024-01-03 16:30:00 +09:00);
+        let usdkrw = FxCode::new(Currency::USD, Currency::KRW);
+        let call = usdkrw_call(start_dt);
+        let stock = Stock::new(
+            "Samsung Electronics".to_string(),
+            "005930".to_string(),
+            vec!["005930".to_string()],
+            Currency::KRW,
+            None,
+        );
+        let instruments = Instruments::new(vec![
+            Rc::new(Instrument::FxOption(call)),
+            Rc::new(Instrument::Stock(stock)),
+        ]);
+
+        let match_parameter = usdkrw_match_parameter(&["005930"]);
+        let calculation_configuration = CalculationConfiguration::default()
+            .with_delta_calculation(true)
+            .with_gamma_calculation(true)
+            .with_vega_calculation(true)
+            .with_rho_calculation(true)
+            .with_theta_calculation(true);
+
+        let run =
+            |dt, fx: Real, spot: Real, vol: Real, krw_rate: Real| -> Result<EngineGenerator> {
+                let value = |v: Real, code: &str| krw_value(v, dt, code);
+                let curve_data =
+                    flat_curve_data(&[("USDOIS", 0.05), ("KRWCRS", krw_rate), ("005930", 0.0)])?;
+                let mut engine_generator = EngineGenerator::builder();
+                engine_generator
+                    .with_configuration(
+                        calculation_configuration.clone(),
+                        dt,
+                        match_parameter.clone(),
+                    )?
+                    .with_instruments(instruments.clone())?
+                    .with_instrument_categories(vec![InstrumentCategory::default()])?
+                    .with_data(
+                        HashMap::from([(usdkrw, value(fx, "USDKRW")?)]),
+                        HashMap::from([("005930".to_string(), value(spot, "005930")?)]),
+                        curve_data,
+                        HashMap::new(),
+                        HashMap::new(),
+                        HashMap::new(),
+                        HashMap::from([(usdkrw, value(vol, "USDKRW")?)]),
+                        HashMap::new(),
+                        HashMap::new(),
+                    )?;
+                engine_generator.distribute_instruments()?;
+                engine_generator.calculate()?;
+                Ok(engine_generator)
+            };
+        let start = run(start_dt, 1300.0, 71_000.0, 0.1, 0.035)?;
+        let end = run(end_dt, 1305.0, 72_000.0, 0.102, 0.0352)?;
+
+        for method in [PnlExplainMethod::RiskBased, PnlExplainMethod::StepByStep] {
+            let explains = start.explain_pnl(&end, method)?;
+            assert_eq!(explains.len(), 2);
+
+            // the stock moves by its price only
+            let stock = &explains["005930"];
+            assert!((stock.get_total() - 1_000.0).abs() < 1.0e-2);
+            assert!((stock.get_spot() - 1_000.0).abs() < 1.0e-2);
+            assert!(stock.get_residual().abs() < 1.0e-2);
+
+            let call = &explains["USDKRWC13006M"];
+            let total = call.get_total();
+            assert!(total > 0.0, "{:?}: total = {}", method, total);
+            assert!(call.get_carry() < 0.0);
+            assert!(call.get_fx() > 0.0);
+            assert!(call.get_vega() > 0.0);
+            assert!(call.get_rho()["KRWCRS"] > 0.0);
+            assert_eq!(call.get_spot(), 0.0);
+            // the higher orders are small against the total
+            assert!(
+                call.get_residual().abs() < 0.05 * total,
+                "{:?}: residual = {}, total = {}",
+                method,
+                call.get_residual(),
+                total
+            );
+        }
+        // the step-by-step method ends on the end value
+        let explains = start.explain_pnl(&end, PnlExplainMethod::StepByStep)?;
+        assert!(explains["USDKRWC13006M"].get_residual().abs() < 1.0);
+
+        assert!(end
+            .explain_pnl(&start, PnlExplainMethod::RiskBased)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pnl_explain_quanto_correlation() -> Result<()> {
+        let start_dt = datetime!(2024-01-02 16:30:00 +09:00);
+        let end_dt = datetime!(2024-01-03 16:30:00 +09:00);
+        let maturity = datetime!(2024-07-02 16:00:00 +09:00);
+        let usdkrw = FxCode::new(Currency::USD, Currency::KRW);
+        let option = VanillaOption::new(
+            5_000.0,
+            1.0,
+            start_dt,
+            maturity,
+            maturity,
+            maturity,
+            vec!["SPX".to_string()],
+            Currency::USD,
+            Currency::KRW,
+            OptionType::Call,
+            OptionExerciseType::European,
+            OptionDailySettlementType::NotSettled,
+            "SPX C 5000 quanto".to_string(),
+            "SPXC5000Q".to_string(),
+        );
+        let instruments = Instruments::new(vec![Rc::new(Instrument::VanillaOption(option))]);
+
+        let mut collateral_curve_map = HashMap::new();
+        collateral_curve_map.insert("SPX".to_string(), "USDOIS".to_string());
+        let mut borrowing_curve_map = HashMap::new();
+        borrowing_curve_map.insert("SPX".to_string(), "SPX".to_string());
+        let mut funding_cost_map = HashMap::new();
+        funding_cost_map.insert(Currency::KRW, "KRWCRS".to_string());
+        let match_parameter = MatchParameter::new(
+            collateral_curve_map,
+            borrowing_curve_map,
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            funding_cost_map,
+        );
+
+        // the market data moves only by the quanto correlation
+        let run = |dt, correlation: Real| -> Result<EngineGenerator> {
+            let value = |v: Real, currency: Currency, code: &str| {
+                ValueData::new(v, Some(dt), currency, code.to_string(), code.to_string())
+            };
+            let curve_data = flat_curve_data(&[("USDOIS", 0.05), ("KRWCRS", 0.035), ("SPX", 0.0)])?;
+            let mut engine_generator = EngineGenerator::builder();
+            engine_generator
+                .with_configuration(
+                    CalculationConfiguration::default().with_vanilla_option_calculation_method(
+                        VanillaOptionCalculationMethod::FiniteDifference,
+                    ),
+                    dt,
+                    match_parameter.clone(),
+                )?
+                .with_instruments(instruments.clone())?
+                .with_instrument_categories(vec![InstrumentCategory::default()])?
+                .with_data(
+                    HashMap::from([(usdkrw, value(1300.0, Currency::KRW, "USDKRW")?)]),
+                    HashMap::from([("SPX".to_string(), value(5_000.0, Currency::USD, "SPX")?)]),
+                    curve_data,
+                    HashMap::new(),
+                    HashMap::from([("SPX".to_string(), value(0.2, Currency::USD, "SPX")?)]),
+                    HashMap::new(),
+                    HashMap::from([(usdkrw, value(0.1, Currency::KRW, "USDKRW")?)]),
+                    HashMap::from([(
+                        ("SPX".to_string(), usdkrw),
+                        value(correlation, Currency::KRW, "SPX USDKRW")?,
+                    )]),
+                    HashMap::new(),
+                )?;
+            engine_generator.distribute_instruments()?;
+            engine_generator.calculate()?;
+            Ok(engine_generator)
+        };
+        let start = run(start_dt, 0.3)?;
+        let end = run(end_dt, -0.3)?;
+        let carry_only = run(end_dt, 0.3)?;
+
+        let explain = &start.explain_pnl(&end, PnlExplainMethod::StepByStep)?["SPXC5000Q"];
+        // the lower correlation raises the quanto forward and the call
+        assert!(explain.get_vega() > 0.0, "{:?}", explain);
+        assert!(explain.get_residual().abs() < 1.0e-2 * explain.get_vega());
+        let carry_value = carry_only.get_calculation_results()["SPXC5000Q"]
+            .get_value()
+            .unwrap();
+        assert!((explain.get_start_value() + explain.get_carry() - carry_value).abs() < 1.0e-2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_analytic_greeks_in_engine() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let maturity = datetime!(2024-09-12 16:30:00 +09:00);
+        let option = VanillaOption::new(
+            360.0,
+            250_000.0,
+            dt,
+            maturity,
+            maturity,
+            maturity,
+            vec!["KOSPI2".to_string()],
+            Currency::KRW,
+            Currency::KRW,
+            OptionType::Call,
+            OptionExerciseType::European,
+            OptionDailySettlementType::NotSettled,
+            "KOSPI2 C 360".to_string(),
+            "KOSPI2C360".to_string(),
+        );
+
+        let mut collateral_curve_map = HashMap::new();
+        collateral_curve_map.insert("KOSPI2".to_string(), "KSD".to_string());
+        let mut borrowing_curve_map = HashMap::new();
+        borrowing_curve_map.insert("KOSPI2".to_string(), "KOSPI2".to_string());
+        let mut funding_cost_map = HashMap::new();
+        funding_cost_map.insert(Currency::KRW, "KSD".to_string());
+        let match_parameter = MatchParameter::new(
+            collateral_curve_map,
+            borrowing_curve_map,
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            funding_cost_map,
+        );
+        let calculation_configuration = CalculationConfiguration::default()
+            .with_delta_calculation(true)
+            .with_gamma_calculation(true)
+            .with_vega_calculation(true)
+            .with_theta_calculation(true);
+
+        let run = |dt, vol: Real| -> Result<CalculationResult> {
+            let value = |v: Real| {
+                ValueData::new(
+                    v,
+                    Some(dt),
+                    Currency::KRW,
+                    "KOSPI2".to_string(),
+                    "KOSPI2".to_string(),
+                )
+            };
+            let mut curve_data = HashMap::new();
+            for (name, rate) in [("KSD", 0.035), ("KOSPI2", 0.005)] {
+                curve_data.insert(
+                    name.to_string(),
+                    VectorData::new(
+                        array![rate, rate],
+                        None,
+                        Some(array![0.5, 5.0]),
+                        None,
+                        Currency::KRW,
+                        name.to_string(),
+                        name.to_string(),
+                    )?,
+                );
+            }
+            let mut engine = Engine::builder(
+                0,
+                calculation_configuration.clone(),
+                dt,
+                match_parameter.clone(),
+            )
+            .with_instruments(vec![Instrument::VanillaOption(option.clone())])?
+            .with_parameter_data(
+                Default::default(),
+                std::sync::Arc::new(HashMap::from([("KOSPI2".to_string(), value(350.0)?)])),
+                std::sync::Arc::new(curve_data),
+                Default::default(),
+                std::sync::Arc::new(HashMap::from([("KOSPI2".to_string(), value(vol)?)])),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+            )?;
+            engine.initialize_pricers()?;
+            engine.calculate()?;
+            Ok(engine.get_calculation_result_clone()["KOSPI2C360"].clone())
+        };
+        let result = run(dt, 0.2)?;
+
+        // the analytic theta against the value a day later
+        let next_day = run(dt + Duration::days(1), 0.2)?;
+        let theta = result.get_theta().unwrap();
+        let value_change = next_day.get_value().unwrap() - result.get_value().unwrap();
+        assert!(theta < 0.0);
+        assert!(
+            (theta - value_change).abs() < 0.02 * value_change.abs(),
+            "theta = {}, value change = {}",
+            theta,
+            value_change
+        );
+
+        // vanna and volga against the delta and the vega on the bumped volatilities
+        let vol_up = run(dt, 0.21)?;
+        let vol_down = run(dt, 0.19)?;
+        let vanna = result.get_vanna().unwrap()["KOSPI2"];
+        let bumped_vanna =
+            0.5 * (vol_up.get_delta().unwrap()["KOSPI2"] - vol_down.get_delta().unwrap()["KOSPI2"]);
+        assert!(
+            (vanna - bumped_vanna).abs() < 0.02 * bumped_vanna.abs(),
+            "vanna = {}, bumped = {}",
+            vanna,
+            bumped_vanna
+        );
+        let volga = result.get_volga().unwrap()["KOSPI2"];
+        let bumped_volga =
+            0.25 * (vol_up.get_vega().unwrap()["KOSPI2"] - vol_down.get_vega().unwrap()["KOSPI2"]);
+        assert!(
+            (volga - bumped_volga).abs() < 0.02 * bumped_volga.abs(),
+            "volga = {}, bumped = {}",
+            volga,
+            bumped_volga
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bond_analytics_on_pricing_date() -> Result<()> {
+        // settled two days after the evaluation date, over the coupon date 2024-03-20
+        let dt = datetime!(2024-03-19 16:30:00 +09:00);
+        let pricing_date = datetime!(2024-03-21 00:00:00 +09:00);
+        let calendar = JointCalendar::new(vec![Calendar::SouthKorea(SouthKorea::new(
+            SouthKoreaType::Settlement,
+        ))])?;
+        let mut bond = Bond::new_from_conventions(
+            IssuerType::Government,
+            CreditRating::None,
+            "Government".to_string(),
+            RankType::Senior,
+            Currency::KRW,
+            10_000.0,
+            false,
+            datetime!(2023-06-20 00:00:00 +09:00),
+            datetime!(2023-06-20 00:00:00 +09:00),
+            None,
+            datetime!(2028-06-20 00:00:00 +09:00),
+            Some(0.045),
+            None,
+            None,
+            None,
+            calendar,
+            true,
+            DayCountConvention::StreetConvention,
+            BusinessDayConvention::Unadjusted,
+            PaymentFrequency::Quarterly,
+            0,
+            0,
+            "KTB 5Y".to_string(),
+            "KRKTB5Y".to_string(),
+        )?;
+        bond.set_pricing_date(pricing_date);
+
+        let curve_data = VectorData::new(
+            array![0.03, 0.035],
+            None,
+            Some(array![1.0, 5.0]),
+            None,
+            Currency::KRW,
+            "KRWGOV".to_string(),
+            "KRWGOV".to_string(),
+        )?;
+        let mut bond_discount_curve_map = HashMap::new();
+        bond_discount_curve_map.insert(
+            (
+                "Government".to_string(),
+                IssuerType::Government,
+                CreditRating::None,
+                Currency::KRW,
+            ),
+            "KRWGOV".to_string(),
+        );
+        let match_parameter = MatchParameter::new(
+            HashMap::new(),
+            HashMap::new(),
+            bond_discount_curve_map,
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+        let calculation_configuration =
+            CalculationConfiguration::default().with_bond_analytics_calculation(true);
+        let mut engine = Engine::builder(0, calculation_configuration, dt, match_parameter)
+            .with_instruments(vec![Instrument::Bond(bond.clone())])?
+            .with_parameter_data(
+                Default::default(),
+                Default::default(),
+                std::sync::Arc::new(HashMap::from([("KRWGOV".to_string(), curve_data.clone())])),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+            )?;
+        engine.initialize_pricers()?;
+        engine.calculate()?;
+        let result = engine.get_calculation_result_clone()["KRKTB5Y"].clone();
+        let analytics = result.get_bond_analytics().unwrap();
+
+        // the cashflows after the pricing date valued on the pricing date
+        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(dt)));
+        let curve = ZeroCurve::new(
+            evaluation_date,
+            &curve_data,
+            "KRWGOV".to_string(),
+            "KRWGOV".to_string(),
+        )?;
+        let mut expected = 0.0;
+        for (date, amount) in bond.get_cashflows(&pricing_date, None, None)?.iter() {
+            if date.date() > pricing_date.date() {
+                expected += amount * curve.get_discount_factor_at_date(date)?;
+            }
+        }
+        expected /= curve.get_discount_factor_at_date(&pricing_date)?;
+        assert!(
+            (analytics.get_dirty_price() - expected).abs() < 1.0e-5,
+            "dirty price = {}, expected = {}",
+            analytics.get_dirty_price(),
+            expected
+        );
+        assert!(
+            (analytics.get_clean_price() + analytics.get_accrued_interest()
+                - analytics.get_dirty_price())
+            .abs()
+                < 1.0e-6
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bond_futures_analytics() -> Result<()> {
+        let dt = datetime!(2024-01-02 16:30:00 +09:00);
+        let calendar = JointCalendar::new(vec![Calendar::SouthKorea(SouthKorea::new(
+            SouthKoreaType::Settlement,
+        ))])?;
+        let government_bond = |coupon_rate: Real, issue_date, maturity, code: &str| {
+            Bond::new_from_conventions(
+                IssuerType::Government,
+                CreditRating::None,
+                "Government".to_string(),
+                RankType::Senior,
+                Currency::KRW,
+                1.0,
+                false,
+                issue_date,
+                issue_date,
+                None,
+                maturity,
+                Some(coupon_rate),
+                None,
+                None,
+                None,
+                calendar.clone(),
+                true,
+                DayCountConvention::StreetConvention,
+                BusinessDayConvention::Unadjusted,
+                PaymentFrequency::SemiAnnually,
+                0,
+                0,
+                code.to_string(),
+                code.to_string(),
+            )
+        };
+        let deliverables = vec![
+            government_bond(
+                0.0325,
+                datetime!(2023-06-10 00:00:00 +09:00),
+                datetime!(2033-06-10 00:00:00 +09:00),
+                "KTB 3.25 2033",
+            )?,
+            government_bond(
+                0.0425,
+                datetime!(2023-12-10 00:00:00 +09:00),
+                datetime!(2033-12-10 00:00:00 +09:00),
+                "KTB 4.25 2033",
+            )?,
+        ];
+        let bond_futures = BondFutures::new(
+            Currency::KRW,
+            100_000_000.0,
+            datetime!(2023-12-20 00:00:00 +09:00),
+            datetime!(2024-03-19 00:00:00 +09:00),
+            datetime!(2024-03-21 00:00:00 +09:00),
+            0.05,
+            3,
+            deliverables,
+            "KRWREPO".to_string(),
+            "KTB10 Deliverable".to_string(),
+            "KTBDH4".to_string(),
+        )?;
+
+        let bond_discount_curve_map = HashMap::from([(
+            (
+                "Government".to_string(),
+                IssuerType::Government,
+                CreditRating::None,
+                Currency::KRW,
+            ),
+            "KRWGOV".to_string(),
+        )]);
+        let borrowing_curve_map = HashMap::from([("KRWREPO".to_string(), "KRWREPO".to_string())]);
+        let match_parameter = MatchParameter::new(
+            HashMap::new(),
+            borrowing_curve_map,
+            bond_discount_curve_map,
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+        let calculation_configuration =
+            CalculationConfiguration::default().with_bond_futures_analytics_calculation(true);
+        let mut engine = Engine::builder(0, calculation_configuration, dt, match_parameter)
+            .with_instruments(vec![Instrument::BondFutures(bond_futures)])?
+            .with_parameter_data(
+                Default::default(),
+                Default::default(),
+                std::sync::Arc::new(flat_curve_data(&[("KRWGOV", 0.033), ("KRWREPO", 0.035)])?),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+            )?;
+        engine.initialize_pricers()?;
+        engine.calculate()?;
+        let result = engine.get_calculation_result_clone()["KTBDH4"].clone();
+        let analytics = result.get_bond_futures_analytics().unwrap();
+
+        // at the theoretical price, the cheapest-to-deliver has no net basis and the others are richer
+        let deliverables = analytics.get_deliverables();
+        let ctd = analytics.get_cheapest_to_deliver();
+        assert_eq!(deliverables.len(), 2);
+        assert!(ctd.get_net_basis().abs() < 1.0e-5, "{:?}", ctd);
+        for x in deliverables.iter() {
+            assert!(x.get_conversion_factor() < 1.0);
+            assert!(x.get_net_basis() >= ctd.get_net_basis() - 1.0e-6);
+            assert!(x.get_implied_repo_rate() <= ctd.get_implied_repo_rate() + 1.0e-6);
+        }
+        assert!(
+            (ctd.get_forward_clean_price() / ctd.get_conversion_factor()
+                - analytics.get_futures_price())
+            .abs()
+                < 1.0e-5
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_ktbf_analytics() -> Result<()> {
+        let dt = datetime!(2024-01-02 16:30:00 +09:00);
+        let ktbf_maturity = datetime!(2024-03-19 16:30:00 +09:00);
+        let calendar = JointCalendar::new(vec![Calendar::SouthKorea(SouthKorea::new(
+            SouthKoreaType::Settlement,
+        ))])?;
+        let government_bond = |maturity, code: &str| {
+            Bond::new_from_conventions(
+                IssuerType::Government,
+                CreditRating::None,
+                "Government".to_string(),
+                RankType::Senior,
+                Currency::KRW,
+                10_000.0,
+                false,
+                datetime!(2023-12-10 00:00:00 +09:00),
+                datetime!(2023-12-10 00:00:00 +09:00),
+                Some(ktbf_maturity),
+                maturity,
+                Some(0.0325),
+                None,
+                None,
+                None,
+                calendar.clone(),
+                true,
+                DayCountConvention::StreetConvention,
+                BusinessDayConvention::Unadjusted,
+                PaymentFrequency::SemiAnnually,
+                0,
+                0,
+                code.to_string(),
+                code.to_string(),
+            )
+        };
+        let ktbf = KTBF::new(
+            Currency::KRW,
+            1_000_000.0,
+            datetime!(2023-12-20 16:30:00 +09:00),
+            ktbf_maturity,
+            ktbf_maturity,
+            KtbfVirtualBond::new(3, 0.05, PaymentFrequency::SemiAnnually, 100.0),
+            vec![
+                government_bond(datetime!(2026-12-10 00:00:00 +09:00), "KTB 3.25 2026")?,
+                government_bond(datetime!(2027-06-10 00:00:00 +09:00), "KTB 3.25 2027")?,
+            ],
+            "KTBF3Y".to_string(),
+            "KTBF 3Y".to_string(),
+            "KTBFH4".to_string(),
+        )?;
+
+        let bond_discount_curve_map = HashMap::from([(
+            (
+                "Government".to_string(),
+                IssuerType::Government,
+                CreditRating::None,
+                Currency::KRW,
+            ),
+            "KRWGOV".to_string(),
+        )]);
+        let borrowing_curve_map = HashMap::from([("KTBF3Y".to_string(), "KTBF3Y".to_string())]);
+        let match_parameter = MatchParameter::new(
+            HashMap::new(),
+            borrowing_curve_map,
+            bond_discount_curve_map,
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+        let calculation_configuration =
+            CalculationConfiguration::default().with_ktbf_analytics_calculation(true);
+        let mut engine = Engine::builder(0, calculation_configuration, dt, match_parameter)
+            .with_instruments(vec![Instrument::KTBF(ktbf)])?
+            .with_parameter_data(
+                Default::default(),
+                Default::default(),
+                std::sync::Arc::new(flat_curve_data(&[("KRWGOV", 0.033), ("KTBF3Y", 0.035)])?),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+            )?;
+        engine.initialize_pricers()?;
+        engine.calculate()?;
+        let result = engine.get_calculation_result_clone()["KTBFH4"].clone();
+        let analytics = result.get_ktbf_analytics().unwrap();
+
+        // at the theoretical price, there is no basis and the basket yield is implied
+        assert_eq!(analytics.get_tenor(), 3);
+        assert!(analytics.get_basis().abs() < 1.0e-4, "{:?}", analytics);
+        assert!(
+            analytics.get_yield_basis().abs() < 1.0e-5,
+            "{:?}",
+            analytics
+        );
+        assert!((analytics.get_basket_yield() - 0.033).abs() < 2.0e-3);
+        assert!(analytics.get_dv01() > 0.0);
+        assert_eq!(analytics.get_underlying_analytics().len(), 2);
+        Ok(())
+    }
 }